@@ -0,0 +1,394 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Enums and `match`: checks that variants are built with the right number
+//! of arguments, that patterns fit the type of what they match, and that
+//! every match is exhaustive and has no unreachable arms.
+//!
+//! Patterns are checked against each other here: a match's patterns take the
+//! type of the first of them that has one, and a variant field without a type
+//! annotation accepts any pattern. Whether that type is the scrutinee's is
+//! left to inference.
+
+use crate::annotations::BUILTIN_TYPES;
+use crate::ast::*;
+use crate::decision::{self, Arm, Constructor, Enums, Pat};
+use crate::elab::Dependent;
+use crate::lint::UNREACHABLE_PATTERNS;
+use crate::types::{Aliases, Ty};
+use crate::visit::{Visitor, walk_expr, walk_func};
+use flerry_core::{Diagnostic, Span};
+use std::collections::HashMap;
+
+struct VariantInfo<'m> {
+    enum_name: &'m str,
+    def: &'m Variant,
+    fields: Vec<Ty>,
+}
+
+struct Checker<'m> {
+    variants: HashMap<&'m str, VariantInfo<'m>>,
+    enums: Enums,
+    /// Families and the functions using them, which are checked with the
+    /// core calculus instead.
+    dependent: Dependent<'m>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+pub fn check(module: &Module) -> Vec<Diagnostic> {
+    let mut types: HashMap<&str, usize> = BUILTIN_TYPES.iter().copied().collect();
+    for item in &module.items {
+        if let ItemKind::Enum(EnumDef { name, .. }) | ItemKind::Struct(StructDef { name, .. }) = &item.kind {
+            types.insert(&name.name, 0);
+        }
+    }
+    let aliases = Aliases::new(module);
+
+    let mut checker = Checker {
+        variants: HashMap::new(),
+        enums: Enums::new(),
+        dependent: Dependent::new(module),
+        diagnostics: Vec::new(),
+    };
+    for item in &module.items {
+        let ItemKind::Enum(def) = &item.kind else {
+            continue;
+        };
+        if checker.dependent.families.contains(def.name.name.as_str()) {
+            continue;
+        }
+        for variant in &def.variants {
+            let fields = variant
+                .fields
+                .iter()
+                .map(|field| {
+                    field
+                        .ty
+                        .as_ref()
+                        .map_or(Ty::Unknown, |ty| aliases.lower(ty, &|name| types.get(name).copied()))
+                })
+                .collect();
+            checker.variants.insert(
+                &variant.name.name,
+                VariantInfo {
+                    enum_name: &def.name.name,
+                    def: variant,
+                    fields,
+                },
+            );
+        }
+        checker.enums.insert(
+            def.name.name.clone(),
+            def.variants
+                .iter()
+                .map(|variant| (variant.name.name.clone(), variant.fields.len()))
+                .collect(),
+        );
+    }
+
+    checker.visit_module(module);
+    checker.diagnostics
+}
+
+/// Reports a match on `scrutinee` that does not cover the `missing` patterns.
+pub fn non_exhaustive(scrutinee: Span, missing: &[String]) -> Diagnostic {
+    let shown: Vec<String> = missing.iter().take(3).map(|p| format!("`{p}`")).collect();
+    let list = match (shown.as_slice(), missing.len()) {
+        ([only], 1) => only.clone(),
+        ([first, second], 2) => format!("{first} and {second}"),
+        (shown, 3) => format!("{}, {} and {}", shown[0], shown[1], shown[2]),
+        (shown, n) => format!("{} and {} more", shown.join(", "), n - shown.len()),
+    };
+    let patterns = if missing.len() == 1 { "pattern" } else { "patterns" };
+    Diagnostic::error(format!("non-exhaustive patterns: {list} not covered"), scrutinee)
+        .with_label(scrutinee, format!("{patterns} {list} not covered"))
+        .with_help(
+            "ensure that all possible cases are being handled by adding a match arm with a wildcard pattern or an \
+             explicit pattern",
+        )
+}
+
+pub fn plural(n: usize, what: &str) -> String {
+    format!("{n} {what}{}", if n == 1 { "" } else { "s" })
+}
+
+/// Every name `pattern` binds; for alternatives, those of the first.
+fn bindings<'p>(pattern: &'p Pattern, out: &mut Vec<&'p Ident>) {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Lit(_) | PatternKind::Error => {}
+        PatternKind::Binding(name) => out.push(name),
+        PatternKind::Variant { args, .. } => args.iter().for_each(|arg| bindings(arg, out)),
+        PatternKind::Or(alternatives) => bindings(&alternatives[0], out),
+    }
+}
+
+impl<'m> Visitor<'m> for Checker<'m> {
+    fn visit_func(&mut self, func: &'m Func) {
+        if !self.dependent.functions.contains(func.name.name.as_str()) {
+            walk_func(self, func);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'m Expr) {
+        match &expr.kind {
+            ExprKind::Call { callee, args } => {
+                if let ExprKind::Ident(name) = &callee.kind {
+                    self.check_constructor(name, args.len(), expr.span);
+                }
+            }
+            ExprKind::Match { scrutinee, arms } => self.check_match(scrutinee, arms),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl<'m> Checker<'m> {
+    fn check_constructor(&mut self, name: &Ident, supplied: usize, span: Span) {
+        let Some(info) = self.variants.get(name.name.as_str()) else {
+            return;
+        };
+        let expected = info.def.fields.len();
+        if supplied != expected {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    format!(
+                        "this variant takes {} but {} supplied",
+                        plural(expected, "argument"),
+                        if supplied == 1 {
+                            "1 argument was".to_owned()
+                        } else {
+                            format!("{supplied} arguments were")
+                        }
+                    ),
+                    span,
+                )
+                .with_label(info.def.span, format!("`{}` defined here", name.name)),
+            );
+        }
+    }
+
+    /// The type `pattern` forces on what it matches, if any.
+    fn pattern_type(&self, pattern: &Pattern) -> Option<Ty> {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Binding(_) | PatternKind::Error => None,
+            PatternKind::Lit(lit) => Some(Ty::of_literal(lit)),
+            PatternKind::Variant { name, .. } => self
+                .variants
+                .get(name.name.as_str())
+                .map(|info| Ty::named(info.enum_name)),
+            PatternKind::Or(alternatives) => alternatives.iter().find_map(|p| self.pattern_type(p)),
+        }
+    }
+
+    fn check_match(&mut self, scrutinee: &Expr, arms: &'m [MatchArm]) {
+        let typed = arms
+            .iter()
+            .find_map(|arm| Some((arm, self.pattern_type(&arm.pattern)?)));
+        let expected = typed.as_ref().map_or(Ty::Unknown, |(_, ty)| ty.clone());
+        let origin = typed.map(|(arm, _)| arm.pattern.span);
+
+        let errors = self.diagnostics.len();
+        let mut lowered = Vec::with_capacity(arms.len());
+        for arm in arms {
+            self.duplicate_bindings(&arm.pattern);
+            lowered.push(Arm {
+                pattern: self.lower(&arm.pattern, &expected, origin),
+                guarded: arm.guard.is_some(),
+            });
+        }
+        // Wrong or missing patterns would only make for misleading reports.
+        if self.diagnostics.len() > errors || arms.iter().any(|arm| arm.pattern.kind == PatternKind::Error) {
+            return;
+        }
+
+        let compiled = decision::compile(&lowered, &self.enums);
+        if !compiled.missing.is_empty() {
+            let mut missing = compiled.missing;
+            let mut seen = Vec::new();
+            missing.retain(|p| {
+                !seen.contains(p) && {
+                    seen.push(p.clone());
+                    true
+                }
+            });
+            let missing: Vec<String> = missing.iter().map(ToString::to_string).collect();
+            self.diagnostics.push(non_exhaustive(scrutinee.span, &missing));
+        }
+
+        for (index, arm) in arms.iter().enumerate() {
+            let reachable = &compiled.reachable[index];
+            let spans: Vec<Span> = if reachable.iter().all(|r| !r) {
+                vec![arm.pattern.span]
+            } else if let PatternKind::Or(alternatives) = &arm.pattern.kind {
+                alternatives
+                    .iter()
+                    .zip(reachable)
+                    .filter(|(_, reachable)| !**reachable)
+                    .map(|(alternative, _)| alternative.span)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            // Point at an earlier arm that takes everything, if there is one.
+            let catch_all = arms[..index]
+                .iter()
+                .find(|arm| arm.guard.is_none() && matches_anything(&arm.pattern));
+            for span in spans {
+                let mut diagnostic = Diagnostic::warning("unreachable pattern", span)
+                    .with_label(span, "no value can reach this pattern")
+                    .with_lint(UNREACHABLE_PATTERNS.name);
+                if let Some(catch_all) = catch_all {
+                    diagnostic = diagnostic.with_label(catch_all.pattern.span, "this pattern matches any value");
+                }
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    fn duplicate_bindings(&mut self, pattern: &Pattern) {
+        let mut names = Vec::new();
+        bindings(pattern, &mut names);
+        for (i, name) in names.iter().enumerate() {
+            if let Some(first) = names[..i].iter().find(|n| n.name == name.name) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        format!("identifier `{}` is bound more than once in the same pattern", name.name),
+                        name.span,
+                    )
+                    .with_label(name.span, "used in a pattern more than once")
+                    .with_label(first.span, "first bound here"),
+                );
+            }
+        }
+    }
+
+    /// Checks `pattern` against `expected` and lowers it for the decision
+    /// tree. A pattern with an error is lowered to a wildcard.
+    fn lower(&mut self, pattern: &Pattern, expected: &Ty, origin: Option<Span>) -> Pat {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Error => Pat::Any(None),
+            PatternKind::Binding(name) => Pat::Any(Some(name.clone())),
+            PatternKind::Lit(lit) => {
+                let found = match lit {
+                    Lit::Int(_) if expected.fixed_width().is_some() => expected.clone(),
+                    _ => Ty::of_literal(lit),
+                };
+                if !self.expect(expected, &found, pattern.span, origin) {
+                    return Pat::Any(None);
+                }
+                Pat::Con(
+                    match lit {
+                        Lit::Int(value) => Constructor::Int(*value),
+                        Lit::Float(value) => Constructor::Float(*value),
+                        Lit::Str(value) => Constructor::Str(value.clone()),
+                        Lit::Bool(value) => Constructor::Bool(*value),
+                    },
+                    Vec::new(),
+                )
+            }
+            PatternKind::Variant { name, args } => {
+                // Unknown names are reported by name resolution.
+                let Some(info) = self.variants.get(name.name.as_str()) else {
+                    return Pat::Any(None);
+                };
+                let (enum_name, def, fields) = (info.enum_name, info.def, info.fields.clone());
+                if !self.expect(expected, &Ty::named(enum_name), pattern.span, origin) {
+                    return Pat::Any(None);
+                }
+                if args.len() != fields.len() {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            format!(
+                                "this pattern has {}, but the variant `{}` has {}",
+                                plural(args.len(), "field"),
+                                name.name,
+                                plural(fields.len(), "field")
+                            ),
+                            pattern.span,
+                        )
+                        .with_label(def.span, format!("`{}` defined here", name.name)),
+                    );
+                    return Pat::Any(None);
+                }
+                let args = args
+                    .iter()
+                    .zip(&fields)
+                    .zip(&def.fields)
+                    .map(|((arg, ty), field)| self.lower(arg, ty, field.ty.as_ref().map(|ty| ty.span)))
+                    .collect();
+                Pat::Con(
+                    Constructor::Variant {
+                        enum_name: enum_name.to_owned(),
+                        name: name.name.clone(),
+                        arity: fields.len(),
+                    },
+                    args,
+                )
+            }
+            PatternKind::Or(alternatives) => {
+                self.same_bindings(alternatives);
+                Pat::Or(
+                    alternatives
+                        .iter()
+                        .map(|alternative| self.lower(alternative, expected, origin))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Reports a pattern of type `found` where `expected` is needed, and
+    /// returns whether the types agree.
+    fn expect(&mut self, expected: &Ty, found: &Ty, span: Span, origin: Option<Span>) -> bool {
+        // A type variable may stand for any type.
+        if matches!(expected, Ty::Unknown | Ty::Param(_)) || expected == found {
+            return true;
+        }
+        let mut diagnostic = Diagnostic::error("mismatched types", span)
+            .with_label(span, format!("expected `{expected}`, found `{found}`"));
+        if let Some(origin) = origin.filter(|origin| *origin != span) {
+            diagnostic = diagnostic.with_label(origin, "expected due to this");
+        }
+        self.diagnostics.push(diagnostic);
+        false
+    }
+
+    /// Reports names bound by some alternatives of an or-pattern but not all.
+    fn same_bindings(&mut self, alternatives: &[Pattern]) {
+        let names: Vec<Vec<&Ident>> = alternatives
+            .iter()
+            .map(|alternative| {
+                let mut names = Vec::new();
+                bindings(alternative, &mut names);
+                names
+            })
+            .collect();
+        for (i, alternative) in alternatives.iter().enumerate() {
+            for name in names.iter().flatten() {
+                if names[i].iter().any(|n| n.name == name.name) {
+                    continue;
+                }
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        format!("variable `{}` is not bound in all patterns", name.name),
+                        alternative.span,
+                    )
+                    .with_label(alternative.span, format!("pattern doesn't bind `{}`", name.name))
+                    .with_label(name.span, "variable not in all patterns"),
+                );
+                // One report per alternative and name is plenty.
+                break;
+            }
+        }
+    }
+}
+
+/// Whether `pattern` matches every value on its own.
+fn matches_anything(pattern: &Pattern) -> bool {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Binding(_) => true,
+        PatternKind::Or(alternatives) => alternatives.iter().any(matches_anything),
+        PatternKind::Lit(_) | PatternKind::Variant { .. } | PatternKind::Error => false,
+    }
+}
@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Type annotations: every type written in the source must name a type that
+//! exists, applied to as many arguments as it takes, and type aliases must
+//! not expand into themselves.
+//!
+//! A name starting with a lowercase letter is a type variable, standing for
+//! any type: `func first(xs: List(a)) -> a`. Annotations are optional
+//! everywhere; what is left out is inferred. The fixed-width integer types,
+//! `u8` to `u64` and `i8` to `i64`, are the exception: they are types.
+//!
+//! Universes (`Type`, `Type1`, ...) are types too, and the arguments of an
+//! inductive family may be values: the variants and functions they name are
+//! checked with the rest of the core calculus. So are the arguments of a
+//! refinement type, `Index(n)`, which its condition refers to.
+
+use crate::ast::*;
+use crate::kernel::universe_level;
+use crate::visit::{Visitor, walk_type_expr};
+use flerry_core::suggest::find_best_match;
+use flerry_core::{Diagnostic, Span, Suggestion};
+use std::collections::HashMap;
+
+/// The built-in types and how many type arguments each takes.
+pub const BUILTIN_TYPES: &[(&str, usize)] = &[
+    ("Int", 0),
+    ("Float", 0),
+    ("Str", 0),
+    ("Bool", 0),
+    ("Nat", 0),
+    ("List", 1),
+    ("u8", 0),
+    ("u16", 0),
+    ("u32", 0),
+    ("u64", 0),
+    ("i8", 0),
+    ("i16", 0),
+    ("i32", 0),
+    ("i64", 0),
+];
+
+struct Checker<'m> {
+    /// Every type in scope and the number of arguments it takes.
+    types: HashMap<&'m str, usize>,
+    aliases: HashMap<&'m str, &'m TypeAlias>,
+    /// Variants and functions, which a family may take as arguments.
+    values: Vec<&'m str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+pub fn check(module: &Module) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        types: BUILTIN_TYPES.iter().copied().collect(),
+        aliases: HashMap::new(),
+        values: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let mut aliases = Vec::new();
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Struct(def) => {
+                checker.types.insert(&def.name.name, 0);
+            }
+            ItemKind::Enum(def) => {
+                checker.types.insert(&def.name.name, def.params.len());
+                checker
+                    .values
+                    .extend(def.variants.iter().map(|variant| variant.name.name.as_str()));
+            }
+            ItemKind::Type(alias) => {
+                checker.types.insert(&alias.name.name, alias.params.len());
+                checker.aliases.insert(&alias.name.name, alias);
+                aliases.push(alias);
+            }
+            ItemKind::Func(func) => checker.values.push(&func.name.name),
+            ItemKind::Import(_) | ItemKind::Error => {}
+        }
+    }
+
+    checker.visit_module(module);
+    let mut reported = Vec::new();
+    for alias in aliases {
+        checker.check_cycle(alias, &mut Vec::new(), &mut reported);
+    }
+    checker.diagnostics
+}
+
+/// Whether `name` is a type variable rather than the name of a type.
+fn is_type_variable(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_lowercase() || c == '_')
+}
+
+/// The names of the aliases `ty` refers to, in order.
+fn alias_refs<'m>(ty: &'m TypeExpr, out: &mut Vec<&'m Ident>) {
+    match &ty.kind {
+        TypeExprKind::Named { name, args } => {
+            out.push(name);
+            args.iter().for_each(|arg| alias_refs(arg, out));
+        }
+        TypeExprKind::Function { params, ret } => {
+            params.iter().for_each(|param| alias_refs(param, out));
+            alias_refs(ret, out);
+        }
+        TypeExprKind::Tuple(elements) => elements.iter().for_each(|ty| alias_refs(ty, out)),
+        TypeExprKind::Bind { ty, .. } => alias_refs(ty, out),
+        TypeExprKind::Expr(_) => {}
+    }
+}
+
+impl<'m> Visitor<'m> for Checker<'m> {
+    fn visit_type_expr(&mut self, ty: &'m TypeExpr) {
+        if let TypeExprKind::Named { name, args } = &ty.kind {
+            self.check_named(ty, name, args.len());
+        }
+        walk_type_expr(self, ty);
+    }
+}
+
+impl<'m> Checker<'m> {
+    fn check_named(&mut self, ty: &TypeExpr, name: &Ident, supplied: usize) {
+        if universe_level(&name.name).is_some() || self.values.contains(&name.name.as_str()) {
+            return;
+        }
+        let expected = match self.types.get(name.name.as_str()) {
+            Some(expected) => *expected,
+            None => {
+                let similar = find_best_match(&name.name, self.types.keys().copied());
+                // `int` is far more likely a typo than a type variable, but `a`
+                // is a type variable even with a type `A` around.
+                let typo =
+                    similar.filter(|similar| name.name.len() > 1 && similar.to_lowercase() == name.name.to_lowercase());
+                if is_type_variable(&name.name) && typo.is_none() {
+                    return;
+                }
+                let mut diagnostic =
+                    Diagnostic::error(format!("cannot find type `{}` in this scope", name.name), name.span)
+                        .with_label(name.span, "not found in this scope");
+                if let Some(similar) = similar {
+                    diagnostic = diagnostic.with_suggestion(
+                        Suggestion::replace(
+                            format!("a type with a similar name exists: `{similar}`"),
+                            name.span,
+                            similar,
+                        )
+                        .maybe_incorrect(),
+                    );
+                }
+                self.diagnostics.push(diagnostic);
+                return;
+            }
+        };
+        if supplied == expected {
+            return;
+        }
+
+        // A refinement type takes values rather than types.
+        let refinement = self
+            .aliases
+            .get(name.name.as_str())
+            .is_some_and(|alias| alias.refinement.is_some());
+        let argument = if refinement { "argument" } else { "type argument" };
+        let takes = match expected {
+            1 => format!("1 {argument}"),
+            n => format!("{n} {argument}s"),
+        };
+        let given = match supplied {
+            1 => format!("1 {argument} was"),
+            n => format!("{n} {argument}s were"),
+        };
+        let mut diagnostic = Diagnostic::error(
+            format!("type `{}` takes {takes} but {given} supplied", name.name),
+            name.span,
+        )
+        .with_label(name.span, format!("expected {takes}"));
+        if expected == 0 {
+            diagnostic = diagnostic.with_suggestion(Suggestion::replace(
+                format!("remove the {argument}s"),
+                Span::new(name.span.end, ty.span.end),
+                "",
+            ));
+        } else if supplied == 0 {
+            let placeholders = vec![if refinement { "_" } else { "T" }; expected].join(", ");
+            diagnostic = diagnostic.with_suggestion(
+                Suggestion::insert(
+                    format!("add the missing {argument}{}", if expected == 1 { "" } else { "s" }),
+                    name.span.end,
+                    format!("({placeholders})"),
+                )
+                .maybe_incorrect(),
+            );
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Follows the aliases `alias` refers to, reporting each cycle once.
+    fn check_cycle(&mut self, alias: &'m TypeAlias, stack: &mut Vec<&'m TypeAlias>, reported: &mut Vec<&'m str>) {
+        if let Some(start) = stack.iter().position(|a| std::ptr::eq(*a, alias)) {
+            let cycle = &stack[start..];
+            if cycle.iter().any(|a| reported.contains(&a.name.name.as_str())) {
+                return;
+            }
+            reported.extend(cycle.iter().map(|a| a.name.name.as_str()));
+            let first = &cycle[0].name;
+            let mut diagnostic = Diagnostic::error(
+                format!("cycle detected when expanding type alias `{}`", first.name),
+                first.span,
+            );
+            for next in &cycle[1..] {
+                diagnostic = diagnostic.with_note(format!(
+                    "...which requires expanding type alias `{}`...",
+                    next.name.name
+                ));
+            }
+            self.diagnostics.push(
+                diagnostic
+                    .with_note(format!(
+                        "...which again requires expanding type alias `{}`, completing the cycle",
+                        first.name
+                    ))
+                    .with_help("type aliases cannot be recursive; use a struct or an enum to define a recursive type"),
+            );
+            return;
+        }
+
+        stack.push(alias);
+        let mut refs = Vec::new();
+        alias_refs(&alias.ty, &mut refs);
+        for name in refs {
+            if let Some(next) = self.aliases.get(name.name.as_str()).copied() {
+                self.check_cycle(next, stack, reported);
+            }
+        }
+        stack.pop();
+    }
+}
@@ -0,0 +1,406 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! The abstract syntax tree produced by the parser. Every node carries the
+//! span of source text it was parsed from.
+
+use flerry_core::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub items: Vec<Item>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    /// `import IO`
+    Import(Ident),
+    /// `func name(params) = body end`, the body opened by any contracts.
+    Func(Func),
+    /// `struct Name = field: Type, ... end`
+    Struct(StructDef),
+    /// `enum Name = Variant | Variant(field: Type, ...) end`, or with
+    /// parameters an inductive family: `enum Vec(a: Type, n: Nat) = ... end`.
+    Enum(EnumDef),
+    /// `type Name = Type`, or a refinement type `type Pos = Int where self > 0`.
+    Type(TypeAlias),
+    /// Source that failed to parse as an item; the error is already reported.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Func {
+    pub name: Ident,
+    pub params: Vec<Param>,
+    /// `-> Type`; without one the result type is inferred.
+    pub ret: Option<TypeExpr>,
+    /// The `requires` and `ensures` clauses opening the body.
+    pub contracts: Vec<Contract>,
+    pub body: Block,
+}
+
+/// `requires cond`, which callers must establish, or `ensures cond`, which
+/// the function establishes for its result; `ensures` sees `result` and
+/// `old(x)`, the value `x` had on entry. On a loop, `invariant cond`, which
+/// holds before every iteration and after the last, or `decreases expr`, an
+/// integer that stays at least zero and shrinks with every iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    pub kind: ContractKind,
+    pub cond: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContractKind {
+    Requires,
+    Ensures,
+    Invariant,
+    Decreases,
+}
+
+impl ContractKind {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            ContractKind::Requires => "requires",
+            ContractKind::Ensures => "ensures",
+            ContractKind::Invariant => "invariant",
+            ContractKind::Decreases => "decreases",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: Ident,
+    /// `name: Type`; parameters without one take any value.
+    pub ty: Option<TypeExpr>,
+    /// `{n: Nat}`, a parameter calls leave out and the checker infers from
+    /// the other arguments.
+    pub implicit: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDef {
+    pub name: Ident,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    pub name: Ident,
+    /// `(a: Type, n: Nat)`, the arguments the enum takes as a type. Those
+    /// every variant's type passes through unchanged are parameters; the
+    /// rest are indices, which each variant may fix differently.
+    pub params: Vec<Param>,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: Ident,
+    pub fields: Vec<Field>,
+    /// `-> Vec(a, n + 1)`, the type the variant builds, for an enum with
+    /// indices.
+    pub ret: Option<TypeExpr>,
+    pub span: Span,
+}
+
+/// `name: Type`, or just `name` when the type is left open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: Ident,
+    pub ty: Option<TypeExpr>,
+    /// `{k: Nat}` in a variant of a family, a field left out where the
+    /// variant is used.
+    pub implicit: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAlias {
+    pub name: Ident,
+    /// `(n)` in `type Index(n) = Nat where self < n`: values the refinement
+    /// refers to, which each use of the type supplies.
+    pub params: Vec<Ident>,
+    pub ty: TypeExpr,
+    /// `where self > 0`, what every value of the type satisfies, with
+    /// `self` standing for the value.
+    pub refinement: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeExpr {
+    pub kind: TypeExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExprKind {
+    /// A named type, possibly applied to arguments: `Int`, `List(Int)`.
+    Named { name: Ident, args: Vec<TypeExpr> },
+    /// `Int -> Int`, or `(Int, Int) -> Int` for several parameters.
+    Function { params: Vec<TypeExpr>, ret: Box<TypeExpr> },
+    /// `(Int, Str)`; `()` is the unit type.
+    Tuple(Vec<TypeExpr>),
+    /// `n: Nat` as a parameter of a function type or an element of a tuple
+    /// type, which the types after it may refer to: `(n: Nat) -> Vec(Int, n)`.
+    Bind { name: Ident, ty: Box<TypeExpr> },
+    /// A value a type is applied to: `n + 1` in `Vec(a, n + 1)`.
+    Expr(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    /// `target = value`, where the target is a name, field or index, or
+    /// `name: Type = value` to declare the type of a local.
+    Assign {
+        target: Expr,
+        ty: Option<TypeExpr>,
+        value: Expr,
+    },
+    Expr(Expr),
+    Return(Option<Expr>),
+    /// `if c ... elsif c ... else ... end`
+    If {
+        branches: Vec<(Expr, Block)>,
+        else_block: Option<Block>,
+    },
+    /// `while c ... end`, the body opened by any `invariant` and
+    /// `decreases` clauses.
+    While {
+        cond: Expr,
+        contracts: Vec<Contract>,
+        body: Block,
+    },
+    /// `for x in xs ... end`, the body opened by any `invariant` and
+    /// `decreases` clauses.
+    For {
+        var: Ident,
+        iter: Expr,
+        contracts: Vec<Contract>,
+        body: Block,
+    },
+    /// A statement that failed to parse; the error is already reported.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Lit(Lit),
+    Ident(Ident),
+    List(Vec<Expr>),
+    /// `(1, "one")`; `()` is the unit value. Elements are read with `.0`,
+    /// `.1` and so on.
+    Tuple(Vec<Expr>),
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// A field access, or with a call around it a method call: `p.norm()`.
+    Field {
+        expr: Box<Expr>,
+        field: Ident,
+    },
+    Index {
+        expr: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    /// `Point { x: 1.0, y }`, or `Point { x: 1.0, ..p }` to take the other
+    /// fields from `p`.
+    Struct {
+        name: Ident,
+        fields: Vec<FieldInit>,
+        base: Option<Box<Expr>>,
+    },
+    /// `(x) => x + 1`
+    Lambda {
+        params: Vec<Param>,
+        body: Box<Expr>,
+    },
+    /// A lambda after closure conversion: a call to the lifted function
+    /// `func` with the captured variables as its first arguments. Only
+    /// `closure::convert` makes these.
+    Closure {
+        func: Ident,
+        captures: Vec<Capture>,
+    },
+    /// `?` or `?name`, a part of the program still to be written. The
+    /// checker reports the type it must have and what is in scope there.
+    Hole(Option<Ident>),
+    /// An expression that failed to parse; the error is already reported.
+    Error,
+}
+
+/// A variable a closure uses from the function around it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub name: Ident,
+    pub mode: CaptureMode,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// The value when the closure is made; the variable never changes after.
+    Value,
+    /// The variable itself, which is assigned again and must be shared.
+    Ref,
+}
+
+/// `name: value`, or just `name` for a variable of the same name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit {
+    pub name: Ident,
+    pub value: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lit {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// The remainder of `/`, with the sign of the dividend.
+    Rem,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    /// `a => b`, only allowed in specifications.
+    Implies,
+}
+
+impl BinaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Implies => "=>",
+        }
+    }
+}
+
+impl UnaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    /// `pattern if guard => body`
+    pub guard: Option<Expr>,
+    pub body: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    /// `_`
+    Wildcard,
+    /// A lowercase name, binding the matched value.
+    Binding(Ident),
+    Lit(Lit),
+    /// A variant, with or without sub-patterns: `None`, `Circle(r)`.
+    Variant {
+        name: Ident,
+        args: Vec<Pattern>,
+    },
+    /// Alternatives, any of which may match: `Circle(_) | Empty`.
+    Or(Vec<Pattern>),
+    /// An arm that failed to parse; the error is already reported.
+    Error,
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Checks that run directly on the token stream, before any parsing.
+
+use flerry_core::{Applicability, Diagnostic, Suggestion, Token, TokenType};
+use flerry_lexer::lexer::{KEYWORDS, similar_keyword};
+use std::ops::Range;
+
+/// Spellings borrowed from other languages that Flerry writes differently.
+const MISSPELLED_KEYWORDS: &[(&str, &str)] = &[("elseif", "elsif"), ("elif", "elsif")];
+
+pub fn check_tokens(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    misspelled_keywords(tokens, &mut diagnostics);
+    diagnostics
+}
+
+/// Token index ranges of every `#[...]` attribute.
+pub(crate) fn attributes(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    for (i, window) in tokens.windows(2).enumerate() {
+        if window[0].ttype == TokenType::Hash && window[1].ttype == TokenType::LSqBrace {
+            let close = tokens[i..]
+                .iter()
+                .position(|t| matches!(t.ttype, TokenType::RSqBrace | TokenType::Eof))
+                .map_or(tokens.len(), |p| i + p);
+            ranges.push(i..(close + 1).min(tokens.len()));
+        }
+    }
+    ranges
+}
+
+/// Token index ranges of every item. Functions cover their body as in
+/// `function_bodies`; the other items end with their `end` or their line.
+pub(crate) fn items(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut items = function_bodies(tokens);
+    for (i, token) in tokens.iter().enumerate() {
+        let end = match token.ttype {
+            TokenType::Import => (i + 2).min(tokens.len()),
+            TokenType::Struct | TokenType::Enum => tokens[i..]
+                .iter()
+                .position(|t| matches!(t.ttype, TokenType::End | TokenType::Eof))
+                .map_or(tokens.len(), |p| i + p + 1),
+            TokenType::Type => tokens[i..]
+                .iter()
+                .position(|t| t.line != token.line || t.ttype == TokenType::Eof)
+                .map_or(tokens.len(), |p| i + p),
+            _ => continue,
+        };
+        items.push(i..end);
+    }
+    items
+}
+
+/// Returns the keyword the identifier at `tokens[i]` was meant to be, if it
+/// is a known misspelling or sits where only a keyword could and is close to one.
+pub(crate) fn misspelled_keyword(tokens: &[Token], i: usize) -> Option<(&'static str, Applicability)> {
+    let token = &tokens[i];
+    if token.ttype != TokenType::Identifier {
+        return None;
+    }
+    if let Some((_, keyword)) = MISSPELLED_KEYWORDS.iter().find(|(bad, _)| *bad == token.lexeme) {
+        return Some((keyword, Applicability::MachineApplicable));
+    }
+
+    // Flerry has no juxtaposition, so a line starting with a name followed by
+    // another name or literal (`retrun x`, `fucn main`), or a name alone on its
+    // line (`ned`), can only be a keyword gone wrong.
+    let first_on_line = i == 0 || tokens[i - 1].line != token.line;
+    let keyword_position = first_on_line
+        && match tokens.get(i + 1) {
+            Some(next) if next.ttype != TokenType::Eof && next.line == token.line => matches!(
+                next.ttype,
+                TokenType::Identifier | TokenType::Integer | TokenType::Float | TokenType::Strings
+            ),
+            _ => true,
+        };
+    if !keyword_position {
+        return None;
+    }
+    similar_keyword(token.lexeme).map(|keyword| (keyword, Applicability::MaybeIncorrect))
+}
+
+/// Retypes misspelled keywords as the keyword they were meant to be, so the
+/// parser carries on as if they were spelled right. The misspellings
+/// themselves are reported by `check_tokens`.
+pub fn repair_keywords(tokens: &mut [Token]) {
+    for i in 0..tokens.len() {
+        if let Some((keyword, _)) = misspelled_keyword(tokens, i)
+            && let Some((_, ttype)) = KEYWORDS.iter().find(|(k, _)| *k == keyword)
+        {
+            tokens[i].ttype = *ttype;
+        }
+    }
+}
+
+fn misspelled_keywords(tokens: &[Token], diagnostics: &mut Vec<Diagnostic>) {
+    for (i, token) in tokens.iter().enumerate() {
+        let Some((keyword, applicability)) = misspelled_keyword(tokens, i) else {
+            continue;
+        };
+        let mut suggestion = Suggestion::replace(format!("did you mean `{keyword}`?"), token.span, keyword);
+        if applicability == Applicability::MaybeIncorrect {
+            suggestion = suggestion.maybe_incorrect();
+        }
+        diagnostics.push(
+            Diagnostic::error(format!("unknown keyword `{}`", token.lexeme), token.span).with_suggestion(suggestion),
+        );
+    }
+}
+
+fn opens_block(ttype: TokenType) -> bool {
+    matches!(
+        ttype,
+        TokenType::Func
+            | TokenType::If
+            | TokenType::While
+            | TokenType::For
+            | TokenType::Match
+            | TokenType::Struct
+            | TokenType::Enum
+    )
+}
+
+/// Token index ranges of every `func` block. An unclosed function extends up
+/// to where the next one begins.
+pub(crate) fn function_bodies(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut bodies = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        if matches!(token.ttype, TokenType::Func | TokenType::Eof)
+            && let Some(start) = start.take()
+        {
+            bodies.push(start..i);
+        }
+
+        if opens_block(token.ttype) {
+            if token.ttype == TokenType::Func {
+                start = Some(i);
+                depth = 0;
+            }
+            depth += 1;
+        } else if token.ttype == TokenType::End && depth > 0 {
+            depth -= 1;
+            if depth == 0
+                && let Some(start) = start.take()
+            {
+                bodies.push(start..i + 1);
+            }
+        }
+    }
+
+    bodies
+}
@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Closure conversion: every lambda becomes a top-level function plus a
+//! `Closure` value that pairs it with the variables it captures.
+//!
+//! The lifted function `f$lambda0` takes the captured variables first and the
+//! lambda's own parameters after them, so a backend calls a closure by
+//! passing its environment ahead of the arguments. A variable is captured by
+//! reference when the enclosing function assigns to it again or mutates it
+//! in place, so every copy sees the changes; otherwise by value.
+//!
+//! `$` cannot appear in source names, so lifted names never collide.
+
+use crate::ast::*;
+use crate::fold::{Folder, walk_expr, walk_match_arm, walk_stmt};
+use crate::visit::{Visitor, walk_expr as visit_expr, walk_match_arm as visit_match_arm, walk_stmt as visit_stmt};
+use flerry_core::Span;
+use std::collections::HashSet;
+
+/// Lifts every lambda in `module` out into a function of its own.
+pub fn convert(module: Module) -> Module {
+    let mut converter = Converter::default();
+    let mut items = Vec::new();
+    for item in module.items {
+        let item = converter.fold_item(item);
+        items.push(item);
+        converter.lifted.sort_by_key(|(id, _)| *id);
+        items.extend(converter.lifted.drain(..).map(|(_, item)| item));
+    }
+    Module {
+        items,
+        span: module.span,
+    }
+}
+
+#[derive(Default)]
+struct Converter {
+    /// The function being converted, which names its lambdas.
+    enclosing: String,
+    next_id: usize,
+    /// Lambdas lifted out of the current function, by the order they appear.
+    lifted: Vec<(usize, Item)>,
+    /// The names bound at each level: the function, then every loop, arm and
+    /// lambda inside it.
+    scopes: Vec<HashSet<String>>,
+    /// Function-level variables that are assigned more than once or mutated
+    /// through a field or index.
+    reassigned: HashSet<String>,
+}
+
+impl Converter {
+    fn capture(&self, name: &Ident) -> Option<Capture> {
+        let level = self.scopes.iter().rposition(|scope| scope.contains(&name.name))?;
+        // Loop variables, arm bindings and lambda parameters are fresh for
+        // each iteration, arm or call, so a copy never goes stale.
+        let mode = if level == 0 && self.reassigned.contains(&name.name) {
+            CaptureMode::Ref
+        } else {
+            CaptureMode::Value
+        };
+        Some(Capture {
+            name: name.clone(),
+            mode,
+        })
+    }
+
+    fn lift(&mut self, params: Vec<Param>, body: Expr, span: Span) -> ExprKind {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.scopes
+            .push(params.iter().map(|param| param.name.name.clone()).collect());
+        let body = self.fold_expr(body);
+        self.scopes.pop();
+
+        let mut free = FreeNames::default();
+        free.visit_expr(&body);
+        let own: HashSet<&str> = params.iter().map(|param| param.name.name.as_str()).collect();
+        let captures: Vec<Capture> = free
+            .names
+            .iter()
+            .filter(|name| !own.contains(name.name.as_str()))
+            .filter_map(|name| self.capture(name))
+            .collect();
+
+        let func = Ident {
+            name: format!("{}$lambda{id}", self.enclosing),
+            span,
+        };
+        let lifted = Func {
+            name: func.clone(),
+            params: captures
+                .iter()
+                .map(|capture| Param {
+                    name: capture.name.clone(),
+                    ty: None,
+                    implicit: false,
+                    span: capture.name.span,
+                })
+                .chain(params)
+                .collect(),
+            ret: None,
+            contracts: Vec::new(),
+            body: Block {
+                span: body.span,
+                stmts: vec![Stmt {
+                    span: body.span,
+                    kind: StmtKind::Return(Some(body)),
+                }],
+            },
+        };
+        self.lifted.push((
+            id,
+            Item {
+                kind: ItemKind::Func(lifted),
+                span,
+            },
+        ));
+        ExprKind::Closure { func, captures }
+    }
+}
+
+impl Folder for Converter {
+    fn fold_func(&mut self, func: Func) -> Func {
+        let mut assigned = Assignments::default();
+        assigned.visit_block(&func.body);
+        let mut scope: HashSet<String> = func.params.iter().map(|param| param.name.name.clone()).collect();
+        let mut reassigned = assigned.mutated;
+        for name in assigned.names {
+            // A parameter counts as the first assignment.
+            if !scope.insert(name.clone()) {
+                reassigned.insert(name);
+            }
+        }
+
+        self.enclosing = func.name.name.clone();
+        self.next_id = 0;
+        self.scopes = vec![scope];
+        self.reassigned = reassigned;
+        let func = crate::fold::walk_func(self, func);
+        self.scopes.clear();
+        func
+    }
+
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        let StmtKind::For { var, .. } = &stmt.kind else {
+            return walk_stmt(self, stmt);
+        };
+        self.scopes.push(HashSet::from([var.name.clone()]));
+        let stmt = walk_stmt(self, stmt);
+        self.scopes.pop();
+        stmt
+    }
+
+    fn fold_match_arm(&mut self, arm: MatchArm) -> MatchArm {
+        let mut bindings = FreeNames::default();
+        bindings.visit_pattern(&arm.pattern);
+        self.scopes.push(bindings.bound.into_iter().collect());
+        let arm = walk_match_arm(self, arm);
+        self.scopes.pop();
+        arm
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr.kind {
+            ExprKind::Lambda { params, body } => Expr {
+                kind: self.lift(params, *body, expr.span),
+                span: expr.span,
+            },
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+/// Counts the assignments to each variable in a function body.
+#[derive(Default)]
+struct Assignments {
+    /// One entry per assignment to a plain name.
+    names: Vec<String>,
+    /// Variables changed through `v.field = ...` or `v[i] = ...`.
+    mutated: HashSet<String>,
+}
+
+impl<'m> Visitor<'m> for Assignments {
+    fn visit_stmt(&mut self, stmt: &'m Stmt) {
+        if let StmtKind::Assign { target, .. } = &stmt.kind {
+            let mut place = target;
+            while let ExprKind::Field { expr, .. } | ExprKind::Index { expr, .. } = &place.kind {
+                place = expr;
+            }
+            if let ExprKind::Ident(name) = &place.kind {
+                if std::ptr::eq(place, target) {
+                    self.names.push(name.name.clone());
+                } else {
+                    self.mutated.insert(name.name.clone());
+                }
+            }
+        }
+        visit_stmt(self, stmt);
+    }
+}
+
+/// The names an already converted lambda body refers to but does not bind,
+/// in the order they first appear.
+#[derive(Default)]
+struct FreeNames {
+    names: Vec<Ident>,
+    /// Names bound by the patterns visited so far.
+    bound: Vec<String>,
+}
+
+impl FreeNames {
+    fn refer(&mut self, name: &Ident) {
+        if !self.bound.contains(&name.name) && !self.names.iter().any(|n| n.name == name.name) {
+            self.names.push(name.clone());
+        }
+    }
+}
+
+impl<'m> Visitor<'m> for FreeNames {
+    fn visit_expr(&mut self, expr: &'m Expr) {
+        match &expr.kind {
+            ExprKind::Ident(name) => self.refer(name),
+            // Inner lambdas are closures by now; their captures are what
+            // they need from here.
+            ExprKind::Closure { captures, .. } => captures.iter().for_each(|capture| self.refer(&capture.name)),
+            // Field names and struct names are not variables.
+            ExprKind::Field { expr, .. } => self.visit_expr(expr),
+            ExprKind::Struct { fields, base, .. } => {
+                fields.iter().for_each(|init| self.visit_expr(&init.value));
+                if let Some(base) = base {
+                    self.visit_expr(base);
+                }
+            }
+            _ => visit_expr(self, expr),
+        }
+    }
+
+    fn visit_match_arm(&mut self, arm: &'m MatchArm) {
+        let outer = self.bound.len();
+        visit_match_arm(self, arm);
+        self.bound.truncate(outer);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'m Pattern) {
+        match &pattern.kind {
+            PatternKind::Binding(name) => self.bound.push(name.name.clone()),
+            PatternKind::Variant { args, .. } => args.iter().for_each(|arg| self.visit_pattern(arg)),
+            PatternKind::Or(alternatives) => alternatives.iter().for_each(|alt| self.visit_pattern(alt)),
+            PatternKind::Wildcard | PatternKind::Lit(_) | PatternKind::Error => {}
+        }
+    }
+}
@@ -1,6 +1,179 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
 
-pub fn compile() {
-    todo!("");
+use crate::ast::Module;
+use crate::contracts::{self, ContractMode, Proofs};
+use crate::holes::Goal;
+use crate::lint::{LintOptions, apply_lint_levels};
+use crate::parser::parse;
+use crate::refine::Refinements;
+use crate::vc::CheckKind;
+use crate::verify::{Obligation, Report};
+use crate::{adt, annotations, checks, closure, elab, infer, reachability, resolve, structs, verify};
+use flerry_core::{Diagnostic, Token};
+use flerry_lexer::lexer::tokenize;
+use flerry_prover::Limits;
+use flerry_prover::smtlib::Script;
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub lints: LintOptions,
+    /// How long the solver may spend on each verification condition.
+    pub limits: Limits,
+    /// What becomes of contracts and refinement types.
+    pub contracts: ContractMode,
+    /// Whether to leave out the runtime checks of what was proved.
+    pub release: bool,
+    /// The command running an SMT-LIB solver to prove the checks with,
+    /// instead of the prover: `z3 -in`.
+    pub solver: Option<String>,
+}
+
+/// Compiles `source` with the default options.
+pub fn compile(source: &str) -> Vec<Diagnostic> {
+    compile_with(source, &Options::default())
+}
+
+/// Compiles `source`, returning every diagnostic produced along the way,
+/// each hole reported with its goal.
+pub fn compile_with(source: &str, options: &Options) -> Vec<Diagnostic> {
+    let (mut diagnostics, goals) = compile_goals(source, options);
+    diagnostics.extend(goals.iter().map(Goal::diagnostic));
+    diagnostics
+}
+
+/// Compiles `source`, returning the goals of its holes apart from the other
+/// diagnostics.
+pub fn compile_goals(source: &str, options: &Options) -> (Vec<Diagnostic>, Vec<Goal>) {
+    let mut analysis = analyze(source, options);
+    analysis
+        .diagnostics
+        .extend(analysis.reports.into_iter().filter_map(|report| report.diagnostic));
+    (analysis.diagnostics, analysis.goals)
+}
+
+/// Compiles `source`, returning what became of each verification
+/// condition apart from the other diagnostics. There are none if the
+/// program has other errors.
+pub fn verify(source: &str, options: &Options) -> (Vec<Diagnostic>, Vec<Report>) {
+    let mut analysis = analyze(source, options);
+    analysis.diagnostics.extend(analysis.goals.iter().map(Goal::diagnostic));
+    (analysis.diagnostics, analysis.reports)
+}
+
+/// Compiles `source` and writes the condition of each of its checks as an
+/// SMT-LIB script, with the function it is in. There are none if the
+/// program has errors.
+pub fn smt2(source: &str, options: &Options) -> (Vec<Diagnostic>, Vec<(String, Script)>) {
+    // The conditions are only written out, so nothing is proved.
+    let options = Options {
+        contracts: ContractMode::Off,
+        ..options.clone()
+    };
+    let analysis = analyze(source, &options);
+    let mut diagnostics = analysis.diagnostics;
+    diagnostics.extend(analysis.goals.iter().map(Goal::diagnostic));
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        return (diagnostics, Vec::new());
+    }
+    let scripts = verify::conditions(&analysis.module)
+        .iter()
+        .map(|(function, check, condition)| (function.to_string(), verify::script(source, function, check, condition)))
+        .collect();
+    (diagnostics, scripts)
+}
+
+/// Compiles `source` and lowers it for a backend: its contracts become the
+/// runtime checks `options` asks for, and every lambda a function.
+pub fn lower(source: &str, options: &Options) -> (Module, Vec<Diagnostic>) {
+    let mut analysis = analyze(source, options);
+    analysis
+        .diagnostics
+        .extend(analysis.reports.into_iter().filter_map(|report| report.diagnostic));
+    analysis.diagnostics.extend(analysis.goals.iter().map(Goal::diagnostic));
+    let module = contracts::lower(
+        analysis.module,
+        source,
+        &analysis.proofs,
+        options.contracts,
+        options.release,
+    );
+    (closure::convert(module), analysis.diagnostics)
+}
+
+/// What the passes found in a module.
+struct Analysis {
+    module: Module,
+    diagnostics: Vec<Diagnostic>,
+    goals: Vec<Goal>,
+    reports: Vec<Report>,
+    proofs: Proofs,
+}
+
+fn analyze(source: &str, options: &Options) -> Analysis {
+    let (tokens, mut diagnostics) = tokenize(source);
+    let module = parse_tokens(source, &tokens, &mut diagnostics);
+    diagnostics.extend(resolve::resolve(source, &module));
+    diagnostics.extend(reachability::unreachable_code(&module));
+    diagnostics.extend(adt::check(&module));
+    let inference = infer::infer(&module);
+    diagnostics.extend(structs::check(&module, &inference.receivers));
+    diagnostics.extend(annotations::check(&module));
+    diagnostics.extend(inference.diagnostics);
+    let elaboration = elab::elaborate(&module);
+    diagnostics.extend(elaboration.diagnostics);
+    diagnostics.extend(Refinements::new(&module).check(&module));
+    // Conditions about a program that does not type check would only add
+    // noise to its errors.
+    let obligations = if diagnostics.iter().any(Diagnostic::is_error) || !options.contracts.proves() {
+        Vec::new()
+    } else {
+        verify::verify(&module, source, &options.limits, options.solver.as_deref())
+    };
+    let proofs = Proofs::new(&obligations);
+    let mut reports: Vec<Report> = obligations.iter().map(Obligation::report).collect();
+    if options.contracts == ContractMode::Both {
+        // Overflow and division by zero are not checked when the program
+        // runs, so they stay errors.
+        let deferred = obligations.iter().map(|obligation| {
+            !matches!(
+                obligation.check.kind,
+                CheckKind::Overflow { .. } | CheckKind::DivisionByZero { .. }
+            )
+        });
+        for (report, deferred) in reports.iter_mut().zip(deferred) {
+            if deferred {
+                report.diagnostic = report.diagnostic.take().map(contracts::deferred);
+            }
+        }
+    }
+
+    let mut goals = inference.goals;
+    goals.extend(elaboration.goals);
+    goals.sort_by_key(|goal| goal.span.start);
+    Analysis {
+        diagnostics: apply_lint_levels(&tokens, diagnostics, &options.lints),
+        module,
+        goals,
+        reports,
+        proofs,
+    }
+}
+
+/// Lexes and parses `source` without checking names, returning the module
+/// with the lexical and syntax errors.
+pub fn parse_source(source: &str) -> (Module, Vec<Diagnostic>) {
+    let (tokens, mut diagnostics) = tokenize(source);
+    let module = parse_tokens(source, &tokens, &mut diagnostics);
+    (module, diagnostics)
+}
+
+fn parse_tokens(source: &str, tokens: &[Token], diagnostics: &mut Vec<Diagnostic>) -> Module {
+    diagnostics.extend(checks::check_tokens(tokens));
+
+    let mut repaired = tokens.to_vec();
+    checks::repair_keywords(&mut repaired);
+    let (module, parse_diagnostics) = parse(source, &repaired);
+    diagnostics.extend(parse_diagnostics);
+    module
 }
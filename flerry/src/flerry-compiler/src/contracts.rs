@@ -0,0 +1,680 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Contract modes, and the lowering that turns contracts into checks made
+//! when the program runs.
+//!
+//! A check becomes a call to the intrinsic `contract$assert(cond, clause,
+//! location)`, which stops the program with the clause and the location
+//! when the condition is false. What a callee asks of its caller, its
+//! `requires` clauses and the refinement types of its parameters, is
+//! checked by a wrapper `f$checked` taking the caller's location as an
+//! extra argument; a call that needs checking calls the wrapper instead.
+//! What a function promises, its `ensures` clauses and the refinement type
+//! of its result, is checked where it returns, and the refinement type of
+//! a local wherever it is assigned. `old(x)` reads a copy `x$old` taken on
+//! entry when the function assigns `x`.
+//!
+//! A loop's `invariant` clauses are checked before it, and again at the end
+//! of its body. Its `decreases` measure is copied to `measure$n` when an
+//! iteration starts, and at its end checked to have been at least zero and
+//! to have become smaller.
+//!
+//! `$` cannot appear in source names, so generated names never collide.
+
+use crate::ast::*;
+use crate::fold::{Folder, walk_expr, walk_stmt};
+use crate::pretty::{self, type_expr};
+use crate::refine::Refinements;
+use crate::vc::{Assigned, CheckKind, LoopObligation, falls_through};
+use crate::verify::{Obligation, article};
+use crate::visit::Visitor;
+use flerry_core::span::line_col;
+use flerry_core::{Diagnostic, Level, Span};
+use flerry_prover::Verdict;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// What becomes of contracts and refinement types: `--contracts=<mode>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ContractMode {
+    /// Neither proved nor checked.
+    Off,
+    /// Checked when the program runs, without asking the solver.
+    Runtime,
+    /// Proved, with an error for every one that is not.
+    #[default]
+    Static,
+    /// Proved, and those that are not checked when the program runs, with
+    /// a warning.
+    Both,
+}
+
+impl ContractMode {
+    pub fn from_name(name: &str) -> Option<ContractMode> {
+        match name {
+            "off" => Some(ContractMode::Off),
+            "runtime" => Some(ContractMode::Runtime),
+            "static" => Some(ContractMode::Static),
+            "both" => Some(ContractMode::Both),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ContractMode::Off => "off",
+            ContractMode::Runtime => "runtime",
+            ContractMode::Static => "static",
+            ContractMode::Both => "both",
+        }
+    }
+
+    /// Whether the solver is asked to prove the checks.
+    pub fn proves(self) -> bool {
+        matches!(self, ContractMode::Static | ContractMode::Both)
+    }
+}
+
+/// The kinds of check, told apart where several share a span.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Clause {
+    Requires,
+    Ensures,
+    Refinement,
+    Loop(LoopObligation),
+}
+
+/// Where the solver proved every check of a kind.
+#[derive(Debug, Default)]
+pub struct Proofs {
+    sites: HashMap<(Span, Clause), bool>,
+}
+
+impl Proofs {
+    pub fn new(obligations: &[Obligation]) -> Proofs {
+        let mut sites = HashMap::new();
+        for obligation in obligations {
+            let clause = match &obligation.check.kind {
+                CheckKind::Requires { .. } => Clause::Requires,
+                CheckKind::Ensures { .. } => Clause::Ensures,
+                CheckKind::Refinement { .. } => Clause::Refinement,
+                CheckKind::Loop { obligation, .. } => Clause::Loop(*obligation),
+                // Overflow and division by zero are not checked when the
+                // program runs.
+                CheckKind::Overflow { .. } | CheckKind::DivisionByZero { .. } => continue,
+            };
+            let proved = matches!(obligation.verdict, Verdict::Proved);
+            *sites.entry((obligation.check.span, clause)).or_insert(true) &= proved;
+        }
+        Proofs { sites }
+    }
+
+    fn proved(&self, span: Span, clause: Clause) -> bool {
+        self.sites.get(&(span, clause)).copied().unwrap_or(false)
+    }
+}
+
+/// The error for a check that was not proved, as a warning that it is
+/// checked when the program runs instead.
+pub fn deferred(diagnostic: Diagnostic) -> Diagnostic {
+    Diagnostic {
+        level: Level::Warning,
+        ..diagnostic
+    }
+    .with_note("it is checked when the program runs instead")
+}
+
+/// Replaces the contracts of `module` with the checks `mode` makes when the
+/// program runs. A release build leaves out the checks that were proved.
+pub fn lower(module: Module, source: &str, proofs: &Proofs, mode: ContractMode, release: bool) -> Module {
+    let original = module.clone();
+    let functions = original
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Func(func) => Some((func.name.name.as_str(), func)),
+            _ => None,
+        })
+        .collect();
+    let mut lowerer = Lowerer {
+        source,
+        proofs,
+        mode,
+        release,
+        refinements: Refinements::new(&original),
+        functions,
+        func: None,
+        locals: HashSet::new(),
+        declared: HashMap::new(),
+        snapshot: false,
+        measures: 0,
+        wrapped: BTreeSet::new(),
+    };
+    let items: Vec<Item> = module.items.into_iter().map(|item| lowerer.fold_item(item)).collect();
+    let mut lowered = Vec::new();
+    for item in items {
+        let wrapper = match &item.kind {
+            ItemKind::Func(func) if lowerer.wrapped.contains(func.name.name.as_str()) => {
+                Some(lowerer.wrapper(lowerer.functions[func.name.name.as_str()]))
+            }
+            _ => None,
+        };
+        lowered.push(item);
+        lowered.extend(wrapper.map(|func| Item {
+            span: func.name.span,
+            kind: ItemKind::Func(func),
+        }));
+    }
+    Module {
+        items: lowered,
+        span: module.span,
+    }
+}
+
+struct Lowerer<'a> {
+    source: &'a str,
+    proofs: &'a Proofs,
+    mode: ContractMode,
+    release: bool,
+    refinements: Refinements<'a>,
+    /// The functions of the module as written.
+    functions: HashMap<&'a str, &'a Func>,
+    /// The function being lowered, as written.
+    func: Option<&'a Func>,
+    /// The names bound in the function, which hide functions of the same name.
+    locals: HashSet<String>,
+    /// The types locals are declared with.
+    declared: HashMap<String, TypeExpr>,
+    /// Whether a check reads the parameters as they were on entry.
+    snapshot: bool,
+    /// How many loop measures are copied in the module.
+    measures: usize,
+    /// The functions some call checks through a wrapper.
+    wrapped: BTreeSet<&'a str>,
+}
+
+impl<'a> Lowerer<'a> {
+    /// Whether the check of `clause` at `span` is made when the program runs.
+    fn checked(&self, span: Span, clause: Clause) -> bool {
+        match self.mode {
+            ContractMode::Off | ContractMode::Static => false,
+            ContractMode::Runtime => true,
+            ContractMode::Both => !(self.release && self.proofs.proved(span, clause)),
+        }
+    }
+
+    fn location(&self, span: Span) -> Expr {
+        let (line, column) = line_col(self.source, span.start);
+        string(format!("{line}:{column}"), span)
+    }
+
+    /// What a value of `ty` satisfies, about `value`, if `ty` is a
+    /// refinement type.
+    fn condition(&self, ty: &TypeExpr, value: &Expr) -> Option<Expr> {
+        let mut parts = Vec::new();
+        self.condition_in(ty, value, &HashMap::new(), &mut parts, &mut Vec::new());
+        parts.into_iter().reduce(|lhs, rhs| binary(BinaryOp::And, lhs, rhs))
+    }
+
+    /// Adds the conditions of `ty` to `parts`, innermost base first, the
+    /// names in its arguments standing for the expressions in `scope`.
+    fn condition_in(
+        &self,
+        ty: &TypeExpr,
+        value: &Expr,
+        scope: &HashMap<String, Expr>,
+        parts: &mut Vec<Expr>,
+        expanding: &mut Vec<&'a str>,
+    ) {
+        let TypeExprKind::Named { name, args } = &ty.kind else {
+            return;
+        };
+        let Some(alias) = self.refinements.alias(&name.name) else {
+            return;
+        };
+        if expanding.contains(&alias.name.name.as_str()) {
+            return;
+        }
+        let mut inner = HashMap::from([("self".to_owned(), value.clone())]);
+        for (param, arg) in alias.params.iter().zip(args) {
+            let arg = match &arg.kind {
+                TypeExprKind::Named { name, args } if args.is_empty() => scope
+                    .get(&name.name)
+                    .cloned()
+                    .unwrap_or_else(|| ident(&name.name, arg.span)),
+                TypeExprKind::Expr(expr) => substitute((**expr).clone(), scope, None),
+                _ => ident(&type_expr(arg), arg.span),
+            };
+            inner.insert(param.name.clone(), arg);
+        }
+        expanding.push(&alias.name.name);
+        self.condition_in(&alias.ty, value, &inner, parts, expanding);
+        expanding.pop();
+        if let Some(cond) = &alias.refinement {
+            parts.push(substitute(cond.clone(), &inner, None));
+        }
+    }
+
+    /// The parameters as they were on entry: the copies of those the
+    /// function assigns.
+    fn entry(&mut self, func: &Func) -> HashMap<String, Expr> {
+        let mut assigned = Assigned::default();
+        assigned.visit_block(&func.body);
+        self.snapshot |= func
+            .params
+            .iter()
+            .any(|param| assigned.names.contains(&param.name.name.as_str()));
+        func.params
+            .iter()
+            .filter(|param| assigned.names.contains(&param.name.name.as_str()))
+            .map(|param| {
+                let name = &param.name;
+                (name.name.clone(), ident(&format!("{}$old", name.name), name.span))
+            })
+            .collect()
+    }
+
+    /// The checks of what the function promises where it returns `result`,
+    /// or `()` for a return without a value.
+    fn returns(&mut self, result: Option<&Expr>, value_span: Option<Span>, span: Span) -> Vec<Stmt> {
+        let Some(func) = self.func else {
+            return Vec::new();
+        };
+        let mut checks = Vec::new();
+        let location = self.location(span);
+        if let (Some(result), Some(value_span), Some(ret)) = (result, value_span, &func.ret)
+            && self.checked(value_span, Clause::Refinement)
+            && let Some(cond) = self.condition(ret, result)
+        {
+            let entry = self.entry(func);
+            let clause = format!("`{}` returns {}", func.name.name, article(&type_expr(ret)));
+            checks.push(assertion(substitute(cond, &entry, None), clause, location.clone()));
+        }
+        if self.checked(span, Clause::Ensures) {
+            let result = result.cloned().unwrap_or_else(|| unit(span));
+            for contract in &func.contracts {
+                if contract.kind != ContractKind::Ensures {
+                    continue;
+                }
+                let entry = self.entry(func);
+                let names = HashMap::from([("result".to_owned(), result.clone())]);
+                let cond = substitute(contract.cond.clone(), &names, Some(&entry));
+                let clause = format!("`{}` ensures `{}`", func.name.name, pretty::expr(&contract.cond));
+                checks.push(assertion(cond, clause, location.clone()));
+            }
+        }
+        checks
+    }
+
+    /// A statement, followed by the checks it needs.
+    fn stmt(&mut self, stmt: Stmt) -> Vec<Stmt> {
+        match &stmt.kind {
+            StmtKind::Assign { target, ty, value } => {
+                let ExprKind::Ident(name) = &target.kind else {
+                    return vec![walk_stmt(self, stmt)];
+                };
+                let name = name.clone();
+                if let Some(ty) = ty {
+                    self.declared.insert(name.name.clone(), ty.clone());
+                }
+                let (value_span, written) = (value.span, pretty::expr(value));
+                let check = self
+                    .declared
+                    .get(&name.name)
+                    .filter(|_| self.checked(value_span, Clause::Refinement))
+                    .and_then(|ty| {
+                        let cond = self.condition(ty, &ident(&name.name, name.span))?;
+                        let clause = format!("`{written}` is {}", article(&type_expr(ty)));
+                        Some(assertion(cond, clause, self.location(value_span)))
+                    });
+                let mut stmts = vec![walk_stmt(self, stmt)];
+                stmts.extend(check);
+                stmts
+            }
+            StmtKind::Return(value) => {
+                let value_span = value.as_ref().map(|value| value.span);
+                let result = value.as_ref().map(|value| ident("result$", value.span));
+                let checks = self.returns(result.as_ref(), value_span, stmt.span);
+                if checks.is_empty() {
+                    return vec![walk_stmt(self, stmt)];
+                }
+                let Stmt {
+                    kind: StmtKind::Return(value),
+                    span,
+                } = walk_stmt(self, stmt)
+                else {
+                    unreachable!("a return folds to a return")
+                };
+                let mut stmts = Vec::new();
+                if let (Some(value), Some(result)) = (value, &result) {
+                    stmts.push(assign(result.clone(), value, span));
+                }
+                stmts.extend(checks);
+                stmts.push(Stmt {
+                    kind: StmtKind::Return(result),
+                    span,
+                });
+                stmts
+            }
+            StmtKind::While { contracts, .. } | StmtKind::For { contracts, .. } if !contracts.is_empty() => {
+                self.looping(stmt)
+            }
+            _ => vec![walk_stmt(self, stmt)],
+        }
+    }
+
+    /// A loop without its clauses, preceded by the checks of its invariants
+    /// on entry and with the checks of an iteration around its body.
+    fn looping(&mut self, stmt: Stmt) -> Vec<Stmt> {
+        // The clauses are checked as written, not with their calls checked.
+        let (StmtKind::While { contracts, .. } | StmtKind::For { contracts, .. }) = &stmt.kind else {
+            unreachable!("only loops have clauses")
+        };
+        let contracts = contracts.clone();
+        let mut stmt = walk_stmt(self, stmt);
+        let (StmtKind::While {
+            contracts: folded,
+            body,
+            ..
+        }
+        | StmtKind::For {
+            contracts: folded,
+            body,
+            ..
+        }) = &mut stmt.kind
+        else {
+            unreachable!("a loop folds to a loop")
+        };
+        folded.clear();
+        let mut stmts = Vec::new();
+        let mut start = Vec::new();
+        let mut end = Vec::new();
+        for contract in &contracts {
+            let span = contract.span;
+            let location = self.location(span);
+            let written = pretty::expr(&contract.cond);
+            match contract.kind {
+                ContractKind::Invariant => {
+                    if self.checked(span, Clause::Loop(LoopObligation::Initiation)) {
+                        let clause = format!("loop invariant `{written}` holds on entry");
+                        stmts.push(assertion(contract.cond.clone(), clause, location.clone()));
+                    }
+                    if self.checked(span, Clause::Loop(LoopObligation::Preservation)) {
+                        let clause = format!("loop invariant `{written}` is preserved");
+                        end.push(assertion(contract.cond.clone(), clause, location));
+                    }
+                }
+                ContractKind::Decreases if self.checked(span, Clause::Loop(LoopObligation::Termination)) => {
+                    self.measures += 1;
+                    let measure = ident(&format!("measure${}", self.measures), span);
+                    start.push(assign(measure.clone(), contract.cond.clone(), span));
+                    let bounded = binary(BinaryOp::Ge, measure.clone(), int(0, span));
+                    let smaller = binary(BinaryOp::Lt, contract.cond.clone(), measure);
+                    let clause = format!("loop measure `{written}` decreases");
+                    end.push(assertion(binary(BinaryOp::And, bounded, smaller), clause, location));
+                }
+                _ => {}
+            }
+        }
+        body.stmts.splice(0..0, start);
+        body.stmts.extend(end);
+        stmts.push(stmt);
+        stmts
+    }
+
+    /// `f$checked`, which checks what `func` asks of its callers before
+    /// calling it.
+    fn wrapper(&self, func: &Func) -> Func {
+        let span = func.name.span;
+        let caller = ident("caller$", span);
+        let mut stmts = Vec::new();
+        for param in &func.params {
+            if let Some(ty) = &param.ty
+                && let Some(cond) = self.condition(ty, &ident(&param.name.name, param.span))
+            {
+                let clause = format!(
+                    "`{}` requires `{}` to be {}",
+                    func.name.name,
+                    param.name.name,
+                    article(&type_expr(ty))
+                );
+                stmts.push(assertion(cond, clause, caller.clone()));
+            }
+        }
+        for contract in &func.contracts {
+            if contract.kind == ContractKind::Requires {
+                let clause = format!("`{}` requires `{}`", func.name.name, pretty::expr(&contract.cond));
+                stmts.push(assertion(contract.cond.clone(), clause, caller.clone()));
+            }
+        }
+        let args = func
+            .params
+            .iter()
+            .map(|param| ident(&param.name.name, param.span))
+            .collect();
+        stmts.push(Stmt {
+            kind: StmtKind::Return(Some(call(&func.name.name, args, span))),
+            span,
+        });
+        let caller = Param {
+            name: Ident {
+                name: "caller$".to_owned(),
+                span,
+            },
+            ty: Some(TypeExpr {
+                kind: TypeExprKind::Named {
+                    name: Ident {
+                        name: "Str".to_owned(),
+                        span,
+                    },
+                    args: Vec::new(),
+                },
+                span,
+            }),
+            implicit: false,
+            span,
+        };
+        Func {
+            name: Ident {
+                name: format!("{}$checked", func.name.name),
+                span,
+            },
+            params: func.params.iter().cloned().chain([caller]).collect(),
+            ret: func.ret.clone(),
+            contracts: Vec::new(),
+            body: Block {
+                stmts,
+                span: func.body.span,
+            },
+        }
+    }
+
+    /// Whether a call to `callee` with `args` at `span` is checked through
+    /// its wrapper.
+    fn wrap(&self, callee: &Func, args: &[Expr], span: Span) -> bool {
+        let requires = callee
+            .contracts
+            .iter()
+            .any(|contract| contract.kind == ContractKind::Requires)
+            && self.checked(span, Clause::Requires);
+        requires
+            || callee.params.iter().zip(args).any(|(param, arg)| {
+                param.ty.as_ref().is_some_and(|ty| {
+                    self.checked(arg.span, Clause::Refinement)
+                        && self.condition(ty, &ident(&param.name.name, param.span)).is_some()
+                })
+            })
+    }
+}
+
+impl Folder for Lowerer<'_> {
+    fn fold_func(&mut self, func: Func) -> Func {
+        let original = self.functions[func.name.name.as_str()];
+        self.func = Some(original);
+        self.snapshot = false;
+        self.declared = func
+            .params
+            .iter()
+            .filter_map(|param| Some((param.name.name.clone(), param.ty.clone()?)))
+            .collect();
+        let mut assigned = Assigned::default();
+        assigned.visit_block(&func.body);
+        self.locals = func
+            .params
+            .iter()
+            .map(|param| param.name.name.as_str())
+            .chain(assigned.names)
+            .map(str::to_owned)
+            .collect();
+
+        let mut body = self.fold_block(func.body);
+        if falls_through(&original.body) {
+            body.stmts.extend(self.returns(None, None, func.name.span));
+        }
+        if self.snapshot {
+            let entry = self.entry(original);
+            let copies = func.params.iter().filter_map(|param| {
+                let copy = entry.get(&param.name.name)?;
+                Some(assign(copy.clone(), ident(&param.name.name, param.span), param.span))
+            });
+            body.stmts.splice(0..0, copies.collect::<Vec<_>>());
+        }
+        self.func = None;
+        Func {
+            contracts: Vec::new(),
+            body,
+            ..func
+        }
+    }
+
+    fn fold_block(&mut self, block: Block) -> Block {
+        Block {
+            stmts: block.stmts.into_iter().flat_map(|stmt| self.stmt(stmt)).collect(),
+            span: block.span,
+        }
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let mut expr = walk_expr(self, expr);
+        if let ExprKind::Call { callee, args } = &mut expr.kind
+            && let ExprKind::Ident(name) = &mut callee.kind
+            && !self.locals.contains(&name.name)
+            && let Some(function) = self.functions.get(name.name.as_str()).copied()
+            && function.params.len() == args.len()
+            && self.wrap(function, args, expr.span)
+        {
+            self.wrapped.insert(&function.name.name);
+            name.name = format!("{}$checked", name.name);
+            args.push(self.location(expr.span));
+        }
+        expr
+    }
+}
+
+/// `expr` with the names in `names` replaced, and those inside `old(...)`
+/// by the ones in `entry` when given.
+pub fn substitute(expr: Expr, names: &HashMap<String, Expr>, entry: Option<&HashMap<String, Expr>>) -> Expr {
+    let mut substitution = Substitution { names, entry };
+    substitution.fold_expr(expr)
+}
+
+struct Substitution<'a> {
+    names: &'a HashMap<String, Expr>,
+    entry: Option<&'a HashMap<String, Expr>>,
+}
+
+impl Folder for Substitution<'_> {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr.kind {
+            ExprKind::Ident(name) => match self.names.get(&name.name) {
+                Some(replacement) => replacement.clone(),
+                None => Expr {
+                    kind: ExprKind::Ident(name),
+                    span: expr.span,
+                },
+            },
+            ExprKind::Call { callee, mut args }
+                if matches!(&callee.kind, ExprKind::Ident(name) if name.name == "old")
+                    && args.len() == 1
+                    && self.entry.is_some() =>
+            {
+                substitute(args.remove(0), self.entry.unwrap_or(self.names), None)
+            }
+            kind => walk_expr(self, Expr { kind, span: expr.span }),
+        }
+    }
+}
+
+/// `contract$assert(cond, clause, location)`.
+fn assertion(cond: Expr, clause: String, location: Expr) -> Stmt {
+    let span = cond.span;
+    let args = vec![cond, string(clause, span), location];
+    Stmt {
+        kind: StmtKind::Expr(call("contract$assert", args, span)),
+        span,
+    }
+}
+
+fn assign(target: Expr, value: Expr, span: Span) -> Stmt {
+    Stmt {
+        kind: StmtKind::Assign {
+            target,
+            ty: None,
+            value,
+        },
+        span,
+    }
+}
+
+fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+    let span = lhs.span;
+    Expr {
+        kind: ExprKind::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        },
+        span,
+    }
+}
+
+fn call(name: &str, args: Vec<Expr>, span: Span) -> Expr {
+    Expr {
+        kind: ExprKind::Call {
+            callee: Box::new(ident(name, span)),
+            args,
+        },
+        span,
+    }
+}
+
+fn ident(name: &str, span: Span) -> Expr {
+    Expr {
+        kind: ExprKind::Ident(Ident {
+            name: name.to_owned(),
+            span,
+        }),
+        span,
+    }
+}
+
+fn int(value: i64, span: Span) -> Expr {
+    Expr {
+        kind: ExprKind::Lit(Lit::Int(value)),
+        span,
+    }
+}
+
+fn string(text: String, span: Span) -> Expr {
+    Expr {
+        kind: ExprKind::Lit(Lit::Str(text)),
+        span,
+    }
+}
+
+fn unit(span: Span) -> Expr {
+    Expr {
+        kind: ExprKind::Tuple(Vec::new()),
+        span,
+    }
+}
@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Compiling `match` expressions to decision trees.
+//!
+//! The arms are treated as rows of tests on parts of the scrutinee, each
+//! part named by its `Occurrence`. The first row with no tests left matches;
+//! otherwise the compiler switches on a part the first row tests, choosing the
+//! one the most rows test, and carries on with the rows that agree with each
+//! case. No path through the tree tests the same part twice.
+//!
+//! The same pass checks the match: a `Fail` leaf is reached by values no arm
+//! covers, and the tests on the way there describe one of them; an arm that
+//! no leaf leads to can never be chosen.
+
+use crate::ast::Ident;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The variants of every enum, with the number of fields each has.
+pub type Enums = HashMap<String, Vec<(String, usize)>>;
+
+/// A path to part of the scrutinee: the field indices taken from the
+/// scrutinee itself, which is `[]`.
+pub type Occurrence = Vec<usize>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    Variant {
+        enum_name: String,
+        name: String,
+        arity: usize,
+    },
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl Constructor {
+    pub fn arity(&self) -> usize {
+        match self {
+            Constructor::Variant { arity, .. } => *arity,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Constructor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constructor::Variant { name, .. } => write!(f, "{name}"),
+            Constructor::Bool(value) => write!(f, "{value}"),
+            Constructor::Int(value) => write!(f, "{value}"),
+            Constructor::Float(value) => write!(f, "{value:?}"),
+            Constructor::Str(value) => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+/// A pattern with names and types already checked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pat {
+    /// A wildcard, or a binding of the whole value.
+    Any(Option<Ident>),
+    Con(Constructor, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm {
+    pub pattern: Pat,
+    pub guarded: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// No arm matches.
+    Fail,
+    /// Runs `arm` with each name bound to the part of the scrutinee at its
+    /// occurrence.
+    Success {
+        arm: usize,
+        bindings: Vec<(Ident, Occurrence)>,
+    },
+    /// Runs the guard of `arm` with the bindings in place, and continues with
+    /// `otherwise` if it is false.
+    Guard {
+        arm: usize,
+        bindings: Vec<(Ident, Occurrence)>,
+        otherwise: Box<Decision>,
+    },
+    /// Continues with the case for the constructor of the part at
+    /// `occurrence`, or with `default` when none is listed.
+    Switch {
+        occurrence: Occurrence,
+        cases: Vec<(Constructor, Decision)>,
+        default: Option<Box<Decision>>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Compiled {
+    pub tree: Decision,
+    /// Example values no arm matches, as patterns.
+    pub missing: Vec<String>,
+    /// For every arm, whether each of its top-level alternatives can match.
+    pub reachable: Vec<Vec<bool>>,
+}
+
+/// Compiles the arms of a match, in order, into a decision tree.
+pub fn compile(arms: &[Arm], enums: &Enums) -> Compiled {
+    let mut compiler = Compiler {
+        enums,
+        missing: Vec::new(),
+        reachable: Vec::new(),
+    };
+    let mut rows = Vec::new();
+    for (index, arm) in arms.iter().enumerate() {
+        let alternatives = match &arm.pattern {
+            Pat::Or(alternatives) => alternatives.clone(),
+            pattern => vec![pattern.clone()],
+        };
+        compiler.reachable.push(vec![false; alternatives.len()]);
+        for (alternative, pattern) in alternatives.into_iter().enumerate() {
+            rows.push(Row {
+                tests: vec![(Vec::new(), pattern)],
+                bindings: Vec::new(),
+                arm: index,
+                alternative,
+                guarded: arm.guarded,
+            });
+        }
+    }
+    let tree = compiler.compile(rows, &mut Vec::new());
+    Compiled {
+        tree,
+        missing: compiler.missing,
+        reachable: compiler.reachable,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    tests: Vec<(Occurrence, Pat)>,
+    bindings: Vec<(Ident, Occurrence)>,
+    arm: usize,
+    alternative: usize,
+    guarded: bool,
+}
+
+impl Row {
+    fn test(&self, occurrence: &Occurrence) -> Option<&Pat> {
+        self.tests
+            .iter()
+            .find(|(o, _)| o == occurrence)
+            .map(|(_, pattern)| pattern)
+    }
+
+    /// Moves wildcards and bindings out of the tests: they always match.
+    fn simplify(mut self) -> Row {
+        let mut tests = Vec::with_capacity(self.tests.len());
+        for (occurrence, pattern) in self.tests {
+            match pattern {
+                Pat::Any(Some(name)) => self.bindings.push((name, occurrence)),
+                Pat::Any(None) => {}
+                pattern => tests.push((occurrence, pattern)),
+            }
+        }
+        self.tests = tests;
+        self
+    }
+
+    /// Replaces an or-pattern at `occurrence` with one row per alternative.
+    fn expand(self, occurrence: &Occurrence, out: &mut Vec<Row>) {
+        let Some(Pat::Or(alternatives)) = self.test(occurrence) else {
+            out.push(self);
+            return;
+        };
+        for alternative in alternatives.clone() {
+            let mut row = self.clone();
+            for (o, pattern) in &mut row.tests {
+                if o == occurrence {
+                    *pattern = alternative.clone();
+                }
+            }
+            row.expand(occurrence, out);
+        }
+    }
+
+    /// The row as it continues once the part at `occurrence` is known to be
+    /// built by `constructor`, or `None` if the row cannot match then.
+    fn specialize(&self, occurrence: &Occurrence, constructor: &Constructor) -> Option<Row> {
+        let Some(index) = self.tests.iter().position(|(o, _)| o == occurrence) else {
+            return Some(self.clone());
+        };
+        let Pat::Con(found, args) = &self.tests[index].1 else {
+            unreachable!("or-patterns are expanded before specializing");
+        };
+        if found != constructor {
+            return None;
+        }
+        let mut row = self.clone();
+        row.tests.remove(index);
+        for (i, arg) in args.iter().enumerate() {
+            let mut sub = occurrence.clone();
+            sub.push(i);
+            row.tests.push((sub, arg.clone()));
+        }
+        Some(row)
+    }
+}
+
+/// What the path to a node knows about one part of the scrutinee.
+enum Known {
+    Is(Constructor),
+    /// Built by none of these.
+    Not(Vec<Constructor>),
+}
+
+struct Compiler<'e> {
+    enums: &'e Enums,
+    missing: Vec<String>,
+    reachable: Vec<Vec<bool>>,
+}
+
+impl Compiler<'_> {
+    fn compile(&mut self, rows: Vec<Row>, path: &mut Vec<(Occurrence, Known)>) -> Decision {
+        let mut rows: Vec<Row> = rows.into_iter().map(Row::simplify).collect();
+        if rows.is_empty() {
+            let witnesses = self.witnesses(path, &Vec::new());
+            self.missing.extend(witnesses);
+            return Decision::Fail;
+        }
+        if rows[0].tests.is_empty() {
+            let first = rows.remove(0);
+            self.reachable[first.arm][first.alternative] = true;
+            if first.guarded {
+                return Decision::Guard {
+                    arm: first.arm,
+                    bindings: first.bindings,
+                    otherwise: Box::new(self.compile(rows, path)),
+                };
+            }
+            return Decision::Success {
+                arm: first.arm,
+                bindings: first.bindings,
+            };
+        }
+
+        let occurrence = rows[0]
+            .tests
+            .iter()
+            .map(|(occurrence, _)| occurrence)
+            .max_by_key(|occurrence| {
+                // Ties go to the earliest test, so take the reverse position.
+                let tested = rows.iter().filter(|row| row.test(occurrence).is_some()).count();
+                let position = rows[0].tests.iter().position(|(o, _)| o == *occurrence);
+                (tested, std::cmp::Reverse(position))
+            })
+            .cloned()
+            .expect("the first row has a test");
+
+        let mut expanded = Vec::with_capacity(rows.len());
+        for row in rows {
+            row.expand(&occurrence, &mut expanded);
+        }
+        let rows: Vec<Row> = expanded.into_iter().map(Row::simplify).collect();
+
+        let mut constructors: Vec<Constructor> = Vec::new();
+        for row in &rows {
+            if let Some(Pat::Con(constructor, _)) = row.test(&occurrence)
+                && !constructors.contains(constructor)
+            {
+                constructors.push(constructor.clone());
+            }
+        }
+
+        let mut cases = Vec::with_capacity(constructors.len());
+        for constructor in &constructors {
+            let specialized = rows
+                .iter()
+                .filter_map(|row| row.specialize(&occurrence, constructor))
+                .collect();
+            path.push((occurrence.clone(), Known::Is(constructor.clone())));
+            cases.push((constructor.clone(), self.compile(specialized, path)));
+            path.pop();
+        }
+
+        let default = if self.is_complete(&constructors) {
+            None
+        } else {
+            let rest = rows.into_iter().filter(|row| row.test(&occurrence).is_none()).collect();
+            path.push((occurrence.clone(), Known::Not(constructors)));
+            let default = self.compile(rest, path);
+            path.pop();
+            Some(Box::new(default))
+        };
+
+        Decision::Switch {
+            occurrence,
+            cases,
+            default,
+        }
+    }
+
+    /// Whether `constructors` cover every value of their type.
+    fn is_complete(&self, constructors: &[Constructor]) -> bool {
+        match constructors.first() {
+            Some(Constructor::Variant { enum_name, .. }) => self
+                .enums
+                .get(enum_name)
+                .is_some_and(|variants| variants.len() == constructors.len()),
+            Some(Constructor::Bool(_)) => constructors.len() == 2,
+            _ => false,
+        }
+    }
+
+    /// Patterns for the values that reach the end of `path`, as they apply
+    /// to the part at `occurrence`: one for each variant it can still be.
+    fn witnesses(&self, path: &[(Occurrence, Known)], occurrence: &Occurrence) -> Vec<String> {
+        let known = path.iter().rev().find(|(o, _)| o == occurrence).map(|(_, known)| known);
+        match known {
+            Some(Known::Is(constructor)) if constructor.arity() == 0 => vec![constructor.to_string()],
+            Some(Known::Is(constructor)) => {
+                let mut witnesses = vec![Vec::new()];
+                for i in 0..constructor.arity() {
+                    let mut sub = occurrence.clone();
+                    sub.push(i);
+                    let args = self.witnesses(path, &sub);
+                    witnesses = witnesses
+                        .into_iter()
+                        .flat_map(|prefix: Vec<String>| {
+                            args.iter().map(move |arg| {
+                                let mut witness = prefix.clone();
+                                witness.push(arg.clone());
+                                witness
+                            })
+                        })
+                        .collect();
+                }
+                witnesses
+                    .into_iter()
+                    .map(|args| format!("{constructor}({})", args.join(", ")))
+                    .collect()
+            }
+            Some(Known::Not(excluded)) => match excluded.first() {
+                Some(Constructor::Variant { enum_name, .. }) => {
+                    let witnesses: Vec<String> = self
+                        .enums
+                        .get(enum_name)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(name, _)| {
+                            !excluded
+                                .iter()
+                                .any(|c| matches!(c, Constructor::Variant { name: n, .. } if n == name))
+                        })
+                        .map(|(name, arity)| match arity {
+                            0 => name.clone(),
+                            _ => format!("{name}({})", vec!["_"; *arity].join(", ")),
+                        })
+                        .collect();
+                    if witnesses.is_empty() {
+                        vec!["_".to_owned()]
+                    } else {
+                        witnesses
+                    }
+                }
+                Some(Constructor::Bool(value)) => vec![(!value).to_string()],
+                _ => vec!["_".to_owned()],
+            },
+            None => vec!["_".to_owned()],
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Dumping the AST for debugging, to see how a program was parsed.
+//!
+//! The AST is first flattened into generic nodes, each with a kind, a span,
+//! an optional value such as a name or an operator, and its children in
+//! source order. Every format prints those same nodes.
+
+use crate::ast::*;
+use crate::pretty::{literal, type_expr};
+use flerry_core::Span;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// One node per line, children indented under their parent, with spans.
+    Tree,
+    /// S-expressions on one line, without spans.
+    Sexp,
+    /// A JSON object per node.
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "tree" => Some(Format::Tree),
+            "sexp" => Some(Format::Sexp),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Prints `module` in `format`.
+pub fn dump(module: &Module, format: Format) -> String {
+    let node = module_node(module);
+    let mut out = String::new();
+    match format {
+        Format::Tree => tree(&node, 0, &mut out),
+        Format::Sexp => {
+            sexp(&node, &mut out);
+            out.push('\n');
+        }
+        Format::Json => {
+            json(&node, &mut out);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+struct Node {
+    kind: &'static str,
+    span: Span,
+    value: Option<String>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(kind: &'static str, span: Span) -> Node {
+        Node {
+            kind,
+            span,
+            value: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn value(mut self, value: impl Into<String>) -> Node {
+        self.value = Some(value.into());
+        self
+    }
+
+    fn children(mut self, children: impl IntoIterator<Item = Node>) -> Node {
+        self.children.extend(children);
+        self
+    }
+}
+
+fn tree(node: &Node, depth: usize, out: &mut String) {
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(node.kind);
+    if let Some(value) = &node.value {
+        out.push(' ');
+        out.push_str(value);
+    }
+    out.push_str(&format!(" @{}..{}\n", node.span.start, node.span.end));
+    for child in &node.children {
+        tree(child, depth + 1, out);
+    }
+}
+
+fn sexp(node: &Node, out: &mut String) {
+    out.push('(');
+    out.push_str(node.kind);
+    if let Some(value) = &node.value {
+        out.push(' ');
+        out.push_str(value);
+    }
+    for child in &node.children {
+        out.push(' ');
+        sexp(child, out);
+    }
+    out.push(')');
+}
+
+fn json(node: &Node, out: &mut String) {
+    out.push_str(&format!("{{\"kind\":\"{}\"", node.kind));
+    if let Some(value) = &node.value {
+        out.push_str(",\"value\":");
+        json_string(value, out);
+    }
+    out.push_str(&format!(",\"span\":[{},{}]", node.span.start, node.span.end));
+    if !node.children.is_empty() {
+        out.push_str(",\"children\":[");
+        for (i, child) in node.children.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            json(child, out);
+        }
+        out.push(']');
+    }
+    out.push('}');
+}
+
+pub(crate) fn json_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn module_node(module: &Module) -> Node {
+    Node::new("Module", module.span).children(module.items.iter().map(item_node))
+}
+
+fn item_node(item: &Item) -> Node {
+    let node = Node::new(
+        match &item.kind {
+            ItemKind::Import(_) => "Import",
+            ItemKind::Func(_) => "Func",
+            ItemKind::Struct(_) => "Struct",
+            ItemKind::Enum(_) => "Enum",
+            ItemKind::Type(_) => "Type",
+            ItemKind::Error => "Error",
+        },
+        item.span,
+    );
+    match &item.kind {
+        ItemKind::Import(name) => node.value(&name.name),
+        ItemKind::Func(func) => node
+            .value(&func.name.name)
+            .children(func.params.iter().map(param_node))
+            .children(func.ret.iter().map(type_node))
+            .children(func.contracts.iter().map(contract_node))
+            .children([block_node(&func.body)]),
+        ItemKind::Struct(def) => node.value(&def.name.name).children(def.fields.iter().map(field_node)),
+        ItemKind::Enum(def) => node
+            .value(&def.name.name)
+            .children(def.params.iter().map(param_node))
+            .children(def.variants.iter().map(|variant| {
+                Node::new("Variant", variant.span)
+                    .value(&variant.name.name)
+                    .children(variant.fields.iter().map(field_node))
+                    .children(variant.ret.iter().map(type_node))
+            })),
+        ItemKind::Type(alias) => node
+            .value(&alias.name.name)
+            .children(
+                alias
+                    .params
+                    .iter()
+                    .map(|param| Node::new("Param", param.span).value(&param.name)),
+            )
+            .children([type_node(&alias.ty)])
+            .children(
+                alias
+                    .refinement
+                    .iter()
+                    .map(|refinement| Node::new("Where", refinement.span).children([expr_node(refinement)])),
+            ),
+        ItemKind::Error => node,
+    }
+}
+
+fn contract_node(contract: &Contract) -> Node {
+    let kind = match contract.kind {
+        ContractKind::Requires => "Requires",
+        ContractKind::Ensures => "Ensures",
+        ContractKind::Invariant => "Invariant",
+        ContractKind::Decreases => "Decreases",
+    };
+    Node::new(kind, contract.span).children([expr_node(&contract.cond)])
+}
+
+fn param_node(param: &Param) -> Node {
+    Node::new(if param.implicit { "ImplicitParam" } else { "Param" }, param.span)
+        .value(&param.name.name)
+        .children(param.ty.iter().map(type_node))
+}
+
+fn field_node(field: &Field) -> Node {
+    Node::new(if field.implicit { "ImplicitField" } else { "Field" }, field.span)
+        .value(&field.name.name)
+        .children(field.ty.iter().map(type_node))
+}
+
+fn type_node(ty: &TypeExpr) -> Node {
+    Node::new("TypeExpr", ty.span).value(type_expr(ty))
+}
+
+fn block_node(block: &Block) -> Node {
+    Node::new("Block", block.span).children(block.stmts.iter().map(stmt_node))
+}
+
+fn stmt_node(stmt: &Stmt) -> Node {
+    let node = Node::new(
+        match &stmt.kind {
+            StmtKind::Assign { .. } => "Assign",
+            StmtKind::Expr(_) => "ExprStmt",
+            StmtKind::Return(_) => "Return",
+            StmtKind::If { .. } => "If",
+            StmtKind::While { .. } => "While",
+            StmtKind::For { .. } => "For",
+            StmtKind::Error => "Error",
+        },
+        stmt.span,
+    );
+    match &stmt.kind {
+        StmtKind::Assign { target, ty, value } => node
+            .children([expr_node(target)])
+            .children(ty.iter().map(type_node))
+            .children([expr_node(value)]),
+        StmtKind::Expr(expr) | StmtKind::Return(Some(expr)) => node.children([expr_node(expr)]),
+        StmtKind::If { branches, else_block } => node
+            .children(branches.iter().map(|(cond, block)| {
+                Node::new("Branch", cond.span.to(block.span)).children([expr_node(cond), block_node(block)])
+            }))
+            .children(
+                else_block
+                    .iter()
+                    .map(|block| Node::new("Else", block.span).children([block_node(block)])),
+            ),
+        StmtKind::While { cond, contracts, body } => node
+            .children([expr_node(cond)])
+            .children(contracts.iter().map(contract_node))
+            .children([block_node(body)]),
+        StmtKind::For {
+            var,
+            iter,
+            contracts,
+            body,
+        } => node
+            .value(&var.name)
+            .children([expr_node(iter)])
+            .children(contracts.iter().map(contract_node))
+            .children([block_node(body)]),
+        StmtKind::Return(None) | StmtKind::Error => node,
+    }
+}
+
+fn expr_node(expr: &Expr) -> Node {
+    let node = Node::new(
+        match &expr.kind {
+            ExprKind::Lit(_) => "Lit",
+            ExprKind::Ident(_) => "Ident",
+            ExprKind::List(_) => "List",
+            ExprKind::Tuple(_) => "Tuple",
+            ExprKind::Unary { .. } => "Unary",
+            ExprKind::Binary { .. } => "Binary",
+            ExprKind::Call { .. } => "Call",
+            ExprKind::Field { .. } => "Field",
+            ExprKind::Index { .. } => "Index",
+            ExprKind::Match { .. } => "Match",
+            ExprKind::Struct { .. } => "StructLit",
+            ExprKind::Lambda { .. } => "Lambda",
+            ExprKind::Closure { .. } => "Closure",
+            ExprKind::Hole(_) => "Hole",
+            ExprKind::Error => "Error",
+        },
+        expr.span,
+    );
+    match &expr.kind {
+        ExprKind::Lit(lit) => node.value(literal(lit)),
+        ExprKind::Ident(name) => node.value(&name.name),
+        ExprKind::List(items) | ExprKind::Tuple(items) => node.children(items.iter().map(expr_node)),
+        ExprKind::Unary { op, expr } => node.value(op.symbol()).children([expr_node(expr)]),
+        ExprKind::Binary { op, lhs, rhs } => node.value(op.symbol()).children([expr_node(lhs), expr_node(rhs)]),
+        ExprKind::Call { callee, args } => node.children([expr_node(callee)]).children(args.iter().map(expr_node)),
+        ExprKind::Field { expr, field } => node.value(&field.name).children([expr_node(expr)]),
+        ExprKind::Index { expr, index } => node.children([expr_node(expr), expr_node(index)]),
+        ExprKind::Match { scrutinee, arms } => node
+            .children([expr_node(scrutinee)])
+            .children(arms.iter().map(arm_node)),
+        ExprKind::Struct { name, fields, base } => node
+            .value(&name.name)
+            .children(fields.iter().map(|init| {
+                Node::new("FieldInit", init.span)
+                    .value(&init.name.name)
+                    .children([expr_node(&init.value)])
+            }))
+            .children(
+                base.iter()
+                    .map(|base| Node::new("Base", base.span).children([expr_node(base)])),
+            ),
+        ExprKind::Lambda { params, body } => node.children(params.iter().map(param_node)).children([expr_node(body)]),
+        ExprKind::Closure { func, captures } => node.value(&func.name).children(captures.iter().map(|capture| {
+            let value = match capture.mode {
+                CaptureMode::Value => capture.name.name.clone(),
+                CaptureMode::Ref => format!("ref {}", capture.name.name),
+            };
+            Node::new("Capture", capture.name.span).value(value)
+        })),
+        ExprKind::Hole(Some(name)) => node.value(&name.name),
+        ExprKind::Hole(None) | ExprKind::Error => node,
+    }
+}
+
+fn arm_node(arm: &MatchArm) -> Node {
+    Node::new("Arm", arm.span)
+        .children([pattern_node(&arm.pattern)])
+        .children(
+            arm.guard
+                .iter()
+                .map(|guard| Node::new("Guard", guard.span).children([expr_node(guard)])),
+        )
+        .children([expr_node(&arm.body)])
+}
+
+fn pattern_node(pattern: &Pattern) -> Node {
+    match &pattern.kind {
+        PatternKind::Wildcard => Node::new("WildcardPat", pattern.span),
+        PatternKind::Binding(name) => Node::new("BindingPat", pattern.span).value(&name.name),
+        PatternKind::Lit(lit) => Node::new("LitPat", pattern.span).value(literal(lit)),
+        PatternKind::Variant { name, args } => Node::new("VariantPat", pattern.span)
+            .value(&name.name)
+            .children(args.iter().map(pattern_node)),
+        PatternKind::Error => Node::new("Error", pattern.span),
+        PatternKind::Or(alternatives) => {
+            Node::new("OrPat", pattern.span).children(alternatives.iter().map(pattern_node))
+        }
+    }
+}
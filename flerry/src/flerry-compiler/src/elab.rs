@@ -0,0 +1,2180 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Dependent types: inductive families and the functions that use them,
+//! checked by elaborating them into the core calculus of `kernel`.
+//!
+//! An enum is a family when it takes arguments, gives its variants the type
+//! they build, or has a field whose type is dependent, and a function is
+//! checked here when a parameter or its result has such a type: one that
+//! mentions a family, a universe or a value, or when it uses a family or
+//! such a function. Everything else is left to inference, `Nat` included, which is then an `Int` the verifier proves is
+//! never negative. A function inference checks whose parameters and result
+//! are all annotated with types the core knows is checked here as well, if
+//! quietly, so the functions checked here can call it: its body unfolds
+//! where the core accepts it, and where not the call is left as it is.
+//!
+//! A body is assignments to names, each a `let` around the rest, then a
+//! `return`. A function whose type is checked here may be used as a proof,
+//! so it must terminate: its recursive calls must all pass, for one
+//! parameter, a field of that parameter taken apart by a `match`.
+//!
+//! Checking is bidirectional: an expression is checked against its type
+//! where that is known, and its type is inferred from it where not. A match
+//! on a family refines the types in each arm by unifying the indices of the
+//! variant with those of the value matched, so in the arm for `Cons` of a
+//! `Vec(a, n)` the `n` is known to be `k + 1`, and variants whose indices
+//! cannot be unified with the value's need no arm at all.
+//!
+//! A parameter written `{n: Nat}` is implicit: calls leave it out, and a
+//! metavariable stands in for it until unifying the types of the other
+//! arguments and of the result solves it (see `unify`). `_` asks for the
+//! same for an explicit argument. A hole, `?` or `?name`, is a metavariable
+//! left for the programmer instead: its goal is reported once the rest of
+//! the declaration has been checked (see `holes`).
+//!
+//! Universes form a hierarchy, `Type : Type1 : Type2 : ...`, as a universe
+//! that contained itself would make every type inhabited (Girard's paradox).
+//! A family lives in the smallest universe that holds all its fields, and
+//! may only use itself in a field strictly positively, never to the left of
+//! an arrow.
+
+use crate::adt::{non_exhaustive, plural};
+use crate::ast::*;
+use crate::holes::{self, Goal};
+use crate::kernel::{self, Arm, Global, Globals, Neutral, Term, Value, universe_level};
+use crate::lint::UNREACHABLE_PATTERNS;
+use crate::types::fixed_width;
+use crate::unify::{self, Problem};
+use crate::visit::Visitor;
+use flerry_core::{Diagnostic, Span};
+use std::collections::{HashMap, HashSet};
+
+/// The built-in types of the core, which it does not look inside.
+const BASE_TYPES: &[&str] = &["Int", "Float", "Str", "Bool"];
+
+/// The families of a module and the functions checked along with them.
+pub struct Dependent<'m> {
+    pub families: HashSet<&'m str>,
+    pub functions: HashSet<&'m str>,
+}
+
+impl<'m> Dependent<'m> {
+    pub fn new(module: &'m Module) -> Dependent<'m> {
+        let refinements: HashSet<&str> = module
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Type(alias) if alias.refinement.is_some() => Some(alias.name.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut families = HashSet::new();
+        // An enum with a field of a family's type is a family too.
+        loop {
+            let before = families.len();
+            for item in &module.items {
+                if let ItemKind::Enum(def) = &item.kind
+                    && (!def.params.is_empty()
+                        || def.variants.iter().any(|variant| {
+                            variant.ret.is_some()
+                                || variant.fields.iter().any(|field| field.implicit)
+                                || variant
+                                    .fields
+                                    .iter()
+                                    .filter_map(|field| field.ty.as_ref())
+                                    .any(|ty| depends(ty, &families, &refinements))
+                        }))
+                {
+                    families.insert(def.name.name.as_str());
+                }
+            }
+            if families.len() == before {
+                break;
+            }
+        }
+        let mut functions: HashSet<&str> = module
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Func(func)
+                    if func.params.iter().any(|param| param.implicit)
+                        || func
+                            .params
+                            .iter()
+                            .filter_map(|param| param.ty.as_ref())
+                            .chain(&func.ret)
+                            .any(|ty| depends(ty, &families, &refinements)) =>
+                {
+                    Some(func.name.name.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        // Inference cannot see into families and the functions using them,
+        // so a function that uses one is checked here too.
+        let mut core: HashSet<&str> = families.clone();
+        for item in &module.items {
+            if let ItemKind::Enum(def) = &item.kind
+                && families.contains(def.name.name.as_str())
+            {
+                core.extend(def.variants.iter().map(|variant| variant.name.name.as_str()));
+            }
+        }
+        loop {
+            let before = functions.len();
+            for item in &module.items {
+                if let ItemKind::Func(func) = &item.kind
+                    && !functions.contains(func.name.name.as_str())
+                    && names(func)
+                        .iter()
+                        .any(|name| core.contains(name) || functions.contains(name))
+                {
+                    functions.insert(func.name.name.as_str());
+                }
+            }
+            if functions.len() == before {
+                break;
+            }
+        }
+        Dependent { families, functions }
+    }
+}
+
+/// The names `func` mentions.
+fn names(func: &Func) -> HashSet<&str> {
+    struct Names<'m>(HashSet<&'m str>);
+    impl<'m> Visitor<'m> for Names<'m> {
+        fn visit_ident(&mut self, ident: &'m Ident) {
+            self.0.insert(&ident.name);
+        }
+    }
+    let mut names = Names(HashSet::new());
+    names.visit_func(func);
+    names.0
+}
+
+/// Whether `ty` needs the core calculus to be checked. The arguments of a
+/// refinement type are only for its condition.
+fn depends(ty: &TypeExpr, families: &HashSet<&str>, refinements: &HashSet<&str>) -> bool {
+    match &ty.kind {
+        TypeExprKind::Named { name, args } => {
+            universe_level(&name.name).is_some()
+                || families.contains(name.name.as_str())
+                || (!refinements.contains(name.name.as_str())
+                    && args.iter().any(|arg| depends(arg, families, refinements)))
+        }
+        TypeExprKind::Function { params, ret } => {
+            params.iter().any(|param| depends(param, families, refinements)) || depends(ret, families, refinements)
+        }
+        TypeExprKind::Tuple(elements) => elements.iter().any(|ty| depends(ty, families, refinements)),
+        TypeExprKind::Bind { .. } | TypeExprKind::Expr(_) => true,
+    }
+}
+
+/// An error that has already been reported.
+struct Reported;
+
+type Checked<T> = Result<T, Reported>;
+
+/// The variables in scope, with their types and values.
+#[derive(Clone, Default)]
+struct Ctx {
+    names: Vec<String>,
+    /// The type of each variable, over the variables before it.
+    types: Vec<Term>,
+    /// The value of each variable: the variable itself, or what matching has
+    /// shown it to be.
+    env: Vec<Value>,
+    /// Whether each variable is an implicit field bound by a match, which
+    /// the arm cannot name.
+    hidden: Vec<bool>,
+}
+
+impl Ctx {
+    fn level(&self) -> usize {
+        self.names.len()
+    }
+
+    fn bind(&mut self, globals: &Globals, name: &str, ty: &Value) {
+        self.define(globals, name, ty, Value::var(self.level()));
+    }
+
+    fn define(&mut self, globals: &Globals, name: &str, ty: &Value, value: Value) {
+        self.types.push(globals.quote(self.level(), ty));
+        self.env.push(value);
+        self.names.push(name.to_owned());
+        self.hidden.push(false);
+    }
+
+    fn truncate(&mut self, level: usize) {
+        self.names.truncate(level);
+        self.types.truncate(level);
+        self.env.truncate(level);
+        self.hidden.truncate(level);
+    }
+
+    fn lookup(&self, globals: &Globals, name: &str) -> Option<(Term, Value)> {
+        let i = (0..self.level()).rposition(|i| self.names[i] == name && name != "_" && !self.hidden[i])?;
+        Some((
+            Term::Var(self.level() - 1 - i),
+            globals.eval(&self.types[i], &self.env[..i]),
+        ))
+    }
+
+    fn eval(&self, globals: &Globals, term: &Term) -> Value {
+        globals.eval(term, &self.env)
+    }
+
+    fn show(&self, globals: &Globals, value: &Value) -> String {
+        kernel::show(&globals.quote(self.level(), value), &self.names)
+    }
+
+    /// The variables a new metavariable is applied to: those matching has
+    /// not turned into something else, each once.
+    fn vars(&self) -> Vec<Term> {
+        let mut seen = Vec::new();
+        let mut vars = Vec::new();
+        for (i, value) in self.env.iter().enumerate() {
+            if let Value::Neutral(Neutral::Var(var)) = value
+                && !seen.contains(var)
+            {
+                seen.push(*var);
+                vars.push(Term::Var(self.level() - 1 - i));
+            }
+        }
+        vars
+    }
+
+    /// The variables in scope and their types, as `a : Type, n : Nat`.
+    fn describe(&self, globals: &Globals) -> String {
+        let entries: Vec<String> = self
+            .entries(globals)
+            .into_iter()
+            .map(|(name, ty)| format!("{name} : {ty}"))
+            .collect();
+        entries.join(", ")
+    }
+
+    /// Each variable in scope with its type, outermost first.
+    fn entries(&self, globals: &Globals) -> Vec<(String, String)> {
+        (0..self.level())
+            .filter(|&i| self.names[i] != "_")
+            .map(|i| {
+                // Matching may have made it mention variables bound after it.
+                (self.names[i].clone(), self.show(globals, &self.type_of(globals, i)))
+            })
+            .collect()
+    }
+
+    /// The type of the variable at `level`.
+    fn type_of(&self, globals: &Globals, level: usize) -> Value {
+        globals.eval(&self.types[level], &self.env[..level])
+    }
+}
+
+/// What unifying the indices of a variant with those of the value matched
+/// shows.
+enum Refinement {
+    /// The variant can build the value, in the context given.
+    Possible(Ctx),
+    /// The variant never builds a value of this type.
+    Impossible,
+    /// Unification cannot tell, stuck on these two values.
+    Stuck(String, String),
+}
+
+enum Unified {
+    Same,
+    Conflict,
+    Stuck(Value, Value),
+}
+
+/// The argument of a family or a call, written in a type or an expression.
+#[derive(Copy, Clone)]
+enum Arg<'m> {
+    Type(&'m TypeExpr),
+    Expr(&'m Expr),
+}
+
+/// A metavariable standing for an argument left out, and where.
+struct Implicit {
+    meta: usize,
+    /// The parameter it stands for, or `_`.
+    name: String,
+    /// The function or variant it is an argument of.
+    callee: Option<String>,
+    span: Span,
+    ctx: Ctx,
+    ty: Value,
+}
+
+/// A hole, with the context it is in and the type it must have.
+struct Hole {
+    name: Option<String>,
+    span: Span,
+    ctx: Ctx,
+    ty: Value,
+}
+
+/// A problem unification postponed, with what to report if it fails.
+struct Postponed {
+    problem: Problem,
+    ctx: Ctx,
+    span: Span,
+    found: Value,
+    expected: Value,
+}
+
+struct Elab<'m> {
+    globals: Globals,
+    /// The metavariables made in the declaration being checked.
+    implicits: Vec<Implicit>,
+    postponed: Vec<Postponed>,
+    /// The holes in the declaration being checked.
+    holes: Vec<Hole>,
+    goals: Vec<Goal>,
+    aliases: HashMap<&'m str, &'m TypeAlias>,
+    /// Aliases being expanded, to stop at one that expands into itself.
+    expanding: Vec<&'m str>,
+    /// Names inference checks, which have no type in the core.
+    inferred: HashSet<&'m str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The goal of each hole in the declarations checked here, and what was
+/// wrong with them.
+pub struct Elaboration {
+    pub goals: Vec<Goal>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub fn check(module: &Module) -> Vec<Diagnostic> {
+    elaborate(module).diagnostics
+}
+
+pub fn elaborate(module: &Module) -> Elaboration {
+    let dependent = Dependent::new(module);
+    let mut elab = Elab {
+        globals: Globals::default(),
+        implicits: Vec::new(),
+        postponed: Vec::new(),
+        holes: Vec::new(),
+        goals: Vec::new(),
+        aliases: HashMap::new(),
+        expanding: Vec::new(),
+        inferred: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Func(func) if !dependent.functions.contains(func.name.name.as_str()) => {
+                elab.inferred.insert(&func.name.name);
+            }
+            ItemKind::Enum(def) if !dependent.families.contains(def.name.name.as_str()) => {
+                elab.inferred.insert(&def.name.name);
+                elab.inferred
+                    .extend(def.variants.iter().map(|variant| variant.name.name.as_str()));
+            }
+            ItemKind::Struct(def) => {
+                elab.inferred.insert(&def.name.name);
+            }
+            ItemKind::Type(alias) => {
+                elab.aliases.insert(&alias.name.name, alias);
+            }
+            _ => {}
+        }
+    }
+
+    for item in &module.items {
+        if let ItemKind::Enum(def) = &item.kind
+            && dependent.families.contains(def.name.name.as_str())
+        {
+            let _ = elab.declaration(|elab| elab.family(def));
+        }
+    }
+    let mut signatures = Vec::new();
+    for item in &module.items {
+        let ItemKind::Func(func) = &item.kind else {
+            continue;
+        };
+        if dependent.functions.contains(func.name.name.as_str()) {
+            if let Ok(ty) = elab.declaration(|elab| elab.signature(func)) {
+                signatures.push((func, ty));
+            }
+        } else if func.params.iter().all(|param| param.ty.is_some())
+            && func.ret.is_some()
+            && let Ok(ty) = elab.quietly(|elab| elab.signature(func))
+        {
+            elab.inferred.remove(func.name.name.as_str());
+            signatures.push((func, ty));
+        }
+    }
+    let mut checked = Vec::new();
+    for (func, ty) in signatures {
+        let dependent = dependent.functions.contains(func.name.name.as_str());
+        let body = match dependent {
+            true => elab.declaration(|elab| elab.body(func, &ty)),
+            false => elab.quietly(|elab| elab.body(func, &ty)),
+        };
+        match body {
+            Ok(()) => checked.push((func, dependent)),
+            Err(Reported) => elab.globals.undefine(&func.name.name),
+        }
+    }
+    elab.terminates(&checked);
+    Elaboration {
+        goals: elab.goals,
+        diagnostics: elab.diagnostics,
+    }
+}
+
+/// How a variable relates to the parameters of the function it is in.
+#[derive(Copy, Clone, PartialEq)]
+enum Size {
+    /// The parameter at this position.
+    Param(usize),
+    /// A field of the parameter at this position, or of one of its fields.
+    Part(usize),
+    Other,
+}
+
+/// A call from one of the functions checked for termination to another,
+/// with how each argument relates to the caller's parameters.
+struct RecursiveCall {
+    caller: String,
+    callee: String,
+    args: Vec<Size>,
+}
+
+/// Adds the calls in `term` to the functions `names` to `found`, where
+/// `sizes` holds what each variable in scope is, outermost first. A
+/// function used but not called counts as a call with no arguments.
+fn recursive_calls(
+    caller: &str,
+    term: &Term,
+    sizes: &mut Vec<Size>,
+    names: &HashSet<&str>,
+    found: &mut Vec<RecursiveCall>,
+) {
+    let size = |term: &Term, sizes: &[Size]| match term {
+        Term::Var(index) => sizes[sizes.len() - 1 - index],
+        _ => Size::Other,
+    };
+    let under = |term: &Term, bound: &[Size], sizes: &mut Vec<Size>, found: &mut Vec<RecursiveCall>| {
+        sizes.extend(bound);
+        recursive_calls(caller, term, sizes, names, found);
+        sizes.truncate(sizes.len() - bound.len());
+    };
+    match term {
+        Term::App(..) | Term::Global(_) => {
+            let mut args = Vec::new();
+            let mut head = term;
+            while let Term::App(f, arg) = head {
+                args.push(&**arg);
+                head = f;
+            }
+            args.reverse();
+            match head {
+                Term::Global(name) if names.contains(name.as_str()) => found.push(RecursiveCall {
+                    caller: caller.to_owned(),
+                    callee: name.clone(),
+                    args: args.iter().map(|arg| size(arg, sizes)).collect(),
+                }),
+                Term::Global(_) => {}
+                head => recursive_calls(caller, head, sizes, names, found),
+            }
+            for arg in args {
+                recursive_calls(caller, arg, sizes, names, found);
+            }
+        }
+        Term::Var(_)
+        | Term::Universe(_)
+        | Term::Base(_)
+        | Term::Lit(_)
+        | Term::Nat
+        | Term::NatLit(_)
+        | Term::Meta(..) => {}
+        Term::Pi(_, a, b) | Term::Sigma(_, a, b) => {
+            recursive_calls(caller, a, sizes, names, found);
+            under(b, &[Size::Other], sizes, found);
+        }
+        Term::Lam(_, body) => under(body, &[Size::Other], sizes, found),
+        Term::Let(_, value, body) => {
+            recursive_calls(caller, value, sizes, names, found);
+            let bound = size(value, sizes);
+            under(body, &[bound], sizes, found);
+        }
+        Term::Pair(a, b) | Term::Add(a, b) | Term::Op(_, a, b) => {
+            recursive_calls(caller, a, sizes, names, found);
+            recursive_calls(caller, b, sizes, names, found);
+        }
+        Term::First(pair) | Term::Second(pair) => recursive_calls(caller, pair, sizes, names, found),
+        Term::Case(scrutinee, arms, default) => {
+            recursive_calls(caller, scrutinee, sizes, names, found);
+            let matched = size(scrutinee, sizes);
+            let field = match matched {
+                Size::Param(param) | Size::Part(param) => Size::Part(param),
+                Size::Other => Size::Other,
+            };
+            for arm in arms {
+                under(&arm.body, &vec![field; arm.fields.len()], sizes, found);
+            }
+            if let Some((_, body)) = default {
+                under(body, &[matched], sizes, found);
+            }
+        }
+    }
+}
+
+/// Whether every call in `calls` passes the parameter at `param`, or a
+/// part of it, in its place, and every cycle of calls passes a part of it
+/// at least once.
+fn decreases(calls: &[&RecursiveCall], param: usize) -> bool {
+    if !calls
+        .iter()
+        .all(|call| matches!(call.args.get(param), Some(Size::Param(p) | Size::Part(p)) if *p == param))
+    {
+        return false;
+    }
+    // The calls passing the parameter itself must not form a cycle.
+    let same: Vec<(&str, &str)> = calls
+        .iter()
+        .filter(|call| call.args[param] == Size::Param(param))
+        .map(|call| (call.caller.as_str(), call.callee.as_str()))
+        .collect();
+    let mut callers: HashSet<&str> = same.iter().map(|(caller, _)| *caller).collect();
+    // A function that calls none of those left along these calls is on no
+    // cycle of them.
+    loop {
+        let left: HashSet<&str> = callers
+            .iter()
+            .copied()
+            .filter(|caller| same.iter().any(|(from, to)| from == caller && callers.contains(to)))
+            .collect();
+        if left.len() == callers.len() {
+            return left.is_empty();
+        }
+        callers = left;
+    }
+}
+
+/// `(x: A) -> ... -> body` for each of the `binders`.
+fn pi(binders: &[(String, Term)], body: Term) -> Term {
+    binders.iter().rev().fold(body, |body, (name, ty)| {
+        Term::Pi(name.clone(), Box::new(ty.clone()), Box::new(body))
+    })
+}
+
+/// The number of arguments a term of type `ty` takes before it is no
+/// longer a function, less those that are implicit.
+fn explicit_arity(ty: &Term, implicit: &[bool]) -> usize {
+    arity(ty) - implicit.iter().filter(|implicit| **implicit).count()
+}
+
+fn arity(ty: &Term) -> usize {
+    let mut arity = 0;
+    let mut ty = ty;
+    while let Term::Pi(_, _, body) = ty {
+        arity += 1;
+        ty = body;
+    }
+    arity
+}
+
+/// How many of a family's arguments are parameters: those, from the first,
+/// that every variant passes through unchanged.
+fn uniform_params(def: &EnumDef) -> usize {
+    (0..def.params.len())
+        .take_while(|&i| {
+            def.variants
+                .iter()
+                .all(|variant| match variant.ret.as_ref().map(|ret| &ret.kind) {
+                    None => true,
+                    Some(TypeExprKind::Named { name, args }) => {
+                        name.name == def.name.name
+                            && matches!(
+                                args.get(i).map(|arg| &arg.kind),
+                                Some(TypeExprKind::Named { name, args })
+                                    if args.is_empty() && name.name == def.params[i].name.name
+                            )
+                    }
+                    Some(_) => false,
+                })
+        })
+        .count()
+}
+
+/// Where `family` occurs in `ty`.
+fn occurrence(ty: &TypeExpr, family: &str) -> Option<Span> {
+    match &ty.kind {
+        TypeExprKind::Named { name, args } => {
+            if name.name == family {
+                return Some(name.span);
+            }
+            args.iter().find_map(|arg| occurrence(arg, family))
+        }
+        TypeExprKind::Function { params, ret } => params.iter().chain([&**ret]).find_map(|ty| occurrence(ty, family)),
+        TypeExprKind::Tuple(elements) => elements.iter().find_map(|ty| occurrence(ty, family)),
+        TypeExprKind::Bind { ty, .. } => occurrence(ty, family),
+        TypeExprKind::Expr(_) => None,
+    }
+}
+
+/// Where `family` occurs in the type of one of its fields other than as
+/// the whole type, or the result of a function, and whether that is to the
+/// left of an arrow.
+fn non_positive(ty: &TypeExpr, family: &str) -> Option<(Span, bool)> {
+    match &ty.kind {
+        TypeExprKind::Named { args, .. } => args
+            .iter()
+            .find_map(|arg| occurrence(arg, family))
+            .map(|span| (span, false)),
+        TypeExprKind::Function { params, ret } => params
+            .iter()
+            .find_map(|param| occurrence(param, family))
+            .map(|span| (span, true))
+            .or_else(|| non_positive(ret, family)),
+        TypeExprKind::Tuple(elements) => elements.iter().find_map(|ty| non_positive(ty, family)),
+        TypeExprKind::Bind { ty, .. } => non_positive(ty, family),
+        TypeExprKind::Expr(_) => None,
+    }
+}
+
+/// A value no unification can take apart, so two different ones conflict.
+fn rigid(value: &Value) -> bool {
+    match value {
+        Value::Sum(_, atoms) => atoms.is_empty(),
+        Value::Neutral(_) => false,
+        _ => true,
+    }
+}
+
+impl<'m> Elab<'m> {
+    fn report(&mut self, diagnostic: Diagnostic) -> Reported {
+        self.diagnostics.push(diagnostic);
+        Reported
+    }
+
+    fn unsupported(&mut self, span: Span, what: &str) -> Reported {
+        self.report(
+            Diagnostic::error(
+                format!("{what} cannot be used in a function with a dependent type"),
+                span,
+            )
+            .with_label(span, "not part of the core calculus"),
+        )
+    }
+
+    // Declarations
+
+    /// Checks a declaration, then reports the arguments left out of it that
+    /// unification could not work out.
+    fn declaration<T>(&mut self, check: impl FnOnce(&mut Self) -> Checked<T>) -> Checked<T> {
+        let errors = self.diagnostics.len();
+        let result = check(self);
+        self.retry();
+        let postponed = std::mem::take(&mut self.postponed);
+        let implicits = std::mem::take(&mut self.implicits);
+        // An argument a goal mentions is left for whoever fills the hole in.
+        let mut in_goals = Vec::new();
+        for hole in std::mem::take(&mut self.holes) {
+            in_goals.extend(unify::metas(&self.globals.quote(hole.ctx.level(), &hole.ty)));
+            let goal = self.goal(hole);
+            self.goals.push(goal);
+        }
+        // Arguments left unsolved by an error already reported add nothing.
+        if result.is_err() || self.diagnostics.len() > errors {
+            return result;
+        }
+        // What is left of each postponed problem mentions an unsolved
+        // metavariable, so it is reported with that.
+        let mut reported = Vec::new();
+        for implicit in implicits {
+            if self.globals.solution(implicit.meta).is_some()
+                || reported.contains(&implicit.span)
+                || in_goals.contains(&implicit.meta)
+            {
+                continue;
+            }
+            reported.push(implicit.span);
+            let stuck = postponed.iter().find(|postponed| {
+                let Problem { level, a, b } = &postponed.problem;
+                [a, b]
+                    .iter()
+                    .any(|value| unify::metas(&self.globals.quote(*level, value)).contains(&implicit.meta))
+            });
+            let mut diagnostic = self.unsolved(&implicit);
+            if let Some(stuck) = stuck {
+                diagnostic = diagnostic.with_note(format!("unification is stuck on `{}`", self.problem(stuck)));
+            }
+            self.diagnostics.push(diagnostic);
+        }
+        match self.diagnostics.len() > errors {
+            true => Err(Reported),
+            false => result,
+        }
+    }
+
+    /// Checks a declaration inference checks as well, forgetting whatever
+    /// the core makes of it if it does not accept it.
+    fn quietly<T>(&mut self, check: impl FnOnce(&mut Self) -> Checked<T>) -> Checked<T> {
+        let (diagnostics, goals) = (self.diagnostics.len(), self.goals.len());
+        let result = self.declaration(check);
+        let accepted = self.diagnostics.len() == diagnostics && self.goals.len() == goals;
+        self.diagnostics.truncate(diagnostics);
+        self.goals.truncate(goals);
+        match accepted {
+            true => result,
+            false => Err(Reported),
+        }
+    }
+
+    /// Reports each group of functions checked here that call one another
+    /// and cannot be shown to terminate, and keeps calls to those inference
+    /// checks from unfolding.
+    fn terminates(&mut self, checked: &[(&'m Func, bool)]) {
+        let names: HashSet<&str> = checked.iter().map(|(func, _)| func.name.name.as_str()).collect();
+        let mut calls = HashMap::new();
+        for (func, _) in checked {
+            let mut found = Vec::new();
+            if let Some(body) = self.globals.body(&func.name.name) {
+                let mut sizes = (0..func.params.len()).map(Size::Param).collect();
+                recursive_calls(&func.name.name, body, &mut sizes, &names, &mut found);
+            }
+            calls.insert(func.name.name.as_str(), found);
+        }
+        let reaches = |from: &str| {
+            let mut seen = HashSet::new();
+            let mut stack = vec![from];
+            while let Some(name) = stack.pop() {
+                for call in &calls[name] {
+                    if seen.insert(call.callee.as_str()) {
+                        stack.push(call.callee.as_str());
+                    }
+                }
+            }
+            seen
+        };
+        let reached: HashMap<&str, HashSet<&str>> = names.iter().map(|name| (*name, reaches(name))).collect();
+        let mut done = HashSet::new();
+        for (func, _) in checked {
+            let name = func.name.name.as_str();
+            if done.contains(name) || !reached[name].contains(name) {
+                continue;
+            }
+            // The functions that call one another, in the order declared.
+            let group: Vec<&(&Func, bool)> = checked
+                .iter()
+                .filter(|(other, _)| {
+                    let other = other.name.name.as_str();
+                    reached[name].contains(other) && reached[other].contains(name)
+                })
+                .collect();
+            done.extend(group.iter().map(|(func, _)| func.name.name.as_str()));
+            let within: Vec<&RecursiveCall> = group
+                .iter()
+                .flat_map(|(func, _)| &calls[func.name.name.as_str()])
+                .filter(|call| group.iter().any(|(func, _)| func.name.name == call.callee))
+                .collect();
+            let params = group.iter().map(|(func, _)| func.params.len()).min().unwrap_or(0);
+            if (0..params).any(|param| decreases(&within, param)) {
+                continue;
+            }
+            for (func, _) in &group {
+                self.globals.undefine(&func.name.name);
+            }
+            if let Some((func, _)) = group.iter().find(|(_, dependent)| *dependent) {
+                let span = func.name.span;
+                self.diagnostics.push(
+                    Diagnostic::error(format!("cannot show that `{}` terminates", func.name.name), span)
+                        .with_label(span, "no parameter is passed a smaller value in every recursive call")
+                        .with_note(
+                            "a function with a dependent type is a proof of its type, which one that never \
+                             returns would prove whatever it claims",
+                        )
+                        .with_help(
+                            "take a parameter apart with `match` and pass one of its fields in its place in \
+                             each recursive call",
+                        ),
+                );
+            }
+        }
+    }
+
+    /// A postponed problem as `a = b`, in the context it arose in.
+    fn problem(&self, postponed: &Postponed) -> String {
+        let Problem { level, a, b } = &postponed.problem;
+        let mut names = postponed.ctx.names.clone();
+        names.resize(*level, "_".to_owned());
+        let a = kernel::show(&self.globals.quote(*level, a), &names);
+        let b = kernel::show(&self.globals.quote(*level, b), &names);
+        format!("{a} = {b}")
+    }
+
+    fn unsolved(&self, implicit: &Implicit) -> Diagnostic {
+        let (message, label) = match (&implicit.callee, implicit.name.as_str()) {
+            (_, "_") => (
+                "could not infer this argument".to_owned(),
+                "cannot infer the value of this `_`".to_owned(),
+            ),
+            (Some(callee), name) => (
+                format!("could not infer implicit argument `{name}`"),
+                format!("cannot infer `{name}` in this use of `{callee}`"),
+            ),
+            (None, name) => (
+                format!("could not infer implicit argument `{name}`"),
+                format!("cannot infer `{name}`"),
+            ),
+        };
+        let ty = implicit.ctx.show(&self.globals, &implicit.ty);
+        let mut diagnostic = Diagnostic::error(message, implicit.span)
+            .with_label(implicit.span, label)
+            .with_note(format!("the goal is `?{} : {ty}`", implicit.name));
+        let context = implicit.ctx.describe(&self.globals);
+        if !context.is_empty() {
+            diagnostic = diagnostic.with_note(format!("in the context `{context}`"));
+        }
+        match implicit.name.as_str() {
+            "_" => diagnostic.with_help("write the argument out in place of the `_`"),
+            _ => diagnostic.with_help(
+                "give an argument whose type mentions it, or annotate the result so its type can be worked out",
+            ),
+        }
+    }
+
+    /// What `hole` must be filled in with: its type, the variables in scope
+    /// and the terms that would fit.
+    fn goal(&self, hole: Hole) -> Goal {
+        let Hole { name, span, ctx, ty } = hole;
+        let mut candidates = Vec::new();
+        // Anything fits a hole whose type nothing decided.
+        if !matches!(
+            self.globals.force(&ty),
+            Value::Neutral(Neutral::Meta(..) | Neutral::App(..))
+        ) {
+            for i in (0..ctx.level()).rev() {
+                let name = &ctx.names[i];
+                // Only the innermost of the variables with a name can be used.
+                let visible = matches!(
+                    ctx.lookup(&self.globals, name),
+                    Some((Term::Var(index), _)) if index == ctx.level() - 1 - i
+                );
+                if visible && self.fits(&ctx, &ctx.type_of(&self.globals, i), &ty) {
+                    candidates.push(name.clone());
+                }
+            }
+            let mut globals: Vec<&str> = self
+                .globals
+                .names()
+                .filter(|name| ctx.lookup(&self.globals, name).is_none())
+                .collect();
+            globals.sort_unstable();
+            for name in globals {
+                let Some(global) = self.globals.get(name) else { continue };
+                let found = self.globals.eval(global.ty(), &[]);
+                if self.fits(&ctx, &found, &ty) {
+                    candidates.push(name.to_owned());
+                    continue;
+                }
+                let arity = explicit_arity(global.ty(), global.implicit());
+                if arity > 0 && self.returns(&ctx, found, &ty) {
+                    candidates.push(holes::call(name, arity));
+                }
+            }
+        }
+        Goal {
+            name,
+            span,
+            ty: ctx.show(&self.globals, &ty),
+            context: ctx.entries(&self.globals),
+            candidates,
+        }
+    }
+
+    /// Whether a value of type `found` could be used where `expected` is,
+    /// for some solution of the metavariables, leaving them as they were.
+    fn fits(&self, ctx: &Ctx, found: &Value, expected: &Value) -> bool {
+        let snapshot = self.globals.snapshot();
+        let fits = self.globals.subtype(ctx.level(), found, expected)
+            || unify::unify(&self.globals, ctx.level(), found, expected, &mut Vec::new()).is_ok();
+        self.globals.rollback(snapshot);
+        fits
+    }
+
+    /// Whether a function of type `found`, applied to as many arguments as
+    /// it takes, gives a value of type `expected`.
+    fn returns(&self, ctx: &Ctx, found: Value, expected: &Value) -> bool {
+        let snapshot = self.globals.snapshot();
+        let mut ty = found;
+        while let Value::Pi(name, _, cod) = self.globals.force(&ty) {
+            let arg = Term::apply(self.globals.fresh_meta(&name), ctx.vars());
+            ty = self.globals.instantiate(&cod, ctx.eval(&self.globals, &arg));
+        }
+        let fits = self.fits(ctx, &ty, expected);
+        self.globals.rollback(snapshot);
+        fits
+    }
+
+    fn family(&mut self, def: &'m EnumDef) -> Checked<()> {
+        let name = &def.name.name;
+        let mut ctx = Ctx::default();
+        let mut binders = Vec::new();
+        for param in &def.params {
+            let (term, _) = self.param(&ctx, param)?;
+            let ty = ctx.eval(&self.globals, &term);
+            ctx.bind(&self.globals, &param.name.name, &ty);
+            binders.push((param.name.name.clone(), term));
+        }
+
+        let params = uniform_params(def);
+        if params < def.params.len()
+            && let Some(variant) = def.variants.iter().find(|variant| variant.ret.is_none())
+        {
+            return Err(self.report(
+                Diagnostic::error(
+                    format!("variant `{}` needs the type it builds", variant.name.name),
+                    variant.name.span,
+                )
+                .with_label(variant.name.span, format!("the indices of this `{name}` are not known"))
+                .with_help(format!(
+                    "`{name}` has indices, so give each variant its type: `{} -> {name}(...)`",
+                    variant.name.name
+                )),
+            ));
+        }
+
+        let variants = def.variants.iter().map(|variant| variant.name.name.clone()).collect();
+        // Fields of the family's own type do not raise its level, so it can
+        // be declared at the lowest before they are checked.
+        self.globals.insert(
+            name,
+            Global::Family {
+                ty: pi(&binders, Term::Universe(0)),
+                params,
+                variants,
+            },
+        );
+        ctx.truncate(params);
+        let mut level = 0;
+        for variant in &def.variants {
+            match self.variant(def, variant, &ctx, &binders[..params]) {
+                Ok((ty, variant_level)) => {
+                    level = level.max(variant_level);
+                    let implicit = (0..params)
+                        .map(|_| false)
+                        .chain(variant.fields.iter().map(|field| field.implicit))
+                        .collect();
+                    self.globals.insert(
+                        &variant.name.name,
+                        Global::Variant {
+                            ty,
+                            family: name.clone(),
+                            fields: variant.fields.len(),
+                            implicit,
+                        },
+                    );
+                }
+                Err(reported) => {
+                    self.globals.remove(name);
+                    return Err(reported);
+                }
+            }
+        }
+        if let Some(Global::Family { params, variants, .. }) = self.globals.get(name).cloned() {
+            self.globals.insert(
+                name,
+                Global::Family {
+                    ty: pi(&binders, Term::Universe(level)),
+                    params,
+                    variants,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// The type of a variant, and the level of the universe its fields need.
+    fn variant(
+        &mut self,
+        def: &'m EnumDef,
+        variant: &'m Variant,
+        params: &Ctx,
+        binders: &[(String, Term)],
+    ) -> Checked<(Term, u32)> {
+        let family = &def.name.name;
+        let mut ctx = params.clone();
+        let mut binders = binders.to_vec();
+        let mut level = 0;
+        for field in &variant.fields {
+            let Some(ty) = &field.ty else {
+                return Err(self.report(
+                    Diagnostic::error("type annotations needed", field.span)
+                        .with_label(field.span, "this field needs a type")
+                        .with_note("every field of a family needs a type"),
+                ));
+            };
+            if let Some((span, negative)) = non_positive(ty, family) {
+                let diagnostic = match negative {
+                    true => Diagnostic::error(
+                        format!(
+                            "`{family}` occurs in a negative position in variant `{}`",
+                            variant.name.name
+                        ),
+                        span,
+                    )
+                    .with_label(span, "to the left of an arrow"),
+                    false => Diagnostic::error(
+                        format!(
+                            "`{family}` does not occur strictly positively in variant `{}`",
+                            variant.name.name
+                        ),
+                        span,
+                    )
+                    .with_label(span, "as an argument of another type"),
+                };
+                return Err(self.report(diagnostic.with_note(
+                    "a family whose fields take it apart could build a term that never finishes evaluating, which \
+                     would make the type system inconsistent",
+                )));
+            }
+            let (term, field_level) = self.check_type(&ctx, ty)?;
+            level = level.max(field_level);
+            let value = ctx.eval(&self.globals, &term);
+            ctx.bind(&self.globals, &field.name.name, &value);
+            binders.push((field.name.name.clone(), term));
+        }
+
+        let ret = match &variant.ret {
+            Some(ret) => {
+                let (term, _) = self.check_type(&ctx, ret)?;
+                let builds = match ctx.eval(&self.globals, &term) {
+                    Value::Con(name, args) => name == *family && args.len() == def.params.len(),
+                    _ => false,
+                };
+                if !builds {
+                    let found = kernel::show(&term, &ctx.names);
+                    return Err(self.report(
+                        Diagnostic::error(
+                            format!("variant `{}` must build a `{family}`", variant.name.name),
+                            ret.span,
+                        )
+                        .with_label(ret.span, format!("expected `{family}(...)`, found `{found}`")),
+                    ));
+                }
+                term
+            }
+            None => Term::apply(
+                Term::Global(family.clone()),
+                (0..params.level()).map(|i| Term::Var(ctx.level() - 1 - i)),
+            ),
+        };
+        Ok((pi(&binders, ret), level))
+    }
+
+    /// The type of a parameter of a function or family.
+    fn param(&mut self, ctx: &Ctx, param: &'m Param) -> Checked<(Term, u32)> {
+        let Some(ty) = &param.ty else {
+            return Err(self.report(
+                Diagnostic::error("type annotations needed", param.name.span)
+                    .with_label(param.name.span, "this parameter needs a type")
+                    .with_note("a parameter that types can depend on needs a type of its own"),
+            ));
+        };
+        self.check_type(ctx, ty)
+    }
+
+    fn signature(&mut self, func: &'m Func) -> Checked<Term> {
+        let mut ctx = Ctx::default();
+        let mut binders = Vec::new();
+        for param in &func.params {
+            let (term, _) = self.param(&ctx, param)?;
+            let ty = ctx.eval(&self.globals, &term);
+            ctx.bind(&self.globals, &param.name.name, &ty);
+            binders.push((param.name.name.clone(), term));
+        }
+        let Some(ret) = &func.ret else {
+            return Err(self.report(
+                Diagnostic::error("type annotations needed", func.name.span)
+                    .with_label(func.name.span, "this function needs a return type")
+                    .with_note("the result of a function with a dependent type is not inferred"),
+            ));
+        };
+        let (ret, _) = self.check_type(&ctx, ret)?;
+        let ty = pi(&binders, ret);
+        self.globals.insert(
+            &func.name.name,
+            Global::Func {
+                ty: ty.clone(),
+                arity: func.params.len(),
+                implicit: func.params.iter().map(|param| param.implicit).collect(),
+                body: None,
+            },
+        );
+        Ok(ty)
+    }
+
+    fn body(&mut self, func: &'m Func, ty: &Term) -> Checked<()> {
+        let mut ctx = Ctx::default();
+        let mut ty = self.globals.eval(ty, &[]);
+        for param in &func.params {
+            let Value::Pi(_, dom, cod) = ty else {
+                return Err(Reported);
+            };
+            let var = Value::var(ctx.level());
+            ctx.bind(&self.globals, &param.name.name, &dom);
+            ty = self.globals.instantiate(&cod, var);
+        }
+
+        let term = self.block(&ctx, &func.body.stmts, &ty, func.name.span)?;
+        self.globals.define(&func.name.name, term);
+        Ok(())
+    }
+
+    /// Checks the statements of a body against `expected`: assignments to
+    /// names, each a `let` around the rest, then a `return`.
+    fn block(&mut self, ctx: &Ctx, stmts: &'m [Stmt], expected: &Value, span: Span) -> Checked<Term> {
+        match stmts {
+            [
+                Stmt {
+                    kind: StmtKind::Return(Some(value)),
+                    ..
+                },
+            ] => self.check(ctx, value, expected),
+            [
+                Stmt {
+                    kind:
+                        StmtKind::Assign {
+                            target:
+                                Expr {
+                                    kind: ExprKind::Ident(name),
+                                    ..
+                                },
+                            ty,
+                            value,
+                        },
+                    ..
+                },
+                rest @ ..,
+            ] if !rest.is_empty() => {
+                // A variable assigned again keeps its type.
+                let declared = match ty {
+                    Some(ty) => {
+                        let (term, _) = self.check_type(ctx, ty)?;
+                        Some(ctx.eval(&self.globals, &term))
+                    }
+                    None => ctx.lookup(&self.globals, &name.name).map(|(_, ty)| ty),
+                };
+                let (term, ty) = match declared {
+                    Some(ty) => (self.check(ctx, value, &ty)?, ty),
+                    None => self.infer(ctx, value)?,
+                };
+                let mut inner = ctx.clone();
+                inner.define(&self.globals, &name.name, &ty, ctx.eval(&self.globals, &term));
+                let body = self.block(&inner, rest, expected, span)?;
+                Ok(Term::Let(name.name.clone(), Box::new(term), Box::new(body)))
+            }
+            stmts => {
+                if stmts.iter().any(|stmt| stmt.kind == StmtKind::Error) {
+                    return Err(Reported);
+                }
+                let span = stmts
+                    .iter()
+                    .find(|stmt| {
+                        !matches!(
+                            &stmt.kind,
+                            StmtKind::Assign {
+                                target: Expr {
+                                    kind: ExprKind::Ident(_),
+                                    ..
+                                },
+                                ..
+                            }
+                        )
+                    })
+                    .map_or(span, |stmt| stmt.span);
+                Err(self.report(
+                    Diagnostic::error("statements cannot be used in a function with a dependent type", span)
+                        .with_label(span, "not an assignment to a name or the final `return`")
+                        .with_help("write the body as assignments to names followed by a `return` of an expression"),
+                ))
+            }
+        }
+    }
+
+    /// A metavariable standing for an argument of type `ty` left out at
+    /// `span`, applied to the variables in scope.
+    fn fresh(&mut self, ctx: &Ctx, name: &str, callee: Option<&str>, span: Span, ty: &Value) -> Term {
+        let meta = self.globals.fresh_meta(name);
+        let Term::Meta(id, _) = &meta else {
+            unreachable!("a new metavariable");
+        };
+        self.implicits.push(Implicit {
+            meta: *id,
+            name: name.to_owned(),
+            callee: callee.map(ToOwned::to_owned),
+            span,
+            ctx: ctx.clone(),
+            ty: ty.clone(),
+        });
+        Term::apply(meta, ctx.vars())
+    }
+
+    /// A metavariable standing for the hole at `span`, of type `ty`.
+    fn hole(&mut self, ctx: &Ctx, name: &Option<Ident>, span: Span, ty: &Value) -> Term {
+        let name = name.as_ref().map(|name| name.name.clone());
+        let meta = self.globals.fresh_meta(name.as_deref().unwrap_or("?"));
+        self.holes.push(Hole {
+            name,
+            span,
+            ctx: ctx.clone(),
+            ty: ty.clone(),
+        });
+        Term::apply(meta, ctx.vars())
+    }
+
+    // Types
+
+    /// Elaborates a type, returning it with the level of the universe it
+    /// lives in.
+    fn check_type(&mut self, ctx: &Ctx, ty: &'m TypeExpr) -> Checked<(Term, u32)> {
+        let (term, sort) = self.infer_type(ctx, ty)?;
+        match sort {
+            Value::Universe(level) => Ok((term, level)),
+            sort => {
+                let shown = ctx.show(&self.globals, &sort);
+                Err(self.report(
+                    Diagnostic::error(format!("expected a type, found a value of type `{shown}`"), ty.span)
+                        .with_label(ty.span, "not a type"),
+                ))
+            }
+        }
+    }
+
+    fn infer_type(&mut self, ctx: &Ctx, ty: &'m TypeExpr) -> Checked<(Term, Value)> {
+        match &ty.kind {
+            TypeExprKind::Named { name, args } => {
+                if ctx.lookup(&self.globals, &name.name).is_none()
+                    && let Some(alias) = self.aliases.get(name.name.as_str()).copied()
+                {
+                    // Arguments and cycles are reported with the annotations.
+                    // A refinement type is its base type; the arguments are
+                    // for its condition, which is proved apart.
+                    if (alias.refinement.is_none() && !args.is_empty()) || self.expanding.contains(&name.name.as_str())
+                    {
+                        return Err(Reported);
+                    }
+                    self.expanding.push(&name.name);
+                    let expanded = self.infer_type(ctx, &alias.ty);
+                    self.expanding.pop();
+                    return expanded;
+                }
+                let (head, head_ty) = self.head(ctx, name, true)?;
+                let args: Vec<_> = args.iter().map(Arg::Type).collect();
+                self.arity(ctx, name, &args)?;
+                self.apply(ctx, name.span, Some(name), head, head_ty, args)
+            }
+            TypeExprKind::Function { params, ret } => {
+                let mut inner = ctx.clone();
+                let mut binders = Vec::new();
+                let mut level = 0;
+                for param in params {
+                    let (name, param) = match &param.kind {
+                        TypeExprKind::Bind { name, ty } => (name.name.as_str(), &**ty),
+                        _ => ("_", param),
+                    };
+                    let (term, param_level) = self.check_type(&inner, param)?;
+                    level = level.max(param_level);
+                    let value = inner.eval(&self.globals, &term);
+                    inner.bind(&self.globals, name, &value);
+                    binders.push((name.to_owned(), term));
+                }
+                let (ret, ret_level) = self.check_type(&inner, ret)?;
+                Ok((pi(&binders, ret), Value::Universe(level.max(ret_level))))
+            }
+            TypeExprKind::Tuple(elements) if elements.len() >= 2 => {
+                let mut inner = ctx.clone();
+                let mut binders = Vec::new();
+                let mut level = 0;
+                for element in elements {
+                    let (name, element) = match &element.kind {
+                        TypeExprKind::Bind { name, ty } => (name.name.as_str(), &**ty),
+                        _ => ("_", element),
+                    };
+                    let (term, element_level) = self.check_type(&inner, element)?;
+                    level = level.max(element_level);
+                    let value = inner.eval(&self.globals, &term);
+                    inner.bind(&self.globals, name, &value);
+                    binders.push((name.to_owned(), term));
+                }
+                let (_, last) = binders.pop().expect("at least two elements");
+                let sigma = binders
+                    .into_iter()
+                    .rev()
+                    .fold(last, |body, (name, ty)| Term::Sigma(name, Box::new(ty), Box::new(body)));
+                Ok((sigma, Value::Universe(level)))
+            }
+            TypeExprKind::Tuple(_) => Err(self.unsupported(ty.span, "tuples of fewer than two elements")),
+            TypeExprKind::Bind { name, .. } => Err(self.report(
+                Diagnostic::error(format!("`{}` cannot be bound here", name.name), ty.span)
+                    .with_label(ty.span, "a name can only be given to a parameter or a tuple element")
+                    .with_help("write the type of a parameter as `(n: Nat) -> T`, or of a tuple as `(n: Nat, T)`"),
+            )),
+            TypeExprKind::Expr(expr) => self.infer(ctx, expr),
+        }
+    }
+
+    /// The term and type a name stands for, in a type or an expression.
+    fn head(&mut self, ctx: &Ctx, name: &Ident, in_type: bool) -> Checked<(Term, Value)> {
+        if let Some(found) = ctx.lookup(&self.globals, &name.name) {
+            return Ok(found);
+        }
+        if let Some(level) = universe_level(&name.name) {
+            return Ok((Term::Universe(level), Value::Universe(level + 1)));
+        }
+        if name.name == "Nat" {
+            return Ok((Term::Nat, Value::Universe(0)));
+        }
+        if BASE_TYPES.contains(&name.name.as_str()) {
+            return Ok((Term::Base(name.name.clone()), Value::Universe(0)));
+        }
+        if let Some(global) = self.globals.get(&name.name) {
+            return Ok((Term::Global(name.name.clone()), self.globals.eval(global.ty(), &[])));
+        }
+        if name.name == "List" || fixed_width(&name.name).is_some() || self.inferred.contains(name.name.as_str()) {
+            return Err(self.report(
+                Diagnostic::error(
+                    format!("`{}` cannot be used in a function with a dependent type", name.name),
+                    name.span,
+                )
+                .with_label(name.span, "its type is inferred, not checked by the core calculus")
+                .with_note(
+                    "only families, their variants, functions with dependent types, `Nat`, `Int`, `Float`, `Str`, \
+                     `Bool` and the universes can be used here",
+                ),
+            ));
+        }
+        if in_type && name.name.starts_with(|c: char| c.is_lowercase()) {
+            return Err(self.report(
+                Diagnostic::error(format!("cannot find type `{}` in this scope", name.name), name.span)
+                    .with_label(name.span, "not found in this scope")
+                    .with_help(format!("bind it as a parameter first: `{}: Type`", name.name)),
+            ));
+        }
+        // Unknown names are reported by name resolution and the annotations,
+        // and declarations that failed to check where they are.
+        Err(Reported)
+    }
+
+    /// Checks the number of arguments a global is applied to.
+    fn arity(&mut self, ctx: &Ctx, name: &Ident, args: &[Arg<'m>]) -> Checked<()> {
+        if ctx.lookup(&self.globals, &name.name).is_none()
+            && let Some(global) = self.globals.get(&name.name)
+        {
+            let (expected, what) = match global {
+                Global::Func { .. } => (explicit_arity(global.ty(), global.implicit()), "function"),
+                Global::Family { ty, .. } => (arity(ty), "family"),
+                Global::Variant { .. } => (explicit_arity(global.ty(), global.implicit()), "variant"),
+            };
+            let implicits = global.implicit().iter().filter(|implicit| **implicit).count();
+            // A family with too many arguments is reported with the
+            // annotations when they are types.
+            if expected != args.len() && !(what == "family" && matches!(args.first(), Some(Arg::Type(_)))) {
+                let supplied = match args.len() {
+                    1 => "1 argument was".to_owned(),
+                    n => format!("{n} arguments were"),
+                };
+                let mut diagnostic = Diagnostic::error(
+                    format!(
+                        "this {what} takes {} but {supplied} supplied",
+                        plural(expected, "argument")
+                    ),
+                    name.span,
+                )
+                .with_label(
+                    name.span,
+                    format!("`{}` takes {}", name.name, plural(expected, "argument")),
+                );
+                if implicits > 0 {
+                    diagnostic = diagnostic.with_note(format!(
+                        "`{}` also has {}, which are worked out from the others and left out of the call",
+                        name.name,
+                        plural(implicits, "implicit argument")
+                    ));
+                }
+                return Err(self.report(diagnostic));
+            } else if expected != args.len() {
+                return Err(Reported);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies `head`, of type `ty`, to `args` in turn, with a metavariable
+    /// for each implicit argument of the global `callee`.
+    fn apply(
+        &mut self,
+        ctx: &Ctx,
+        span: Span,
+        callee: Option<&Ident>,
+        head: Term,
+        ty: Value,
+        args: Vec<Arg<'m>>,
+    ) -> Checked<(Term, Value)> {
+        let implicit = match callee {
+            Some(callee) if ctx.lookup(&self.globals, &callee.name).is_none() => self
+                .globals
+                .get(&callee.name)
+                .map(|global| global.implicit().to_vec())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let mut term = head;
+        let mut ty = ty;
+        let mut args = args.into_iter();
+        for position in 0.. {
+            let implicit = implicit.get(position).copied().unwrap_or(false);
+            // Implicit arguments are filled in up to the last explicit one,
+            // and after it while the type says more follow.
+            let arg = match implicit {
+                true => None,
+                false => match args.next() {
+                    Some(arg) => Some(arg),
+                    None => break,
+                },
+            };
+            let Value::Pi(name, dom, cod) = self.globals.force(&ty) else {
+                if arg.is_none() {
+                    break;
+                }
+                let shown = ctx.show(&self.globals, &ty);
+                return Err(self.report(
+                    Diagnostic::error(format!("expected function, found `{shown}`"), span)
+                        .with_label(span, "applied to too many arguments"),
+                ));
+            };
+            let arg = match arg {
+                Some(arg) => self.check_arg(ctx, arg, &dom)?,
+                None => self.fresh(ctx, &name, callee.map(|callee| callee.name.as_str()), span, &dom),
+            };
+            let value = ctx.eval(&self.globals, &arg);
+            ty = self.globals.instantiate(&cod, value);
+            term = Term::App(Box::new(term), Box::new(arg));
+        }
+        Ok((term, ty))
+    }
+
+    fn check_arg(&mut self, ctx: &Ctx, arg: Arg<'m>, expected: &Value) -> Checked<Term> {
+        match arg {
+            Arg::Expr(expr) => self.check(ctx, expr, expected),
+            Arg::Type(TypeExpr {
+                kind: TypeExprKind::Expr(expr),
+                ..
+            }) => self.check(ctx, expr, expected),
+            Arg::Type(ty) => {
+                let (term, found) = self.infer_type(ctx, ty)?;
+                self.expect(ctx, &found, expected, ty.span)?;
+                Ok(term)
+            }
+        }
+    }
+
+    /// Unifies the type `found` with the `expected` one, reporting them if
+    /// they differ.
+    fn expect(&mut self, ctx: &Ctx, found: &Value, expected: &Value, span: Span) -> Checked<()> {
+        if self.globals.subtype(ctx.level(), found, expected) {
+            return Ok(());
+        }
+        let mut problems = Vec::new();
+        if unify::unify(&self.globals, ctx.level(), found, expected, &mut problems).is_err() {
+            return Err(self.report(self.mismatch(ctx, found, expected, span)));
+        }
+        self.postponed.extend(problems.into_iter().map(|problem| Postponed {
+            problem,
+            ctx: ctx.clone(),
+            span,
+            found: found.clone(),
+            expected: expected.clone(),
+        }));
+        self.retry();
+        Ok(())
+    }
+
+    /// Tries the postponed problems again for as long as that solves more
+    /// metavariables.
+    fn retry(&mut self) {
+        loop {
+            let solved = self.globals.solved();
+            for postponed in std::mem::take(&mut self.postponed) {
+                let Problem { level, a, b } = &postponed.problem;
+                let mut problems = Vec::new();
+                if unify::unify(&self.globals, *level, a, b, &mut problems).is_err() {
+                    let diagnostic =
+                        self.mismatch(&postponed.ctx, &postponed.found, &postponed.expected, postponed.span);
+                    self.diagnostics.push(diagnostic);
+                    continue;
+                }
+                self.postponed.extend(problems.into_iter().map(|problem| Postponed {
+                    problem,
+                    ctx: postponed.ctx.clone(),
+                    span: postponed.span,
+                    found: postponed.found.clone(),
+                    expected: postponed.expected.clone(),
+                }));
+            }
+            if self.globals.solved() == solved {
+                break;
+            }
+        }
+    }
+
+    fn mismatch(&self, ctx: &Ctx, found: &Value, expected: &Value, span: Span) -> Diagnostic {
+        let shown_expected = ctx.show(&self.globals, expected);
+        let shown_found = ctx.show(&self.globals, found);
+        let label = format!("expected `{shown_expected}`, found `{shown_found}`");
+        match (self.globals.force(found), self.globals.force(expected)) {
+            (Value::Universe(_), Value::Universe(_)) => Diagnostic::error("universe inconsistency", span)
+                .with_label(span, label)
+                .with_note("each universe lives in the next one up: `Type : Type1`, `Type1 : Type2`, and so on")
+                .with_note(
+                    "a universe that contained itself would make the type system inconsistent (Girard's paradox)",
+                ),
+            _ => Diagnostic::error("mismatched types", span).with_label(span, label),
+        }
+    }
+
+    // Expressions
+
+    fn check(&mut self, ctx: &Ctx, expr: &'m Expr, expected: &Value) -> Checked<Term> {
+        let expected = &self.globals.force(expected);
+        match (&expr.kind, expected) {
+            (ExprKind::Ident(name), _) if name.name == "_" => Ok(self.fresh(ctx, "_", None, expr.span, expected)),
+            (ExprKind::Hole(name), _) => Ok(self.hole(ctx, name, expr.span, expected)),
+            (ExprKind::Lit(Lit::Int(n)), Value::Nat) => match u64::try_from(*n) {
+                Ok(n) => Ok(Term::NatLit(n)),
+                Err(_) => Err(self.report(
+                    Diagnostic::error("mismatched types", expr.span)
+                        .with_label(expr.span, "expected `Nat`, found a negative number"),
+                )),
+            },
+            (
+                ExprKind::Binary {
+                    op: op @ (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul),
+                    lhs,
+                    rhs,
+                },
+                Value::Nat,
+            ) => {
+                let lhs = Box::new(self.check(ctx, lhs, &Value::Nat)?);
+                let rhs = Box::new(self.check(ctx, rhs, &Value::Nat)?);
+                Ok(match op {
+                    BinaryOp::Add => Term::Add(lhs, rhs),
+                    op => Term::Op(*op, lhs, rhs),
+                })
+            }
+            (ExprKind::Tuple(items), Value::Sigma(..)) if items.len() >= 2 => self.check_tuple(ctx, items, expected),
+            (ExprKind::Lambda { params, body }, Value::Pi(..)) => self.check_lambda(ctx, params, body, expected),
+            (ExprKind::Match { scrutinee, arms }, _) => self.check_match(ctx, scrutinee, arms, expected),
+            _ => {
+                let (term, found) = self.infer(ctx, expr)?;
+                self.expect(ctx, &found, expected, expr.span)?;
+                Ok(term)
+            }
+        }
+    }
+
+    fn check_tuple(&mut self, ctx: &Ctx, items: &'m [Expr], expected: &Value) -> Checked<Term> {
+        let [first, rest @ ..] = items else {
+            return Err(Reported);
+        };
+        if rest.is_empty() {
+            return self.check(ctx, first, expected);
+        }
+        let Value::Sigma(_, a, b) = expected else {
+            let shown = ctx.show(&self.globals, expected);
+            let span = first.span.to(items[items.len() - 1].span);
+            return Err(self.report(
+                Diagnostic::error("mismatched types", span)
+                    .with_label(span, format!("expected `{shown}`, found a tuple")),
+            ));
+        };
+        let first = self.check(ctx, first, a)?;
+        let value = ctx.eval(&self.globals, &first);
+        let rest = self.check_tuple(ctx, rest, &self.globals.instantiate(b, value))?;
+        Ok(Term::Pair(Box::new(first), Box::new(rest)))
+    }
+
+    fn check_lambda(&mut self, ctx: &Ctx, params: &'m [Param], body: &'m Expr, expected: &Value) -> Checked<Term> {
+        let mut inner = ctx.clone();
+        let mut ty = expected.clone();
+        for param in params {
+            let Value::Pi(_, dom, cod) = ty else {
+                let shown = ctx.show(&self.globals, expected);
+                return Err(
+                    self.report(Diagnostic::error("mismatched types", param.span).with_label(
+                        param.span,
+                        format!(
+                            "expected `{shown}`, found a function of {}",
+                            plural(params.len(), "parameter")
+                        ),
+                    )),
+                );
+            };
+            if let Some(annotation) = &param.ty {
+                let (term, _) = self.check_type(&inner, annotation)?;
+                let declared = inner.eval(&self.globals, &term);
+                if !self.globals.conv(inner.level(), &declared, &dom) {
+                    let shown_expected = inner.show(&self.globals, &dom);
+                    let shown_found = inner.show(&self.globals, &declared);
+                    return Err(
+                        self.report(Diagnostic::error("mismatched types", annotation.span).with_label(
+                            annotation.span,
+                            format!("expected `{shown_expected}`, found `{shown_found}`"),
+                        )),
+                    );
+                }
+            }
+            let var = Value::var(inner.level());
+            inner.bind(&self.globals, &param.name.name, &dom);
+            ty = self.globals.instantiate(&cod, var);
+        }
+        let body = self.check(&inner, body, &ty)?;
+        Ok(params
+            .iter()
+            .rev()
+            .fold(body, |body, param| Term::Lam(param.name.name.clone(), Box::new(body))))
+    }
+
+    fn infer(&mut self, ctx: &Ctx, expr: &'m Expr) -> Checked<(Term, Value)> {
+        match &expr.kind {
+            ExprKind::Lit(lit) => {
+                let ty = match lit {
+                    Lit::Int(_) => "Int",
+                    Lit::Float(_) => "Float",
+                    Lit::Str(_) => "Str",
+                    Lit::Bool(_) => "Bool",
+                };
+                Ok((Term::Lit(lit.clone()), Value::Base(ty.to_owned())))
+            }
+            ExprKind::Ident(name) => self.head(ctx, name, false),
+            ExprKind::Call { callee, args } => {
+                let args: Vec<_> = args.iter().map(Arg::Expr).collect();
+                if let ExprKind::Ident(name) = &callee.kind {
+                    self.arity(ctx, name, &args)?;
+                }
+                let (head, ty) = self.infer(ctx, callee)?;
+                let name = match &callee.kind {
+                    ExprKind::Ident(name) => Some(name),
+                    _ => None,
+                };
+                self.apply(ctx, callee.span, name, head, ty, args)
+            }
+            ExprKind::Binary {
+                op: op @ BinaryOp::Implies,
+                ..
+            } => Err(self.unsupported(expr.span, &format!("`{}`", op.symbol()))),
+            ExprKind::Binary { op, lhs, rhs } => self.infer_binary(ctx, *op, lhs, rhs),
+            ExprKind::Field { expr: pair, field } => {
+                let (term, ty) = self.infer(ctx, pair)?;
+                let ty = self.globals.force(&ty);
+                match (&ty, field.name.as_str()) {
+                    (Value::Sigma(_, a, _), "0") => Ok((Term::First(Box::new(term)), (**a).clone())),
+                    (Value::Sigma(_, _, b), "1") => {
+                        let first = kernel::first(ctx.eval(&self.globals, &term));
+                        Ok((Term::Second(Box::new(term)), self.globals.instantiate(b, first)))
+                    }
+                    _ => {
+                        let shown = ctx.show(&self.globals, &ty);
+                        let mut diagnostic =
+                            Diagnostic::error(format!("no field `{}` on type `{shown}`", field.name), field.span)
+                                .with_label(field.span, "unknown field");
+                        if matches!(ty, Value::Sigma(..)) {
+                            diagnostic = diagnostic
+                                .with_note("a pair has the fields `0` and `1`; a longer tuple is a pair of pairs");
+                        }
+                        Err(self.report(diagnostic))
+                    }
+                }
+            }
+            ExprKind::Tuple(items) if items.len() >= 2 => self.infer_tuple(ctx, items),
+            ExprKind::Lambda { params, body } => {
+                let mut inner = ctx.clone();
+                let mut binders = Vec::new();
+                for param in params {
+                    let Some(annotation) = &param.ty else {
+                        return Err(self.report(
+                            Diagnostic::error("type annotations needed", param.span)
+                                .with_label(param.span, "the type of this parameter cannot be inferred")
+                                .with_help(format!("give it a type: `{}: T`", param.name.name)),
+                        ));
+                    };
+                    let (term, _) = self.check_type(&inner, annotation)?;
+                    let value = inner.eval(&self.globals, &term);
+                    inner.bind(&self.globals, &param.name.name, &value);
+                    binders.push((param.name.name.clone(), term));
+                }
+                let (body, body_ty) = self.infer(&inner, body)?;
+                let ty = pi(&binders, self.globals.quote(inner.level(), &body_ty));
+                let term = params
+                    .iter()
+                    .rev()
+                    .fold(body, |body, param| Term::Lam(param.name.name.clone(), Box::new(body)));
+                Ok((term, ctx.eval(&self.globals, &ty)))
+            }
+            ExprKind::Match { .. } => Err(self.report(
+                Diagnostic::error("type annotations needed", expr.span)
+                    .with_label(expr.span, "cannot infer the type of this `match`")
+                    .with_help("use the match where its type is known, such as the result of the function"),
+            )),
+            ExprKind::Hole(name) => {
+                // Its type is whatever it is used as.
+                let ty = Term::apply(self.globals.fresh_meta("_"), ctx.vars());
+                let ty = ctx.eval(&self.globals, &ty);
+                Ok((self.hole(ctx, name, expr.span, &ty), ty))
+            }
+            ExprKind::Error => Err(Reported),
+            ExprKind::Tuple(_) => Err(self.unsupported(expr.span, "tuples of fewer than two elements")),
+            ExprKind::List(_) => Err(self.unsupported(expr.span, "lists")),
+            ExprKind::Unary { op, .. } => Err(self.unsupported(expr.span, &format!("`{}`", op.symbol()))),
+            ExprKind::Index { .. } => Err(self.unsupported(expr.span, "indexing")),
+            ExprKind::Struct { .. } => Err(self.unsupported(expr.span, "struct literals")),
+            ExprKind::Closure { .. } => Err(self.unsupported(expr.span, "closures")),
+        }
+    }
+
+    /// An operator on naturals or on values of a built-in type. Both
+    /// operands have the type of the first.
+    fn infer_binary(&mut self, ctx: &Ctx, op: BinaryOp, lhs: &'m Expr, rhs: &'m Expr) -> Checked<(Term, Value)> {
+        let (lhs_term, lhs_ty) = self.infer(ctx, lhs)?;
+        let mut lhs_ty = self.globals.force(&lhs_ty);
+        if let Value::Neutral(_) = lhs_ty {
+            self.expect(ctx, &lhs_ty, &Value::Nat, lhs.span)?;
+            lhs_ty = Value::Nat;
+        }
+        let comparison = matches!(
+            op,
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+        );
+        let defined = match (&lhs_ty, op) {
+            // A division could be by zero, which the core has no way to say.
+            (Value::Nat, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul) => true,
+            (Value::Nat, _) => comparison,
+            (Value::Base(base), BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div) => {
+                base == "Int" || base == "Float"
+            }
+            (Value::Base(base), BinaryOp::Rem | BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::Shl | BinaryOp::Shr) => {
+                base == "Int"
+            }
+            (Value::Base(base), BinaryOp::And | BinaryOp::Or) => base == "Bool",
+            (Value::Base(_), BinaryOp::Eq | BinaryOp::Ne) => true,
+            (Value::Base(base), _) => comparison && base != "Bool",
+            _ => false,
+        };
+        if !defined {
+            let shown = ctx.show(&self.globals, &lhs_ty);
+            return Err(self.report(
+                Diagnostic::error(
+                    format!("cannot apply `{}` to a value of type `{shown}`", op.symbol()),
+                    lhs.span,
+                )
+                .with_label(lhs.span, format!("this is `{shown}`"))
+                .with_note(
+                    "in a function with a dependent type, operators apply to `Nat` and the built-in types, \
+                     but only `+`, `-`, `*` and comparisons to `Nat`",
+                ),
+            ));
+        }
+        let rhs = Box::new(self.check(ctx, rhs, &lhs_ty)?);
+        let lhs_term = Box::new(lhs_term);
+        let ty = match comparison {
+            true => Value::Base("Bool".to_owned()),
+            false => lhs_ty.clone(),
+        };
+        let term = match (op, lhs_ty) {
+            (BinaryOp::Add, Value::Nat) => Term::Add(lhs_term, rhs),
+            (op, _) => Term::Op(op, lhs_term, rhs),
+        };
+        Ok((term, ty))
+    }
+
+    fn infer_tuple(&mut self, ctx: &Ctx, items: &'m [Expr]) -> Checked<(Term, Value)> {
+        let [first, rest @ ..] = items else {
+            return Err(Reported);
+        };
+        if rest.is_empty() {
+            return self.infer(ctx, first);
+        }
+        let (first, first_ty) = self.infer(ctx, first)?;
+        let (rest, rest_ty) = self.infer_tuple(ctx, rest)?;
+        // The type of the rest does not depend on the first element.
+        let level = ctx.level();
+        let ty = Term::Sigma(
+            "_".to_owned(),
+            Box::new(self.globals.quote(level, &first_ty)),
+            Box::new(self.globals.quote(level + 1, &rest_ty)),
+        );
+        Ok((
+            Term::Pair(Box::new(first), Box::new(rest)),
+            ctx.eval(&self.globals, &ty),
+        ))
+    }
+
+    // Matching
+
+    fn check_match(&mut self, ctx: &Ctx, scrutinee: &'m Expr, arms: &'m [MatchArm], expected: &Value) -> Checked<Term> {
+        let (scrutinee_term, scrutinee_ty) = self.infer(ctx, scrutinee)?;
+        let scrutinee_ty = self.globals.force(&scrutinee_ty);
+        let family = match &scrutinee_ty {
+            Value::Con(name, args) => match self.globals.get(name) {
+                Some(Global::Family { params, variants, .. }) => {
+                    Some((name.clone(), args.clone(), *params, variants.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some((family, args, params, variants)) = family else {
+            let shown = ctx.show(&self.globals, &scrutinee_ty);
+            return Err(self.report(
+                Diagnostic::error(
+                    format!("cannot match on a value of type `{shown}` in a function with a dependent type"),
+                    scrutinee.span,
+                )
+                .with_label(scrutinee.span, "not a value of a family")
+                .with_note("only values of an enum checked by the core calculus can be matched on here"),
+            ));
+        };
+        let shown_ty = ctx.show(&self.globals, &scrutinee_ty);
+        let scrutinee_value = ctx.eval(&self.globals, &scrutinee_term);
+        let goal = self.globals.quote(ctx.level(), expected);
+
+        let mut covered = vec![false; variants.len()];
+        let mut case_arms = Vec::new();
+        let mut default = None;
+        for arm in arms {
+            if let Some(guard) = &arm.guard {
+                return Err(self.unsupported(guard.span, "guards"));
+            }
+            match &arm.pattern.kind {
+                PatternKind::Error => return Err(Reported),
+                PatternKind::Variant { name, args: fields } => {
+                    let Some(index) = variants.iter().position(|variant| *variant == name.name) else {
+                        return Err(
+                            self.report(Diagnostic::error("mismatched types", arm.pattern.span).with_label(
+                                arm.pattern.span,
+                                format!("expected a variant of `{family}`, found `{}`", name.name),
+                            )),
+                        );
+                    };
+                    if default.is_some() || covered[index] {
+                        self.unreachable(arm.pattern.span);
+                        continue;
+                    }
+                    covered[index] = true;
+                    let mut bound = Vec::new();
+                    for field in fields {
+                        match &field.kind {
+                            PatternKind::Binding(name) => bound.push(name.name.clone()),
+                            PatternKind::Wildcard => bound.push("_".to_owned()),
+                            PatternKind::Error => return Err(Reported),
+                            _ => return Err(self.unsupported(field.span, "nested patterns")),
+                        }
+                    }
+                    let declared = self.fields(&name.name);
+                    let expected_fields = declared.iter().filter(|(_, implicit)| !implicit).count();
+                    if bound.len() != expected_fields {
+                        return Err(self.report(Diagnostic::error(
+                            format!(
+                                "this pattern has {}, but the variant `{}` has {}",
+                                plural(bound.len(), "field"),
+                                name.name,
+                                plural(expected_fields, "field")
+                            ),
+                            arm.pattern.span,
+                        )));
+                    }
+                    // Implicit fields are bound under their own names, which
+                    // the arm cannot refer to.
+                    let mut bound = bound.into_iter();
+                    let names: Vec<(String, bool)> = declared
+                        .into_iter()
+                        .map(|(field, implicit)| match implicit {
+                            true => (field, true),
+                            false => (bound.next().unwrap_or_default(), false),
+                        })
+                        .collect();
+                    match self.refine(ctx, &args, params, &scrutinee_value, &name.name, &names) {
+                        Refinement::Possible(inner) => {
+                            let goal = self.globals.eval(&goal, &inner.env[..ctx.level()]);
+                            let body = self.check(&inner, &arm.body, &goal)?;
+                            case_arms.push(Arm {
+                                variant: name.name.clone(),
+                                fields: names.into_iter().map(|(name, _)| name).collect(),
+                                body,
+                            });
+                        }
+                        Refinement::Impossible => {
+                            self.diagnostics.push(
+                                Diagnostic::warning("unreachable pattern", arm.pattern.span)
+                                    .with_label(
+                                        arm.pattern.span,
+                                        format!("`{}` never builds a value of type `{shown_ty}`", name.name),
+                                    )
+                                    .with_lint(UNREACHABLE_PATTERNS.name),
+                            );
+                        }
+                        Refinement::Stuck(a, b) => {
+                            return Err(self.report(
+                                Diagnostic::error(
+                                    format!(
+                                        "cannot tell whether `{}` builds a value of type `{shown_ty}`",
+                                        name.name
+                                    ),
+                                    arm.pattern.span,
+                                )
+                                .with_label(arm.pattern.span, format!("cannot unify `{a}` with `{b}`"))
+                                .with_note(
+                                    "an index can be matched on when it is built from variables, numbers, `+` and \
+                                     variants",
+                                ),
+                            ));
+                        }
+                    }
+                }
+                PatternKind::Binding(_) | PatternKind::Wildcard => {
+                    if default.is_some() || self.all_covered(ctx, &args, params, &scrutinee_value, &variants, &covered)
+                    {
+                        self.unreachable(arm.pattern.span);
+                        continue;
+                    }
+                    let name = match &arm.pattern.kind {
+                        PatternKind::Binding(name) => name.name.clone(),
+                        _ => "_".to_owned(),
+                    };
+                    let mut inner = ctx.clone();
+                    inner.define(&self.globals, &name, &scrutinee_ty, scrutinee_value.clone());
+                    let body = self.check(&inner, &arm.body, expected)?;
+                    default = Some((name, Box::new(body)));
+                }
+                PatternKind::Lit(_) | PatternKind::Or(_) => {
+                    return Err(self.unsupported(arm.pattern.span, "this pattern"));
+                }
+            }
+        }
+
+        if default.is_none() {
+            let missing: Vec<String> = variants
+                .iter()
+                .zip(&covered)
+                .filter(|(_, covered)| !**covered)
+                .filter(|(variant, _)| self.possible(ctx, &args, params, &scrutinee_value, variant))
+                .map(
+                    |(variant, _)| match self.fields(variant).iter().filter(|(_, implicit)| !implicit).count() {
+                        0 => variant.clone(),
+                        fields => format!("{variant}({})", vec!["_"; fields].join(", ")),
+                    },
+                )
+                .collect();
+            if !missing.is_empty() {
+                return Err(self.report(non_exhaustive(scrutinee.span, &missing)));
+            }
+        }
+        Ok(Term::Case(Box::new(scrutinee_term), case_arms, default))
+    }
+
+    /// The names of the fields of `variant`, and whether each is implicit.
+    fn fields(&self, variant: &str) -> Vec<(String, bool)> {
+        let Some(global @ Global::Variant { fields, .. }) = self.globals.get(variant) else {
+            return Vec::new();
+        };
+        let mut names = Vec::new();
+        let mut ty = global.ty();
+        while let Term::Pi(name, _, body) = ty {
+            names.push(name.clone());
+            ty = body;
+        }
+        let skip = names.len() - fields;
+        names
+            .into_iter()
+            .zip(global.implicit().iter().copied())
+            .skip(skip)
+            .collect()
+    }
+
+    fn unreachable(&mut self, span: Span) {
+        self.diagnostics.push(
+            Diagnostic::warning("unreachable pattern", span)
+                .with_label(span, "no value can reach this pattern")
+                .with_lint(UNREACHABLE_PATTERNS.name),
+        );
+    }
+
+    /// Whether every variant has an arm or can never build the value.
+    fn all_covered(
+        &self,
+        ctx: &Ctx,
+        args: &[Value],
+        params: usize,
+        scrutinee: &Value,
+        variants: &[String],
+        covered: &[bool],
+    ) -> bool {
+        variants
+            .iter()
+            .zip(covered)
+            .all(|(variant, covered)| *covered || !self.possible(ctx, args, params, scrutinee, variant))
+    }
+
+    fn possible(&self, ctx: &Ctx, args: &[Value], params: usize, scrutinee: &Value, variant: &str) -> bool {
+        let fields = match self.globals.get(variant) {
+            Some(Global::Variant { fields, .. }) => *fields,
+            _ => 0,
+        };
+        let names = vec![("_".to_owned(), false); fields];
+        !matches!(
+            self.refine(ctx, args, params, scrutinee, variant, &names),
+            Refinement::Impossible
+        )
+    }
+
+    /// Binds the fields of `variant` and unifies the value it builds with
+    /// `scrutinee`, of the family applied to `args`.
+    fn refine(
+        &self,
+        ctx: &Ctx,
+        args: &[Value],
+        params: usize,
+        scrutinee: &Value,
+        variant: &str,
+        names: &[(String, bool)],
+    ) -> Refinement {
+        let Some(global) = self.globals.get(variant) else {
+            return Refinement::Impossible;
+        };
+        let mut ctx = ctx.clone();
+        let mut ty = self.globals.eval(global.ty(), &[]);
+        for param in &args[..params] {
+            let Value::Pi(_, _, cod) = ty else {
+                return Refinement::Impossible;
+            };
+            ty = self.globals.instantiate(&cod, param.clone());
+        }
+        let mut fields = Vec::new();
+        for (name, hidden) in names {
+            let Value::Pi(_, dom, cod) = ty else {
+                return Refinement::Impossible;
+            };
+            let var = Value::var(ctx.level());
+            ctx.bind(&self.globals, name, &dom);
+            *ctx.hidden.last_mut().expect("a variable just bound") = *hidden;
+            fields.push(var.clone());
+            ty = self.globals.instantiate(&cod, var);
+        }
+        let Value::Con(_, built) = ty else {
+            return Refinement::Impossible;
+        };
+
+        for (index, built) in args[params..].iter().zip(&built[params..]) {
+            match self.unify(&mut ctx, index, built) {
+                Unified::Same => {}
+                Unified::Conflict => return Refinement::Impossible,
+                Unified::Stuck(a, b) => {
+                    return Refinement::Stuck(ctx.show(&self.globals, &a), ctx.show(&self.globals, &b));
+                }
+            }
+        }
+        // Where the value matched is a variable, it is now the variant.
+        let mut built = args[..params].to_vec();
+        built.extend(fields);
+        if let Unified::Conflict = self.unify(&mut ctx, scrutinee, &Value::Con(variant.to_owned(), built)) {
+            return Refinement::Impossible;
+        }
+        Refinement::Possible(ctx)
+    }
+
+    /// `value` with what matching has shown about the variables in it.
+    fn force(&self, ctx: &Ctx, value: &Value) -> Value {
+        ctx.eval(&self.globals, &self.globals.quote(ctx.level(), value))
+    }
+
+    fn occurs(&self, ctx: &Ctx, var: usize, value: &Value) -> bool {
+        let level = ctx.level();
+        self.globals.quote(level, value).mentions(level - 1 - var)
+    }
+
+    /// Makes the variable `var` stand for `value` from now on.
+    fn solve(&self, ctx: &mut Ctx, var: usize, value: Value) -> Unified {
+        let level = ctx.level();
+        let mut substitution: Vec<Value> = (0..level).map(Value::var).collect();
+        substitution[var] = value;
+        for i in 0..level {
+            let term = self.globals.quote(level, &ctx.env[i]);
+            ctx.env[i] = self.globals.eval(&term, &substitution);
+        }
+        Unified::Same
+    }
+
+    fn unify(&self, ctx: &mut Ctx, a: &Value, b: &Value) -> Unified {
+        let a = self.force(ctx, a);
+        let b = self.force(ctx, b);
+        if self.globals.conv(ctx.level(), &a, &b) {
+            return Unified::Same;
+        }
+        match (&a, &b) {
+            (Value::Neutral(Neutral::Var(x)), Value::Neutral(Neutral::Var(y))) => {
+                // The later variable, usually a field just bound, gives way.
+                match x > y {
+                    true => self.solve(ctx, *x, b.clone()),
+                    false => self.solve(ctx, *y, a.clone()),
+                }
+            }
+            (Value::Neutral(Neutral::Var(var)), other) | (other, Value::Neutral(Neutral::Var(var)))
+                if !self.occurs(ctx, *var, other) =>
+            {
+                self.solve(ctx, *var, other.clone())
+            }
+            (Value::Con(n1, args1), Value::Con(n2, args2)) => {
+                if n1 != n2 || args1.len() != args2.len() {
+                    return Unified::Conflict;
+                }
+                let mut result = Unified::Same;
+                for (a, b) in args1.iter().zip(args2) {
+                    match self.unify(ctx, a, b) {
+                        Unified::Same => {}
+                        Unified::Conflict => return Unified::Conflict,
+                        stuck => result = stuck,
+                    }
+                }
+                result
+            }
+            (Value::Sum(..), _) | (_, Value::Sum(..)) => self.unify_sums(ctx, a, b),
+            _ if rigid(&a) && rigid(&b) => Unified::Conflict,
+            _ => Unified::Stuck(a, b),
+        }
+    }
+
+    fn unify_sums(&self, ctx: &mut Ctx, a: Value, b: Value) -> Unified {
+        let (Some((c1, atoms1)), Some((c2, atoms2))) = (a.as_sum(), b.as_sum()) else {
+            return Unified::Conflict;
+        };
+        let (atoms1, atoms2) = self.globals.cancel(ctx.level(), atoms1, atoms2);
+        let common = c1.min(c2);
+        let (c1, c2) = (c1 - common, c2 - common);
+        match (c1, atoms1.as_slice(), c2, atoms2.as_slice()) {
+            (_, [], _, []) if c1 == c2 => Unified::Same,
+            // A sum is at least its constant.
+            (_, [], _, _) if c2 > c1 => Unified::Conflict,
+            (_, _, _, []) if c1 > c2 => Unified::Conflict,
+            (_, [], _, []) => Unified::Conflict,
+            (0, [Neutral::Var(var)], _, _) if !self.occurs(ctx, *var, &Value::sum(c2, atoms2.clone())) => {
+                let var = *var;
+                self.solve(ctx, var, Value::sum(c2, atoms2))
+            }
+            (_, _, 0, [Neutral::Var(var)]) if !self.occurs(ctx, *var, &Value::sum(c1, atoms1.clone())) => {
+                let var = *var;
+                self.solve(ctx, var, Value::sum(c1, atoms1))
+            }
+            _ => Unified::Stuck(a, b),
+        }
+    }
+}
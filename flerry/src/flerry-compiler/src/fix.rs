@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+use flerry_core::{Applicability, Diagnostic, Edit};
+
+/// Applies every machine-applicable suggestion in `diagnostics` to `source`.
+///
+/// Suggestions whose edits overlap an already accepted edit are skipped as a
+/// whole, so a fix is never half-applied. Returns the rewritten source and the
+/// number of suggestions applied.
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> (String, usize) {
+    let mut accepted: Vec<&Edit> = Vec::new();
+    let mut applied = 0;
+
+    let suggestions = diagnostics
+        .iter()
+        .flat_map(|d| &d.suggestions)
+        .filter(|s| s.applicability == Applicability::MachineApplicable);
+
+    for suggestion in suggestions {
+        let clashes = suggestion
+            .edits
+            .iter()
+            .any(|edit| accepted.iter().any(|other| overlaps(edit, other)));
+        if !clashes {
+            accepted.extend(&suggestion.edits);
+            applied += 1;
+        }
+    }
+
+    // Apply back to front so earlier offsets stay valid. Insertions at the same
+    // point keep the order in which they were suggested.
+    let mut order: Vec<usize> = (0..accepted.len()).collect();
+    order.sort_by_key(|&i| (std::cmp::Reverse(accepted[i].span.start), std::cmp::Reverse(i)));
+
+    let mut fixed = source.to_owned();
+    for i in order {
+        let edit = accepted[i];
+        fixed.replace_range(edit.span.start..edit.span.end, &edit.replacement);
+    }
+
+    (fixed, applied)
+}
+
+fn overlaps(a: &Edit, b: &Edit) -> bool {
+    if a.span == b.span {
+        // Two insertions at the same point can both go in; two replacements cannot.
+        return a.span.start != a.span.end;
+    }
+    a.span.start < b.span.end && b.span.start < a.span.end
+}
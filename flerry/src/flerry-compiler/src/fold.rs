@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Rebuilding the AST by value.
+//!
+//! A `Folder` takes each node apart and returns its replacement, which may be
+//! a node of a different shape: this is the trait for desugaring. Each
+//! `fold_*` method defaults to the `walk_*` function here, which folds the
+//! children in source order and reassembles the node with its span.
+
+use crate::ast::*;
+
+pub trait Folder: Sized {
+    fn fold_module(&mut self, module: Module) -> Module {
+        walk_module(self, module)
+    }
+
+    fn fold_item(&mut self, item: Item) -> Item {
+        walk_item(self, item)
+    }
+
+    fn fold_func(&mut self, func: Func) -> Func {
+        walk_func(self, func)
+    }
+
+    fn fold_param(&mut self, param: Param) -> Param {
+        walk_param(self, param)
+    }
+
+    fn fold_struct_def(&mut self, def: StructDef) -> StructDef {
+        walk_struct_def(self, def)
+    }
+
+    fn fold_enum_def(&mut self, def: EnumDef) -> EnumDef {
+        walk_enum_def(self, def)
+    }
+
+    fn fold_variant(&mut self, variant: Variant) -> Variant {
+        walk_variant(self, variant)
+    }
+
+    fn fold_field(&mut self, field: Field) -> Field {
+        walk_field(self, field)
+    }
+
+    fn fold_type_alias(&mut self, alias: TypeAlias) -> TypeAlias {
+        walk_type_alias(self, alias)
+    }
+
+    fn fold_type_expr(&mut self, ty: TypeExpr) -> TypeExpr {
+        walk_type_expr(self, ty)
+    }
+
+    fn fold_block(&mut self, block: Block) -> Block {
+        walk_block(self, block)
+    }
+
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        walk_stmt(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr(self, expr)
+    }
+
+    fn fold_field_init(&mut self, init: FieldInit) -> FieldInit {
+        walk_field_init(self, init)
+    }
+
+    fn fold_match_arm(&mut self, arm: MatchArm) -> MatchArm {
+        walk_match_arm(self, arm)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        walk_pattern(self, pattern)
+    }
+
+    fn fold_lit(&mut self, lit: Lit) -> Lit {
+        lit
+    }
+
+    fn fold_ident(&mut self, ident: Ident) -> Ident {
+        ident
+    }
+}
+
+/// Folds a boxed child, reusing its allocation.
+fn fold_box<F: Folder>(folder: &mut F, mut expr: Box<Expr>) -> Box<Expr> {
+    *expr = folder.fold_expr(*expr);
+    expr
+}
+
+fn fold_exprs<F: Folder>(folder: &mut F, exprs: Vec<Expr>) -> Vec<Expr> {
+    exprs.into_iter().map(|expr| folder.fold_expr(expr)).collect()
+}
+
+fn fold_contracts<F: Folder>(folder: &mut F, contracts: Vec<Contract>) -> Vec<Contract> {
+    contracts
+        .into_iter()
+        .map(|contract| Contract {
+            cond: folder.fold_expr(contract.cond),
+            ..contract
+        })
+        .collect()
+}
+
+pub fn walk_module<F: Folder>(folder: &mut F, module: Module) -> Module {
+    Module {
+        items: module.items.into_iter().map(|item| folder.fold_item(item)).collect(),
+        span: module.span,
+    }
+}
+
+pub fn walk_item<F: Folder>(folder: &mut F, item: Item) -> Item {
+    let kind = match item.kind {
+        ItemKind::Import(name) => ItemKind::Import(folder.fold_ident(name)),
+        ItemKind::Func(func) => ItemKind::Func(folder.fold_func(func)),
+        ItemKind::Struct(def) => ItemKind::Struct(folder.fold_struct_def(def)),
+        ItemKind::Enum(def) => ItemKind::Enum(folder.fold_enum_def(def)),
+        ItemKind::Type(alias) => ItemKind::Type(folder.fold_type_alias(alias)),
+        ItemKind::Error => ItemKind::Error,
+    };
+    Item { kind, span: item.span }
+}
+
+pub fn walk_func<F: Folder>(folder: &mut F, func: Func) -> Func {
+    Func {
+        name: folder.fold_ident(func.name),
+        params: func.params.into_iter().map(|param| folder.fold_param(param)).collect(),
+        ret: func.ret.map(|ret| folder.fold_type_expr(ret)),
+        contracts: fold_contracts(folder, func.contracts),
+        body: folder.fold_block(func.body),
+    }
+}
+
+pub fn walk_param<F: Folder>(folder: &mut F, param: Param) -> Param {
+    Param {
+        name: folder.fold_ident(param.name),
+        ty: param.ty.map(|ty| folder.fold_type_expr(ty)),
+        implicit: param.implicit,
+        span: param.span,
+    }
+}
+
+pub fn walk_struct_def<F: Folder>(folder: &mut F, def: StructDef) -> StructDef {
+    StructDef {
+        name: folder.fold_ident(def.name),
+        fields: def.fields.into_iter().map(|field| folder.fold_field(field)).collect(),
+    }
+}
+
+pub fn walk_enum_def<F: Folder>(folder: &mut F, def: EnumDef) -> EnumDef {
+    EnumDef {
+        name: folder.fold_ident(def.name),
+        params: def.params.into_iter().map(|param| folder.fold_param(param)).collect(),
+        variants: def
+            .variants
+            .into_iter()
+            .map(|variant| folder.fold_variant(variant))
+            .collect(),
+    }
+}
+
+pub fn walk_variant<F: Folder>(folder: &mut F, variant: Variant) -> Variant {
+    Variant {
+        name: folder.fold_ident(variant.name),
+        fields: variant
+            .fields
+            .into_iter()
+            .map(|field| folder.fold_field(field))
+            .collect(),
+        ret: variant.ret.map(|ty| folder.fold_type_expr(ty)),
+        span: variant.span,
+    }
+}
+
+pub fn walk_field<F: Folder>(folder: &mut F, field: Field) -> Field {
+    Field {
+        name: folder.fold_ident(field.name),
+        ty: field.ty.map(|ty| folder.fold_type_expr(ty)),
+        implicit: field.implicit,
+        span: field.span,
+    }
+}
+
+pub fn walk_type_alias<F: Folder>(folder: &mut F, alias: TypeAlias) -> TypeAlias {
+    TypeAlias {
+        name: folder.fold_ident(alias.name),
+        params: alias.params.into_iter().map(|param| folder.fold_ident(param)).collect(),
+        ty: folder.fold_type_expr(alias.ty),
+        refinement: alias.refinement.map(|refinement| folder.fold_expr(refinement)),
+    }
+}
+
+pub fn walk_type_expr<F: Folder>(folder: &mut F, ty: TypeExpr) -> TypeExpr {
+    let kind = match ty.kind {
+        TypeExprKind::Named { name, args } => TypeExprKind::Named {
+            name: folder.fold_ident(name),
+            args: args.into_iter().map(|arg| folder.fold_type_expr(arg)).collect(),
+        },
+        TypeExprKind::Function { params, ret } => TypeExprKind::Function {
+            params: params.into_iter().map(|param| folder.fold_type_expr(param)).collect(),
+            ret: Box::new(folder.fold_type_expr(*ret)),
+        },
+        TypeExprKind::Tuple(elements) => {
+            TypeExprKind::Tuple(elements.into_iter().map(|ty| folder.fold_type_expr(ty)).collect())
+        }
+        TypeExprKind::Bind { name, ty } => TypeExprKind::Bind {
+            name: folder.fold_ident(name),
+            ty: Box::new(folder.fold_type_expr(*ty)),
+        },
+        TypeExprKind::Expr(expr) => TypeExprKind::Expr(fold_box(folder, expr)),
+    };
+    TypeExpr { kind, span: ty.span }
+}
+
+pub fn walk_block<F: Folder>(folder: &mut F, block: Block) -> Block {
+    Block {
+        stmts: block.stmts.into_iter().map(|stmt| folder.fold_stmt(stmt)).collect(),
+        span: block.span,
+    }
+}
+
+pub fn walk_stmt<F: Folder>(folder: &mut F, stmt: Stmt) -> Stmt {
+    let kind = match stmt.kind {
+        StmtKind::Assign { target, ty, value } => StmtKind::Assign {
+            target: folder.fold_expr(target),
+            ty: ty.map(|ty| folder.fold_type_expr(ty)),
+            value: folder.fold_expr(value),
+        },
+        StmtKind::Expr(expr) => StmtKind::Expr(folder.fold_expr(expr)),
+        StmtKind::Return(value) => StmtKind::Return(value.map(|value| folder.fold_expr(value))),
+        StmtKind::If { branches, else_block } => StmtKind::If {
+            branches: branches
+                .into_iter()
+                .map(|(cond, block)| (folder.fold_expr(cond), folder.fold_block(block)))
+                .collect(),
+            else_block: else_block.map(|block| folder.fold_block(block)),
+        },
+        StmtKind::While { cond, contracts, body } => StmtKind::While {
+            cond: folder.fold_expr(cond),
+            contracts: fold_contracts(folder, contracts),
+            body: folder.fold_block(body),
+        },
+        StmtKind::For {
+            var,
+            iter,
+            contracts,
+            body,
+        } => StmtKind::For {
+            var: folder.fold_ident(var),
+            iter: folder.fold_expr(iter),
+            contracts: fold_contracts(folder, contracts),
+            body: folder.fold_block(body),
+        },
+        StmtKind::Error => StmtKind::Error,
+    };
+    Stmt { kind, span: stmt.span }
+}
+
+pub fn walk_expr<F: Folder>(folder: &mut F, expr: Expr) -> Expr {
+    let kind = match expr.kind {
+        ExprKind::Lit(lit) => ExprKind::Lit(folder.fold_lit(lit)),
+        ExprKind::Ident(name) => ExprKind::Ident(folder.fold_ident(name)),
+        ExprKind::List(items) => ExprKind::List(fold_exprs(folder, items)),
+        ExprKind::Tuple(items) => ExprKind::Tuple(fold_exprs(folder, items)),
+        ExprKind::Unary { op, expr } => ExprKind::Unary {
+            op,
+            expr: fold_box(folder, expr),
+        },
+        ExprKind::Binary { op, lhs, rhs } => ExprKind::Binary {
+            op,
+            lhs: fold_box(folder, lhs),
+            rhs: fold_box(folder, rhs),
+        },
+        ExprKind::Call { callee, args } => ExprKind::Call {
+            callee: fold_box(folder, callee),
+            args: fold_exprs(folder, args),
+        },
+        ExprKind::Field { expr, field } => ExprKind::Field {
+            expr: fold_box(folder, expr),
+            field: folder.fold_ident(field),
+        },
+        ExprKind::Index { expr, index } => ExprKind::Index {
+            expr: fold_box(folder, expr),
+            index: fold_box(folder, index),
+        },
+        ExprKind::Match { scrutinee, arms } => ExprKind::Match {
+            scrutinee: fold_box(folder, scrutinee),
+            arms: arms.into_iter().map(|arm| folder.fold_match_arm(arm)).collect(),
+        },
+        ExprKind::Struct { name, fields, base } => ExprKind::Struct {
+            name: folder.fold_ident(name),
+            fields: fields.into_iter().map(|field| folder.fold_field_init(field)).collect(),
+            base: base.map(|base| fold_box(folder, base)),
+        },
+        ExprKind::Lambda { params, body } => ExprKind::Lambda {
+            params: params.into_iter().map(|param| folder.fold_param(param)).collect(),
+            body: fold_box(folder, body),
+        },
+        ExprKind::Closure { func, captures } => ExprKind::Closure {
+            func: folder.fold_ident(func),
+            captures: captures
+                .into_iter()
+                .map(|capture| Capture {
+                    name: folder.fold_ident(capture.name),
+                    mode: capture.mode,
+                })
+                .collect(),
+        },
+        ExprKind::Hole(name) => ExprKind::Hole(name),
+        ExprKind::Error => ExprKind::Error,
+    };
+    Expr { kind, span: expr.span }
+}
+
+pub fn walk_field_init<F: Folder>(folder: &mut F, init: FieldInit) -> FieldInit {
+    FieldInit {
+        name: folder.fold_ident(init.name),
+        value: folder.fold_expr(init.value),
+        span: init.span,
+    }
+}
+
+pub fn walk_match_arm<F: Folder>(folder: &mut F, arm: MatchArm) -> MatchArm {
+    MatchArm {
+        pattern: folder.fold_pattern(arm.pattern),
+        guard: arm.guard.map(|guard| folder.fold_expr(guard)),
+        body: folder.fold_expr(arm.body),
+        span: arm.span,
+    }
+}
+
+pub fn walk_pattern<F: Folder>(folder: &mut F, pattern: Pattern) -> Pattern {
+    let kind = match pattern.kind {
+        PatternKind::Wildcard => PatternKind::Wildcard,
+        PatternKind::Error => PatternKind::Error,
+        PatternKind::Binding(name) => PatternKind::Binding(folder.fold_ident(name)),
+        PatternKind::Lit(lit) => PatternKind::Lit(folder.fold_lit(lit)),
+        PatternKind::Variant { name, args } => PatternKind::Variant {
+            name: folder.fold_ident(name),
+            args: args.into_iter().map(|arg| folder.fold_pattern(arg)).collect(),
+        },
+        PatternKind::Or(alternatives) => PatternKind::Or(
+            alternatives
+                .into_iter()
+                .map(|alternative| folder.fold_pattern(alternative))
+                .collect(),
+        ),
+    };
+    Pattern {
+        kind,
+        span: pattern.span,
+    }
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Typed holes: `?` or `?name` in place of an expression still to be
+//! written.
+//!
+//! A hole checks against any type, so the rest of the file is checked as
+//! if it were filled in. Each one is reported as a goal: the type it must
+//! have, the locals in scope with their types, and the terms in scope that
+//! would fit. Inference and `elab` find the goals in the functions each of
+//! them checks.
+
+use crate::dump::json_string;
+use flerry_core::span::line_col;
+use flerry_core::{Diagnostic, Span};
+
+/// What a hole must be filled in with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Goal {
+    /// The `name` of `?name`.
+    pub name: Option<String>,
+    pub span: Span,
+    /// The type the hole must have.
+    pub ty: String,
+    /// The locals in scope and their types, innermost last.
+    pub context: Vec<(String, String)>,
+    /// Terms in scope of the type, locals first; `f(_, _)` is a call.
+    pub candidates: Vec<String>,
+}
+
+impl Goal {
+    /// `?name`, or `?`.
+    pub fn hole(&self) -> String {
+        format!("?{}", self.name.as_deref().unwrap_or_default())
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let message = match &self.name {
+            Some(_) => format!("found hole `{}`", self.hole()),
+            None => "found a hole".to_owned(),
+        };
+        let mut diagnostic = Diagnostic::error(message, self.span)
+            .with_label(self.span, format!("expected a value of type `{}` here", self.ty))
+            .with_note(format!("the goal is `{} : {}`", self.hole(), self.ty));
+        if !self.context.is_empty() {
+            let context: Vec<String> = self.context.iter().map(|(name, ty)| format!("{name} : {ty}")).collect();
+            diagnostic = diagnostic.with_note(format!("in the context `{}`", context.join(", ")));
+        }
+        let candidates: Vec<String> = self.candidates.iter().map(|term| format!("`{term}`")).collect();
+        match candidates.as_slice() {
+            [] => diagnostic,
+            [one] => diagnostic.with_help(format!("{one} would fit here")),
+            [init @ .., last] => diagnostic.with_help(format!("{} and {last} would fit here", init.join(", "))),
+        }
+    }
+}
+
+/// The goals as a JSON array, one object per hole with its position in
+/// `source`, for editors to show inline.
+pub fn json(goals: &[Goal], source: &str) -> String {
+    let mut out = String::from("[");
+    for (i, goal) in goals.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        match &goal.name {
+            Some(name) => json_string(name, &mut out),
+            None => out.push_str("null"),
+        }
+        let (line, column) = line_col(source, goal.span.start);
+        out.push_str(&format!(
+            ",\"span\":[{},{}],\"line\":{line},\"column\":{column},\"type\":",
+            goal.span.start, goal.span.end
+        ));
+        json_string(&goal.ty, &mut out);
+        out.push_str(",\"context\":[");
+        for (i, (name, ty)) in goal.context.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            json_string(name, &mut out);
+            out.push_str(",\"type\":");
+            json_string(ty, &mut out);
+            out.push('}');
+        }
+        out.push_str("],\"candidates\":[");
+        for (i, candidate) in goal.candidates.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            json_string(candidate, &mut out);
+        }
+        out.push_str("]}");
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// A call to a global taking `arity` explicit arguments, as a candidate:
+/// `f(_, _)`.
+pub fn call(name: &str, arity: usize) -> String {
+    format!("{name}({})", vec!["_"; arity].join(", "))
+}
@@ -0,0 +1,1562 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Type inference: Hindley-Milner with let-polymorphism, so annotations are
+//! never required.
+//!
+//! Top-level functions are inferred in dependency order, each group of
+//! mutually recursive functions together, and then generalized: `func id(x)
+//! = return x end` gets the type `a -> a` and every call instantiates it
+//! afresh. A local bound once to a lambda is generalized the same way.
+//!
+//! Operators restrict the types they apply to to a class rather than to one
+//! type, so `func add(x, y) = return x + y end` is `(a, a) -> a where a: Num`
+//! and works on `Int` and `Float` alike. An integer literal is any number
+//! until something decides which; if nothing does, it is an `Int`. Once it
+//! is decided, a literal must fit in a fixed-width integer it turned out to
+//! be: `300` is not a `u8`.
+//!
+//! A mismatch is reported where the offending type was found, with the type
+//! that was expected and, where there is one, the place that made it
+//! expected. Mistakes other checkers already report, such as calling a
+//! variant with the wrong number of arguments, are left to them.
+//!
+//! Inductive families and the functions whose types mention them, a universe
+//! or a value are checked by `elab` instead; here their names have no known
+//! type.
+
+use crate::annotations::BUILTIN_TYPES;
+use crate::ast::*;
+use crate::elab::Dependent;
+use crate::holes::{self, Goal};
+use crate::types::{Aliases, Class, FIXED_WIDTH, Scheme, Ty};
+use crate::visit::{Visitor, walk_expr, walk_stmt};
+use flerry_core::{Diagnostic, Span};
+use std::collections::{HashMap, HashSet};
+
+/// The inferred type of every top-level function, in source order.
+pub struct Inference {
+    pub functions: Vec<(String, Scheme)>,
+    /// The goal of each hole, in the order the functions were inferred.
+    pub goals: Vec<Goal>,
+    /// The type of every field access's and method call's receiver, and of
+    /// every struct literal's base, by the span of that expression.
+    pub receivers: HashMap<Span, Ty>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub fn check(module: &Module) -> Vec<Diagnostic> {
+    infer(module).diagnostics
+}
+
+pub fn infer(module: &Module) -> Inference {
+    let dependent = Dependent::new(module);
+    let mut infer = Infer::new(module, &dependent);
+    let funcs: Vec<&Func> = module
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Func(func) if !dependent.functions.contains(func.name.name.as_str()) => Some(func),
+            _ => None,
+        })
+        .collect();
+    for group in infer.groups(&funcs) {
+        infer.infer_group(&group);
+    }
+    let functions = funcs
+        .iter()
+        .filter_map(|func| {
+            Some((
+                func.name.name.clone(),
+                infer.globals.get(func.name.name.as_str())?.clone(),
+            ))
+        })
+        .collect();
+    let receivers = infer
+        .receivers
+        .iter()
+        .map(|(span, ty)| (*span, infer.resolve(ty)))
+        .collect();
+    Inference {
+        functions,
+        goals: infer.goals,
+        receivers,
+        diagnostics: infer.diagnostics,
+    }
+}
+
+/// What the inference table knows about a type variable.
+struct VarState {
+    bound: Option<Ty>,
+    class: Option<Class>,
+    /// Where the type the variable was bound to came from.
+    origin: Option<Span>,
+}
+
+#[derive(Clone)]
+enum Binding {
+    Mono(Ty),
+    Poly(Scheme),
+}
+
+struct Local {
+    binding: Binding,
+    /// The annotation that fixed its type, if any.
+    declared: Option<Span>,
+    /// Where the name is bound.
+    bound: Span,
+}
+
+/// A hole in the functions being inferred, whose goal is reported once
+/// they are.
+struct Hole<'m> {
+    name: Option<&'m Ident>,
+    span: Span,
+    ty: Ty,
+    /// The locals bound before the hole, innermost last.
+    context: Vec<(&'m str, Binding)>,
+}
+
+enum Mismatch {
+    Types,
+    /// Binding a variable to a type containing it.
+    Infinite,
+}
+
+struct Infer<'m> {
+    vars: Vec<VarState>,
+    aliases: Aliases<'m>,
+    /// Every type in scope and the number of arguments it takes.
+    type_names: HashMap<&'m str, usize>,
+    globals: HashMap<&'m str, Scheme>,
+    functions: HashMap<&'m str, &'m Func>,
+    structs: HashMap<&'m str, &'m StructDef>,
+    variants: HashMap<&'m str, (&'m str, &'m Variant)>,
+    /// `(struct, method)` to the function implementing it.
+    methods: HashMap<(&'m str, &'m str), &'m str>,
+    imports: HashSet<&'m str>,
+    scopes: Vec<HashMap<&'m str, Local>>,
+    /// The current function's result type and its annotation.
+    ret: Option<(Ty, Option<Span>)>,
+    /// The types of the functions being inferred together, before they are
+    /// generalized.
+    group: Vec<Ty>,
+    /// Locals read before they are first assigned, which cannot be
+    /// generalized.
+    read: HashSet<&'m str>,
+    /// Locals of the current function assigned by exactly one statement.
+    assigned_once: HashSet<&'m str>,
+    /// Whether an `ensures` clause is being inferred, where `old(x)` has the
+    /// type of `x`.
+    ensures: bool,
+    holes: Vec<Hole<'m>>,
+    /// The integer literals of the functions being inferred, with their
+    /// values, negated where a minus sign is in front, and their types.
+    literals: Vec<(Span, i128, Ty)>,
+    goals: Vec<Goal>,
+    receivers: HashMap<Span, Ty>,
+    /// The field accesses of the functions being inferred that found no
+    /// field, with their receiver's type and whether they are called. They
+    /// are reported once the functions are, when the receiver's type is known.
+    missing_fields: Vec<(Ty, &'m Ident, bool)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// `1 argument was` or `2 arguments were`.
+fn supplied(n: usize) -> String {
+    match n {
+        1 => "1 argument was".to_owned(),
+        n => format!("{n} arguments were"),
+    }
+}
+
+/// The first parameter's declared type name, which makes a function a method.
+fn receiver_type(func: &Func) -> Option<&str> {
+    let TypeExprKind::Named { name, args } = &func.params.first()?.ty.as_ref()?.kind else {
+        return None;
+    };
+    args.is_empty().then_some(name.name.as_str())
+}
+
+/// Names of the top-level functions and methods `func` refers to.
+#[derive(Default)]
+struct References<'m> {
+    names: Vec<&'m str>,
+}
+
+impl<'m> Visitor<'m> for References<'m> {
+    fn visit_expr(&mut self, expr: &'m Expr) {
+        match &expr.kind {
+            ExprKind::Ident(name) => self.names.push(&name.name),
+            ExprKind::Field { field, .. } => self.names.push(&field.name),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// The locals of a function: every name assigned to, in order, with the
+/// first annotation given to each and the number of assignments.
+#[derive(Default)]
+struct Assigned<'m> {
+    names: Vec<(&'m Ident, Option<&'m TypeExpr>, usize)>,
+    returns_value: bool,
+    /// Whether a statement did not parse, which may have been a `return`.
+    recovered: bool,
+}
+
+impl<'m> Visitor<'m> for Assigned<'m> {
+    fn visit_stmt(&mut self, stmt: &'m Stmt) {
+        match &stmt.kind {
+            StmtKind::Assign { target, ty, .. } => {
+                if let ExprKind::Ident(name) = &target.kind {
+                    match self.names.iter_mut().find(|(n, ..)| n.name == name.name) {
+                        Some((_, declared, count)) => {
+                            *declared = declared.or(ty.as_ref());
+                            *count += 1;
+                        }
+                        None => self.names.push((name, ty.as_ref(), 1)),
+                    }
+                }
+            }
+            StmtKind::Return(Some(_)) => self.returns_value = true,
+            StmtKind::Error => self.recovered = true,
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    // Lambda bodies are expressions: they cannot assign or return.
+    fn visit_expr(&mut self, _expr: &'m Expr) {}
+}
+
+/// Type variable names for a scheme: `a`, `b`, ..., `z`, `a1`, ...
+fn param_name(index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => letter.to_string(),
+        n => format!("{letter}{n}"),
+    }
+}
+
+fn params_in(ty: &Ty, out: &mut HashSet<String>) {
+    match ty {
+        Ty::Param(name) => {
+            out.insert(name.clone());
+        }
+        Ty::Con(_, args) | Ty::Tuple(args) => args.iter().for_each(|arg| params_in(arg, out)),
+        Ty::Fn(params, ret) => {
+            params.iter().for_each(|param| params_in(param, out));
+            params_in(ret, out);
+        }
+        Ty::Var(_) | Ty::Unknown => {}
+    }
+}
+
+impl<'m> Infer<'m> {
+    fn new(module: &'m Module, dependent: &Dependent) -> Infer<'m> {
+        let mut infer = Infer {
+            vars: Vec::new(),
+            aliases: Aliases::new(module),
+            type_names: BUILTIN_TYPES.iter().copied().collect(),
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            variants: HashMap::new(),
+            methods: HashMap::new(),
+            imports: HashSet::new(),
+            scopes: Vec::new(),
+            ret: None,
+            group: Vec::new(),
+            read: HashSet::new(),
+            assigned_once: HashSet::new(),
+            ensures: false,
+            holes: Vec::new(),
+            literals: Vec::new(),
+            goals: Vec::new(),
+            receivers: HashMap::new(),
+            missing_fields: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+
+        let a = || Ty::Param("a".to_owned());
+        let prelude = [
+            ("print", Ty::Fn(vec![a()], Box::new(Ty::unit()))),
+            ("println", Ty::Fn(vec![a()], Box::new(Ty::unit()))),
+            ("read_line", Ty::Fn(Vec::new(), Box::new(Ty::str()))),
+        ];
+        for (name, ty) in prelude {
+            let mut params = HashSet::new();
+            params_in(&ty, &mut params);
+            let scheme = Scheme {
+                params: params.into_iter().map(|name| (name, None)).collect(),
+                ty,
+            };
+            infer.globals.insert(name, scheme);
+        }
+
+        for item in &module.items {
+            match &item.kind {
+                ItemKind::Struct(def) => {
+                    infer.type_names.insert(&def.name.name, 0);
+                    infer.structs.insert(&def.name.name, def);
+                }
+                // Families and the functions using them are checked with the
+                // core calculus instead.
+                ItemKind::Enum(def) if dependent.families.contains(def.name.name.as_str()) => {}
+                ItemKind::Func(func) if dependent.functions.contains(func.name.name.as_str()) => {}
+                ItemKind::Enum(def) => {
+                    infer.type_names.insert(&def.name.name, 0);
+                }
+                ItemKind::Func(func) => {
+                    infer.functions.insert(&func.name.name, func);
+                }
+                ItemKind::Import(name) => {
+                    infer.imports.insert(&name.name);
+                }
+                ItemKind::Type(_) | ItemKind::Error => {}
+            }
+        }
+        for item in &module.items {
+            match &item.kind {
+                ItemKind::Enum(def) if !dependent.families.contains(def.name.name.as_str()) => {
+                    for variant in &def.variants {
+                        infer.variants.insert(&variant.name.name, (&def.name.name, variant));
+                        let fields: Vec<Ty> = variant.fields.iter().map(|field| infer.field_type(field)).collect();
+                        let ty = if fields.is_empty() {
+                            Ty::named(&def.name.name)
+                        } else {
+                            Ty::Fn(fields, Box::new(Ty::named(&def.name.name)))
+                        };
+                        infer.globals.insert(&variant.name.name, Scheme::mono(ty));
+                    }
+                }
+                ItemKind::Func(func) => {
+                    if let Some(receiver) = receiver_type(func)
+                        && let Some((receiver, _)) = infer.structs.get_key_value(receiver)
+                    {
+                        infer.methods.insert((*receiver, &func.name.name), &func.name.name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        infer
+    }
+
+    fn lower(&self, ty: &TypeExpr) -> Ty {
+        self.aliases.lower(ty, &|name| self.type_names.get(name).copied())
+    }
+
+    /// A declared field's type; a field without one holds anything.
+    fn field_type(&self, field: &Field) -> Ty {
+        field.ty.as_ref().map_or(Ty::Unknown, |ty| self.lower(ty))
+    }
+
+    /// Splits the functions into groups that refer to each other, each group
+    /// after the groups it uses (Tarjan's algorithm).
+    fn groups(&self, funcs: &[&'m Func]) -> Vec<Vec<&'m Func>> {
+        let index_of: HashMap<&str, usize> = funcs
+            .iter()
+            .enumerate()
+            .map(|(i, func)| (func.name.name.as_str(), i))
+            .collect();
+        let edges: Vec<Vec<usize>> = funcs
+            .iter()
+            .map(|func| {
+                let mut references = References::default();
+                references.visit_block(&func.body);
+                let mut targets: Vec<usize> = references
+                    .names
+                    .iter()
+                    .filter_map(|name| index_of.get(name).copied())
+                    .collect();
+                targets.dedup();
+                targets
+            })
+            .collect();
+
+        struct Tarjan<'e> {
+            edges: &'e [Vec<usize>],
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            next: usize,
+            groups: Vec<Vec<usize>>,
+        }
+        impl Tarjan<'_> {
+            fn visit(&mut self, v: usize) {
+                self.index[v] = Some(self.next);
+                self.low[v] = self.next;
+                self.next += 1;
+                self.stack.push(v);
+                self.on_stack[v] = true;
+                for &w in &self.edges[v] {
+                    match self.index[w] {
+                        None => {
+                            self.visit(w);
+                            self.low[v] = self.low[v].min(self.low[w]);
+                        }
+                        Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+                        Some(_) => {}
+                    }
+                }
+                if Some(self.low[v]) == self.index[v] {
+                    let mut group = Vec::new();
+                    while let Some(w) = self.stack.pop() {
+                        self.on_stack[w] = false;
+                        group.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    group.sort_unstable();
+                    self.groups.push(group);
+                }
+            }
+        }
+
+        let mut tarjan = Tarjan {
+            edges: &edges,
+            index: vec![None; funcs.len()],
+            low: vec![0; funcs.len()],
+            stack: Vec::new(),
+            on_stack: vec![false; funcs.len()],
+            next: 0,
+            groups: Vec::new(),
+        };
+        for v in 0..funcs.len() {
+            if tarjan.index[v].is_none() {
+                tarjan.visit(v);
+            }
+        }
+        tarjan
+            .groups
+            .into_iter()
+            .map(|group| group.into_iter().map(|i| funcs[i]).collect())
+            .collect()
+    }
+
+    // The inference table
+
+    fn fresh(&mut self, class: Option<Class>) -> Ty {
+        self.vars.push(VarState {
+            bound: None,
+            class,
+            origin: None,
+        });
+        Ty::Var(self.vars.len() as u32 - 1)
+    }
+
+    /// Follows bound variables until a type that is not one.
+    fn shallow(&self, ty: &Ty) -> Ty {
+        let mut ty = ty.clone();
+        while let Ty::Var(id) = ty {
+            match &self.vars[id as usize].bound {
+                Some(bound) => ty = bound.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// `ty` with every bound variable replaced by what it is bound to.
+    fn resolve(&self, ty: &Ty) -> Ty {
+        match self.shallow(ty) {
+            Ty::Con(name, args) => Ty::Con(name, args.iter().map(|arg| self.resolve(arg)).collect()),
+            Ty::Fn(params, ret) => Ty::Fn(
+                params.iter().map(|param| self.resolve(param)).collect(),
+                Box::new(self.resolve(&ret)),
+            ),
+            Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(|ty| self.resolve(ty)).collect()),
+            ty => ty,
+        }
+    }
+
+    /// Where the type `ty` stands for was decided, if it is a variable that
+    /// has been bound.
+    fn origin_of(&self, ty: &Ty) -> Option<Span> {
+        let mut ty = ty.clone();
+        while let Ty::Var(id) = ty {
+            let var = &self.vars[id as usize];
+            if var.origin.is_some() {
+                return var.origin;
+            }
+            ty = var.bound.clone()?;
+        }
+        None
+    }
+
+    fn free_vars(&self, ty: &Ty, out: &mut Vec<u32>) {
+        match self.shallow(ty) {
+            Ty::Var(id) => {
+                if !out.contains(&id) {
+                    out.push(id);
+                }
+            }
+            Ty::Con(_, args) | Ty::Tuple(args) => args.iter().for_each(|arg| self.free_vars(arg, out)),
+            Ty::Fn(params, ret) => {
+                params.iter().for_each(|param| self.free_vars(param, out));
+                self.free_vars(&ret, out);
+            }
+            Ty::Param(_) | Ty::Unknown => {}
+        }
+    }
+
+    /// Makes `a` and `b` the same type. `b` was found at `span`, and `a` was
+    /// expected because of `origin`; binding a variable records which.
+    fn unify(&mut self, a: &Ty, b: &Ty, span: Span, origin: Option<Span>) -> Result<(), Mismatch> {
+        let (a, b) = (self.shallow(a), self.shallow(b));
+        match (&a, &b) {
+            (Ty::Unknown, _) | (_, Ty::Unknown) => Ok(()),
+            (Ty::Var(x), Ty::Var(y)) if x == y => Ok(()),
+            (Ty::Var(x), Ty::Var(y)) => {
+                let class = match (self.vars[*x as usize].class, self.vars[*y as usize].class) {
+                    (Some(c), Some(d)) => Some(c.meet(d)),
+                    (c, d) => c.or(d),
+                };
+                self.vars[*y as usize].class = class;
+                self.vars[*x as usize].bound = Some(b.clone());
+                self.vars[*x as usize].origin = Some(span);
+                Ok(())
+            }
+            (Ty::Var(x), _) => self.bind(*x, &b, Some(span)),
+            (_, Ty::Var(y)) => self.bind(*y, &a, origin),
+            (Ty::Param(p), Ty::Param(q)) if p == q => Ok(()),
+            (Ty::Con(p, xs), Ty::Con(q, ys)) if p == q && xs.len() == ys.len() => {
+                xs.iter().zip(ys).try_for_each(|(x, y)| self.unify(x, y, span, origin))
+            }
+            (Ty::Tuple(xs), Ty::Tuple(ys)) if xs.len() == ys.len() => {
+                xs.iter().zip(ys).try_for_each(|(x, y)| self.unify(x, y, span, origin))
+            }
+            (Ty::Fn(xs, r), Ty::Fn(ys, s)) if xs.len() == ys.len() => {
+                xs.iter()
+                    .zip(ys)
+                    .try_for_each(|(x, y)| self.unify(x, y, span, origin))?;
+                self.unify(r, s, span, origin)
+            }
+            _ => Err(Mismatch::Types),
+        }
+    }
+
+    fn bind(&mut self, id: u32, ty: &Ty, origin: Option<Span>) -> Result<(), Mismatch> {
+        let mut free = Vec::new();
+        self.free_vars(ty, &mut free);
+        if free.contains(&id) {
+            return Err(Mismatch::Infinite);
+        }
+        if let Some(class) = self.vars[id as usize].class
+            && !class.contains(ty)
+        {
+            return Err(Mismatch::Types);
+        }
+        self.vars[id as usize].bound = Some(ty.clone());
+        self.vars[id as usize].origin = origin;
+        Ok(())
+    }
+
+    /// How a type reads in a diagnostic: unresolved variables are `_`, or
+    /// the class they are restricted to at the top.
+    fn show(&self, ty: &Ty) -> String {
+        let ty = self.resolve(ty);
+        if let Ty::Var(id) = ty
+            && let Some(class) = self.vars[id as usize].class
+        {
+            return class.describe().to_owned();
+        }
+        format!("`{}`", self.written(&ty))
+    }
+
+    /// `ty` as it would be annotated, with `_` for unresolved variables.
+    fn written(&self, ty: &Ty) -> String {
+        let mut out = String::new();
+        self.resolve(ty)
+            .write(&mut out, &|_| "_".to_owned())
+            .expect("writing to a string");
+        out
+    }
+
+    /// What the table knows, to go back to with `restore`.
+    fn snapshot(&self) -> Vec<(Option<Ty>, Option<Class>, Option<Span>)> {
+        self.vars
+            .iter()
+            .map(|var| (var.bound.clone(), var.class, var.origin))
+            .collect()
+    }
+
+    /// Forgets the variables made and bound since `snapshot`.
+    fn restore(&mut self, snapshot: Vec<(Option<Ty>, Option<Class>, Option<Span>)>) {
+        self.vars.truncate(snapshot.len());
+        for (var, (bound, class, origin)) in self.vars.iter_mut().zip(snapshot) {
+            var.bound = bound;
+            var.class = class;
+            var.origin = origin;
+        }
+    }
+
+    /// Whether `found` could be used where `expected` is, leaving the table
+    /// as it was.
+    fn fits(&mut self, expected: &Ty, found: &Ty) -> bool {
+        let snapshot = self.snapshot();
+        let fits = self.unify(expected, found, Span::default(), None).is_ok();
+        self.restore(snapshot);
+        fits
+    }
+
+    /// Checks that `found`, the type of what is at `span`, is `expected`,
+    /// reporting a mismatch otherwise. `origin` is what made `expected`
+    /// expected, if it is not a variable that remembers that itself.
+    fn expect(&mut self, expected: &Ty, found: &Ty, span: Span, origin: Option<Span>) -> bool {
+        let origin = origin.or_else(|| self.origin_of(expected));
+        // Shown as they were before the failed attempt bound anything.
+        let (shown_expected, shown_found) = (self.show(expected), self.show(found));
+        let snapshot = self.snapshot();
+        let mismatch = match self.unify(expected, found, span, origin) {
+            Ok(()) => return true,
+            Err(mismatch) => mismatch,
+        };
+        // Leave the table as it was, so the error does not spread.
+        self.restore(snapshot);
+
+        let diagnostic = match mismatch {
+            Mismatch::Types => Diagnostic::error("mismatched types", span)
+                .with_label(span, format!("expected {shown_expected}, found {shown_found}")),
+            Mismatch::Infinite => Diagnostic::error("cannot build an infinite type", span).with_label(
+                span,
+                format!("expected {shown_expected}, found {shown_found}, which contains it"),
+            ),
+        };
+        let diagnostic = match origin.filter(|origin| *origin != span) {
+            Some(origin) => diagnostic.with_label(origin, "expected due to this"),
+            None => diagnostic,
+        };
+        self.diagnostics.push(diagnostic);
+        false
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Ty {
+        let substitution: HashMap<String, Ty> = scheme
+            .params
+            .iter()
+            .map(|(name, class)| (name.clone(), self.fresh(*class)))
+            .collect();
+        scheme.ty.substitute(&substitution)
+    }
+
+    /// Quantifies `ty` over its variables that are not in `fixed`. The table
+    /// is left alone: the variables may still be used monomorphically.
+    fn generalize(&self, ty: &Ty, fixed: &[u32]) -> Scheme {
+        let ty = self.resolve(ty);
+        let mut vars = Vec::new();
+        self.free_vars(&ty, &mut vars);
+        vars.retain(|var| !fixed.contains(var));
+
+        let mut taken = HashSet::new();
+        params_in(&ty, &mut taken);
+        let mut names = (0..).map(param_name).filter(|name| !taken.contains(name));
+        let mut substitution = HashMap::new();
+        let mut params = Vec::new();
+        for var in vars {
+            let name = names.next().expect("names never run out");
+            substitution.insert(var, Ty::Param(name.clone()));
+            params.push((name, self.vars[var as usize].class));
+        }
+        // Annotated type variables are quantified too.
+        let mut annotated: Vec<String> = taken.into_iter().collect();
+        annotated.sort();
+        params.extend(annotated.into_iter().map(|name| (name, None)));
+        Scheme {
+            params,
+            ty: replace_vars(&ty, &substitution),
+        }
+    }
+
+    // Functions
+
+    fn infer_group(&mut self, group: &[&'m Func]) {
+        let first_var = self.vars.len();
+        self.group = group
+            .iter()
+            .map(|func| {
+                let params = func
+                    .params
+                    .iter()
+                    .map(|param| match &param.ty {
+                        Some(ty) => self.lower(ty),
+                        None => self.fresh(None),
+                    })
+                    .collect();
+                let ret = match &func.ret {
+                    Some(ty) => self.lower(ty),
+                    None => self.fresh(None),
+                };
+                Ty::Fn(params, Box::new(ret))
+            })
+            .collect();
+        for (func, ty) in group.iter().zip(self.group.clone()) {
+            self.globals.insert(&func.name.name, Scheme::mono(ty));
+        }
+        for (func, ty) in group.iter().zip(self.group.clone()) {
+            self.infer_func(func, &ty);
+        }
+
+        // A number only the result mentions would be decided by every caller
+        // differently; it is defaulted instead, so `1` is an `Int`.
+        for ty in self.group.clone() {
+            let Ty::Fn(params, ret) = ty else { continue };
+            let mut in_params = Vec::new();
+            params.iter().for_each(|param| self.free_vars(param, &mut in_params));
+            let mut in_ret = Vec::new();
+            self.free_vars(&ret, &mut in_ret);
+            for id in in_ret.into_iter().filter(|id| !in_params.contains(id)) {
+                if let Some(class) = self.vars[id as usize].class {
+                    self.vars[id as usize].bound = Some(Ty::named(class.members()[0]));
+                }
+            }
+        }
+        let schemes: Vec<Scheme> = self.group.iter().map(|ty| self.generalize(ty, &[])).collect();
+        for (func, scheme) in group.iter().zip(schemes) {
+            self.globals.insert(&func.name.name, scheme);
+        }
+        // So does any number left undecided inside the bodies.
+        for id in first_var..self.vars.len() {
+            let var = &self.vars[id];
+            if var.bound.is_none()
+                && let Some(class) = var.class
+            {
+                self.vars[id].bound = Some(Ty::named(class.members()[0]));
+            }
+        }
+        self.group.clear();
+        for (span, value, ty) in std::mem::take(&mut self.literals) {
+            self.literal_fits(span, value, &ty);
+        }
+        for hole in std::mem::take(&mut self.holes) {
+            let goal = self.goal(hole);
+            self.goals.push(goal);
+        }
+        for (receiver, field, called) in std::mem::take(&mut self.missing_fields) {
+            self.no_field(&receiver, field, called);
+        }
+    }
+
+    /// Reports a field access that found no field, unless its receiver's type
+    /// is still unknown. Structs lacking the field are reported with the struct.
+    fn no_field(&mut self, receiver: &Ty, field: &Ident, called: bool) {
+        let receiver = self.resolve(receiver);
+        match &receiver {
+            Ty::Var(_) | Ty::Unknown => return,
+            Ty::Con(name, args) if args.is_empty() && self.structs.contains_key(name.as_str()) => return,
+            _ if self.field(&receiver, field).is_some() => return,
+            _ => {}
+        }
+        let shown = self.show(&receiver);
+        let diagnostic = if called {
+            Diagnostic::error(
+                format!("no method named `{}` found for type {shown}", field.name),
+                field.span,
+            )
+            .with_label(field.span, format!("method not found in {shown}"))
+        } else {
+            Diagnostic::error(format!("no field `{}` on type {shown}", field.name), field.span)
+                .with_label(field.span, "unknown field")
+        };
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Reports an integer literal that does not fit in the fixed-width
+    /// integer type it has.
+    fn literal_fits(&mut self, span: Span, value: i128, ty: &Ty) {
+        let ty = self.resolve(ty);
+        let Some(&(name, width, signed)) = FIXED_WIDTH.iter().find(|(name, _, _)| ty == Ty::named(name)) else {
+            return;
+        };
+        let (min, max) = if signed {
+            (-(1 << (width - 1)), (1 << (width - 1)) - 1)
+        } else {
+            (0, (1 << width) - 1)
+        };
+        if (min..=max).contains(&value) {
+            return;
+        }
+        self.diagnostics.push(
+            Diagnostic::error(format!("literal out of range for `{name}`"), span)
+                .with_label(span, format!("`{value}` does not fit in the type `{name}`"))
+                .with_note(format!("the range of `{name}` is `{min}..={max}`")),
+        );
+    }
+
+    /// What `hole` must be filled in with, now its type is known.
+    fn goal(&mut self, hole: Hole<'m>) -> Goal {
+        let context = hole
+            .context
+            .iter()
+            .map(|(name, binding)| {
+                let ty = match binding {
+                    Binding::Mono(ty) => self.written(ty),
+                    Binding::Poly(scheme) => scheme.to_string(),
+                };
+                (name.to_string(), ty)
+            })
+            .collect();
+
+        // Anything fits a hole whose type nothing decided.
+        let mut candidates = Vec::new();
+        if !matches!(self.resolve(&hole.ty), Ty::Var(_) | Ty::Unknown) {
+            for (name, binding) in hole.context.iter().rev() {
+                let ty = match binding {
+                    Binding::Mono(ty) => ty.clone(),
+                    Binding::Poly(scheme) => self.instantiate(scheme),
+                };
+                if self.fits(&hole.ty, &ty) && !candidates.contains(&name.to_string()) {
+                    candidates.push(name.to_string());
+                }
+            }
+            let mut globals: Vec<(&'m str, Scheme)> = self
+                .globals
+                .iter()
+                .filter(|(name, _)| !hole.context.iter().any(|(local, _)| local == *name))
+                .map(|(name, scheme)| (*name, scheme.clone()))
+                .collect();
+            globals.sort_by_key(|(name, _)| *name);
+            for (name, scheme) in globals {
+                let snapshot = self.snapshot();
+                let ty = self.instantiate(&scheme);
+                if self.fits(&hole.ty, &ty) {
+                    candidates.push(name.to_owned());
+                } else if let Ty::Fn(params, ret) = &ty
+                    && self.fits(&hole.ty, ret)
+                {
+                    candidates.push(holes::call(name, params.len()));
+                }
+                self.restore(snapshot);
+            }
+        }
+        Goal {
+            name: hole.name.map(|name| name.name.clone()),
+            span: hole.span,
+            ty: self.written(&hole.ty),
+            context,
+            candidates,
+        }
+    }
+
+    fn infer_func(&mut self, func: &'m Func, ty: &Ty) {
+        let Ty::Fn(params, ret) = ty else {
+            unreachable!("functions have function types");
+        };
+        let mut scope = HashMap::new();
+        for (param, ty) in func.params.iter().zip(params) {
+            scope.insert(
+                param.name.name.as_str(),
+                Local {
+                    binding: Binding::Mono(ty.clone()),
+                    declared: param.ty.as_ref().map(|ty| ty.span),
+                    bound: param.name.span,
+                },
+            );
+        }
+        let mut assigned = Assigned::default();
+        assigned.visit_block(&func.body);
+        self.assigned_once.clear();
+        for (name, declared, count) in &assigned.names {
+            if scope.contains_key(name.name.as_str()) {
+                continue;
+            }
+            if *count == 1 {
+                self.assigned_once.insert(&name.name);
+            }
+            let ty = match declared {
+                Some(ty) => self.lower(ty),
+                None => self.fresh(None),
+            };
+            scope.insert(
+                &name.name,
+                Local {
+                    binding: Binding::Mono(ty),
+                    declared: declared.map(|ty| ty.span),
+                    bound: name.span,
+                },
+            );
+        }
+
+        self.scopes = vec![scope];
+        self.read.clear();
+        let declared = func.ret.as_ref().map(|ty| ty.span);
+        self.ret = Some((ret.as_ref().clone(), declared));
+        for contract in &func.contracts {
+            let mut scope = HashMap::new();
+            self.ensures = contract.kind == ContractKind::Ensures;
+            if self.ensures {
+                let local = Local {
+                    binding: Binding::Mono(ret.as_ref().clone()),
+                    declared,
+                    bound: func.name.span,
+                };
+                scope.insert("result", local);
+            }
+            self.in_scope(scope, |infer| infer.condition(&contract.cond));
+            self.ensures = false;
+        }
+        self.block(&func.body);
+
+        // Falling off the end returns nothing.
+        if !assigned.returns_value
+            && !assigned.recovered
+            && self.unify(ret, &Ty::unit(), func.name.span, declared).is_err()
+            && let Some(declared) = declared
+        {
+            self.diagnostics.push(
+                Diagnostic::error("mismatched types", declared)
+                    .with_label(declared, format!("expected {}, found `()`", self.show(ret)))
+                    .with_label(func.name.span, "implicitly returns `()` as its body has no `return`"),
+            );
+        }
+        self.ret = None;
+        self.scopes.clear();
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Local> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The variables the types of the enclosing locals and functions use,
+    /// which a local's type must not be generalized over.
+    fn fixed_vars(&self, except: &str) -> Vec<u32> {
+        let mut fixed = Vec::new();
+        for scope in &self.scopes {
+            for (name, local) in scope {
+                if *name != except
+                    && let Binding::Mono(ty) = &local.binding
+                {
+                    self.free_vars(ty, &mut fixed);
+                }
+            }
+        }
+        if let Some((ret, _)) = &self.ret {
+            self.free_vars(ret, &mut fixed);
+        }
+        for ty in &self.group {
+            self.free_vars(ty, &mut fixed);
+        }
+        fixed
+    }
+
+    // Statements
+
+    fn block(&mut self, block: &'m Block) {
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'m Stmt) {
+        match &stmt.kind {
+            StmtKind::Assign { target, ty, value } => self.assign(target, ty.as_ref(), value),
+            StmtKind::Expr(expr) => {
+                self.expr(expr);
+            }
+            StmtKind::Return(value) => {
+                let (found, span) = match value {
+                    Some(value) => (self.expr(value), value.span),
+                    None => (Ty::unit(), stmt.span),
+                };
+                if let Some((ret, declared)) = self.ret.clone() {
+                    self.expect(&ret, &found, span, declared);
+                }
+            }
+            StmtKind::If { branches, else_block } => {
+                for (cond, block) in branches {
+                    self.condition(cond);
+                    self.in_scope(HashMap::new(), |infer| infer.block(block));
+                }
+                if let Some(block) = else_block {
+                    self.in_scope(HashMap::new(), |infer| infer.block(block));
+                }
+            }
+            StmtKind::While { cond, contracts, body } => {
+                self.condition(cond);
+                self.loop_contracts(contracts);
+                self.in_scope(HashMap::new(), |infer| infer.block(body));
+            }
+            StmtKind::For {
+                var,
+                iter,
+                contracts,
+                body,
+            } => {
+                let found = self.expr(iter);
+                let element = self.fresh(None);
+                self.expect(&Ty::list(element.clone()), &found, iter.span, None);
+                self.loop_contracts(contracts);
+                let scope = HashMap::from([(
+                    var.name.as_str(),
+                    Local {
+                        binding: Binding::Mono(element),
+                        declared: None,
+                        bound: var.span,
+                    },
+                )]);
+                self.in_scope(scope, |infer| infer.block(body));
+            }
+            StmtKind::Error => {}
+        }
+    }
+
+    fn in_scope(&mut self, scope: HashMap<&'m str, Local>, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(scope);
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn condition(&mut self, cond: &'m Expr) {
+        let found = self.expr(cond);
+        self.expect(&Ty::bool(), &found, cond.span, None);
+    }
+
+    /// An `invariant` is a condition, and a `decreases` measure a number.
+    fn loop_contracts(&mut self, contracts: &'m [Contract]) {
+        for contract in contracts {
+            if contract.kind == ContractKind::Decreases {
+                let found = self.expr(&contract.cond);
+                let measure = self.fresh(Some(Class::Num));
+                self.expect(&measure, &found, contract.cond.span, None);
+            } else {
+                self.condition(&contract.cond);
+            }
+        }
+    }
+
+    fn assign(&mut self, target: &'m Expr, ty: Option<&'m TypeExpr>, value: &'m Expr) {
+        let ExprKind::Ident(name) = &target.kind else {
+            let expected = self.expr(target);
+            let found = self.expr(value);
+            self.expect(&expected, &found, value.span, None);
+            return;
+        };
+        let Some(local) = self.lookup(&name.name) else {
+            self.expr(value);
+            return;
+        };
+        let Binding::Mono(expected) = &local.binding else {
+            // A generalized local is only ever assigned once.
+            self.expr(value);
+            return;
+        };
+        let (expected, declared) = (expected.clone(), local.declared);
+        if let Some(ty) = ty {
+            let annotated = self.lower(ty);
+            self.expect(&expected, &annotated, ty.span, declared);
+        }
+        let found = self.expr(value);
+        if !self.expect(&expected, &found, value.span, declared) {
+            return;
+        }
+
+        // Let-polymorphism: a lambda bound once, and not read before then, can
+        // be used at many types.
+        if matches!(value.kind, ExprKind::Lambda { .. })
+            && !self.read.contains(name.name.as_str())
+            && self.assigned_once.contains(name.name.as_str())
+        {
+            let fixed = self.fixed_vars(&name.name);
+            let scheme = self.generalize(&found, &fixed);
+            if !scheme.params.is_empty()
+                && let Some(local) = self
+                    .scopes
+                    .iter_mut()
+                    .rev()
+                    .find_map(|scope| scope.get_mut(name.name.as_str()))
+            {
+                local.binding = Binding::Poly(scheme);
+            }
+        }
+    }
+
+    // Expressions
+
+    fn expr(&mut self, expr: &'m Expr) -> Ty {
+        match &expr.kind {
+            ExprKind::Lit(Lit::Int(value)) => {
+                let ty = self.fresh(Some(Class::Num));
+                self.literals.push((expr.span, (*value).into(), ty.clone()));
+                ty
+            }
+            ExprKind::Lit(lit) => Ty::of_literal(lit),
+            ExprKind::Ident(name) => self.name(name),
+            ExprKind::List(items) => {
+                let element = self.fresh(None);
+                for item in items {
+                    let found = self.expr(item);
+                    self.expect(&element, &found, item.span, None);
+                }
+                Ty::list(element)
+            }
+            ExprKind::Tuple(items) => Ty::Tuple(items.iter().map(|item| self.expr(item)).collect()),
+            ExprKind::Unary { op, expr: operand } => {
+                let found = self.expr(operand);
+                // `-128` is an `i8`, though `128` on its own is not.
+                if *op == UnaryOp::Neg
+                    && let ExprKind::Lit(Lit::Int(value)) = operand.kind
+                    && let Some(literal) = self.literals.last_mut()
+                {
+                    *literal = (expr.span, -i128::from(value), literal.2.clone());
+                }
+                let expected = match op {
+                    UnaryOp::Neg => self.fresh(Some(Class::Num)),
+                    UnaryOp::Not => Ty::bool(),
+                };
+                self.expect(&expected, &found, operand.span, None);
+                expected
+            }
+            ExprKind::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs),
+            ExprKind::Call { callee, args } => match (&callee.kind, args.as_slice()) {
+                (ExprKind::Ident(name), [arg]) if name.name == "old" && self.ensures => self.expr(arg),
+                _ => self.call(expr, callee, args),
+            },
+            ExprKind::Field { expr: receiver, field } => self.field_access(receiver, field, false),
+            ExprKind::Index { expr: list, index } => {
+                let found = self.expr(list);
+                let element = self.fresh(None);
+                self.expect(&Ty::list(element.clone()), &found, list.span, None);
+                let found = self.expr(index);
+                self.expect(&Ty::int(), &found, index.span, None);
+                element
+            }
+            ExprKind::Match { scrutinee, arms } => self.match_expr(scrutinee, arms),
+            ExprKind::Struct { name, fields, base } => self.struct_literal(name, fields, base.as_deref()),
+            ExprKind::Lambda { params, body } => {
+                let mut scope = HashMap::new();
+                let mut types = Vec::new();
+                for param in params {
+                    let ty = match &param.ty {
+                        Some(ty) => self.lower(ty),
+                        None => self.fresh(None),
+                    };
+                    types.push(ty.clone());
+                    scope.insert(
+                        param.name.name.as_str(),
+                        Local {
+                            binding: Binding::Mono(ty),
+                            declared: param.ty.as_ref().map(|ty| ty.span),
+                            bound: param.name.span,
+                        },
+                    );
+                }
+                let mut ret = Ty::Unknown;
+                self.in_scope(scope, |infer| ret = infer.expr(body));
+                Ty::Fn(types, Box::new(ret))
+            }
+            ExprKind::Hole(name) => {
+                let ty = self.fresh(None);
+                let context = self.context(expr.span);
+                self.holes.push(Hole {
+                    name: name.as_ref(),
+                    span: expr.span,
+                    ty: ty.clone(),
+                    context,
+                });
+                ty
+            }
+            ExprKind::Closure { .. } | ExprKind::Error => Ty::Unknown,
+        }
+    }
+
+    /// The locals bound before `span`, each scope in the order they are
+    /// bound, less those an inner one hides.
+    fn context(&self, span: Span) -> Vec<(&'m str, Binding)> {
+        let mut context: Vec<(&'m str, Binding)> = Vec::new();
+        for scope in &self.scopes {
+            let mut locals: Vec<(&&'m str, &Local)> = scope
+                .iter()
+                .filter(|(_, local)| local.bound.start < span.start)
+                .collect();
+            locals.sort_by_key(|(_, local)| local.bound.start);
+            for (name, local) in locals {
+                context.retain(|(bound, _)| bound != name);
+                context.push((name, local.binding.clone()));
+            }
+        }
+        context
+    }
+
+    fn name(&mut self, name: &'m Ident) -> Ty {
+        self.read.insert(&name.name);
+        match self.lookup(&name.name).map(|local| &local.binding) {
+            Some(Binding::Mono(ty)) => return ty.clone(),
+            Some(Binding::Poly(scheme)) => {
+                let scheme = scheme.clone();
+                return self.instantiate(&scheme);
+            }
+            None => {}
+        }
+        match self.globals.get(name.name.as_str()).cloned() {
+            Some(scheme) => self.instantiate(&scheme),
+            // Unknown names are reported by name resolution.
+            None => Ty::Unknown,
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: &'m Expr, rhs: &'m Expr) -> Ty {
+        let operand = match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => self.fresh(Some(Class::Num)),
+            BinaryOp::Rem | BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::Shl | BinaryOp::Shr => {
+                self.fresh(Some(Class::Integral))
+            }
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => self.fresh(Some(Class::Ord)),
+            BinaryOp::Eq | BinaryOp::Ne => self.fresh(None),
+            BinaryOp::And | BinaryOp::Or | BinaryOp::Implies => Ty::bool(),
+        };
+        let found = self.expr(lhs);
+        // `a < b < c` is reported as a chained comparison.
+        let comparison = |op: &BinaryOp| {
+            matches!(
+                op,
+                BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+            )
+        };
+        let chained = comparison(&op) && matches!(&lhs.kind, ExprKind::Binary { op, .. } if comparison(op));
+        if !chained {
+            self.expect(&operand, &found, lhs.span, None);
+        }
+        let found = self.expr(rhs);
+        self.expect(&operand, &found, rhs.span, None);
+        match op {
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge
+            | BinaryOp::And
+            | BinaryOp::Or
+            | BinaryOp::Implies => Ty::bool(),
+            _ => operand,
+        }
+    }
+
+    fn call(&mut self, call: &'m Expr, callee: &'m Expr, args: &'m [Expr]) -> Ty {
+        // A variant called with the wrong number of arguments is reported
+        // with the variant.
+        if let ExprKind::Ident(name) = &callee.kind
+            && self.lookup(&name.name).is_none()
+            && let Some((enum_name, variant)) = self.variants.get(name.name.as_str()).copied()
+            && variant.fields.len() != args.len()
+        {
+            args.iter().for_each(|arg| {
+                self.expr(arg);
+            });
+            return Ty::named(enum_name);
+        }
+
+        let callee_ty = match &callee.kind {
+            ExprKind::Field { expr: receiver, field } => match self.method(receiver, field, args.len()) {
+                Some(Some(method)) => {
+                    let Ty::Fn(params, ret) = method else {
+                        unreachable!("methods are functions");
+                    };
+                    let receiver_ty = self.receiver(receiver);
+                    self.expect(&params[0], &receiver_ty, receiver.span, None);
+                    self.arguments(&params[1..], args);
+                    return *ret;
+                }
+                // The wrong number of arguments, reported with the struct.
+                Some(None) => {
+                    self.receiver(receiver);
+                    args.iter().for_each(|arg| {
+                        self.expr(arg);
+                    });
+                    return Ty::Unknown;
+                }
+                None => self.field_access(receiver, field, true),
+            },
+            _ => self.expr(callee),
+        };
+
+        match self.shallow(&callee_ty) {
+            Ty::Fn(params, ret) => {
+                if params.len() != args.len() {
+                    let mut diagnostic = Diagnostic::error(
+                        format!(
+                            "this function takes {} but {} supplied",
+                            match params.len() {
+                                1 => "1 argument".to_owned(),
+                                n => format!("{n} arguments"),
+                            },
+                            supplied(args.len())
+                        ),
+                        call.span,
+                    );
+                    if let ExprKind::Ident(name) = &callee.kind
+                        && self.lookup(&name.name).is_none()
+                        && let Some(func) = self.functions.get(name.name.as_str())
+                    {
+                        diagnostic = diagnostic.with_label(func.name.span, format!("`{}` defined here", name.name));
+                    }
+                    self.diagnostics.push(diagnostic);
+                    args.iter().for_each(|arg| {
+                        self.expr(arg);
+                    });
+                    return *ret;
+                }
+                self.arguments(&params, args);
+                *ret
+            }
+            Ty::Var(_) => {
+                let found: Vec<Ty> = args.iter().map(|arg| self.expr(arg)).collect();
+                let ret = self.fresh(None);
+                let expected = Ty::Fn(found, Box::new(ret.clone()));
+                self.expect(&expected, &callee_ty, callee.span, None);
+                ret
+            }
+            Ty::Unknown => {
+                args.iter().for_each(|arg| {
+                    self.expr(arg);
+                });
+                Ty::Unknown
+            }
+            _ => {
+                let shown = self.show(&callee_ty);
+                self.diagnostics.push(
+                    Diagnostic::error(format!("expected function, found {shown}"), callee.span)
+                        .with_label(call.span, "call expression requires a function"),
+                );
+                args.iter().for_each(|arg| {
+                    self.expr(arg);
+                });
+                Ty::Unknown
+            }
+        }
+    }
+
+    fn arguments(&mut self, params: &[Ty], args: &'m [Expr]) {
+        for (param, arg) in params.iter().zip(args) {
+            let found = self.expr(arg);
+            self.expect(param, &found, arg.span, None);
+        }
+    }
+
+    /// The type of the method `receiver.name(...)` calls with `supplied`
+    /// arguments: `None` if it is not a method call, `Some(None)` if the
+    /// arguments do not fit.
+    fn method(&mut self, receiver: &'m Expr, name: &Ident, supplied: usize) -> Option<Option<Ty>> {
+        // `IO.print(x)` calls a function of a module.
+        if let ExprKind::Ident(module) = &receiver.kind
+            && self.lookup(&module.name).is_none()
+            && self.imports.contains(module.name.as_str())
+        {
+            return None;
+        }
+        let receiver_ty = self.peek_type(receiver)?;
+        let Ty::Con(struct_name, args) = self.resolve(&receiver_ty) else {
+            return None;
+        };
+        if !args.is_empty() {
+            return None;
+        }
+        let func = *self.methods.get(&(struct_name.as_str(), name.name.as_str()))?;
+        let scheme = self.globals.get(func)?.clone();
+        let method = self.instantiate(&scheme);
+        match &method {
+            Ty::Fn(params, _) if params.len() == supplied + 1 => Some(Some(method)),
+            _ => Some(None),
+        }
+    }
+
+    /// The type of `expr` if it can be had without inferring anything: a
+    /// local, or a field chain on one.
+    fn peek_type(&mut self, expr: &'m Expr) -> Option<Ty> {
+        match &expr.kind {
+            ExprKind::Ident(name) => match &self.lookup(&name.name)?.binding {
+                Binding::Mono(ty) => Some(ty.clone()),
+                Binding::Poly(_) => None,
+            },
+            ExprKind::Field { expr, field } => {
+                let receiver = self.peek_type(expr)?;
+                self.field(&receiver, field)
+            }
+            ExprKind::Struct { name, .. } if self.structs.contains_key(name.name.as_str()) => {
+                Some(Ty::named(&name.name))
+            }
+            ExprKind::Call { callee, .. } => {
+                let ExprKind::Ident(name) = &callee.kind else {
+                    return None;
+                };
+                if self.lookup(&name.name).is_some() {
+                    return None;
+                }
+                let scheme = self.globals.get(name.name.as_str())?.clone();
+                match self.instantiate(&scheme) {
+                    Ty::Fn(_, ret) => Some(*ret),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Infers `expr`, remembering its type for the struct checks.
+    fn receiver(&mut self, expr: &'m Expr) -> Ty {
+        let ty = self.expr(expr);
+        self.receivers.insert(expr.span, ty.clone());
+        ty
+    }
+
+    /// The type of `receiver.field`. A field that cannot be found is `Unknown`,
+    /// and reported once the receiver's type is known.
+    fn field_access(&mut self, receiver: &'m Expr, field: &'m Ident, called: bool) -> Ty {
+        let receiver = self.receiver(receiver);
+        match self.field(&receiver, field) {
+            Some(ty) => ty,
+            None => {
+                self.missing_fields.push((receiver, field, called));
+                Ty::Unknown
+            }
+        }
+    }
+
+    /// The type of `field` on a value of type `receiver`, when that is known.
+    fn field(&mut self, receiver: &Ty, field: &Ident) -> Option<Ty> {
+        let (name, args) = match self.resolve(receiver) {
+            Ty::Con(name, args) => (name, args),
+            Ty::Tuple(elements) => return elements.get(field.name.parse::<usize>().ok()?).cloned(),
+            _ => return None,
+        };
+        let def = self.structs.get(name.as_str()).filter(|_| args.is_empty())?;
+        let field = def.fields.iter().find(|f| f.name.name == field.name)?;
+        Some(self.field_type(field))
+    }
+
+    fn struct_literal(&mut self, name: &Ident, fields: &'m [FieldInit], base: Option<&'m Expr>) -> Ty {
+        let def = self.structs.get(name.name.as_str()).copied();
+        for init in fields {
+            let found = self.expr(&init.value);
+            let Some(field) = def.and_then(|def| def.fields.iter().find(|f| f.name.name == init.name.name)) else {
+                continue;
+            };
+            if let Some(annotation) = &field.ty {
+                let expected = self.lower(annotation);
+                self.expect(&expected, &found, init.value.span, Some(annotation.span));
+            }
+        }
+        if let Some(base) = base {
+            // A base of the wrong struct is reported with the struct.
+            let found = self.receiver(base);
+            if def.is_some() {
+                let _ = self.unify(&Ty::named(&name.name), &found, base.span, None);
+            }
+        }
+        match def {
+            Some(_) => Ty::named(&name.name),
+            None => Ty::Unknown,
+        }
+    }
+
+    fn match_expr(&mut self, scrutinee: &'m Expr, arms: &'m [MatchArm]) -> Ty {
+        let expected = self.expr(scrutinee);
+        let result = self.fresh(None);
+        // Patterns that disagree with each other are reported with the
+        // patterns; here only the first that has a type is checked against
+        // the scrutinee.
+        let mut first = true;
+        for arm in arms {
+            let mut scope = HashMap::new();
+            if self.pattern(&arm.pattern, &expected, &mut scope, first.then_some(scrutinee)) {
+                first = false;
+            }
+            self.in_scope(scope, |infer| {
+                if let Some(guard) = &arm.guard {
+                    infer.condition(guard);
+                }
+                let found = infer.expr(&arm.body);
+                infer.expect(&result, &found, arm.body.span, None);
+            });
+        }
+        result
+    }
+
+    /// Binds the names in `pattern`, which matches values of type
+    /// `expected`, and returns whether the pattern has a type of its own.
+    /// A mismatch is only reported when `scrutinee` is given.
+    fn pattern(
+        &mut self,
+        pattern: &'m Pattern,
+        expected: &Ty,
+        scope: &mut HashMap<&'m str, Local>,
+        scrutinee: Option<&Expr>,
+    ) -> bool {
+        let found = match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Error => return false,
+            PatternKind::Binding(name) => {
+                scope.insert(
+                    &name.name,
+                    Local {
+                        binding: Binding::Mono(expected.clone()),
+                        declared: None,
+                        bound: name.span,
+                    },
+                );
+                return false;
+            }
+            // An integer matches a fixed-width integer as well.
+            PatternKind::Lit(Lit::Int(_)) if self.shallow(expected).fixed_width().is_some() => self.shallow(expected),
+            PatternKind::Lit(lit) => Ty::of_literal(lit),
+            PatternKind::Variant { name, args } => {
+                let Some((enum_name, variant)) = self.variants.get(name.name.as_str()).copied() else {
+                    for arg in args {
+                        self.pattern(arg, &Ty::Unknown, scope, None);
+                    }
+                    return false;
+                };
+                let fields: Vec<Ty> = variant.fields.iter().map(|field| self.field_type(field)).collect();
+                for (i, arg) in args.iter().enumerate() {
+                    let field = fields.get(i).cloned().unwrap_or(Ty::Unknown);
+                    self.pattern(arg, &field, scope, None);
+                }
+                Ty::named(enum_name)
+            }
+            PatternKind::Or(alternatives) => {
+                let mut typed = false;
+                for alternative in alternatives {
+                    typed |= self.pattern(alternative, expected, scope, scrutinee.filter(|_| !typed));
+                }
+                return typed;
+            }
+        };
+        match scrutinee {
+            Some(scrutinee) => {
+                let shown = self.show(expected);
+                if self.unify(expected, &found, pattern.span, None).is_err() {
+                    let found = self.show(&found);
+                    self.diagnostics.push(
+                        Diagnostic::error("mismatched types", pattern.span)
+                            .with_label(pattern.span, format!("expected {shown}, found {found}"))
+                            .with_label(scrutinee.span, format!("this expression has type {shown}")),
+                    );
+                }
+            }
+            None => {
+                let _ = self.unify(expected, &found, pattern.span, None);
+            }
+        }
+        true
+    }
+}
+
+/// Replaces the variables in `substitution` with what it maps them to.
+fn replace_vars(ty: &Ty, substitution: &HashMap<u32, Ty>) -> Ty {
+    match ty {
+        Ty::Var(id) => substitution.get(id).cloned().unwrap_or_else(|| ty.clone()),
+        Ty::Con(name, args) => Ty::Con(
+            name.clone(),
+            args.iter().map(|arg| replace_vars(arg, substitution)).collect(),
+        ),
+        Ty::Fn(params, ret) => Ty::Fn(
+            params.iter().map(|param| replace_vars(param, substitution)).collect(),
+            Box::new(replace_vars(ret, substitution)),
+        ),
+        Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(|ty| replace_vars(ty, substitution)).collect()),
+        Ty::Param(_) | Ty::Unknown => ty.clone(),
+    }
+}
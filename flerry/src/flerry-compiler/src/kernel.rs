@@ -0,0 +1,917 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! The core calculus functions with dependent types are checked in: Pi and
+//! Sigma types, a hierarchy of universes, natural numbers and inductive
+//! families.
+//!
+//! Terms refer to variables by de Bruijn index, counting binders outwards,
+//! and values by de Bruijn level, counting them from the outside in, so
+//! evaluating under a binder never renames anything. Two terms are
+//! definitionally equal when they evaluate to the same normal form, which is
+//! decided by normalization by evaluation: evaluate both, then compare the
+//! values, going under binders with fresh variables. Sums of naturals are
+//! compared as a constant plus a bag of atoms, so `n + 1 + m` and
+//! `n + m + 1` are the same type.
+//!
+//! `+` on naturals is the only operator the core reasons about; the others,
+//! on naturals and on the built-in types, compute when their operands are
+//! literals and are otherwise left as they are.
+//!
+//! A metavariable stands for a term the checker has yet to work out, such
+//! as an implicit argument. It is closed, so the checker applies it to the
+//! variables in scope where it is made, and once solved it is replaced by
+//! its solution wherever it is forced.
+
+use crate::ast::{BinaryOp, Lit};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// How deep calls may unfold inside one another before they are left as
+/// they are, so a function that never finishes cannot hang the checker.
+const MAX_UNFOLDING: usize = 256;
+
+/// The level of the universe `name` names: `Type` is 0, `Type1` is 1.
+pub fn universe_level(name: &str) -> Option<u32> {
+    let digits = name.strip_prefix("Type")?;
+    if digits.is_empty() {
+        return Some(0);
+    }
+    if digits.starts_with('0') {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Var(usize),
+    /// A function, family or variant declared at the top level.
+    Global(String),
+    Universe(u32),
+    /// `(x: A) -> B`, where `B` may refer to `x`.
+    Pi(String, Box<Term>, Box<Term>),
+    Lam(String, Box<Term>),
+    App(Box<Term>, Box<Term>),
+    /// `(x: A, B)`, a pair whose second element's type may refer to the
+    /// first.
+    Sigma(String, Box<Term>, Box<Term>),
+    Pair(Box<Term>, Box<Term>),
+    First(Box<Term>),
+    Second(Box<Term>),
+    /// A built-in type the core does not look inside: `Int`, `Str`, ...
+    Base(String),
+    Lit(Lit),
+    Nat,
+    NatLit(u64),
+    Add(Box<Term>, Box<Term>),
+    /// An operator on two values of a built-in type, or on naturals other
+    /// than `+`. It computes once both operands are literals.
+    Op(BinaryOp, Box<Term>, Box<Term>),
+    /// `x = value` followed by the body, which may refer to `x`.
+    Let(String, Box<Term>, Box<Term>),
+    /// A match on a value of a family. Each arm binds the variant's fields;
+    /// the default arm binds the value matched.
+    Case(Box<Term>, Vec<Arm>, Option<(String, Box<Term>)>),
+    /// A metavariable, with the name of what it stands for.
+    Meta(usize, Rc<str>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm {
+    pub variant: String,
+    pub fields: Vec<String>,
+    pub body: Term,
+}
+
+impl Term {
+    /// Whether the variable with index `var` occurs in the term.
+    pub fn mentions(&self, var: usize) -> bool {
+        match self {
+            Term::Var(index) => *index == var,
+            Term::Global(_)
+            | Term::Universe(_)
+            | Term::Base(_)
+            | Term::Lit(_)
+            | Term::Nat
+            | Term::NatLit(_)
+            | Term::Meta(..) => false,
+            Term::Pi(_, a, b) | Term::Sigma(_, a, b) => a.mentions(var) || b.mentions(var + 1),
+            Term::Lam(_, body) => body.mentions(var + 1),
+            Term::Let(_, a, b) => a.mentions(var) || b.mentions(var + 1),
+            Term::App(a, b) | Term::Pair(a, b) | Term::Add(a, b) | Term::Op(_, a, b) => {
+                a.mentions(var) || b.mentions(var)
+            }
+            Term::First(term) | Term::Second(term) => term.mentions(var),
+            Term::Case(scrutinee, arms, default) => {
+                scrutinee.mentions(var)
+                    || arms.iter().any(|arm| arm.body.mentions(var + arm.fields.len()))
+                    || default.as_ref().is_some_and(|(_, body)| body.mentions(var + 1))
+            }
+        }
+    }
+
+    /// `head` applied to each of `args` in turn.
+    pub fn apply(head: Term, args: impl IntoIterator<Item = Term>) -> Term {
+        args.into_iter()
+            .fold(head, |f, arg| Term::App(Box::new(f), Box::new(arg)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Universe(u32),
+    Pi(String, Rc<Value>, Closure),
+    Lam(String, Closure),
+    Sigma(String, Rc<Value>, Closure),
+    Pair(Rc<Value>, Rc<Value>),
+    /// A family or a variant applied to arguments. Neither ever reduces.
+    Con(String, Vec<Value>),
+    Base(String),
+    Lit(Lit),
+    Nat,
+    /// A natural number that is a constant plus a bag of neutral atoms,
+    /// kept in the order they were added.
+    Sum(u64, Vec<Neutral>),
+    Neutral(Neutral),
+}
+
+/// A value stuck on a variable.
+#[derive(Debug, Clone)]
+pub enum Neutral {
+    Var(usize),
+    App(Box<Neutral>, Box<Value>),
+    First(Box<Neutral>),
+    Second(Box<Neutral>),
+    /// A match on a value that is not a variant yet.
+    Case(Box<Value>, Rc<Cases>),
+    /// A call that does not reduce: its function's body matches on an
+    /// argument that is not a variant yet, or is still being checked.
+    Call(String, Vec<Value>),
+    /// An operator with an operand that is not a literal yet.
+    Op(BinaryOp, Box<Value>, Box<Value>),
+    /// A metavariable that has no solution yet.
+    Meta(usize, Rc<str>),
+}
+
+/// A term under one binder, with the values of the variables around it.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    env: Vec<Value>,
+    body: Rc<Term>,
+}
+
+#[derive(Debug)]
+pub struct Cases {
+    env: Vec<Value>,
+    arms: Vec<Arm>,
+    default: Option<(String, Term)>,
+}
+
+impl Value {
+    pub fn var(level: usize) -> Value {
+        Value::Neutral(Neutral::Var(level))
+    }
+
+    /// `value` as a constant plus atoms, if it is a natural number.
+    pub fn as_sum(&self) -> Option<(u64, Vec<Neutral>)> {
+        match self {
+            Value::Sum(constant, atoms) => Some((*constant, atoms.clone())),
+            Value::Neutral(neutral) => Some((0, vec![neutral.clone()])),
+            _ => None,
+        }
+    }
+
+    pub fn sum(constant: u64, mut atoms: Vec<Neutral>) -> Value {
+        if constant == 0 && atoms.len() == 1 {
+            return Value::Neutral(atoms.pop().expect("one atom"));
+        }
+        Value::Sum(constant, atoms)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Global {
+    /// A function with its type, its number of parameters, which of them are
+    /// implicit, and its body over them once it has been checked.
+    Func {
+        ty: Term,
+        arity: usize,
+        implicit: Vec<bool>,
+        body: Option<Term>,
+    },
+    /// A family with its type, the number of parameters its variants all
+    /// pass through unchanged, and its variants.
+    Family {
+        ty: Term,
+        params: usize,
+        variants: Vec<String>,
+    },
+    /// A variant with its type, over the family's parameters then its
+    /// fields, and which of those are implicit.
+    Variant {
+        ty: Term,
+        family: String,
+        fields: usize,
+        implicit: Vec<bool>,
+    },
+}
+
+impl Global {
+    pub fn ty(&self) -> &Term {
+        match self {
+            Global::Func { ty, .. } | Global::Family { ty, .. } | Global::Variant { ty, .. } => ty,
+        }
+    }
+
+    /// Which of the arguments the global is applied to are implicit.
+    pub fn implicit(&self) -> &[bool] {
+        match self {
+            Global::Func { implicit, .. } | Global::Variant { implicit, .. } => implicit,
+            Global::Family { .. } => &[],
+        }
+    }
+}
+
+/// The top-level declarations terms refer to, and the operations on terms
+/// and values that need them.
+#[derive(Debug, Default)]
+pub struct Globals {
+    entries: HashMap<String, Global>,
+    depth: Cell<usize>,
+    /// The solution of each metavariable, once it has one.
+    metas: RefCell<Vec<Option<Value>>>,
+}
+
+impl Globals {
+    pub fn insert(&mut self, name: impl Into<String>, global: Global) {
+        self.entries.insert(name.into(), global);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Global> {
+        self.entries.get(name)
+    }
+
+    /// The names of the globals, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.entries.remove(name);
+    }
+
+    /// Gives the function `name` its checked body, so calls to it unfold.
+    pub fn define(&mut self, name: &str, term: Term) {
+        if let Some(Global::Func { body, .. }) = self.entries.get_mut(name) {
+            *body = Some(term);
+        }
+    }
+
+    /// Takes the body of the function `name` away again, so calls to it
+    /// stay as they are.
+    pub fn undefine(&mut self, name: &str) {
+        if let Some(Global::Func { body, .. }) = self.entries.get_mut(name) {
+            *body = None;
+        }
+    }
+
+    /// The checked body of the function `name`, if it has one.
+    pub fn body(&self, name: &str) -> Option<&Term> {
+        match self.get(name) {
+            Some(Global::Func { body, .. }) => body.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// A new metavariable, standing for `name`.
+    pub fn fresh_meta(&self, name: &str) -> Term {
+        let mut metas = self.metas.borrow_mut();
+        metas.push(None);
+        Term::Meta(metas.len() - 1, name.into())
+    }
+
+    /// How many metavariables have been solved.
+    pub fn solved(&self) -> usize {
+        self.metas.borrow().iter().filter(|meta| meta.is_some()).count()
+    }
+
+    pub fn solution(&self, meta: usize) -> Option<Value> {
+        self.metas.borrow()[meta].clone()
+    }
+
+    /// Solves `meta` with `value`, which must be closed.
+    pub fn solve(&self, meta: usize, value: Value) {
+        self.metas.borrow_mut()[meta] = Some(value);
+    }
+
+    /// The metavariables and their solutions so far, to go back to with
+    /// `rollback`.
+    pub fn snapshot(&self) -> Vec<Option<Value>> {
+        self.metas.borrow().clone()
+    }
+
+    /// Forgets the metavariables made and solved since `snapshot`.
+    pub fn rollback(&self, snapshot: Vec<Option<Value>>) {
+        *self.metas.borrow_mut() = snapshot;
+    }
+
+    /// `value` with the solved metavariable at its head, if any, replaced by
+    /// its solution, and what is stuck on it evaluated again.
+    pub fn force(&self, value: &Value) -> Value {
+        match value {
+            Value::Neutral(neutral) => self.force_neutral(neutral),
+            Value::Sum(constant, atoms) => atoms.iter().fold(Value::Sum(*constant, Vec::new()), |sum, atom| {
+                add(sum, self.force_neutral(atom))
+            }),
+            value => value.clone(),
+        }
+    }
+
+    fn force_neutral(&self, neutral: &Neutral) -> Value {
+        match neutral {
+            Neutral::Meta(meta, _) => match self.solution(*meta) {
+                Some(value) => self.force(&value),
+                None => Value::Neutral(neutral.clone()),
+            },
+            Neutral::App(f, arg) => self.apply(self.force_neutral(f), (**arg).clone()),
+            Neutral::First(pair) => first(self.force_neutral(pair)),
+            Neutral::Second(pair) => second(self.force_neutral(pair)),
+            Neutral::Case(scrutinee, cases) => self.case(self.force(scrutinee), cases.clone()),
+            Neutral::Call(name, args) => self.call(name, args.iter().map(|arg| self.force(arg)).collect()),
+            Neutral::Op(op, a, b) => operate(*op, self.force(a), self.force(b)),
+            Neutral::Var(_) => Value::Neutral(neutral.clone()),
+        }
+    }
+
+    /// The number of parameters of the family `variant` belongs to.
+    fn params_of(&self, variant: &str) -> usize {
+        match self.get(variant) {
+            Some(Global::Variant { family, .. }) => match self.get(family) {
+                Some(Global::Family { params, .. }) => *params,
+                _ => 0,
+            },
+            _ => 0,
+        }
+    }
+
+    pub fn eval(&self, term: &Term, env: &[Value]) -> Value {
+        match term {
+            Term::Var(index) => env[env.len() - 1 - index].clone(),
+            Term::Global(name) => match self.get(name) {
+                Some(Global::Func { .. }) | None => self.call(name, Vec::new()),
+                Some(_) => Value::Con(name.clone(), Vec::new()),
+            },
+            Term::Universe(level) => Value::Universe(*level),
+            Term::Pi(name, a, b) => Value::Pi(name.clone(), Rc::new(self.eval(a, env)), closure(env, b)),
+            Term::Lam(name, body) => Value::Lam(name.clone(), closure(env, body)),
+            Term::App(f, arg) => self.apply(self.eval(f, env), self.eval(arg, env)),
+            Term::Sigma(name, a, b) => Value::Sigma(name.clone(), Rc::new(self.eval(a, env)), closure(env, b)),
+            Term::Pair(a, b) => Value::Pair(Rc::new(self.eval(a, env)), Rc::new(self.eval(b, env))),
+            Term::First(pair) => first(self.eval(pair, env)),
+            Term::Second(pair) => second(self.eval(pair, env)),
+            Term::Base(name) => Value::Base(name.clone()),
+            Term::Lit(lit) => Value::Lit(lit.clone()),
+            Term::Nat => Value::Nat,
+            Term::NatLit(n) => Value::Sum(*n, Vec::new()),
+            Term::Add(a, b) => add(self.eval(a, env), self.eval(b, env)),
+            Term::Op(op, a, b) => operate(*op, self.eval(a, env), self.eval(b, env)),
+            Term::Let(_, value, body) => {
+                let mut env = env.to_vec();
+                env.push(self.eval(value, &env));
+                self.eval(body, &env)
+            }
+            Term::Case(scrutinee, arms, default) => {
+                let cases = Cases {
+                    env: env.to_vec(),
+                    arms: arms.clone(),
+                    default: default.as_ref().map(|(name, body)| (name.clone(), (**body).clone())),
+                };
+                self.case(self.eval(scrutinee, env), Rc::new(cases))
+            }
+            Term::Meta(meta, name) => match self.solution(*meta) {
+                Some(value) => value,
+                None => Value::Neutral(Neutral::Meta(*meta, name.clone())),
+            },
+        }
+    }
+
+    pub fn apply(&self, f: Value, arg: Value) -> Value {
+        match f {
+            Value::Lam(_, closure) => self.instantiate(&closure, arg),
+            Value::Con(name, mut args) => {
+                args.push(arg);
+                Value::Con(name, args)
+            }
+            Value::Neutral(Neutral::Call(name, mut args)) => {
+                args.push(arg);
+                self.call(&name, args)
+            }
+            Value::Neutral(neutral) => Value::Neutral(Neutral::App(Box::new(neutral), Box::new(arg))),
+            // Only well-typed terms are evaluated.
+            f => f,
+        }
+    }
+
+    pub fn instantiate(&self, closure: &Closure, arg: Value) -> Value {
+        let mut env = closure.env.clone();
+        env.push(arg);
+        self.eval(&closure.body, &env)
+    }
+
+    /// Unfolds a call to `name` once it has all its arguments, unless its
+    /// body is then stuck matching on one of them.
+    fn call(&self, name: &str, args: Vec<Value>) -> Value {
+        if let Some(Global::Func {
+            arity,
+            body: Some(body),
+            ..
+        }) = self.get(name)
+            && *arity == args.len()
+            && self.depth.get() < MAX_UNFOLDING
+        {
+            self.depth.set(self.depth.get() + 1);
+            let value = self.eval(body, &args);
+            self.depth.set(self.depth.get() - 1);
+            if !matches!(value, Value::Neutral(Neutral::Case(..))) {
+                return value;
+            }
+        }
+        Value::Neutral(Neutral::Call(name.to_owned(), args))
+    }
+
+    fn case(&self, scrutinee: Value, cases: Rc<Cases>) -> Value {
+        if let Value::Con(variant, args) = &scrutinee {
+            if let Some(arm) = cases.arms.iter().find(|arm| arm.variant == *variant) {
+                let mut env = cases.env.clone();
+                env.extend(args.iter().skip(self.params_of(variant)).cloned());
+                return self.eval(&arm.body, &env);
+            }
+            if let Some((_, body)) = &cases.default {
+                let mut env = cases.env.clone();
+                env.push(scrutinee.clone());
+                return self.eval(body, &env);
+            }
+        }
+        Value::Neutral(Neutral::Case(Box::new(scrutinee), cases))
+    }
+
+    /// The term in normal form that `value` denotes, under `level` binders.
+    pub fn quote(&self, level: usize, value: &Value) -> Term {
+        match &self.force(value) {
+            Value::Universe(n) => Term::Universe(*n),
+            Value::Pi(name, a, b) => Term::Pi(
+                name.clone(),
+                Box::new(self.quote(level, a)),
+                Box::new(self.quote_closure(level, b)),
+            ),
+            Value::Lam(name, body) => Term::Lam(name.clone(), Box::new(self.quote_closure(level, body))),
+            Value::Sigma(name, a, b) => Term::Sigma(
+                name.clone(),
+                Box::new(self.quote(level, a)),
+                Box::new(self.quote_closure(level, b)),
+            ),
+            Value::Pair(a, b) => Term::Pair(Box::new(self.quote(level, a)), Box::new(self.quote(level, b))),
+            Value::Con(name, args) => Term::apply(
+                Term::Global(name.clone()),
+                args.iter().map(|arg| self.quote(level, arg)),
+            ),
+            Value::Base(name) => Term::Base(name.clone()),
+            Value::Lit(lit) => Term::Lit(lit.clone()),
+            Value::Nat => Term::Nat,
+            Value::Sum(constant, atoms) => {
+                let mut atoms = atoms.iter().map(|atom| self.quote_neutral(level, atom));
+                let Some(first) = atoms.next() else {
+                    return Term::NatLit(*constant);
+                };
+                let sum = atoms.fold(first, |sum, atom| Term::Add(Box::new(sum), Box::new(atom)));
+                match constant {
+                    0 => sum,
+                    n => Term::Add(Box::new(sum), Box::new(Term::NatLit(*n))),
+                }
+            }
+            Value::Neutral(neutral) => self.quote_neutral(level, neutral),
+        }
+    }
+
+    fn quote_closure(&self, level: usize, closure: &Closure) -> Term {
+        self.quote(level + 1, &self.instantiate(closure, Value::var(level)))
+    }
+
+    fn quote_neutral(&self, level: usize, neutral: &Neutral) -> Term {
+        match neutral {
+            Neutral::Var(var) => Term::Var(level - 1 - var),
+            Neutral::Meta(meta, name) => Term::Meta(*meta, name.clone()),
+            Neutral::App(f, arg) => Term::App(Box::new(self.quote_neutral(level, f)), Box::new(self.quote(level, arg))),
+            Neutral::First(pair) => Term::First(Box::new(self.quote_neutral(level, pair))),
+            Neutral::Second(pair) => Term::Second(Box::new(self.quote_neutral(level, pair))),
+            Neutral::Call(name, args) => Term::apply(
+                Term::Global(name.clone()),
+                args.iter().map(|arg| self.quote(level, arg)),
+            ),
+            Neutral::Op(op, a, b) => Term::Op(*op, Box::new(self.quote(level, a)), Box::new(self.quote(level, b))),
+            Neutral::Case(scrutinee, cases) => {
+                let arms = cases
+                    .arms
+                    .iter()
+                    .map(|arm| Arm {
+                        variant: arm.variant.clone(),
+                        fields: arm.fields.clone(),
+                        body: self.quote_arm(level, &cases.env, arm.fields.len(), &arm.body),
+                    })
+                    .collect();
+                let default = cases
+                    .default
+                    .as_ref()
+                    .map(|(name, body)| (name.clone(), Box::new(self.quote_arm(level, &cases.env, 1, body))));
+                Term::Case(Box::new(self.quote(level, scrutinee)), arms, default)
+            }
+        }
+    }
+
+    /// An arm's body in normal form, with its bound variables left free.
+    fn quote_arm(&self, level: usize, env: &[Value], bound: usize, body: &Term) -> Term {
+        let mut env = env.to_vec();
+        env.extend((level..level + bound).map(Value::var));
+        self.quote(level + bound, &self.eval(body, &env))
+    }
+
+    /// Whether `a` and `b` are definitionally equal, under `level` binders.
+    pub fn conv(&self, level: usize, a: &Value, b: &Value) -> bool {
+        let (a, b) = (&self.force(a), &self.force(b));
+        match (a, b) {
+            (Value::Universe(i), Value::Universe(j)) => i == j,
+            (Value::Pi(_, a1, b1), Value::Pi(_, a2, b2)) | (Value::Sigma(_, a1, b1), Value::Sigma(_, a2, b2)) => {
+                self.conv(level, a1, a2) && self.conv_closures(level, b1, b2)
+            }
+            (Value::Lam(_, b1), Value::Lam(_, b2)) => self.conv_closures(level, b1, b2),
+            // Eta: a function is equal to the lambda applying it.
+            (Value::Lam(_, body), f) | (f, Value::Lam(_, body)) => {
+                let var = Value::var(level);
+                self.conv(
+                    level + 1,
+                    &self.instantiate(body, var.clone()),
+                    &self.apply(f.clone(), var),
+                )
+            }
+            (Value::Pair(a1, b1), Value::Pair(a2, b2)) => self.conv(level, a1, a2) && self.conv(level, b1, b2),
+            (Value::Pair(a, b), pair @ Value::Neutral(_)) | (pair @ Value::Neutral(_), Value::Pair(a, b)) => {
+                self.conv(level, a, &first(pair.clone())) && self.conv(level, b, &second(pair.clone()))
+            }
+            (Value::Con(n1, args1), Value::Con(n2, args2)) => {
+                n1 == n2 && args1.len() == args2.len() && args1.iter().zip(args2).all(|(a, b)| self.conv(level, a, b))
+            }
+            (Value::Base(a), Value::Base(b)) => a == b,
+            (Value::Lit(a), Value::Lit(b)) => a == b,
+            (Value::Nat, Value::Nat) => true,
+            (Value::Neutral(a), Value::Neutral(b)) => self.conv_neutral(level, a, b),
+            (Value::Sum(..), _) | (_, Value::Sum(..)) => {
+                let (Some((c1, atoms1)), Some((c2, atoms2))) = (a.as_sum(), b.as_sum()) else {
+                    return false;
+                };
+                c1 == c2 && atoms1.len() == atoms2.len() && self.cancel(level, atoms1, atoms2).0.is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the atoms the two bags have in common, returning the rest of
+    /// each.
+    pub fn cancel(&self, level: usize, a: Vec<Neutral>, mut b: Vec<Neutral>) -> (Vec<Neutral>, Vec<Neutral>) {
+        let mut rest = Vec::new();
+        for atom in a {
+            match b.iter().position(|other| self.conv_neutral(level, &atom, other)) {
+                Some(i) => {
+                    b.remove(i);
+                }
+                None => rest.push(atom),
+            }
+        }
+        (rest, b)
+    }
+
+    fn conv_closures(&self, level: usize, a: &Closure, b: &Closure) -> bool {
+        let var = Value::var(level);
+        self.conv(level + 1, &self.instantiate(a, var.clone()), &self.instantiate(b, var))
+    }
+
+    fn conv_neutral(&self, level: usize, a: &Neutral, b: &Neutral) -> bool {
+        match (a, b) {
+            (Neutral::Var(a), Neutral::Var(b)) => a == b,
+            (Neutral::Meta(a, _), Neutral::Meta(b, _)) => a == b,
+            (Neutral::App(f1, a1), Neutral::App(f2, a2)) => {
+                self.conv_neutral(level, f1, f2) && self.conv(level, a1, a2)
+            }
+            (Neutral::First(a), Neutral::First(b)) | (Neutral::Second(a), Neutral::Second(b)) => {
+                self.conv_neutral(level, a, b)
+            }
+            (Neutral::Call(n1, args1), Neutral::Call(n2, args2)) => {
+                n1 == n2 && args1.len() == args2.len() && args1.iter().zip(args2).all(|(a, b)| self.conv(level, a, b))
+            }
+            (Neutral::Op(op1, a1, b1), Neutral::Op(op2, a2, b2)) => {
+                op1 == op2 && self.conv(level, a1, a2) && self.conv(level, b1, b2)
+            }
+            (Neutral::Case(s1, c1), Neutral::Case(s2, c2)) => {
+                self.conv(level, s1, s2)
+                    && c1.arms.len() == c2.arms.len()
+                    && c1.arms.iter().zip(&c2.arms).all(|(a1, a2)| {
+                        let bound = a1.fields.len();
+                        a1.variant == a2.variant
+                            && self.conv_arms(level, bound, (&c1.env, &a1.body), (&c2.env, &a2.body))
+                    })
+                    && match (&c1.default, &c2.default) {
+                        (Some((_, b1)), Some((_, b2))) => self.conv_arms(level, 1, (&c1.env, b1), (&c2.env, b2)),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            _ => false,
+        }
+    }
+
+    fn conv_arms(&self, level: usize, bound: usize, a: (&[Value], &Term), b: (&[Value], &Term)) -> bool {
+        let fresh: Vec<Value> = (level..level + bound).map(Value::var).collect();
+        let (mut env1, mut env2) = (a.0.to_vec(), b.0.to_vec());
+        env1.extend(fresh.iter().cloned());
+        env2.extend(fresh);
+        self.conv(level + bound, &self.eval(a.1, &env1), &self.eval(b.1, &env2))
+    }
+
+    /// Whether a value of type `a` can be used where `b` is expected: they
+    /// are equal, or `a` is a smaller universe, also in the result of a
+    /// function type.
+    pub fn subtype(&self, level: usize, a: &Value, b: &Value) -> bool {
+        match (&self.force(a), &self.force(b)) {
+            (Value::Universe(i), Value::Universe(j)) => i <= j,
+            (Value::Pi(_, a1, b1), Value::Pi(_, a2, b2)) => {
+                let var = Value::var(level);
+                self.conv(level, a1, a2)
+                    && self.subtype(
+                        level + 1,
+                        &self.instantiate(b1, var.clone()),
+                        &self.instantiate(b2, var),
+                    )
+            }
+            _ => self.conv(level, a, b),
+        }
+    }
+}
+
+fn closure(env: &[Value], body: &Term) -> Closure {
+    Closure {
+        env: env.to_vec(),
+        body: Rc::new(body.clone()),
+    }
+}
+
+pub fn first(pair: Value) -> Value {
+    match pair {
+        Value::Pair(a, _) => (*a).clone(),
+        Value::Neutral(neutral) => Value::Neutral(Neutral::First(Box::new(neutral))),
+        other => other,
+    }
+}
+
+pub fn second(pair: Value) -> Value {
+    match pair {
+        Value::Pair(_, b) => (*b).clone(),
+        Value::Neutral(neutral) => Value::Neutral(Neutral::Second(Box::new(neutral))),
+        other => other,
+    }
+}
+
+pub fn add(a: Value, b: Value) -> Value {
+    match (a.as_sum(), b.as_sum()) {
+        (Some((c1, mut atoms)), Some((c2, more))) => {
+            atoms.extend(more);
+            Value::sum(c1 + c2, atoms)
+        }
+        // Only well-typed terms are evaluated.
+        _ => a,
+    }
+}
+
+/// `a op b`, computed if both are literals. Subtraction of naturals stops
+/// at zero, and what cannot be computed, such as a division by zero or an
+/// `Int` that overflows, is left as it is.
+pub fn operate(op: BinaryOp, a: Value, b: Value) -> Value {
+    let computed = match (&a, &b) {
+        (Value::Lit(Lit::Int(x)), Value::Lit(Lit::Int(y))) => match op {
+            BinaryOp::Add => x.checked_add(*y).map(Lit::Int),
+            BinaryOp::Sub => x.checked_sub(*y).map(Lit::Int),
+            BinaryOp::Mul => x.checked_mul(*y).map(Lit::Int),
+            BinaryOp::Div => x.checked_div(*y).map(Lit::Int),
+            BinaryOp::Rem => x.checked_rem(*y).map(Lit::Int),
+            BinaryOp::BitAnd => Some(Lit::Int(x & y)),
+            BinaryOp::BitOr => Some(Lit::Int(x | y)),
+            // A shift that loses bits overflows.
+            BinaryOp::Shl => u32::try_from(*y)
+                .ok()
+                .and_then(|y| x.checked_shl(y).filter(|shifted| shifted >> y == *x))
+                .map(Lit::Int),
+            BinaryOp::Shr => u32::try_from(*y).ok().and_then(|y| x.checked_shr(y)).map(Lit::Int),
+            op => compare(op, x, y),
+        }
+        .map(Value::Lit),
+        (Value::Lit(Lit::Float(x)), Value::Lit(Lit::Float(y))) => match op {
+            BinaryOp::Add => Some(Lit::Float(x + y)),
+            BinaryOp::Sub => Some(Lit::Float(x - y)),
+            BinaryOp::Mul => Some(Lit::Float(x * y)),
+            BinaryOp::Div => Some(Lit::Float(x / y)),
+            op => compare(op, x, y),
+        }
+        .map(Value::Lit),
+        (Value::Lit(Lit::Str(x)), Value::Lit(Lit::Str(y))) => compare(op, x, y).map(Value::Lit),
+        (Value::Lit(Lit::Bool(x)), Value::Lit(Lit::Bool(y))) => match op {
+            BinaryOp::And => Some(Lit::Bool(*x && *y)),
+            BinaryOp::Or => Some(Lit::Bool(*x || *y)),
+            op => compare(op, x, y),
+        }
+        .map(Value::Lit),
+        (Value::Sum(x, atoms1), Value::Sum(y, atoms2)) if atoms1.is_empty() && atoms2.is_empty() => match op {
+            BinaryOp::Sub => Some(Value::Sum(x.saturating_sub(*y), Vec::new())),
+            BinaryOp::Mul => x.checked_mul(*y).map(|n| Value::Sum(n, Vec::new())),
+            op => compare(op, x, y).map(Value::Lit),
+        },
+        _ => None,
+    };
+    computed.unwrap_or_else(|| Value::Neutral(Neutral::Op(op, Box::new(a), Box::new(b))))
+}
+
+fn compare<T: PartialOrd>(op: BinaryOp, x: &T, y: &T) -> Option<Lit> {
+    let holds = match op {
+        BinaryOp::Eq => x == y,
+        BinaryOp::Ne => x != y,
+        BinaryOp::Lt => x < y,
+        BinaryOp::Le => x <= y,
+        BinaryOp::Gt => x > y,
+        BinaryOp::Ge => x >= y,
+        _ => return None,
+    };
+    Some(Lit::Bool(holds))
+}
+
+/// Prints `term` the way it would be written in the source, with `names`
+/// the names of the variables it is under, innermost last.
+pub fn show(term: &Term, names: &[String]) -> String {
+    let mut printer = Printer { names: names.to_vec() };
+    printer.term(term, Prec::Arrow)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Arrow,
+    Sum,
+    Atom,
+}
+
+struct Printer {
+    names: Vec<String>,
+}
+
+impl Printer {
+    /// A name for a new binder that does not hide one in scope.
+    fn fresh(&self, name: &str) -> String {
+        if name == "_" || !self.names.iter().any(|n| n == name) {
+            return name.to_owned();
+        }
+        (1..)
+            .map(|i| format!("{name}{i}"))
+            .find(|candidate| !self.names.contains(candidate))
+            .expect("some name is free")
+    }
+
+    fn under<T>(&mut self, name: &str, f: impl FnOnce(&mut Self, String) -> T) -> T {
+        let name = self.fresh(name);
+        self.names.push(name.clone());
+        let result = f(self, name);
+        self.names.pop();
+        result
+    }
+
+    fn term(&mut self, term: &Term, prec: Prec) -> String {
+        let (text, own) = match term {
+            Term::Var(index) => (
+                self.names
+                    .len()
+                    .checked_sub(index + 1)
+                    .map_or("?".to_owned(), |i| self.names[i].clone()),
+                Prec::Atom,
+            ),
+            Term::Global(name) | Term::Base(name) => (name.clone(), Prec::Atom),
+            Term::Meta(_, name) => (format!("?{name}"), Prec::Atom),
+            Term::Universe(0) => ("Type".to_owned(), Prec::Atom),
+            Term::Universe(level) => (format!("Type{level}"), Prec::Atom),
+            Term::Nat => ("Nat".to_owned(), Prec::Atom),
+            Term::NatLit(n) => (n.to_string(), Prec::Atom),
+            Term::Lit(lit) => (crate::pretty::literal(lit), Prec::Atom),
+            Term::Pi(name, a, b) => {
+                let text = if b.mentions(0) {
+                    let a = self.term(a, Prec::Arrow);
+                    self.under(name, |p, name| format!("({name}: {a}) -> {}", p.term(b, Prec::Arrow)))
+                } else {
+                    let a = self.term(a, Prec::Sum);
+                    self.under("_", |p, _| format!("{a} -> {}", p.term(b, Prec::Arrow)))
+                };
+                (text, Prec::Arrow)
+            }
+            Term::Lam(name, body) => {
+                let text = self.under(name, |p, name| format!("({name}) => {}", p.term(body, Prec::Arrow)));
+                (text, Prec::Arrow)
+            }
+            Term::App(..) => {
+                let mut args = Vec::new();
+                let mut head = term;
+                while let Term::App(f, arg) = head {
+                    args.push(arg);
+                    head = f;
+                }
+                // A metavariable is applied to the variables in scope, which
+                // would only clutter it.
+                if let Term::Meta(_, name) = head {
+                    return format!("?{name}");
+                }
+                let args: Vec<String> = args.iter().rev().map(|arg| self.term(arg, Prec::Arrow)).collect();
+                (
+                    format!("{}({})", self.term(head, Prec::Atom), args.join(", ")),
+                    Prec::Atom,
+                )
+            }
+            Term::Sigma(..) | Term::Pair(..) => {
+                let mut elements = Vec::new();
+                self.tuple(term, &mut elements);
+                (format!("({})", elements.join(", ")), Prec::Atom)
+            }
+            Term::First(pair) => (format!("{}.0", self.term(pair, Prec::Atom)), Prec::Atom),
+            Term::Second(pair) => (format!("{}.1", self.term(pair, Prec::Atom)), Prec::Atom),
+            Term::Add(a, b) => (
+                format!("{} + {}", self.term(a, Prec::Sum), self.term(b, Prec::Atom)),
+                Prec::Sum,
+            ),
+            Term::Op(op, a, b) => (
+                format!(
+                    "{} {} {}",
+                    self.term(a, Prec::Sum),
+                    op.symbol(),
+                    self.term(b, Prec::Atom)
+                ),
+                Prec::Sum,
+            ),
+            Term::Let(name, value, body) => {
+                let value = self.term(value, Prec::Arrow);
+                let text = self.under(name, |p, name| {
+                    format!("{name} = {value}; {}", p.term(body, Prec::Arrow))
+                });
+                (text, Prec::Arrow)
+            }
+            Term::Case(scrutinee, arms, default) => {
+                let mut text = format!("match {}", self.term(scrutinee, Prec::Arrow));
+                for arm in arms {
+                    let saved = self.names.len();
+                    let fields: Vec<String> = arm
+                        .fields
+                        .iter()
+                        .map(|field| {
+                            let field = self.fresh(field);
+                            self.names.push(field.clone());
+                            field
+                        })
+                        .collect();
+                    let pattern = match fields.is_empty() {
+                        true => arm.variant.clone(),
+                        false => format!("{}({})", arm.variant, fields.join(", ")),
+                    };
+                    text += &format!(" {pattern} => {}", self.term(&arm.body, Prec::Arrow));
+                    self.names.truncate(saved);
+                }
+                if let Some((name, body)) = default {
+                    text += &self.under(name, |p, name| format!(" {name} => {}", p.term(body, Prec::Arrow)));
+                }
+                (text + " end", Prec::Atom)
+            }
+        };
+        if own < prec { format!("({text})") } else { text }
+    }
+
+    /// The elements of nested pairs or pair types, `(a, (b, c))` as `a, b, c`.
+    fn tuple(&mut self, term: &Term, out: &mut Vec<String>) {
+        match term {
+            Term::Sigma(name, a, b) => {
+                let a = self.term(a, Prec::Arrow);
+                self.under(name, |p, name| {
+                    out.push(match b.mentions(0) {
+                        true => format!("{name}: {a}"),
+                        false => a,
+                    });
+                    p.tuple(b, out);
+                });
+            }
+            Term::Pair(a, b) => {
+                out.push(self.term(a, Prec::Arrow));
+                self.tuple(b, out);
+            }
+            _ => out.push(self.term(term, Prec::Arrow)),
+        }
+    }
+}
@@ -1,4 +1,2649 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
 
+pub mod adt;
+pub mod annotations;
+pub mod ast;
+pub mod checks;
+pub mod closure;
 pub mod compiler;
+pub mod contracts;
+pub mod decision;
+pub mod dump;
+pub mod elab;
+pub mod fix;
+pub mod fold;
+pub mod holes;
+pub mod infer;
+pub mod kernel;
+pub mod lint;
+pub mod parser;
+pub mod precedence;
+pub mod pretty;
+pub mod reachability;
+pub mod refine;
+pub mod resolve;
+pub mod structs;
+pub mod syntax;
+pub mod types;
+pub mod unify;
+pub mod vc;
+pub mod verify;
+pub mod visit;
+pub mod visit_mut;
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::*;
+    use crate::closure;
+    use crate::compiler::{Options, compile, compile_goals, compile_with, lower, parse_source, smt2, verify};
+    use crate::contracts::ContractMode;
+    use crate::decision::{self, Constructor, Decision, Occurrence, Pat};
+    use crate::dump::{Format, dump};
+    use crate::elab;
+    use crate::fix::apply_fixes;
+    use crate::fold::{self, Folder};
+    use crate::holes::{self, Goal};
+    use crate::infer;
+    use crate::kernel::{Globals, Term, Value, show};
+    use crate::lint::{LintLevel, LintOptions};
+    use crate::parser::{Parser, parse};
+    use crate::precedence::{Assoc, BINARY_OPERATORS, PREFIX_OPERATORS};
+    use crate::pretty::{self, parenthesized};
+    use crate::syntax::views::{self, AstNode};
+    use crate::syntax::{NodeCache, NodeKind, parse_lossless};
+    use crate::verify::Status;
+    use crate::visit::{self, Visitor};
+    use crate::visit_mut::VisitorMut;
+    use flerry_core::span::line_col;
+    use flerry_core::{Diagnostic, Level, Span, TokenType};
+    use std::rc::Rc;
+    use flerry_lexer::lexer::tokenize;
+
+    fn fixed(source: &str) -> String {
+        let diagnostics = compile(source);
+        apply_fixes(source, &diagnostics).0
+    }
+
+    #[test]
+    fn test_fix_misspelled_keyword_and_comment() {
+        let source = "func f(x) =\n    if x\n        return 1\n    elseif x\n        return 2 // two\n    end\nend\n";
+        let expected = "func f(x) =\n    if x\n        return 1\n    elsif x\n        return 2 # two\n    end\nend\n";
+        assert_eq!(fixed(source), expected);
+    }
+
+    #[test]
+    fn test_fix_missing_end() {
+        let source = "func a() =\n    if b\n        c()\n\nfunc b() =\n    return 1\nend\n";
+        let expected = "func a() =\n    if b\n        c()\n    end\nend\n\nfunc b() =\n    return 1\nend\n";
+        assert_eq!(fixed(source), expected);
+
+        let diagnostics = compile(source);
+        let unclosed = diagnostics.iter().find(|d| d.message == "unclosed `func`").unwrap();
+        assert_eq!(unclosed.span.text(source), "func");
+        assert_eq!(unclosed.labels[0].span, Span::new(0, 4));
+        assert_eq!(unclosed.labels[0].message, "unclosed `func` started here");
+    }
+
+    #[test]
+    fn test_fix_unused_variable() {
+        let source = "func main(arg) =\n    total = 1\n    total = 2\n    used = 3\n    return used\nend\n";
+        let expected = "func main(_arg) =\n    _total = 1\n    _total = 2\n    used = 3\n    return used\nend\n";
+        assert_eq!(fixed(source), expected);
+
+        // Fixing is idempotent: nothing is left to apply the second time.
+        let diagnostics = compile(expected);
+        assert_eq!(apply_fixes(expected, &diagnostics).1, 0);
+    }
+
+    fn did_you_mean(source: &str) -> Vec<(String, String)> {
+        compile(source)
+            .into_iter()
+            .flat_map(|d| d.suggestions.into_iter().map(move |s| (d.message.clone(), s.message)))
+            .filter(|(_, help)| help.starts_with("did you mean"))
+            .collect()
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        use flerry_core::suggest::{damerau_levenshtein, find_best_match};
+
+        assert_eq!(damerau_levenshtein("retrun", "return"), 1);
+        assert_eq!(damerau_levenshtein("elseif", "elsif"), 1);
+        assert_eq!(damerau_levenshtein("kitten", "sitting"), 3);
+        assert_eq!(damerau_levenshtein("", "end"), 3);
+
+        assert_eq!(find_best_match("prnitln", ["print", "println"]), Some("println"));
+        assert_eq!(find_best_match("Println", ["println"]), Some("println"));
+        // Short names only tolerate a single edit.
+        assert_eq!(find_best_match("ab", ["xyz"]), None);
+    }
+
+    #[test]
+    fn test_did_you_mean_keywords_and_names() {
+        let source = "fucn helper(x) =\n    retrun x\nend\n\nfunc main() =\n    total = helper(1)\n    prnitln(totl, total)\nend\n";
+        let suggestions = did_you_mean(source);
+        assert_eq!(
+            suggestions,
+            vec![
+                ("unknown keyword `fucn`".to_owned(), "did you mean `func`?".to_owned()),
+                ("unknown keyword `retrun`".to_owned(), "did you mean `return`?".to_owned()),
+                ("cannot find `prnitln` in this scope".to_owned(), "did you mean `println`?".to_owned()),
+                ("cannot find `totl` in this scope".to_owned(), "did you mean `total`?".to_owned()),
+            ]
+        );
+
+        // These are guesses, so `flerry fix` must not apply them.
+        let diagnostics = compile(source);
+        assert_eq!(apply_fixes(source, &diagnostics).1, 0);
+    }
+
+    fn lints_with(source: &str, flags: &[(LintLevel, &str)], cap: Option<LintLevel>) -> Vec<(Level, &'static str)> {
+        let options = Options {
+            lints: LintOptions {
+                flags: flags.iter().map(|(level, name)| (*level, name.to_string())).collect(),
+                cap,
+            },
+            ..Options::default()
+        };
+        compile_with(source, &options)
+            .into_iter()
+            .filter_map(|d| Some((d.level, d.lint?)))
+            .collect()
+    }
+
+    #[test]
+    fn test_lints_default_levels() {
+        let source = "import IO\nimport Math\n\nfunc add(x, y) =\n    return x + y\n    println(x)\nend\n\nfunc main() =\n    add = 1\n    sum = add(2, 4)\nend\n";
+        assert_eq!(
+            lints_with(source, &[], None),
+            vec![
+                (Level::Warning, "shadowing"),
+                (Level::Warning, "unused_variables"),
+                (Level::Warning, "unused_imports"),
+                (Level::Warning, "unreachable_code"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_order() {
+        // Lints come in the order of the passes that find them: name
+        // resolution reports shadowing and unused variables as it walks each
+        // function, then unused imports once every use is known, and
+        // reachability runs after it.
+        let source = "import IO\nimport Math\n\nfunc add(x, y) =\n    return x + y\n    println(x)\nend\n\nfunc main() =\n    add = 1\n    sum = add(2, 4)\nend\n";
+        let lines: Vec<(&str, usize)> = compile(source)
+            .iter()
+            .filter_map(|d| Some((d.lint?, line_col(source, d.span.start).0)))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![("shadowing", 10), ("unused_variables", 11), ("unused_imports", 2), ("unreachable_code", 6)]
+        );
+    }
+
+    #[test]
+    fn test_lint_flags_attributes_and_cap() {
+        let source = "func a() =\n    x = 1\nend\n\n#[allow(unused_variables)]\nfunc b() =\n    y = 1\nend\n";
+        assert_eq!(lints_with(source, &[], None), vec![(Level::Warning, "unused_variables")]);
+        assert!(lints_with(source, &[(LintLevel::Allow, "unused_variables")], None).is_empty());
+        assert_eq!(
+            lints_with(source, &[(LintLevel::Deny, "warnings")], None),
+            vec![(Level::Error, "unused_variables")]
+        );
+        assert_eq!(
+            lints_with(source, &[(LintLevel::Deny, "warnings")], Some(LintLevel::Warn)),
+            vec![(Level::Warning, "unused_variables")]
+        );
+        // Later flags override earlier ones.
+        assert!(
+            lints_with(
+                source,
+                &[(LintLevel::Deny, "unused_variables"), (LintLevel::Allow, "unused_variables")],
+                None
+            )
+            .is_empty()
+        );
+
+        // A forbidden lint cannot be allowed by an attribute.
+        let options = Options {
+            lints: LintOptions {
+                flags: vec![(LintLevel::Forbid, "unused_variables".to_owned())],
+                cap: None,
+            },
+            ..Options::default()
+        };
+        let diagnostics = compile_with(source, &options);
+        assert!(diagnostics.iter().any(|d| d.message == "allow(unused_variables) incompatible with previous forbid"));
+        assert_eq!(diagnostics.iter().filter(|d| d.lint == Some("unused_variables")).count(), 2);
+    }
+
+    fn parsed(source: &str) -> (Module, Vec<Diagnostic>) {
+        let (tokens, _) = tokenize(source);
+        parse(source, &tokens)
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let source = "import IO
+
+struct Point = x, y end
+
+enum Shape = Circle(r) | Empty end
+
+type Points = List(Point)
+
+func origin() =
+    return Point(0, 0)
+end
+";
+        let (module, diagnostics) = parsed(source);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let names: Vec<&str> = module
+            .items
+            .iter()
+            .map(|item| match &item.kind {
+                ItemKind::Import(name) => name.name.as_str(),
+                ItemKind::Func(func) => &func.name.name,
+                ItemKind::Struct(def) => &def.name.name,
+                ItemKind::Enum(def) => &def.name.name,
+                ItemKind::Type(alias) => &alias.name.name,
+                ItemKind::Error => "<error>",
+            })
+            .collect();
+        assert_eq!(names, ["IO", "Point", "Shape", "Points", "origin"]);
+
+        let ItemKind::Enum(shape) = &module.items[2].kind else { panic!("expected an enum") };
+        assert_eq!(shape.variants[0].fields[0].name.name, "r");
+        assert!(shape.variants[1].fields.is_empty());
+
+        // Every node spans exactly the text it was parsed from.
+        let ItemKind::Func(origin) = &module.items[4].kind else { panic!("expected a function") };
+        assert_eq!(module.items[4].span.text(source), "func origin() =\n    return Point(0, 0)\nend");
+        assert_eq!(origin.body.stmts[0].span.text(source), "return Point(0, 0)");
+    }
+
+    #[test]
+    fn test_parse_statements_and_expressions() {
+        let source = "func main(xs) =
+    for x in xs
+        p.x = x[0] + 2 * -x[1]
+    end
+    while !done && n < 3
+        n = n +
+            1
+    end
+end
+";
+        let (module, diagnostics) = parsed(source);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let ItemKind::Func(main) = &module.items[0].kind else { panic!("expected a function") };
+        let StmtKind::For { var, body, .. } = &main.body.stmts[0].kind else { panic!("expected a for loop") };
+        assert_eq!(var.name, "x");
+        let StmtKind::Assign { target, value, .. } = &body.stmts[0].kind else { panic!("expected an assignment") };
+        assert!(matches!(target.kind, ExprKind::Field { .. }));
+        let ExprKind::Binary { op: BinaryOp::Add, rhs, .. } = &value.kind else { panic!("expected an addition") };
+        assert_eq!(rhs.span.text(source), "2 * -x[1]");
+
+        // An operator at the end of a line continues the expression onto the next.
+        let StmtKind::While { cond, body, .. } = &main.body.stmts[1].kind else { panic!("expected a while loop") };
+        assert!(matches!(cond.kind, ExprKind::Binary { op: BinaryOp::And, .. }));
+        assert_eq!(body.stmts[0].span.text(source), "n = n +\n            1");
+    }
+
+    #[test]
+    fn test_parse_match() {
+        let source = "func area(s) =
+    return match s
+        Circle(r) => 3 * r * r
+        0 => 0
+        _ => -1
+    end
+end
+";
+        let (module, diagnostics) = parsed(source);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let ItemKind::Func(area) = &module.items[0].kind else { panic!("expected a function") };
+        let StmtKind::Return(Some(value)) = &area.body.stmts[0].kind else { panic!("expected a return") };
+        let ExprKind::Match { arms, .. } = &value.kind else { panic!("expected a match") };
+        let patterns: Vec<&PatternKind> = arms.iter().map(|arm| &arm.pattern.kind).collect();
+        assert!(matches!(patterns[0], PatternKind::Variant { args, .. } if args.len() == 1));
+        assert_eq!(patterns[1], &PatternKind::Lit(Lit::Int(0)));
+        assert_eq!(patterns[2], &PatternKind::Wildcard);
+        assert_eq!(arms[0].span.text(source), "Circle(r) => 3 * r * r");
+    }
+
+    #[test]
+    fn test_parse_errors_are_diagnostics() {
+        let source = "func f() =
+    x = (1 +
+end
+
+func g() =
+    1 = y
+end
+";
+        let (module, diagnostics) = parsed(source);
+        let messages: Vec<(&str, Span)> = diagnostics.iter().map(|d| (d.message.as_str(), d.span)).collect();
+        assert_eq!(
+            messages,
+            [
+                ("expected an expression, found `end`", Span::new(24, 27)),
+                ("invalid assignment target", Span::new(44, 45)),
+            ]
+        );
+        // Both functions are still there for later passes to look at.
+        assert_eq!(module.items.len(), 2);
+    }
+
+    /// Parses `source` as a specification expression and prints its grouping.
+    fn grouping(source: &str) -> (String, Vec<String>) {
+        let (tokens, _) = tokenize(source);
+        let mut parser = Parser::new(source, &tokens);
+        let printed = parser.parse_spec_expr().map_or_else(|e| e.message, |expr| parenthesized(&expr));
+        let errors = parser.into_diagnostics().into_iter().map(|d| d.message).collect();
+        (printed, errors)
+    }
+
+    #[test]
+    fn test_precedence_of_every_operator_pair() {
+        for first in BINARY_OPERATORS {
+            for second in BINARY_OPERATORS {
+                let (a, b) = (first.op.symbol(), second.op.symbol());
+                let (printed, errors) = grouping(&format!("x {a} y {b} z"));
+                let left = format!("((x {a} y) {b} z)");
+                let right = format!("(x {a} (y {b} z))");
+                let expected = match first.precedence.cmp(&second.precedence) {
+                    std::cmp::Ordering::Greater => &left,
+                    std::cmp::Ordering::Less => &right,
+                    std::cmp::Ordering::Equal => match first.assoc {
+                        Assoc::Left | Assoc::None => &left,
+                        Assoc::Right => &right,
+                    },
+                };
+                assert_eq!(&printed, expected, "`x {a} y {b} z`");
+
+                let chained = first.precedence == second.precedence && first.assoc == Assoc::None;
+                let expected_errors: &[&str] = if chained { &["comparison operators cannot be chained"] } else { &[] };
+                assert_eq!(errors, expected_errors, "`x {a} y {b} z`");
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix_operators_bind_tighter_than_binary() {
+        for (_, unary) in PREFIX_OPERATORS {
+            let u = unary.symbol();
+            for binary in BINARY_OPERATORS {
+                let b = binary.op.symbol();
+                assert_eq!(grouping(&format!("{u}x {b} y")).0, format!("(({u}x) {b} y)"));
+                assert_eq!(grouping(&format!("x {b} {u}y")).0, format!("(x {b} ({u}y))"));
+            }
+            // Calls, fields and indexing bind tighter still.
+            assert_eq!(grouping(&format!("{u}f(x).y[0]")).0, format!("({u}f(x).y[0])"));
+        }
+        assert_eq!(grouping("- -x").0, "(-(-x))");
+        assert_eq!(grouping("!(a && b) || c").0, "((!(a && b)) || c)");
+    }
+
+    #[test]
+    fn test_implication_and_chained_comparisons() {
+        assert_eq!(
+            grouping("a > 0 && b => c || d => e").0,
+            "(((a > 0) && b) => ((c || d) => e))"
+        );
+
+        // `=>` is only an operator in specifications.
+        let (module, diagnostics) = parsed("func f(a, b) =\n    a => b\nend\n");
+        assert_eq!(module.items.len(), 1);
+        assert_eq!(diagnostics[0].message, "expected the end of the statement, found `=>`");
+
+        let source = "func f(a, b, c) =\n    return a < b < c\nend\n";
+        let diagnostics = compile(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "comparison operators cannot be chained");
+        assert_eq!(diagnostics[0].span.text(source), "<");
+        assert_eq!(diagnostics[0].labels[0].span.text(source), "<");
+        let edit = &diagnostics[0].suggestions[0].edits[0];
+        assert_eq!((edit.span.start, edit.replacement.as_str()), (source.find("b <").unwrap() + 1, " && b"));
+    }
+
+    #[test]
+    fn test_recovered_return_is_not_missing() {
+        // The statement that did not parse may have been the `return`.
+        let source = "func f(a: Int, b: Int, c: Int) -> Bool =\n    return a < b => b < c\nend\n";
+        let diagnostics = compile(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "expected the end of the statement, found `=>`");
+
+        let diagnostics = compile("func f(a: Int) -> Bool =\n    a = a\nend\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].labels[1].message, "implicitly returns `()` as its body has no `return`");
+    }
+
+    #[test]
+    fn test_independent_syntax_errors_are_reported_once_each() {
+        let source = "\
+enum Option = Some(v) | None end
+
+func params(x,, y) =
+    return x
+end
+
+func condition(x) =
+    if x +
+        return 1
+    end
+    return 0
+end
+
+func loop(xs) =
+    for x xs
+        println(x)
+    end
+end
+
+func arms(s) =
+    return match s
+        Some(v) => v v
+        None => 0
+    end
+end
+
+end
+
+func assign(x) =
+    1 = x
+    return x
+end
+";
+        let (module, diagnostics) = parsed(source);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected a parameter name, found `,`",
+                "expected an expression, found `return`",
+                "expected `in`, found identifier `xs`",
+                "expected the end of the match arm, found identifier `v`",
+                "expected `func`, `struct`, `enum`, `type` or `import`, found `end`",
+                "invalid assignment target",
+            ]
+        );
+
+        // Every function survives, with error nodes where the damage was.
+        let funcs: Vec<&Func> = module
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Func(func) => Some(func),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(funcs.len(), 5);
+        assert!(module.items.iter().any(|item| item.kind == ItemKind::Error));
+        let StmtKind::If { branches, .. } = &funcs[1].body.stmts[0].kind else { panic!("expected an if") };
+        assert_eq!(branches[0].0.kind, ExprKind::Error);
+        assert_eq!(funcs[1].body.stmts.len(), 2);
+        assert_eq!(funcs[4].body.stmts[0].kind, StmtKind::Error);
+
+        // Later passes do not pile on.
+        assert_eq!(compile(source).len(), messages.len());
+    }
+
+    #[test]
+    fn test_missing_end_among_many_functions() {
+        let mut source = String::new();
+        for i in 0..30 {
+            source += &format!("func f{i}(x) =\n    while x > {i}\n        x = x - 1\n");
+            // One loop lost its `end`; the function's `end` must not be taken for it.
+            if i != 17 {
+                source += "    end\n";
+            }
+            source += "    return x\nend\n\n";
+        }
+        let diagnostics = compile(&source);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        let unclosed = &diagnostics[0];
+        assert_eq!(unclosed.message, "unclosed `while`");
+        assert_eq!(unclosed.labels[0].message, "unclosed `while` started here");
+        assert_eq!(
+            unclosed.labels[0].span.start,
+            source.find("while x > 17").unwrap()
+        );
+
+        let (fixed, applied) = apply_fixes(&source, &diagnostics);
+        assert_eq!(applied, 1);
+        assert!(fixed.contains("while x > 17\n        x = x - 1\n    end\n    return x\n"));
+        assert!(compile(&fixed).is_empty());
+    }
+
+    #[test]
+    fn test_syntax_tree_is_lossless() {
+        let sources = [
+            include_str!("../../flerry-lexer/src/test.flerry"),
+            "import IO\n\n#[allow(unused_variables)]\nfunc f(x) =  # trailing\n    y = (x +  1) * 2\n\n    (* nested (* comment *) *)\n    return match y\n        0 => 1\n        _ => y\n    end\nend\n",
+            // Broken code keeps every byte too.
+            "func broken(a,, b) =\n    if a +\n        x = @ 1 // slashes\n    end\n\"unterminated\nend\nfunc é() = (* open",
+            "",
+        ];
+        for source in sources {
+            let (tree, _) = parse_lossless(source, &mut NodeCache::new());
+            assert_eq!(tree.kind(), NodeKind::SourceFile);
+            assert_eq!(tree.text(), source);
+            assert_eq!(tree.span(), Span::new(0, source.len()));
+
+            // Every token knows where it is.
+            for token in tree.descendant_tokens() {
+                assert_eq!(token.span().text(source), token.text());
+            }
+        }
+    }
+
+    #[test]
+    fn test_syntax_tree_typed_views() {
+        let source = "# adds\nfunc add(x, y) =\n    return (x + y) * 2 # doubled\nend\n";
+        let (tree, diagnostics) = parse_lossless(source, &mut NodeCache::new());
+        assert!(diagnostics.is_empty());
+
+        let file = views::SourceFile::cast(tree.clone()).unwrap();
+        let Some(views::Item::Func(add)) = file.items().next() else { panic!("expected a function") };
+        assert_eq!(add.name().unwrap().text(), "add");
+        let params: Vec<String> = add.params().map(|p| p.name().unwrap().text().to_owned()).collect();
+        assert_eq!(params, ["x", "y"]);
+
+        let Some(views::Stmt::Return(ret)) = add.body().unwrap().statements().next() else { panic!("expected a return") };
+        let Some(views::Expr::Binary(product)) = ret.value() else { panic!("expected a binary expression") };
+        assert_eq!(product.op().unwrap().text(), "*");
+        let Some(views::Expr::Binary(sum)) = product.lhs() else { panic!("expected a binary expression") };
+        assert_eq!(sum.syntax().text(), "(x + y)");
+        assert_eq!(sum.op().unwrap().text(), "+");
+
+        // Comments before an item belong to the file, comments inside it to the item.
+        let comment = tree.token_at_offset(source.find("# doubled").unwrap()).unwrap();
+        assert_eq!(comment.kind(), TokenType::Comment);
+        assert_eq!(comment.parent().kind(), NodeKind::Func);
+        assert_eq!(tree.token_at_offset(0).unwrap().parent().kind(), NodeKind::SourceFile);
+        assert!(comment.parent().ancestors().any(|node| node == tree));
+    }
+
+    #[test]
+    fn test_syntax_tree_shares_unchanged_subtrees() {
+        let functions: Vec<String> = (0..5)
+            .map(|i| format!("func f{i}(x) =\n    return x + {i}\nend\n"))
+            .collect();
+        let before = functions.join("\n");
+        let after = before.replace("return x + 2", "return x * 2 - 1");
+
+        let mut cache = NodeCache::new();
+        let (old, _) = parse_lossless(&before, &mut cache);
+        let (new, _) = parse_lossless(&after, &mut cache);
+        assert_eq!(new.text(), after);
+
+        let old_funcs: Vec<_> = old.children().filter(|n| n.kind() == NodeKind::Func).collect();
+        let new_funcs: Vec<_> = new.children().filter(|n| n.kind() == NodeKind::Func).collect();
+        for (i, (old, new)) in old_funcs.iter().zip(&new_funcs).enumerate() {
+            // Positions shift, but unchanged functions are the very same green node.
+            assert_eq!(Rc::ptr_eq(old.green(), new.green()), i != 2, "function {i}");
+        }
+
+        // Identical subtrees are stored once even within one file.
+        let params: Vec<_> = new.descendants().into_iter().filter(|n| n.kind() == NodeKind::Param).collect();
+        assert!(params.windows(2).all(|pair| Rc::ptr_eq(pair[0].green(), pair[1].green())));
+    }
+
+    /// Parses, prints and parses again, checking the second parse gives the
+    /// same tree and printing it again changes nothing.
+    fn round_trip(source: &str) -> String {
+        let (module, diagnostics) = parse_source(source);
+        assert!(diagnostics.is_empty(), "{source}\n{diagnostics:?}");
+        let printed = pretty::module(&module);
+        let (reparsed, diagnostics) = parse_source(&printed);
+        assert!(diagnostics.is_empty(), "{printed}\n{diagnostics:?}");
+        assert_eq!(dump(&reparsed, Format::Sexp), dump(&module, Format::Sexp), "{printed}");
+        assert_eq!(pretty::module(&reparsed), printed);
+        printed
+    }
+
+    #[test]
+    fn test_pretty_printer_is_canonical() {
+        let source = "import IO\nfunc f(x,y)=\n  z = (x+y)*-(x-1)  # tidy\n  if z>1 && !(x==y)\n    return match z\n      0=>[1,2.5]\n      Some(v)=>(-v).f(1)[2]\n    end\n  elsif x\n    return\n  else\n   for i in xs\n     IO.print(i)\n   end\n  end\nend\nstruct Point = x, y end\nenum Shape = Circle(r) | Empty end\ntype Grid = List(List(Int))\n";
+        let expected = "\
+import IO
+
+func f(x, y) =
+    z = (x + y) * -(x - 1)
+    if z > 1 && !(x == y)
+        return match z
+            0 => [1, 2.5]
+            Some(v) => (-v).f(1)[2]
+        end
+    elsif x
+        return
+    else
+        for i in xs
+            IO.print(i)
+        end
+    end
+end
+
+struct Point = x, y end
+
+enum Shape = Circle(r) | Empty end
+
+type Grid = List(List(Int))
+";
+        assert_eq!(round_trip(source), expected);
+    }
+
+    #[test]
+    fn test_pretty_printer_round_trips() {
+        let sources = [
+            "func f(a, b, c) =\n    return a - (b - c) + (a - b) - c\nend\n",
+            "func f(a, b, c) =\n    return a / (b * c) * a / b\nend\n",
+            "func f(a, b, c) =\n    return a || b && c || (a || b) && c\nend\n",
+            "func f(a, b) =\n    return (a < b) == (b < a)\nend\n",
+            "func f(a) =\n    return --a + -(-a) - !!a\nend\n",
+            "func f(xs) =\n    xs[0] = xs[1].y(2, [3])\n    return (-xs)[0]\nend\n",
+            "func f(x) =\n    y = match x\n        _ => match x\n            -1 => 1.0\n            true => 2\n        end + 1\n    end\n    return y\nend\n",
+            "func f(x) =\n    while x > 0\n        if x == 1\n            x = x - 1\n        end\n    end\nend\n",
+            "func big() =\n    return 100000000000000000000.0\nend\n",
+        ];
+        for source in sources {
+            round_trip(source);
+        }
+    }
+
+    #[test]
+    fn test_dump_formats() {
+        let (module, _) = parse_source("func f(x) =\n    return -x + \"a\\b\"\nend\n");
+        assert_eq!(
+            dump(&module, Format::Sexp),
+            "(Module (Func f (Param x) (Block (Return (Binary + (Unary - (Ident x)) (Lit \"a\\b\"))))))\n"
+        );
+        assert_eq!(
+            dump(&module, Format::Tree),
+            "\
+Module @0..38
+  Func f @0..37
+    Param x @7..8
+    Block @16..33
+      Return @16..33
+        Binary + @23..33
+          Unary - @23..25
+            Ident x @24..25
+          Lit \"a\\b\" @28..33
+"
+        );
+        let json = dump(&module, Format::Json);
+        assert!(json.starts_with(r#"{"kind":"Module","span":[0,38],"children":[{"kind":"Func","value":"f""#));
+        assert!(json.contains(r#"{"kind":"Lit","value":"\"a\\b\"","span":[28,33]}"#), "{json}");
+        assert_eq!(Format::from_name("sexp"), Some(Format::Sexp));
+        assert_eq!(Format::from_name("yaml"), None);
+    }
+
+    /// A program with at least one node of every kind.
+    const EVERY_NODE: &str = "\
+import IO
+struct Point = x, y end
+enum Shape = Circle(r) | Empty end
+type Grid = List(List(Int))
+
+func area(shape, p) =
+    p.x = [1, 2.5, \"s\", true][0]
+    if -p.y < 0 && !false
+        return match shape
+            Circle(r) => r * r
+            Empty => 0
+            _ => -1
+        end
+    elsif p
+        while p.x > 0
+            p.x = p.x - 1
+        end
+    else
+        for i in range(3)
+            IO.print(i)
+        end
+    end
+    return
+end
+";
+
+    #[test]
+    fn test_identity_fold_reproduces_the_tree() {
+        struct Identity;
+        impl Folder for Identity {}
+
+        let (module, diagnostics) = parse_source(EVERY_NODE);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(Identity.fold_module(module.clone()), module);
+    }
+
+    /// The syntax added since `EVERY_NODE`: typed fields, indexed enums,
+    /// function, tuple and refinement types, implicit parameters, contracts,
+    /// annotations, holes, lambdas, struct literals, tuples, guards and
+    /// or-patterns.
+    const LATER_NODES: &str = "\
+struct Point = x: Float, y end
+enum Vec(a: Type, n: Nat) =
+    Nil -> Vec(a, 0)
+    | Cons({k: Nat}, x: a, xs: Vec(a, k)) -> Vec(a, k + 1)
+end
+type Pair = (Int, Str)
+type Make = (n: Nat) -> Vec(Int, n)
+type Index(n) = Nat where self < n
+
+func walk({n: Nat}, xs: Vec(Int, n), f: (Int, Int) -> Int, p: Point) -> (Int, Bool) =
+    requires n > 0
+    ensures result == old(result)
+    k: Int = ?
+    g = (x: Int, y) => f(x, y) + k
+    q = Point { x: 1.0, ..p }
+    i = 0
+    while i < n
+        invariant i >= 0 && i <= n
+        decreases n - i
+        i = i + 1
+    end
+    for x in xs
+        invariant true
+        g(x, ?step)
+    end
+    m = match xs
+        Cons(x, _) if x > 0 => x
+        Nil | Cons(_, _) => p.norm()
+    end
+    return (m, q.x > 0.0)
+end
+";
+
+    #[test]
+    fn test_identity_fold_reproduces_later_nodes() {
+        struct Identity;
+        impl Folder for Identity {}
+
+        let (module, diagnostics) = parse_source(LATER_NODES);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        let tree = dump(&module, Format::Sexp);
+        for kind in ["Lambda", "Tuple", "Hole", "Struct", "Guard", "OrPat", "Invariant", "Where"] {
+            assert!(tree.contains(kind), "{kind} in {tree}");
+        }
+        assert_eq!(Identity.fold_module(module.clone()), module);
+
+        // Closures only come from lifting lambdas.
+        let (lowered, diagnostics) = lower("func adder(n) =\n    return (x) => x + n\nend\n", &Options::default());
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert!(dump(&lowered, Format::Sexp).contains("Closure"));
+        assert_eq!(Identity.fold_module(lowered.clone()), lowered);
+    }
+
+    #[test]
+    fn test_visitors_reach_every_node() {
+        #[derive(Default)]
+        struct Counter<'a> {
+            idents: Vec<&'a str>,
+            exprs: usize,
+            patterns: usize,
+        }
+        impl<'a> Visitor<'a> for Counter<'a> {
+            fn visit_ident(&mut self, ident: &'a Ident) {
+                self.idents.push(&ident.name);
+            }
+            fn visit_expr(&mut self, expr: &'a Expr) {
+                self.exprs += 1;
+                visit::walk_expr(self, expr);
+            }
+            fn visit_pattern(&mut self, pattern: &'a Pattern) {
+                self.patterns += 1;
+                visit::walk_pattern(self, pattern);
+            }
+        }
+
+        let (module, _) = parse_source(EVERY_NODE);
+        let mut counter = Counter::default();
+        counter.visit_module(&module);
+        assert_eq!(counter.patterns, 4);
+        assert_eq!(counter.exprs, 43);
+        assert_eq!(
+            counter.idents[..12],
+            ["IO", "Point", "x", "y", "Shape", "Circle", "r", "Empty", "Grid", "List", "List", "Int"]
+        );
+        assert_eq!(counter.idents.iter().filter(|name| **name == "p").count(), 7);
+
+        // Renaming in place reaches bindings, uses and field names alike.
+        struct Rename;
+        impl VisitorMut for Rename {
+            fn visit_ident_mut(&mut self, ident: &mut Ident) {
+                if ident.name == "p" {
+                    ident.name = "point".to_owned();
+                }
+            }
+        }
+        let mut renamed = module.clone();
+        Rename.visit_module_mut(&mut renamed);
+        let printed = pretty::module(&renamed);
+        assert!(printed.contains("func area(shape, point) =\n    point.x = "), "{printed}");
+        assert!(!printed.contains(" p.") && !printed.contains("(p"));
+    }
+
+    #[test]
+    fn test_traversals_reach_struct_nodes() {
+        let source = "struct Point = x: Float, y end\n\nfunc f(p: Point) =\n    return Point { x: p.x, ..p }\nend\n";
+        struct Identity;
+        impl Folder for Identity {}
+        let (module, diagnostics) = parse_source(source);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(Identity.fold_module(module.clone()), module);
+
+        #[derive(Default)]
+        struct Idents<'a>(Vec<&'a str>);
+        impl<'a> Visitor<'a> for Idents<'a> {
+            fn visit_ident(&mut self, ident: &'a Ident) {
+                self.0.push(&ident.name);
+            }
+        }
+        let mut idents = Idents::default();
+        idents.visit_module(&module);
+        // Field types, parameter types, literal fields and the base.
+        assert_eq!(
+            idents.0,
+            ["Point", "x", "Float", "y", "f", "p", "Point", "Point", "x", "p", "x", "p"]
+        );
+
+        struct Rename;
+        impl VisitorMut for Rename {
+            fn visit_ident_mut(&mut self, ident: &mut Ident) {
+                if ident.name == "p" {
+                    ident.name = "point".to_owned();
+                }
+            }
+        }
+        let mut renamed = module.clone();
+        Rename.visit_module_mut(&mut renamed);
+        assert!(
+            pretty::module(&renamed).contains("func f(point: Point) =\n    return Point { x: point.x, ..point }")
+        );
+    }
+
+    #[test]
+    fn test_folder_rewrites_nodes() {
+        /// Folds additions and multiplications of integer literals, inside out.
+        struct ConstantFolder;
+        impl Folder for ConstantFolder {
+            fn fold_expr(&mut self, expr: Expr) -> Expr {
+                let expr = fold::walk_expr(self, expr);
+                let ExprKind::Binary { op, lhs, rhs } = &expr.kind else {
+                    return expr;
+                };
+                let value = match (op, &lhs.kind, &rhs.kind) {
+                    (BinaryOp::Add, ExprKind::Lit(Lit::Int(a)), ExprKind::Lit(Lit::Int(b))) => a + b,
+                    (BinaryOp::Mul, ExprKind::Lit(Lit::Int(a)), ExprKind::Lit(Lit::Int(b))) => a * b,
+                    _ => return expr,
+                };
+                Expr {
+                    kind: ExprKind::Lit(Lit::Int(value)),
+                    span: expr.span,
+                }
+            }
+        }
+
+        let (module, _) = parse_source("func f(x) =\n    return [1 + 2 * 3, x + 2 * 3, x * (1 + 1)]\nend\n");
+        let folded = ConstantFolder.fold_module(module);
+        assert_eq!(
+            pretty::module(&folded),
+            "func f(x) =\n    return [7, x + 6, x * 2]\nend\n"
+        );
+    }
+
+    const SHAPES: &str =
+        "enum Shape = Circle(r: Float) | Rect(w: Float, h: Float) | Empty end\nenum Opt = Some(v: Shape) | None end\n";
+
+    fn match_errors(body: &str) -> Vec<Diagnostic> {
+        compile(&format!(
+            "{SHAPES}func f(s) =\n    return match s\n{body}    end\nend\n"
+        ))
+    }
+
+    #[test]
+    fn test_parse_enum_payloads_guards_and_or_patterns() {
+        let printed = round_trip(
+            "enum Shape = Circle(r: Float) | Rect(w: Float, h) | Empty end\nfunc f(s) =\n    return match s\n        Circle(r) if r > 0.0 => 1\n        Rect(_, _) | Empty => 0\n        _ => -1\n    end\nend\n",
+        );
+        assert!(printed.contains("Rect(w: Float, h)"), "{printed}");
+
+        let (module, _) = parsed(&printed);
+        let ItemKind::Func(func) = &module.items[1].kind else {
+            panic!("expected a function")
+        };
+        let StmtKind::Return(Some(Expr {
+            kind: ExprKind::Match { arms, .. },
+            ..
+        })) = &func.body.stmts[0].kind
+        else {
+            panic!("expected a match");
+        };
+        assert!(matches!(
+            &arms[0].guard,
+            Some(Expr {
+                kind: ExprKind::Binary { op: BinaryOp::Gt, .. },
+                ..
+            })
+        ));
+        let PatternKind::Or(alternatives) = &arms[1].pattern.kind else {
+            panic!("expected alternatives")
+        };
+        assert_eq!(alternatives.len(), 2);
+        assert_eq!(arms[1].guard, None);
+
+        let tree = dump(&module, Format::Sexp);
+        assert!(tree.contains("(Guard"), "{tree}");
+        assert!(tree.contains("(OrPat"), "{tree}");
+    }
+
+    #[test]
+    fn test_non_exhaustive_match() {
+        let diagnostics = match_errors("        Some(Circle(r)) => r\n        None => 0.0\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(
+            diagnostics[0].message,
+            "non-exhaustive patterns: `Some(Rect(_, _))` and `Some(Empty)` not covered"
+        );
+        assert_eq!(
+            diagnostics[0].labels[0].message,
+            "patterns `Some(Rect(_, _))` and `Some(Empty)` not covered"
+        );
+        assert!(
+            diagnostics[0]
+                .help
+                .iter()
+                .any(|help| help.starts_with("ensure that all possible cases"))
+        );
+
+        // A guarded arm covers nothing for certain.
+        let diagnostics = match_errors("        Some(_) if true => 1\n        None => 0\n");
+        assert_eq!(diagnostics[0].message, "non-exhaustive patterns: `Some(_)` not covered");
+
+        // Types with infinitely many values need a catch-all.
+        let diagnostics = compile("func f(n) =\n    return match n\n        0 => 1\n        1 => 1\n    end\nend\n");
+        assert_eq!(diagnostics[0].message, "non-exhaustive patterns: `_` not covered");
+        let diagnostics = compile("func f(b) =\n    return match b\n        true => 1\n    end\nend\n");
+        assert_eq!(diagnostics[0].message, "non-exhaustive patterns: `false` not covered");
+
+        for exhaustive in [
+            "        Some(Circle(_)) | Some(Rect(_, _)) => 1\n        Some(Empty) | None => 0\n",
+            "        Some(x) if x == x => 1\n        Some(_) => 2\n        None => 0\n",
+        ] {
+            assert_eq!(match_errors(exhaustive), [], "{exhaustive}");
+        }
+        // Exhaustive, but `0` is not an option like `other`.
+        let diagnostics = match_errors("        None => 0\n        other => other\n");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["mismatched types"]);
+    }
+
+    #[test]
+    fn test_unreachable_patterns() {
+        let diagnostics = match_errors("        _ => 0\n        None => 1\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].level, Level::Warning);
+        assert_eq!(diagnostics[0].message, "unreachable pattern");
+        assert_eq!(diagnostics[0].lint, Some("unreachable_patterns"));
+        let messages: Vec<&str> = diagnostics[0]
+            .labels
+            .iter()
+            .map(|label| label.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            ["no value can reach this pattern", "this pattern matches any value"]
+        );
+
+        // An alternative covered by the arms before it.
+        let diagnostics = match_errors("        Some(_) => 1\n        None | Some(Empty) => 0\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "unreachable pattern");
+        let source = format!(
+            "{SHAPES}func f(s) =\n    return match s\n        Some(_) => 1\n        None | Some(Empty) => 0\n    end\nend\n"
+        );
+        assert_eq!(diagnostics[0].span.text(&source), "Some(Empty)");
+
+        // A guard can fail, so the arms after it still matter.
+        assert_eq!(
+            match_errors("        None if true => 0\n        None => 1\n        Some(_) => 2\n"),
+            []
+        );
+    }
+
+    #[test]
+    fn test_pattern_and_constructor_errors() {
+        let messages = |body: &str| -> Vec<String> { match_errors(body).into_iter().map(|d| d.message).collect() };
+        assert_eq!(
+            messages("        Some(Circle(\"r\")) => 0\n        _ => 1\n"),
+            ["mismatched types"]
+        );
+        let diagnostics = match_errors("        Some(Circle(\"r\")) => 0\n        _ => 1\n");
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Float`, found `Str`");
+        assert_eq!(
+            messages("        Some(Rect(w)) => w\n        _ => 0.0\n"),
+            ["this pattern has 1 field, but the variant `Rect` has 2 fields"]
+        );
+        assert_eq!(
+            messages("        Some(Rect(x, x)) => x\n        _ => 0.0\n"),
+            ["identifier `x` is bound more than once in the same pattern"]
+        );
+        assert_eq!(
+            messages("        Some(Circle(r)) | None => r\n        _ => 1.0\n"),
+            ["variable `r` is not bound in all patterns"]
+        );
+
+        let diagnostics = compile(&format!("{SHAPES}func f() =\n    return Rect(1.0)\nend\n"));
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(
+            diagnostics[0].message,
+            "this variant takes 2 arguments but 1 argument was supplied"
+        );
+        assert_eq!(diagnostics[0].labels.last().unwrap().message, "`Rect` defined here");
+    }
+
+    /// Runs a decision tree on a value, returning the chosen arm and checking
+    /// no path tests the same part of the value twice.
+    fn run(tree: &Decision, value: &Pat, guards: &[bool], tested: &mut Vec<Occurrence>) -> Option<usize> {
+        fn part<'v>(value: &'v Pat, occurrence: &[usize]) -> &'v Pat {
+            occurrence.iter().fold(value, |value, &i| match value {
+                Pat::Con(_, args) => &args[i],
+                _ => panic!("no part {occurrence:?}"),
+            })
+        }
+        match tree {
+            Decision::Fail => None,
+            Decision::Success { arm, .. } => Some(*arm),
+            Decision::Guard { arm, otherwise, .. } if guards[*arm] => Some(*arm),
+            Decision::Guard { otherwise, .. } => run(otherwise, value, guards, tested),
+            Decision::Switch {
+                occurrence,
+                cases,
+                default,
+            } => {
+                assert!(!tested.contains(occurrence), "{occurrence:?} is tested twice");
+                tested.push(occurrence.clone());
+                let Pat::Con(constructor, _) = part(value, occurrence) else {
+                    panic!("values are constructors")
+                };
+                let next = cases
+                    .iter()
+                    .find(|(c, _)| c == constructor)
+                    .map(|(_, next)| next)
+                    .or(default.as_deref())
+                    .expect("a case for every constructor");
+                let arm = run(next, value, guards, tested);
+                tested.pop();
+                arm
+            }
+        }
+    }
+
+    #[test]
+    fn test_decision_tree_picks_the_first_matching_arm() {
+        let enums: decision::Enums = [("Opt".to_owned(), vec![("Some".to_owned(), 1), ("None".to_owned(), 0)])].into();
+        let con = |name: &str, args: Vec<Pat>| {
+            let constructor = Constructor::Variant {
+                enum_name: "Opt".to_owned(),
+                name: name.to_owned(),
+                arity: args.len(),
+            };
+            Pat::Con(constructor, args)
+        };
+        let int = |value| Pat::Con(Constructor::Int(value), Vec::new());
+        let any = || Pat::Any(None);
+        let arm = |pattern, guarded| decision::Arm { pattern, guarded };
+        let arms = [
+            arm(con("Some", vec![int(0)]), false),
+            arm(
+                Pat::Or(vec![con("Some", vec![int(1)]), con("Some", vec![int(2)])]),
+                true,
+            ),
+            arm(con("None", vec![]), false),
+            arm(con("Some", vec![any()]), false),
+        ];
+        let compiled = decision::compile(&arms, &enums);
+        assert_eq!(compiled.missing, Vec::<String>::new());
+        assert_eq!(
+            compiled.reachable,
+            [vec![true], vec![true, true], vec![true], vec![true]]
+        );
+
+        let values = [
+            con("None", vec![]),
+            con("Some", vec![int(0)]),
+            con("Some", vec![int(1)]),
+            con("Some", vec![int(2)]),
+            con("Some", vec![int(5)]),
+        ];
+        for guards in [[false; 4], [true; 4]] {
+            for value in &values {
+                let expected = arms
+                    .iter()
+                    .position(|arm| matches(&arm.pattern, value) && (!arm.guarded || guards[1]))
+                    .unwrap();
+                assert_eq!(
+                    run(&compiled.tree, value, &guards, &mut Vec::new()),
+                    Some(expected),
+                    "{value:?}"
+                );
+            }
+        }
+
+        fn matches(pattern: &Pat, value: &Pat) -> bool {
+            match (pattern, value) {
+                (Pat::Any(_), _) => true,
+                (Pat::Or(alternatives), _) => alternatives.iter().any(|p| matches(p, value)),
+                (Pat::Con(c, args), Pat::Con(d, values)) => {
+                    c == d && args.iter().zip(values).all(|(p, v)| matches(p, v))
+                }
+                _ => false,
+            }
+        }
+
+        let compiled = decision::compile(&arms[..3], &enums);
+        // Without the catch-all, `Some` of any other number is missing, and
+        // so are the values the guard might turn away.
+        assert_eq!(compiled.missing, ["Some(1)", "Some(2)", "Some(_)"]);
+    }
+
+    const POINTS: &str = "struct Point = x: Float, y: Float end\nstruct Line = from: Point, to: Point end\n\nfunc norm(p: Point) =\n    return p.x * p.x + p.y * p.y\nend\n\nfunc scale(p: Point, k) =\n    return Point { x: p.x * k, y: p.y * k }\nend\n\n";
+
+    fn struct_errors(body: &str) -> Vec<Diagnostic> {
+        let diagnostics = compile(&format!("{POINTS}func main(k) =\n{body}end\n"));
+        diagnostics.into_iter().filter(|d| d.level == Level::Error).collect()
+    }
+
+    #[test]
+    fn test_parse_structs() {
+        let printed = round_trip(
+            "struct Point = x: Float, y end\nfunc f(p: Point, y) =\n    q = Point {\n        x: 1.0,\n        y: y\n    }\n    return Point { x: q.x, ..p }.x\nend\n",
+        );
+        assert!(printed.contains("struct Point = x: Float, y end"), "{printed}");
+        assert!(printed.contains("func f(p: Point, y) ="), "{printed}");
+        // `y: y` is printed in its shorthand form.
+        assert!(printed.contains("q = Point { x: 1.0, y }"), "{printed}");
+        assert!(printed.contains("return Point { x: q.x, ..p }.x"), "{printed}");
+
+        let mut cache = NodeCache::default();
+        let (root, _) = parse_lossless(&printed, &mut cache);
+        let literal = root
+            .descendants()
+            .into_iter()
+            .filter_map(views::StructExpr::cast)
+            .nth(1)
+            .expect("a struct literal with a base");
+        assert_eq!(literal.name().unwrap().text(), "Point");
+        let fields: Vec<String> = literal.fields().map(|f| f.name().unwrap().text().to_owned()).collect();
+        assert_eq!(fields, ["x"]);
+        assert_eq!(literal.base().unwrap().syntax().text(), "p");
+    }
+
+    #[test]
+    fn test_struct_literal_errors() {
+        assert_eq!(struct_errors("    return Point { x: 1.0, y: 2.0 }\n"), []);
+        assert_eq!(struct_errors("    p = Point { x: 1.0, y: 2.0 }\n    return Point { y: 0.0, ..p }\n"), []);
+
+        let diagnostics = struct_errors("    return Point { x: 1.0, y: 2.0, x: 3.0 }\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "field `x` specified more than once");
+        assert_eq!(diagnostics[0].labels[1].message, "first use of `x`");
+
+        let diagnostics = struct_errors("    return Point { x: 1.0, yy: 2.0 }\n");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["struct `Point` has no field named `yy`", "missing field `y` in initializer of `Point`"]
+        );
+        assert_eq!(diagnostics[0].suggestions[0].message, "a field with a similar name exists: `y`");
+        assert_eq!(diagnostics[1].labels[0].message, "missing `y`");
+
+        let diagnostics = compile("struct Big = a, b, c, d, e, a end\nfunc f() =\n    return Big { }\nend\n");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "field `a` is already declared",
+                "missing fields `a`, `b`, `c` and 3 other fields in initializer of `Big`"
+            ]
+        );
+
+        let diagnostics = struct_errors("    l = Line { from: Point { x: 0.0, y: 0.0 }, to: 1 }\n    return Point { ..l }\n");
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "mismatched types");
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Point`, found `Line`");
+        // The field is declared a `Point`, so `1` does not fit it.
+        assert_eq!(diagnostics[1].message, "mismatched types");
+        assert_eq!(diagnostics[1].labels[0].message, "expected `Point`, found a number");
+    }
+
+    #[test]
+    fn test_field_access_and_methods() {
+        let fine = "    p = Point { x: 3.0, y: 4.0 }\n    l = Line { from: p, to: p.scale(2.0) }\n    l.to.x = p.norm()\n    return l.from.y + k.anything\n";
+        assert_eq!(struct_errors(fine), []);
+
+        let messages = |body: &str| -> Vec<String> { struct_errors(body).into_iter().map(|d| d.message).collect() };
+        assert_eq!(
+            messages("    l = Line { from: k, to: k }\n    return l.to.z\n"),
+            ["no field `z` on type `Point`"]
+        );
+        assert_eq!(
+            messages("    p = Point { x: 3.0, y: 4.0 }\n    p.w = 1.0\n    return p.norm\n"),
+            ["no field `w` on type `Point`", "attempted to take value of method `norm` on type `Point`"]
+        );
+        assert_eq!(
+            messages("    p = Point { x: 3.0, y: 4.0 }\n    return p.nrom() + p.scale()\n"),
+            [
+                "no method named `nrom` found for struct `Point`",
+                "this method takes 1 argument but 0 arguments were supplied"
+            ]
+        );
+
+        // A function without a typed first parameter is not a method.
+        let diagnostics = compile(&format!(
+            "{POINTS}func length(p) =\n    return p\nend\n\nfunc main() =\n    return Point {{ x: 1.0, y: 1.0 }}.length()\nend\n"
+        ));
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "no method named `length` found for struct `Point`");
+        assert_eq!(
+            diagnostics[0].help,
+            ["to call `length` as a method, declare its first parameter with type `Point`"]
+        );
+
+        // Which struct a value is comes from inference, across assignments
+        // and calls; a receiver whose type is not known is not checked.
+        assert_eq!(
+            messages("    p = Point { x: 3.0, y: 4.0 }\n    p = k\n    return p.z + k.w\n"),
+            ["no field `z` on type `Point`", "no field `w` on type `Point`"]
+        );
+        let diagnostics = compile(&format!(
+            "{POINTS}func origin() -> Point =\n    return Point {{ x: 0.0, y: 0.0 }}\nend\n\nfunc main(k) =\n    return origin().z + origin().nrom() + k.z\nend\n"
+        ));
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["no field `z` on type `Point`", "no method named `nrom` found for struct `Point`"]
+        );
+    }
+
+    #[test]
+    fn test_parse_lambdas_and_function_types() {
+        let printed = round_trip(
+            "type Op = (Int, Int) -> Int\ntype Curried = Int -> (Int -> Int) -> Int\nfunc f(g: Int -> Int, n) =\n    h = (x: Int, y) => g(x) + y * n\n    return [((x) => x)(1), 1 + ((y) => y), -((z) => z), (f) => (g) => f(g)]\nend\n",
+        );
+        assert!(printed.contains("type Op = (Int, Int) -> Int"), "{printed}");
+        // The arrow is right associative, so only the parameter needs parentheses.
+        assert!(printed.contains("type Curried = Int -> (Int -> Int) -> Int"), "{printed}");
+        assert!(printed.contains("func f(g: Int -> Int, n) ="), "{printed}");
+        assert!(printed.contains("h = (x: Int, y) => g(x) + y * n"), "{printed}");
+        assert!(printed.contains("[((x) => x)(1), 1 + ((y) => y), -((z) => z), (f) => (g) => f(g)]"), "{printed}");
+
+        let (module, _) = parse_source("func f() =\n    return (x) => x\nend\n");
+        assert_eq!(
+            dump(&module, Format::Sexp),
+            "(Module (Func f (Block (Return (Lambda (Param x) (Ident x))))))\n"
+        );
+
+        let mut cache = NodeCache::default();
+        let (root, _) = parse_lossless(&printed, &mut cache);
+        let lambda = root
+            .descendants()
+            .into_iter()
+            .find_map(views::LambdaExpr::cast)
+            .expect("a lambda");
+        let params: Vec<String> = lambda.params().map(|p| p.name().unwrap().text().to_owned()).collect();
+        assert_eq!(params, ["x", "y"]);
+        assert_eq!(lambda.body().unwrap().syntax().text(), "g(x) + y * n");
+
+        // A parenthesized list is a tuple, not a lambda, unless `=>` follows.
+        let (module, diagnostics) = parse_source("func f(x, y) =\n    return (x, y)\nend\n");
+        assert_eq!(diagnostics, [], "{module:?}");
+        assert_eq!(
+            dump(&module, Format::Sexp),
+            "(Module (Func f (Param x) (Param y) (Block (Return (Tuple (Ident x) (Ident y))))))\n"
+        );
+    }
+
+    #[test]
+    fn test_lambda_parameters_are_scoped() {
+        assert_eq!(compile("func f(n) =\n    return (x) => x + n\nend\n"), []);
+
+        let diagnostics = compile("func f() =\n    g = (x) => x\n    return g(x)\nend\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "cannot find `x` in this scope");
+
+        let diagnostics = compile("func f() =\n    return (x, y) => y\nend\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "unused variable: `x`");
+    }
+
+    fn lowered(source: &str) -> String {
+        let (module, diagnostics) = parse_source(source);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        pretty::module(&closure::convert(module))
+    }
+
+    #[test]
+    fn test_closure_conversion() {
+        assert_eq!(
+            lowered("func adder(n) =\n    return (x) => x + n\nend\n"),
+            "func adder(n) =\n    return closure adder$lambda0[n]\nend\n\nfunc adder$lambda0(n, x) =\n    return x + n\nend\n"
+        );
+
+        // A variable assigned again after the closure is made is shared with it.
+        let printed = lowered(
+            "func counter(xs) =\n    count = 0\n    f = (x: Int) => count + x\n    for x in xs\n        count = count + 1\n    end\n    return f\nend\n",
+        );
+        assert!(printed.contains("f = closure counter$lambda0[ref count]"), "{printed}");
+        assert!(printed.contains("func counter$lambda0(count, x: Int) ="), "{printed}");
+
+        // Mutating a field counts too; globals and the lambda's own names are
+        // never captured.
+        let printed = lowered("func f(p, k) =\n    p.x = 1\n    return (q) => print(p.x + q + k)\nend\n");
+        assert!(printed.contains("closure f$lambda0[ref p, k]"), "{printed}");
+
+        // Inner lambdas are lifted first, and the outer one captures what
+        // they need from further out.
+        let printed = lowered("func nested(a, b) =\n    return (x) => (y) => a + x + y + b\nend\n");
+        assert!(printed.contains("return closure nested$lambda0[a, b]"), "{printed}");
+        assert!(printed.contains("func nested$lambda0(a, b, x) =\n    return closure nested$lambda1[a, x, b]"), "{printed}");
+        assert!(printed.contains("func nested$lambda1(a, x, b, y) =\n    return a + x + y + b"), "{printed}");
+
+        // Names bound by a pattern inside the lambda are its own.
+        let printed = lowered("func f(v, xs) =\n    return (o) => match o\n        Some(v) => v\n        _ => xs\n    end\nend\n");
+        assert!(printed.contains("closure f$lambda0[xs]"), "{printed}");
+    }
+
+    #[test]
+    fn test_parse_type_annotations() {
+        let printed = round_trip(
+            "type Pair = (Int, Str)\ntype Unit = ()\nfunc f(p: Pair, g: ((Int, Int)) -> Int) -> List(Int) -> Int =\n    n: Int = g(p)\n    xs: List((a, Bool)) = []\n    return (ys: List(Int)) => n\nend\n",
+        );
+        assert!(printed.contains("type Pair = (Int, Str)\n\ntype Unit = ()"), "{printed}");
+        // A lone tuple parameter keeps its own parentheses.
+        assert!(printed.contains("func f(p: Pair, g: ((Int, Int)) -> Int) -> List(Int) -> Int ="), "{printed}");
+        assert!(printed.contains("    n: Int = g(p)\n    xs: List((a, Bool)) = []"), "{printed}");
+
+        let (module, _) = parse_source("func f(x) -> Int =\n    y: Int = x\n    return y\nend\n");
+        assert_eq!(
+            dump(&module, Format::Sexp),
+            "(Module (Func f (Param x) (TypeExpr Int) (Block (Assign (Ident y) (TypeExpr Int) (Ident x)) (Return (Ident y)))))\n"
+        );
+
+        let mut cache = NodeCache::default();
+        let (root, _) = parse_lossless(&printed, &mut cache);
+        let func = root.descendants().into_iter().find_map(views::Func::cast).expect("a function");
+        assert_eq!(func.ret().unwrap().syntax().text(), "List(Int) -> Int");
+        let assign = root.descendants().into_iter().find_map(views::AssignStmt::cast).expect("an assignment");
+        assert_eq!(assign.ty().unwrap().syntax().text(), "Int");
+        assert_eq!(assign.value().unwrap().syntax().text(), "g(p)");
+
+        // Only a name can be declared with a type.
+        let (_, diagnostics) = parse_source("func f(p) =\n    p.x: Int = 1\nend\n");
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "expected the end of the statement, found `:`");
+    }
+
+    #[test]
+    fn test_type_annotation_errors() {
+        let messages = |source: &str| -> Vec<String> {
+            compile(source).into_iter().filter(|d| d.level == Level::Error).map(|d| d.message).collect()
+        };
+        // Type variables need no declaration; only the result is wrong.
+        assert_eq!(
+            messages("struct Point = x: Float, y: Float end\nfunc f(xs: List(a), g: a -> b, p: Point) -> (b, Str) =\n    return g\nend\n"),
+            ["mismatched types"]
+        );
+
+        let diagnostics = compile("func f(x: Pointt, y: int) =\n    return x + y\nend\nstruct Point = x end\n");
+        let messages_and_fixes: Vec<(&str, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), d.suggestions[0].message.as_str()))
+            .collect();
+        assert_eq!(
+            messages_and_fixes,
+            [
+                ("cannot find type `Pointt` in this scope", "a type with a similar name exists: `Point`"),
+                ("cannot find type `int` in this scope", "a type with a similar name exists: `Int`")
+            ]
+        );
+
+        let source = "func f() =\n    xs: List = []\n    n: Int(Str) = 1\n    return [xs, n]\nend\n";
+        assert_eq!(
+            messages(source),
+            [
+                "type `List` takes 1 type argument but 0 type arguments were supplied",
+                "type `Int` takes 0 type arguments but 1 type argument was supplied"
+            ]
+        );
+        assert_eq!(
+            fixed(source),
+            "func f() =\n    xs: List = []\n    n: Int = 1\n    return [xs, n]\nend\n"
+        );
+
+        let diagnostics = compile("type A = List(B)\ntype B = (Int, A -> C)\ntype C = Int\ntype D = D\n");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["cycle detected when expanding type alias `A`", "cycle detected when expanding type alias `D`"]
+        );
+        assert_eq!(
+            diagnostics[0].notes,
+            [
+                "...which requires expanding type alias `B`...",
+                "...which again requires expanding type alias `A`, completing the cycle"
+            ]
+        );
+    }
+
+    fn inferred(source: &str) -> Vec<(String, String)> {
+        let (module, _) = parse_source(source);
+        let inference = infer::infer(&module);
+        assert_eq!(inference.diagnostics, [], "{source}");
+        inference.functions.into_iter().map(|(name, scheme)| (name, scheme.to_string())).collect()
+    }
+
+    fn type_errors(source: &str) -> Vec<Diagnostic> {
+        let (module, _) = parse_source(source);
+        infer::infer(&module).diagnostics
+    }
+
+    #[test]
+    fn test_type_inference() {
+        let types = inferred(
+            "func add(x, y) =\n    return x + y\nend\nfunc id(x) =\n    return x\nend\nfunc main() =\n    n = add(2, 4)\n    s = id(\"s\")\n    return id(n) > 0\nend\n",
+        );
+        assert_eq!(
+            types,
+            [
+                ("add".to_owned(), "(a, a) -> a where a: Num".to_owned()),
+                ("id".to_owned(), "a -> a".to_owned()),
+                ("main".to_owned(), "() -> Bool".to_owned())
+            ]
+        );
+
+        // Mutually recursive functions are inferred together.
+        let types = inferred(
+            "func even(n) =\n    if n == 0\n        return true\n    end\n    return odd(n - 1)\nend\nfunc odd(n) =\n    return !even(n)\nend\n",
+        );
+        assert_eq!(types[0].1, "a -> Bool where a: Num");
+        assert_eq!(types[1].1, "a -> Bool where a: Num");
+
+        // A literal nothing decides the type of is an `Int`; otherwise it is
+        // whatever number it is used as.
+        let types = inferred("func f() =\n    n = 1\n    return n\nend\nfunc g() =\n    x = 1\n    return x * 2.5\nend\n");
+        assert_eq!(types[0].1, "() -> Int");
+        assert_eq!(types[1].1, "() -> Float");
+
+        // Annotated type variables are rigid and quantified.
+        let types = inferred("func apply(f: a -> b, x: a) -> b =\n    return f(x)\nend\nfunc log(x) =\n    println(x)\nend\n");
+        assert_eq!(types[0].1, "(a -> b, a) -> b");
+        assert_eq!(types[1].1, "a -> ()");
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        let types = inferred(
+            "func f() =\n    id = (x) => x\n    n = id(1) + 1\n    return id(\"s\")\nend\n",
+        );
+        assert_eq!(types[0].1, "() -> Str");
+
+        // Parameters are not generalized, and neither is a local assigned twice.
+        for source in [
+            "func f(g) =\n    n = g(1)\n    return g(\"s\")\nend\n",
+            "func f() =\n    id = (x) => x\n    id = (y) => y\n    n = id(1)\n    return id(\"s\")\nend\n",
+        ] {
+            let diagnostics = type_errors(source);
+            assert_eq!(diagnostics.len(), 1, "{source}: {diagnostics:?}");
+            assert_eq!(diagnostics[0].labels[0].message, "expected a number, found `Str`");
+        }
+    }
+
+    #[test]
+    fn test_type_mismatches() {
+        let source = "func f() =\n    n: Int = 1\n    n = \"one\"\nend\n";
+        let diagnostics: Vec<Diagnostic> = compile(source).into_iter().filter(|d| d.level == Level::Error).collect();
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "mismatched types");
+        assert_eq!(diagnostics[0].span.text(source), "\"one\"");
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Int`, found `Str`");
+        assert_eq!(diagnostics[0].labels[1].message, "expected due to this");
+        assert_eq!(diagnostics[0].labels[1].span.text(source), "Int");
+
+        // Without an annotation, the constraint comes from the first value.
+        let source = "func f() =\n    x = 1.5\n    x = \"s\"\nend\n";
+        let diagnostics = type_errors(source);
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Float`, found `Str`");
+        assert_eq!(diagnostics[0].labels[1].span.text(source), "1.5");
+
+        let source = "func f(x) =\n    return x + \"a\"\nend\nfunc g() =\n    return f(true)\nend\n";
+        let diagnostics = type_errors(source);
+        let labels: Vec<(&str, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.span.text(source), d.labels[0].message.as_str()))
+            .collect();
+        // The bad operand does not spread: `f` still takes a number.
+        assert_eq!(
+            labels,
+            [("\"a\"", "expected a number, found `Str`"), ("true", "expected a number, found `Bool`")]
+        );
+
+        let source = "func f(b) =\n    if b\n        return 1\n    end\n    return \"s\"\nend\n";
+        let diagnostics = type_errors(source);
+        assert_eq!(diagnostics[0].labels[0].message, "expected a number, found `Str`");
+        assert_eq!(diagnostics[0].labels[1].span.text(source), "1");
+
+        let source = "func f() -> Int =\n    println(1)\nend\n";
+        let diagnostics = type_errors(source);
+        assert_eq!(diagnostics[0].span.text(source), "Int");
+        assert_eq!(diagnostics[0].labels[1].message, "implicitly returns `()` as its body has no `return`");
+
+        let source = "func f(x) =\n    return x(x)\nend\nfunc g() =\n    return g(1)\nend\n";
+        let messages: Vec<String> = type_errors(source).into_iter().map(|d| d.message).collect();
+        assert_eq!(
+            messages,
+            ["cannot build an infinite type", "this function takes 0 arguments but 1 argument was supplied"]
+        );
+
+        let source = "func f(n: Int) =\n    return match n\n        \"zero\" => 0\n        _ => 1\n    end\nend\n";
+        let diagnostics = type_errors(source);
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Int`, found `Str`");
+        assert_eq!(diagnostics[0].labels[1].message, "this expression has type `Int`");
+    }
+
+    const VEC: &str =
+        "enum Vec(a: Type, n: Nat) =\n    Nil -> Vec(a, 0)\n    | Cons(k: Nat, x: a, xs: Vec(a, k)) -> Vec(a, k + 1)\nend\n";
+
+    fn dependent_errors(source: &str) -> Vec<Diagnostic> {
+        let (module, diagnostics) = parse_source(source);
+        assert_eq!(diagnostics, [], "{source}");
+        elab::check(&module).into_iter().filter(|d| d.level == Level::Error).collect()
+    }
+
+    #[test]
+    fn test_parse_inductive_families() {
+        let source = format!("{VEC}type Packed = (n: Nat, Vec(Int, n))\ntype Build = (n: Nat) -> Vec(Int, n + 1)\n");
+        let (module, diagnostics) = parse_source(&source);
+        assert_eq!(diagnostics, []);
+        let printed = pretty::module(&module);
+        assert!(printed.contains("enum Vec(a: Type, n: Nat) ="), "{printed}");
+        assert!(printed.contains("Cons(k: Nat, x: a, xs: Vec(a, k)) -> Vec(a, k + 1)"), "{printed}");
+        assert!(printed.contains("type Packed = (n: Nat, Vec(Int, n))"), "{printed}");
+        assert!(printed.contains("type Build = (n: Nat) -> Vec(Int, n + 1)"), "{printed}");
+        let (reparsed, _) = parse_source(&printed);
+        assert_eq!(pretty::module(&reparsed), printed);
+
+        let source = "func f(p: (n: Nat, Vec(Int, n))) -> Vec(Int, p.0) =\n    return p.1\nend\n";
+        let (module, _) = parse_source(source);
+        let ItemKind::Func(func) = &module.items[0].kind else { panic!("expected a function") };
+        let Some(TypeExprKind::Named { args, .. }) = func.ret.as_ref().map(|ty| &ty.kind) else {
+            panic!("expected a named type")
+        };
+        assert!(matches!(&args[1].kind, TypeExprKind::Expr(e) if matches!(e.kind, ExprKind::Field { .. })));
+    }
+
+    #[test]
+    fn test_dependent_append() {
+        let source = format!(
+            "{VEC}func append(a: Type, n: Nat, m: Nat, xs: Vec(a, n), ys: Vec(a, m)) -> Vec(a, n + m) =\n    \
+             return match xs\n        Nil => ys\n        \
+             Cons(k, x, rest) => Cons(a, k + m, x, append(a, k, m, rest, ys))\n    \
+             end\nend\n"
+        );
+        assert_eq!(compile(&source).into_iter().filter(|d| d.level == Level::Error).collect::<Vec<_>>(), []);
+
+        // The result of the recursive call has one element fewer than claimed.
+        let wrong = source.replace("Cons(a, k + m, x,", "Cons(a, k + m + 1, x,");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "mismatched types");
+        assert_eq!(diagnostics[0].span.text(&wrong), "append(a, k, m, rest, ys)");
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Vec(a, k + m + 1)`, found `Vec(a, k + m)`");
+
+        let wrong = source.replace("Nil => ys", "Nil => xs");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Vec(a, m)`, found `Vec(a, 0)`");
+    }
+
+    #[test]
+    fn test_dependent_match_coverage() {
+        // `Nil` never builds a `Vec(a, n + 1)`, so no arm is needed for it.
+        let head = format!(
+            "{VEC}func head(a: Type, n: Nat, xs: Vec(a, n + 1)) -> a =\n    return match xs\n        \
+             Cons(_, x, _) => x\n    end\nend\n"
+        );
+        assert_eq!(dependent_errors(&head), []);
+
+        let (module, _) = parse_source(&head.replace("Cons(_, x, _) => x", "Cons(_, x, _) => x\n        Nil => x"));
+        let warnings = elab::check(&module);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert_eq!(warnings[0].message, "unreachable pattern");
+        assert_eq!(warnings[0].labels[0].message, "`Nil` never builds a value of type `Vec(a, n + 1)`");
+
+        let tail = format!(
+            "{VEC}func size(a: Type, n: Nat, xs: Vec(a, n)) -> Nat =\n    return match xs\n        Nil => 0\n    end\nend\n"
+        );
+        let diagnostics = dependent_errors(&tail);
+        assert_eq!(diagnostics[0].message, "non-exhaustive patterns: `Cons(_, _, _)` not covered");
+    }
+
+    #[test]
+    fn test_dependent_pairs() {
+        let source = format!(
+            "{VEC}func pack() -> (n: Nat, Vec(Int, n)) =\n    return (1, Cons(Int, 0, 7, Nil(Int)))\nend\n\
+             func size(p: (n: Nat, Vec(Int, n))) -> Nat =\n    return p.0\nend\n\
+             func items(p: (n: Nat, Vec(Int, n))) -> Vec(Int, p.0) =\n    return p.1\nend\n"
+        );
+        assert_eq!(dependent_errors(&source), []);
+
+        let wrong = source.replace("return (1,", "return (2,");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Vec(Int, 2)`, found `Vec(Int, 1)`");
+    }
+
+    #[test]
+    fn test_universes() {
+        let source = "func f() -> Type =\n    return Type\nend\n";
+        let diagnostics = dependent_errors(source);
+        assert_eq!(diagnostics[0].message, "universe inconsistency");
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Type`, found `Type1`");
+        assert_eq!(dependent_errors("func f() -> Type1 =\n    return Type\nend\n"), []);
+
+        // A family holding types is itself a type of the next universe.
+        let source = "enum Box = MkBox(t: Type) end\nfunc f() -> Type =\n    return Box\nend\n";
+        assert_eq!(dependent_errors(source)[0].labels[0].message, "expected `Type`, found `Type1`");
+        let source = "enum Box = MkBox(t: Type) end\nfunc f() -> Type2 =\n    return Box\nend\n";
+        assert_eq!(dependent_errors(source), []);
+    }
+
+    #[test]
+    fn test_strict_positivity() {
+        let source = "enum Bad(a: Type) = MkBad(f: Bad(a) -> a) end\n";
+        let diagnostics = dependent_errors(source);
+        assert_eq!(diagnostics[0].message, "`Bad` occurs in a negative position in variant `MkBad`");
+        assert_eq!(diagnostics[0].span.text(source), "Bad");
+        assert_eq!(diagnostics[0].labels[0].message, "to the left of an arrow");
+
+        let source = "enum Tree(a: Type) = Leaf(x: a) | Node(f: Nat -> Tree(a)) end\n";
+        assert_eq!(dependent_errors(source), []);
+    }
+
+    #[test]
+    fn test_kernel_normalization() {
+        let globals = Globals::default();
+        // `((x) => x)(n)` is `n`, and `n + 1 + m` is `n + m + 1`.
+        let id = Term::Lam("x".to_owned(), Box::new(Term::Var(0)));
+        let env = [Value::var(0), Value::var(1)];
+        let applied = globals.eval(&Term::App(Box::new(id), Box::new(Term::Var(1))), &env);
+        assert!(globals.conv(2, &applied, &Value::var(0)));
+
+        let add = |a: Term, b: Term| Term::Add(Box::new(a), Box::new(b));
+        let lhs = globals.eval(&add(add(Term::Var(1), Term::NatLit(1)), Term::Var(0)), &env);
+        let rhs = globals.eval(&add(add(Term::Var(1), Term::Var(0)), Term::NatLit(1)), &env);
+        assert!(globals.conv(2, &lhs, &rhs));
+        assert!(!globals.conv(2, &lhs, &globals.eval(&add(Term::Var(1), Term::Var(0)), &env)));
+        let names = ["n".to_owned(), "m".to_owned()];
+        assert_eq!(show(&globals.quote(2, &lhs), &names), "n + m + 1");
+    }
+
+    const IMPLICIT_VEC: &str = "enum Vec(a: Type, n: Nat) =\n    Nil -> Vec(a, 0)\n    \
+                                | Cons({k: Nat}, x: a, xs: Vec(a, k)) -> Vec(a, k + 1)\nend\n\
+                                func append({a: Type, n: Nat, m: Nat}, xs: Vec(a, n), ys: Vec(a, m)) -> Vec(a, n + m) =\n    \
+                                return match xs\n        Nil => ys\n        Cons(x, rest) => Cons(a, x, append(rest, ys))\n    \
+                                end\nend\n";
+
+    #[test]
+    fn test_parse_implicit_parameters() {
+        let (module, diagnostics) = parse_source(IMPLICIT_VEC);
+        assert_eq!(diagnostics, []);
+        let ItemKind::Func(append) = &module.items[1].kind else { panic!("expected a function") };
+        let implicit: Vec<bool> = append.params.iter().map(|param| param.implicit).collect();
+        assert_eq!(implicit, [true, true, true, false, false]);
+        let printed = pretty::module(&module);
+        assert!(printed.contains("Cons({k: Nat}, x: a, xs: Vec(a, k))"), "{printed}");
+        assert!(printed.contains("func append({a: Type}, {n: Nat}, {m: Nat}, xs: Vec(a, n)"), "{printed}");
+        assert_eq!(parse_source(&printed).0.items.len(), 2);
+
+        let (module, _) = parse_source("func f({n: Nat}) -> Nat =\n    return n\nend\n");
+        assert_eq!(
+            dump(&module, Format::Sexp),
+            "(Module (Func f (ImplicitParam n (TypeExpr Nat)) (TypeExpr Nat) (Block (Return (Ident n)))))\n"
+        );
+        let (tree, _) = parse_lossless(IMPLICIT_VEC, &mut NodeCache::new());
+        assert_eq!(tree.text(), IMPLICIT_VEC);
+    }
+
+    #[test]
+    fn test_implicit_arguments() {
+        let source = format!(
+            "{IMPLICIT_VEC}func three() -> Vec(Int, 3) =\n    \
+             return append(Cons(Int, 1, Nil(Int)), Cons(_, 2, Cons(_, 3, Nil(_))))\nend\n\
+             func head({{a: Type, n: Nat}}, xs: Vec(a, n + 1)) -> a =\n    return match xs\n        \
+             Cons(x, _) => x\n    end\nend\n\
+             func first() -> Int =\n    return head(three())\nend\n"
+        );
+        assert_eq!(compile(&source).into_iter().filter(|d| d.level == Level::Error).collect::<Vec<_>>(), []);
+
+        let wrong = source.replace("func three() -> Vec(Int, 3)", "func three() -> Vec(Int, 4)");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Vec(Int, 4)`, found `Vec(Int, 3)`");
+
+        // Implicit arguments are left out of calls.
+        let wrong = format!("{source}func second(xs: Vec(Int, 3)) -> Int =\n    return head(Int, 2, xs)\nend\n");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics[0].message, "this function takes 1 argument but 3 arguments were supplied");
+        assert_eq!(
+            diagnostics[0].notes,
+            ["`head` also has 2 implicit arguments, which are worked out from the others and left out of the call"]
+        );
+
+        // `_` is only a value where the checker can work it out.
+        let diagnostics = compile("func f(x) =\n    return f(_)\nend\n");
+        assert_eq!(diagnostics[0].message, "`_` cannot be used as a value here");
+    }
+
+    #[test]
+    fn test_postponed_unification() {
+        // `n + m` cannot be split until `m` is known from the second argument. The body of `split` is left as a
+        // hole, as one that only called itself would never terminate.
+        let source = format!(
+            "{IMPLICIT_VEC}func split({{n: Nat, m: Nat}}, xs: Vec(Int, n + m), ys: Vec(Int, m)) -> Vec(Int, n) =\n    \
+             return ?\nend\n\
+             func use(xs: Vec(Int, 3), ys: Vec(Int, 1)) -> Vec(Int, 2) =\n    return split(xs, ys)\nend\n"
+        );
+        assert_eq!(dependent_errors(&source), []);
+
+        let wrong = source.replace("-> Vec(Int, 2) =", "-> Vec(Int, 1) =");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Vec(Int, 1)`, found `Vec(Int, 2)`");
+
+        let wrong = source.replace("ys: Vec(Int, 1)) -> Vec(Int, 2)", "ys: Vec(Int, 5)) -> Vec(Int, 2)");
+        let diagnostics = dependent_errors(&wrong);
+        assert_eq!(diagnostics[0].message, "mismatched types");
+        assert_eq!(diagnostics[0].labels[0].message, "expected `Vec(Int, ?n + 5)`, found `Vec(Int, 3)`");
+    }
+
+    #[test]
+    fn test_unsolved_implicit_arguments() {
+        let source = format!(
+            "{IMPLICIT_VEC}func pick({{n: Nat, m: Nat}}, xs: Vec(Int, n + m)) -> Nat =\n    return n\nend\n\
+             func use(b: Type, xs: Vec(Int, 3)) -> Nat =\n    return pick(xs)\nend\n"
+        );
+        let diagnostics = dependent_errors(&source);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "could not infer implicit argument `n`");
+        assert_eq!(diagnostics[0].span.text(&source), "pick");
+        assert_eq!(diagnostics[0].labels[0].message, "cannot infer `n` in this use of `pick`");
+        assert_eq!(
+            diagnostics[0].notes,
+            [
+                "the goal is `?n : Nat`",
+                "in the context `b : Type, xs : Vec(Int, 3)`",
+                "unification is stuck on `3 = ?n + ?m`"
+            ]
+        );
+
+        // Both halves of an empty vector are empty, but `?n + ?m = 0` is not a pattern.
+        let source = format!(
+            "{IMPLICIT_VEC}func pick({{n: Nat, m: Nat}}, xs: Vec(Int, n + m)) -> Nat =\n    return n\nend\n\
+             func empty() -> Nat =\n    return pick(Nil(_))\nend\n"
+        );
+        let messages: Vec<String> = dependent_errors(&source).into_iter().map(|d| d.message).collect();
+        assert_eq!(messages, ["could not infer implicit argument `n`"]);
+    }
+
+    #[test]
+    fn test_parse_holes() {
+        let source = "func f(x) =\n    return g(?, ?name) + ?\nend\n";
+        let (module, diagnostics) = parse_source(source);
+        assert_eq!(diagnostics, []);
+        assert_eq!(
+            dump(&module, Format::Sexp),
+            "(Module (Func f (Param x) (Block (Return (Binary + (Call (Ident g) (Hole) (Hole name)) (Hole))))))\n"
+        );
+        let ItemKind::Func(func) = &module.items[0].kind else { panic!("expected a function") };
+        let StmtKind::Return(Some(value)) = &func.body.stmts[0].kind else { panic!("expected a return") };
+        assert_eq!(parenthesized(value), "(g(?, ?name) + ?)");
+        assert_eq!(pretty::module(&module), source);
+
+        let (tree, _) = parse_lossless(source, &mut NodeCache::new());
+        assert_eq!(tree.text(), source);
+        let holes: Vec<String> = tree
+            .descendants()
+            .into_iter()
+            .filter(|node| node.kind() == NodeKind::HoleExpr)
+            .map(|node| node.text())
+            .collect();
+        assert_eq!(holes, ["?", "?name", "?"]);
+    }
+
+    #[test]
+    fn test_typed_holes() {
+        let source = "func add(x: Int, y: Int) -> Int =\n    z = x * 2\n    return ?sum\nend\n\
+                      func greet(name: Str) -> Str =\n    return ?\nend\n\
+                      func bad() -> Int =\n    return \"one\"\nend\n";
+        let (diagnostics, goals) = compile_goals(source, &Options::default());
+        assert_eq!(
+            goals[0],
+            Goal {
+                name: Some("sum".to_owned()),
+                span: Span::new(59, 63),
+                ty: "Int".to_owned(),
+                context: vec![
+                    ("x".to_owned(), "Int".to_owned()),
+                    ("y".to_owned(), "Int".to_owned()),
+                    ("z".to_owned(), "Int".to_owned())
+                ],
+                candidates: vec!["z".to_owned(), "y".to_owned(), "x".to_owned(), "add(_, _)".to_owned()],
+            }
+        );
+        assert_eq!(goals[1].ty, "Str");
+        assert_eq!(goals[1].candidates, ["name", "greet(_)", "read_line()"]);
+        // The rest of the file is still checked.
+        let messages: Vec<&str> = diagnostics.iter().filter(|d| d.is_error()).map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["mismatched types"]);
+
+        let diagnostics = compile(source);
+        let hole = diagnostics.iter().find(|d| d.message == "found hole `?sum`").expect("the hole is reported");
+        assert_eq!(hole.level, Level::Error);
+        assert_eq!(hole.labels[0].message, "expected a value of type `Int` here");
+        assert_eq!(
+            hole.notes,
+            ["the goal is `?sum : Int`", "in the context `x : Int, y : Int, z : Int`"]
+        );
+        assert_eq!(hole.help, ["`z`, `y`, `x` and `add(_, _)` would fit here"]);
+
+        // A hole's type is decided by how it is used, after it too.
+        let (_, goals) = compile_goals("func f(xs) =\n    x = ?\n    return xs[x]\nend\n", &Options::default());
+        assert_eq!(goals[0].ty, "Int");
+        let (_, goals) = compile_goals("func f() =\n    return [?]\nend\n", &Options::default());
+        assert_eq!((goals[0].ty.as_str(), goals[0].candidates.len()), ("_", 0));
+    }
+
+    #[test]
+    fn test_dependent_holes() {
+        let source = format!(
+            "{IMPLICIT_VEC}func zip({{a: Type, n: Nat}}, xs: Vec(a, n), ys: Vec(a, n)) -> Vec(a, n) =\n    \
+             return match xs\n        Nil => ?nil\n        Cons(x, rest) => Cons(a, x, ?)\n    end\nend\n"
+        );
+        let (diagnostics, goals) = compile_goals(&source, &Options::default());
+        assert_eq!(diagnostics.iter().filter(|d| d.is_error()).count(), 0, "{diagnostics:?}");
+        assert_eq!(goals.len(), 2);
+        assert_eq!(goals[0].name.as_deref(), Some("nil"));
+        assert_eq!(goals[0].ty, "Vec(a, 0)");
+        // `n` is `0` in this arm, so `ys` fits too.
+        assert_eq!(goals[0].candidates, ["ys", "xs", "Nil(_)", "append(_, _)", "zip(_, _)"]);
+        assert_eq!(goals[1].ty, "Vec(a, k)");
+        assert_eq!(goals[1].candidates, ["rest", "Cons(_, _, _)", "append(_, _)", "zip(_, _)"]);
+        // Matching refined the indices, and `k` is bound though it has no name in the arm.
+        let context: Vec<String> = goals[1].context.iter().map(|(name, ty)| format!("{name} : {ty}")).collect();
+        assert_eq!(
+            context,
+            [
+                "a : Type",
+                "n : Nat",
+                "xs : Vec(a, k + 1)",
+                "ys : Vec(a, k + 1)",
+                "k : Nat",
+                "x : a",
+                "rest : Vec(a, k)"
+            ]
+        );
+
+        // An argument worked out from nothing but the hole is left to it.
+        let source = format!(
+            "{IMPLICIT_VEC}func pick({{n: Nat, m: Nat}}, xs: Vec(Int, n + m)) -> Nat =\n    return n\nend\n\
+             func use() -> Nat =\n    return pick(?)\nend\n"
+        );
+        let (diagnostics, goals) = compile_goals(&source, &Options::default());
+        assert_eq!(diagnostics.iter().filter(|d| d.is_error()).count(), 0, "{diagnostics:?}");
+        assert_eq!(goals[0].ty, "Vec(Int, ?n + ?m)");
+        // Solving what it can before the goal is shown.
+        let source =
+            format!("{IMPLICIT_VEC}func one() -> Vec(Int, 1) =\n    return append(?, Cons(Int, 1, Nil(Int)))\nend\n");
+        assert_eq!(compile_goals(&source, &Options::default()).1[0].ty, "Vec(Int, 0)");
+    }
+
+    #[test]
+    fn test_holes_json() {
+        let source = "func f(x: Int) -> Int =\n    return ?\"\nend\n";
+        let goals = [Goal {
+            name: Some("a\"b".to_owned()),
+            span: Span::new(35, 36),
+            ty: "Int".to_owned(),
+            context: vec![("x".to_owned(), "Int".to_owned())],
+            candidates: vec!["x".to_owned()],
+        }];
+        assert_eq!(
+            holes::json(&goals, source),
+            "[{\"name\":\"a\\\"b\",\"span\":[35,36],\"line\":2,\"column\":12,\"type\":\"Int\",\
+             \"context\":[{\"name\":\"x\",\"type\":\"Int\"}],\"candidates\":[\"x\"]}]\n"
+        );
+        assert_eq!(holes::json(&[], source), "[]\n");
+    }
+
+    const REFINEMENTS: &str = "type Pos = Int where self > 0\ntype Index(n) = Nat where self < n\n\
+                               func get(n: Nat, i: Index(n)) -> Nat =\n    return i\nend\n";
+
+    fn refinement_errors(source: &str) -> Vec<Diagnostic> {
+        compile(source).into_iter().filter(|d| d.level == Level::Error).collect()
+    }
+
+    #[test]
+    fn test_parse_refinement_types() {
+        let (module, diagnostics) = parse_source(REFINEMENTS);
+        assert_eq!(diagnostics, []);
+        let ItemKind::Type(alias) = &module.items[1].kind else { panic!("expected a type alias") };
+        assert_eq!(alias.params.iter().map(|param| param.name.as_str()).collect::<Vec<_>>(), ["n"]);
+        assert!(matches!(&alias.refinement, Some(Expr { kind: ExprKind::Binary { op: BinaryOp::Lt, .. }, .. })));
+        let printed = pretty::module(&module);
+        assert!(printed.starts_with("type Pos = Int where self > 0\n\ntype Index(n) = Nat where self < n\n"), "{printed}");
+        assert_eq!(pretty::module(&parse_source(&printed).0), printed);
+        // `where` and `self` are still names everywhere else.
+        let (_, diagnostics) = parse_source("func f(where: Int) -> Int =\n    self = where\n    return self\nend\n");
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn test_refinement_obligations() {
+        // Checked at calls: `n - 1` is below `n`, and not negative when `n > 0`.
+        let source = format!(
+            "{REFINEMENTS}func last(n: Nat) -> Nat =\n    if n > 0\n        return get(n, n - 1)\n    end\n    return 0\nend\n"
+        );
+        assert_eq!(refinement_errors(&source), []);
+
+        let wrong = source.replace("get(n, n - 1)", "get(n, n)");
+        let diagnostics = refinement_errors(&wrong);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "cannot prove that `n` is an `Index(n)`");
+        assert_eq!(diagnostics[0].span.text(&wrong), "n");
+        let labels: Vec<&str> = diagnostics[0].labels.iter().map(|label| label.message.as_str()).collect();
+        assert_eq!(
+            labels,
+            [
+                "`n >= 0 && n < n` might not hold",
+                "the parameter `i` of `get` is an `Index(n)`",
+                "required by the condition of `Index`"
+            ]
+        );
+        assert!(diagnostics[0].notes[0].starts_with("counterexample: `n = "), "{:?}", diagnostics[0].notes);
+
+        // Checked at assignments to a local declared with the type.
+        let source = "type Pos = Int where self > 0\nfunc f(x: Int) -> Int =\n    y: Pos = x - 1\n    return y\nend\n";
+        let diagnostics = refinement_errors(source);
+        assert_eq!(diagnostics[0].message, "cannot prove that `x - 1` is a `Pos`");
+        assert_eq!(diagnostics[0].labels[0].message, "`x - 1 > 0` might not hold");
+        assert_eq!(diagnostics[0].notes, ["counterexample: `x = 0`"]);
+        assert_eq!(refinement_errors(&source.replace("Int) -> Int", "Pos) -> Int").replace("x - 1", "x + 1")), []);
+
+        // Checked at returns, with what the branch taken shows.
+        let source = "type Pos = Int where self > 0\nfunc abs(x: Int) -> Pos =\n    if x > 0\n        return x\n    end\n    return 1 - x\nend\n";
+        assert_eq!(refinement_errors(source), []);
+        let diagnostics = refinement_errors(&source.replace("1 - x", "-x"));
+        assert_eq!(diagnostics[0].message, "cannot prove that `-x` is a `Pos`");
+        assert_eq!(diagnostics[0].notes, ["counterexample: `x = 0`"]);
+        assert_eq!(diagnostics[0].labels[1].message, "the return type is declared here");
+    }
+
+    #[test]
+    fn test_refinements_of_nat_are_nats() {
+        // A refined `Nat` flows to and from `Nat`, and is known not to be negative.
+        let source = "type Index(n) = Nat where self < n\ntype Small = Nat where self < 10\n\
+                      func get(n: Nat, i: Index(n)) -> Nat =\n    return i\nend\n\
+                      func g(n: Nat) -> Small =\n    if n < 10\n        return n\n    end\n    return 9\nend\n\
+                      func h(n: Nat) -> Nat =\n    s: Small = g(n)\n    return s + get(10, s)\nend\n";
+        assert_eq!(refinement_errors(source), []);
+
+        let diagnostics = refinement_errors(&source.replace("return 9", "return n - 10"));
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "cannot prove that `n - 10` is a `Small`");
+        assert_eq!(diagnostics[0].labels[0].message, "`n - 10 >= 0 && n - 10 < 10` might not hold");
+        // Only the arguments of a parameterized refinement make it a type.
+        let diagnostics = refinement_errors(&source.replace("i: Index(n)", "i: Index"));
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    }
+
+    #[test]
+    fn test_refinements_in_loops() {
+        // A refinement may refine another; the loop only knows what the types of the variables it assigns say.
+        let source = "type Pos = Int where self > 0\ntype Small = Pos where self < 10\n\
+                      func count(n: Pos) -> Pos =\n    i: Pos = 1\n    while i < n\n        i = i + 1\n    end\n    return i\nend\n\
+                      func down() -> Small =\n    s: Small = 5\n    for x in [1, 2]\n        s = s - x\n    end\n    return s\nend\n";
+        let diagnostics = refinement_errors(source);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "cannot prove that `s - x` is a `Small`");
+        assert_eq!(diagnostics[0].labels[0].message, "`s - x > 0 && s - x < 10` might not hold");
+        // Calls are unknown values, and the result type of the callee is assumed.
+        let source = "type Pos = Int where self > 0\nfunc one() -> Pos =\n    return 1\nend\n\
+                      func two() -> Pos =\n    return one() + one()\nend\nfunc zero() -> Pos =\n    return one() - 1\nend\n";
+        let diagnostics = refinement_errors(source);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].notes, ["counterexample: `one() = 1`"]);
+    }
+
+    #[test]
+    fn test_refinement_declaration_errors() {
+        let messages = |source: &str| -> Vec<String> { refinement_errors(source).into_iter().map(|d| d.message).collect() };
+        assert_eq!(
+            messages(
+                "type Bad(k) = Int\ntype Text = Str where self > 0\ntype Odd = Int where f(self)\n\
+                 type Wrong = Int where self + 1\ntype Flag = Bool where self && 1\nfunc f(x: Int) -> Bool =\n    return x > 0\nend\n"
+            ),
+            [
+                "only a refinement type can take parameters",
+                "`Str` cannot be refined",
+                "this cannot be used in the condition of a refinement type",
+                "mismatched types",
+                "mismatched types"
+            ]
+        );
+        assert_eq!(messages("type Index(n) = Nat where self < m\n"), ["cannot find `m` in this scope"]);
+        assert_eq!(
+            messages("type Index(n) = Nat where self < n\nfunc f(i: Index) -> Int =\n    return i\nend\n"),
+            ["type `Index` takes 1 argument but 0 arguments were supplied"]
+        );
+    }
+
+    const CONTRACTS: &str = "func div(a: Int, b: Int) -> Int =\n    requires b != 0\n    ensures result * b <= a\n    return a / b\nend\n\n\
+                             func abs(x: Int) -> Int =\n    ensures result >= 0\n    if x < 0\n        return -x\n    end\n    return x\nend\n\n\
+                             func inc(x: Int) -> Int =\n    ensures result > old(x)\n    x = x + 1\n    return x\nend\n";
+
+    #[test]
+    fn test_parse_contracts() {
+        let (module, diagnostics) = parse_source(CONTRACTS);
+        assert_eq!(diagnostics, []);
+        let ItemKind::Func(div) = &module.items[0].kind else { panic!("expected a function") };
+        let kinds: Vec<&str> = div.contracts.iter().map(|contract| contract.kind.keyword()).collect();
+        assert_eq!(kinds, ["requires", "ensures"]);
+        assert_eq!(div.contracts[0].span.text(CONTRACTS), "requires b != 0");
+        assert_eq!(div.body.stmts.len(), 1);
+        let printed = pretty::module(&module);
+        assert_eq!(printed, CONTRACTS);
+        // `requires` and `ensures` are still names everywhere else.
+        let (_, diagnostics) = parse_source("func f(requires: Int) -> Int =\n    ensures = requires\n    return ensures\nend\n");
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn test_verify_contracts() {
+        let (diagnostics, reports) = verify(CONTRACTS, &Options::default());
+        let statuses: Vec<(&str, &Status)> =
+            reports.iter().map(|report| (report.description.as_str(), &report.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                ("the divisor of `a / b` is not zero in `div`", &Status::Proved),
+                ("`div` ensures `result * b <= a`", &Status::Unknown("the arithmetic is not linear".to_owned())),
+                ("`abs` ensures `result >= 0`", &Status::Proved),
+                ("`abs` ensures `result >= 0`", &Status::Proved),
+                ("`inc` ensures `result > old(x)`", &Status::Proved)
+            ]
+        );
+        assert_eq!(diagnostics, []);
+        let diagnostic = reports[1].diagnostic.as_ref().unwrap();
+        assert_eq!(diagnostic.message, "cannot prove the postcondition of `div`");
+        assert_eq!(diagnostic.notes, ["the solver could not decide: the arithmetic is not linear"]);
+
+        // A postcondition is checked at every return, against the values the parameters had on entry.
+        let source = CONTRACTS.replace("result > old(x)", "result > x");
+        let diagnostics = refinement_errors(&source);
+        assert_eq!(diagnostics[1].message, "cannot prove the postcondition of `inc`");
+        assert_eq!(diagnostics[1].span.text(&source), "return x");
+        let labels: Vec<&str> = diagnostics[1].labels.iter().map(|label| label.message.as_str()).collect();
+        assert_eq!(labels, ["`result > x` might not hold when the function returns here", "`inc` ensures this"]);
+        assert_eq!(diagnostics[1].notes, ["counterexample: `x = 0`"]);
+    }
+
+    #[test]
+    fn test_preconditions_at_calls() {
+        // Callers must establish `requires`, and may rely on `ensures`.
+        let source = format!(
+            "{CONTRACTS}\nfunc half(y: Int) -> Int =\n    z = abs(y)\n    return div(10, z)\nend\n\n\
+             func third(y: Int) -> Int =\n    z = inc(abs(y))\n    return div(10, z)\nend\n"
+        );
+        let (_, reports) = verify(&source, &Options::default());
+        let failed: Vec<&str> = reports
+            .iter()
+            .filter(|report| matches!(report.status, Status::Failed(_)))
+            .map(|report| report.description.as_str())
+            .collect();
+        assert_eq!(failed, ["`div` requires `b != 0` in `half`"]);
+        let diagnostics = refinement_errors(&source);
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        let diagnostic = &diagnostics[1];
+        assert_eq!(diagnostic.message, "cannot prove the precondition of `div`");
+        assert_eq!(diagnostic.span.text(&source), "div(10, z)");
+        assert_eq!(diagnostic.labels[0].message, "`z != 0` might not hold");
+        assert_eq!(diagnostic.labels[1].message, "`div` requires this of its callers");
+        assert_eq!(diagnostic.notes, ["counterexample: `abs(y) = 0, y = 0`"]);
+
+        // The clause is shown with the arguments in place, not simplified away.
+        let source = "func add(a: Int, b: Int) -> Int =\n    requires a > 0 && b < a\n    return a + b\nend\n\n\
+                      func zero() -> Int =\n    return add(0, 1)\nend\n\n\
+                      func twice(x: Int) -> Int =\n    return add(x + 1, x * 2)\nend\n";
+        let diagnostics = refinement_errors(source);
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].span.text(source), "add(0, 1)");
+        assert_eq!(diagnostics[0].labels[0].message, "`0 > 0 && 1 < 0` might not hold");
+        assert_eq!(diagnostics[0].labels[1].span.text(source), "requires a > 0 && b < a");
+        assert_eq!(diagnostics[0].labels[1].message, "`add` requires this of its callers");
+        assert_eq!(diagnostics[1].labels[0].message, "`x + 1 > 0 && x * 2 < x + 1` might not hold");
+    }
+
+    #[test]
+    fn test_contract_errors() {
+        let messages = |source: &str| -> Vec<String> { refinement_errors(source).into_iter().map(|d| d.message).collect() };
+        assert_eq!(
+            messages("func f(x: Int) -> Int =\n    requires result > 0\n    return x\nend\n"),
+            ["cannot find `result` in this scope"]
+        );
+        assert_eq!(
+            messages("func f(x: Int) -> Int =\n    ensures result > old(x, x)\n    return x\nend\n"),
+            ["`old` takes 1 argument but 2 arguments were supplied"]
+        );
+        assert_eq!(
+            messages("func f(x: Int) -> Int =\n    requires x + 1\n    return x\nend\n"),
+            ["mismatched types"]
+        );
+    }
+
+    fn with_checks(source: &str, contracts: ContractMode, release: bool) -> (String, Vec<Diagnostic>) {
+        let options = Options {
+            contracts,
+            release,
+            ..Options::default()
+        };
+        let (module, diagnostics) = lower(source, &options);
+        (pretty::module(&module), diagnostics)
+    }
+
+    #[test]
+    fn test_contract_modes() {
+        for mode in [ContractMode::Off, ContractMode::Runtime, ContractMode::Static, ContractMode::Both] {
+            assert_eq!(ContractMode::from_name(mode.name()), Some(mode));
+        }
+        let source = "func div(a: Int, b: Int) -> Int =\n    requires b != 0\n    return a / b\nend\n\n\
+                      func half(y: Int) -> Int =\n    return div(y, y - 2)\nend\n";
+        let compiled = |contracts| {
+            compile_with(
+                source,
+                &Options {
+                    contracts,
+                    ..Options::default()
+                },
+            )
+        };
+        assert_eq!(compiled(ContractMode::Static)[0].level, Level::Error);
+        // Unproved checks are left to run time, with a warning.
+        let diagnostics = compiled(ContractMode::Both);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].level, Level::Warning);
+        assert_eq!(diagnostics[0].message, "cannot prove the precondition of `div`");
+        assert_eq!(diagnostics[0].notes.last().unwrap(), "it is checked when the program runs instead");
+        assert_eq!(compiled(ContractMode::Runtime), []);
+        assert_eq!(compiled(ContractMode::Off), []);
+    }
+
+    #[test]
+    fn test_runtime_contract_checks() {
+        let source = "type Pos = Int where self > 0\n\n\
+                      func dec(x: Pos) -> Int =\n    ensures result == old(x) - 1\n    x = x - 1\n    return x\nend\n\n\
+                      func use(y: Int) -> Int =\n    z: Pos = y * y + 1\n    return dec(z)\nend\n";
+        let (printed, diagnostics) = with_checks(source, ContractMode::Runtime, false);
+        assert_eq!(diagnostics, []);
+        assert_eq!(
+            printed,
+            "type Pos = Int where self > 0\n\n\
+             func dec(x: Pos) -> Int =\n    x$old = x\n    x = x - 1\n    \
+             contract$assert(x > 0, \"`x - 1` is a `Pos`\", \"5:9\")\n    result$ = x\n    \
+             contract$assert(result$ == x$old - 1, \"`dec` ensures `result == old(x) - 1`\", \"6:5\")\n    \
+             return result$\nend\n\n\
+             func dec$checked(x: Pos, caller$: Str) -> Int =\n    \
+             contract$assert(x > 0, \"`dec` requires `x` to be a `Pos`\", caller$)\n    return dec(x)\nend\n\n\
+             func use(y: Int) -> Int =\n    z: Pos = y * y + 1\n    \
+             contract$assert(z > 0, \"`y * y + 1` is a `Pos`\", \"10:14\")\n    return dec$checked(z, \"11:12\")\nend\n"
+        );
+
+        // A release build leaves out what was proved: the call and the postcondition, but not `x - 1`.
+        let (printed, diagnostics) = with_checks(source, ContractMode::Both, true);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["cannot prove that `x - 1` is a `Pos`", "cannot prove that `y * y + 1` is a `Pos`"]);
+        assert!(printed.contains("contract$assert(x > 0, \"`x - 1` is a `Pos`\", \"5:9\")"), "{printed}");
+        assert!(!printed.contains("ensures"), "{printed}");
+        assert!(printed.contains("    return dec(z)\n"), "{printed}");
+        // Without contracts to check, lowering only drops them.
+        let (printed, _) = with_checks(source, ContractMode::Static, false);
+        assert!(!printed.contains("contract$assert") && !printed.contains("ensures"), "{printed}");
+    }
+
+    #[test]
+    fn test_fixed_width_types() {
+        let source = "func low(x: u16) -> u8 =\n    return match x\n        0 => 1\n        _ => 2\n    end\nend\n\n\
+                      func wide(x: u8, y: u16) =\n    return x + y\nend\n\n\
+                      func byte() -> u8 =\n    return 256\nend\n\n\
+                      func least() -> i8 =\n    return -128\nend\n";
+        let diagnostics = compile(source);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["mismatched types", "literal out of range for `u8`"]);
+        assert_eq!(diagnostics[0].span.text(source), "y");
+        assert_eq!(diagnostics[1].span.text(source), "256");
+        assert_eq!(diagnostics[1].notes, ["the range of `u8` is `0..=255`"]);
+        // `u8` is a type, not a type variable.
+        let source = "func id(x: u8) -> u8 =\n    return x\nend\n\nfunc two() -> u8 =\n    return id(2)\nend\n";
+        assert_eq!(compile(source), []);
+    }
+
+    #[test]
+    fn test_overflow_checks() {
+        let source = "func area(w: u64, h: u64) -> u64 =\n    requires w < 4294967296 && h < 4294967296\n    \
+                      return w * h\nend\n\n\
+                      func next(x: u8) -> u8 =\n    return x + 1\nend\n\n\
+                      func bounded(x: u8) -> u8 =\n    if x < 255\n        return x + 1\n    end\n    return 0\nend\n\n\
+                      func flip(x: i8) -> i8 =\n    return -x\nend\n\n\
+                      func ratio(x: i16, y: i16) -> i16 =\n    requires y > 0\n    return x / y\nend\n";
+        let (diagnostics, reports) = verify(source, &Options::default());
+        assert_eq!(diagnostics, []);
+        let statuses: Vec<(&str, &Status)> =
+            reports.iter().map(|report| (report.description.as_str(), &report.status)).collect();
+        let failed = |values: &str| Status::Failed(Some(values.to_owned()));
+        assert_eq!(
+            statuses,
+            [
+                ("`w * h` fits in a `u64` in `area`", &Status::Proved),
+                ("`x + 1` fits in a `u8` in `next`", &failed("x = 255 (0b11111111)")),
+                ("`x + 1` fits in a `u8` in `bounded`", &Status::Proved),
+                ("`-x` fits in an `i8` in `flip`", &failed("x = -128 (0b10000000)")),
+                ("the divisor of `x / y` is not zero in `ratio`", &Status::Proved),
+                ("`x / y` fits in an `i16` in `ratio`", &Status::Proved),
+            ]
+        );
+        let diagnostic = reports[1].diagnostic.as_ref().unwrap();
+        assert_eq!(diagnostic.message, "cannot prove that `x + 1` does not overflow");
+        assert_eq!(diagnostic.labels[0].message, "might not fit in a `u8`");
+        assert_eq!(diagnostic.notes, ["counterexample: `x = 255 (0b11111111)`"]);
+
+        // Overflow is not checked when the program runs, so it stays an error.
+        let options = Options {
+            contracts: ContractMode::Both,
+            ..Options::default()
+        };
+        let levels: Vec<Level> = compile_with(source, &options).iter().map(|d| d.level).collect();
+        assert_eq!(levels, [Level::Error, Level::Error]);
+    }
+
+    #[test]
+    fn test_fixed_width_refinements() {
+        let source = "type Small = u8 where self < 16\n\n\
+                      func grow(x: Small) -> u8 =\n    return x * 16 + 15\nend\n\n\
+                      func shrink(x: u8) -> Small =\n    return x / 16\nend\n";
+        assert_eq!(refinement_errors(source), []);
+        let source = source.replace("x / 16", "x / 8");
+        let messages: Vec<String> = refinement_errors(&source).into_iter().map(|d| d.message).collect();
+        assert_eq!(messages, ["cannot prove that `x / 8` is a `Small`"]);
+    }
+
+    #[test]
+    fn test_smt2() {
+        let source = "func next(x: u8) -> u8 =\n    return x + 1\nend\n\n\
+                      func half(x: Int) -> Int =\n    requires x > 1\n    ensures result < x\n    return x / 2\nend\n";
+        let (diagnostics, scripts) = smt2(source, &Options::default());
+        assert_eq!(diagnostics, []);
+        let names: Vec<&str> = scripts.iter().map(|(function, _)| function.as_str()).collect();
+        assert_eq!(names, ["next", "half", "half"]);
+        assert!(scripts[1].1.text().starts_with("; the divisor of `x / 2` is not zero in `half`\n"));
+        let text = scripts[2].1.text();
+        assert!(text.starts_with("; `half` ensures `result < x`\n; checked at 8:5, `sat` gives a counterexample\n"));
+        assert!(text.contains("(declare-const x Int)\n"));
+        assert!(text.contains(":named |half@8:5-8:17|))\n(check-sat)\n"), "{text}");
+        assert!(scripts[0].1.text().contains("(declare-const x (_ BitVec 8))\n"));
+        let (diagnostics, scripts) = smt2("func f() -> Int =\n    return true\nend\n", &Options::default());
+        assert_eq!((diagnostics.len(), scripts.len()), (1, 0));
+
+        // A solver of its own proves the checks, or shows them false.
+        let solve = |answer: &str| {
+            let options = Options {
+                solver: Some(format!("sh -c 'cat > /dev/null; echo \"{answer}\"'")),
+                ..Options::default()
+            };
+            let (_, reports) = verify(source, &options);
+            reports.into_iter().map(|report| report.status).collect::<Vec<Status>>()
+        };
+        assert_eq!(solve("unsat"), [Status::Proved, Status::Proved, Status::Proved]);
+        let model = "sat ((define-fun x () (_ BitVec 8) #xff) (define-fun x () Int 2))";
+        assert_eq!(
+            solve(model),
+            [
+                Status::Failed(Some("x = 255 (0b11111111)".to_owned())),
+                Status::Failed(None),
+                Status::Failed(Some("x = 2".to_owned()))
+            ]
+        );
+        assert_eq!(
+            solve("unknown"),
+            [
+                Status::Unknown("the solver answered unknown".to_owned()),
+                Status::Unknown("the solver answered unknown".to_owned()),
+                Status::Unknown("the solver answered unknown".to_owned())
+            ]
+        );
+    }
+
+    const LOOPS: &str = "func sum(n: Int) -> Int =\n    requires n >= 0\n    ensures result >= 0\n    \
+                         i = 0\n    total = 0\n    while i < n\n        invariant 0 <= i && i <= n\n        \
+                         invariant total >= 0\n        decreases n - i\n        total = total + i\n        \
+                         i = i + 1\n    end\n    return total\nend\n\n\
+                         func count(xs: List(Int)) -> Int =\n    seen = 0\n    for x in xs\n        \
+                         invariant seen >= 0\n        seen = seen + x * 0 + 1\n    end\n    return seen\nend\n";
+
+    #[test]
+    fn test_parse_loop_contracts() {
+        let (module, diagnostics) = parse_source(LOOPS);
+        assert_eq!(diagnostics, []);
+        let ItemKind::Func(sum) = &module.items[0].kind else { panic!("expected a function") };
+        let StmtKind::While { contracts, body, .. } = &sum.body.stmts[2].kind else {
+            panic!("expected a while loop")
+        };
+        let kinds: Vec<&str> = contracts.iter().map(|contract| contract.kind.keyword()).collect();
+        assert_eq!(kinds, ["invariant", "invariant", "decreases"]);
+        assert_eq!(contracts[2].span.text(LOOPS), "decreases n - i");
+        assert_eq!(body.stmts.len(), 2);
+        assert_eq!(pretty::module(&module), LOOPS);
+        // They are names outside the opening of a loop body.
+        let source = "func f(invariant: Int) -> Int =\n    decreases = invariant\n    while decreases > 0\n        \
+                      decreases = decreases - 1\n    end\n    return decreases\nend\n";
+        let (module, diagnostics) = parse_source(source);
+        assert_eq!(diagnostics, []);
+        let ItemKind::Func(f) = &module.items[0].kind else { panic!("expected a function") };
+        assert!(f.contracts.is_empty());
+        assert!(matches!(&f.body.stmts[1].kind, StmtKind::While { contracts, .. } if contracts.is_empty()));
+    }
+
+    #[test]
+    fn test_loop_invariants() {
+        let (diagnostics, reports) = verify(LOOPS, &Options::default());
+        assert_eq!(diagnostics, []);
+        assert!(reports.iter().all(|report| report.status == Status::Proved), "{reports:?}");
+        let descriptions: Vec<&str> = reports.iter().map(|report| report.description.as_str()).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "loop invariant `0 <= i && i <= n` holds on entry in `sum`",
+                "loop invariant `total >= 0` holds on entry in `sum`",
+                "loop invariant `0 <= i && i <= n` is preserved in `sum`",
+                "loop invariant `total >= 0` is preserved in `sum`",
+                "loop measure `n - i` decreases in `sum`",
+                "`sum` ensures `result >= 0`",
+                "loop invariant `seen >= 0` holds on entry in `count`",
+                "loop invariant `seen >= 0` is preserved in `count`",
+            ]
+        );
+
+        // Each obligation fails on its own.
+        let source = LOOPS.replace("invariant 0 <= i && i <= n", "invariant 0 <= i && i < n");
+        let diagnostics = refinement_errors(&source);
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].message, "cannot prove that the loop invariant holds on entry");
+        assert_eq!(diagnostics[0].span.text(&source), "invariant 0 <= i && i < n");
+        let labels: Vec<(&str, &str)> = diagnostics[0]
+            .labels
+            .iter()
+            .map(|label| (label.span.text(&source), label.message.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("invariant 0 <= i && i < n", "`0 <= i && i < n` might not hold before the first iteration"),
+                ("while i < n", "in this loop")
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].notes,
+            [
+                "failed obligation: initiation: the invariant must hold when the loop is reached",
+                "counterexample: `n = 0`"
+            ]
+        );
+        assert_eq!(diagnostics[1].message, "cannot prove that the loop invariant is preserved");
+        assert_eq!(diagnostics[1].labels[0].message, "`0 <= i && i < n` might not hold after an iteration");
+        assert_eq!(diagnostics[1].notes[1], "counterexample: `i = 0, n = 1, total = 0`");
+
+        // A path that does not make progress is named by its branches.
+        let source = "func halve(n: Int) -> Int =\n    i = n\n    while i > 0\n        decreases i\n        \
+                      if i > 10\n            i = i - 10\n        elsif i == 5\n            i = i + 0\n        \
+                      else\n            i = i - 1\n        end\n    end\n    return i\nend\n";
+        let (_, reports) = verify(source, &Options::default());
+        let statuses: Vec<(&str, &Status)> =
+            reports.iter().map(|report| (report.description.as_str(), &report.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                ("loop measure `i` decreases when `i > 10` in `halve`", &Status::Proved),
+                (
+                    "loop measure `i` decreases when not `i > 10` and `i == 5` in `halve`",
+                    &Status::Failed(Some("i = 5, n = 0".to_owned()))
+                ),
+                ("loop measure `i` decreases when not `i > 10` and not `i == 5` in `halve`", &Status::Proved),
+            ]
+        );
+        let diagnostic = reports[1].diagnostic.as_ref().unwrap();
+        assert_eq!(diagnostic.message, "cannot prove that the loop terminates");
+        let labels: Vec<(&str, &str)> =
+            diagnostic.labels.iter().map(|label| (label.span.text(source), label.message.as_str())).collect();
+        assert_eq!(
+            labels,
+            [
+                ("decreases i", "`i` might not decrease towards zero"),
+                ("while i > 0", "in this loop"),
+                ("i > 10", "on the path where this fails"),
+                ("i == 5", "on the path where this holds")
+            ]
+        );
+        assert_eq!(
+            diagnostic.notes[0],
+            "failed obligation: termination: the measure must be at least zero when an iteration starts, \
+             and smaller when it ends"
+        );
+
+        // What is known after the loop is its invariant and its condition failing.
+        let source = LOOPS.replace("ensures result >= 0", "ensures result >= 0 && n >= 0");
+        assert_eq!(refinement_errors(&source), []);
+        let messages = |source: &str| -> Vec<String> {
+            refinement_errors(source).into_iter().map(|d| d.message).collect()
+        };
+        let source = LOOPS.replace("invariant total >= 0\n        ", "");
+        assert_eq!(messages(&source), ["cannot prove the postcondition of `sum`"]);
+        assert_eq!(messages(&LOOPS.replace("decreases n - i", "decreases i < n")), ["mismatched types"]);
+    }
+
+    #[test]
+    fn test_runtime_loop_checks() {
+        let (printed, diagnostics) = with_checks(LOOPS, ContractMode::Runtime, false);
+        assert_eq!(diagnostics, []);
+        assert!(
+            printed.contains(
+                "    contract$assert(total >= 0, \"loop invariant `total >= 0` holds on entry\", \"8:9\")\n    \
+                 while i < n\n        measure$1 = n - i\n        total = total + i\n        i = i + 1\n        \
+                 contract$assert(0 <= i && i <= n, \"loop invariant `0 <= i && i <= n` is preserved\", \
+                 \"7:9\")\n        \
+                 contract$assert(total >= 0, \"loop invariant `total >= 0` is preserved\", \"8:9\")\n        \
+                 contract$assert(measure$1 >= 0 && n - i < measure$1, \"loop measure `n - i` decreases\", \
+                 \"9:9\")\n    end\n"
+            ),
+            "{printed}"
+        );
+        assert!(!printed.contains("        invariant ") && !printed.contains("        decreases "), "{printed}");
+
+        // A release build leaves out what was proved.
+        let source = LOOPS.replace("invariant seen >= 0", "invariant seen > 0");
+        let (printed, diagnostics) = with_checks(&source, ContractMode::Both, true);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["cannot prove that the loop invariant holds on entry"]);
+        assert!(!printed.contains("measure$") && !printed.contains("is preserved"), "{printed}");
+        let entry = "contract$assert(seen > 0, \"loop invariant `seen > 0` holds on entry\"";
+        assert!(printed.contains(entry), "{printed}");
+    }
+
+    #[test]
+    fn test_attribute_placement() {
+        // `#[` is only an attribute alone on its line before an item.
+        let source = "#[1] see the spec\nfunc main() =\n    x = 1 #[note] this\n    println(x)\nend\n";
+        assert!(compile(source).is_empty(), "{:?}", compile(source));
+
+        // Attributes apply to any item, not just functions.
+        let source = "#[allow(unused_imports)]\nimport IO\n\n#[deny(unused_variables)]\n#[allow(shadowing)]\nstruct P = x, y end\n";
+        assert!(lints_with(source, &[], None).is_empty());
+        let source = "#[deny(unused_imports)]\nimport IO\n";
+        assert_eq!(lints_with(source, &[], None), vec![(Level::Error, "unused_imports")]);
+    }
+
+    #[test]
+    fn test_no_field_on_type() {
+        let messages = |source: &str| -> Vec<String> { type_errors(source).into_iter().map(|d| d.message).collect() };
+        assert_eq!(
+            messages("func f(x: Int) -> Bool =\n    return x.foo\nend\n"),
+            ["no field `foo` on type `Int`"]
+        );
+        assert_eq!(
+            messages("func f(x: Int) =\n    return x.foo(1)\nend\n"),
+            ["no method named `foo` found for type `Int`"]
+        );
+        // A lambda's parameter is only known to be a number once it is called.
+        assert_eq!(
+            messages("func f() =\n    return ((p) => p.w)(1.0)\nend\n"),
+            ["no field `w` on type `Float`"]
+        );
+        // A receiver whose type is never decided may have any field.
+        assert_eq!(messages("func f(p) =\n    return p.w\nend\nfunc g(t) =\n    return (t, 1).1\nend\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_nat_outside_the_core() {
+        let errors = |source: &str| -> Vec<String> {
+            compile(source).into_iter().filter(|d| d.is_error()).map(|d| d.message).collect()
+        };
+        // A signature that only mentions `Nat` is inferred, with locals and every operator.
+        let source = "func f(n: Nat) -> Nat =\n    m = n + 1\n    return m * 2 - 1\nend\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+        // The verifier proves it is never negative instead.
+        let source = "func back(n: Nat) -> Nat =\n    return n - 1\nend\n";
+        assert_eq!(errors(source), ["cannot prove that `n - 1` is a `Nat`"]);
+        let source = "func back(n: Nat) -> Nat =\n    requires n > 0\n    return n - 1\nend\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_core_bodies() {
+        // Locals are `let`s, and calls to annotated functions inference checks unfold where the core accepts them.
+        let source = format!(
+            "{IMPLICIT_VEC}func h(x: Int) -> Int =\n    return x * 2\nend\n\
+             func double(n: Nat) -> Nat =\n    return n + n\nend\n\
+             func size({{a: Type, n: Nat}}, xs: Vec(a, n)) -> Int =\n    total = h(1)\n    total = total - 1\n    \
+             return match xs\n        Nil => total\n        Cons(_, rest) => size(rest) + total\n    end\nend\n\
+             func two() -> Vec(Int, double(1)) =\n    pair: Vec(Int, 2) = Cons(Int, 1, Cons(Int, 2, Nil(Int)))\n    \
+             return pair\nend\n"
+        );
+        assert_eq!(dependent_errors(&source), []);
+        let wrong = source.replace("double(1)) =", "double(2)) =");
+        assert_eq!(dependent_errors(&wrong)[0].labels[0].message, "expected `Vec(Int, 4)`, found `Vec(Int, 2)`");
+
+        let diagnostics = dependent_errors(&format!(
+            "{VEC}func f(a: Type, n: Nat, xs: Vec(a, n)) -> Nat =\n    while true\n    end\n    return n\nend\n"
+        ));
+        assert_eq!(diagnostics[0].labels[0].message, "not an assignment to a name or the final `return`");
+        let diagnostics = dependent_errors(&format!("{VEC}func f(a: Type, n: Nat, xs: Vec(a, n)) -> Str =\n    return n / 2\nend\n"));
+        assert_eq!(diagnostics[0].message, "cannot apply `/` to a value of type `Nat`");
+    }
+
+    #[test]
+    fn test_termination() {
+        let messages = |source: &str| -> Vec<String> { dependent_errors(source).into_iter().map(|d| d.message).collect() };
+        let source = format!("{VEC}func forever(n: Nat) -> Vec(Int, n + 1) =\n    return forever(n)\nend\n");
+        assert_eq!(messages(&source), ["cannot show that `forever` terminates"]);
+
+        // Calls that pass a parameter on unchanged are fine as long as each cycle takes it apart somewhere.
+        let source = format!(
+            "{IMPLICIT_VEC}func ping({{n: Nat}}, xs: Vec(Int, n)) -> Int =\n    return match xs\n        Nil => 0\n        \
+             Cons(_, rest) => pong(rest)\n    end\nend\n\
+             func pong({{n: Nat}}, xs: Vec(Int, n)) -> Int =\n    return ping(xs)\nend\n"
+        );
+        assert_eq!(messages(&source), Vec::<String>::new());
+        let wrong = source.replace("pong(rest)", "pong(xs)");
+        assert_eq!(messages(&wrong), ["cannot show that `ping` terminates"]);
+
+        // A function inference checks may recurse however it likes; calls to it are just never unfolded.
+        let source = format!(
+            "{VEC}func spin(n: Nat) -> Nat =\n    return spin(n)\nend\n\
+             func f(a: Type, xs: Vec(a, spin(1))) -> Vec(a, spin(1)) =\n    return xs\nend\n"
+        );
+        assert_eq!(messages(&source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        // They bind between comparisons and arithmetic, `|` loosest.
+        let (printed, errors) = grouping("x & 1 << 2 + 1 | x % 3 == 0");
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(printed, "(((x & (1 << (2 + 1))) | (x % 3)) == 0)");
+
+        // They apply to integers, and give the type of their operands.
+        let diagnostics = type_errors("func f(x: Float) -> Float =\n    return x % 2.0\nend\n");
+        assert_eq!(diagnostics[0].message, "mismatched types");
+        assert_eq!(diagnostics[0].labels[0].message, "expected an integer, found `Float`");
+        assert_eq!(compile("func f(x: Int, y: Int) -> Int =\n    return x & y | x << 2 >> y % 3\nend\n"), []);
+
+        // On fixed-width integers they are proved at their width.
+        let source = "type Nibble = u8 where self < 16\n\n\
+                      func low(x: u8) -> Nibble =\n    return x & 15\nend\n\n\
+                      func high(x: u8) -> Nibble =\n    return x >> 4\nend\n\n\
+                      func odd(x: u8) -> Bool =\n    ensures result == (x % 2 == 1)\n    return x & 1 == 1\nend\n";
+        assert_eq!(refinement_errors(source), []);
+        let source = source.replace("x >> 4", "x >> 3");
+        let messages: Vec<String> = refinement_errors(&source).into_iter().map(|d| d.message).collect();
+        assert_eq!(messages, ["cannot prove that `x >> 3` is a `Nibble`"]);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let source = "func f(x: u8) -> u8 =\n    return x / 0\nend\n\n\
+                      func g(a: Int) -> Int =\n    return a % 0\nend\n\n\
+                      func h(a: Int, b: Int) -> Int =\n    requires b != 0\n    return a / b + a % b\nend\n\n\
+                      func k(a: Int, b: Int) -> Int =\n    return a / b\nend\n\n\
+                      func m(x: Float, y: Float) -> Float =\n    return x / y\nend\n";
+        let (diagnostics, reports) = verify(source, &Options::default());
+        assert_eq!(diagnostics, []);
+        let statuses: Vec<(&str, &Status)> =
+            reports.iter().map(|report| (report.description.as_str(), &report.status)).collect();
+        let failed = |values: &str| Status::Failed(Some(values.to_owned()));
+        assert_eq!(
+            statuses,
+            [
+                ("the divisor of `x / 0` is not zero in `f`", &failed("x = 0 (0b00000000)")),
+                ("the divisor of `a % 0` is not zero in `g`", &failed("a = 0")),
+                ("the divisor of `a / b` is not zero in `h`", &Status::Proved),
+                ("the divisor of `a % b` is not zero in `h`", &Status::Proved),
+                ("the divisor of `a / b` is not zero in `k`", &failed("a = 0, b = 0")),
+            ]
+        );
+        let diagnostic = reports[0].diagnostic.as_ref().unwrap();
+        assert_eq!(diagnostic.message, "cannot prove that `x / 0` does not divide by zero");
+        assert_eq!(diagnostic.span.text(source), "x / 0");
+        assert_eq!(diagnostic.labels[0].message, "`0` might be zero");
+        assert_eq!(diagnostic.notes, ["counterexample: `x = 0 (0b00000000)`"]);
+
+        // Division by zero is not checked when the program runs, so it stays an error.
+        let options = Options {
+            contracts: ContractMode::Both,
+            ..Options::default()
+        };
+        let levels: Vec<Level> = compile_with(source, &options).iter().map(|d| d.level).collect();
+        assert_eq!(levels, [Level::Error; 3]);
+    }
+}
@@ -0,0 +1,322 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Named lints and the levels they are reported at.
+//!
+//! A lint's level starts at its default, is then adjusted by the command line
+//! flags in the order given, then by `#[allow(...)]`-style attributes on the
+//! enclosing item, and is finally capped by `--cap-lints`.
+
+use crate::checks::{attributes, items};
+use flerry_core::suggest::find_best_match;
+use flerry_core::{Diagnostic, Level, Span, Suggestion, Token, TokenType};
+use std::collections::HashSet;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Forbid,
+}
+
+impl LintLevel {
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            "forbid" => Some(LintLevel::Forbid),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        }
+    }
+
+    /// The command line flag that sets this level.
+    pub fn flag(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "-A",
+            LintLevel::Warn => "-W",
+            LintLevel::Deny => "-D",
+            LintLevel::Forbid => "-F",
+        }
+    }
+}
+
+pub struct Lint {
+    pub name: &'static str,
+    pub default_level: LintLevel,
+    pub description: &'static str,
+}
+
+pub const UNUSED_VARIABLES: &Lint = &Lint {
+    name: "unused_variables",
+    default_level: LintLevel::Warn,
+    description: "detects variables and parameters that are never read",
+};
+
+pub const UNUSED_IMPORTS: &Lint = &Lint {
+    name: "unused_imports",
+    default_level: LintLevel::Warn,
+    description: "detects imports whose names are never used",
+};
+
+pub const UNREACHABLE_CODE: &Lint = &Lint {
+    name: "unreachable_code",
+    default_level: LintLevel::Warn,
+    description: "detects statements that can never run",
+};
+
+pub const UNREACHABLE_PATTERNS: &Lint = &Lint {
+    name: "unreachable_patterns",
+    default_level: LintLevel::Warn,
+    description: "detects match arms and alternatives no value can reach",
+};
+
+pub const SHADOWING: &Lint = &Lint {
+    name: "shadowing",
+    default_level: LintLevel::Warn,
+    description: "detects local names that hide a function or import",
+};
+
+pub const UNKNOWN_LINTS: &Lint = &Lint {
+    name: "unknown_lints",
+    default_level: LintLevel::Warn,
+    description: "detects lint attributes naming a lint that does not exist",
+};
+
+pub const LINTS: &[&Lint] = &[
+    UNUSED_VARIABLES,
+    UNUSED_IMPORTS,
+    UNREACHABLE_CODE,
+    UNREACHABLE_PATTERNS,
+    SHADOWING,
+    UNKNOWN_LINTS,
+];
+
+/// The group covering every lint that would otherwise be reported as a warning.
+pub const WARNINGS: &str = "warnings";
+
+pub fn find_lint(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().copied().find(|lint| lint.name == name)
+}
+
+/// Suggests a known lint name for a misspelled one.
+pub fn similar_lint(name: &str) -> Option<&'static str> {
+    find_best_match(name, LINTS.iter().map(|lint| lint.name).chain([WARNINGS]))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    /// `-A`/`-W`/`-D`/`-F` flags in command line order.
+    pub flags: Vec<(LintLevel, String)>,
+    /// The highest level any lint may be reported at.
+    pub cap: Option<LintLevel>,
+}
+
+/// One `level(lint, ...)` group of a `#[...]` attribute and the item it
+/// applies to.
+struct LintAttribute<'a> {
+    level: LintLevel,
+    lints: Vec<&'a Token<'a>>,
+    scope: Span,
+}
+
+#[derive(Clone, Copy)]
+enum LevelSource {
+    Default,
+    CommandLine(LintLevel),
+    Attribute(Span),
+    WarningsGroup(LintLevel),
+}
+
+fn collect_attributes<'a>(
+    tokens: &'a [Token<'a>],
+    options: &LintOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<LintAttribute<'a>> {
+    let items = items(tokens);
+    let ranges = attributes(tokens);
+    let mut collected = Vec::new();
+
+    for range in &ranges {
+        let attribute = &tokens[range.clone()];
+        let span = attribute[0].span.to(attribute[attribute.len() - 1].span);
+        // Stacked attributes all apply to the item after the last of them.
+        let mut start = range.end;
+        while let Some(next) = ranges.iter().find(|next| next.start == start) {
+            start = next.end;
+        }
+        let Some(item) = items.iter().find(|item| item.start == start) else {
+            diagnostics.push(Diagnostic::error(
+                "lint attributes must be placed directly before an item",
+                span,
+            ));
+            continue;
+        };
+        let scope = tokens[item.start].span.to(tokens[item.end - 1].span);
+
+        // The attribute holds comma separated groups such as `allow(a, b)`.
+        let mut i = 2;
+        while i < attribute.len() {
+            let token = &attribute[i];
+            if token.ttype != TokenType::Identifier {
+                i += 1;
+                continue;
+            }
+            let Some(level) = LintLevel::from_name(token.lexeme) else {
+                diagnostics.push(
+                    Diagnostic::error(format!("unknown attribute `{}`", token.lexeme), token.span)
+                        .with_help("expected `allow`, `warn`, `deny` or `forbid`"),
+                );
+                break;
+            };
+            let close = attribute[i..]
+                .iter()
+                .position(|t| t.ttype == TokenType::RParen)
+                .map_or(attribute.len(), |p| i + p);
+            let names = attribute[i + 1..close]
+                .iter()
+                .filter(|t| t.ttype == TokenType::Identifier);
+            let lints = names
+                .filter(|name| check_attribute_lint(name, level, options, diagnostics))
+                .collect();
+            collected.push(LintAttribute { level, lints, scope });
+            i = close + 1;
+        }
+    }
+
+    collected
+}
+
+/// Whether a lint named in an attribute exists and may be set to `level`.
+fn check_attribute_lint(
+    token: &Token,
+    level: LintLevel,
+    options: &LintOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> bool {
+    if token.lexeme != WARNINGS && find_lint(token.lexeme).is_none() {
+        let mut unknown =
+            Diagnostic::warning(format!("unknown lint: `{}`", token.lexeme), token.span).with_lint(UNKNOWN_LINTS.name);
+        if let Some(name) = similar_lint(token.lexeme) {
+            unknown = unknown.with_suggestion(
+                Suggestion::replace(format!("did you mean `{name}`?"), token.span, name).maybe_incorrect(),
+            );
+        }
+        diagnostics.push(unknown);
+        return false;
+    }
+
+    let forbidden = options
+        .flags
+        .iter()
+        .any(|(flag, name)| *flag == LintLevel::Forbid && name == token.lexeme);
+    if forbidden && level < LintLevel::Forbid {
+        diagnostics.push(
+            Diagnostic::error(
+                format!("{}({}) incompatible with previous forbid", level.name(), token.lexeme),
+                token.span,
+            )
+            .with_note(format!("`-F {}` was given on the command line", token.lexeme)),
+        );
+        return false;
+    }
+    true
+}
+
+fn level_for(lint: &Lint, span: Span, options: &LintOptions, attributes: &[LintAttribute]) -> (LintLevel, LevelSource) {
+    let mut level = lint.default_level;
+    let mut source = LevelSource::Default;
+    let mut warnings = None;
+
+    for (flag, name) in &options.flags {
+        if name == WARNINGS {
+            warnings = Some(*flag);
+        } else if name == lint.name && level != LintLevel::Forbid {
+            level = *flag;
+            source = LevelSource::CommandLine(*flag);
+        }
+    }
+
+    let enclosing = attributes
+        .iter()
+        .filter(|attr| attr.scope.start <= span.start && span.end <= attr.scope.end);
+    for attribute in enclosing {
+        for token in &attribute.lints {
+            if token.lexeme == WARNINGS {
+                warnings = Some(attribute.level);
+            } else if token.lexeme == lint.name && level != LintLevel::Forbid {
+                level = attribute.level;
+                source = LevelSource::Attribute(token.span);
+            }
+        }
+    }
+
+    if level == LintLevel::Warn
+        && let Some(group) = warnings
+    {
+        level = group;
+        source = LevelSource::WarningsGroup(group);
+    }
+    if let Some(cap) = options.cap {
+        level = level.min(cap);
+    }
+
+    (level, source)
+}
+
+/// Gives every lint diagnostic its final level, dropping the allowed ones.
+/// The first report of each lint explains where its level came from.
+pub fn apply_lint_levels(tokens: &[Token], diagnostics: Vec<Diagnostic>, options: &LintOptions) -> Vec<Diagnostic> {
+    let mut pending = Vec::new();
+    let attributes = collect_attributes(tokens, options, &mut pending);
+    pending.extend(diagnostics);
+
+    let mut explained = HashSet::new();
+    let mut leveled = Vec::new();
+
+    for mut diagnostic in pending {
+        let Some(lint) = diagnostic.lint.and_then(find_lint) else {
+            leveled.push(diagnostic);
+            continue;
+        };
+        let (level, source) = level_for(lint, diagnostic.span, options, &attributes);
+        diagnostic.level = match level {
+            LintLevel::Allow => continue,
+            LintLevel::Warn => Level::Warning,
+            LintLevel::Deny | LintLevel::Forbid => Level::Error,
+        };
+
+        if explained.insert(lint.name) {
+            diagnostic = match source {
+                LevelSource::Default => {
+                    diagnostic.with_note(format!("`#[{}({})]` on by default", level.name(), lint.name))
+                }
+                LevelSource::CommandLine(flag) => diagnostic.with_note(format!(
+                    "requested on the command line with `{} {}`",
+                    flag.flag(),
+                    lint.name
+                )),
+                LevelSource::Attribute(span) => diagnostic.with_label(span, "the lint level is defined here"),
+                LevelSource::WarningsGroup(group) => diagnostic.with_note(format!(
+                    "`{} {}` implied by `{} {WARNINGS}`",
+                    group.flag(),
+                    lint.name,
+                    group.flag()
+                )),
+            };
+        }
+        leveled.push(diagnostic);
+    }
+
+    leveled
+}
@@ -0,0 +1,1208 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! A recursive-descent parser from the token stream to the AST.
+//!
+//! Flerry has no statement terminator: a statement ends where the next token
+//! cannot continue it. Operators, calls and field accesses only continue an
+//! expression when they start on the same line as the token before them, so
+//! `f\n(x)` is two statements while `a +\n b` is one.
+
+use crate::ast::*;
+use crate::precedence::{Assoc, BinaryOperator, Precedence, binary_operator, prefix_operator};
+use flerry_core::span::line_bounds;
+use flerry_core::{Diagnostic, Span, Suggestion, Token, TokenType};
+
+/// Parse errors are boxed: they are rare and `Diagnostic` is large.
+type PResult<T> = Result<T, Box<Diagnostic>>;
+
+pub struct Parser<'t, 'a> {
+    source: &'a str,
+    tokens: &'t [Token<'a>],
+    pos: usize,
+    /// Whether `=>` is read as implication.
+    in_spec: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Parses a whole file. `tokens` must end with an `Eof` token.
+pub fn parse<'a>(source: &'a str, tokens: &[Token<'a>]) -> (Module, Vec<Diagnostic>) {
+    let mut parser = Parser::new(source, tokens);
+    let module = parser.parse_module();
+    (module, parser.diagnostics)
+}
+
+fn describe(token: &Token) -> String {
+    match token.ttype {
+        TokenType::Eof => "end of file".to_owned(),
+        TokenType::Identifier => format!("identifier `{}`", token.lexeme),
+        _ => format!("`{}`", token.lexeme),
+    }
+}
+
+impl<'t, 'a> Parser<'t, 'a> {
+    pub fn new(source: &'a str, tokens: &'t [Token<'a>]) -> Parser<'t, 'a> {
+        Parser {
+            source,
+            tokens,
+            pos: 0,
+            in_spec: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// The errors reported so far.
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    // Token helpers
+    fn peek(&self) -> &'t Token<'a> {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn peek_at(&self, n: usize) -> &'t Token<'a> {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)]
+    }
+
+    fn previous(&self) -> &'t Token<'a> {
+        &self.tokens[self.pos.saturating_sub(1)]
+    }
+
+    fn check(&self, ttype: TokenType) -> bool {
+        self.peek().ttype == ttype
+    }
+
+    fn at_end(&self) -> bool {
+        self.check(TokenType::Eof)
+    }
+
+    fn advance(&mut self) -> &'t Token<'a> {
+        let token = self.peek();
+        if !self.at_end() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, ttype: TokenType) -> bool {
+        if self.check(ttype) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the current token sits on the same line as the one before it.
+    fn same_line(&self) -> bool {
+        self.pos > 0 && self.peek().line == self.previous().line
+    }
+
+    fn error_here(&self, expected: &str) -> Box<Diagnostic> {
+        let token = self.peek();
+        Box::new(Diagnostic::error(
+            format!("expected {expected}, found {}", describe(token)),
+            token.span,
+        ))
+    }
+
+    fn expect(&mut self, ttype: TokenType, expected: &str) -> PResult<&'t Token<'a>> {
+        if self.check(ttype) {
+            Ok(self.advance())
+        } else {
+            Err(self.error_here(expected))
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> PResult<Ident> {
+        let token = self.expect(TokenType::Identifier, what)?;
+        Ok(Ident {
+            name: token.lexeme.to_owned(),
+            span: token.span,
+        })
+    }
+
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
+    /// The leading whitespace of the line containing `offset`.
+    fn indent_of(&self, offset: usize) -> &'a str {
+        let (start, end) = line_bounds(self.source, offset);
+        let line = &self.source[start..end];
+        &line[..line.len() - line.trim_start().len()]
+    }
+
+    /// Consumes the `end` closing the block opened by `opener`.
+    ///
+    /// The block is reported as unclosed, with a fix inserting the `end`, when
+    /// the file or the function runs out first, or when the `end` found is
+    /// indented less than `opener` and so must belong to an enclosing block.
+    fn expect_end(&mut self, opener: &Token) -> PResult<()> {
+        let token = self.peek();
+        let outdented = token.ttype == TokenType::End
+            && !self.same_line()
+            && self.indent_of(token.span.start).len() < self.indent_of(opener.span.start).len();
+        if token.ttype == TokenType::End && !outdented {
+            self.advance();
+            return Ok(());
+        }
+        if !outdented && !matches!(token.ttype, TokenType::Eof | TokenType::Func) {
+            return Err(self.error_here(&format!("`end` to close `{}`", opener.lexeme)));
+        }
+
+        // Close after the last line indented deeper than `opener`, keeping any
+        // trailing comment on that line where it was.
+        let indent = self.indent_of(opener.span.start);
+        let opener_index = self.tokens.iter().position(|t| t.span == opener.span).unwrap_or(0);
+        let last_inside = self.tokens[opener_index..self.pos]
+            .iter()
+            .rev()
+            .find(|t| self.indent_of(t.span.start).len() > indent.len())
+            .unwrap_or(self.previous());
+        let insert_at = line_bounds(self.source, last_inside.span.end).1;
+        let span = if token.ttype == TokenType::Eof {
+            Span::point(insert_at)
+        } else {
+            token.span
+        };
+        self.diagnostics.push(
+            Diagnostic::error(format!("unclosed `{}`", opener.lexeme), span)
+                .with_label(opener.span, format!("unclosed `{}` started here", opener.lexeme))
+                .with_suggestion(Suggestion::insert(
+                    "insert the missing `end`",
+                    insert_at,
+                    format!("\n{indent}end"),
+                )),
+        );
+        Ok(())
+    }
+
+    /// Skips to the first token of the next line, or to `end`, whichever
+    /// comes first, so one bad statement does not take the block with it.
+    fn skip_line(&mut self) {
+        let line = self.peek().line;
+        while !self.at_end() && self.peek().line == line && !self.check(TokenType::End) {
+            self.advance();
+        }
+    }
+
+    /// Skips to the next token that can start a top-level item.
+    fn skip_to_item(&mut self) {
+        while !self.at_end() && !self.at_item_start() {
+            self.advance();
+        }
+    }
+
+    fn at_item_start(&self) -> bool {
+        let token = self.peek();
+        matches!(
+            token.ttype,
+            TokenType::Func | TokenType::Struct | TokenType::Enum | TokenType::Type | TokenType::Import
+        ) || token.ttype == TokenType::Hash && self.peek_at(1).ttype == TokenType::LSqBrace
+    }
+
+    // Items
+    pub fn parse_module(&mut self) -> Module {
+        let mut items = Vec::new();
+        while !self.at_end() {
+            // Lint attributes are handled on the token stream.
+            if self.check(TokenType::Hash) {
+                while !self.at_end() && !self.check(TokenType::RSqBrace) {
+                    self.advance();
+                }
+                self.advance();
+                continue;
+            }
+            let start = self.peek().span;
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(diagnostic) => {
+                    self.diagnostics.push(*diagnostic);
+                    self.advance();
+                    self.skip_to_item();
+                    items.push(Item {
+                        kind: ItemKind::Error,
+                        span: self.span_from(start),
+                    });
+                }
+            }
+        }
+        let span = Span::new(0, self.source.len());
+        Module { items, span }
+    }
+
+    fn parse_item(&mut self) -> PResult<Item> {
+        let start = self.peek().span;
+        let kind = match self.peek().ttype {
+            TokenType::Import => {
+                self.advance();
+                ItemKind::Import(self.expect_ident("a module name")?)
+            }
+            TokenType::Func => ItemKind::Func(self.parse_func()?),
+            TokenType::Struct => ItemKind::Struct(self.parse_struct()?),
+            TokenType::Enum => ItemKind::Enum(self.parse_enum()?),
+            TokenType::Type => ItemKind::Type(self.parse_type_alias()?),
+            _ => return Err(self.error_here("`func`, `struct`, `enum`, `type` or `import`")),
+        };
+        Ok(Item {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_func(&mut self) -> PResult<Func> {
+        let opener = self.advance();
+        let name = self.expect_ident("a function name")?;
+        let mut params = Vec::new();
+        let mut ret = None;
+        // A broken signature still leaves a body worth checking.
+        if let Err(diagnostic) = self.parse_signature(&mut params, &mut ret) {
+            self.diagnostics.push(*diagnostic);
+            self.skip_line();
+        }
+        let contracts = self.parse_contracts(&[ContractKind::Requires, ContractKind::Ensures]);
+        let body = self.parse_block(&[TokenType::End]);
+        self.expect_end(opener)?;
+        Ok(Func {
+            name,
+            params,
+            ret,
+            contracts,
+            body,
+        })
+    }
+
+    /// The clauses of `kinds` opening a body.
+    fn parse_contracts(&mut self, kinds: &[ContractKind]) -> Vec<Contract> {
+        let mut contracts = Vec::new();
+        while let Some(kind) = self.at_contract(kinds) {
+            let start = self.advance().span;
+            match self.parse_spec_expr() {
+                Ok(cond) => contracts.push(Contract {
+                    kind,
+                    cond,
+                    span: self.span_from(start),
+                }),
+                Err(diagnostic) => {
+                    self.diagnostics.push(*diagnostic);
+                    self.skip_line();
+                }
+            }
+        }
+        contracts
+    }
+
+    /// The clause of one of `kinds` starting here. `requires`, `ensures`,
+    /// `invariant` and `decreases` are only keywords at the start of a line
+    /// followed by a condition, so they stay free as names.
+    fn at_contract(&self, kinds: &[ContractKind]) -> Option<ContractKind> {
+        let token = self.peek();
+        if token.ttype != TokenType::Identifier {
+            return None;
+        }
+        let kind = kinds.iter().copied().find(|kind| kind.keyword() == token.lexeme)?;
+        let next = self.peek_at(1);
+        let starts_statement = matches!(
+            next.ttype,
+            TokenType::Equal
+                | TokenType::Colon
+                | TokenType::Dot
+                | TokenType::LSqBrace
+                | TokenType::End
+                | TokenType::Eof
+        );
+        (next.line == token.line && !starts_statement).then_some(kind)
+    }
+
+    /// `(params) =` or `(params) -> Type =`.
+    fn parse_signature(&mut self, params: &mut Vec<Param>, ret: &mut Option<TypeExpr>) -> PResult<()> {
+        self.expect(TokenType::LParen, "`(`")?;
+        while !self.check(TokenType::RParen) {
+            if self.eat(TokenType::LBrace) {
+                let group = self.parse_implicit(Self::parse_param)?;
+                params.extend(group.into_iter().map(|param| Param {
+                    implicit: true,
+                    ..param
+                }));
+            } else {
+                params.push(self.parse_param()?);
+            }
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenType::RParen, "`,` or `)`")?;
+        if self.same_line() && self.eat(TokenType::ThinArrow) {
+            *ret = Some(self.parse_type()?);
+        }
+        self.expect(TokenType::Equal, "`=`")?;
+        Ok(())
+    }
+
+    /// `name`, or `name: Type`.
+    fn parse_param(&mut self) -> PResult<Param> {
+        let name = self.expect_ident("a parameter name")?;
+        let ty = if self.eat(TokenType::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        Ok(Param {
+            span: self.span_from(name.span),
+            name,
+            ty,
+            implicit: false,
+        })
+    }
+
+    /// The rest of `{a: Type, n: Nat}`, after the `{`.
+    fn parse_implicit<T>(&mut self, mut parse: impl FnMut(&mut Self) -> PResult<T>) -> PResult<Vec<T>> {
+        let mut group = Vec::new();
+        while !self.check(TokenType::RBrace) {
+            group.push(parse(self)?);
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenType::RBrace, "`,` or `}`")?;
+        Ok(group)
+    }
+
+    fn parse_struct(&mut self) -> PResult<StructDef> {
+        let opener = self.advance();
+        let name = self.expect_ident("a struct name")?;
+        self.expect(TokenType::Equal, "`=`")?;
+        let mut fields = Vec::new();
+        while self.check(TokenType::Identifier) {
+            fields.push(self.parse_field()?);
+            self.eat(TokenType::Comma);
+        }
+        self.expect_end(opener)?;
+        Ok(StructDef { name, fields })
+    }
+
+    fn parse_enum(&mut self) -> PResult<EnumDef> {
+        let opener = self.advance();
+        let name = self.expect_ident("an enum name")?;
+        let mut params = Vec::new();
+        if self.same_line() && self.eat(TokenType::LParen) {
+            while !self.check(TokenType::RParen) {
+                params.push(self.parse_param()?);
+                if !self.eat(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.expect(TokenType::RParen, "`,` or `)`")?;
+        }
+        self.expect(TokenType::Equal, "`=`")?;
+        let mut variants = Vec::new();
+        self.eat(TokenType::Pipe);
+        while self.check(TokenType::Identifier) {
+            let name = self.expect_ident("a variant name")?;
+            let mut fields = Vec::new();
+            if self.eat(TokenType::LParen) {
+                while !self.check(TokenType::RParen) {
+                    if self.eat(TokenType::LBrace) {
+                        let group = self.parse_implicit(Self::parse_field)?;
+                        fields.extend(group.into_iter().map(|field| Field {
+                            implicit: true,
+                            ..field
+                        }));
+                    } else {
+                        fields.push(self.parse_field()?);
+                    }
+                    if !self.eat(TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.expect(TokenType::RParen, "`,` or `)`")?;
+            }
+            let ret = if self.same_line() && self.eat(TokenType::ThinArrow) {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            variants.push(Variant {
+                span: self.span_from(name.span),
+                name,
+                fields,
+                ret,
+            });
+            self.eat(TokenType::Pipe);
+        }
+        self.expect_end(opener)?;
+        Ok(EnumDef { name, params, variants })
+    }
+
+    /// `name: Type`, or a bare `name`.
+    fn parse_field(&mut self) -> PResult<Field> {
+        let name = self.expect_ident("a field name")?;
+        let ty = if self.eat(TokenType::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        Ok(Field {
+            span: self.span_from(name.span),
+            name,
+            ty,
+            implicit: false,
+        })
+    }
+
+    /// `type Name = Type`, or with `where` a refinement type whose
+    /// parameters its condition may use: `type Index(n) = Nat where self < n`.
+    fn parse_type_alias(&mut self) -> PResult<TypeAlias> {
+        self.advance();
+        let name = self.expect_ident("a type name")?;
+        let mut params = Vec::new();
+        if self.same_line() && self.eat(TokenType::LParen) {
+            while !self.check(TokenType::RParen) {
+                params.push(self.expect_ident("a parameter name")?);
+                if !self.eat(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.expect(TokenType::RParen, "`,` or `)`")?;
+        }
+        self.expect(TokenType::Equal, "`=`")?;
+        let ty = self.parse_type()?;
+        // `where` is only a keyword here, so it stays free as a name.
+        let refinement = if self.same_line() && self.check(TokenType::Identifier) && self.peek().lexeme == "where" {
+            self.advance();
+            Some(self.parse_spec_expr()?)
+        } else {
+            None
+        };
+        Ok(TypeAlias {
+            name,
+            params,
+            ty,
+            refinement,
+        })
+    }
+
+    /// A type, where `->` groups to the right: `Int -> Int -> Int` takes an
+    /// `Int` and returns an `Int -> Int`. Parentheses around one type group
+    /// it; around several, or none, they make a tuple.
+    fn parse_type(&mut self) -> PResult<TypeExpr> {
+        let start = self.peek().span;
+        let (mut params, grouped) = if self.eat(TokenType::LParen) {
+            (self.parse_type_list()?, true)
+        } else {
+            (vec![self.parse_named_type()?], false)
+        };
+        if self.same_line() && self.eat(TokenType::ThinArrow) {
+            let ret = self.parse_type()?;
+            return Ok(TypeExpr {
+                span: self.span_from(start),
+                kind: TypeExprKind::Function {
+                    params,
+                    ret: Box::new(ret),
+                },
+            });
+        }
+        if !grouped {
+            return Ok(params.remove(0));
+        }
+        if params.len() == 1 {
+            // `(T)` is just `T`.
+            let mut ty = params.remove(0);
+            ty.span = self.span_from(start);
+            return Ok(ty);
+        }
+        Ok(TypeExpr {
+            span: self.span_from(start),
+            kind: TypeExprKind::Tuple(params),
+        })
+    }
+
+    fn parse_named_type(&mut self) -> PResult<TypeExpr> {
+        let name = self.expect_ident("a type")?;
+        let mut args = Vec::new();
+        if self.same_line() && self.eat(TokenType::LParen) {
+            args = self.parse_type_list()?;
+        }
+        Ok(TypeExpr {
+            span: self.span_from(name.span),
+            kind: TypeExprKind::Named { name, args },
+        })
+    }
+
+    /// Parses comma separated types up to and including `)`.
+    fn parse_type_list(&mut self) -> PResult<Vec<TypeExpr>> {
+        let mut types = Vec::new();
+        while !self.check(TokenType::RParen) {
+            types.push(self.parse_type_arg()?);
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenType::RParen, "`,` or `)`")?;
+        Ok(types)
+    }
+
+    /// A type in a list: one that names its value, `n: Nat`, or a value a
+    /// type is applied to, `n + 1` or `3`.
+    fn parse_type_arg(&mut self) -> PResult<TypeExpr> {
+        if self.check(TokenType::Identifier) && self.peek_at(1).ttype == TokenType::Colon {
+            let name = self.expect_ident("a name")?;
+            self.advance();
+            let ty = self.parse_type()?;
+            return Ok(TypeExpr {
+                span: self.span_from(name.span),
+                kind: TypeExprKind::Bind { name, ty: Box::new(ty) },
+            });
+        }
+        let start = self.pos;
+        if !matches!(self.peek().ttype, TokenType::Integer | TokenType::Minus)
+            && let Ok(ty) = self.parse_type()
+            && !(self.same_line() && (self.peek_binary().is_some() || self.check(TokenType::Dot)))
+        {
+            return Ok(ty);
+        }
+        // Not a type after all: parse it again as an expression.
+        self.pos = start;
+        let expr = self.parse_expr()?;
+        Ok(TypeExpr {
+            span: expr.span,
+            kind: TypeExprKind::Expr(Box::new(expr)),
+        })
+    }
+
+    // Statements
+    /// Parses statements until one of `terminators`, or anything that cannot
+    /// be inside a block: the end of the file or the next `func`.
+    fn parse_block(&mut self, terminators: &[TokenType]) -> Block {
+        let start = self.peek().span;
+        let mut stmts = Vec::new();
+        while !terminators.contains(&self.peek().ttype)
+            && !matches!(self.peek().ttype, TokenType::Eof | TokenType::Func)
+        {
+            let before = self.pos;
+            let start = self.peek().span;
+            match self.parse_stmt() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(diagnostic) => {
+                    self.diagnostics.push(*diagnostic);
+                    self.skip_line();
+                    if self.pos == before {
+                        self.advance();
+                    }
+                    stmts.push(Stmt {
+                        kind: StmtKind::Error,
+                        span: self.span_from(start),
+                    });
+                }
+            }
+        }
+        let span = if stmts.is_empty() {
+            Span::point(start.start)
+        } else {
+            stmts[0].span.to(stmts[stmts.len() - 1].span)
+        };
+        Block { stmts, span }
+    }
+
+    fn parse_stmt(&mut self) -> PResult<Stmt> {
+        let start = self.peek().span;
+        let kind = match self.peek().ttype {
+            TokenType::Return => {
+                self.advance();
+                // A bare `return` is followed by the next line or the block's end.
+                let value = if self.same_line() && !self.check(TokenType::End) && !self.at_end() {
+                    Some(self.parse_expr()?)
+                } else {
+                    None
+                };
+                StmtKind::Return(value)
+            }
+            TokenType::If => self.parse_if()?,
+            TokenType::While => {
+                let opener = self.advance();
+                let cond = self.parse_header_expr();
+                let contracts = self.parse_contracts(&[ContractKind::Invariant, ContractKind::Decreases]);
+                let body = self.parse_block(&[TokenType::End]);
+                self.expect_end(opener)?;
+                StmtKind::While { cond, contracts, body }
+            }
+            TokenType::For => {
+                let opener = self.advance();
+                let var = self.expect_ident("a loop variable")?;
+                let in_token = self.peek();
+                let iter = if in_token.ttype == TokenType::Identifier && in_token.lexeme == "in" {
+                    self.advance();
+                    self.parse_header_expr()
+                } else {
+                    let diagnostic = self.error_here("`in`");
+                    self.header_error(*diagnostic)
+                };
+                let contracts = self.parse_contracts(&[ContractKind::Invariant, ContractKind::Decreases]);
+                let body = self.parse_block(&[TokenType::End]);
+                self.expect_end(opener)?;
+                StmtKind::For {
+                    var,
+                    iter,
+                    contracts,
+                    body,
+                }
+            }
+            _ => {
+                let expr = self.parse_expr()?;
+                let ty = if matches!(expr.kind, ExprKind::Ident(_)) && self.same_line() && self.eat(TokenType::Colon) {
+                    let ty = self.parse_type()?;
+                    if !self.check(TokenType::Equal) {
+                        return Err(self.error_here("`=`"));
+                    }
+                    Some(ty)
+                } else {
+                    None
+                };
+                if self.same_line() && self.eat(TokenType::Equal) {
+                    if !matches!(
+                        expr.kind,
+                        ExprKind::Ident(_) | ExprKind::Field { .. } | ExprKind::Index { .. }
+                    ) {
+                        return Err(Diagnostic::error("invalid assignment target", expr.span)
+                            .with_help("only names, fields and indexed elements can be assigned to")
+                            .into());
+                    }
+                    let value = self.parse_expr()?;
+                    StmtKind::Assign {
+                        target: expr,
+                        ty,
+                        value,
+                    }
+                } else {
+                    StmtKind::Expr(expr)
+                }
+            }
+        };
+
+        // Anything left on the line would be silently juxtaposed otherwise.
+        if self.same_line() && !matches!(self.peek().ttype, TokenType::End | TokenType::Eof) {
+            return Err(self.error_here("the end of the statement"));
+        }
+
+        Ok(Stmt {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses the expression on the first line of a block statement. On an
+    /// error the rest of the line is skipped but the block is still parsed, so
+    /// its `end` is not mistaken for the end of the enclosing block.
+    fn parse_header_expr(&mut self) -> Expr {
+        match self.parse_expr() {
+            Ok(expr) => expr,
+            Err(diagnostic) => self.header_error(*diagnostic),
+        }
+    }
+
+    fn header_error(&mut self, diagnostic: Diagnostic) -> Expr {
+        let start = self.peek().span;
+        self.diagnostics.push(diagnostic);
+        self.skip_line();
+        Expr {
+            kind: ExprKind::Error,
+            span: Span::new(start.start, self.previous().span.end.max(start.start)),
+        }
+    }
+
+    fn parse_if(&mut self) -> PResult<StmtKind> {
+        let opener = self.advance();
+        let mut branches = Vec::new();
+        let mut else_block = None;
+
+        let cond = self.parse_header_expr();
+        let block = self.parse_block(&[TokenType::Elsif, TokenType::Else, TokenType::End]);
+        branches.push((cond, block));
+        loop {
+            if self.eat(TokenType::Elsif) {
+                let cond = self.parse_header_expr();
+                let block = self.parse_block(&[TokenType::Elsif, TokenType::Else, TokenType::End]);
+                branches.push((cond, block));
+            } else if self.eat(TokenType::Else) {
+                else_block = Some(self.parse_block(&[TokenType::End]));
+                break;
+            } else {
+                break;
+            }
+        }
+        self.expect_end(opener)?;
+        Ok(StmtKind::If { branches, else_block })
+    }
+
+    // Expressions, by precedence climbing over the table in `precedence`.
+    pub fn parse_expr(&mut self) -> PResult<Expr> {
+        self.parse_binary(Precedence::Implication)
+    }
+
+    /// Parses an expression in a specification, where `=>` is implication.
+    pub fn parse_spec_expr(&mut self) -> PResult<Expr> {
+        let outer = std::mem::replace(&mut self.in_spec, true);
+        let expr = self.parse_expr();
+        self.in_spec = outer;
+        expr
+    }
+
+    /// The binary operator at the current token, if it continues the expression.
+    fn peek_binary(&self) -> Option<&'static BinaryOperator> {
+        let operator = binary_operator(self.peek().ttype)?;
+        // Outside specifications `=>` separates match arms and lambda bodies.
+        if operator.op == BinaryOp::Implies && !self.in_spec {
+            return None;
+        }
+        self.same_line().then_some(operator)
+    }
+
+    /// Parses operands and every operator binding at least as tightly as `min`.
+    fn parse_binary(&mut self, min: Precedence) -> PResult<Expr> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(operator) = self.peek_binary().filter(|o| o.precedence >= min) {
+            let op_token = self.advance();
+            let rhs = match operator.assoc {
+                Assoc::Right => self.parse_binary(operator.precedence)?,
+                Assoc::Left | Assoc::None => self.parse_binary(operator.precedence.tighter())?,
+            };
+            if operator.assoc == Assoc::None
+                && let Some(next) = self.peek_binary().filter(|o| o.precedence == operator.precedence)
+            {
+                self.chained_comparison(op_token, next, &rhs);
+            }
+            lhs = Expr {
+                span: lhs.span.to(rhs.span),
+                kind: ExprKind::Binary {
+                    op: operator.op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+            };
+        }
+        Ok(lhs)
+    }
+
+    /// Reports `a < b < c`. Parsing carries on as if it grouped to the left.
+    fn chained_comparison(&mut self, first: &Token, second: &BinaryOperator, middle: &Expr) {
+        let token = self.peek();
+        let middle_text = middle.span.text(self.source);
+        self.diagnostics.push(
+            Diagnostic::error("comparison operators cannot be chained", token.span)
+                .with_label(first.span, "the first comparison ends here")
+                .with_suggestion(
+                    Suggestion::insert(
+                        "split the comparison into two",
+                        middle.span.end,
+                        format!(" && {middle_text}"),
+                    )
+                    .maybe_incorrect(),
+                )
+                .with_note(format!(
+                    "`{}` and `{}` do not associate, so one must be grouped explicitly",
+                    first.lexeme,
+                    second.op.symbol()
+                )),
+        );
+    }
+
+    fn parse_unary(&mut self) -> PResult<Expr> {
+        let Some(op) = prefix_operator(self.peek().ttype) else {
+            return self.parse_postfix();
+        };
+        let start = self.advance().span;
+        let expr = self.parse_unary()?;
+        Ok(Expr {
+            span: start.to(expr.span),
+            kind: ExprKind::Unary {
+                op,
+                expr: Box::new(expr),
+            },
+        })
+    }
+
+    fn parse_postfix(&mut self) -> PResult<Expr> {
+        let mut expr = self.parse_primary()?;
+        while self.same_line() {
+            if self.eat(TokenType::LParen) {
+                let args = self.parse_list(TokenType::RParen)?;
+                expr = Expr {
+                    span: self.span_from(expr.span),
+                    kind: ExprKind::Call {
+                        callee: Box::new(expr),
+                        args,
+                    },
+                };
+            } else if self.eat(TokenType::Dot) {
+                for field in self.parse_field_names()? {
+                    expr = Expr {
+                        span: expr.span.to(field.span),
+                        kind: ExprKind::Field {
+                            expr: Box::new(expr),
+                            field,
+                        },
+                    };
+                }
+            } else if self.eat(TokenType::LSqBrace) {
+                let index = self.parse_expr()?;
+                self.expect(TokenType::RSqBrace, "`]`")?;
+                expr = Expr {
+                    span: self.span_from(expr.span),
+                    kind: ExprKind::Index {
+                        expr: Box::new(expr),
+                        index: Box::new(index),
+                    },
+                };
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+
+    /// The field after a `.`: a name, or the position of a tuple element.
+    /// `t.0.1` lexes its positions as the float `0.1`, which is split up.
+    fn parse_field_names(&mut self) -> PResult<Vec<Ident>> {
+        let token = self.peek();
+        match token.ttype {
+            TokenType::Integer => {
+                self.advance();
+                Ok(vec![Ident {
+                    name: token.lexeme.to_owned(),
+                    span: token.span,
+                }])
+            }
+            TokenType::Float
+                if let Some((first, second)) = token.lexeme.split_once('.')
+                    && !first.is_empty()
+                    && !second.is_empty()
+                    && token.lexeme.bytes().all(|b| b.is_ascii_digit() || b == b'.') =>
+            {
+                self.advance();
+                let split = token.span.start + first.len();
+                Ok(vec![
+                    Ident {
+                        name: first.to_owned(),
+                        span: Span::new(token.span.start, split),
+                    },
+                    Ident {
+                        name: second.to_owned(),
+                        span: Span::new(split + 1, token.span.end),
+                    },
+                ])
+            }
+            _ => Ok(vec![self.expect_ident("a field name")?]),
+        }
+    }
+
+    /// Parses comma separated expressions up to and including `close`.
+    fn parse_list(&mut self, close: TokenType) -> PResult<Vec<Expr>> {
+        let mut items = Vec::new();
+        while !self.check(close) {
+            items.push(self.parse_expr()?);
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        self.expect(
+            close,
+            &format!("`,` or `{}`", if close == TokenType::RParen { ")" } else { "]" }),
+        )?;
+        Ok(items)
+    }
+
+    fn parse_primary(&mut self) -> PResult<Expr> {
+        let token = self.peek();
+        let kind = match token.ttype {
+            TokenType::Integer => match token.lexeme.parse() {
+                Ok(value) => ExprKind::Lit(Lit::Int(value)),
+                Err(_) => return Err(Diagnostic::error("integer literal is too large", token.span).into()),
+            },
+            TokenType::Float => ExprKind::Lit(Lit::Float(token.lexeme.parse().unwrap_or(f64::INFINITY))),
+            TokenType::Strings => ExprKind::Lit(Lit::Str(token.lexeme[1..token.lexeme.len() - 1].to_owned())),
+            TokenType::Identifier if token.lexeme == "true" || token.lexeme == "false" => {
+                ExprKind::Lit(Lit::Bool(token.lexeme == "true"))
+            }
+            TokenType::Identifier
+                if token.lexeme.starts_with(|c: char| c.is_uppercase())
+                    && self.peek_at(1).ttype == TokenType::LBrace
+                    && self.peek_at(1).line == token.line =>
+            {
+                return self.parse_struct_literal();
+            }
+            TokenType::Identifier => ExprKind::Ident(Ident {
+                name: token.lexeme.to_owned(),
+                span: token.span,
+            }),
+            TokenType::LParen if self.at_lambda() => return self.parse_lambda(),
+            TokenType::LParen => {
+                self.advance();
+                if self.eat(TokenType::RParen) {
+                    return Ok(Expr {
+                        kind: ExprKind::Tuple(Vec::new()),
+                        span: self.span_from(token.span),
+                    });
+                }
+                let mut inner = self.parse_expr()?;
+                if self.eat(TokenType::Comma) {
+                    let mut items = vec![inner];
+                    items.extend(self.parse_list(TokenType::RParen)?);
+                    return Ok(Expr {
+                        kind: ExprKind::Tuple(items),
+                        span: self.span_from(token.span),
+                    });
+                }
+                self.expect(TokenType::RParen, "`,` or `)`")?;
+                inner.span = self.span_from(token.span);
+                return Ok(inner);
+            }
+            TokenType::LSqBrace => {
+                self.advance();
+                let items = self.parse_list(TokenType::RSqBrace)?;
+                return Ok(Expr {
+                    kind: ExprKind::List(items),
+                    span: self.span_from(token.span),
+                });
+            }
+            TokenType::Match => return self.parse_match(),
+            TokenType::Question => {
+                self.advance();
+                // `?name` is one hole; `? name` is a hole and a stray name.
+                let next = self.peek();
+                let name = (next.ttype == TokenType::Identifier && next.span.start == token.span.end).then(|| {
+                    self.advance();
+                    Ident {
+                        name: next.lexeme.to_owned(),
+                        span: next.span,
+                    }
+                });
+                return Ok(Expr {
+                    kind: ExprKind::Hole(name),
+                    span: self.span_from(token.span),
+                });
+            }
+            _ => return Err(self.error_here("an expression")),
+        };
+        self.advance();
+        Ok(Expr { kind, span: token.span })
+    }
+
+    /// Whether the `(` here opens the parameters of a lambda: its `)` is
+    /// followed by `=>`. In a specification `=>` is implication instead.
+    fn at_lambda(&self) -> bool {
+        if self.in_spec {
+            return false;
+        }
+        let mut depth = 0;
+        for (i, token) in self.tokens[self.pos..].iter().enumerate() {
+            match token.ttype {
+                TokenType::LParen => depth += 1,
+                TokenType::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return self.peek_at(i + 1).ttype == TokenType::FatArrow;
+                    }
+                }
+                TokenType::Eof => break,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// `(params) => body`, where the body takes in everything it can.
+    fn parse_lambda(&mut self) -> PResult<Expr> {
+        let start = self.advance().span;
+        let mut params = Vec::new();
+        while !self.check(TokenType::RParen) {
+            params.push(self.parse_param()?);
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenType::RParen, "`,` or `)`")?;
+        self.expect(TokenType::FatArrow, "`=>`")?;
+        let body = self.parse_expr()?;
+        Ok(Expr {
+            span: start.to(body.span),
+            kind: ExprKind::Lambda {
+                params,
+                body: Box::new(body),
+            },
+        })
+    }
+
+    /// `Name { field: value, field, ..base }`, which may span several lines.
+    fn parse_struct_literal(&mut self) -> PResult<Expr> {
+        let name = self.expect_ident("a struct name")?;
+        self.advance();
+        let mut fields = Vec::new();
+        let mut base = None;
+        while !self.check(TokenType::RBrace) {
+            if self.eat(TokenType::DotDot) {
+                base = Some(Box::new(self.parse_expr()?));
+                break;
+            }
+            let field = self.expect_ident("a field name")?;
+            let value = if self.eat(TokenType::Colon) {
+                self.parse_expr()?
+            } else {
+                Expr {
+                    kind: ExprKind::Ident(field.clone()),
+                    span: field.span,
+                }
+            };
+            fields.push(FieldInit {
+                span: self.span_from(field.span),
+                name: field,
+                value,
+            });
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        let expected = if base.is_some() { "`}`" } else { "`,` or `}`" };
+        self.expect(TokenType::RBrace, expected)?;
+        Ok(Expr {
+            span: self.span_from(name.span),
+            kind: ExprKind::Struct { name, fields, base },
+        })
+    }
+
+    fn parse_match(&mut self) -> PResult<Expr> {
+        let opener = self.advance();
+        let scrutinee = self.parse_header_expr();
+        let mut arms = Vec::new();
+        while !matches!(self.peek().ttype, TokenType::End | TokenType::Eof | TokenType::Func) {
+            let before = self.pos;
+            let start = self.peek().span;
+            match self.parse_arm() {
+                Ok(arm) => arms.push(arm),
+                Err(diagnostic) => {
+                    self.diagnostics.push(*diagnostic);
+                    self.skip_line();
+                    if self.pos == before {
+                        self.advance();
+                    }
+                    let span = self.span_from(start);
+                    arms.push(MatchArm {
+                        pattern: Pattern {
+                            kind: PatternKind::Error,
+                            span,
+                        },
+                        guard: None,
+                        body: Expr {
+                            kind: ExprKind::Error,
+                            span,
+                        },
+                        span,
+                    });
+                }
+            }
+        }
+        self.expect_end(opener)?;
+        Ok(Expr {
+            span: self.span_from(opener.span),
+            kind: ExprKind::Match {
+                scrutinee: Box::new(scrutinee),
+                arms,
+            },
+        })
+    }
+
+    fn parse_arm(&mut self) -> PResult<MatchArm> {
+        let pattern = self.parse_pattern()?;
+        let guard = if self.eat(TokenType::If) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        self.expect(TokenType::FatArrow, "`=>`")?;
+        let body = self.parse_expr()?;
+        if self.same_line() && !matches!(self.peek().ttype, TokenType::End | TokenType::Eof) {
+            return Err(self.error_here("the end of the match arm"));
+        }
+        Ok(MatchArm {
+            span: pattern.span.to(body.span),
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    /// Parses a pattern and any `|` alternatives following it on the line.
+    fn parse_pattern(&mut self) -> PResult<Pattern> {
+        let first = self.parse_single_pattern()?;
+        if !(self.same_line() && self.check(TokenType::Pipe)) {
+            return Ok(first);
+        }
+        let mut alternatives = vec![first];
+        while self.same_line() && self.eat(TokenType::Pipe) {
+            alternatives.push(self.parse_single_pattern()?);
+        }
+        Ok(Pattern {
+            span: alternatives[0].span.to(self.previous().span),
+            kind: PatternKind::Or(alternatives),
+        })
+    }
+
+    fn parse_single_pattern(&mut self) -> PResult<Pattern> {
+        let token = self.peek();
+        let kind = match token.ttype {
+            TokenType::Identifier if token.lexeme == "_" => PatternKind::Wildcard,
+            TokenType::Identifier if token.lexeme == "true" || token.lexeme == "false" => {
+                PatternKind::Lit(Lit::Bool(token.lexeme == "true"))
+            }
+            TokenType::Identifier if token.lexeme.starts_with(|c: char| c.is_uppercase()) => {
+                let name = self.expect_ident("a variant")?;
+                let mut args = Vec::new();
+                if self.same_line() && self.eat(TokenType::LParen) {
+                    while !self.check(TokenType::RParen) {
+                        args.push(self.parse_pattern()?);
+                        if !self.eat(TokenType::Comma) {
+                            break;
+                        }
+                    }
+                    self.expect(TokenType::RParen, "`,` or `)`")?;
+                }
+                return Ok(Pattern {
+                    span: self.span_from(name.span),
+                    kind: PatternKind::Variant { name, args },
+                });
+            }
+            TokenType::Identifier => PatternKind::Binding(Ident {
+                name: token.lexeme.to_owned(),
+                span: token.span,
+            }),
+            TokenType::Integer | TokenType::Float | TokenType::Strings | TokenType::Minus => {
+                let expr = self.parse_unary()?;
+                return match expr.kind {
+                    ExprKind::Lit(lit) => Ok(Pattern {
+                        kind: PatternKind::Lit(lit),
+                        span: expr.span,
+                    }),
+                    ExprKind::Unary {
+                        op: UnaryOp::Neg,
+                        expr: inner,
+                    } => match inner.kind {
+                        ExprKind::Lit(Lit::Int(value)) => Ok(Pattern {
+                            kind: PatternKind::Lit(Lit::Int(-value)),
+                            span: expr.span,
+                        }),
+                        ExprKind::Lit(Lit::Float(value)) => Ok(Pattern {
+                            kind: PatternKind::Lit(Lit::Float(-value)),
+                            span: expr.span,
+                        }),
+                        _ => Err(Diagnostic::error("expected a literal pattern", expr.span).into()),
+                    },
+                    _ => Err(Diagnostic::error("expected a literal pattern", expr.span).into()),
+                };
+            }
+            _ => return Err(self.error_here("a pattern")),
+        };
+        self.advance();
+        Ok(Pattern { kind, span: token.span })
+    }
+}
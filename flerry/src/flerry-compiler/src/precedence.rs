@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! How tightly each operator binds. This is the only place operator
+//! precedence and associativity are defined; the parser and the pretty
+//! printer both read it from here.
+//!
+//! From the loosest binding to the tightest:
+//!
+//! | Precedence       | Operators                        | Associativity     |
+//! |------------------|----------------------------------|-------------------|
+//! | `Implication`    | `=>` (specifications only)       | right             |
+//! | `Or`             | `\|\|`                           | left              |
+//! | `And`            | `&&`                             | left              |
+//! | `Comparison`     | `==` `!=` `<` `<=` `>` `>=`      | none              |
+//! | `BitOr`          | `\|`                             | left              |
+//! | `BitAnd`         | `&`                              | left              |
+//! | `Shift`          | `<<` `>>`                        | left              |
+//! | `Additive`       | `+` `-`                          | left              |
+//! | `Multiplicative` | `*` `/` `%`                      | left              |
+//! | `Unary`          | prefix `-` `!`                   | prefix            |
+//! | `Postfix`        | calls, `.field`, `[index]`       | postfix           |
+//!
+//! The bitwise operators bind tighter than comparisons, so `x & 1 == 0`
+//! tests a bit rather than comparing `1 == 0` with `x`.
+//!
+//! Comparisons do not associate: `a < b < c` is an error rather than
+//! silently comparing a boolean with `c`.
+
+use crate::ast::{BinaryOp, UnaryOp};
+use flerry_core::TokenType;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Implication,
+    Or,
+    And,
+    Comparison,
+    BitOr,
+    BitAnd,
+    Shift,
+    Additive,
+    Multiplicative,
+    Unary,
+    Postfix,
+}
+
+impl Precedence {
+    /// The next tighter precedence.
+    pub fn tighter(self) -> Precedence {
+        match self {
+            Precedence::Implication => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Comparison,
+            Precedence::Comparison => Precedence::BitOr,
+            Precedence::BitOr => Precedence::BitAnd,
+            Precedence::BitAnd => Precedence::Shift,
+            Precedence::Shift => Precedence::Additive,
+            Precedence::Additive => Precedence::Multiplicative,
+            Precedence::Multiplicative => Precedence::Unary,
+            Precedence::Unary | Precedence::Postfix => Precedence::Postfix,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+    /// Chaining two operators of this precedence is an error.
+    None,
+}
+
+pub struct BinaryOperator {
+    pub token: TokenType,
+    pub op: BinaryOp,
+    pub precedence: Precedence,
+    pub assoc: Assoc,
+}
+
+const fn binary(token: TokenType, op: BinaryOp, precedence: Precedence, assoc: Assoc) -> BinaryOperator {
+    BinaryOperator {
+        token,
+        op,
+        precedence,
+        assoc,
+    }
+}
+
+pub const BINARY_OPERATORS: &[BinaryOperator] = &[
+    binary(
+        TokenType::FatArrow,
+        BinaryOp::Implies,
+        Precedence::Implication,
+        Assoc::Right,
+    ),
+    binary(TokenType::PipePipe, BinaryOp::Or, Precedence::Or, Assoc::Left),
+    binary(TokenType::AmprAmprSand, BinaryOp::And, Precedence::And, Assoc::Left),
+    binary(TokenType::EqualEqual, BinaryOp::Eq, Precedence::Comparison, Assoc::None),
+    binary(TokenType::BangEqual, BinaryOp::Ne, Precedence::Comparison, Assoc::None),
+    binary(TokenType::Less, BinaryOp::Lt, Precedence::Comparison, Assoc::None),
+    binary(TokenType::LessEqual, BinaryOp::Le, Precedence::Comparison, Assoc::None),
+    binary(TokenType::Greater, BinaryOp::Gt, Precedence::Comparison, Assoc::None),
+    binary(
+        TokenType::GreaterEqual,
+        BinaryOp::Ge,
+        Precedence::Comparison,
+        Assoc::None,
+    ),
+    binary(TokenType::Pipe, BinaryOp::BitOr, Precedence::BitOr, Assoc::Left),
+    binary(TokenType::AmprSand, BinaryOp::BitAnd, Precedence::BitAnd, Assoc::Left),
+    binary(TokenType::LessLess, BinaryOp::Shl, Precedence::Shift, Assoc::Left),
+    binary(TokenType::GreaterGreater, BinaryOp::Shr, Precedence::Shift, Assoc::Left),
+    binary(TokenType::Plus, BinaryOp::Add, Precedence::Additive, Assoc::Left),
+    binary(TokenType::Minus, BinaryOp::Sub, Precedence::Additive, Assoc::Left),
+    binary(TokenType::Star, BinaryOp::Mul, Precedence::Multiplicative, Assoc::Left),
+    binary(TokenType::Slash, BinaryOp::Div, Precedence::Multiplicative, Assoc::Left),
+    binary(
+        TokenType::Percent,
+        BinaryOp::Rem,
+        Precedence::Multiplicative,
+        Assoc::Left,
+    ),
+];
+
+pub const PREFIX_OPERATORS: &[(TokenType, UnaryOp)] =
+    &[(TokenType::Minus, UnaryOp::Neg), (TokenType::Bang, UnaryOp::Not)];
+
+pub fn binary_operator(token: TokenType) -> Option<&'static BinaryOperator> {
+    BINARY_OPERATORS.iter().find(|operator| operator.token == token)
+}
+
+pub fn prefix_operator(token: TokenType) -> Option<UnaryOp> {
+    PREFIX_OPERATORS.iter().find(|(t, _)| *t == token).map(|(_, op)| *op)
+}
+
+impl BinaryOp {
+    fn operator(self) -> &'static BinaryOperator {
+        BINARY_OPERATORS
+            .iter()
+            .find(|operator| operator.op == self)
+            .expect("every binary operator is in the table")
+    }
+
+    pub fn precedence(self) -> Precedence {
+        self.operator().precedence
+    }
+
+    pub fn assoc(self) -> Assoc {
+        self.operator().assoc
+    }
+}
@@ -0,0 +1,500 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Printing the AST back out as source text.
+//!
+//! `module` prints canonical source: one statement per line, four spaces of
+//! indentation per block, and only the parentheses the grammar needs.
+//! Parsing what it prints gives back the same tree.
+
+use crate::ast::*;
+use crate::precedence::Assoc;
+
+const INDENT: &str = "    ";
+
+/// Prints `module` as canonical Flerry source.
+pub fn module(module: &Module) -> String {
+    let mut printer = Printer::default();
+    for (i, item) in module.items.iter().enumerate() {
+        if i > 0 {
+            printer.out.push('\n');
+        }
+        printer.item(item);
+    }
+    printer.out
+}
+
+/// Prints `expr` as canonical Flerry source, as it would appear at the start
+/// of a line with no indentation.
+pub fn expr(expr: &Expr) -> String {
+    let mut printer = Printer::default();
+    printer.expr(expr);
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        self.indent();
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn indent(&mut self) {
+        self.out.push_str(&INDENT.repeat(self.depth));
+    }
+
+    fn item(&mut self, item: &Item) {
+        match &item.kind {
+            ItemKind::Import(name) => self.line(&format!("import {}", name.name)),
+            ItemKind::Func(func) => {
+                let params: Vec<String> = func.params.iter().map(param).collect();
+                let ret = func
+                    .ret
+                    .as_ref()
+                    .map(|ret| format!(" -> {}", type_expr(ret)))
+                    .unwrap_or_default();
+                self.line(&format!("func {}({}){ret} =", func.name.name, params.join(", ")));
+                self.contracts(&func.contracts);
+                self.block(&func.body);
+                self.line("end");
+            }
+            ItemKind::Struct(def) => {
+                let fields: Vec<String> = def.fields.iter().map(field).collect();
+                self.line(&format!("struct {} = {} end", def.name.name, fields.join(", ")));
+            }
+            ItemKind::Enum(def) => {
+                let variants: Vec<String> = def
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let mut out = variant.name.name.clone();
+                        if !variant.fields.is_empty() {
+                            let fields: Vec<String> = variant.fields.iter().map(field).collect();
+                            out.push_str(&format!("({})", fields.join(", ")));
+                        }
+                        if let Some(ret) = &variant.ret {
+                            out.push_str(&format!(" -> {}", type_expr(ret)));
+                        }
+                        out
+                    })
+                    .collect();
+                let params = if def.params.is_empty() {
+                    String::new()
+                } else {
+                    let params: Vec<String> = def.params.iter().map(param).collect();
+                    format!("({})", params.join(", "))
+                };
+                self.line(&format!(
+                    "enum {}{params} = {} end",
+                    def.name.name,
+                    variants.join(" | ")
+                ));
+            }
+            ItemKind::Type(alias) => {
+                let params = if alias.params.is_empty() {
+                    String::new()
+                } else {
+                    let params: Vec<&str> = alias.params.iter().map(|param| param.name.as_str()).collect();
+                    format!("({})", params.join(", "))
+                };
+                let refinement = match &alias.refinement {
+                    Some(refinement) => format!(" where {}", expr(refinement)),
+                    None => String::new(),
+                };
+                self.line(&format!(
+                    "type {}{params} = {}{refinement}",
+                    alias.name.name,
+                    type_expr(&alias.ty)
+                ));
+            }
+            ItemKind::Error => self.line("<error>"),
+        }
+    }
+
+    /// The clauses opening a body, indented as its statements.
+    fn contracts(&mut self, contracts: &[Contract]) {
+        self.depth += 1;
+        for contract in contracts {
+            self.line(&format!("{} {}", contract.kind.keyword(), expr(&contract.cond)));
+        }
+        self.depth -= 1;
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.depth += 1;
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        self.depth -= 1;
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        self.indent();
+        match &stmt.kind {
+            StmtKind::Assign { target, ty, value } => {
+                self.expr(target);
+                if let Some(ty) = ty {
+                    self.out.push_str(&format!(": {}", type_expr(ty)));
+                }
+                self.out.push_str(" = ");
+                self.expr(value);
+            }
+            StmtKind::Expr(expr) => self.expr(expr),
+            StmtKind::Return(None) => self.out.push_str("return"),
+            StmtKind::Return(Some(value)) => {
+                self.out.push_str("return ");
+                self.expr(value);
+            }
+            StmtKind::If { branches, else_block } => {
+                for (i, (cond, block)) in branches.iter().enumerate() {
+                    if i > 0 {
+                        self.indent();
+                    }
+                    self.out.push_str(if i == 0 { "if " } else { "elsif " });
+                    self.expr(cond);
+                    self.out.push('\n');
+                    self.block(block);
+                }
+                if let Some(block) = else_block {
+                    self.line("else");
+                    self.block(block);
+                }
+                self.indent();
+                self.out.push_str("end");
+            }
+            StmtKind::While { cond, contracts, body } => {
+                self.out.push_str("while ");
+                self.expr(cond);
+                self.out.push('\n');
+                self.contracts(contracts);
+                self.block(body);
+                self.indent();
+                self.out.push_str("end");
+            }
+            StmtKind::For {
+                var,
+                iter,
+                contracts,
+                body,
+            } => {
+                self.out.push_str(&format!("for {} in ", var.name));
+                self.expr(iter);
+                self.out.push('\n');
+                self.contracts(contracts);
+                self.block(body);
+                self.indent();
+                self.out.push_str("end");
+            }
+            StmtKind::Error => self.out.push_str("<error>"),
+        }
+        self.out.push('\n');
+    }
+
+    /// Prints `expr` where the cursor is; a `match` spans several lines,
+    /// indented from the current block.
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Lit(lit) => self.out.push_str(&literal(lit)),
+            ExprKind::Ident(name) => self.out.push_str(&name.name),
+            ExprKind::Hole(name) => self.out.push_str(&hole(name)),
+            ExprKind::Error => self.out.push_str("<error>"),
+            ExprKind::List(items) => {
+                self.out.push('[');
+                self.list(items);
+                self.out.push(']');
+            }
+            ExprKind::Tuple(items) => {
+                self.out.push('(');
+                self.list(items);
+                if items.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            ExprKind::Unary { op, expr } => {
+                self.out.push_str(op.symbol());
+                self.operand(
+                    expr,
+                    matches!(expr.kind, ExprKind::Binary { .. } | ExprKind::Lambda { .. }),
+                );
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let (precedence, assoc) = (op.precedence(), op.assoc());
+                let needs_parens = |operand: &Expr, side: Assoc| match &operand.kind {
+                    ExprKind::Binary { op, .. } => {
+                        op.precedence() < precedence || op.precedence() == precedence && assoc != side
+                    }
+                    // A lambda's body would take in the rest of the expression.
+                    ExprKind::Lambda { .. } => true,
+                    _ => false,
+                };
+                self.operand(lhs, needs_parens(lhs, Assoc::Left));
+                self.out.push_str(&format!(" {} ", op.symbol()));
+                self.operand(rhs, needs_parens(rhs, Assoc::Right));
+            }
+            ExprKind::Call { callee, args } => {
+                self.receiver(callee);
+                self.out.push('(');
+                self.list(args);
+                self.out.push(')');
+            }
+            ExprKind::Field { expr, field } => {
+                self.receiver(expr);
+                self.out.push('.');
+                self.out.push_str(&field.name);
+            }
+            ExprKind::Index { expr, index } => {
+                self.receiver(expr);
+                self.out.push('[');
+                self.expr(index);
+                self.out.push(']');
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.out.push_str("match ");
+                self.expr(scrutinee);
+                self.out.push('\n');
+                self.depth += 1;
+                for arm in arms {
+                    self.indent();
+                    self.out.push_str(&pattern(&arm.pattern));
+                    if let Some(guard) = &arm.guard {
+                        self.out.push_str(" if ");
+                        self.expr(guard);
+                    }
+                    self.out.push_str(" => ");
+                    self.expr(&arm.body);
+                    self.out.push('\n');
+                }
+                self.depth -= 1;
+                self.indent();
+                self.out.push_str("end");
+            }
+            ExprKind::Lambda { params, body } => {
+                let params: Vec<String> = params.iter().map(param).collect();
+                self.out.push_str(&format!("({}) => ", params.join(", ")));
+                self.expr(body);
+            }
+            ExprKind::Closure { func, captures } => {
+                self.out
+                    .push_str(&format!("closure {}[{}]", func.name, self::captures(captures)));
+            }
+            ExprKind::Struct { name, fields, base } => {
+                self.out.push_str(&name.name);
+                self.out.push_str(" { ");
+                for (i, init) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(&init.name.name);
+                    if !is_shorthand(init) {
+                        self.out.push_str(": ");
+                        self.expr(&init.value);
+                    }
+                }
+                if let Some(base) = base {
+                    if !fields.is_empty() {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str("..");
+                    self.expr(base);
+                }
+                self.out.push_str(" }");
+            }
+        }
+    }
+
+    fn operand(&mut self, expr: &Expr, parenthesize: bool) {
+        if parenthesize {
+            self.out.push('(');
+            self.expr(expr);
+            self.out.push(')');
+        } else {
+            self.expr(expr);
+        }
+    }
+
+    /// The expression a call, field access or index applies to: postfix
+    /// operators bind tighter than any prefix or binary one.
+    fn receiver(&mut self, expr: &Expr) {
+        self.operand(
+            expr,
+            matches!(
+                expr.kind,
+                ExprKind::Unary { .. } | ExprKind::Binary { .. } | ExprKind::Lambda { .. }
+            ),
+        );
+    }
+
+    fn list(&mut self, items: &[Expr]) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(item);
+        }
+    }
+}
+
+fn param(param: &Param) -> String {
+    let text = match &param.ty {
+        Some(ty) => format!("{}: {}", param.name.name, type_expr(ty)),
+        None => param.name.name.clone(),
+    };
+    match param.implicit {
+        true => format!("{{{text}}}"),
+        false => text,
+    }
+}
+
+fn captures(captures: &[Capture]) -> String {
+    let captures: Vec<String> = captures
+        .iter()
+        .map(|capture| match capture.mode {
+            CaptureMode::Value => capture.name.name.clone(),
+            CaptureMode::Ref => format!("ref {}", capture.name.name),
+        })
+        .collect();
+    captures.join(", ")
+}
+
+/// Whether `init` is written `name` rather than `name: name`.
+fn is_shorthand(init: &FieldInit) -> bool {
+    matches!(&init.value.kind, ExprKind::Ident(value) if value.name == init.name.name)
+}
+
+fn field(field: &Field) -> String {
+    let text = match &field.ty {
+        Some(ty) => format!("{}: {}", field.name.name, type_expr(ty)),
+        None => field.name.name.clone(),
+    };
+    match field.implicit {
+        true => format!("{{{text}}}"),
+        false => text,
+    }
+}
+
+pub fn type_expr(ty: &TypeExpr) -> String {
+    match &ty.kind {
+        TypeExprKind::Named { name, args } if args.is_empty() => name.name.clone(),
+        TypeExprKind::Named { name, args } => {
+            let args: Vec<String> = args.iter().map(type_expr).collect();
+            format!("{}({})", name.name, args.join(", "))
+        }
+        // A lone parameter needs no parentheses unless it is a function or a
+        // tuple, whose own parentheses would read as the parameter list.
+        TypeExprKind::Function { params, ret } => match params.as_slice() {
+            [param] if matches!(param.kind, TypeExprKind::Named { .. }) => {
+                format!("{} -> {}", type_expr(param), type_expr(ret))
+            }
+            params => {
+                let params: Vec<String> = params.iter().map(type_expr).collect();
+                format!("({}) -> {}", params.join(", "), type_expr(ret))
+            }
+        },
+        TypeExprKind::Tuple(elements) => {
+            let elements: Vec<String> = elements.iter().map(type_expr).collect();
+            format!("({})", elements.join(", "))
+        }
+        TypeExprKind::Bind { name, ty } => format!("{}: {}", name.name, type_expr(ty)),
+        TypeExprKind::Expr(value) => expr(value),
+    }
+}
+
+/// Prints `expr` on one line with every operator application wrapped in
+/// parentheses, so the grouping the parser chose can be read off directly:
+/// `a + b * c` prints as `(a + (b * c))`.
+pub fn parenthesized(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Lit(lit) => literal(lit),
+        ExprKind::Ident(name) => name.name.clone(),
+        ExprKind::Hole(name) => hole(name),
+        ExprKind::Error => "<error>".to_owned(),
+        ExprKind::List(items) => format!("[{}]", parenthesized_list(items)),
+        ExprKind::Tuple(items) if items.len() == 1 => format!("({},)", parenthesized(&items[0])),
+        ExprKind::Tuple(items) => format!("({})", parenthesized_list(items)),
+        ExprKind::Unary { op, expr } => format!("({}{})", op.symbol(), parenthesized(expr)),
+        ExprKind::Binary { op, lhs, rhs } => {
+            format!("({} {} {})", parenthesized(lhs), op.symbol(), parenthesized(rhs))
+        }
+        ExprKind::Call { callee, args } => format!("{}({})", parenthesized(callee), parenthesized_list(args)),
+        ExprKind::Field { expr, field } => format!("{}.{}", parenthesized(expr), field.name),
+        ExprKind::Index { expr, index } => format!("{}[{}]", parenthesized(expr), parenthesized(index)),
+        ExprKind::Match { scrutinee, arms } => {
+            let arms: Vec<String> = arms
+                .iter()
+                .map(|arm| match &arm.guard {
+                    Some(guard) => format!(
+                        "{} if {} => {}",
+                        pattern(&arm.pattern),
+                        parenthesized(guard),
+                        parenthesized(&arm.body)
+                    ),
+                    None => format!("{} => {}", pattern(&arm.pattern), parenthesized(&arm.body)),
+                })
+                .collect();
+            format!("(match {} {{ {} }})", parenthesized(scrutinee), arms.join(", "))
+        }
+        ExprKind::Struct { name, fields, base } => {
+            let mut inits: Vec<String> = fields
+                .iter()
+                .map(|init| format!("{}: {}", init.name.name, parenthesized(&init.value)))
+                .collect();
+            if let Some(base) = base {
+                inits.push(format!("..{}", parenthesized(base)));
+            }
+            format!("{} {{ {} }}", name.name, inits.join(", "))
+        }
+        ExprKind::Lambda { params, body } => {
+            let params: Vec<String> = params.iter().map(param).collect();
+            format!("(({}) => {})", params.join(", "), parenthesized(body))
+        }
+        ExprKind::Closure { func, captures } => format!("closure {}[{}]", func.name, self::captures(captures)),
+    }
+}
+
+fn parenthesized_list(items: &[Expr]) -> String {
+    items.iter().map(parenthesized).collect::<Vec<_>>().join(", ")
+}
+
+pub fn literal(lit: &Lit) -> String {
+    match lit {
+        Lit::Int(value) => value.to_string(),
+        // `Display` never uses an exponent, which the lexer does not read.
+        Lit::Float(value) if value.fract() == 0.0 => format!("{value}.0"),
+        Lit::Float(value) => value.to_string(),
+        Lit::Str(value) => format!("\"{value}\""),
+        Lit::Bool(value) => value.to_string(),
+    }
+}
+
+/// `?` or `?name`.
+pub fn hole(name: &Option<Ident>) -> String {
+    match name {
+        Some(name) => format!("?{}", name.name),
+        None => "?".to_owned(),
+    }
+}
+
+pub fn pattern(pattern: &Pattern) -> String {
+    match &pattern.kind {
+        PatternKind::Wildcard => "_".to_owned(),
+        PatternKind::Binding(name) => name.name.clone(),
+        PatternKind::Lit(lit) => literal(lit),
+        PatternKind::Variant { name, args } if args.is_empty() => name.name.clone(),
+        PatternKind::Variant { name, args } => {
+            let args: Vec<String> = args.iter().map(self::pattern).collect();
+            format!("{}({})", name.name, args.join(", "))
+        }
+        PatternKind::Or(alternatives) => {
+            let alternatives: Vec<String> = alternatives.iter().map(self::pattern).collect();
+            alternatives.join(" | ")
+        }
+        PatternKind::Error => "<error>".to_owned(),
+    }
+}
@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Reports statements that follow a `return` in the same block.
+
+use crate::ast::*;
+use crate::lint::UNREACHABLE_CODE;
+use crate::visit::{Visitor, walk_block};
+use flerry_core::{Diagnostic, Span};
+
+pub fn unreachable_code(module: &Module) -> Vec<Diagnostic> {
+    let mut checker = Reachability {
+        diagnostics: Vec::new(),
+    };
+    checker.visit_module(module);
+    checker.diagnostics
+}
+
+struct Reachability {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Visitor<'_> for Reachability {
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+
+        let Some(ret) = block.stmts.iter().position(|s| matches!(s.kind, StmtKind::Return(_))) else {
+            return;
+        };
+        let dead = &block.stmts[ret + 1..];
+        let (Some(first), Some(last)) = (dead.first(), dead.last()) else {
+            return;
+        };
+        let keyword = Span::new(
+            block.stmts[ret].span.start,
+            block.stmts[ret].span.start + "return".len(),
+        );
+        self.diagnostics.push(
+            Diagnostic::warning("unreachable statement", first.span.to(last.span))
+                .with_label(keyword, "any code following this `return` is unreachable")
+                .with_lint(UNREACHABLE_CODE.name),
+        );
+    }
+}
@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Refinement types: `type Pos = Int where self > 0` is an `Int` that is
+//! positive, and `type Index(n) = Nat where self < n` a natural number
+//! below the `n` each use supplies.
+//!
+//! The type checkers see only the base type. What the condition adds is
+//! proved by `verify`, which asks this module what a value of a type is
+//! known to satisfy. A refinement may refine another, adding its condition
+//! to the base's; a `Nat` is an `Int` that is not negative. A refinement
+//! of a fixed-width integer, `type Byte = u16 where self < 256`, is
+//! reasoned about at its width.
+
+use crate::ast::*;
+use crate::pretty::{self, type_expr};
+use crate::types::fixed_width;
+use flerry_core::{Diagnostic, Span};
+use flerry_prover::{BitOp, Bits, Cmp, Sort, Term};
+use std::collections::HashMap;
+
+/// The type aliases of a module, for the conditions of refinement types.
+pub struct Refinements<'m> {
+    aliases: HashMap<&'m str, &'m TypeAlias>,
+}
+
+/// What a value of a type satisfies.
+pub struct Condition<'m> {
+    /// The condition about the value, with the arguments of the type in
+    /// place of its parameters.
+    pub term: Term,
+    /// The outermost refinement type the condition comes from; `None` for
+    /// `Nat` on its own.
+    pub refinement: Option<&'m TypeAlias>,
+}
+
+/// Why a condition could not be written as a formula.
+pub enum Unsupported {
+    /// A name not in scope, already reported.
+    Reported,
+    /// An expression the solver cannot reason about.
+    Expr(Span),
+    /// An operand of the wrong sort.
+    Mismatch { span: Span, expected: Sort, found: Sort },
+}
+
+impl<'m> Refinements<'m> {
+    pub fn new(module: &'m Module) -> Refinements<'m> {
+        let aliases = module
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Type(alias) => Some((alias.name.name.as_str(), alias)),
+                _ => None,
+            })
+            .collect();
+        Refinements { aliases }
+    }
+
+    /// The type alias called `name`.
+    pub fn alias(&self, name: &str) -> Option<&'m TypeAlias> {
+        self.aliases.get(name).copied()
+    }
+
+    /// Whether any type has a condition to prove.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.values().all(|alias| alias.refinement.is_none())
+    }
+
+    /// The sort of values of `ty`, for the types the solver reasons about.
+    pub fn sort(&self, ty: &TypeExpr) -> Option<Sort> {
+        self.sort_in(ty, &mut Vec::new())
+    }
+
+    fn sort_in(&self, ty: &TypeExpr, expanding: &mut Vec<&'m str>) -> Option<Sort> {
+        let TypeExprKind::Named { name, .. } = &ty.kind else {
+            return None;
+        };
+        match name.name.as_str() {
+            "Int" | "Nat" => Some(Sort::Int),
+            "Bool" => Some(Sort::Bool),
+            name if let Some((width, signed)) = fixed_width(name) => Some(Sort::Bits { width, signed }),
+            name => {
+                let (name, alias) = self.aliases.get_key_value(name)?;
+                if expanding.contains(name) {
+                    return None;
+                }
+                expanding.push(name);
+                let sort = self.sort_in(&alias.ty, expanding);
+                expanding.pop();
+                sort
+            }
+        }
+    }
+
+    /// What `value` satisfies as a value of `ty`, the names in the
+    /// arguments of the type standing for the terms in `scope`. `None` if
+    /// the type says nothing the solver can use.
+    pub fn condition(&self, ty: &TypeExpr, value: &Term, scope: &HashMap<String, Term>) -> Option<Condition<'m>> {
+        let mut parts = Vec::new();
+        let refinement = self.condition_in(ty, value, scope, &mut parts, &mut Vec::new());
+        let term = match parts.len() {
+            0 => return None,
+            1 => parts.remove(0),
+            _ => Term::And(parts),
+        };
+        Some(Condition { term, refinement })
+    }
+
+    /// Adds the conditions of `ty` to `parts`, innermost base first,
+    /// returning the outermost refinement.
+    fn condition_in(
+        &self,
+        ty: &TypeExpr,
+        value: &Term,
+        scope: &HashMap<String, Term>,
+        parts: &mut Vec<Term>,
+        expanding: &mut Vec<&'m str>,
+    ) -> Option<&'m TypeAlias> {
+        let TypeExprKind::Named { name, args } = &ty.kind else {
+            return None;
+        };
+        if name.name == "Nat" && args.is_empty() {
+            parts.push(Term::Cmp(Cmp::Ge, Box::new(value.clone()), Box::new(Term::Int(0))));
+            return None;
+        }
+        let (name, alias) = self.aliases.get_key_value(name.name.as_str())?;
+        if expanding.contains(name) {
+            return None;
+        }
+        let mut inner = HashMap::from([("self".to_owned(), value.clone())]);
+        for (param, arg) in alias.params.iter().zip(args) {
+            inner.insert(param.name.clone(), argument(arg, scope));
+        }
+        expanding.push(name);
+        let base = self.condition_in(&alias.ty, value, &inner, parts, expanding);
+        expanding.pop();
+        match &alias.refinement {
+            Some(refinement) => {
+                // Conditions that cannot be written as formulas are reported
+                // with the declaration.
+                if let Ok(term) = term(refinement, &inner) {
+                    parts.push(term);
+                }
+                Some(alias)
+            }
+            None => base,
+        }
+    }
+
+    /// Reports refinement types the solver cannot reason about.
+    pub fn check(&self, module: &Module) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for item in &module.items {
+            let ItemKind::Type(alias) = &item.kind else {
+                continue;
+            };
+            let Some(refinement) = &alias.refinement else {
+                if let (Some(first), Some(last)) = (alias.params.first(), alias.params.last()) {
+                    diagnostics.push(
+                        Diagnostic::error("only a refinement type can take parameters", first.span.to(last.span))
+                            .with_label(first.span.to(last.span), "no condition refers to these")
+                            .with_help("add a condition with `where`, or remove the parameters"),
+                    );
+                }
+                continue;
+            };
+            let Some(sort) = self.sort(&alias.ty) else {
+                let base = type_expr(&alias.ty);
+                diagnostics.push(
+                    Diagnostic::error(format!("`{base}` cannot be refined"), alias.ty.span)
+                        .with_label(alias.ty.span, "not a type a condition can be proved about")
+                        .with_note(
+                            "refinement types refine `Int`, `Nat`, `Bool`, a fixed-width integer or another \
+                             refinement type",
+                        ),
+                );
+                continue;
+            };
+            let mut scope = HashMap::from([("self".to_owned(), Term::var("self", sort))]);
+            for param in &alias.params {
+                scope.insert(param.name.clone(), Term::int_var(&param.name));
+            }
+            match term(refinement, &scope) {
+                Ok(term) if term.sort() == Sort::Bool => {}
+                Ok(_) => diagnostics.push(mismatch(refinement.span, Sort::Bool, Sort::Int)),
+                Err(Unsupported::Reported) => {}
+                Err(Unsupported::Expr(span)) => diagnostics.push(
+                    Diagnostic::error("this cannot be used in the condition of a refinement type", span)
+                        .with_label(span, "not something the solver can reason about")
+                        .with_note("conditions use arithmetic, comparisons and logic on `self` and the parameters"),
+                ),
+                Err(Unsupported::Mismatch { span, expected, found }) => {
+                    diagnostics.push(mismatch(span, expected, found))
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+fn mismatch(span: Span, expected: Sort, found: Sort) -> Diagnostic {
+    Diagnostic::error("mismatched types", span).with_label(span, format!("expected `{expected}`, found `{found}`"))
+}
+
+/// The term an argument of a refinement type stands for: a name in
+/// `scope` or an expression over them. Anything else is an unknown value,
+/// named after its text.
+fn argument(arg: &TypeExpr, scope: &HashMap<String, Term>) -> Term {
+    match &arg.kind {
+        TypeExprKind::Named { name, args } if args.is_empty() => scope
+            .get(&name.name)
+            .cloned()
+            .unwrap_or_else(|| Term::int_var(&name.name)),
+        TypeExprKind::Expr(expr) => term(expr, scope).unwrap_or_else(|_| Term::int_var(pretty::expr(expr))),
+        _ => Term::int_var(type_expr(arg)),
+    }
+}
+
+/// A condition as a formula, with the names in `scope` standing for their
+/// terms. The term is built as written, without folding constants, so it
+/// reads the same when shown in a diagnostic.
+pub fn term(expr: &Expr, scope: &HashMap<String, Term>) -> Result<Term, Unsupported> {
+    let sorted = |expr: &Expr, expected: Sort| {
+        let term = term(expr, scope)?;
+        if term.sort() != expected {
+            return Err(Unsupported::Mismatch {
+                span: expr.span,
+                expected,
+                found: term.sort(),
+            });
+        }
+        Ok(Box::new(term))
+    };
+    // Both operands of arithmetic or a comparison, of the same sort. An
+    // integer literal takes the sort of a fixed-width operand.
+    let operands = |lhs: &Expr, rhs: &Expr, numeric: bool| {
+        let (mut l, mut r) = (term(lhs, scope)?, term(rhs, scope)?);
+        if let ExprKind::Lit(Lit::Int(value)) = lhs.kind {
+            l = literal(value, r.sort()).ok_or(Unsupported::Mismatch {
+                span: lhs.span,
+                expected: r.sort(),
+                found: Sort::Int,
+            })?;
+        } else if let ExprKind::Lit(Lit::Int(value)) = rhs.kind {
+            r = literal(value, l.sort()).ok_or(Unsupported::Mismatch {
+                span: rhs.span,
+                expected: l.sort(),
+                found: Sort::Int,
+            })?;
+        }
+        if numeric && !is_numeric(l.sort()) {
+            return Err(Unsupported::Mismatch {
+                span: lhs.span,
+                expected: Sort::Int,
+                found: l.sort(),
+            });
+        }
+        if r.sort() != l.sort() {
+            return Err(Unsupported::Mismatch {
+                span: rhs.span,
+                expected: l.sort(),
+                found: r.sort(),
+            });
+        }
+        Ok((Box::new(l), Box::new(r)))
+    };
+    Ok(match &expr.kind {
+        ExprKind::Lit(Lit::Int(value)) => Term::Int(*value),
+        ExprKind::Lit(Lit::Bool(value)) => Term::Bool(*value),
+        ExprKind::Ident(name) => scope.get(&name.name).cloned().ok_or(Unsupported::Reported)?,
+        ExprKind::Unary { op: UnaryOp::Neg, expr } => {
+            let operand = term(expr, scope)?;
+            if !is_numeric(operand.sort()) {
+                return Err(Unsupported::Mismatch {
+                    span: expr.span,
+                    expected: Sort::Int,
+                    found: operand.sort(),
+                });
+            }
+            Term::Neg(Box::new(operand))
+        }
+        ExprKind::Unary { op: UnaryOp::Not, expr } => Term::Not(sorted(expr, Sort::Bool)?),
+        ExprKind::Binary { op, lhs, rhs } => match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                let (lhs, rhs) = operands(lhs, rhs, true)?;
+                match op {
+                    BinaryOp::Add => Term::Add(lhs, rhs),
+                    BinaryOp::Sub => Term::Sub(lhs, rhs),
+                    BinaryOp::Mul => Term::Mul(lhs, rhs),
+                    _ => Term::Div(lhs, rhs),
+                }
+            }
+            // The remainder of a division rounding towards zero.
+            BinaryOp::Rem => {
+                let (lhs, rhs) = operands(lhs, rhs, true)?;
+                let quotient = Term::Div(lhs.clone(), rhs.clone());
+                Term::Sub(lhs, Box::new(Term::Mul(Box::new(quotient), rhs)))
+            }
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::Shl | BinaryOp::Shr => {
+                let (lhs, rhs) = operands(lhs, rhs, true)?;
+                // Only fixed-width integers have bits to operate on.
+                if !matches!(lhs.sort(), Sort::Bits { .. }) {
+                    return Err(Unsupported::Expr(expr.span));
+                }
+                Term::Bitwise(bit_op(*op), lhs, rhs)
+            }
+            BinaryOp::And => Term::And(vec![*sorted(lhs, Sort::Bool)?, *sorted(rhs, Sort::Bool)?]),
+            BinaryOp::Or => Term::Or(vec![*sorted(lhs, Sort::Bool)?, *sorted(rhs, Sort::Bool)?]),
+            BinaryOp::Implies => Term::Implies(sorted(lhs, Sort::Bool)?, sorted(rhs, Sort::Bool)?),
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                let cmp = comparison(*op);
+                // Only `==` and `!=` compare booleans.
+                let (lhs, rhs) = operands(lhs, rhs, !matches!(cmp, Cmp::Eq | Cmp::Ne))?;
+                Term::Cmp(cmp, lhs, rhs)
+            }
+        },
+        _ => return Err(Unsupported::Expr(expr.span)),
+    })
+}
+
+/// Whether arithmetic applies to values of `sort`.
+pub fn is_numeric(sort: Sort) -> bool {
+    matches!(sort, Sort::Int | Sort::Bits { .. })
+}
+
+/// The integer `value` as a term of `sort`, if it is a number of the sort.
+pub fn literal(value: i64, sort: Sort) -> Option<Term> {
+    match sort {
+        Sort::Bits { width, signed } => {
+            let bits = Bits::new(width, signed, value.into());
+            (bits.value() == Some(value.into())).then_some(Term::Bits(bits))
+        }
+        _ => Some(Term::Int(value)),
+    }
+}
+
+/// The operation on bits a bitwise operator stands for.
+pub fn bit_op(op: BinaryOp) -> BitOp {
+    match op {
+        BinaryOp::BitAnd => BitOp::And,
+        BinaryOp::BitOr => BitOp::Or,
+        BinaryOp::Shl => BitOp::Shl,
+        BinaryOp::Shr => BitOp::Shr,
+        _ => unreachable!("`{}` is not a bitwise operator", op.symbol()),
+    }
+}
+
+/// The comparison a comparison operator stands for.
+pub fn comparison(op: BinaryOp) -> Cmp {
+    match op {
+        BinaryOp::Eq => Cmp::Eq,
+        BinaryOp::Ne => Cmp::Ne,
+        BinaryOp::Lt => Cmp::Lt,
+        BinaryOp::Le => Cmp::Le,
+        BinaryOp::Gt => Cmp::Gt,
+        BinaryOp::Ge => Cmp::Ge,
+        _ => unreachable!("`{}` is not a comparison", op.symbol()),
+    }
+}
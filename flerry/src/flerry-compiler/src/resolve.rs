@@ -0,0 +1,498 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Name resolution: every name used inside a function must be bound there,
+//! declared at the top level, imported, or come from the prelude.
+//!
+//! Assignments bind for the whole function, like parameters. Loop variables
+//! and match arm bindings are only visible in their own body. The condition
+//! of a refinement type sees `self` and the type's parameters, and a
+//! contract clause the function's parameters; an `ensures` clause also sees
+//! `result` and `old(...)`.
+//!
+//! Types can be passed to functions with dependent types, so the built-in
+//! types and universes are names too, and a parameter the type of a later
+//! one refers to is used. In those functions `_` is a value too, an
+//! argument left for the checker to work out.
+
+use crate::annotations::BUILTIN_TYPES;
+use crate::ast::*;
+use crate::elab::Dependent;
+use crate::kernel::universe_level;
+use crate::lint::{SHADOWING, UNUSED_IMPORTS, UNUSED_VARIABLES};
+use crate::visit::{Visitor, walk_stmt};
+use flerry_core::span::line_bounds;
+use flerry_core::suggest::find_best_match;
+use flerry_core::{Diagnostic, Edit, Span, Suggestion};
+
+/// Names that are always in scope.
+pub const PRELUDE: &[&str] = &["print", "println"];
+
+/// The standard modules and the names importing them brings into scope.
+pub const MODULES: &[(&str, &[&str])] = &[("IO", &["print", "println", "read_line"])];
+
+struct Binding<'m> {
+    name: &'m str,
+    /// Every place the name is bound: its parameter or each assignment.
+    defs: Vec<Span>,
+    used: bool,
+}
+
+struct Resolver<'m> {
+    globals: Vec<&'m str>,
+    functions: Vec<&'m Ident>,
+    imports: Vec<(&'m Ident, Span)>,
+    /// Global names referenced anywhere, for the unused import lint.
+    used_globals: Vec<&'m str>,
+    scopes: Vec<Vec<Binding<'m>>>,
+    /// Whether the current function has parts that failed to parse, whose
+    /// uses of variables are lost.
+    recovered: bool,
+    /// Whether the current function has a dependent type.
+    dependent: bool,
+    /// Whether an `ensures` clause is being resolved, where `old` is bound.
+    ensures: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+pub fn resolve(source: &str, module: &Module) -> Vec<Diagnostic> {
+    let mut resolver = Resolver {
+        globals: PRELUDE
+            .iter()
+            .chain(BUILTIN_TYPES.iter().map(|(name, _)| name))
+            .copied()
+            .collect(),
+        functions: Vec::new(),
+        imports: Vec::new(),
+        used_globals: Vec::new(),
+        scopes: Vec::new(),
+        recovered: false,
+        dependent: false,
+        ensures: false,
+        diagnostics: Vec::new(),
+    };
+
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Import(name) => {
+                resolver.imports.push((name, item.span));
+                resolver.globals.push(&name.name);
+                if let Some((_, exports)) = MODULES.iter().find(|(module, _)| *module == name.name) {
+                    resolver.globals.extend(exports.iter());
+                }
+            }
+            ItemKind::Func(func) => {
+                resolver.functions.push(&func.name);
+                resolver.globals.push(&func.name.name);
+            }
+            ItemKind::Struct(def) => resolver.globals.push(&def.name.name),
+            ItemKind::Enum(def) => {
+                resolver.globals.push(&def.name.name);
+                resolver
+                    .globals
+                    .extend(def.variants.iter().map(|v| v.name.name.as_str()));
+            }
+            ItemKind::Type(alias) => resolver.globals.push(&alias.name.name),
+            ItemKind::Error => {}
+        }
+    }
+
+    let dependent = Dependent::new(module);
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Func(func) => {
+                resolver.dependent = dependent.functions.contains(func.name.name.as_str());
+                resolver.resolve_func(func);
+            }
+            ItemKind::Type(alias) => {
+                resolver.dependent = false;
+                resolver.resolve_alias(alias);
+            }
+            _ => {}
+        }
+    }
+
+    resolver.unused_imports(source);
+    resolver.diagnostics
+}
+
+/// Collects every name assigned to in a function body, including nested
+/// blocks.
+#[derive(Default)]
+struct AssignedNames<'m> {
+    names: Vec<&'m Ident>,
+}
+
+impl<'m> Visitor<'m> for AssignedNames<'m> {
+    fn visit_stmt(&mut self, stmt: &'m Stmt) {
+        if let StmtKind::Assign { target, .. } = &stmt.kind
+            && let ExprKind::Ident(name) = &target.kind
+        {
+            self.names.push(name);
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+/// Collects every name in a type, in types and in values alike.
+#[derive(Default)]
+struct Names<'m> {
+    names: Vec<&'m str>,
+}
+
+impl<'m> Visitor<'m> for Names<'m> {
+    fn visit_ident(&mut self, ident: &'m Ident) {
+        self.names.push(&ident.name);
+    }
+}
+
+impl<'m> Resolver<'m> {
+    fn resolve_func(&mut self, func: &'m Func) {
+        self.recovered = false;
+        self.scopes.push(Vec::new());
+        for param in &func.params {
+            self.bind(&param.name);
+        }
+        for ty in func
+            .params
+            .iter()
+            .filter_map(|param| param.ty.as_ref())
+            .chain(&func.ret)
+        {
+            self.type_uses(ty);
+        }
+        for contract in &func.contracts {
+            self.contract(contract);
+        }
+        let mut assigned = AssignedNames::default();
+        assigned.visit_block(&func.body);
+        for name in assigned.names {
+            self.bind(name);
+        }
+        self.block(&func.body);
+        self.pop_scope();
+    }
+
+    /// The condition of a refinement type sees `self`, the value it is
+    /// about, and the parameters of the type.
+    fn resolve_alias(&mut self, alias: &'m TypeAlias) {
+        let Some(refinement) = &alias.refinement else {
+            return;
+        };
+        self.recovered = false;
+        self.scopes.push(vec![Binding {
+            name: "self",
+            defs: Vec::new(),
+            used: true,
+        }]);
+        for param in &alias.params {
+            self.bind(param);
+        }
+        self.expr(refinement);
+        self.pop_scope();
+    }
+
+    /// Resolves a contract clause before the locals are bound, as it only
+    /// sees the parameters.
+    fn contract(&mut self, contract: &'m Contract) {
+        self.ensures = contract.kind == ContractKind::Ensures;
+        if self.ensures {
+            self.scopes.push(vec![Binding {
+                name: "result",
+                defs: Vec::new(),
+                used: true,
+            }]);
+        }
+        self.expr(&contract.cond);
+        if self.ensures {
+            self.pop_scope();
+        }
+        self.ensures = false;
+    }
+
+    /// Binds `name` in the innermost scope, or records another definition
+    /// site if it is already bound there.
+    fn bind(&mut self, name: &'m Ident) {
+        let scope = self.scopes.last_mut().expect("bindings live inside a function");
+        if let Some(binding) = scope.iter_mut().find(|b| b.name == name.name) {
+            binding.defs.push(name.span);
+            return;
+        }
+        scope.push(Binding {
+            name: &name.name,
+            defs: vec![name.span],
+            used: false,
+        });
+        self.shadowing(name);
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for binding in scope {
+            if binding.used || binding.name.starts_with('_') || self.recovered {
+                continue;
+            }
+            let renamed = format!("_{}", binding.name);
+            let mut suggestion = Suggestion::replace(
+                format!("if this is intentional, prefix it with an underscore: `{renamed}`"),
+                binding.defs[0],
+                renamed.clone(),
+            );
+            suggestion.edits = binding
+                .defs
+                .iter()
+                .map(|span| Edit {
+                    span: *span,
+                    replacement: renamed.clone(),
+                })
+                .collect();
+            self.diagnostics.push(
+                Diagnostic::warning(format!("unused variable: `{}`", binding.name), binding.defs[0])
+                    .with_suggestion(suggestion)
+                    .with_lint(UNUSED_VARIABLES.name),
+            );
+        }
+    }
+
+    fn shadowing(&mut self, name: &Ident) {
+        let function = self.functions.iter().find(|f| f.name == name.name);
+        let import = self.imports.iter().find(|(i, _)| i.name == name.name);
+        let (defined_at, kind) = match (function, import) {
+            (Some(function), _) => (Some(function.span), "function"),
+            (None, Some((import, _))) => (Some(import.span), "import"),
+            (None, None) if PRELUDE.contains(&name.name.as_str()) => (None, "prelude name"),
+            (None, None) => return,
+        };
+        let mut diagnostic = Diagnostic::warning(
+            format!("`{}` shadows the {kind} of the same name", name.name),
+            name.span,
+        )
+        .with_lint(SHADOWING.name);
+        if let Some(span) = defined_at {
+            diagnostic = diagnostic.with_label(span, format!("the {kind} is defined here"));
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Marks the variables `ty` refers to as used. Other names in types are
+    /// checked with the types.
+    fn type_uses(&mut self, ty: &'m TypeExpr) {
+        let mut names = Names::default();
+        names.visit_type_expr(ty);
+        for name in names.names {
+            if let Some(binding) = self
+                .scopes
+                .iter_mut()
+                .rev()
+                .find_map(|scope| scope.iter_mut().find(|b| b.name == name))
+            {
+                binding.used = true;
+            }
+        }
+    }
+
+    fn use_name(&mut self, name: &'m Ident) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.iter_mut().find(|b| b.name == name.name) {
+                binding.used = true;
+                return;
+            }
+        }
+        if universe_level(&name.name).is_some() || (name.name == "_" && self.dependent) {
+            return;
+        }
+        if name.name == "_" {
+            self.diagnostics.push(
+                Diagnostic::error("`_` cannot be used as a value here", name.span)
+                    .with_label(name.span, "not in a function with a dependent type")
+                    .with_note("`_` leaves an argument for the checker to work out, which only functions with dependent types can do"),
+            );
+            return;
+        }
+        if self.globals.contains(&name.name.as_str()) {
+            self.used_globals.push(&name.name);
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(format!("cannot find `{}` in this scope", name.name), name.span);
+        let locals = self.scopes.iter().flatten().map(|b| b.name);
+        if let Some(similar) = find_best_match(&name.name, locals.chain(self.globals.iter().copied())) {
+            diagnostic = diagnostic.with_suggestion(
+                Suggestion::replace(format!("did you mean `{similar}`?"), name.span, similar).maybe_incorrect(),
+            );
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn block(&mut self, block: &'m Block) {
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'m Stmt) {
+        match &stmt.kind {
+            StmtKind::Assign { target, value, .. } => {
+                // Assigning to a name is not a use of it, but `p.x = ...` uses `p`.
+                if !matches!(target.kind, ExprKind::Ident(_)) {
+                    self.expr(target);
+                }
+                self.expr(value);
+            }
+            StmtKind::Expr(expr) => self.expr(expr),
+            StmtKind::Error => self.recovered = true,
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StmtKind::If { branches, else_block } => {
+                for (cond, block) in branches {
+                    self.expr(cond);
+                    self.block(block);
+                }
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+            }
+            StmtKind::While { cond, contracts, body } => {
+                self.expr(cond);
+                for contract in contracts {
+                    self.expr(&contract.cond);
+                }
+                self.block(body);
+            }
+            // The clauses hold between iterations, where the loop variable is not bound.
+            StmtKind::For {
+                var,
+                iter,
+                contracts,
+                body,
+            } => {
+                self.expr(iter);
+                for contract in contracts {
+                    self.expr(&contract.cond);
+                }
+                self.scopes.push(Vec::new());
+                self.bind(var);
+                self.block(body);
+                self.pop_scope();
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &'m Expr) {
+        match &expr.kind {
+            ExprKind::Lit(_) => {}
+            ExprKind::Hole(_) => {}
+            ExprKind::Error => self.recovered = true,
+            ExprKind::Ident(name) => self.use_name(name),
+            ExprKind::List(items) | ExprKind::Tuple(items) => items.iter().for_each(|item| self.expr(item)),
+            ExprKind::Unary { expr, .. } => self.expr(expr),
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::Call { callee, args } => {
+                match &callee.kind {
+                    ExprKind::Ident(name) if name.name == "old" && self.ensures => {
+                        if args.len() != 1 {
+                            self.diagnostics.push(
+                                Diagnostic::error(
+                                    format!("`old` takes 1 argument but {} arguments were supplied", args.len()),
+                                    expr.span,
+                                )
+                                .with_label(
+                                    callee.span,
+                                    "`old(x)` is the value `x` had when the function was called",
+                                ),
+                            );
+                        }
+                    }
+                    _ => self.expr(callee),
+                }
+                args.iter().for_each(|arg| self.expr(arg));
+            }
+            // The field name belongs to the value on the left.
+            ExprKind::Field { expr, .. } => self.expr(expr),
+            ExprKind::Lambda { params, body } => {
+                self.scopes.push(Vec::new());
+                for param in params {
+                    self.bind(&param.name);
+                }
+                self.expr(body);
+                self.pop_scope();
+            }
+            ExprKind::Closure { func, captures } => {
+                self.use_name(func);
+                captures.iter().for_each(|capture| self.use_name(&capture.name));
+            }
+            ExprKind::Struct { name, fields, base } => {
+                self.use_name(name);
+                fields.iter().for_each(|init| self.expr(&init.value));
+                if let Some(base) = base {
+                    self.expr(base);
+                }
+            }
+            ExprKind::Index { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.scopes.push(Vec::new());
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                    self.pop_scope();
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &'m Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Lit(_) => {}
+            PatternKind::Error => self.recovered = true,
+            PatternKind::Binding(name) => self.bind(name),
+            PatternKind::Variant { name, args } => {
+                self.use_name(name);
+                args.iter().for_each(|arg| self.pattern(arg));
+            }
+            // Every alternative binds the same names, checked with the types.
+            PatternKind::Or(alternatives) => alternatives.iter().for_each(|alternative| self.pattern(alternative)),
+        }
+    }
+
+    fn unused_imports(&mut self, source: &str) {
+        for (module, span) in &self.imports {
+            let exports = MODULES
+                .iter()
+                .find(|(name, _)| *name == module.name)
+                .map_or(&[][..], |(_, exports)| *exports);
+            if self
+                .used_globals
+                .iter()
+                .any(|name| *name == module.name || exports.contains(name))
+            {
+                continue;
+            }
+
+            // Remove the whole line when the import is the only thing on it.
+            let (start, end) = line_bounds(source, span.start);
+            let removal = if source[start..end].trim() == span.text(source) {
+                Span::new(start, (end + 1).min(source.len()))
+            } else {
+                *span
+            };
+            self.diagnostics.push(
+                Diagnostic::warning(format!("unused import: `{}`", module.name), *span)
+                    .with_suggestion(Suggestion::replace("remove the unused import", removal, ""))
+                    .with_lint(UNUSED_IMPORTS.name),
+            );
+        }
+    }
+}
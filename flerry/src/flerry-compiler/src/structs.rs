@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Structs: checks declarations and literals against their fields, field
+//! accesses against the struct they are made on, and resolves method calls.
+//!
+//! A function is a method of a struct when its first parameter is declared
+//! with the struct's type, and `p.norm()` then calls `norm(p)`.
+//!
+//! The struct a value belongs to is the type inference gave it. Values whose
+//! type it could not work out are left alone.
+
+use crate::ast::*;
+use crate::types::Ty;
+use crate::visit::{Visitor, walk_expr};
+use flerry_core::suggest::find_best_match;
+use flerry_core::{Diagnostic, Span, Suggestion};
+use std::collections::HashMap;
+
+struct Checker<'m> {
+    structs: HashMap<&'m str, &'m StructDef>,
+    /// The methods of each struct, by name.
+    methods: HashMap<(&'m str, &'m str), &'m Func>,
+    /// Every function, for explaining why one is not a method.
+    functions: HashMap<&'m str, &'m Func>,
+    /// The inferred types of receivers and struct literal bases.
+    receivers: &'m HashMap<Span, Ty>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Checks the structs of `module`, given the types inference found for the
+/// receivers of its field accesses and method calls.
+pub fn check<'m>(module: &'m Module, receivers: &'m HashMap<Span, Ty>) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        structs: HashMap::new(),
+        methods: HashMap::new(),
+        functions: HashMap::new(),
+        receivers,
+        diagnostics: Vec::new(),
+    };
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Struct(def) => {
+                checker.structs.insert(&def.name.name, def);
+            }
+            ItemKind::Func(func) => {
+                checker.functions.insert(&func.name.name, func);
+            }
+            _ => {}
+        }
+    }
+    for func in checker.functions.values() {
+        if let Some(name) = func
+            .params
+            .first()
+            .and_then(|param| param.ty.as_ref())
+            .and_then(type_name)
+            && checker.structs.contains_key(name)
+        {
+            checker.methods.insert((name, &func.name.name), func);
+        }
+    }
+
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Struct(def) => checker.check_def(def),
+            ItemKind::Func(func) => checker.visit_func(func),
+            _ => {}
+        }
+    }
+    checker.diagnostics
+}
+
+/// The name of a type without arguments.
+fn type_name(ty: &TypeExpr) -> Option<&str> {
+    let TypeExprKind::Named { name, args } = &ty.kind else {
+        return None;
+    };
+    args.is_empty().then_some(name.name.as_str())
+}
+
+/// `` `a` ``, `` `a` and `b` `` or `` `a`, `b`, `c` and 2 other fields ``.
+fn field_list(names: &[&str]) -> String {
+    let quoted: Vec<String> = names.iter().map(|name| format!("`{name}`")).collect();
+    match quoted.as_slice() {
+        [only] => only.clone(),
+        [init @ .., last] if quoted.len() <= 3 => format!("{} and {last}", init.join(", ")),
+        _ => {
+            let rest = quoted.len() - 3;
+            let others = if rest == 1 { "other field" } else { "other fields" };
+            format!("{} and {rest} {others}", quoted[..3].join(", "))
+        }
+    }
+}
+
+impl<'m> Visitor<'m> for Checker<'m> {
+    fn visit_expr(&mut self, expr: &'m Expr) {
+        match &expr.kind {
+            ExprKind::Struct { name, fields, base } => self.check_literal(name, fields, base.as_deref()),
+            ExprKind::Call { callee, args } => {
+                if let ExprKind::Field { expr: receiver, field } = &callee.kind {
+                    self.check_method_call(receiver, field, args.len(), expr.span);
+                    // The callee is a method, not a field; check its parts.
+                    self.visit_expr(receiver);
+                    args.iter().for_each(|arg| self.visit_expr(arg));
+                    return;
+                }
+            }
+            ExprKind::Field { expr: receiver, field } => self.check_field(receiver, field),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl<'m> Checker<'m> {
+    fn check_def(&mut self, def: &StructDef) {
+        for (i, field) in def.fields.iter().enumerate() {
+            if let Some(first) = def.fields[..i].iter().find(|f| f.name.name == field.name.name) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        format!("field `{}` is already declared", field.name.name),
+                        field.name.span,
+                    )
+                    .with_label(field.name.span, "field already declared")
+                    .with_label(first.name.span, format!("`{}` first declared here", field.name.name)),
+                );
+            }
+        }
+    }
+
+    /// The struct `expr` evaluates to, if inference found it is one.
+    fn struct_of(&self, expr: &Expr) -> Option<&'m str> {
+        let Some(Ty::Con(name, args)) = self.receivers.get(&expr.span) else {
+            return None;
+        };
+        if !args.is_empty() {
+            return None;
+        }
+        self.structs.get_key_value(name.as_str()).map(|(name, _)| *name)
+    }
+
+    fn check_literal(&mut self, name: &Ident, fields: &[FieldInit], base: Option<&Expr>) {
+        // Unknown names are reported by name resolution.
+        let Some(def) = self.structs.get(name.name.as_str()).copied() else {
+            return;
+        };
+
+        for (i, init) in fields.iter().enumerate() {
+            if let Some(first) = fields[..i].iter().find(|f| f.name.name == init.name.name) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        format!("field `{}` specified more than once", init.name.name),
+                        init.name.span,
+                    )
+                    .with_label(init.name.span, "used more than once")
+                    .with_label(first.name.span, format!("first use of `{}`", init.name.name)),
+                );
+            } else if !def.fields.iter().any(|f| f.name.name == init.name.name) {
+                // Only the fields not given yet are worth suggesting.
+                let unused: Vec<&str> = def
+                    .fields
+                    .iter()
+                    .map(|f| f.name.name.as_str())
+                    .filter(|field| !fields.iter().any(|f| f.name.name == *field))
+                    .collect();
+                let mut diagnostic = Diagnostic::error(
+                    format!("struct `{}` has no field named `{}`", def.name.name, init.name.name),
+                    init.name.span,
+                )
+                .with_label(init.name.span, format!("`{}` does not have this field", def.name.name));
+                if let Some(similar) = find_best_match(&init.name.name, unused.iter().copied()) {
+                    diagnostic = diagnostic.with_suggestion(
+                        Suggestion::replace(
+                            format!("a field with a similar name exists: `{similar}`"),
+                            init.name.span,
+                            similar,
+                        )
+                        .maybe_incorrect(),
+                    );
+                } else if !unused.is_empty() {
+                    diagnostic = diagnostic.with_note(format!("available fields are: {}", field_list(&unused)));
+                }
+                self.diagnostics.push(diagnostic);
+            }
+        }
+
+        match base {
+            // The base supplies the rest, and must be the same struct.
+            Some(base) => {
+                if let Some(found) = self.struct_of(base)
+                    && found != def.name.name
+                {
+                    self.diagnostics.push(
+                        Diagnostic::error("mismatched types", base.span)
+                            .with_label(base.span, format!("expected `{}`, found `{found}`", def.name.name)),
+                    );
+                }
+            }
+            None => {
+                let missing: Vec<&str> = def
+                    .fields
+                    .iter()
+                    .map(|f| f.name.name.as_str())
+                    .filter(|field| !fields.iter().any(|f| f.name.name == *field))
+                    .collect();
+                if !missing.is_empty() {
+                    let list = field_list(&missing);
+                    let fields = if missing.len() == 1 { "field" } else { "fields" };
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            format!("missing {fields} {list} in initializer of `{}`", def.name.name),
+                            name.span,
+                        )
+                        .with_label(name.span, format!("missing {list}")),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_field(&mut self, receiver: &Expr, field: &Ident) {
+        let Some(struct_name) = self.struct_of(receiver) else {
+            return;
+        };
+        let def = self.structs[struct_name];
+        if def.fields.iter().any(|f| f.name.name == field.name) {
+            return;
+        }
+        if self.methods.contains_key(&(struct_name, field.name.as_str())) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    format!(
+                        "attempted to take value of method `{}` on type `{struct_name}`",
+                        field.name
+                    ),
+                    field.span,
+                )
+                .with_label(field.span, "method, not a field")
+                .with_suggestion(Suggestion::insert(
+                    format!("use parentheses to call the method: `{}()`", field.name),
+                    field.span.end,
+                    "()",
+                )),
+            );
+            return;
+        }
+        let mut diagnostic =
+            Diagnostic::error(format!("no field `{}` on type `{struct_name}`", field.name), field.span)
+                .with_label(field.span, "unknown field");
+        let fields: Vec<&str> = def.fields.iter().map(|f| f.name.name.as_str()).collect();
+        if let Some(similar) = find_best_match(&field.name, fields.iter().copied()) {
+            diagnostic = diagnostic.with_suggestion(
+                Suggestion::replace(
+                    format!("a field with a similar name exists: `{similar}`"),
+                    field.span,
+                    similar,
+                )
+                .maybe_incorrect(),
+            );
+        } else if !fields.is_empty() {
+            diagnostic = diagnostic.with_note(format!("available fields are: {}", field_list(&fields)));
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn check_method_call(&mut self, receiver: &Expr, method: &Ident, supplied: usize, span: Span) {
+        let Some(struct_name) = self.struct_of(receiver) else {
+            return;
+        };
+        if let Some(func) = self.methods.get(&(struct_name, method.name.as_str())) {
+            let expected = func.params.len() - 1;
+            if supplied != expected {
+                let takes = match expected {
+                    1 => "1 argument".to_owned(),
+                    n => format!("{n} arguments"),
+                };
+                let given = match supplied {
+                    1 => "1 argument was".to_owned(),
+                    n => format!("{n} arguments were"),
+                };
+                self.diagnostics.push(
+                    Diagnostic::error(format!("this method takes {takes} but {given} supplied"), span)
+                        .with_label(func.name.span, format!("`{}` defined here", method.name)),
+                );
+            }
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(
+            format!("no method named `{}` found for struct `{struct_name}`", method.name),
+            method.span,
+        )
+        .with_label(method.span, format!("method not found in `{struct_name}`"));
+        if let Some(func) = self.functions.get(method.name.as_str()) {
+            diagnostic = diagnostic
+                .with_label(
+                    func.name.span,
+                    format!("`{}` is a function, but not a method", method.name),
+                )
+                .with_help(format!(
+                    "to call `{}` as a method, declare its first parameter with type `{struct_name}`",
+                    method.name
+                ));
+        } else {
+            let mut methods: Vec<&str> = self
+                .methods
+                .keys()
+                .filter(|(owner, _)| *owner == struct_name)
+                .map(|(_, name)| *name)
+                .collect();
+            methods.sort_unstable();
+            if let Some(similar) = find_best_match(&method.name, methods) {
+                diagnostic = diagnostic.with_suggestion(
+                    Suggestion::replace(
+                        format!("there is a method with a similar name: `{similar}`"),
+                        method.span,
+                        similar,
+                    )
+                    .maybe_incorrect(),
+                );
+            }
+        }
+        self.diagnostics.push(diagnostic);
+    }
+}
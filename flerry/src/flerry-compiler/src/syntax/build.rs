@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Builds the green tree from the lossless token stream.
+//!
+//! The parser decides the structure: every AST node becomes a syntax node
+//! over the tokens its span covers. Trivia between two children belongs to
+//! their parent, and tokens the AST dropped, such as attributes or text
+//! skipped while recovering from an error, stay where they are in the
+//! nearest enclosing node.
+
+use super::NodeKind;
+use super::green::{GreenElement, GreenNode, NodeCache};
+use super::red::SyntaxNode;
+use crate::ast::*;
+use crate::checks::repair_keywords;
+use crate::parser::parse;
+use flerry_core::{Diagnostic, Span, Token, TokenType};
+use flerry_lexer::lexer::tokenize_lossless;
+use std::rc::Rc;
+
+/// Parses `source` into a syntax tree whose text is exactly `source`,
+/// together with the lexical and syntax errors found on the way.
+pub fn parse_lossless(source: &str, cache: &mut NodeCache) -> (SyntaxNode, Vec<Diagnostic>) {
+    let (tokens, mut diagnostics) = tokenize_lossless(source);
+    let mut significant: Vec<Token> = tokens
+        .iter()
+        .filter(|t| !matches!(t.ttype, TokenType::Whitespace | TokenType::Comment | TokenType::Unknown))
+        .cloned()
+        .collect();
+    repair_keywords(&mut significant);
+    let (module, parse_diagnostics) = parse(source, &significant);
+    diagnostics.extend(parse_diagnostics);
+
+    let mut nodes = Vec::new();
+    module_nodes(&module, &mut nodes);
+    let mut builder = Builder {
+        source_len: source.len(),
+        tokens: &tokens,
+        pos: 0,
+        nodes: &nodes,
+        next: 0,
+        cache,
+    };
+    let green = builder.node(NodeKind::SourceFile, Span::new(0, source.len()));
+    (SyntaxNode::new_root(green), diagnostics)
+}
+
+struct Builder<'t, 'a, 'c> {
+    source_len: usize,
+    tokens: &'t [Token<'a>],
+    pos: usize,
+    /// Every node to build, in pre-order.
+    nodes: &'t [(NodeKind, Span)],
+    next: usize,
+    cache: &'c mut NodeCache,
+}
+
+impl Builder<'_, '_, '_> {
+    fn current_start(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .filter(|t| t.ttype != TokenType::Eof)
+            .map_or(self.source_len, |t| t.span.start)
+    }
+
+    fn node(&mut self, kind: NodeKind, span: Span) -> Rc<GreenNode> {
+        let mut children = Vec::new();
+        loop {
+            // Open the next node once every token before it is placed.
+            if let Some(&(child_kind, child_span)) = self.nodes.get(self.next)
+                && span.start <= child_span.start
+                && child_span.end <= span.end
+                && child_span.start <= self.current_start()
+            {
+                self.next += 1;
+                children.push(GreenElement::Node(self.node(child_kind, child_span)));
+                continue;
+            }
+            match self.tokens.get(self.pos) {
+                Some(token) if token.ttype != TokenType::Eof && token.span.end <= span.end => {
+                    children.push(GreenElement::Token(self.cache.token(token.ttype, token.lexeme)));
+                    self.pos += 1;
+                }
+                _ => break,
+            }
+        }
+        self.cache.node(kind, children)
+    }
+}
+
+fn module_nodes(module: &Module, nodes: &mut Vec<(NodeKind, Span)>) {
+    for item in &module.items {
+        match &item.kind {
+            ItemKind::Import(_) => nodes.push((NodeKind::Import, item.span)),
+            ItemKind::Func(func) => {
+                nodes.push((NodeKind::Func, item.span));
+                for param in &func.params {
+                    param_nodes(param, nodes);
+                }
+                if let Some(ret) = &func.ret {
+                    type_nodes(ret, nodes);
+                }
+                contract_nodes(&func.contracts, nodes);
+                block_nodes(&func.body, nodes);
+            }
+            ItemKind::Struct(def) => {
+                nodes.push((NodeKind::Struct, item.span));
+                for ty in def.fields.iter().filter_map(|field| field.ty.as_ref()) {
+                    type_nodes(ty, nodes);
+                }
+            }
+            ItemKind::Enum(def) => {
+                nodes.push((NodeKind::Enum, item.span));
+                for param in &def.params {
+                    param_nodes(param, nodes);
+                }
+                for variant in &def.variants {
+                    nodes.push((NodeKind::Variant, variant.span));
+                    for ty in variant.fields.iter().filter_map(|field| field.ty.as_ref()) {
+                        type_nodes(ty, nodes);
+                    }
+                    if let Some(ret) = &variant.ret {
+                        type_nodes(ret, nodes);
+                    }
+                }
+            }
+            ItemKind::Type(alias) => {
+                nodes.push((NodeKind::TypeAlias, item.span));
+                type_nodes(&alias.ty, nodes);
+                if let Some(refinement) = &alias.refinement {
+                    expr_nodes(refinement, nodes);
+                }
+            }
+            ItemKind::Error => nodes.push((NodeKind::Error, item.span)),
+        }
+    }
+}
+
+fn param_nodes(param: &Param, nodes: &mut Vec<(NodeKind, Span)>) {
+    nodes.push((NodeKind::Param, param.span));
+    if let Some(ty) = &param.ty {
+        type_nodes(ty, nodes);
+    }
+}
+
+fn type_nodes(ty: &TypeExpr, nodes: &mut Vec<(NodeKind, Span)>) {
+    nodes.push((NodeKind::TypeExpr, ty.span));
+    match &ty.kind {
+        TypeExprKind::Named { args, .. } => args.iter().for_each(|arg| type_nodes(arg, nodes)),
+        TypeExprKind::Function { params, ret } => {
+            params.iter().for_each(|param| type_nodes(param, nodes));
+            type_nodes(ret, nodes);
+        }
+        TypeExprKind::Tuple(elements) => elements.iter().for_each(|ty| type_nodes(ty, nodes)),
+        TypeExprKind::Bind { ty, .. } => type_nodes(ty, nodes),
+        TypeExprKind::Expr(expr) => expr_nodes(expr, nodes),
+    }
+}
+
+fn block_nodes(block: &Block, nodes: &mut Vec<(NodeKind, Span)>) {
+    nodes.push((NodeKind::Block, block.span));
+    for stmt in &block.stmts {
+        stmt_nodes(stmt, nodes);
+    }
+}
+
+fn contract_nodes(contracts: &[Contract], nodes: &mut Vec<(NodeKind, Span)>) {
+    for contract in contracts {
+        nodes.push((NodeKind::Contract, contract.span));
+        expr_nodes(&contract.cond, nodes);
+    }
+}
+
+fn stmt_nodes(stmt: &Stmt, nodes: &mut Vec<(NodeKind, Span)>) {
+    let kind = match &stmt.kind {
+        StmtKind::Assign { .. } => NodeKind::AssignStmt,
+        StmtKind::Expr(_) => NodeKind::ExprStmt,
+        StmtKind::Return(_) => NodeKind::ReturnStmt,
+        StmtKind::If { .. } => NodeKind::IfStmt,
+        StmtKind::While { .. } => NodeKind::WhileStmt,
+        StmtKind::For { .. } => NodeKind::ForStmt,
+        StmtKind::Error => NodeKind::Error,
+    };
+    nodes.push((kind, stmt.span));
+    match &stmt.kind {
+        StmtKind::Assign { target, ty, value } => {
+            expr_nodes(target, nodes);
+            if let Some(ty) = ty {
+                type_nodes(ty, nodes);
+            }
+            expr_nodes(value, nodes);
+        }
+        StmtKind::Expr(expr) | StmtKind::Return(Some(expr)) => expr_nodes(expr, nodes),
+        StmtKind::If { branches, else_block } => {
+            for (cond, block) in branches {
+                expr_nodes(cond, nodes);
+                block_nodes(block, nodes);
+            }
+            if let Some(block) = else_block {
+                block_nodes(block, nodes);
+            }
+        }
+        StmtKind::While { cond, contracts, body } => {
+            expr_nodes(cond, nodes);
+            contract_nodes(contracts, nodes);
+            block_nodes(body, nodes);
+        }
+        StmtKind::For {
+            iter, contracts, body, ..
+        } => {
+            expr_nodes(iter, nodes);
+            contract_nodes(contracts, nodes);
+            block_nodes(body, nodes);
+        }
+        StmtKind::Return(None) | StmtKind::Error => {}
+    }
+}
+
+fn expr_nodes(expr: &Expr, nodes: &mut Vec<(NodeKind, Span)>) {
+    let kind = match &expr.kind {
+        ExprKind::Lit(_) => NodeKind::Literal,
+        ExprKind::Ident(_) => NodeKind::NameRef,
+        ExprKind::List(_) => NodeKind::ListExpr,
+        ExprKind::Tuple(_) => NodeKind::TupleExpr,
+        ExprKind::Unary { .. } => NodeKind::UnaryExpr,
+        ExprKind::Binary { .. } => NodeKind::BinaryExpr,
+        ExprKind::Call { .. } => NodeKind::CallExpr,
+        ExprKind::Field { .. } => NodeKind::FieldExpr,
+        ExprKind::Index { .. } => NodeKind::IndexExpr,
+        ExprKind::Match { .. } => NodeKind::MatchExpr,
+        ExprKind::Struct { .. } => NodeKind::StructExpr,
+        ExprKind::Lambda { .. } => NodeKind::LambdaExpr,
+        ExprKind::Hole(_) => NodeKind::HoleExpr,
+        ExprKind::Closure { .. } => unreachable!("closures are made after parsing"),
+        ExprKind::Error => NodeKind::Error,
+    };
+    nodes.push((kind, expr.span));
+    match &expr.kind {
+        ExprKind::Lit(_) | ExprKind::Ident(_) | ExprKind::Hole(_) | ExprKind::Error => {}
+        ExprKind::List(items) | ExprKind::Tuple(items) => items.iter().for_each(|item| expr_nodes(item, nodes)),
+        ExprKind::Unary { expr, .. } | ExprKind::Field { expr, .. } => expr_nodes(expr, nodes),
+        ExprKind::Binary { lhs, rhs, .. } => {
+            expr_nodes(lhs, nodes);
+            expr_nodes(rhs, nodes);
+        }
+        ExprKind::Call { callee, args } => {
+            expr_nodes(callee, nodes);
+            args.iter().for_each(|arg| expr_nodes(arg, nodes));
+        }
+        ExprKind::Index { expr, index } => {
+            expr_nodes(expr, nodes);
+            expr_nodes(index, nodes);
+        }
+        ExprKind::Match { scrutinee, arms } => {
+            expr_nodes(scrutinee, nodes);
+            for arm in arms {
+                nodes.push((NodeKind::MatchArm, arm.span));
+                pattern_nodes(&arm.pattern, nodes);
+                if let Some(guard) = &arm.guard {
+                    expr_nodes(guard, nodes);
+                }
+                expr_nodes(&arm.body, nodes);
+            }
+        }
+        ExprKind::Struct { fields, base, .. } => {
+            for init in fields {
+                nodes.push((NodeKind::FieldInit, init.span));
+                expr_nodes(&init.value, nodes);
+            }
+            if let Some(base) = base {
+                expr_nodes(base, nodes);
+            }
+        }
+        ExprKind::Lambda { params, body } => {
+            for param in params {
+                param_nodes(param, nodes);
+            }
+            expr_nodes(body, nodes);
+        }
+        ExprKind::Closure { .. } => {}
+    }
+}
+
+fn pattern_nodes(pattern: &Pattern, nodes: &mut Vec<(NodeKind, Span)>) {
+    let kind = match &pattern.kind {
+        PatternKind::Wildcard => NodeKind::WildcardPat,
+        PatternKind::Binding(_) => NodeKind::BindingPat,
+        PatternKind::Lit(_) => NodeKind::LiteralPat,
+        PatternKind::Variant { .. } => NodeKind::VariantPat,
+        PatternKind::Or(_) => NodeKind::OrPat,
+        PatternKind::Error => NodeKind::Error,
+    };
+    nodes.push((kind, pattern.span));
+    if let PatternKind::Variant { args: patterns, .. } | PatternKind::Or(patterns) = &pattern.kind {
+        for pattern in patterns {
+            pattern_nodes(pattern, nodes);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+use super::NodeKind;
+use flerry_core::TokenType;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GreenToken {
+    kind: TokenType,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn kind(&self) -> TokenType {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn width(&self) -> usize {
+        self.text.len()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GreenNode {
+    kind: NodeKind,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width(),
+            GreenElement::Token(token) => token.width(),
+        }
+    }
+
+    /// Identifies an interned element: two elements from the same cache are
+    /// structurally equal exactly when their addresses are.
+    fn address(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => Rc::as_ptr(node) as usize,
+            GreenElement::Token(token) => Rc::as_ptr(token) as usize,
+        }
+    }
+}
+
+/// Interns green tokens and nodes so equal subtrees are stored once.
+///
+/// Children are interned before their parents, so a node is looked up by its
+/// kind and the addresses of its children rather than by a deep comparison.
+/// The cache holds on to everything it hands out, which keeps those
+/// addresses from being reused.
+#[derive(Debug, Default)]
+pub struct NodeCache {
+    tokens: HashMap<(TokenType, String), Rc<GreenToken>>,
+    nodes: HashMap<(NodeKind, Vec<usize>), Rc<GreenNode>>,
+}
+
+impl NodeCache {
+    pub fn new() -> NodeCache {
+        NodeCache::default()
+    }
+
+    pub fn token(&mut self, kind: TokenType, text: &str) -> Rc<GreenToken> {
+        self.tokens
+            .entry((kind, text.to_owned()))
+            .or_insert_with(|| {
+                Rc::new(GreenToken {
+                    kind,
+                    text: text.to_owned(),
+                })
+            })
+            .clone()
+    }
+
+    pub fn node(&mut self, kind: NodeKind, children: Vec<GreenElement>) -> Rc<GreenNode> {
+        let key = (kind, children.iter().map(GreenElement::address).collect());
+        self.nodes
+            .entry(key)
+            .or_insert_with(|| {
+                Rc::new(GreenNode {
+                    kind,
+                    width: children.iter().map(GreenElement::width).sum(),
+                    children,
+                })
+            })
+            .clone()
+    }
+}
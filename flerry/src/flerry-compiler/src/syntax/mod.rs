@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! A lossless concrete syntax tree for tooling.
+//!
+//! The tree keeps every byte of the source, whitespace and comments included,
+//! and is built for broken code as readily as for valid code. It comes in two
+//! layers, as in rowan:
+//!
+//! - The green tree is immutable and position independent: a node only knows
+//!   its kind, its width and its children. Green nodes are interned in a
+//!   `NodeCache`, so identical subtrees are shared, and re-parsing an edited
+//!   file with the same cache reuses every subtree the edit did not touch.
+//! - The red tree (`SyntaxNode`, `SyntaxToken`) is a cursor over the green
+//!   tree that adds parents and absolute offsets, created on demand.
+//!
+//! Typed views over the red tree live in `views`.
+
+mod build;
+mod green;
+mod red;
+pub mod views;
+
+pub use build::parse_lossless;
+pub use green::{GreenElement, GreenNode, GreenToken, NodeCache};
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+/// The kinds of interior nodes. Leaves are tokens, whose kind is their
+/// `TokenType`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    SourceFile,
+    Import,
+    Func,
+    Param,
+    /// A `requires`, `ensures`, `invariant` or `decreases` clause.
+    Contract,
+    Struct,
+    Enum,
+    Variant,
+    TypeAlias,
+    TypeExpr,
+    Block,
+    AssignStmt,
+    ExprStmt,
+    ReturnStmt,
+    IfStmt,
+    WhileStmt,
+    ForStmt,
+    Literal,
+    NameRef,
+    ListExpr,
+    TupleExpr,
+    UnaryExpr,
+    BinaryExpr,
+    CallExpr,
+    FieldExpr,
+    IndexExpr,
+    MatchExpr,
+    MatchArm,
+    StructExpr,
+    FieldInit,
+    LambdaExpr,
+    HoleExpr,
+    WildcardPat,
+    BindingPat,
+    LiteralPat,
+    VariantPat,
+    OrPat,
+    /// Source the parser could not make sense of.
+    Error,
+}
@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+use super::NodeKind;
+use super::green::{GreenElement, GreenNode, GreenToken};
+use flerry_core::{Span, TokenType};
+use std::fmt;
+use std::rc::Rc;
+
+/// A node of the green tree at a known position, with a link to its parent.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.width())
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + use<> {
+        let mut offset = self.0.offset;
+        let mut elements = Vec::with_capacity(self.0.green.children().len());
+        for child in self.0.green.children() {
+            let start = offset;
+            offset += child.width();
+            elements.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    offset: start,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    offset: start,
+                }),
+            });
+        }
+        elements.into_iter()
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + use<> {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// The tokens directly under this node, trivia included.
+    pub fn child_tokens(&self) -> impl Iterator<Item = SyntaxToken> + use<> {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    /// This node and every node below it, in source order.
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+        nodes
+    }
+
+    /// Every token below this node, in source order.
+    pub fn descendant_tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.descendant_tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// The token covering `offset`, if any.
+    pub fn token_at_offset(&self, offset: usize) -> Option<SyntaxToken> {
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) if node.span().start <= offset && offset < node.span().end => {
+                    return node.token_at_offset(offset);
+                }
+                SyntaxElement::Token(token) if token.span().start <= offset && offset < token.span().end => {
+                    return Some(token);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// The source text this node was built from, byte for byte.
+    pub fn text(&self) -> String {
+        self.descendant_tokens().iter().map(SyntaxToken::text).collect()
+    }
+
+    /// An indented dump of the tree with the kind and span of every element.
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        self.dump_into(&mut out, 0);
+        out
+    }
+
+    fn dump_into(&self, out: &mut String, depth: usize) {
+        let span = self.span();
+        out.push_str(&format!(
+            "{}{:?}@{}..{}\n",
+            "  ".repeat(depth),
+            self.kind(),
+            span.start,
+            span.end
+        ));
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => node.dump_into(out, depth + 1),
+                SyntaxElement::Token(token) => out.push_str(&format!("{}{:?}\n", "  ".repeat(depth + 1), token)),
+            }
+        }
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> TokenType {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.green.width())
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind(), TokenType::Whitespace | TokenType::Comment)
+    }
+}
+
+/// Two red nodes are the same node when they sit at the same place over the
+/// same green node.
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        Rc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &SyntaxToken) -> bool {
+        Rc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.span();
+        write!(f, "{:?}@{}..{}", self.kind(), span.start, span.end)
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.span();
+        write!(f, "{:?}@{}..{} {:?}", self.kind(), span.start, span.end, self.text())
+    }
+}
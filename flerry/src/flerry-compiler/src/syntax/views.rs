@@ -0,0 +1,565 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Typed views over syntax nodes. A view is just a `SyntaxNode` known to be
+//! of a certain kind; its accessors return `None` where broken code left a
+//! part out.
+
+use super::{NodeKind, SyntaxNode, SyntaxToken};
+use flerry_core::TokenType;
+
+pub trait AstNode: Sized {
+    fn cast(node: SyntaxNode) -> Option<Self>;
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! ast_node {
+    ($($name:ident => $kind:ident),* $(,)?) => {$(
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name(SyntaxNode);
+
+        impl AstNode for $name {
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                (node.kind() == NodeKind::$kind).then(|| $name(node))
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                &self.0
+            }
+        }
+    )*};
+}
+
+ast_node! {
+    SourceFile => SourceFile,
+    Import => Import,
+    Func => Func,
+    Param => Param,
+    Contract => Contract,
+    StructDef => Struct,
+    EnumDef => Enum,
+    Variant => Variant,
+    TypeAlias => TypeAlias,
+    TypeExpr => TypeExpr,
+    Block => Block,
+    AssignStmt => AssignStmt,
+    ExprStmt => ExprStmt,
+    ReturnStmt => ReturnStmt,
+    IfStmt => IfStmt,
+    WhileStmt => WhileStmt,
+    ForStmt => ForStmt,
+    Literal => Literal,
+    NameRef => NameRef,
+    ListExpr => ListExpr,
+    TupleExpr => TupleExpr,
+    UnaryExpr => UnaryExpr,
+    BinaryExpr => BinaryExpr,
+    CallExpr => CallExpr,
+    FieldExpr => FieldExpr,
+    IndexExpr => IndexExpr,
+    MatchExpr => MatchExpr,
+    MatchArm => MatchArm,
+    StructExpr => StructExpr,
+    FieldInit => FieldInit,
+    LambdaExpr => LambdaExpr,
+    ErrorNode => Error,
+}
+
+fn child<N: AstNode>(node: &SyntaxNode) -> Option<N> {
+    node.children().find_map(N::cast)
+}
+
+fn children<N: AstNode>(node: &SyntaxNode) -> impl Iterator<Item = N> + use<N> {
+    node.children().filter_map(N::cast)
+}
+
+fn token(node: &SyntaxNode, kind: TokenType) -> Option<SyntaxToken> {
+    node.child_tokens().find(|t| t.kind() == kind)
+}
+
+/// The first token directly under `node` that is not trivia.
+fn first_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.child_tokens().find(|t| !t.is_trivia())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Import(Import),
+    Func(Func),
+    Struct(StructDef),
+    Enum(EnumDef),
+    TypeAlias(TypeAlias),
+    Error(ErrorNode),
+}
+
+impl AstNode for Item {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        Some(match node.kind() {
+            NodeKind::Import => Item::Import(Import(node)),
+            NodeKind::Func => Item::Func(Func(node)),
+            NodeKind::Struct => Item::Struct(StructDef(node)),
+            NodeKind::Enum => Item::Enum(EnumDef(node)),
+            NodeKind::TypeAlias => Item::TypeAlias(TypeAlias(node)),
+            NodeKind::Error => Item::Error(ErrorNode(node)),
+            _ => return None,
+        })
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Item::Import(node) => node.syntax(),
+            Item::Func(node) => node.syntax(),
+            Item::Struct(node) => node.syntax(),
+            Item::Enum(node) => node.syntax(),
+            Item::TypeAlias(node) => node.syntax(),
+            Item::Error(node) => node.syntax(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Assign(AssignStmt),
+    Expr(ExprStmt),
+    Return(ReturnStmt),
+    If(IfStmt),
+    While(WhileStmt),
+    For(ForStmt),
+    Error(ErrorNode),
+}
+
+impl AstNode for Stmt {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        Some(match node.kind() {
+            NodeKind::AssignStmt => Stmt::Assign(AssignStmt(node)),
+            NodeKind::ExprStmt => Stmt::Expr(ExprStmt(node)),
+            NodeKind::ReturnStmt => Stmt::Return(ReturnStmt(node)),
+            NodeKind::IfStmt => Stmt::If(IfStmt(node)),
+            NodeKind::WhileStmt => Stmt::While(WhileStmt(node)),
+            NodeKind::ForStmt => Stmt::For(ForStmt(node)),
+            NodeKind::Error => Stmt::Error(ErrorNode(node)),
+            _ => return None,
+        })
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Stmt::Assign(node) => node.syntax(),
+            Stmt::Expr(node) => node.syntax(),
+            Stmt::Return(node) => node.syntax(),
+            Stmt::If(node) => node.syntax(),
+            Stmt::While(node) => node.syntax(),
+            Stmt::For(node) => node.syntax(),
+            Stmt::Error(node) => node.syntax(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Literal),
+    NameRef(NameRef),
+    List(ListExpr),
+    Tuple(TupleExpr),
+    Unary(UnaryExpr),
+    Binary(BinaryExpr),
+    Call(CallExpr),
+    Field(FieldExpr),
+    Index(IndexExpr),
+    Match(MatchExpr),
+    Struct(StructExpr),
+    Lambda(LambdaExpr),
+    Error(ErrorNode),
+}
+
+impl AstNode for Expr {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        Some(match node.kind() {
+            NodeKind::Literal => Expr::Literal(Literal(node)),
+            NodeKind::NameRef => Expr::NameRef(NameRef(node)),
+            NodeKind::ListExpr => Expr::List(ListExpr(node)),
+            NodeKind::TupleExpr => Expr::Tuple(TupleExpr(node)),
+            NodeKind::UnaryExpr => Expr::Unary(UnaryExpr(node)),
+            NodeKind::BinaryExpr => Expr::Binary(BinaryExpr(node)),
+            NodeKind::CallExpr => Expr::Call(CallExpr(node)),
+            NodeKind::FieldExpr => Expr::Field(FieldExpr(node)),
+            NodeKind::IndexExpr => Expr::Index(IndexExpr(node)),
+            NodeKind::MatchExpr => Expr::Match(MatchExpr(node)),
+            NodeKind::StructExpr => Expr::Struct(StructExpr(node)),
+            NodeKind::LambdaExpr => Expr::Lambda(LambdaExpr(node)),
+            NodeKind::Error => Expr::Error(ErrorNode(node)),
+            _ => return None,
+        })
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Literal(node) => node.syntax(),
+            Expr::NameRef(node) => node.syntax(),
+            Expr::List(node) => node.syntax(),
+            Expr::Tuple(node) => node.syntax(),
+            Expr::Unary(node) => node.syntax(),
+            Expr::Binary(node) => node.syntax(),
+            Expr::Call(node) => node.syntax(),
+            Expr::Field(node) => node.syntax(),
+            Expr::Index(node) => node.syntax(),
+            Expr::Match(node) => node.syntax(),
+            Expr::Struct(node) => node.syntax(),
+            Expr::Lambda(node) => node.syntax(),
+            Expr::Error(node) => node.syntax(),
+        }
+    }
+}
+
+impl SourceFile {
+    pub fn items(&self) -> impl Iterator<Item = Item> + use<> {
+        children(&self.0)
+    }
+}
+
+impl Import {
+    pub fn module_name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+}
+
+impl Func {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = Param> + use<> {
+        children(&self.0)
+    }
+
+    /// The type after `->`.
+    pub fn ret(&self) -> Option<TypeExpr> {
+        child(&self.0)
+    }
+
+    pub fn contracts(&self) -> impl Iterator<Item = Contract> + use<> {
+        children(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl Param {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn ty(&self) -> Option<TypeExpr> {
+        child(&self.0)
+    }
+}
+
+impl Contract {
+    /// `requires`, `ensures`, `invariant` or `decreases`.
+    pub fn keyword(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn cond(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl StructDef {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = SyntaxToken> + use<> {
+        self.0
+            .child_tokens()
+            .filter(|t| t.kind() == TokenType::Identifier)
+            .skip(1)
+    }
+}
+
+impl EnumDef {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    /// The parameters and indices of an inductive family.
+    pub fn params(&self) -> impl Iterator<Item = Param> + use<> {
+        children(&self.0)
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = Variant> + use<> {
+        children(&self.0)
+    }
+}
+
+impl Variant {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = SyntaxToken> + use<> {
+        self.0
+            .child_tokens()
+            .filter(|t| t.kind() == TokenType::Identifier)
+            .skip(1)
+    }
+
+    /// The type after `->`, the last type inside the variant.
+    pub fn ret(&self) -> Option<TypeExpr> {
+        token(&self.0, TokenType::ThinArrow)?;
+        children(&self.0).last()
+    }
+}
+
+impl TypeAlias {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn ty(&self) -> Option<TypeExpr> {
+        child(&self.0)
+    }
+
+    /// The condition after `where`, for a refinement type.
+    pub fn refinement(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl TypeExpr {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    /// The arguments of `List(Int)`, the elements of a tuple, or a function
+    /// type's parameters followed by its result.
+    pub fn args(&self) -> impl Iterator<Item = TypeExpr> + use<> {
+        children(&self.0)
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = Stmt> + use<> {
+        children(&self.0)
+    }
+}
+
+impl AssignStmt {
+    pub fn target(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    /// The declared type, in `name: Type = value`.
+    pub fn ty(&self) -> Option<TypeExpr> {
+        child(&self.0)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl ExprStmt {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl ReturnStmt {
+    pub fn value(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl IfStmt {
+    /// The conditions of the `if` and each `elsif`, in order.
+    pub fn conditions(&self) -> impl Iterator<Item = Expr> + use<> {
+        children(&self.0)
+    }
+
+    /// The block of each branch, with the `else` block last if there is one.
+    pub fn blocks(&self) -> impl Iterator<Item = Block> + use<> {
+        children(&self.0)
+    }
+}
+
+impl WhileStmt {
+    pub fn condition(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn contracts(&self) -> impl Iterator<Item = Contract> + use<> {
+        children(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl ForStmt {
+    pub fn variable(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn iterable(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn contracts(&self) -> impl Iterator<Item = Contract> + use<> {
+        children(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl Literal {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+}
+
+impl NameRef {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+}
+
+impl ListExpr {
+    pub fn items(&self) -> impl Iterator<Item = Expr> + use<> {
+        children(&self.0)
+    }
+}
+
+impl TupleExpr {
+    pub fn items(&self) -> impl Iterator<Item = Expr> + use<> {
+        children(&self.0)
+    }
+}
+
+impl UnaryExpr {
+    pub fn op(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+
+    pub fn operand(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl BinaryExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn op(&self) -> Option<SyntaxToken> {
+        // Parentheses around the whole expression are its tokens too.
+        self.0
+            .child_tokens()
+            .find(|t| !t.is_trivia() && !matches!(t.kind(), TokenType::LParen | TokenType::RParen))
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> + use<> {
+        children(&self.0).skip(1)
+    }
+}
+
+impl FieldExpr {
+    pub fn base(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn field(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+}
+
+impl IndexExpr {
+    pub fn base(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn index(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl MatchExpr {
+    pub fn scrutinee(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn arms(&self) -> impl Iterator<Item = MatchArm> + use<> {
+        children(&self.0)
+    }
+}
+
+impl MatchArm {
+    /// The pattern's own node: a wildcard, binding, literal, variant or
+    /// alternatives.
+    pub fn pattern(&self) -> Option<SyntaxNode> {
+        self.0.children().next()
+    }
+
+    /// The expression after `if`, when the arm has a guard.
+    pub fn guard(&self) -> Option<Expr> {
+        token(&self.0, TokenType::If)?;
+        children(&self.0).next()
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        children(&self.0).last()
+    }
+}
+
+impl StructExpr {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldInit> + use<> {
+        children(&self.0)
+    }
+
+    /// The expression after `..`, which supplies the fields not listed.
+    pub fn base(&self) -> Option<Expr> {
+        token(&self.0, TokenType::DotDot)?;
+        children(&self.0).last()
+    }
+}
+
+impl FieldInit {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenType::Identifier)
+    }
+
+    /// The value, which for the shorthand `name` is the name itself.
+    pub fn value(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl LambdaExpr {
+    pub fn params(&self) -> impl Iterator<Item = Param> + use<> {
+        children(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
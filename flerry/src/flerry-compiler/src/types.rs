@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Types as the checkers see them, and the translation of type annotations
+//! into them.
+//!
+//! Type aliases are expanded away, so two types are the same exactly when
+//! they are equal as values once their variables are resolved. Refinement
+//! types are their base type here; their conditions are proved apart.
+
+use crate::ast::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The fixed-width integer types, with their widths in bits and whether
+/// they are signed. Their arithmetic is proved at machine width.
+pub const FIXED_WIDTH: &[(&str, u32, bool)] = &[
+    ("u8", 8, false),
+    ("u16", 16, false),
+    ("u32", 32, false),
+    ("u64", 64, false),
+    ("i8", 8, true),
+    ("i16", 16, true),
+    ("i32", 32, true),
+    ("i64", 64, true),
+];
+
+/// The width and signedness of the fixed-width integer type `name`.
+pub fn fixed_width(name: &str) -> Option<(u32, bool)> {
+    FIXED_WIDTH
+        .iter()
+        .find(|(ty, _, _)| *ty == name)
+        .map(|&(_, width, signed)| (width, signed))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty {
+    /// A type still being inferred, resolved through the inference table.
+    Var(u32),
+    /// A type variable the programmer wrote, `a` in `List(a)`, or one a
+    /// scheme quantifies over. It stands for one unknown type and agrees only
+    /// with itself.
+    Param(String),
+    /// A named type and its arguments: `Int`, `List(Int)`, a struct or an enum.
+    Con(String, Vec<Ty>),
+    Fn(Vec<Ty>, Box<Ty>),
+    /// `(Int, Str)`; the empty tuple is the unit type, the result of functions
+    /// that return nothing.
+    Tuple(Vec<Ty>),
+    /// A type that could not be worked out because of an earlier error. It
+    /// agrees with every type, so one mistake is reported once.
+    Unknown,
+}
+
+impl Ty {
+    pub fn named(name: &str) -> Ty {
+        Ty::Con(name.to_owned(), Vec::new())
+    }
+
+    pub fn int() -> Ty {
+        Ty::named("Int")
+    }
+
+    pub fn float() -> Ty {
+        Ty::named("Float")
+    }
+
+    pub fn str() -> Ty {
+        Ty::named("Str")
+    }
+
+    pub fn bool() -> Ty {
+        Ty::named("Bool")
+    }
+
+    pub fn unit() -> Ty {
+        Ty::Tuple(Vec::new())
+    }
+
+    pub fn list(element: Ty) -> Ty {
+        Ty::Con("List".to_owned(), vec![element])
+    }
+
+    /// The width and signedness of a fixed-width integer type.
+    pub fn fixed_width(&self) -> Option<(u32, bool)> {
+        match self {
+            Ty::Con(name, args) if args.is_empty() => fixed_width(name),
+            _ => None,
+        }
+    }
+
+    /// The type of a literal, taking integers to be `Int`.
+    pub fn of_literal(lit: &Lit) -> Ty {
+        match lit {
+            Lit::Int(_) => Ty::int(),
+            Lit::Float(_) => Ty::float(),
+            Lit::Str(_) => Ty::str(),
+            Lit::Bool(_) => Ty::bool(),
+        }
+    }
+
+    /// Replaces every `Param` named in `substitution`.
+    pub fn substitute(&self, substitution: &HashMap<String, Ty>) -> Ty {
+        match self {
+            Ty::Param(name) => substitution.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Ty::Con(name, args) => Ty::Con(
+                name.clone(),
+                args.iter().map(|arg| arg.substitute(substitution)).collect(),
+            ),
+            Ty::Fn(params, ret) => Ty::Fn(
+                params.iter().map(|param| param.substitute(substitution)).collect(),
+                Box::new(ret.substitute(substitution)),
+            ),
+            Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(|ty| ty.substitute(substitution)).collect()),
+            Ty::Var(_) | Ty::Unknown => self.clone(),
+        }
+    }
+
+    /// Writes the type as it would be annotated, showing variables with `var`.
+    pub fn write(&self, f: &mut dyn fmt::Write, var: &dyn Fn(u32) -> String) -> fmt::Result {
+        match self {
+            Ty::Var(id) => f.write_str(&var(*id)),
+            Ty::Param(name) => f.write_str(name),
+            Ty::Con(name, args) => {
+                f.write_str(name)?;
+                if !args.is_empty() {
+                    f.write_char('(')?;
+                    write_list(f, args, var)?;
+                    f.write_char(')')?;
+                }
+                Ok(())
+            }
+            Ty::Fn(params, ret) => {
+                // Parenthesized like the annotations the pretty-printer writes.
+                if let [param @ (Ty::Con(..) | Ty::Param(_) | Ty::Var(_) | Ty::Unknown)] = params.as_slice() {
+                    param.write(f, var)?;
+                } else {
+                    f.write_char('(')?;
+                    write_list(f, params, var)?;
+                    f.write_char(')')?;
+                }
+                f.write_str(" -> ")?;
+                ret.write(f, var)
+            }
+            Ty::Tuple(elements) => {
+                f.write_char('(')?;
+                write_list(f, elements, var)?;
+                f.write_char(')')
+            }
+            Ty::Unknown => f.write_char('_'),
+        }
+    }
+}
+
+fn write_list(f: &mut dyn fmt::Write, types: &[Ty], var: &dyn Fn(u32) -> String) -> fmt::Result {
+    for (i, ty) in types.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        ty.write(f, var)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, &|_| "_".to_owned())
+    }
+}
+
+/// A restriction on the types a variable may stand for, from the operators
+/// applied to it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Class {
+    /// `Int` or a fixed-width integer: `%` and the bitwise operators.
+    Integral,
+    /// `Int`, `Nat`, `Float` or a fixed-width integer: arithmetic and
+    /// negation.
+    Num,
+    /// A number or `Str`: ordering comparisons.
+    Ord,
+}
+
+impl Class {
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Integral => "Integral",
+            Class::Num => "Num",
+            Class::Ord => "Ord",
+        }
+    }
+
+    /// The types in the class, in the order defaulting tries them.
+    pub fn members(self) -> &'static [&'static str] {
+        match self {
+            Class::Integral => &["Int", "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64"],
+            Class::Num => &[
+                "Int", "Float", "Nat", "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64",
+            ],
+            Class::Ord => &[
+                "Int", "Float", "Str", "Nat", "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64",
+            ],
+        }
+    }
+
+    /// How the class reads in "expected ..., found `Str`".
+    pub fn describe(self) -> &'static str {
+        match self {
+            Class::Integral => "an integer",
+            Class::Num => "a number",
+            Class::Ord => "a number or `Str`",
+        }
+    }
+
+    pub fn contains(self, ty: &Ty) -> bool {
+        matches!(ty, Ty::Con(name, args) if args.is_empty() && self.members().contains(&name.as_str()))
+    }
+
+    /// The class of a variable restricted by both.
+    pub fn meet(self, other: Class) -> Class {
+        self.min(other)
+    }
+}
+
+/// A type with variables that every use fills in afresh:
+/// `(a, a) -> a where a: Num`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scheme {
+    /// The quantified variables, each with the class it is restricted to.
+    pub params: Vec<(String, Option<Class>)>,
+    pub ty: Ty,
+}
+
+impl Scheme {
+    /// A scheme for a type without variables of its own.
+    pub fn mono(ty: Ty) -> Scheme {
+        Scheme { params: Vec::new(), ty }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ty)?;
+        let bounds: Vec<String> = self
+            .params
+            .iter()
+            .filter_map(|(name, class)| Some(format!("{name}: {}", (*class)?.name())))
+            .collect();
+        if !bounds.is_empty() {
+            write!(f, " where {}", bounds.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The type aliases of a module, for expanding annotations.
+#[derive(Default)]
+pub struct Aliases<'m> {
+    aliases: HashMap<&'m str, &'m TypeAlias>,
+}
+
+impl<'m> Aliases<'m> {
+    pub fn new(module: &'m Module) -> Aliases<'m> {
+        let aliases = module
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Type(alias) => Some((alias.name.name.as_str(), alias)),
+                _ => None,
+            })
+            .collect();
+        Aliases { aliases }
+    }
+
+    /// The type an annotation denotes, given the number of arguments each
+    /// type in scope takes. Names that are not types, types applied to the
+    /// wrong number of arguments and aliases that expand into themselves give
+    /// `Unknown`; all are reported when annotations are checked.
+    pub fn lower(&self, ty: &TypeExpr, types: &dyn Fn(&str) -> Option<usize>) -> Ty {
+        self.lower_in(ty, types, &mut Vec::new())
+    }
+
+    fn lower_in(&self, ty: &TypeExpr, types: &dyn Fn(&str) -> Option<usize>, expanding: &mut Vec<&'m str>) -> Ty {
+        match &ty.kind {
+            TypeExprKind::Named { name, args } => {
+                let args: Vec<Ty> = args.iter().map(|arg| self.lower_in(arg, types, expanding)).collect();
+                if let Some((name, alias)) = self.aliases.get_key_value(name.name.as_str()) {
+                    if expanding.contains(name) || alias.params.len() != args.len() {
+                        return Ty::Unknown;
+                    }
+                    expanding.push(name);
+                    let ty = self.lower_in(&alias.ty, types, expanding);
+                    expanding.pop();
+                    return ty;
+                }
+                if types(&name.name) == Some(args.len()) {
+                    Ty::Con(name.name.clone(), args)
+                } else if name.name.starts_with(|c: char| c.is_lowercase() || c == '_') {
+                    // `int` is reported as a misspelt `Int`, not taken for a
+                    // type variable.
+                    let mut capitalized = name.name.clone();
+                    capitalized[..1].make_ascii_uppercase();
+                    if name.name.len() > 1 && types(&capitalized).is_some() {
+                        return Ty::Unknown;
+                    }
+                    Ty::Param(name.name.clone())
+                } else {
+                    Ty::Unknown
+                }
+            }
+            TypeExprKind::Function { params, ret } => Ty::Fn(
+                params
+                    .iter()
+                    .map(|param| self.lower_in(param, types, expanding))
+                    .collect(),
+                Box::new(self.lower_in(ret, types, expanding)),
+            ),
+            TypeExprKind::Tuple(elements) => {
+                Ty::Tuple(elements.iter().map(|ty| self.lower_in(ty, types, expanding)).collect())
+            }
+            TypeExprKind::Bind { ty, .. } => self.lower_in(ty, types, expanding),
+            // Types that depend on values are left to the core calculus.
+            TypeExprKind::Expr(_) => Ty::Unknown,
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Unification of values with metavariables in them, which is how implicit
+//! arguments are worked out.
+//!
+//! A metavariable applied to distinct variables, `?n(x, y) = v`, has one
+//! most general solution: `(x, y) => v`, as long as `v` mentions no other
+//! variable and not `?n` itself. That is Miller's pattern fragment. A
+//! problem outside it, such as `?n + ?m = 7`, may come within it once
+//! other metavariables are solved, so it is postponed rather than failed.
+
+use crate::kernel::{Arm, Globals, Neutral, Term, Value};
+use std::rc::Rc;
+
+/// A problem put off until more metavariables are solved.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub level: usize,
+    pub a: Value,
+    pub b: Value,
+}
+
+/// Two values that no solution of the metavariables in them can make
+/// equal.
+#[derive(Debug)]
+pub struct Mismatch;
+
+/// A metavariable, with the name of what it stands for.
+type Meta = (usize, Rc<str>);
+
+/// Makes `a` and `b` equal under `level` binders by solving metavariables,
+/// adding what cannot be decided yet to `postponed`.
+pub fn unify(
+    globals: &Globals,
+    level: usize,
+    a: &Value,
+    b: &Value,
+    postponed: &mut Vec<Problem>,
+) -> Result<(), Mismatch> {
+    let a = globals.force(a);
+    let b = globals.force(b);
+    if globals.conv(level, &a, &b) {
+        return Ok(());
+    }
+    if let Some((meta, spine)) = flex(&a) {
+        return solve(globals, level, meta, &spine, &b, postponed);
+    }
+    if let Some((meta, spine)) = flex(&b) {
+        return solve(globals, level, meta, &spine, &a, postponed);
+    }
+    match (&a, &b) {
+        (Value::Pi(_, a1, b1), Value::Pi(_, a2, b2)) | (Value::Sigma(_, a1, b1), Value::Sigma(_, a2, b2)) => {
+            unify(globals, level, a1, a2, postponed)?;
+            let var = Value::var(level);
+            let b1 = globals.instantiate(b1, var.clone());
+            let b2 = globals.instantiate(b2, var);
+            unify(globals, level + 1, &b1, &b2, postponed)
+        }
+        (Value::Lam(_, body), f) | (f, Value::Lam(_, body)) => {
+            let var = Value::var(level);
+            let body = globals.instantiate(body, var.clone());
+            unify(globals, level + 1, &body, &globals.apply(f.clone(), var), postponed)
+        }
+        (Value::Pair(a1, b1), Value::Pair(a2, b2)) => {
+            unify(globals, level, a1, a2, postponed)?;
+            unify(globals, level, b1, b2, postponed)
+        }
+        (Value::Con(n1, args1), Value::Con(n2, args2)) if n1 == n2 && args1.len() == args2.len() => {
+            for (a, b) in args1.iter().zip(args2) {
+                unify(globals, level, a, b, postponed)?;
+            }
+            Ok(())
+        }
+        (Value::Sum(..), _) | (_, Value::Sum(..)) => unify_sums(globals, level, &a, &b, postponed),
+        (Value::Neutral(Neutral::App(f1, a1)), Value::Neutral(Neutral::App(f2, a2))) => {
+            let (f1, f2) = (Value::Neutral((**f1).clone()), Value::Neutral((**f2).clone()));
+            unify(globals, level, &f1, &f2, postponed)?;
+            unify(globals, level, a1, a2, postponed)
+        }
+        (Value::Neutral(Neutral::Call(n1, args1)), Value::Neutral(Neutral::Call(n2, args2)))
+            if n1 == n2 && args1.len() == args2.len() =>
+        {
+            for (a, b) in args1.iter().zip(args2) {
+                unify(globals, level, a, b, postponed)?;
+            }
+            Ok(())
+        }
+        (Value::Neutral(Neutral::Op(op1, a1, b1)), Value::Neutral(Neutral::Op(op2, a2, b2))) if op1 == op2 => {
+            unify(globals, level, a1, a2, postponed)?;
+            unify(globals, level, b1, b2, postponed)
+        }
+        _ => postpone(globals, level, a, b, postponed),
+    }
+}
+
+/// Postpones the problem if a metavariable might still make the two values
+/// equal, or fails it.
+fn postpone(globals: &Globals, level: usize, a: Value, b: Value, postponed: &mut Vec<Problem>) -> Result<(), Mismatch> {
+    if has_metas(&globals.quote(level, &a)) || has_metas(&globals.quote(level, &b)) {
+        postponed.push(Problem { level, a, b });
+        return Ok(());
+    }
+    Err(Mismatch)
+}
+
+fn unify_sums(
+    globals: &Globals,
+    level: usize,
+    a: &Value,
+    b: &Value,
+    postponed: &mut Vec<Problem>,
+) -> Result<(), Mismatch> {
+    let (Some((c1, atoms1)), Some((c2, atoms2))) = (a.as_sum(), b.as_sum()) else {
+        return Err(Mismatch);
+    };
+    let (atoms1, atoms2) = globals.cancel(level, atoms1, atoms2);
+    let common = c1.min(c2);
+    let (c1, c2) = (c1 - common, c2 - common);
+    match (c1, atoms1.as_slice(), c2, atoms2.as_slice()) {
+        (0, [atom], _, _) if flex(&Value::Neutral(atom.clone())).is_some() => unify(
+            globals,
+            level,
+            &Value::Neutral(atom.clone()),
+            &Value::sum(c2, atoms2),
+            postponed,
+        ),
+        (_, _, 0, [atom]) if flex(&Value::Neutral(atom.clone())).is_some() => unify(
+            globals,
+            level,
+            &Value::sum(c1, atoms1),
+            &Value::Neutral(atom.clone()),
+            postponed,
+        ),
+        // A sum is never less than its constant.
+        (_, [], _, _) if c2 > c1 => Err(Mismatch),
+        (_, _, _, []) if c1 > c2 => Err(Mismatch),
+        _ => postpone(
+            globals,
+            level,
+            Value::sum(c1, atoms1),
+            Value::sum(c2, atoms2),
+            postponed,
+        ),
+    }
+}
+
+/// The unsolved metavariable `value` is stuck on and the arguments it is
+/// applied to, if it is one applied to arguments.
+fn flex(value: &Value) -> Option<(Meta, Vec<Value>)> {
+    let Value::Neutral(mut neutral) = value.clone() else {
+        return None;
+    };
+    let mut spine = Vec::new();
+    loop {
+        match neutral {
+            Neutral::Meta(meta, name) => {
+                spine.reverse();
+                return Some(((meta, name), spine));
+            }
+            Neutral::App(f, arg) => {
+                spine.push(*arg);
+                neutral = *f;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Solves `?meta(spine) = rhs`, if the spine is distinct variables.
+fn solve(
+    globals: &Globals,
+    level: usize,
+    (meta, name): Meta,
+    spine: &[Value],
+    rhs: &Value,
+    postponed: &mut Vec<Problem>,
+) -> Result<(), Mismatch> {
+    let mut vars = Vec::new();
+    for arg in spine {
+        match globals.force(arg) {
+            Value::Neutral(Neutral::Var(var)) if !vars.contains(&var) => vars.push(var),
+            _ => {
+                let flex = Value::Neutral(spine.iter().fold(Neutral::Meta(meta, name), |f, arg| {
+                    Neutral::App(Box::new(f), Box::new(arg.clone()))
+                }));
+                postponed.push(Problem {
+                    level,
+                    a: flex,
+                    b: rhs.clone(),
+                });
+                return Ok(());
+            }
+        }
+    }
+    let rhs = globals.quote(level, rhs);
+    // The variable with index `i` under `depth` binders is at level
+    // `level - 1 - (i - depth)` outside them, and becomes the parameter of
+    // the solution in the position of that level in the spine.
+    let renamed = rename(&rhs, 0, meta, &|index| {
+        let var = level - 1 - index;
+        vars.iter()
+            .position(|v| *v == var)
+            .map(|position| vars.len() - 1 - position)
+    })
+    .ok_or(Mismatch)?;
+    let solution = (0..vars.len()).fold(renamed, |body, i| Term::Lam(format!("x{i}"), Box::new(body)));
+    globals.solve(meta, globals.eval(&solution, &[]));
+    Ok(())
+}
+
+/// `term` with each free variable renamed by `map`, or `None` if one has
+/// no new name or `meta` occurs in it.
+fn rename(term: &Term, depth: usize, meta: usize, map: &dyn Fn(usize) -> Option<usize>) -> Option<Term> {
+    let go = |term: &Term, bound: usize| rename(term, depth + bound, meta, map).map(Box::new);
+    Some(match term {
+        Term::Var(index) if *index < depth => Term::Var(*index),
+        Term::Var(index) => Term::Var(map(index - depth)? + depth),
+        Term::Meta(m, _) if *m == meta => return None,
+        Term::Global(_)
+        | Term::Universe(_)
+        | Term::Base(_)
+        | Term::Lit(_)
+        | Term::Nat
+        | Term::NatLit(_)
+        | Term::Meta(..) => term.clone(),
+        Term::Pi(name, a, b) => Term::Pi(name.clone(), go(a, 0)?, go(b, 1)?),
+        Term::Sigma(name, a, b) => Term::Sigma(name.clone(), go(a, 0)?, go(b, 1)?),
+        Term::Lam(name, body) => Term::Lam(name.clone(), go(body, 1)?),
+        Term::App(a, b) => Term::App(go(a, 0)?, go(b, 0)?),
+        Term::Pair(a, b) => Term::Pair(go(a, 0)?, go(b, 0)?),
+        Term::Add(a, b) => Term::Add(go(a, 0)?, go(b, 0)?),
+        Term::Op(op, a, b) => Term::Op(*op, go(a, 0)?, go(b, 0)?),
+        Term::Let(name, value, body) => Term::Let(name.clone(), go(value, 0)?, go(body, 1)?),
+        Term::First(pair) => Term::First(go(pair, 0)?),
+        Term::Second(pair) => Term::Second(go(pair, 0)?),
+        Term::Case(scrutinee, arms, default) => {
+            let arms = arms
+                .iter()
+                .map(|arm| {
+                    Some(Arm {
+                        variant: arm.variant.clone(),
+                        fields: arm.fields.clone(),
+                        body: *go(&arm.body, arm.fields.len())?,
+                    })
+                })
+                .collect::<Option<_>>()?;
+            let default = match default {
+                Some((name, body)) => Some((name.clone(), go(body, 1)?)),
+                None => None,
+            };
+            Term::Case(go(scrutinee, 0)?, arms, default)
+        }
+    })
+}
+
+/// Whether an unsolved metavariable occurs in `term`.
+pub fn has_metas(term: &Term) -> bool {
+    !metas(term).is_empty()
+}
+
+/// The unsolved metavariables that occur in `term`.
+pub fn metas(term: &Term) -> Vec<usize> {
+    let mut found = Vec::new();
+    collect_metas(term, &mut found);
+    found
+}
+
+fn collect_metas(term: &Term, found: &mut Vec<usize>) {
+    match term {
+        Term::Meta(meta, _) => found.push(*meta),
+        Term::Var(_)
+        | Term::Global(_)
+        | Term::Universe(_)
+        | Term::Base(_)
+        | Term::Lit(_)
+        | Term::Nat
+        | Term::NatLit(_) => {}
+        Term::Pi(_, a, b)
+        | Term::Sigma(_, a, b)
+        | Term::Let(_, a, b)
+        | Term::App(a, b)
+        | Term::Pair(a, b)
+        | Term::Add(a, b)
+        | Term::Op(_, a, b) => {
+            collect_metas(a, found);
+            collect_metas(b, found);
+        }
+        Term::Lam(_, term) | Term::First(term) | Term::Second(term) => collect_metas(term, found),
+        Term::Case(scrutinee, arms, default) => {
+            collect_metas(scrutinee, found);
+            for arm in arms {
+                collect_metas(&arm.body, found);
+            }
+            if let Some((_, body)) = default {
+                collect_metas(body, found);
+            }
+        }
+    }
+}
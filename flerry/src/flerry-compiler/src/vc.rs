@@ -0,0 +1,1150 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Verification conditions: a function body as guarded commands, and the
+//! formula each check in it needs proved.
+//!
+//! Integers and booleans are tracked as formulas over the parameters;
+//! anything else, and every call, is a fresh unknown value named after its
+//! text, so a counterexample reads `xs[i] = 3`. A call assumes what the
+//! callee's refinement types and `ensures` clauses say about its result
+//! and checks what its types and `requires` clauses say about its
+//! arguments; a loop body is checked from any state its
+//! condition allows, having forgotten the variables it assigns except what
+//! their declared types say. A body assumes its own `requires` clauses
+//! and checks its `ensures` clauses wherever it returns.
+//!
+//! A loop's `invariant` clauses are checked where it is reached and then
+//! assumed of every state it runs from. Each path through an iteration,
+//! told apart by the branches it takes at the `if`s of the body, checks
+//! them again at its end, and that the `decreases` measure it started with
+//! was at least zero and has shrunk.
+//!
+//! Fixed-width integers are bit-vectors of their width, and arithmetic on
+//! them is checked not to overflow: the operands are widened to twice the
+//! width, where the exact result fits, and it must be within the range of
+//! the type. Arithmetic in contract clauses wraps around unchecked.
+//! Outside them, a division or remainder of integers is checked not to be
+//! by zero, and the bitwise operators and shifts are bit-vector operations;
+//! on an `Int` they are unknown values.
+//!
+//! The condition for a check is the weakest precondition of the body for
+//! it, taking the checks before it to hold: each failure is reported on
+//! its own rather than again at every later check.
+
+use crate::ast::*;
+use crate::contracts::substitute;
+use crate::pretty::{self, type_expr};
+use crate::refine::{Refinements, bit_op, comparison};
+use crate::visit::{Visitor, walk_stmt};
+use flerry_core::Span;
+use flerry_prover::{Bits, Cmp, Sort, Term};
+use std::collections::{HashMap, VecDeque};
+
+/// The most paths through a loop body checked on their own; a body with
+/// more has its clauses checked once for all of them.
+const MAX_PATHS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Assume(Term),
+    /// A condition that must hold here, the check with this index.
+    Assert(Term, usize),
+    Assign(String, Term),
+    /// Forgets the value of a variable.
+    Havoc(String, Sort),
+    Seq(Vec<Command>),
+    /// Any one of the commands.
+    Choice(Vec<Command>),
+}
+
+/// Where a value is checked against a refinement type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Site {
+    /// Assigned to a local declared with the type at `declared`.
+    Assignment { declared: Span },
+    /// Passed for the parameter `param` of `function`.
+    Argument { function: String, param: Ident },
+    /// Returned from a function whose return type is at `declared`.
+    Return { declared: Span },
+}
+
+/// A condition that must hold at a point in a function body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check<'m> {
+    /// The value, call or return the condition is checked at.
+    pub span: Span,
+    /// The condition, about the values there: `x - 1 > 0`.
+    pub claim: Term,
+    pub kind: CheckKind<'m>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckKind<'m> {
+    /// A value of a refinement type or `Nat`: its text, `x - 1`, and the
+    /// type as written, `Index(n)`, with the outermost refinement type it
+    /// comes from.
+    Refinement {
+        value: String,
+        ty: String,
+        refinement: Option<&'m TypeAlias>,
+        site: Site,
+    },
+    /// A `requires` clause of the function called, and its text with the
+    /// arguments of the call in place of the parameters: `0 > 0`.
+    Requires {
+        function: String,
+        clause: &'m Contract,
+        instance: String,
+    },
+    /// An `ensures` clause, where the function returns.
+    Ensures { clause: &'m Contract },
+    /// Arithmetic on fixed-width integers: its text, `x * y`, and the type
+    /// its result must fit in, `u64`.
+    Overflow { value: String, ty: String },
+    /// A division or remainder on integers: its text, `x / y`, and that
+    /// of its divisor, which must not be zero.
+    DivisionByZero { value: String, divisor: String },
+    /// An `invariant` or `decreases` clause of the loop whose header is at
+    /// `header`, at the end of the iterations taking `path`.
+    Loop {
+        clause: &'m Contract,
+        obligation: LoopObligation,
+        header: Span,
+        path: Vec<Turn<'m>>,
+    },
+}
+
+/// What a loop clause is checked for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LoopObligation {
+    /// The invariant holds when the loop is reached.
+    Initiation,
+    /// An iteration starting with the invariant ends with it.
+    Preservation,
+    /// The measure is at least zero when an iteration starts, and smaller
+    /// when it ends.
+    Termination,
+}
+
+impl LoopObligation {
+    pub fn name(self) -> &'static str {
+        match self {
+            LoopObligation::Initiation => "initiation",
+            LoopObligation::Preservation => "preservation",
+            LoopObligation::Termination => "termination",
+        }
+    }
+}
+
+/// The condition of a branch of an `if`, and whether it holds on a path
+/// through a loop body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn<'m> {
+    pub cond: &'m Expr,
+    pub holds: bool,
+}
+
+/// A path through a loop body to its end: the branch it takes at each
+/// `if` it reaches, counting the `else` last, and the turns deciding them.
+#[derive(Debug, Clone, Default)]
+struct Path<'m> {
+    choices: Vec<usize>,
+    turns: Vec<Turn<'m>>,
+}
+
+/// A function body translated for verification.
+pub struct Body<'m> {
+    pub command: Command,
+    pub checks: Vec<Check<'m>>,
+}
+
+impl Body<'_> {
+    /// The formula that proves check `target`.
+    pub fn condition(&self, target: usize) -> Term {
+        wp(&self.command, Term::Bool(true), target, &mut 0)
+    }
+}
+
+/// The weakest precondition of `command` for `post`, where check `target`
+/// must hold and the others are assumed.
+pub fn wp(command: &Command, post: Term, target: usize, fresh: &mut usize) -> Term {
+    match command {
+        Command::Assume(term) => Term::implies(term.clone(), post),
+        Command::Assert(term, index) if *index == target => Term::and([term.clone(), post]),
+        Command::Assert(term, _) => Term::implies(term.clone(), post),
+        Command::Assign(name, value) => post.substitute(&HashMap::from([(name.clone(), value.clone())])),
+        Command::Havoc(name, _) => {
+            *fresh += 1;
+            rename(&post, name, &format!("{name}#{fresh}"))
+        }
+        Command::Seq(commands) => commands
+            .iter()
+            .rev()
+            .fold(post, |post, command| wp(command, post, target, fresh)),
+        Command::Choice(commands) => Term::and(
+            commands
+                .iter()
+                .map(|command| wp(command, post.clone(), target, fresh))
+                .collect::<Vec<_>>(),
+        ),
+    }
+}
+
+/// `command` with its checks assumed instead.
+fn assumed(command: Command) -> Command {
+    match command {
+        Command::Assert(term, _) => Command::Assume(term),
+        Command::Seq(commands) => Command::Seq(commands.into_iter().map(assumed).collect()),
+        Command::Choice(commands) => Command::Choice(commands.into_iter().map(assumed).collect()),
+        command => command,
+    }
+}
+
+/// `term` with the variable `from` renamed to `to`, of whatever sort it is
+/// used at.
+fn rename(term: &Term, from: &str, to: &str) -> Term {
+    match term {
+        Term::Var(name, sort) if name == from => Term::var(to, *sort),
+        term => term.map(&mut |child| rename(child, from, to)),
+    }
+}
+
+/// Translates the body of `func`, whose callees are `functions`.
+pub fn translate<'m>(func: &'m Func, functions: &HashMap<&str, &'m Func>, refinements: &Refinements<'m>) -> Body<'m> {
+    let mut translator = Translator {
+        refinements,
+        functions,
+        func,
+        scopes: vec![HashMap::new()],
+        declared: HashMap::new(),
+        entry: None,
+        contract: false,
+        choices: VecDeque::new(),
+        checks: Vec::new(),
+        fresh: 0,
+    };
+    let mut commands = Vec::new();
+    for param in &func.params {
+        let sort = param
+            .ty
+            .as_ref()
+            .and_then(|ty| refinements.sort(ty))
+            .unwrap_or(Sort::Int);
+        translator.scopes[0].insert(param.name.name.clone(), Term::var(&param.name.name, sort));
+        if let Some(ty) = &param.ty {
+            translator.declared.insert(param.name.name.clone(), ty);
+        }
+    }
+    for param in &func.params {
+        if let Some(ty) = &param.ty {
+            let value = translator.scopes[0][&param.name.name].clone();
+            if let Some(condition) = refinements.condition(ty, &value, &translator.scope()) {
+                commands.push(Command::Assume(condition.term));
+            }
+        }
+        // The return type and `old` refer to the parameters as they were passed.
+        if func.ret.is_some()
+            || func
+                .contracts
+                .iter()
+                .any(|contract| contract.kind == ContractKind::Ensures)
+        {
+            let value = translator.scopes[0][&param.name.name].clone();
+            commands.push(Command::Assume(Term::eq(old(&value), value)));
+        }
+    }
+    for contract in &func.contracts {
+        if contract.kind == ContractKind::Requires {
+            let scope = translator.scope();
+            let cond = translator.clause(&contract.cond, Sort::Bool, scope, None, &mut commands);
+            commands.push(Command::Assume(cond));
+        }
+    }
+    translator.block(&func.body, &mut commands);
+    if falls_through(&func.body) {
+        translator.ensures(None, func.name.span, &mut commands);
+    }
+    Body {
+        command: Command::Seq(commands),
+        checks: translator.checks,
+    }
+}
+
+/// Whether running `block` can reach its end, rather than return before.
+pub fn falls_through(block: &Block) -> bool {
+    match block.stmts.last().map(|stmt| &stmt.kind) {
+        Some(StmtKind::Return(_)) => false,
+        Some(StmtKind::If {
+            branches,
+            else_block: Some(else_block),
+        }) => branches.iter().any(|(_, block)| falls_through(block)) || falls_through(else_block),
+        _ => true,
+    }
+}
+
+/// The paths through `block` that reach its end, or `None` if there are
+/// more than `MAX_PATHS`. Loops inside it are single steps.
+fn paths(block: &Block) -> Option<Vec<Path<'_>>> {
+    let mut paths = vec![Path::default()];
+    for stmt in &block.stmts {
+        match &stmt.kind {
+            StmtKind::Return(_) => return Some(Vec::new()),
+            StmtKind::If { branches, else_block } => {
+                let mut next = Vec::new();
+                for choice in 0..=branches.len() {
+                    let mut turns: Vec<Turn> = branches[..choice]
+                        .iter()
+                        .map(|(cond, _)| Turn { cond, holds: false })
+                        .collect();
+                    let rest = match (branches.get(choice), else_block) {
+                        (Some((cond, block)), _) => {
+                            turns.push(Turn { cond, holds: true });
+                            self::paths(block)?
+                        }
+                        (None, Some(block)) => self::paths(block)?,
+                        (None, None) => vec![Path::default()],
+                    };
+                    for path in &paths {
+                        for rest in &rest {
+                            let mut path = path.clone();
+                            path.choices.push(choice);
+                            path.choices.extend(&rest.choices);
+                            path.turns.extend(turns.iter().cloned());
+                            path.turns.extend(rest.turns.iter().cloned());
+                            next.push(path);
+                        }
+                    }
+                }
+                if next.len() > MAX_PATHS {
+                    return None;
+                }
+                paths = next;
+            }
+            _ => {}
+        }
+    }
+    Some(paths)
+}
+
+/// The variable holding the value `var` had when the function was called.
+fn old(var: &Term) -> Term {
+    match var {
+        Term::Var(name, sort) => Term::var(format!("old({name})"), *sort),
+        _ => unreachable!("parameters are variables"),
+    }
+}
+
+struct Translator<'a, 'm> {
+    refinements: &'a Refinements<'m>,
+    functions: &'a HashMap<&'a str, &'m Func>,
+    func: &'m Func,
+    /// The variable each name in scope stands for, innermost scope last.
+    /// The first scope is the whole function.
+    scopes: Vec<HashMap<String, Term>>,
+    /// The declared type of each variable.
+    declared: HashMap<String, &'m TypeExpr>,
+    /// What the names in `old(...)` stand for, in an `ensures` clause.
+    entry: Option<HashMap<String, Term>>,
+    /// Whether a contract clause is being translated, where calls are only
+    /// unknown values so that clauses referring to each other terminate.
+    contract: bool,
+    /// The branches to take at the next `if`s, following one path through
+    /// a loop body; any branch once they run out.
+    choices: VecDeque<usize>,
+    checks: Vec<Check<'m>>,
+    fresh: usize,
+}
+
+impl<'m> Translator<'_, 'm> {
+    /// Every name in scope and the variable it stands for.
+    fn scope(&self) -> HashMap<String, Term> {
+        let mut scope = HashMap::new();
+        for names in &self.scopes {
+            scope.extend(names.iter().map(|(name, var)| (name.clone(), var.clone())));
+        }
+        scope
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Term> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// A fresh variable name, shown as `name`.
+    fn fresh(&mut self, name: &str) -> String {
+        self.fresh += 1;
+        format!("{name}#{}", self.fresh)
+    }
+
+    /// An unknown value, named after the text of `expr`.
+    fn opaque(&mut self, expr: &Expr, sort: Option<Sort>, out: &mut Vec<Command>) -> Term {
+        let name = pretty::expr(expr);
+        let sort = sort.unwrap_or(Sort::Int);
+        out.push(Command::Havoc(name.clone(), sort));
+        Term::var(name, sort)
+    }
+
+    /// Checks `value` against `ty` if it is a refinement type or `Nat`.
+    fn check(
+        &mut self,
+        ty: &'m TypeExpr,
+        value: &Term,
+        expr: &Expr,
+        scope: &HashMap<String, Term>,
+        site: Site,
+        out: &mut Vec<Command>,
+    ) {
+        let Some(condition) = self.refinements.condition(ty, value, scope) else {
+            return;
+        };
+        let kind = CheckKind::Refinement {
+            value: pretty::expr(expr),
+            ty: type_expr(ty),
+            refinement: condition.refinement,
+            site,
+        };
+        self.assert(expr.span, condition.term, kind, out);
+    }
+
+    fn assert(&mut self, span: Span, claim: Term, kind: CheckKind<'m>, out: &mut Vec<Command>) {
+        out.push(Command::Assert(claim.clone(), self.checks.len()));
+        self.checks.push(Check { span, claim, kind });
+    }
+
+    /// The value of a contract clause, of sort `sort`, its names standing
+    /// for the terms in `scope` and those in `old(...)` for the terms in
+    /// `entry`.
+    fn clause(
+        &mut self,
+        cond: &'m Expr,
+        sort: Sort,
+        scope: HashMap<String, Term>,
+        entry: Option<HashMap<String, Term>>,
+        out: &mut Vec<Command>,
+    ) -> Term {
+        let scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+        let entry = std::mem::replace(&mut self.entry, entry);
+        let contract = std::mem::replace(&mut self.contract, true);
+        let term = self.expr(cond, Some(sort), out);
+        self.scopes = scopes;
+        self.entry = entry;
+        self.contract = contract;
+        term
+    }
+
+    /// Checks the `ensures` clauses of the function where it returns
+    /// `result`, or falls off the end of its body.
+    fn ensures(&mut self, result: Option<Term>, span: Span, out: &mut Vec<Command>) {
+        let entry: HashMap<String, Term> = self
+            .func
+            .params
+            .iter()
+            .map(|param| (param.name.name.clone(), old(&self.scopes[0][&param.name.name])))
+            .collect();
+        for contract in &self.func.contracts {
+            if contract.kind != ContractKind::Ensures {
+                continue;
+            }
+            let mut scope = self.scope();
+            let result = result.clone().unwrap_or_else(|| {
+                let name = self.fresh("result");
+                out.push(Command::Havoc(name.clone(), Sort::Int));
+                Term::int_var(name)
+            });
+            scope.insert("result".to_owned(), result);
+            let claim = self.clause(&contract.cond, Sort::Bool, scope, Some(entry.clone()), out);
+            self.assert(span, claim, CheckKind::Ensures { clause: contract }, out);
+        }
+    }
+
+    fn block(&mut self, block: &'m Block, out: &mut Vec<Command>) {
+        for stmt in &block.stmts {
+            self.stmt(stmt, out);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'m Stmt, out: &mut Vec<Command>) {
+        match &stmt.kind {
+            StmtKind::Assign { target, ty, value } => {
+                let ExprKind::Ident(name) = &target.kind else {
+                    self.expr(target, None, out);
+                    self.expr(value, None, out);
+                    return;
+                };
+                let var = self.variable(&name.name);
+                let Term::Var(var_name, _) = &var else {
+                    unreachable!("locals are variables")
+                };
+                let var_name = var_name.clone();
+                if let Some(ty) = ty {
+                    self.declared.insert(var_name.clone(), ty);
+                }
+                let declared = self.declared.get(&var_name).copied();
+                let sort = declared.and_then(|ty| self.refinements.sort(ty));
+                let value_term = self.expr(value, sort, out);
+                if let Some(declared) = declared {
+                    let scope = self.scope();
+                    let site = Site::Assignment {
+                        declared: declared.span,
+                    };
+                    self.check(declared, &value_term, value, &scope, site, out);
+                }
+                self.bind(&name.name, Term::var(&var_name, value_term.sort()));
+                out.push(Command::Assign(var_name, value_term));
+            }
+            StmtKind::Expr(expr) => {
+                self.expr(expr, None, out);
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    let sort = self.func.ret.as_ref().and_then(|ty| self.refinements.sort(ty));
+                    let term = self.expr(value, sort, out);
+                    if let Some(ret) = &self.func.ret {
+                        let scope = self
+                            .func
+                            .params
+                            .iter()
+                            .map(|param| (param.name.name.clone(), old(&self.scopes[0][&param.name.name])))
+                            .collect();
+                        let site = Site::Return { declared: ret.span };
+                        self.check(ret, &term, value, &scope, site, out);
+                    }
+                    self.ensures(Some(term), stmt.span, out);
+                } else {
+                    self.ensures(None, stmt.span, out);
+                }
+                out.push(Command::Assume(Term::Bool(false)));
+            }
+            StmtKind::If { branches, else_block } => match self.choices.pop_front() {
+                Some(choice) => self.taken(branches, else_block.as_ref(), choice, out),
+                None => self.branches(branches, else_block.as_ref(), out),
+            },
+            StmtKind::While { cond, contracts, body } => {
+                let header = Span::new(stmt.span.start, cond.span.end);
+                self.initiation(contracts, header, out);
+                self.forget(body, out);
+                self.invariants(contracts, out);
+                let cond = self.expr(cond, Some(Sort::Bool), out);
+                let start = vec![Command::Assume(cond.clone())];
+                let mut iterations = self.iterations(contracts, header, body, start, HashMap::new());
+                iterations.push(Command::Assume(!cond));
+                out.push(Command::Choice(iterations));
+            }
+            StmtKind::For {
+                var,
+                iter,
+                contracts,
+                body,
+            } => {
+                let header = Span::new(stmt.span.start, iter.span.end);
+                self.expr(iter, None, out);
+                self.initiation(contracts, header, out);
+                self.forget(body, out);
+                self.invariants(contracts, out);
+                let element = self.fresh(&var.name);
+                let start = vec![Command::Havoc(element.clone(), Sort::Int)];
+                let bound = HashMap::from([(var.name.clone(), Term::int_var(element))]);
+                let mut iterations = self.iterations(contracts, header, body, start, bound);
+                iterations.push(Command::Seq(Vec::new()));
+                out.push(Command::Choice(iterations));
+            }
+            StmtKind::Error => {}
+        }
+    }
+
+    /// The variable an assignment to `name` sets: the one in scope, or a
+    /// new local of the whole function.
+    fn variable(&mut self, name: &str) -> Term {
+        if let Some(var) = self.lookup(name) {
+            return var.clone();
+        }
+        let var = Term::int_var(name);
+        self.scopes[0].insert(name.to_owned(), var.clone());
+        var
+    }
+
+    /// Rebinds `name` where it is bound, to record the sort of its value.
+    fn bind(&mut self, name: &str, var: Term) {
+        if let Some(scope) = self.scopes.iter_mut().rev().find(|scope| scope.contains_key(name)) {
+            scope.insert(name.to_owned(), var);
+        }
+    }
+
+    /// Forgets the variables `body` assigns, keeping what their declared
+    /// types say about them.
+    fn forget(&mut self, body: &'m Block, out: &mut Vec<Command>) {
+        let mut assigned = Assigned::default();
+        assigned.visit_block(body);
+        for name in assigned.names {
+            let var = self.variable(name);
+            let Term::Var(var_name, sort) = &var else {
+                unreachable!("locals are variables")
+            };
+            out.push(Command::Havoc(var_name.clone(), *sort));
+            if let Some(ty) = self.declared.get(var_name).copied()
+                && let Some(condition) = self.refinements.condition(ty, &var, &self.scope())
+            {
+                out.push(Command::Assume(condition.term));
+            }
+        }
+    }
+
+    /// Checks the `invariant` clauses of a loop where it is reached.
+    fn initiation(&mut self, contracts: &'m [Contract], header: Span, out: &mut Vec<Command>) {
+        for contract in contracts {
+            if contract.kind == ContractKind::Invariant {
+                let claim = self.clause(&contract.cond, Sort::Bool, self.scope(), None, out);
+                let kind = CheckKind::Loop {
+                    clause: contract,
+                    obligation: LoopObligation::Initiation,
+                    header,
+                    path: Vec::new(),
+                };
+                self.assert(contract.span, claim, kind, out);
+            }
+        }
+    }
+
+    /// Assumes the `invariant` clauses of a loop.
+    fn invariants(&mut self, contracts: &'m [Contract], out: &mut Vec<Command>) {
+        for contract in contracts {
+            if contract.kind == ContractKind::Invariant {
+                let cond = self.clause(&contract.cond, Sort::Bool, self.scope(), None, out);
+                out.push(Command::Assume(cond));
+            }
+        }
+    }
+
+    /// The iterations of a loop, each starting with `start`: one with the
+    /// checks of its body, and without them one for each path through the
+    /// body, checking the loop's clauses at the end. The body sees the
+    /// names in `bound`, and the clauses do not.
+    fn iterations(
+        &mut self,
+        contracts: &'m [Contract],
+        header: Span,
+        body: &'m Block,
+        start: Vec<Command>,
+        bound: HashMap<String, Term>,
+    ) -> Vec<Command> {
+        let choices = std::mem::take(&mut self.choices);
+        let mut iteration = start.clone();
+        self.scopes.push(bound.clone());
+        self.block(body, &mut iteration);
+        self.scopes.pop();
+        iteration.push(Command::Assume(Term::Bool(false)));
+        let mut iterations = vec![Command::Seq(iteration)];
+        if !contracts.is_empty() {
+            for path in paths(body).unwrap_or_else(|| vec![Path::default()]) {
+                let mut iteration = start.clone();
+                let mut measures = Vec::new();
+                for contract in contracts {
+                    if contract.kind == ContractKind::Decreases {
+                        let value = self.clause(&contract.cond, Sort::Int, self.scope(), None, &mut iteration);
+                        let measure = self.fresh("measure");
+                        measures.push(Term::var(&measure, value.sort()));
+                        iteration.push(Command::Assign(measure, value));
+                    }
+                }
+                // The body's own checks are made by the first iteration.
+                let checks = self.checks.len();
+                self.choices = path.choices.into();
+                let mut commands = Vec::new();
+                self.scopes.push(bound.clone());
+                self.block(body, &mut commands);
+                self.scopes.pop();
+                self.checks.truncate(checks);
+                iteration.push(assumed(Command::Seq(commands)));
+                let mut measures = measures.into_iter();
+                for contract in contracts {
+                    let scope = self.scope();
+                    let (obligation, claim) = match contract.kind {
+                        ContractKind::Invariant => {
+                            let claim = self.clause(&contract.cond, Sort::Bool, scope, None, &mut iteration);
+                            (LoopObligation::Preservation, claim)
+                        }
+                        ContractKind::Decreases => {
+                            let before = measures.next().expect("every measure is taken");
+                            let after = self.clause(&contract.cond, before.sort(), scope, None, &mut iteration);
+                            let zero = literal(0, Some(before.sort()));
+                            let claim = Term::and([
+                                Term::cmp(Cmp::Ge, before.clone(), zero),
+                                Term::cmp(Cmp::Lt, after, before),
+                            ]);
+                            (LoopObligation::Termination, claim)
+                        }
+                        ContractKind::Requires | ContractKind::Ensures => continue,
+                    };
+                    let kind = CheckKind::Loop {
+                        clause: contract,
+                        obligation,
+                        header,
+                        path: path.turns.clone(),
+                    };
+                    self.assert(contract.span, claim, kind, &mut iteration);
+                }
+                iteration.push(Command::Assume(Term::Bool(false)));
+                iterations.push(Command::Seq(iteration));
+            }
+        }
+        self.choices = choices;
+        iterations
+    }
+
+    /// `if c ... elsif c ... else ... end` where the branch `choice` is
+    /// taken, the `else` past the last: its condition holds, and the ones
+    /// before it fail.
+    fn taken(
+        &mut self,
+        branches: &'m [(Expr, Block)],
+        else_block: Option<&'m Block>,
+        choice: usize,
+        out: &mut Vec<Command>,
+    ) {
+        for (index, (cond, block)) in branches.iter().enumerate() {
+            let cond = self.expr(cond, Some(Sort::Bool), out);
+            if index == choice {
+                out.push(Command::Assume(cond));
+                self.block(block, out);
+                return;
+            }
+            out.push(Command::Assume(!cond));
+        }
+        if let Some(block) = else_block {
+            self.block(block, out);
+        }
+    }
+
+    /// `if c ... elsif c ... else ... end`: each branch assumes its
+    /// condition and that the ones before it failed.
+    fn branches(&mut self, branches: &'m [(Expr, Block)], else_block: Option<&'m Block>, out: &mut Vec<Command>) {
+        let Some(((cond, block), rest)) = branches.split_first() else {
+            if let Some(block) = else_block {
+                self.block(block, out);
+            }
+            return;
+        };
+        let cond = self.expr(cond, Some(Sort::Bool), out);
+        let mut then = vec![Command::Assume(cond.clone())];
+        self.block(block, &mut then);
+        let mut otherwise = vec![Command::Assume(!cond)];
+        self.branches(rest, else_block, &mut otherwise);
+        out.push(Command::Choice(vec![Command::Seq(then), Command::Seq(otherwise)]));
+    }
+
+    /// The value of `expr` as a term, of sort `sort` if given, with the
+    /// commands evaluating it appended to `out`.
+    fn expr(&mut self, expr: &'m Expr, sort: Option<Sort>, out: &mut Vec<Command>) -> Term {
+        match &expr.kind {
+            ExprKind::Lit(Lit::Int(value)) => literal((*value).into(), sort),
+            ExprKind::Lit(Lit::Bool(value)) => Term::Bool(*value),
+            ExprKind::Ident(name) => match self.lookup(&name.name) {
+                // A fixed-width integer keeps its width whatever is expected.
+                Some(Term::Var(var, found @ Sort::Bits { .. })) => Term::var(var, *found),
+                Some(Term::Var(var, found)) => Term::var(var, sort.unwrap_or(*found)),
+                Some(term) => term.clone(),
+                None => self.opaque(expr, sort, out),
+            },
+            // `-128` is an `i8`, though `128` on its own is not.
+            ExprKind::Unary {
+                op: UnaryOp::Neg,
+                expr: operand,
+            } if let ExprKind::Lit(Lit::Int(value)) = operand.kind => literal(-i128::from(value), sort),
+            ExprKind::Unary {
+                op: UnaryOp::Neg,
+                expr: operand,
+            } => {
+                let value = self.expr(operand, Some(numeric(sort)), out);
+                let zero = literal(0, Some(value.sort()));
+                self.overflow(expr, BinaryOp::Sub, &zero, &value, out);
+                -value
+            }
+            ExprKind::Unary { op: UnaryOp::Not, expr } => !self.expr(expr, Some(Sort::Bool), out),
+            ExprKind::Binary { op, lhs, rhs } => match op {
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                    let floating = self.floating(lhs) || self.floating(rhs);
+                    let divisor = rhs;
+                    let (lhs, rhs) = self.operands(lhs, rhs, Some(numeric(sort)), out);
+                    if matches!(op, BinaryOp::Div | BinaryOp::Rem) && !floating {
+                        self.division(expr, divisor, &rhs, out);
+                    }
+                    self.overflow(expr, *op, &lhs, &rhs, out);
+                    match op {
+                        BinaryOp::Add => lhs + rhs,
+                        BinaryOp::Sub => lhs - rhs,
+                        BinaryOp::Mul => lhs * rhs,
+                        BinaryOp::Div => lhs / rhs,
+                        // The remainder of a division rounding towards zero.
+                        _ => lhs.clone() - lhs / rhs.clone() * rhs,
+                    }
+                }
+                BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::Shl | BinaryOp::Shr => {
+                    let (lhs, rhs) = self.operands(lhs, rhs, Some(numeric(sort)), out);
+                    // Only fixed-width integers have bits the solver knows.
+                    if !matches!(lhs.sort(), Sort::Bits { .. }) {
+                        return self.opaque(expr, sort, out);
+                    }
+                    Term::bitwise(bit_op(*op), lhs, rhs)
+                }
+                BinaryOp::And | BinaryOp::Or | BinaryOp::Implies => {
+                    let lhs = self.expr(lhs, Some(Sort::Bool), out);
+                    // The right operand is only evaluated when the left one
+                    // does not decide the result.
+                    let mut evaluated = Vec::new();
+                    let rhs = self.expr(rhs, Some(Sort::Bool), &mut evaluated);
+                    let needed = match op {
+                        BinaryOp::Or => !lhs.clone(),
+                        _ => lhs.clone(),
+                    };
+                    if !evaluated.is_empty() {
+                        evaluated.insert(0, Command::Assume(needed.clone()));
+                        out.push(Command::Choice(vec![Command::Seq(evaluated), Command::Assume(!needed)]));
+                    }
+                    match op {
+                        BinaryOp::And => Term::and([lhs, rhs]),
+                        BinaryOp::Or => Term::or([lhs, rhs]),
+                        _ => Term::implies(lhs, rhs),
+                    }
+                }
+                _ => {
+                    let cmp = comparison(*op);
+                    let (lhs, rhs) = self.operands(lhs, rhs, None, out);
+                    Term::cmp(cmp, lhs, rhs)
+                }
+            },
+            ExprKind::Call { callee, args } => match (&callee.kind, args.as_slice()) {
+                (ExprKind::Ident(name), [arg]) if name.name == "old" && self.entry.is_some() => {
+                    let entry = self.entry.take().unwrap_or_default();
+                    let scopes = std::mem::replace(&mut self.scopes, vec![entry]);
+                    let term = self.expr(arg, sort, out);
+                    self.entry = self.scopes.pop();
+                    self.scopes = scopes;
+                    term
+                }
+                _ => self.call(expr, callee, args, sort, out),
+            },
+            ExprKind::Match { scrutinee, arms } => self.match_expr(scrutinee, arms, sort, out),
+            ExprKind::List(items) | ExprKind::Tuple(items) => {
+                for item in items {
+                    self.expr(item, None, out);
+                }
+                self.opaque(expr, sort, out)
+            }
+            ExprKind::Field { expr: inner, .. } => {
+                self.expr(inner, None, out);
+                self.opaque(expr, sort, out)
+            }
+            ExprKind::Index { expr: list, index } => {
+                self.expr(list, None, out);
+                self.expr(index, Some(Sort::Int), out);
+                self.opaque(expr, sort, out)
+            }
+            ExprKind::Struct { fields, base, .. } => {
+                for init in fields {
+                    self.expr(&init.value, None, out);
+                }
+                if let Some(base) = base {
+                    self.expr(base, None, out);
+                }
+                self.opaque(expr, sort, out)
+            }
+            ExprKind::Lit(_)
+            | ExprKind::Lambda { .. }
+            | ExprKind::Closure { .. }
+            | ExprKind::Hole(_)
+            | ExprKind::Error => self.opaque(expr, sort, out),
+        }
+    }
+
+    /// Both operands of arithmetic or a comparison, the right one of the
+    /// sort of the left, unless the left is an integer literal and the
+    /// right a fixed-width integer.
+    fn operands(&mut self, lhs: &'m Expr, rhs: &'m Expr, sort: Option<Sort>, out: &mut Vec<Command>) -> (Term, Term) {
+        let mut lhs_term = self.expr(lhs, sort, out);
+        let rhs_term = self.expr(rhs, Some(lhs_term.sort()), out);
+        if lhs_term.sort() != rhs_term.sort()
+            && let ExprKind::Lit(Lit::Int(value)) = lhs.kind
+        {
+            lhs_term = literal(value.into(), Some(rhs_term.sort()));
+        }
+        (lhs_term, rhs_term)
+    }
+
+    /// Checks that `op` on the fixed-width integers `lhs` and `rhs` gives a
+    /// value of their type, the result of `expr`, rather than wrapping
+    /// around.
+    fn overflow(&mut self, expr: &Expr, op: BinaryOp, lhs: &Term, rhs: &Term, out: &mut Vec<Command>) {
+        let sort @ Sort::Bits { width, signed } = lhs.sort() else {
+            return;
+        };
+        if self.contract {
+            return;
+        }
+        let claim = match op {
+            // Only the least signed value divided by minus one overflows.
+            BinaryOp::Div | BinaryOp::Rem if signed => Term::or([
+                Term::cmp(Cmp::Ne, lhs.clone(), Term::Bits(Bits::min(width, signed))),
+                Term::cmp(Cmp::Ne, rhs.clone(), Term::bits(-1, sort)),
+            ]),
+            BinaryOp::Div | BinaryOp::Rem => return,
+            _ => {
+                let wide = Sort::Bits {
+                    width: 2 * width,
+                    signed,
+                };
+                let (lhs, rhs) = (Term::cast(lhs.clone(), wide), Term::cast(rhs.clone(), wide));
+                let exact = match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    _ => lhs * rhs,
+                };
+                let min = Term::Bits(Bits::min(width, signed).cast(2 * width, signed));
+                let max = Term::Bits(Bits::max(width, signed).cast(2 * width, signed));
+                Term::and([Term::cmp(Cmp::Le, min, exact.clone()), Term::cmp(Cmp::Le, exact, max)])
+            }
+        };
+        let kind = CheckKind::Overflow {
+            value: pretty::expr(expr),
+            ty: sort.to_string(),
+        };
+        self.assert(expr.span, claim, kind, out);
+    }
+
+    /// Checks that `divisor`, whose value is `rhs`, is not zero in the
+    /// division or remainder `expr`.
+    fn division(&mut self, expr: &Expr, divisor: &Expr, rhs: &Term, out: &mut Vec<Command>) {
+        if self.contract {
+            return;
+        }
+        let claim = Term::cmp(Cmp::Ne, rhs.clone(), literal(0, Some(rhs.sort())));
+        let kind = CheckKind::DivisionByZero {
+            value: pretty::expr(expr),
+            divisor: pretty::expr(divisor),
+        };
+        self.assert(expr.span, claim, kind, out);
+    }
+
+    /// Whether `expr` is plainly a `Float`, which the translation does not
+    /// tell apart from an `Int`: a float literal, a variable declared
+    /// `Float`, or arithmetic on one. Dividing one by zero is no error.
+    fn floating(&self, expr: &Expr) -> bool {
+        match &expr.kind {
+            ExprKind::Lit(Lit::Float(_)) => true,
+            ExprKind::Ident(name) => self
+                .declared
+                .get(&name.name)
+                .is_some_and(|ty| matches!(&ty.kind, TypeExprKind::Named { name, .. } if name.name == "Float")),
+            ExprKind::Unary { expr, .. } => self.floating(expr),
+            ExprKind::Binary { lhs, rhs, .. } => self.floating(lhs) || self.floating(rhs),
+            _ => false,
+        }
+    }
+
+    /// A call: the arguments are checked against the callee's parameter
+    /// types and `requires` clauses, and the result is whatever its return
+    /// type and `ensures` clauses allow.
+    fn call(
+        &mut self,
+        expr: &'m Expr,
+        callee: &'m Expr,
+        args: &'m [Expr],
+        sort: Option<Sort>,
+        out: &mut Vec<Command>,
+    ) -> Term {
+        let function = match &callee.kind {
+            ExprKind::Ident(name) if self.lookup(&name.name).is_none() && !self.contract => {
+                self.functions.get(name.name.as_str()).copied()
+            }
+            _ => None,
+        };
+        let Some(function) = function.filter(|function| function.params.len() == args.len()) else {
+            for arg in args {
+                self.expr(arg, None, out);
+            }
+            return self.opaque(expr, sort, out);
+        };
+        let mut scope = HashMap::new();
+        for (param, arg) in function.params.iter().zip(args) {
+            let sort = param.ty.as_ref().and_then(|ty| self.refinements.sort(ty));
+            let value = self.expr(arg, sort, out);
+            scope.insert(param.name.name.clone(), value);
+        }
+        for (param, arg) in function.params.iter().zip(args) {
+            if let Some(ty) = &param.ty {
+                let site = Site::Argument {
+                    function: function.name.name.clone(),
+                    param: param.name.clone(),
+                };
+                let value = scope[&param.name.name].clone();
+                self.check(ty, &value, arg, &scope, site, out);
+            }
+        }
+        let names: HashMap<String, Expr> = function
+            .params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (param.name.name.clone(), arg.clone()))
+            .collect();
+        for contract in &function.contracts {
+            if contract.kind == ContractKind::Requires {
+                let claim = self.clause(&contract.cond, Sort::Bool, scope.clone(), None, out);
+                // The claim is simplified, to `false` if the arguments are constants.
+                let instance = pretty::expr(&substitute(contract.cond.clone(), &names, None));
+                let kind = CheckKind::Requires {
+                    function: function.name.name.clone(),
+                    clause: contract,
+                    instance,
+                };
+                self.assert(expr.span, claim, kind, out);
+            }
+        }
+        let sort = function
+            .ret
+            .as_ref()
+            .and_then(|ty| self.refinements.sort(ty))
+            .or(sort)
+            .unwrap_or(Sort::Int);
+        let name = pretty::expr(expr);
+        out.push(Command::Havoc(name.clone(), sort));
+        let result = Term::var(name, sort);
+        if let Some(ret) = &function.ret
+            && let Some(condition) = self.refinements.condition(ret, &result, &scope)
+        {
+            out.push(Command::Assume(condition.term));
+        }
+        // The callee may have changed the parameters it assigns by the time
+        // its `ensures` clauses are about them.
+        let mut assigned = Assigned::default();
+        assigned.visit_block(&function.body);
+        let mut exit = scope.clone();
+        for param in &function.params {
+            if assigned.names.contains(&param.name.name.as_str()) {
+                let sort = scope[&param.name.name].sort();
+                let var = self.fresh("");
+                out.push(Command::Havoc(var.clone(), sort));
+                exit.insert(param.name.name.clone(), Term::var(var, sort));
+            }
+        }
+        exit.insert("result".to_owned(), result.clone());
+        for contract in &function.contracts {
+            if contract.kind == ContractKind::Ensures {
+                let cond = self.clause(&contract.cond, Sort::Bool, exit.clone(), Some(scope.clone()), out);
+                out.push(Command::Assume(cond));
+            }
+        }
+        result
+    }
+
+    /// A match: each arm binds its pattern and gives the result.
+    fn match_expr(
+        &mut self,
+        scrutinee: &'m Expr,
+        arms: &'m [MatchArm],
+        sort: Option<Sort>,
+        out: &mut Vec<Command>,
+    ) -> Term {
+        let value = self.expr(scrutinee, None, out);
+        let result = self.fresh("");
+        let mut result_sort = sort;
+        let mut cases = Vec::new();
+        for arm in arms {
+            let mut case = Vec::new();
+            self.scopes.push(HashMap::new());
+            self.pattern(&arm.pattern, &value, &mut case);
+            if let Some(guard) = &arm.guard {
+                let guard = self.expr(guard, Some(Sort::Bool), &mut case);
+                case.push(Command::Assume(guard));
+            }
+            let body = self.expr(&arm.body, sort, &mut case);
+            result_sort.get_or_insert(body.sort());
+            case.push(Command::Assign(result.clone(), body));
+            self.scopes.pop();
+            cases.push(Command::Seq(case));
+        }
+        out.push(Command::Choice(cases));
+        Term::var(result, result_sort.unwrap_or(Sort::Int))
+    }
+
+    fn pattern(&mut self, pattern: &Pattern, value: &Term, out: &mut Vec<Command>) {
+        match &pattern.kind {
+            PatternKind::Binding(name) => {
+                let var = self.fresh(&name.name);
+                self.scopes
+                    .last_mut()
+                    .expect("arms have a scope")
+                    .insert(name.name.clone(), Term::var(&var, value.sort()));
+                out.push(Command::Assign(var, value.clone()));
+            }
+            PatternKind::Lit(Lit::Int(lit)) if matches!(value.sort(), Sort::Int | Sort::Bits { .. }) => {
+                let lit = literal((*lit).into(), Some(value.sort()));
+                out.push(Command::Assume(Term::eq(value.clone(), lit)));
+            }
+            PatternKind::Lit(Lit::Bool(lit)) if value.sort() == Sort::Bool => {
+                out.push(Command::Assume(Term::eq(value.clone(), Term::Bool(*lit))));
+            }
+            // What the parts of a variant are is not tracked.
+            PatternKind::Variant { args: patterns, .. } | PatternKind::Or(patterns) => {
+                let mut names = Vec::new();
+                patterns.iter().for_each(|pattern| bindings(pattern, &mut names));
+                for name in names {
+                    let var = self.fresh(&name.name);
+                    self.scopes
+                        .last_mut()
+                        .expect("arms have a scope")
+                        .insert(name.name.clone(), Term::int_var(&var));
+                    out.push(Command::Havoc(var, Sort::Int));
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Lit(_) | PatternKind::Error => {}
+        }
+    }
+}
+
+/// The sort arithmetic is done in when `sort` is expected of the result.
+fn numeric(sort: Option<Sort>) -> Sort {
+    match sort {
+        Some(sort @ Sort::Bits { .. }) => sort,
+        _ => Sort::Int,
+    }
+}
+
+/// The integer literal `value`, of `sort` if that is a fixed-width integer.
+fn literal(value: i128, sort: Option<Sort>) -> Term {
+    match sort {
+        Some(sort @ Sort::Bits { .. }) => Term::bits(value, sort),
+        _ => Term::int(value as i64),
+    }
+}
+
+/// The names `pattern` binds.
+fn bindings<'p>(pattern: &'p Pattern, names: &mut Vec<&'p Ident>) {
+    match &pattern.kind {
+        PatternKind::Binding(name) => names.push(name),
+        PatternKind::Variant { args: patterns, .. } => patterns.iter().for_each(|pattern| bindings(pattern, names)),
+        // Every alternative binds the same names.
+        PatternKind::Or(alternatives) => {
+            if let Some(first) = alternatives.first() {
+                bindings(first, names);
+            }
+        }
+        PatternKind::Wildcard | PatternKind::Lit(_) | PatternKind::Error => {}
+    }
+}
+
+/// The names a block assigns, each once.
+#[derive(Default)]
+pub struct Assigned<'m> {
+    pub names: Vec<&'m str>,
+}
+
+impl<'m> Visitor<'m> for Assigned<'m> {
+    fn visit_stmt(&mut self, stmt: &'m Stmt) {
+        if let StmtKind::Assign { target, .. } = &stmt.kind
+            && let ExprKind::Ident(name) = &target.kind
+            && !self.names.contains(&name.name.as_str())
+        {
+            self.names.push(&name.name);
+        }
+        walk_stmt(self, stmt);
+    }
+}
@@ -0,0 +1,392 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Verification: proving that every value given a refinement type meets
+//! the type's condition, and that functions keep their contracts.
+//!
+//! A `Nat` is an `Int` checked the same way never to be negative, except in
+//! the functions the core calculus checks, which keeps it so itself.
+//!
+//! Values are checked where they cross into a type: when assigned to a
+//! local declared with it, passed for a parameter of it, or returned as it.
+//! A `requires` clause is checked at every call, and an `ensures` clause
+//! wherever the function returns. Arithmetic on fixed-width integers is
+//! checked not to overflow, and a counterexample shows their bits. A loop
+//! invariant is checked for initiation and preservation, and a loop measure
+//! for termination, each failure naming the branches of the iteration that
+//! breaks it. Each check is a formula for the solver, and one it cannot
+//! prove is reported with the condition that might not hold and, where the
+//! solver found one, values of the inputs that break it.
+//!
+//! The formulas can be written as SMT-LIB scripts instead, and given to a
+//! solver installed on the machine, to cross-check the prover.
+
+use crate::ast::*;
+use crate::elab::Dependent;
+use crate::pretty;
+use crate::refine::Refinements;
+use crate::types::fixed_width;
+use crate::vc::{self, Check, CheckKind, LoopObligation, Site, Turn};
+use crate::visit::{Visitor, walk_stmt, walk_type_expr};
+use flerry_core::span::line_col;
+use flerry_core::{Diagnostic, Span};
+use flerry_prover::smtlib::{self, Script};
+use flerry_prover::{Limits, Model, Term, Verdict, prove};
+use std::collections::HashMap;
+
+/// A check and what the solver made of it.
+pub struct Obligation<'m> {
+    pub function: &'m str,
+    pub check: Check<'m>,
+    pub verdict: Verdict,
+}
+
+/// What became of a check, for `flerry verify`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub function: String,
+    pub span: Span,
+    /// What was to be proved: "`x - 1` is a `Pos` in `f`".
+    pub description: String,
+    pub status: Status,
+    /// The error for a check that was not proved.
+    pub diagnostic: Option<Diagnostic>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Proved,
+    /// Disproved, with the counterexample if any inputs show up in it.
+    Failed(Option<String>),
+    /// Undecided, for the reason given.
+    Unknown(String),
+}
+
+/// Every check in the functions of `module`, each with its verdict: the
+/// prover's, or that of the SMT-LIB solver the command `solver` runs.
+pub fn verify<'m>(module: &'m Module, source: &str, limits: &Limits, solver: Option<&str>) -> Vec<Obligation<'m>> {
+    conditions(module)
+        .into_iter()
+        .map(|(function, check, condition)| {
+            let verdict = match solver {
+                Some(command) => smtlib::run(command, &script(source, function, &check, &condition), limits),
+                None => prove(&condition, limits),
+            };
+            let verdict = match verdict {
+                Verdict::Unknown(reason) if reason == "timeout" => {
+                    Verdict::Unknown(format!("timed out after {:?}", limits.timeout))
+                }
+                verdict => verdict,
+            };
+            Obligation {
+                function,
+                check,
+                verdict,
+            }
+        })
+        .collect()
+}
+
+/// Every check in the functions of `module`, with the function it is in
+/// and the formula that proves it.
+pub fn conditions<'m>(module: &'m Module) -> Vec<(&'m str, Check<'m>, Term)> {
+    let refinements = Refinements::new(module);
+    let functions: HashMap<&str, &Func> = module
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Func(func) => Some((func.name.name.as_str(), func)),
+            _ => None,
+        })
+        .collect();
+    if refinements.is_empty()
+        && functions.values().all(|func| func.contracts.is_empty())
+        && !mentions_checked_type(module)
+        && !has_loop_contracts(module)
+    {
+        return Vec::new();
+    }
+    let dependent = Dependent::new(module);
+    let mut conditions = Vec::new();
+    for item in &module.items {
+        let ItemKind::Func(func) = &item.kind else {
+            continue;
+        };
+        let body = vc::translate(func, &functions, &refinements);
+        for (index, check) in body.checks.iter().enumerate() {
+            if dependent.functions.contains(func.name.name.as_str())
+                && matches!(check.kind, CheckKind::Refinement { refinement: None, .. })
+            {
+                continue;
+            }
+            conditions.push((func.name.name.as_str(), check.clone(), body.condition(index)));
+        }
+    }
+    conditions
+}
+
+/// The condition of `check`, in `function`, as an SMT-LIB script. Its
+/// assertion is named after the function and the span of the check in
+/// `source`: `next@12:12-12:17`.
+pub fn script(source: &str, function: &str, check: &Check, condition: &Term) -> Script {
+    let (line, column) = line_col(source, check.span.start);
+    let (end_line, end_column) = line_col(source, check.span.end);
+    let comments = [
+        qualified(function, &check.kind),
+        format!("checked at {line}:{column}, `sat` gives a counterexample"),
+    ];
+    let name = format!("{function}@{line}:{column}-{end_line}:{end_column}");
+    Script::validity(condition, &name, &comments)
+}
+
+/// What proving `kind` in `function` shows: "`x - 1` is a `Pos`".
+fn describe(function: &str, kind: &CheckKind) -> String {
+    match kind {
+        CheckKind::Refinement { value, ty, .. } => format!("`{value}` is {}", article(ty)),
+        CheckKind::Requires { function, clause, .. } => {
+            format!("`{function}` requires `{}`", pretty::expr(&clause.cond))
+        }
+        CheckKind::Ensures { clause } => {
+            format!("`{function}` ensures `{}`", pretty::expr(&clause.cond))
+        }
+        CheckKind::Overflow { value, ty } => format!("`{value}` fits in {}", article(ty)),
+        CheckKind::DivisionByZero { value, .. } => format!("the divisor of `{value}` is not zero"),
+        CheckKind::Loop {
+            clause,
+            obligation,
+            path,
+            ..
+        } => {
+            let cond = pretty::expr(&clause.cond);
+            let description = match obligation {
+                LoopObligation::Initiation => return format!("loop invariant `{cond}` holds on entry"),
+                LoopObligation::Preservation => format!("loop invariant `{cond}` is preserved"),
+                LoopObligation::Termination => format!("loop measure `{cond}` decreases"),
+            };
+            if path.is_empty() {
+                return description;
+            }
+            format!("{description} when {}", along(path))
+        }
+    }
+}
+
+/// The turns of a path through a loop body: "`x > 0` and not `y > 0`".
+fn along(path: &[Turn]) -> String {
+    let turns: Vec<String> = path
+        .iter()
+        .map(|turn| {
+            let cond = pretty::expr(turn.cond);
+            if turn.holds {
+                format!("`{cond}`")
+            } else {
+                format!("not `{cond}`")
+            }
+        })
+        .collect();
+    turns.join(" and ")
+}
+
+/// The description with the function it is in, unless it names it already.
+fn qualified(function: &str, kind: &CheckKind) -> String {
+    match kind {
+        CheckKind::Ensures { .. } => describe(function, kind),
+        _ => format!("{} in `{function}`", describe(function, kind)),
+    }
+}
+
+impl Obligation<'_> {
+    /// What is to be proved.
+    pub fn description(&self) -> String {
+        describe(self.function, &self.check.kind)
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            function: self.function.to_owned(),
+            span: self.check.span,
+            description: qualified(self.function, &self.check.kind),
+            status: match &self.verdict {
+                Verdict::Proved => Status::Proved,
+                Verdict::Refuted(model) => Status::Failed(counterexample(model)),
+                Verdict::Unknown(reason) => Status::Unknown(reason.clone()),
+            },
+            diagnostic: self.diagnostic(),
+        }
+    }
+
+    /// The error for a check that was not proved.
+    pub fn diagnostic(&self) -> Option<Diagnostic> {
+        let check = &self.check;
+        let claim = format!("`{}` might not hold", source_names(&check.claim));
+        let mut diagnostic = match &check.kind {
+            CheckKind::Refinement {
+                ty, refinement, site, ..
+            } => {
+                let diagnostic = Diagnostic::error(format!("cannot prove that {}", self.description()), check.span)
+                    .with_label(check.span, claim);
+                let diagnostic = match site {
+                    Site::Assignment { declared } => {
+                        diagnostic.with_label(*declared, format!("expected {} because of this", article(ty)))
+                    }
+                    Site::Argument { function, param } => diagnostic.with_label(
+                        param.span,
+                        format!("the parameter `{}` of `{function}` is {}", param.name, article(ty)),
+                    ),
+                    Site::Return { declared } => diagnostic.with_label(*declared, "the return type is declared here"),
+                };
+                match refinement.and_then(|alias| Some((alias, alias.refinement.as_ref()?))) {
+                    Some((alias, cond)) => {
+                        diagnostic.with_label(cond.span, format!("required by the condition of `{}`", alias.name.name))
+                    }
+                    None => diagnostic,
+                }
+            }
+            CheckKind::Requires {
+                function,
+                clause,
+                instance,
+            } => {
+                Diagnostic::error(format!("cannot prove the precondition of `{function}`"), check.span)
+                    .with_label(check.span, format!("`{instance}` might not hold"))
+                    .with_label(clause.span, format!("`{function}` requires this of its callers"))
+            }
+            CheckKind::Ensures { clause } => {
+                Diagnostic::error(
+                    format!("cannot prove the postcondition of `{}`", self.function),
+                    check.span,
+                )
+                // The claim would show the entry and exit values of a parameter under one name.
+                .with_label(
+                    check.span,
+                    format!(
+                        "`{}` might not hold when the function returns here",
+                        pretty::expr(&clause.cond)
+                    ),
+                )
+                .with_label(clause.span, format!("`{}` ensures this", self.function))
+            }
+            // The claim is about the operands widened, which the source never mentions.
+            CheckKind::Overflow { value, ty } => {
+                Diagnostic::error(format!("cannot prove that `{value}` does not overflow"), check.span)
+                    .with_label(check.span, format!("might not fit in {}", article(ty)))
+            }
+            CheckKind::DivisionByZero { value, divisor } => Diagnostic::error(
+                format!("cannot prove that `{value}` does not divide by zero"),
+                check.span,
+            )
+            .with_label(check.span, format!("`{divisor}` might be zero")),
+            CheckKind::Loop {
+                clause,
+                obligation,
+                header,
+                path,
+            } => {
+                let cond = pretty::expr(&clause.cond);
+                let (message, label, note) = match obligation {
+                    LoopObligation::Initiation => (
+                        "cannot prove that the loop invariant holds on entry",
+                        format!("`{cond}` might not hold before the first iteration"),
+                        "the invariant must hold when the loop is reached",
+                    ),
+                    LoopObligation::Preservation => (
+                        "cannot prove that the loop invariant is preserved",
+                        format!("`{cond}` might not hold after an iteration"),
+                        "an iteration that starts with the invariant must end with it",
+                    ),
+                    LoopObligation::Termination => (
+                        "cannot prove that the loop terminates",
+                        format!("`{cond}` might not decrease towards zero"),
+                        "the measure must be at least zero when an iteration starts, and smaller when it ends",
+                    ),
+                };
+                let mut diagnostic = Diagnostic::error(message, check.span)
+                    .with_label(check.span, label)
+                    .with_label(*header, "in this loop");
+                for turn in path {
+                    let holds = if turn.holds { "holds" } else { "fails" };
+                    diagnostic = diagnostic.with_label(turn.cond.span, format!("on the path where this {holds}"));
+                }
+                diagnostic.with_note(format!("failed obligation: {}: {note}", obligation.name()))
+            }
+        };
+        diagnostic = match &self.verdict {
+            Verdict::Proved => return None,
+            Verdict::Refuted(model) => match counterexample(model) {
+                Some(values) => diagnostic.with_note(format!("counterexample: `{values}`")),
+                None => diagnostic.with_note("it fails whatever the inputs are"),
+            },
+            Verdict::Unknown(reason) => diagnostic.with_note(format!("the solver could not decide: {reason}")),
+        };
+        Some(diagnostic)
+    }
+}
+
+/// Whether a type annotation in `module` names a fixed-width integer type
+/// or `Nat`, which are checked without any refinement type.
+fn mentions_checked_type(module: &Module) -> bool {
+    struct Mentions(bool);
+    impl<'m> Visitor<'m> for Mentions {
+        fn visit_type_expr(&mut self, ty: &'m TypeExpr) {
+            if let TypeExprKind::Named { name, .. } = &ty.kind {
+                self.0 |= fixed_width(&name.name).is_some() || name.name == "Nat";
+            }
+            walk_type_expr(self, ty);
+        }
+    }
+    let mut mentions = Mentions(false);
+    mentions.visit_module(module);
+    mentions.0
+}
+
+/// Whether a loop in `module` has an `invariant` or `decreases` clause.
+fn has_loop_contracts(module: &Module) -> bool {
+    struct Contracts(bool);
+    impl<'m> Visitor<'m> for Contracts {
+        fn visit_stmt(&mut self, stmt: &'m Stmt) {
+            if let StmtKind::While { contracts, .. } | StmtKind::For { contracts, .. } = &stmt.kind {
+                self.0 |= !contracts.is_empty();
+            }
+            walk_stmt(self, stmt);
+        }
+    }
+    let mut contracts = Contracts(false);
+    contracts.visit_module(module);
+    contracts.0
+}
+
+/// `a` or `an` before the quoted type `ty`.
+pub fn article(ty: &str) -> String {
+    // `u8` is read "you eight".
+    let vowel = ty.starts_with(['A', 'E', 'I', 'O', 'U', 'a', 'e', 'i', 'o']);
+    format!("{} `{ty}`", if vowel { "an" } else { "a" })
+}
+
+/// The name `name` has in the source, without what tells apart values it
+/// has at different points.
+fn source_name(name: &str) -> &str {
+    name.split('#').next().unwrap_or_default()
+}
+
+/// `term` with its variables under the names they have in the source.
+fn source_names(term: &Term) -> Term {
+    match term {
+        Term::Var(name, sort) => Term::Var(source_name(name).to_owned(), *sort),
+        term => term.map(&mut source_names),
+    }
+}
+
+/// The values of a model a user can relate to the source: each variable
+/// under the name it has there, the first time it appears.
+pub fn counterexample(model: &Model) -> Option<String> {
+    let mut shown = Model::new();
+    for (name, value) in model.iter() {
+        let name = source_name(name);
+        // Entry values equal the parameters, which are shown already.
+        if name.is_empty() || name.starts_with("old(") || shown.get(name).is_some() {
+            continue;
+        }
+        shown.insert(name, value);
+    }
+    (!shown.is_empty()).then(|| shown.to_string())
+}
@@ -0,0 +1,336 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Read-only traversal of the AST.
+//!
+//! A `Visitor` has one method per node kind. Each defaults to the matching
+//! `walk_*` function, which visits the node's children in source order, so
+//! an implementation overrides only the nodes it cares about and calls the
+//! `walk_*` function from its override to keep descending.
+//!
+//! `visit_mut` and `fold` follow the same shape for rewriting the tree in
+//! place or rebuilding it.
+
+use crate::ast::*;
+
+pub trait Visitor<'ast>: Sized {
+    fn visit_module(&mut self, module: &'ast Module) {
+        walk_module(self, module);
+    }
+
+    fn visit_item(&mut self, item: &'ast Item) {
+        walk_item(self, item);
+    }
+
+    fn visit_func(&mut self, func: &'ast Func) {
+        walk_func(self, func);
+    }
+
+    fn visit_param(&mut self, param: &'ast Param) {
+        walk_param(self, param);
+    }
+
+    fn visit_struct_def(&mut self, def: &'ast StructDef) {
+        walk_struct_def(self, def);
+    }
+
+    fn visit_enum_def(&mut self, def: &'ast EnumDef) {
+        walk_enum_def(self, def);
+    }
+
+    fn visit_variant(&mut self, variant: &'ast Variant) {
+        walk_variant(self, variant);
+    }
+
+    fn visit_field(&mut self, field: &'ast Field) {
+        walk_field(self, field);
+    }
+
+    fn visit_type_alias(&mut self, alias: &'ast TypeAlias) {
+        walk_type_alias(self, alias);
+    }
+
+    fn visit_type_expr(&mut self, ty: &'ast TypeExpr) {
+        walk_type_expr(self, ty);
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_field_init(&mut self, init: &'ast FieldInit) {
+        walk_field_init(self, init);
+    }
+
+    fn visit_match_arm(&mut self, arm: &'ast MatchArm) {
+        walk_match_arm(self, arm);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast Pattern) {
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_lit(&mut self, _lit: &'ast Lit) {}
+
+    /// Called for every name in the tree, whether it binds, refers to or
+    /// labels something.
+    fn visit_ident(&mut self, _ident: &'ast Ident) {}
+}
+
+pub fn walk_module<'ast, V: Visitor<'ast>>(visitor: &mut V, module: &'ast Module) {
+    for item in &module.items {
+        visitor.visit_item(item);
+    }
+}
+
+pub fn walk_item<'ast, V: Visitor<'ast>>(visitor: &mut V, item: &'ast Item) {
+    match &item.kind {
+        ItemKind::Import(name) => visitor.visit_ident(name),
+        ItemKind::Func(func) => visitor.visit_func(func),
+        ItemKind::Struct(def) => visitor.visit_struct_def(def),
+        ItemKind::Enum(def) => visitor.visit_enum_def(def),
+        ItemKind::Type(alias) => visitor.visit_type_alias(alias),
+        ItemKind::Error => {}
+    }
+}
+
+pub fn walk_func<'ast, V: Visitor<'ast>>(visitor: &mut V, func: &'ast Func) {
+    visitor.visit_ident(&func.name);
+    for param in &func.params {
+        visitor.visit_param(param);
+    }
+    if let Some(ret) = &func.ret {
+        visitor.visit_type_expr(ret);
+    }
+    walk_contracts(visitor, &func.contracts);
+    visitor.visit_block(&func.body);
+}
+
+fn walk_contracts<'ast, V: Visitor<'ast>>(visitor: &mut V, contracts: &'ast [Contract]) {
+    for contract in contracts {
+        visitor.visit_expr(&contract.cond);
+    }
+}
+
+pub fn walk_param<'ast, V: Visitor<'ast>>(visitor: &mut V, param: &'ast Param) {
+    visitor.visit_ident(&param.name);
+    if let Some(ty) = &param.ty {
+        visitor.visit_type_expr(ty);
+    }
+}
+
+pub fn walk_struct_def<'ast, V: Visitor<'ast>>(visitor: &mut V, def: &'ast StructDef) {
+    visitor.visit_ident(&def.name);
+    for field in &def.fields {
+        visitor.visit_field(field);
+    }
+}
+
+pub fn walk_enum_def<'ast, V: Visitor<'ast>>(visitor: &mut V, def: &'ast EnumDef) {
+    visitor.visit_ident(&def.name);
+    for param in &def.params {
+        visitor.visit_param(param);
+    }
+    for variant in &def.variants {
+        visitor.visit_variant(variant);
+    }
+}
+
+pub fn walk_variant<'ast, V: Visitor<'ast>>(visitor: &mut V, variant: &'ast Variant) {
+    visitor.visit_ident(&variant.name);
+    for field in &variant.fields {
+        visitor.visit_field(field);
+    }
+    if let Some(ret) = &variant.ret {
+        visitor.visit_type_expr(ret);
+    }
+}
+
+pub fn walk_field<'ast, V: Visitor<'ast>>(visitor: &mut V, field: &'ast Field) {
+    visitor.visit_ident(&field.name);
+    if let Some(ty) = &field.ty {
+        visitor.visit_type_expr(ty);
+    }
+}
+
+pub fn walk_type_alias<'ast, V: Visitor<'ast>>(visitor: &mut V, alias: &'ast TypeAlias) {
+    visitor.visit_ident(&alias.name);
+    for param in &alias.params {
+        visitor.visit_ident(param);
+    }
+    visitor.visit_type_expr(&alias.ty);
+    if let Some(refinement) = &alias.refinement {
+        visitor.visit_expr(refinement);
+    }
+}
+
+pub fn walk_type_expr<'ast, V: Visitor<'ast>>(visitor: &mut V, ty: &'ast TypeExpr) {
+    match &ty.kind {
+        TypeExprKind::Named { name, args } => {
+            visitor.visit_ident(name);
+            for arg in args {
+                visitor.visit_type_expr(arg);
+            }
+        }
+        TypeExprKind::Function { params, ret } => {
+            for param in params {
+                visitor.visit_type_expr(param);
+            }
+            visitor.visit_type_expr(ret);
+        }
+        TypeExprKind::Tuple(elements) => {
+            for ty in elements {
+                visitor.visit_type_expr(ty);
+            }
+        }
+        TypeExprKind::Bind { name, ty } => {
+            visitor.visit_ident(name);
+            visitor.visit_type_expr(ty);
+        }
+        TypeExprKind::Expr(expr) => visitor.visit_expr(expr),
+    }
+}
+
+pub fn walk_block<'ast, V: Visitor<'ast>>(visitor: &mut V, block: &'ast Block) {
+    for stmt in &block.stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &'ast Stmt) {
+    match &stmt.kind {
+        StmtKind::Assign { target, ty, value } => {
+            visitor.visit_expr(target);
+            if let Some(ty) = ty {
+                visitor.visit_type_expr(ty);
+            }
+            visitor.visit_expr(value);
+        }
+        StmtKind::Expr(expr) | StmtKind::Return(Some(expr)) => visitor.visit_expr(expr),
+        StmtKind::If { branches, else_block } => {
+            for (cond, block) in branches {
+                visitor.visit_expr(cond);
+                visitor.visit_block(block);
+            }
+            if let Some(block) = else_block {
+                visitor.visit_block(block);
+            }
+        }
+        StmtKind::While { cond, contracts, body } => {
+            visitor.visit_expr(cond);
+            walk_contracts(visitor, contracts);
+            visitor.visit_block(body);
+        }
+        StmtKind::For {
+            var,
+            iter,
+            contracts,
+            body,
+        } => {
+            visitor.visit_ident(var);
+            visitor.visit_expr(iter);
+            walk_contracts(visitor, contracts);
+            visitor.visit_block(body);
+        }
+        StmtKind::Return(None) | StmtKind::Error => {}
+    }
+}
+
+pub fn walk_expr<'ast, V: Visitor<'ast>>(visitor: &mut V, expr: &'ast Expr) {
+    match &expr.kind {
+        ExprKind::Lit(lit) => visitor.visit_lit(lit),
+        ExprKind::Ident(name) => visitor.visit_ident(name),
+        ExprKind::List(items) | ExprKind::Tuple(items) => {
+            for item in items {
+                visitor.visit_expr(item);
+            }
+        }
+        ExprKind::Unary { expr, .. } => visitor.visit_expr(expr),
+        ExprKind::Binary { lhs, rhs, .. } => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        ExprKind::Call { callee, args } => {
+            visitor.visit_expr(callee);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        ExprKind::Field { expr, field } => {
+            visitor.visit_expr(expr);
+            visitor.visit_ident(field);
+        }
+        ExprKind::Index { expr, index } => {
+            visitor.visit_expr(expr);
+            visitor.visit_expr(index);
+        }
+        ExprKind::Match { scrutinee, arms } => {
+            visitor.visit_expr(scrutinee);
+            for arm in arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        ExprKind::Struct { name, fields, base } => {
+            visitor.visit_ident(name);
+            for field in fields {
+                visitor.visit_field_init(field);
+            }
+            if let Some(base) = base {
+                visitor.visit_expr(base);
+            }
+        }
+        ExprKind::Lambda { params, body } => {
+            for param in params {
+                visitor.visit_param(param);
+            }
+            visitor.visit_expr(body);
+        }
+        ExprKind::Closure { func, captures } => {
+            visitor.visit_ident(func);
+            for capture in captures {
+                visitor.visit_ident(&capture.name);
+            }
+        }
+        ExprKind::Hole(_) | ExprKind::Error => {}
+    }
+}
+
+pub fn walk_field_init<'ast, V: Visitor<'ast>>(visitor: &mut V, init: &'ast FieldInit) {
+    visitor.visit_ident(&init.name);
+    visitor.visit_expr(&init.value);
+}
+
+pub fn walk_match_arm<'ast, V: Visitor<'ast>>(visitor: &mut V, arm: &'ast MatchArm) {
+    visitor.visit_pattern(&arm.pattern);
+    if let Some(guard) = &arm.guard {
+        visitor.visit_expr(guard);
+    }
+    visitor.visit_expr(&arm.body);
+}
+
+pub fn walk_pattern<'ast, V: Visitor<'ast>>(visitor: &mut V, pattern: &'ast Pattern) {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Error => {}
+        PatternKind::Binding(name) => visitor.visit_ident(name),
+        PatternKind::Lit(lit) => visitor.visit_lit(lit),
+        PatternKind::Variant { name, args } => {
+            visitor.visit_ident(name);
+            for arg in args {
+                visitor.visit_pattern(arg);
+            }
+        }
+        PatternKind::Or(alternatives) => {
+            for alternative in alternatives {
+                visitor.visit_pattern(alternative);
+            }
+        }
+    }
+}
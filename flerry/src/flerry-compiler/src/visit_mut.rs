@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! In-place rewriting of the AST.
+//!
+//! `VisitorMut` mirrors `visit::Visitor` with mutable borrows: each
+//! `visit_*_mut` method defaults to the `walk_*` function here, which visits
+//! the node's children in source order.
+
+use crate::ast::*;
+
+pub trait VisitorMut: Sized {
+    fn visit_module_mut(&mut self, module: &mut Module) {
+        walk_module(self, module);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        walk_item(self, item);
+    }
+
+    fn visit_func_mut(&mut self, func: &mut Func) {
+        walk_func(self, func);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        walk_param(self, param);
+    }
+
+    fn visit_struct_def_mut(&mut self, def: &mut StructDef) {
+        walk_struct_def(self, def);
+    }
+
+    fn visit_enum_def_mut(&mut self, def: &mut EnumDef) {
+        walk_enum_def(self, def);
+    }
+
+    fn visit_variant_mut(&mut self, variant: &mut Variant) {
+        walk_variant(self, variant);
+    }
+
+    fn visit_field_mut(&mut self, field: &mut Field) {
+        walk_field(self, field);
+    }
+
+    fn visit_type_alias_mut(&mut self, alias: &mut TypeAlias) {
+        walk_type_alias(self, alias);
+    }
+
+    fn visit_type_expr_mut(&mut self, ty: &mut TypeExpr) {
+        walk_type_expr(self, ty);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_field_init_mut(&mut self, init: &mut FieldInit) {
+        walk_field_init(self, init);
+    }
+
+    fn visit_match_arm_mut(&mut self, arm: &mut MatchArm) {
+        walk_match_arm(self, arm);
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_lit_mut(&mut self, _lit: &mut Lit) {}
+
+    /// Called for every name in the tree, whether it binds, refers to or
+    /// labels something.
+    fn visit_ident_mut(&mut self, _ident: &mut Ident) {}
+}
+
+pub fn walk_module<V: VisitorMut>(visitor: &mut V, module: &mut Module) {
+    for item in &mut module.items {
+        visitor.visit_item_mut(item);
+    }
+}
+
+pub fn walk_item<V: VisitorMut>(visitor: &mut V, item: &mut Item) {
+    match &mut item.kind {
+        ItemKind::Import(name) => visitor.visit_ident_mut(name),
+        ItemKind::Func(func) => visitor.visit_func_mut(func),
+        ItemKind::Struct(def) => visitor.visit_struct_def_mut(def),
+        ItemKind::Enum(def) => visitor.visit_enum_def_mut(def),
+        ItemKind::Type(alias) => visitor.visit_type_alias_mut(alias),
+        ItemKind::Error => {}
+    }
+}
+
+pub fn walk_func<V: VisitorMut>(visitor: &mut V, func: &mut Func) {
+    visitor.visit_ident_mut(&mut func.name);
+    for param in &mut func.params {
+        visitor.visit_param_mut(param);
+    }
+    if let Some(ret) = &mut func.ret {
+        visitor.visit_type_expr_mut(ret);
+    }
+    walk_contracts(visitor, &mut func.contracts);
+    visitor.visit_block_mut(&mut func.body);
+}
+
+fn walk_contracts<V: VisitorMut>(visitor: &mut V, contracts: &mut [Contract]) {
+    for contract in contracts {
+        visitor.visit_expr_mut(&mut contract.cond);
+    }
+}
+
+pub fn walk_param<V: VisitorMut>(visitor: &mut V, param: &mut Param) {
+    visitor.visit_ident_mut(&mut param.name);
+    if let Some(ty) = &mut param.ty {
+        visitor.visit_type_expr_mut(ty);
+    }
+}
+
+pub fn walk_struct_def<V: VisitorMut>(visitor: &mut V, def: &mut StructDef) {
+    visitor.visit_ident_mut(&mut def.name);
+    for field in &mut def.fields {
+        visitor.visit_field_mut(field);
+    }
+}
+
+pub fn walk_enum_def<V: VisitorMut>(visitor: &mut V, def: &mut EnumDef) {
+    visitor.visit_ident_mut(&mut def.name);
+    for param in &mut def.params {
+        visitor.visit_param_mut(param);
+    }
+    for variant in &mut def.variants {
+        visitor.visit_variant_mut(variant);
+    }
+}
+
+pub fn walk_variant<V: VisitorMut>(visitor: &mut V, variant: &mut Variant) {
+    visitor.visit_ident_mut(&mut variant.name);
+    for field in &mut variant.fields {
+        visitor.visit_field_mut(field);
+    }
+    if let Some(ret) = &mut variant.ret {
+        visitor.visit_type_expr_mut(ret);
+    }
+}
+
+pub fn walk_field<V: VisitorMut>(visitor: &mut V, field: &mut Field) {
+    visitor.visit_ident_mut(&mut field.name);
+    if let Some(ty) = &mut field.ty {
+        visitor.visit_type_expr_mut(ty);
+    }
+}
+
+pub fn walk_type_alias<V: VisitorMut>(visitor: &mut V, alias: &mut TypeAlias) {
+    visitor.visit_ident_mut(&mut alias.name);
+    for param in &mut alias.params {
+        visitor.visit_ident_mut(param);
+    }
+    visitor.visit_type_expr_mut(&mut alias.ty);
+    if let Some(refinement) = &mut alias.refinement {
+        visitor.visit_expr_mut(refinement);
+    }
+}
+
+pub fn walk_type_expr<V: VisitorMut>(visitor: &mut V, ty: &mut TypeExpr) {
+    match &mut ty.kind {
+        TypeExprKind::Named { name, args } => {
+            visitor.visit_ident_mut(name);
+            for arg in args {
+                visitor.visit_type_expr_mut(arg);
+            }
+        }
+        TypeExprKind::Function { params, ret } => {
+            for param in params {
+                visitor.visit_type_expr_mut(param);
+            }
+            visitor.visit_type_expr_mut(ret);
+        }
+        TypeExprKind::Tuple(elements) => {
+            for ty in elements {
+                visitor.visit_type_expr_mut(ty);
+            }
+        }
+        TypeExprKind::Bind { name, ty } => {
+            visitor.visit_ident_mut(name);
+            visitor.visit_type_expr_mut(ty);
+        }
+        TypeExprKind::Expr(expr) => visitor.visit_expr_mut(expr),
+    }
+}
+
+pub fn walk_block<V: VisitorMut>(visitor: &mut V, block: &mut Block) {
+    for stmt in &mut block.stmts {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt<V: VisitorMut>(visitor: &mut V, stmt: &mut Stmt) {
+    match &mut stmt.kind {
+        StmtKind::Assign { target, ty, value } => {
+            visitor.visit_expr_mut(target);
+            if let Some(ty) = ty {
+                visitor.visit_type_expr_mut(ty);
+            }
+            visitor.visit_expr_mut(value);
+        }
+        StmtKind::Expr(expr) | StmtKind::Return(Some(expr)) => visitor.visit_expr_mut(expr),
+        StmtKind::If { branches, else_block } => {
+            for (cond, block) in branches {
+                visitor.visit_expr_mut(cond);
+                visitor.visit_block_mut(block);
+            }
+            if let Some(block) = else_block {
+                visitor.visit_block_mut(block);
+            }
+        }
+        StmtKind::While { cond, contracts, body } => {
+            visitor.visit_expr_mut(cond);
+            walk_contracts(visitor, contracts);
+            visitor.visit_block_mut(body);
+        }
+        StmtKind::For {
+            var,
+            iter,
+            contracts,
+            body,
+        } => {
+            visitor.visit_ident_mut(var);
+            visitor.visit_expr_mut(iter);
+            walk_contracts(visitor, contracts);
+            visitor.visit_block_mut(body);
+        }
+        StmtKind::Return(None) | StmtKind::Error => {}
+    }
+}
+
+pub fn walk_expr<V: VisitorMut>(visitor: &mut V, expr: &mut Expr) {
+    match &mut expr.kind {
+        ExprKind::Lit(lit) => visitor.visit_lit_mut(lit),
+        ExprKind::Ident(name) => visitor.visit_ident_mut(name),
+        ExprKind::List(items) | ExprKind::Tuple(items) => {
+            for item in items {
+                visitor.visit_expr_mut(item);
+            }
+        }
+        ExprKind::Unary { expr, .. } => visitor.visit_expr_mut(expr),
+        ExprKind::Binary { lhs, rhs, .. } => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+        ExprKind::Call { callee, args } => {
+            visitor.visit_expr_mut(callee);
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        ExprKind::Field { expr, field } => {
+            visitor.visit_expr_mut(expr);
+            visitor.visit_ident_mut(field);
+        }
+        ExprKind::Index { expr, index } => {
+            visitor.visit_expr_mut(expr);
+            visitor.visit_expr_mut(index);
+        }
+        ExprKind::Match { scrutinee, arms } => {
+            visitor.visit_expr_mut(scrutinee);
+            for arm in arms {
+                visitor.visit_match_arm_mut(arm);
+            }
+        }
+        ExprKind::Struct { name, fields, base } => {
+            visitor.visit_ident_mut(name);
+            for field in fields {
+                visitor.visit_field_init_mut(field);
+            }
+            if let Some(base) = base {
+                visitor.visit_expr_mut(base);
+            }
+        }
+        ExprKind::Lambda { params, body } => {
+            for param in params {
+                visitor.visit_param_mut(param);
+            }
+            visitor.visit_expr_mut(body);
+        }
+        ExprKind::Closure { func, captures } => {
+            visitor.visit_ident_mut(func);
+            for capture in captures {
+                visitor.visit_ident_mut(&mut capture.name);
+            }
+        }
+        ExprKind::Hole(_) | ExprKind::Error => {}
+    }
+}
+
+pub fn walk_field_init<V: VisitorMut>(visitor: &mut V, init: &mut FieldInit) {
+    visitor.visit_ident_mut(&mut init.name);
+    visitor.visit_expr_mut(&mut init.value);
+}
+
+pub fn walk_match_arm<V: VisitorMut>(visitor: &mut V, arm: &mut MatchArm) {
+    visitor.visit_pattern_mut(&mut arm.pattern);
+    if let Some(guard) = &mut arm.guard {
+        visitor.visit_expr_mut(guard);
+    }
+    visitor.visit_expr_mut(&mut arm.body);
+}
+
+pub fn walk_pattern<V: VisitorMut>(visitor: &mut V, pattern: &mut Pattern) {
+    match &mut pattern.kind {
+        PatternKind::Wildcard | PatternKind::Error => {}
+        PatternKind::Binding(name) => visitor.visit_ident_mut(name),
+        PatternKind::Lit(lit) => visitor.visit_lit_mut(lit),
+        PatternKind::Variant { name, args } => {
+            visitor.visit_ident_mut(name);
+            for arg in args {
+                visitor.visit_pattern_mut(arg);
+            }
+        }
+        PatternKind::Or(alternatives) => {
+            for alternative in alternatives {
+                visitor.visit_pattern_mut(alternative);
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+use crate::span::Span;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Error => write!(f, "error"),
+            Level::Warning => write!(f, "warning"),
+            Level::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// How confident the compiler is that a suggestion is correct.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Applicability {
+    /// The edit is definitely what the user wants; `flerry fix` applies it.
+    MachineApplicable,
+    /// The edit is probably right but should be reviewed by a human.
+    MaybeIncorrect,
+}
+
+/// Replaces the text covered by `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    pub edits: Vec<Edit>,
+    pub applicability: Applicability,
+}
+
+impl Suggestion {
+    pub fn replace(message: impl Into<String>, span: Span, replacement: impl Into<String>) -> Self {
+        Suggestion {
+            message: message.into(),
+            edits: vec![Edit {
+                span,
+                replacement: replacement.into(),
+            }],
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    pub fn insert(message: impl Into<String>, offset: usize, text: impl Into<String>) -> Self {
+        Suggestion::replace(message, Span::point(offset), text)
+    }
+
+    /// Marks the suggestion as needing review, so `flerry fix` leaves it alone.
+    pub fn maybe_incorrect(mut self) -> Self {
+        self.applicability = Applicability::MaybeIncorrect;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub span: Span,
+    /// Secondary spans pointing at related code, e.g. where a block was opened.
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+    /// The lint that produced this diagnostic, if it is one.
+    pub lint: Option<&'static str>,
+}
+
+impl Diagnostic {
+    pub fn new(level: Level, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            level,
+            message: message.into(),
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            suggestions: Vec::new(),
+            lint: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic::new(Level::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic::new(Level::Warning, message, span)
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    pub fn with_lint(mut self, lint: &'static str) -> Self {
+        self.lint = Some(lint);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == Level::Error
+    }
+}
+
+impl From<crate::TokenError> for Diagnostic {
+    fn from(err: crate::TokenError) -> Self {
+        let mut diagnostic = Diagnostic::error(err.message, err.span);
+        diagnostic.suggestions.extend(err.suggestion);
+        diagnostic
+    }
+}
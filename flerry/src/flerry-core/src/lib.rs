@@ -1,14 +1,22 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
 
+pub mod diagnostic;
+pub mod span;
+pub mod suggest;
+
+pub use diagnostic::{Applicability, Diagnostic, Edit, Label, Level, Suggestion};
+pub use span::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token<'a> {
     pub ttype: TokenType,
     pub lexeme: &'a str,
     pub line: usize,
+    pub span: Span,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TokenType {
     RSqBrace,
     LSqBrace,
@@ -17,21 +25,28 @@ pub enum TokenType {
     LBrace,
     RBrace,
     Comma,
+    Colon,
+    Hash,
+    Question,
     Dot,
     Minus,
     Plus,
     Slash,
     Star,
+    Percent,
 
     // One or two character tokens
+    DotDot,
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
     Greater,
     GreaterEqual,
+    GreaterGreater,
     Less,
     LessEqual,
+    LessLess,
     AmprSand,
     AmprAmprSand,
     Pipe,
@@ -59,6 +74,13 @@ pub enum TokenType {
     Match,
     Func,
     End,
+    Import,
+
+    // Trivia, only produced when lexing losslessly
+    Whitespace,
+    Comment,
+    /// Text that failed to lex, kept so no byte of the source is lost.
+    Unknown,
 
     Eof,
 }
@@ -73,6 +95,8 @@ impl std::fmt::Display for TokenType {
 pub struct TokenError {
     pub message: String,
     pub line: u32,
+    pub span: Span,
+    pub suggestion: Option<Suggestion>,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Edit-distance based "did you mean" suggestions.
+
+/// The restricted Damerau-Levenshtein (optimal string alignment) distance:
+/// the number of insertions, deletions, substitutions and transpositions of
+/// adjacent characters needed to turn `a` into `b`.
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Three rolling rows are enough: a transposition looks two rows back.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1).min(current[j - 1] + 1).min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Finds the candidate closest to `name`, if any is close enough to be a
+/// plausible typo. The allowed distance grows with the length of the name,
+/// one edit per three characters, so short names only match near-misses.
+pub fn find_best_match<'c>(name: &str, candidates: impl IntoIterator<Item = &'c str>) -> Option<&'c str> {
+    let threshold = name.chars().count().max(3) / 3;
+    let mut best: Option<(usize, &str)> = None;
+
+    for candidate in candidates {
+        if candidate == name {
+            continue;
+        }
+        // A difference only in case is always the best explanation.
+        if candidate.to_lowercase() == name.to_lowercase() {
+            return Some(candidate);
+        }
+        let distance = damerau_levenshtein(name, candidate);
+        if distance <= threshold && best.is_none_or(|(d, _)| distance < d) {
+            best = Some((distance, candidate));
+        }
+    }
+
+    best.map(|(_, candidate)| candidate)
+}
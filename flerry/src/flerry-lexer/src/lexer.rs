@@ -1,6 +1,30 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
-use flerry_core::{Token, TokenError, TokenResult, TokenType};
+use flerry_core::suggest::find_best_match;
+use flerry_core::{Diagnostic, Span, Suggestion, Token, TokenError, TokenResult, TokenType};
+
+/// Reserved words and the tokens they lex to.
+pub const KEYWORDS: &[(&str, TokenType)] = &[
+    ("type", TokenType::Type),
+    ("struct", TokenType::Struct),
+    ("enum", TokenType::Enum),
+    ("if", TokenType::If),
+    ("else", TokenType::Else),
+    ("elsif", TokenType::Elsif),
+    ("return", TokenType::Return),
+    ("while", TokenType::While),
+    ("for", TokenType::For),
+    ("match", TokenType::Match),
+    ("func", TokenType::Func),
+    ("end", TokenType::End),
+    ("import", TokenType::Import),
+];
+
+/// Returns the keyword an identifier was most likely meant to be, e.g.
+/// `elsif` for `elseif` or `return` for `retrun`.
+pub fn similar_keyword(text: &str) -> Option<&'static str> {
+    find_best_match(text, KEYWORDS.iter().map(|(keyword, _)| *keyword))
+}
 
 pub struct Lexer<'a> {
     start: usize,
@@ -19,7 +43,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    pub fn lex(&mut self) -> TokenResult {
+    pub fn lex(&mut self) -> TokenResult<'a> {
         self.start = self.current;
 
         macro_rules! token {
@@ -47,10 +71,14 @@ impl<'a> Lexer<'a> {
             '[' => token!(LSqBrace),
             ']' => token!(RSqBrace),
             ',' => token!(Comma),
-            '.' => token!(Dot),
+            ':' => token!(Colon),
+            '.' => self.check_next('.', TokenType::Dot, TokenType::DotDot),
+            '#' => token!(Hash),
+            '?' => token!(Question),
             '+' => token!(Plus),
             '/' => token!(Slash),
             '*' => token!(Star),
+            '%' => token!(Percent),
             '-' => {
                 if self.peek() == Some('>') {
                     self.advance();
@@ -68,14 +96,35 @@ impl<'a> Lexer<'a> {
                 }
             }
             '!' => self.check_next('=', TokenType::Bang, TokenType::BangEqual),
-            '<' => self.check_next('=', TokenType::Less, TokenType::LessEqual),
-            '>' => self.check_next('=', TokenType::Greater, TokenType::GreaterEqual),
+            '<' => {
+                if self.peek() == Some('<') {
+                    self.advance();
+                    token!(LessLess)
+                } else {
+                    self.check_next('=', TokenType::Less, TokenType::LessEqual)
+                }
+            }
+            '>' => {
+                if self.peek() == Some('>') {
+                    self.advance();
+                    token!(GreaterGreater)
+                } else {
+                    self.check_next('=', TokenType::Greater, TokenType::GreaterEqual)
+                }
+            }
             '&' => self.check_next('&', TokenType::AmprSand, TokenType::AmprAmprSand),
             '|' => self.check_next('|', TokenType::Pipe, TokenType::PipePipe),
             '"' => self.handle_strings(),
             _ if self.is_digit(Some(character)) => self.handle_numbers(),
             _ if self.is_alpha(Some(character)) => self.handle_identifier(),
-            _ => error!(format!("Error: unrecognized character {}", character)),
+            _ => {
+                // Take the whole character, not just its first byte.
+                while !self.source.is_char_boundary(self.current) {
+                    self.current += 1;
+                }
+                let character = &self.source[self.start..self.current];
+                error!(format!("Error: unrecognized character {}", character))
+            }
         }
     }
 
@@ -85,6 +134,7 @@ impl<'a> Lexer<'a> {
             ttype,
             lexeme: &self.source[self.start..self.current],
             line: self.line as usize,
+            span: Span::new(self.start, self.current),
         }
     }
 
@@ -92,11 +142,13 @@ impl<'a> Lexer<'a> {
         TokenError {
             message: msg,
             line: self.line,
+            span: Span::new(self.start, self.current),
+            suggestion: None,
         }
     }
 
     // Literal handlers
-    fn handle_strings(&mut self) -> TokenResult {
+    fn handle_strings(&mut self) -> TokenResult<'a> {
         while self.peek() != Some('"') && !self.is_at_end() {
             if self.peek() == Some('\n') {
                 let msg = format!("unterminated string at line {}.", self.line);
@@ -112,10 +164,10 @@ impl<'a> Lexer<'a> {
 
         // The closing quote.
         self.advance();
-        return TokenResult::Token(self.token(TokenType::Strings));
+        TokenResult::Token(self.token(TokenType::Strings))
     }
 
-    fn handle_numbers(&mut self) -> TokenResult {
+    fn handle_numbers(&mut self) -> TokenResult<'a> {
         let mut ttype: TokenType = TokenType::Integer;
 
         while self.is_digit(self.peek()) {
@@ -133,52 +185,33 @@ impl<'a> Lexer<'a> {
             self.advance();
         }
 
-        return TokenResult::Token(self.token(ttype));
+        TokenResult::Token(self.token(ttype))
     }
 
-    fn handle_identifier(&mut self) -> TokenResult {
+    fn handle_identifier(&mut self) -> TokenResult<'a> {
         while self.is_alphanumeric(self.peek()) {
             self.advance();
         }
 
         let text = &self.source[self.start..self.current];
-        let ttype = match text {
-            "type" => TokenType::Type,
-            "struct" => TokenType::Struct,
-            "enum" => TokenType::Enum,
-            "if" => TokenType::If,
-            "else" => TokenType::Else,
-            "elsif" => TokenType::Elsif,
-            "return" => TokenType::Return,
-            "while" => TokenType::While,
-            "for" => TokenType::For,
-            "match" => TokenType::Match,
-            "func" => TokenType::Func,
-            "end" => TokenType::End,
-            _ => TokenType::Identifier,
-        };
+        let ttype = KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == text)
+            .map_or(TokenType::Identifier, |(_, ttype)| *ttype);
 
-        return TokenResult::Token(self.token(ttype));
+        TokenResult::Token(self.token(ttype))
     }
     // Check Functions
     fn is_digit(&self, c: Option<char>) -> bool {
-        if c >= Some('0') && c <= Some('9') {
-            return true;
-        } else {
-            return false;
-        }
+        c >= Some('0') && c <= Some('9')
     }
 
     fn is_alpha(&self, c: Option<char>) -> bool {
-        if c >= Some('a') && c <= Some('z') || c >= Some('A') && c <= Some('Z') || c == Some('_') {
-            return true;
-        } else {
-            return false;
-        }
+        c >= Some('a') && c <= Some('z') || c >= Some('A') && c <= Some('Z') || c == Some('_')
     }
 
     fn is_alphanumeric(&self, c: Option<char>) -> bool {
-        return self.is_digit(c) || self.is_alpha(c);
+        self.is_digit(c) || self.is_alpha(c)
     }
 
     // Helper Functions
@@ -195,7 +228,7 @@ impl<'a> Lexer<'a> {
         if self.is_at_end() {
             return None;
         }
-        return Some(self.source.as_bytes()[self.current] as char);
+        Some(self.source.as_bytes()[self.current] as char)
     }
 
     pub fn peek_next(&self) -> Option<char> {
@@ -203,17 +236,17 @@ impl<'a> Lexer<'a> {
             return None;
         }
 
-        return Some(self.source.as_bytes()[self.current + 1] as char);
+        Some(self.source.as_bytes()[self.current + 1] as char)
     }
 
     pub fn cur_char(&self) -> char {
         if self.is_at_end() {
             return '\0';
         }
-        return self.source.as_bytes()[self.current] as char;
+        self.source.as_bytes()[self.current] as char
     }
 
-    fn check_next(&mut self, expected: char, single: TokenType, double: TokenType) -> TokenResult {
+    fn check_next(&mut self, expected: char, single: TokenType, double: TokenType) -> TokenResult<'a> {
         if self.peek() == Some(expected) {
             self.advance();
             TokenResult::Token(self.token(double))
@@ -222,57 +255,177 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    pub fn skip_whitespaces(&mut self) -> Option<TokenResult> {
+    /// The byte offset of the next character to be lexed.
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Whether the `#` here opens an attribute rather than a comment: a
+    /// `#[...]` alone on its line, followed by an item. Blank lines, comments
+    /// and further attributes may come in between.
+    fn at_attribute(&self) -> bool {
+        let line_start = self.source[..self.current].rfind('\n').map_or(0, |i| i + 1);
+        if !self.source[line_start..self.current].trim().is_empty() || !attribute_line(&self.source[self.current..]) {
+            return false;
+        }
+        for line in self.source[self.current..].lines().skip(1).map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let word = line
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or("");
+            return matches!(word, "func" | "import" | "struct" | "enum" | "type");
+        }
+        false
+    }
+
+    /// Skips whitespace and comments, stopping early at a malformed comment.
+    pub fn skip_whitespaces(&mut self) -> Option<TokenResult<'a>> {
         loop {
-            match self.peek() {
-                // Use peek() here to check without advancing
-                Some(' ') | Some('\r') | Some('\t') => {
+            match self.lex_trivia()? {
+                TokenResult::Token(_) => continue,
+                error => return Some(error),
+            }
+        }
+    }
+
+    /// Lexes one run of whitespace or one comment, or returns `None` if the
+    /// next character starts a real token.
+    pub fn lex_trivia(&mut self) -> Option<TokenResult<'a>> {
+        self.start = self.current;
+        match self.peek() {
+            Some(' ') | Some('\r') | Some('\t') | Some('\n') => {
+                while let Some(c @ (' ' | '\r' | '\t' | '\n')) = self.peek() {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
                     self.advance();
                 }
-                Some('\n') => {
-                    self.line += 1;
+                Some(TokenResult::Token(self.token(TokenType::Whitespace)))
+            }
+            // `#[` opens an attribute, which lex() turns into tokens.
+            Some('#') if self.at_attribute() => None,
+            Some('#') => {
+                self.advance(); // Consume '#'
+                while self.peek() != Some('\n') && !self.is_at_end() {
                     self.advance();
                 }
-                Some('#') => {
-                    self.advance(); // Consume '#'
-                    while self.peek() != Some('\n') && !self.is_at_end() {
-                        self.advance();
-                    }
+                Some(TokenResult::Token(self.token(TokenType::Comment)))
+            }
+            Some('/') if self.peek_next() == Some('/') => {
+                // `//` is a common habit from other languages; skip the line so
+                // lexing can continue, but point the user at `#`.
+                self.advance(); // Consume '/'
+                self.advance(); // Consume '/'
+                let slashes = Span::new(self.start, self.current);
+                while self.peek() != Some('\n') && !self.is_at_end() {
+                    self.advance();
                 }
-                Some('(') => {
-                    if self.peek_next() == Some('*') {
+                let mut error = self.error_token("`//` does not start a comment".to_owned());
+                error.span = slashes;
+                error.suggestion = Some(Suggestion::replace("use `#` for line comments", slashes, "#"));
+                Some(TokenResult::Error(error))
+            }
+            Some('(') if self.peek_next() == Some('*') => {
+                self.advance(); // Consume '('
+                self.advance(); // Consume '*'
+                let mut comment_depth = 1;
+                while comment_depth > 0 && !self.is_at_end() {
+                    if self.peek() == Some('(') && self.peek_next() == Some('*') {
                         self.advance(); // Consume '('
                         self.advance(); // Consume '*'
-                        let mut comment_depth = 1;
-                        while comment_depth > 0 && !self.is_at_end() {
-                            if self.peek() == Some('(') && self.peek_next() == Some('*') {
-                                self.advance(); // Consume '('
-                                self.advance(); // Consume '*'
-                                comment_depth += 1;
-                            } else if self.peek() == Some('*') && self.peek_next() == Some(')') {
-                                self.advance(); // Consume '*'
-                                self.advance(); // Consume ')'
-                                comment_depth -= 1;
-                            } else if self.peek() == Some('\n') {
-                                self.line += 1;
-                                self.advance();
-                            } else {
-                                self.advance();
-                            }
-                        }
-                        if comment_depth > 0 {
-                            return Some(TokenResult::Error(
-                                self.error_token("Unterminated multi-line comment".to_owned()),
-                            ));
-                        }
-                        // Continue the loop to skip any further whitespaces or comments
-                        continue;
+                        comment_depth += 1;
+                    } else if self.peek() == Some('*') && self.peek_next() == Some(')') {
+                        self.advance(); // Consume '*'
+                        self.advance(); // Consume ')'
+                        comment_depth -= 1;
+                    } else if self.peek() == Some('\n') {
+                        self.line += 1;
+                        self.advance();
                     } else {
-                        return None; // Not a multi-line comment, let lex() handle it
+                        self.advance();
                     }
                 }
-                _ => return None, // Not a whitespace or comment start, let lex() handle it
+                if comment_depth > 0 {
+                    return Some(TokenResult::Error(
+                        self.error_token("Unterminated multi-line comment".to_owned()),
+                    ));
+                }
+                Some(TokenResult::Token(self.token(TokenType::Comment)))
             }
+            _ => None, // Not a whitespace or comment start, let lex() handle it
         }
     }
 }
+
+/// Whether the line starting at `text` is a single `#[...]` attribute, with
+/// at most a comment after it.
+fn attribute_line(text: &str) -> bool {
+    let line = text.lines().next().unwrap_or("");
+    if !line.starts_with("#[") {
+        return false;
+    }
+    let mut depth = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => continue,
+        }
+        if depth == 0 {
+            let rest = line[i + 1..].trim_start();
+            return rest.is_empty() || rest.starts_with('#') && !rest.starts_with("#[");
+        }
+    }
+    false
+}
+
+/// Lexes the whole of `source`, collecting every token up to and including
+/// `Eof` together with the lexical errors encountered along the way.
+pub fn tokenize(source: &str) -> (Vec<Token<'_>>, Vec<Diagnostic>) {
+    let (tokens, diagnostics) = tokenize_lossless(source);
+    let tokens = tokens
+        .into_iter()
+        .filter(|t| !matches!(t.ttype, TokenType::Whitespace | TokenType::Comment | TokenType::Unknown))
+        .collect();
+    (tokens, diagnostics)
+}
+
+/// Like `tokenize`, but keeps whitespace and comments as tokens, and any text
+/// that failed to lex as `Unknown`, so the lexemes concatenate back to `source`.
+pub fn tokenize_lossless(source: &str) -> (Vec<Token<'_>>, Vec<Diagnostic>) {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    loop {
+        let start = lexer.position();
+        let (result, fallback) = match lexer.lex_trivia() {
+            Some(result) => (result, TokenType::Comment),
+            None => (lexer.lex(), TokenType::Unknown),
+        };
+        match result {
+            TokenResult::Token(token) => {
+                let eof = token.ttype == TokenType::Eof;
+                tokens.push(token);
+                if eof {
+                    break;
+                }
+            }
+            TokenResult::Error(err) => {
+                // Errors that stop short of a newline leave it for the next round.
+                tokens.push(Token {
+                    ttype: fallback,
+                    lexeme: &source[start..lexer.position()],
+                    line: err.line as usize,
+                    span: Span::new(start, lexer.position()),
+                });
+                diagnostics.push(Diagnostic::from(err));
+            }
+        }
+    }
+
+    (tokens, diagnostics)
+}
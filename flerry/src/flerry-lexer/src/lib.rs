@@ -5,7 +5,7 @@ pub mod lexer;
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::Lexer;
+    use crate::lexer::{Lexer, tokenize, tokenize_lossless};
     use flerry_core::{TokenResult, TokenType};
 
     #[test]
@@ -205,6 +205,28 @@ mod tests {
             _ => panic!("Expected integer after nested multi-line comment"),
         }
 
+        // `//` is not a comment, but lexing recovers past it with a fix attached
+        let source_slashes = "1 // not a comment\n2";
+        let mut lexer_slashes = Lexer::new(source_slashes);
+        lexer_slashes.skip_whitespaces();
+        lexer_slashes.lex();
+        match lexer_slashes.skip_whitespaces() {
+            Some(TokenResult::Error(err)) => {
+                assert_eq!(err.span, flerry_core::Span::new(2, 4));
+                let suggestion = err.suggestion.expect("Expected a suggestion to use `#`");
+                assert_eq!(suggestion.edits[0].replacement, "#");
+            }
+            _ => panic!("Expected error for `//` comment"),
+        }
+        lexer_slashes.skip_whitespaces();
+        match lexer_slashes.lex() {
+            TokenResult::Token(token) => {
+                assert_eq!(token.lexeme, "2");
+                assert_eq!(token.span, flerry_core::Span::new(19, 20));
+            }
+            _ => panic!("Expected integer after `//` comment"),
+        }
+
         // Unterminated multi-line comment
         let source_unterminated = "(* This is an unterminated comment";
         let mut lexer_unterminated = Lexer::new(source_unterminated);
@@ -292,4 +314,145 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_lossless_tokens() {
+        let source = "x = 1 # one\n(* two *)\t// three\n@ \"open";
+        let (tokens, errors) = tokenize_lossless(source);
+        let kinds: Vec<(TokenType, &str)> = tokens.iter().map(|t| (t.ttype, t.lexeme)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (TokenType::Identifier, "x"),
+                (TokenType::Whitespace, " "),
+                (TokenType::Equal, "="),
+                (TokenType::Whitespace, " "),
+                (TokenType::Integer, "1"),
+                (TokenType::Whitespace, " "),
+                (TokenType::Comment, "# one"),
+                (TokenType::Whitespace, "\n"),
+                (TokenType::Comment, "(* two *)"),
+                (TokenType::Whitespace, "\t"),
+                (TokenType::Comment, "// three"),
+                (TokenType::Whitespace, "\n"),
+                (TokenType::Unknown, "@"),
+                (TokenType::Whitespace, " "),
+                (TokenType::Unknown, "\"open"),
+                (TokenType::Eof, ""),
+            ]
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(tokens.iter().map(|t| t.lexeme).collect::<String>(), source);
+
+        // The parser's view drops the trivia and whatever failed to lex.
+        let (significant, _) = tokenize(source);
+        let lexemes: Vec<&str> = significant.iter().map(|t| t.lexeme).collect();
+        assert_eq!(lexemes, ["x", "=", "1", ""]);
+    }
+
+    #[test]
+    fn test_range_and_spread_tokens() {
+        let (tokens, errors) = tokenize(". .. 1..2 ..p");
+        assert!(errors.is_empty(), "{errors:?}");
+        let kinds: Vec<(TokenType, &str)> = tokens.iter().map(|t| (t.ttype, t.lexeme)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (TokenType::Dot, "."),
+                (TokenType::DotDot, ".."),
+                (TokenType::Integer, "1"),
+                (TokenType::DotDot, ".."),
+                (TokenType::Integer, "2"),
+                (TokenType::DotDot, ".."),
+                (TokenType::Identifier, "p"),
+                (TokenType::Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attributes_and_hash_comments() {
+        let kinds = |source| {
+            let (tokens, _) = tokenize(source);
+            tokens.iter().map(|t| t.ttype).collect::<Vec<_>>()
+        };
+        // Alone on its line before an item, `#[` opens an attribute.
+        assert_eq!(
+            kinds("  #[allow(x)] # why\n\n#[deny(y)]\nimport IO"),
+            vec![
+                TokenType::Hash,
+                TokenType::LSqBrace,
+                TokenType::Identifier,
+                TokenType::LParen,
+                TokenType::Identifier,
+                TokenType::RParen,
+                TokenType::RSqBrace,
+                TokenType::Hash,
+                TokenType::LSqBrace,
+                TokenType::Identifier,
+                TokenType::LParen,
+                TokenType::Identifier,
+                TokenType::RParen,
+                TokenType::RSqBrace,
+                TokenType::Import,
+                TokenType::Identifier,
+                TokenType::Eof,
+            ]
+        );
+        // Anywhere else it is an ordinary comment.
+        assert_eq!(kinds("#[1] see the spec\nfunc"), vec![TokenType::Func, TokenType::Eof]);
+        assert_eq!(
+            kinds("x = 1 #[note] this\nfunc"),
+            vec![
+                TokenType::Identifier,
+                TokenType::Equal,
+                TokenType::Integer,
+                TokenType::Func,
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(kinds("#[allow(x)]\nx = 1"), kinds("x = 1"));
+    }
+
+    #[test]
+    fn test_hole_tokens() {
+        let (tokens, diagnostics) = tokenize("? ?goal");
+        assert!(diagnostics.is_empty());
+        let tokens: Vec<_> = tokens.iter().map(|t| (t.ttype, t.lexeme)).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Question, "?"),
+                (TokenType::Question, "?"),
+                (TokenType::Identifier, "goal"),
+                (TokenType::Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operator_tokens() {
+        let (tokens, diagnostics) = tokenize("a % b << c >> d <<= >>= & |");
+        assert!(diagnostics.is_empty());
+        let tokens: Vec<_> = tokens.iter().map(|t| (t.ttype, t.lexeme)).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Identifier, "a"),
+                (TokenType::Percent, "%"),
+                (TokenType::Identifier, "b"),
+                (TokenType::LessLess, "<<"),
+                (TokenType::Identifier, "c"),
+                (TokenType::GreaterGreater, ">>"),
+                (TokenType::Identifier, "d"),
+                (TokenType::LessLess, "<<"),
+                (TokenType::Equal, "="),
+                (TokenType::GreaterGreater, ">>"),
+                (TokenType::Equal, "="),
+                (TokenType::AmprSand, "&"),
+                (TokenType::Pipe, "|"),
+                (TokenType::Eof, ""),
+            ]
+        );
+    }
 }
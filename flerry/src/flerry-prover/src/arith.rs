@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Conjunctions of linear constraints over integer and rational variables.
+//!
+//! The simplex decides the constraints over the rationals. Over the
+//! integers that is not enough, so two things narrow it down: a constraint
+//! over integer variables only is divided through by the common factor of
+//! its coefficients and its constant rounded, a cut no integer solution is
+//! on the wrong side of; and an integer variable the simplex gives a
+//! fraction is branched on, `x <= 2` or `x >= 3`, each case solved again.
+//!
+//! An unsatisfiable answer comes with a certificate that
+//! [`Certificate::check`] confirms from the constraints alone, so the
+//! simplex is not trusted to be right.
+
+use crate::linear::{Constraint, Linear, Relation};
+use crate::rational::Rational;
+use crate::simplex::{Bound, Check, Delta, Simplex};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Instant;
+
+/// The most cases branching may split into before giving up.
+const MAX_BRANCHES: usize = 2_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Values for the variables in the constraints.
+    Sat(BTreeMap<String, Rational>),
+    Unsat(Certificate),
+    Unknown(String),
+}
+
+/// Why constraints have no solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Certificate {
+    /// Multiples of the constraints, by index, whose sum has no variables
+    /// and is false. Multiples of inequalities are not negative, and a
+    /// constraint over integer variables only is taken as it is tightened.
+    Farkas(BTreeMap<usize, Rational>),
+    /// The equality at the index is over integer variables only, and the
+    /// common factor of its coefficients does not divide its constant.
+    Indivisible(usize),
+    /// The integer `var` is at most `value` or at least `value + 1`. Each
+    /// case is refuted with that bound added after the constraints.
+    Branch {
+        var: String,
+        value: i128,
+        below: Box<Certificate>,
+        above: Box<Certificate>,
+    },
+}
+
+/// Whether the constraints have a common solution, with the variables in
+/// `integers` taking integer values.
+pub fn solve(constraints: &[Constraint], integers: &BTreeSet<String>, deadline: Instant) -> Outcome {
+    let mut tableau = Tableau::default();
+    for (index, constraint) in constraints.iter().enumerate() {
+        let Some(constraint) = tighten(constraint, integers) else {
+            return Outcome::Unsat(Certificate::Indivisible(index));
+        };
+        if let Err(conflict) = tableau.assert(&constraint, index) {
+            return Outcome::Unsat(Certificate::Farkas(conflict));
+        }
+    }
+    let mut search = Search {
+        integers,
+        deadline,
+        branches: 0,
+    };
+    search.branch(tableau, constraints.len())
+}
+
+impl Certificate {
+    /// Whether the certificate shows the constraints have no solution.
+    pub fn check(&self, constraints: &[Constraint], integers: &BTreeSet<String>) -> bool {
+        match self {
+            Certificate::Farkas(multiples) => {
+                let mut coeffs: BTreeMap<&str, Rational> = BTreeMap::new();
+                let mut constant = Rational::ZERO;
+                let mut strict = false;
+                let tightened: Vec<Option<Constraint>> = multiples
+                    .keys()
+                    .map(|&index| tighten(constraints.get(index)?, integers))
+                    .collect();
+                for ((_, &multiple), constraint) in multiples.iter().zip(&tightened) {
+                    let Some(constraint) = constraint else {
+                        return false;
+                    };
+                    match constraint.relation {
+                        Relation::Eq => {}
+                        _ if multiple.is_negative() => return false,
+                        Relation::Le => {}
+                        Relation::Lt => strict |= multiple.is_positive(),
+                    }
+                    for (name, coeff) in &constraint.expr.coeffs {
+                        let sum = coeffs.entry(name).or_insert(Rational::ZERO);
+                        *sum = *sum + multiple * Rational::int(*coeff);
+                    }
+                    constant = constant + multiple * Rational::int(constraint.expr.constant);
+                }
+                if constant.is_overflow() || coeffs.values().any(|coeff| !coeff.is_zero()) {
+                    return false;
+                }
+                // The sum is at most zero, or below it if a strict
+                // inequality is in it.
+                constant.is_positive() || constant.is_zero() && strict
+            }
+            Certificate::Indivisible(index) => constraints.get(*index).is_some_and(|constraint| {
+                constraint.relation == Relation::Eq
+                    && constraint.expr.coeffs.keys().all(|name| integers.contains(name))
+                    && constraint.normalize().is_none()
+            }),
+            Certificate::Branch {
+                var,
+                value,
+                below,
+                above,
+            } => {
+                let Some((lower, upper)) = split(var, *value) else {
+                    return false;
+                };
+                let with = |bound: Constraint| [constraints, &[bound]].concat();
+                integers.contains(var) && below.check(&with(lower), integers) && above.check(&with(upper), integers)
+            }
+        }
+    }
+
+    /// The indices of the first `count` constraints the certificate uses,
+    /// and the certificate for those constraints alone, numbered in order.
+    /// The bounds branching added after them keep their order too.
+    pub fn narrow(&self, count: usize) -> (Vec<usize>, Certificate) {
+        let mut core = BTreeSet::new();
+        self.uses(count, &mut core);
+        let core: Vec<usize> = core.into_iter().collect();
+        let renumber = |index: usize| match core.binary_search(&index) {
+            Ok(position) => position,
+            Err(_) => index - count + core.len(),
+        };
+        let narrowed = self.renumber(&renumber);
+        (core, narrowed)
+    }
+
+    fn uses(&self, count: usize, core: &mut BTreeSet<usize>) {
+        match self {
+            Certificate::Farkas(multiples) => core.extend(multiples.keys().filter(|&&index| index < count)),
+            Certificate::Indivisible(index) if *index < count => {
+                core.insert(*index);
+            }
+            Certificate::Indivisible(_) => {}
+            Certificate::Branch { below, above, .. } => {
+                below.uses(count, core);
+                above.uses(count, core);
+            }
+        }
+    }
+
+    fn renumber(&self, renumber: &dyn Fn(usize) -> usize) -> Certificate {
+        match self {
+            Certificate::Farkas(multiples) => Certificate::Farkas(
+                multiples
+                    .iter()
+                    .map(|(&index, &multiple)| (renumber(index), multiple))
+                    .collect(),
+            ),
+            Certificate::Indivisible(index) => Certificate::Indivisible(renumber(*index)),
+            Certificate::Branch {
+                var,
+                value,
+                below,
+                above,
+            } => Certificate::Branch {
+                var: var.clone(),
+                value: *value,
+                below: Box::new(below.renumber(renumber)),
+                above: Box::new(above.renumber(renumber)),
+            },
+        }
+    }
+}
+
+/// The constraint as the solver takes it: over integer variables only, the
+/// tightest one with the same integer solutions. `None` if it has none.
+fn tighten(constraint: &Constraint, integers: &BTreeSet<String>) -> Option<Constraint> {
+    if constraint.expr.coeffs.keys().all(|name| integers.contains(name)) {
+        constraint.normalize()
+    } else {
+        Some(constraint.clone())
+    }
+}
+
+/// `var <= value` and `var >= value + 1`, as constraints.
+fn split(var: &str, value: i128) -> Option<(Constraint, Constraint)> {
+    let below = Linear::var(var).add_scaled(&Linear::constant(value), -1)?;
+    let above = below.scale(-1)?.add_scaled(&Linear::constant(1), 1)?;
+    Some((Constraint::le(below), Constraint::le(above)))
+}
+
+/// The simplex, with a variable for each variable of the constraints and
+/// for each form with more than one.
+#[derive(Debug, Clone, Default)]
+struct Tableau {
+    simplex: Simplex,
+    vars: BTreeMap<String, usize>,
+    forms: HashMap<BTreeMap<String, i128>, usize>,
+}
+
+impl Tableau {
+    /// Adds the bounds `constraint` puts on a variable, or the conflict
+    /// they are in at once.
+    fn assert(&mut self, constraint: &Constraint, reason: usize) -> Result<(), BTreeMap<usize, Rational>> {
+        let expr = &constraint.expr;
+        let constant = Rational::int(expr.constant);
+        let (var, coeff) = match expr.coeffs.len() {
+            0 => {
+                let holds = match constraint.relation {
+                    Relation::Le => expr.constant <= 0,
+                    Relation::Lt => expr.constant < 0,
+                    Relation::Eq => expr.constant == 0,
+                };
+                if holds {
+                    return Ok(());
+                }
+                // Once the constraint itself, or minus an equality below zero.
+                let multiple = if expr.constant < 0 {
+                    -Rational::ONE
+                } else {
+                    Rational::ONE
+                };
+                return Err(BTreeMap::from([(reason, multiple)]));
+            }
+            1 => {
+                let (name, coeff) = expr.coeffs.iter().next().expect("there is one variable");
+                (self.var(name), Rational::int(*coeff))
+            }
+            _ => (self.form(&expr.coeffs), Rational::ONE),
+        };
+        // `coeff * var + constant ⋈ 0` bounds `var` by `-constant / coeff`.
+        let value = -constant / coeff;
+        let strict = if constraint.relation == Relation::Lt {
+            Rational::ONE
+        } else {
+            Rational::ZERO
+        };
+        let upper = Bound {
+            value: Delta::new(value, -strict),
+            reason,
+            scale: Rational::ONE / coeff,
+        };
+        let lower = Bound {
+            value: Delta::new(value, strict),
+            reason,
+            scale: -Rational::ONE / coeff,
+        };
+        match constraint.relation {
+            Relation::Eq => {
+                self.simplex.upper(var, upper)?;
+                self.simplex.lower(var, lower)
+            }
+            _ if coeff.is_positive() => self.simplex.upper(var, upper),
+            _ => self.simplex.lower(var, lower),
+        }
+    }
+
+    fn var(&mut self, name: &str) -> usize {
+        if let Some(&var) = self.vars.get(name) {
+            return var;
+        }
+        let var = self.simplex.var();
+        self.vars.insert(name.to_owned(), var);
+        var
+    }
+
+    /// The variable defined as the sum of the coefficients times their
+    /// variables.
+    fn form(&mut self, coeffs: &BTreeMap<String, i128>) -> usize {
+        if let Some(&var) = self.forms.get(coeffs) {
+            return var;
+        }
+        let form = coeffs
+            .iter()
+            .map(|(name, coeff)| (self.var(name), Rational::int(*coeff)))
+            .collect();
+        let var = self.simplex.define(&form);
+        self.forms.insert(coeffs.clone(), var);
+        var
+    }
+
+    fn values(&self) -> BTreeMap<String, Rational> {
+        let values = self.simplex.model();
+        self.vars
+            .iter()
+            .map(|(name, &var)| (name.clone(), values[var]))
+            .collect()
+    }
+}
+
+struct Search<'i> {
+    integers: &'i BTreeSet<String>,
+    deadline: Instant,
+    branches: usize,
+}
+
+impl Search<'_> {
+    /// Solves the tableau, whose constraints number `count`, branching on
+    /// integer variables with fractional values.
+    fn branch(&mut self, mut tableau: Tableau, count: usize) -> Outcome {
+        match tableau.simplex.check() {
+            Check::Sat => {}
+            Check::Conflict(multiples) => return Outcome::Unsat(Certificate::Farkas(multiples)),
+            Check::Overflow => return Outcome::Unknown("arithmetic overflow".to_owned()),
+        }
+        let values = tableau.values();
+        if values.values().any(|value| value.is_overflow()) {
+            return Outcome::Unknown("arithmetic overflow".to_owned());
+        }
+        let fraction = values
+            .iter()
+            .find(|(name, value)| self.integers.contains(*name) && !value.is_integer());
+        let Some((var, value)) = fraction else {
+            return Outcome::Sat(values);
+        };
+        if Instant::now() > self.deadline {
+            return Outcome::Unknown("timeout".to_owned());
+        }
+        self.branches += 1;
+        if self.branches > MAX_BRANCHES {
+            return Outcome::Unknown("too many cases".to_owned());
+        }
+        let value = value.floor().num();
+        let Some((lower, upper)) = split(var, value) else {
+            return Outcome::Unknown("arithmetic overflow".to_owned());
+        };
+        let mut cases = Vec::new();
+        for bound in [lower, upper] {
+            let mut tableau = tableau.clone();
+            let outcome = match tableau.assert(&bound, count) {
+                Ok(()) => self.branch(tableau, count + 1),
+                Err(conflict) => Outcome::Unsat(Certificate::Farkas(conflict)),
+            };
+            match outcome {
+                Outcome::Unsat(certificate) => cases.push(certificate),
+                outcome => return outcome,
+            }
+        }
+        let above = cases.pop().expect("there are two cases");
+        let below = cases.pop().expect("there are two cases");
+        Outcome::Unsat(Certificate::Branch {
+            var: var.clone(),
+            value,
+            below: Box::new(below),
+            above: Box::new(above),
+        })
+    }
+}
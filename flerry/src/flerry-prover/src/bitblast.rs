@@ -0,0 +1,795 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Formulas over bit-vectors, decided by bit-blasting: every fixed-width
+//! term becomes a circuit for each of its bits, and the formula one circuit
+//! the SAT solver decides.
+//!
+//! The formula is first simplified a word at a time: it is taken apart into
+//! its conjuncts, constants are folded and terms that cancel dropped, and a
+//! variable that a conjunct sets equal to a term is replaced by the term
+//! everywhere. Building the circuits then folds constants bit by bit and
+//! shares equal gates, so the bits of a widened operand that are known to
+//! be zero cost nothing, and neither do the partial products they are in.
+//!
+//! Integer and rational comparisons are variables to the SAT solver. Each
+//! model it finds has the comparisons it makes true checked together by the
+//! arithmetic solver, and if they cannot all hold, a clause ruling out the
+//! fewest of them that cannot is added and the search goes on. Applications
+//! of uninterpreted functions are variables too, with the instances of
+//! congruence a model breaks added the same way.
+//!
+//! Before any of that, the variables are tried at the values bugs are
+//! usually found at: zero, one, minus one and the least and greatest
+//! values of their widths. An overflow is then found at once, with a
+//! counterexample that reads as one, where the search could take long to
+//! multiply its way there.
+
+use crate::bits::Bits;
+use crate::rational::Rational;
+use crate::sat::{self, Lit};
+use crate::solver::{self, Limits, Proof, Satisfiability};
+use crate::term::{BitOp, Cmp, Model, Sort, Term, Value};
+use std::collections::HashMap;
+use std::ops;
+use std::time::Instant;
+
+/// The most assignments of boundary values tried before bit-blasting.
+const MAX_GUESSES: usize = 256;
+
+/// Whether any part of `term` is a bit-vector.
+pub fn involves_bits(term: &Term) -> bool {
+    matches!(term.sort(), Sort::Bits { .. }) || term.children().into_iter().any(involves_bits)
+}
+
+/// Whether some values of its variables make `formula`, which involves
+/// bit-vectors, true.
+pub fn check(formula: &Term, limits: &Limits) -> Satisfiability {
+    let deadline = Instant::now() + limits.timeout;
+    let mut apps = Vec::new();
+    let abstracted = abstract_apps(formula, &mut apps);
+    if apps.is_empty()
+        && let Some(model) = guess(&abstracted)
+    {
+        return Satisfiability::Sat(model);
+    }
+    let (simplified, definitions) = preprocess(&abstracted);
+    let mut blaster = Blaster::new();
+    match blaster.formula(&simplified) {
+        Ok(root) => blaster.assert(root),
+        Err(reason) => return Satisfiability::Unknown(reason),
+    }
+    let mut lemmas = Vec::new();
+    loop {
+        let values = match blaster.sat.solve(&[], Some(deadline)) {
+            sat::Outcome::Sat(values) => values,
+            sat::Outcome::Unsat(_) => return Satisfiability::Unsat(Proof::BitBlasted(lemmas)),
+            sat::Outcome::Unknown(reason) => return Satisfiability::Unknown(reason),
+        };
+        let mut model = blaster.model(&values);
+        let atoms: Vec<Term> = blaster
+            .atoms
+            .iter()
+            .map(|(atom, lit)| {
+                if value(*lit, &values) {
+                    atom.clone()
+                } else {
+                    !atom.clone()
+                }
+            })
+            .collect();
+        match theory(&atoms, deadline) {
+            Theory::Sat(values) => {
+                for (name, value) in values.iter() {
+                    model.insert(name, value);
+                }
+            }
+            Theory::Unsat(core, proof) => {
+                // None of the comparisons in the core is decided by a
+                // constant, so each has a literal.
+                let clause: Vec<Lit> = core
+                    .iter()
+                    .map(|atom| match blaster.formula(atom) {
+                        Ok(Bit::Lit(lit)) => !lit,
+                        _ => unreachable!("a comparison the solver decides has a literal"),
+                    })
+                    .collect();
+                blaster.sat.add_clause(&clause);
+                lemmas.push(proof);
+                continue;
+            }
+            Theory::Unknown(reason) => return Satisfiability::Unknown(reason),
+        }
+        // Variables the circuits lost, or never had, can be anything.
+        for (name, sort) in abstracted.vars() {
+            if model.get(&name).is_none() {
+                model.insert(name, default(sort));
+            }
+        }
+        for (name, definition) in &definitions {
+            match definition.eval(&model) {
+                Some(value) => model.insert(name.clone(), value),
+                None => return Satisfiability::Unknown("a variable eliminated has no value".to_owned()),
+            }
+        }
+        if let Some(lemma) = congruence(&apps, &model) {
+            match blaster.formula(&lemma) {
+                Ok(bit) => blaster.assert(bit),
+                Err(reason) => return Satisfiability::Unknown(reason),
+            }
+            continue;
+        }
+        if abstracted.eval(&model) != Some(Value::Bool(true)) {
+            return Satisfiability::Unknown("the model found does not satisfy the formula".to_owned());
+        }
+        // Applications are shown as they are written.
+        for app in &apps {
+            if let Some(value) = model.remove(&app.name) {
+                model.insert(app.text.clone(), value);
+            }
+        }
+        return Satisfiability::Sat(model);
+    }
+}
+
+fn default(sort: Sort) -> Value {
+    match sort {
+        Sort::Int => Value::Int(0),
+        Sort::Real => Value::Real(Rational::ZERO),
+        Sort::Bits { width, signed } => Value::Bits(Bits::zero(width, signed)),
+        Sort::Bool => Value::Bool(false),
+    }
+}
+
+/// Values at the edges of their sorts that make `formula` true, if the
+/// first few combinations of them find some. Only formulas over
+/// bit-vectors and booleans are tried.
+fn guess(formula: &Term) -> Option<Model> {
+    let mut candidates: Vec<(String, Vec<Value>)> = Vec::new();
+    for (name, sort) in formula.vars() {
+        let values = match sort {
+            Sort::Bits { width, signed } => {
+                let mut values = vec![
+                    Bits::zero(width, signed),
+                    Bits::new(width, signed, 1),
+                    Bits::new(width, signed, -1),
+                    Bits::min(width, signed),
+                    Bits::max(width, signed),
+                ];
+                values.sort();
+                values.dedup();
+                values.into_iter().map(Value::Bits).collect()
+            }
+            Sort::Bool => vec![Value::Bool(false), Value::Bool(true)],
+            Sort::Int | Sort::Real => return None,
+        };
+        candidates.push((name, values));
+    }
+    // The combinations counted in a mixed radix, the first variable
+    // changing fastest.
+    for mut index in 0..MAX_GUESSES {
+        let mut model = Model::new();
+        for (name, values) in &candidates {
+            model.insert(name.clone(), values[index % values.len()]);
+            index /= values.len();
+        }
+        if index > 0 {
+            // Every combination has been tried.
+            return None;
+        }
+        if formula.eval(&model) == Some(Value::Bool(true)) {
+            return Some(model);
+        }
+    }
+    None
+}
+
+fn value(lit: Lit, values: &[bool]) -> bool {
+    values[lit.var()] == lit.is_positive()
+}
+
+/// An application of an uninterpreted function, standing for a variable.
+struct App {
+    /// The application as written, to show it in a model.
+    text: String,
+    name: String,
+    function: String,
+    /// The arguments, with the applications in them replaced.
+    args: Vec<Term>,
+    sort: Sort,
+}
+
+/// `term` with every application replaced by a variable, the same one for
+/// the same application.
+fn abstract_apps(term: &Term, apps: &mut Vec<App>) -> Term {
+    let Term::App(function, args, sort) = term else {
+        return term.map(&mut |child| abstract_apps(child, apps));
+    };
+    let text = term.to_string();
+    if let Some(app) = apps.iter().find(|app| app.text == text) {
+        return Term::var(app.name.clone(), *sort);
+    }
+    let args = args.iter().map(|arg| abstract_apps(arg, apps)).collect();
+    let name = format!("%{}", apps.len());
+    apps.push(App {
+        text,
+        name: name.clone(),
+        function: function.clone(),
+        args,
+        sort: *sort,
+    });
+    Term::var(name, *sort)
+}
+
+/// An instance of congruence that `model` breaks: two applications of a
+/// function whose arguments it makes equal, but not their results. The
+/// lemma says that if the arguments are equal, so are the results.
+fn congruence(apps: &[App], model: &Model) -> Option<Term> {
+    for (i, a) in apps.iter().enumerate() {
+        for b in &apps[i + 1..] {
+            if a.function != b.function || a.args.len() != b.args.len() {
+                continue;
+            }
+            let same_args = a.args.iter().zip(&b.args).all(|(x, y)| x.eval(model) == y.eval(model));
+            if !same_args || model.get(&a.name) == model.get(&b.name) {
+                continue;
+            }
+            let differ = a.args.iter().zip(&b.args).map(|(x, y)| !Term::eq(x.clone(), y.clone()));
+            let results = Term::eq(Term::var(a.name.clone(), a.sort), Term::var(b.name.clone(), b.sort));
+            return Some(Term::or(differ.chain([results])));
+        }
+    }
+    None
+}
+
+enum Theory {
+    Sat(Model),
+    /// Comparisons that cannot all hold, and why.
+    Unsat(Vec<Term>, Proof),
+    Unknown(String),
+}
+
+/// Whether the arithmetic comparisons can all hold. If not, the ones that
+/// cannot are narrowed down until each is needed.
+fn theory(atoms: &[Term], deadline: Instant) -> Theory {
+    let solve = |atoms: &[Term]| {
+        let limits = Limits {
+            timeout: deadline.saturating_duration_since(Instant::now()),
+        };
+        solver::check(&Term::and(atoms.to_vec()), &limits)
+    };
+    let mut proof = match solve(atoms) {
+        Satisfiability::Sat(model) => return Theory::Sat(model),
+        Satisfiability::Unsat(proof) => proof,
+        Satisfiability::Unknown(reason) => return Theory::Unknown(reason),
+    };
+    let mut core = atoms.to_vec();
+    let mut index = 0;
+    while index < core.len() {
+        let mut without = core.clone();
+        without.remove(index);
+        match solve(&without) {
+            Satisfiability::Unsat(smaller) => {
+                core = without;
+                proof = smaller;
+            }
+            _ => index += 1,
+        }
+    }
+    Theory::Unsat(core, proof)
+}
+
+/// The formula as a conjunction simplified a word at a time, and the
+/// variables taken out of it with the terms they equal, over the variables
+/// left.
+fn preprocess(formula: &Term) -> (Term, Vec<(String, Term)>) {
+    let mut conjuncts = Vec::new();
+    flatten(formula, true, &mut conjuncts);
+    let mut conjuncts: Vec<Term> = conjuncts.iter().map(simplify).collect();
+    let mut definitions: Vec<(String, Term)> = Vec::new();
+    while let Some((index, (name, value))) = conjuncts
+        .iter()
+        .enumerate()
+        .find_map(|(index, conjunct)| Some((index, definition(conjunct)?)))
+    {
+        conjuncts.remove(index);
+        let substitution = HashMap::from([(name.clone(), value.clone())]);
+        conjuncts = conjuncts
+            .iter()
+            .map(|conjunct| simplify(&conjunct.substitute(&substitution)))
+            .collect();
+        for (_, definition) in &mut definitions {
+            *definition = definition.substitute(&substitution);
+        }
+        definitions.push((name, value));
+    }
+    (Term::and(conjuncts), definitions)
+}
+
+/// Adds the conjuncts of `term`, or of its negation when not `positive`.
+fn flatten(term: &Term, positive: bool, out: &mut Vec<Term>) {
+    match term {
+        Term::Not(inner) => flatten(inner, !positive, out),
+        Term::And(terms) if positive => terms.iter().for_each(|term| flatten(term, true, out)),
+        Term::Or(terms) if !positive => terms.iter().for_each(|term| flatten(term, false, out)),
+        Term::Implies(lhs, rhs) if !positive => {
+            flatten(lhs, true, out);
+            flatten(rhs, false, out);
+        }
+        term if positive => out.push(term.clone()),
+        term => out.push(!term.clone()),
+    }
+}
+
+/// The variable a conjunct `x == t` defines, and `t`: a bit-vector `x`
+/// that `t` does not mention.
+fn definition(conjunct: &Term) -> Option<(String, Term)> {
+    let Term::Cmp(Cmp::Eq, lhs, rhs) = conjunct else {
+        return None;
+    };
+    [(lhs, rhs), (rhs, lhs)]
+        .into_iter()
+        .find_map(|(var, value)| match &**var {
+            Term::Var(name, Sort::Bits { .. }) if !value.vars().contains_key(name) => {
+                Some((name.clone(), (**value).clone()))
+            }
+            _ => None,
+        })
+}
+
+/// `term` with constants folded and what cancels dropped.
+fn simplify(term: &Term) -> Term {
+    let term = term.map(&mut simplify);
+    let zero = |term: &Term| match term.sort() {
+        Sort::Bits { width, signed } => Some(Term::Bits(Bits::zero(width, signed))),
+        _ => None,
+    };
+    let is_zero = |term: &Term| matches!(term, Term::Bits(value) if value.is_zero());
+    let unsigned = |term: &Term| matches!(term.sort(), Sort::Bits { signed: false, .. });
+    match &term {
+        Term::Sub(lhs, rhs) | Term::Bitwise(BitOp::Xor, lhs, rhs) if lhs == rhs => zero(lhs).unwrap_or(term),
+        Term::Bitwise(BitOp::And | BitOp::Or, lhs, rhs) if lhs == rhs => (**lhs).clone(),
+        Term::Mul(lhs, rhs) | Term::Bitwise(BitOp::And, lhs, rhs) if is_zero(lhs) || is_zero(rhs) => {
+            zero(lhs).unwrap_or(term)
+        }
+        Term::Cmp(op, lhs, rhs) if lhs == rhs => Term::Bool(matches!(op, Cmp::Eq | Cmp::Le | Cmp::Ge)),
+        // No unsigned value is below zero.
+        Term::Cmp(op @ (Cmp::Ge | Cmp::Lt), lhs, rhs) if unsigned(lhs) && is_zero(rhs) => Term::Bool(*op == Cmp::Ge),
+        Term::Cmp(op @ (Cmp::Le | Cmp::Gt), lhs, rhs) if unsigned(rhs) && is_zero(lhs) => Term::Bool(*op == Cmp::Le),
+        _ => term,
+    }
+}
+
+/// A bit of a circuit: a constant, or a literal of the SAT solver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Bit {
+    Const(bool),
+    Lit(Lit),
+}
+
+impl ops::Not for Bit {
+    type Output = Bit;
+
+    fn not(self) -> Bit {
+        match self {
+            Bit::Const(value) => Bit::Const(!value),
+            Bit::Lit(lit) => Bit::Lit(!lit),
+        }
+    }
+}
+
+struct Blaster {
+    sat: sat::Solver,
+    /// The output of the gate for each pair of inputs, smaller first.
+    ands: HashMap<(Lit, Lit), Lit>,
+    xors: HashMap<(Lit, Lit), Lit>,
+    /// The bits of each variable, least significant first; one for a
+    /// boolean.
+    vars: HashMap<String, (Sort, Vec<Bit>)>,
+    /// Each arithmetic comparison, `==`, `<=` or `<`, and its literal.
+    atoms: Vec<(Term, Lit)>,
+    words: HashMap<Term, Vec<Bit>>,
+    formulas: HashMap<Term, Bit>,
+}
+
+impl Blaster {
+    fn new() -> Blaster {
+        Blaster {
+            sat: sat::Solver::new(),
+            ands: HashMap::new(),
+            xors: HashMap::new(),
+            vars: HashMap::new(),
+            atoms: Vec::new(),
+            words: HashMap::new(),
+            formulas: HashMap::new(),
+        }
+    }
+
+    fn lit(&mut self) -> Lit {
+        Lit::new(self.sat.var(), true)
+    }
+
+    fn assert(&mut self, bit: Bit) {
+        match bit {
+            Bit::Const(true) => {}
+            Bit::Const(false) => {
+                self.sat.add_clause(&[]);
+            }
+            Bit::Lit(lit) => {
+                self.sat.add_clause(&[lit]);
+            }
+        }
+    }
+
+    /// The values of the variables in a model of the SAT solver.
+    fn model(&self, values: &[bool]) -> Model {
+        let mut model = Model::new();
+        let bit = |bit: &Bit| match *bit {
+            Bit::Const(value) => value,
+            Bit::Lit(lit) => value(lit, values),
+        };
+        for (name, (sort, bits)) in &self.vars {
+            let value = match *sort {
+                Sort::Bits { width, signed } => {
+                    let pattern = bits
+                        .iter()
+                        .enumerate()
+                        .fold(0, |pattern, (index, b)| pattern | u128::from(bit(b)) << index);
+                    Value::Bits(Bits::from_pattern(width, signed, pattern))
+                }
+                _ => Value::Bool(bit(&bits[0])),
+            };
+            model.insert(name.clone(), value);
+        }
+        model
+    }
+
+    fn and(&mut self, a: Bit, b: Bit) -> Bit {
+        let (a, b) = match (a, b) {
+            (Bit::Const(false), _) | (_, Bit::Const(false)) => return Bit::Const(false),
+            (Bit::Const(true), other) | (other, Bit::Const(true)) => return other,
+            (Bit::Lit(a), Bit::Lit(b)) if a == b => return Bit::Lit(a),
+            (Bit::Lit(a), Bit::Lit(b)) if a == !b => return Bit::Const(false),
+            (Bit::Lit(a), Bit::Lit(b)) => (a.min(b), a.max(b)),
+        };
+        if let Some(&out) = self.ands.get(&(a, b)) {
+            return Bit::Lit(out);
+        }
+        let out = self.lit();
+        self.sat.add_clause(&[!out, a]);
+        self.sat.add_clause(&[!out, b]);
+        self.sat.add_clause(&[out, !a, !b]);
+        self.ands.insert((a, b), out);
+        Bit::Lit(out)
+    }
+
+    fn or(&mut self, a: Bit, b: Bit) -> Bit {
+        !self.and(!a, !b)
+    }
+
+    fn xor(&mut self, a: Bit, b: Bit) -> Bit {
+        let (a, b) = match (a, b) {
+            (Bit::Const(value), other) | (other, Bit::Const(value)) => return if value { !other } else { other },
+            (Bit::Lit(a), Bit::Lit(b)) if a == b => return Bit::Const(false),
+            (Bit::Lit(a), Bit::Lit(b)) if a == !b => return Bit::Const(true),
+            (Bit::Lit(a), Bit::Lit(b)) => (a, b),
+        };
+        // Negations come out of the gate, so it is shared by all four.
+        let flipped = a.is_positive() != b.is_positive();
+        let (a, b) = (Lit::new(a.var(), true), Lit::new(b.var(), true));
+        let (a, b) = (a.min(b), a.max(b));
+        let out = match self.xors.get(&(a, b)) {
+            Some(&out) => out,
+            None => {
+                let out = self.lit();
+                self.sat.add_clause(&[!out, a, b]);
+                self.sat.add_clause(&[!out, !a, !b]);
+                self.sat.add_clause(&[out, !a, b]);
+                self.sat.add_clause(&[out, a, !b]);
+                self.xors.insert((a, b), out);
+                out
+            }
+        };
+        if flipped { Bit::Lit(!out) } else { Bit::Lit(out) }
+    }
+
+    /// `then` if `cond`, else `otherwise`.
+    fn mux(&mut self, cond: Bit, then: Bit, otherwise: Bit) -> Bit {
+        match cond {
+            _ if then == otherwise => then,
+            Bit::Const(true) => then,
+            Bit::Const(false) => otherwise,
+            Bit::Lit(_) => {
+                let then = self.and(cond, then);
+                let otherwise = self.and(!cond, otherwise);
+                self.or(then, otherwise)
+            }
+        }
+    }
+
+    fn mux_word(&mut self, cond: Bit, then: &[Bit], otherwise: &[Bit]) -> Vec<Bit> {
+        then.iter()
+            .zip(otherwise)
+            .map(|(&then, &otherwise)| self.mux(cond, then, otherwise))
+            .collect()
+    }
+
+    /// `xs + ys + carry`, wrapping around.
+    fn add(&mut self, xs: &[Bit], ys: &[Bit], mut carry: Bit) -> Vec<Bit> {
+        let mut sum = Vec::with_capacity(xs.len());
+        for (&x, &y) in xs.iter().zip(ys) {
+            let half = self.xor(x, y);
+            sum.push(self.xor(half, carry));
+            let both = self.and(x, y);
+            let carried = self.and(half, carry);
+            carry = self.or(both, carried);
+        }
+        sum
+    }
+
+    fn sub(&mut self, xs: &[Bit], ys: &[Bit]) -> Vec<Bit> {
+        let flipped: Vec<Bit> = ys.iter().map(|&y| !y).collect();
+        self.add(xs, &flipped, Bit::Const(true))
+    }
+
+    fn neg(&mut self, xs: &[Bit]) -> Vec<Bit> {
+        let zeros = vec![Bit::Const(false); xs.len()];
+        self.sub(&zeros, xs)
+    }
+
+    /// The product by shifting and adding: a partial product for each bit
+    /// of `ys` that may be set.
+    fn mul(&mut self, xs: &[Bit], ys: &[Bit]) -> Vec<Bit> {
+        let width = xs.len();
+        let mut product = vec![Bit::Const(false); width];
+        for (shift, &y) in ys.iter().enumerate() {
+            if y == Bit::Const(false) {
+                continue;
+            }
+            let partial: Vec<Bit> = (0..width)
+                .map(|i| {
+                    if i < shift {
+                        Bit::Const(false)
+                    } else {
+                        self.and(xs[i - shift], y)
+                    }
+                })
+                .collect();
+            product = self.add(&product, &partial, Bit::Const(false));
+        }
+        product
+    }
+
+    /// The unsigned quotient and remainder, by long division; dividing by
+    /// zero gives all ones and `xs`.
+    fn divide(&mut self, xs: &[Bit], ys: &[Bit]) -> (Vec<Bit>, Vec<Bit>) {
+        let width = xs.len();
+        let divisor: Vec<Bit> = ys.iter().copied().chain([Bit::Const(false)]).collect();
+        let mut quotient = vec![Bit::Const(false); width];
+        let mut remainder = vec![Bit::Const(false); width];
+        for i in (0..width).rev() {
+            // The remainder, below the divisor, with the next bit brought
+            // down needs one bit more.
+            let shifted: Vec<Bit> = [xs[i]].into_iter().chain(remainder.iter().copied()).collect();
+            let fits = !self.less(&shifted, &divisor, false);
+            let difference = self.sub(&shifted, &divisor);
+            quotient[i] = fits;
+            remainder = self.mux_word(fits, &difference[..width], &shifted[..width]);
+        }
+        (quotient, remainder)
+    }
+
+    /// Whether `xs` is below `ys` unsigned, or equal to it too if
+    /// `or_equal`: the highest bit they differ in decides.
+    fn less(&mut self, xs: &[Bit], ys: &[Bit], or_equal: bool) -> Bit {
+        let mut result = Bit::Const(or_equal);
+        for (&x, &y) in xs.iter().zip(ys) {
+            let differ = self.xor(x, y);
+            result = self.mux(differ, y, result);
+        }
+        result
+    }
+
+    fn equal(&mut self, xs: &[Bit], ys: &[Bit]) -> Bit {
+        let mut result = Bit::Const(true);
+        for (&x, &y) in xs.iter().zip(ys) {
+            let same = !self.xor(x, y);
+            result = self.and(result, same);
+        }
+        result
+    }
+
+    /// `xs` shifted by `amount`, bringing in `fill` at the top when shifted
+    /// right: a stage for each bit of the amount.
+    fn shift(&mut self, xs: &[Bit], amount: &[Bit], left: bool, fill: Bit) -> Vec<Bit> {
+        let width = xs.len();
+        let mut result = xs.to_vec();
+        for (stage, &bit) in amount.iter().enumerate() {
+            let distance = 1usize.checked_shl(stage as u32).filter(|&distance| distance < width);
+            let shifted: Vec<Bit> = (0..width)
+                .map(|i| match distance {
+                    None if left => Bit::Const(false),
+                    None => fill,
+                    Some(distance) if left => i.checked_sub(distance).map_or(Bit::Const(false), |from| result[from]),
+                    Some(distance) => result.get(i + distance).copied().unwrap_or(fill),
+                })
+                .collect();
+            result = self.mux_word(bit, &shifted, &result);
+        }
+        result
+    }
+
+    /// The bits of the variable `name`, new ones the first time.
+    fn var(&mut self, name: &str, sort: Sort) -> Vec<Bit> {
+        if let Some((_, bits)) = self.vars.get(name) {
+            return bits.clone();
+        }
+        let width = match sort {
+            Sort::Bits { width, .. } => width,
+            _ => 1,
+        };
+        let bits: Vec<Bit> = (0..width).map(|_| Bit::Lit(self.lit())).collect();
+        self.vars.insert(name.to_owned(), (sort, bits.clone()));
+        bits
+    }
+
+    /// The bits of a bit-vector term, least significant first.
+    fn word(&mut self, term: &Term) -> Result<Vec<Bit>, String> {
+        if let Some(bits) = self.words.get(term) {
+            return Ok(bits.clone());
+        }
+        let Sort::Bits { width, signed } = term.sort() else {
+            return Err(format!("`{term}` is not a bit-vector"));
+        };
+        let bits = match term {
+            Term::Bits(value) => (0..width).map(|i| Bit::Const(value.bit(i))).collect(),
+            Term::Var(name, sort) => self.var(name, *sort),
+            Term::Neg(inner) => {
+                let xs = self.word(inner)?;
+                self.neg(&xs)
+            }
+            Term::Complement(inner) => self.word(inner)?.into_iter().map(|bit| !bit).collect(),
+            Term::Add(lhs, rhs) | Term::Sub(lhs, rhs) | Term::Mul(lhs, rhs) | Term::Div(lhs, rhs) => {
+                let (xs, ys) = (self.word(lhs)?, self.word(rhs)?);
+                match term {
+                    Term::Add(..) => self.add(&xs, &ys, Bit::Const(false)),
+                    Term::Sub(..) => self.sub(&xs, &ys),
+                    Term::Mul(..) => self.mul(&xs, &ys),
+                    _ if !signed => self.divide(&xs, &ys).0,
+                    // The quotient of the magnitudes, negated if the signs
+                    // differ.
+                    _ => {
+                        let (x_sign, y_sign) = (xs[xs.len() - 1], ys[ys.len() - 1]);
+                        let (x_neg, y_neg) = (self.neg(&xs), self.neg(&ys));
+                        let x_abs = self.mux_word(x_sign, &x_neg, &xs);
+                        let y_abs = self.mux_word(y_sign, &y_neg, &ys);
+                        let (quotient, _) = self.divide(&x_abs, &y_abs);
+                        let negated = self.neg(&quotient);
+                        let differ = self.xor(x_sign, y_sign);
+                        self.mux_word(differ, &negated, &quotient)
+                    }
+                }
+            }
+            Term::Bitwise(op, lhs, rhs) => {
+                let (xs, ys) = (self.word(lhs)?, self.word(rhs)?);
+                let pairs = xs.iter().copied().zip(ys.iter().copied());
+                match op {
+                    BitOp::And => pairs.map(|(x, y)| self.and(x, y)).collect(),
+                    BitOp::Or => pairs.map(|(x, y)| self.or(x, y)).collect(),
+                    BitOp::Xor => pairs.map(|(x, y)| self.xor(x, y)).collect(),
+                    BitOp::Shl => self.shift(&xs, &ys, true, Bit::Const(false)),
+                    BitOp::Shr => {
+                        let fill = if signed { xs[xs.len() - 1] } else { Bit::Const(false) };
+                        self.shift(&xs, &ys, false, fill)
+                    }
+                }
+            }
+            Term::Cast(inner, _) => {
+                let xs = self.word(inner)?;
+                let extension = match inner.sort() {
+                    Sort::Bits { signed: true, .. } => xs[xs.len() - 1],
+                    _ => Bit::Const(false),
+                };
+                (0..width as usize)
+                    .map(|i| xs.get(i).copied().unwrap_or(extension))
+                    .collect()
+            }
+            Term::Ite(cond, then, otherwise) => {
+                let cond = self.formula(cond)?;
+                let (then, otherwise) = (self.word(then)?, self.word(otherwise)?);
+                self.mux_word(cond, &then, &otherwise)
+            }
+            term => return Err(format!("`{term}` cannot be turned into bits")),
+        };
+        self.words.insert(term.clone(), bits.clone());
+        Ok(bits)
+    }
+
+    /// The bit that is set exactly when `term`, a formula, holds.
+    fn formula(&mut self, term: &Term) -> Result<Bit, String> {
+        if let Some(&bit) = self.formulas.get(term) {
+            return Ok(bit);
+        }
+        let bit = match term {
+            Term::Bool(value) => Bit::Const(*value),
+            Term::Var(name, Sort::Bool) => self.var(name, Sort::Bool)[0],
+            Term::Not(inner) => !self.formula(inner)?,
+            Term::And(terms) | Term::Or(terms) => {
+                let and = matches!(term, Term::And(_));
+                let mut result = Bit::Const(and);
+                for term in terms {
+                    let bit = self.formula(term)?;
+                    result = if and {
+                        self.and(result, bit)
+                    } else {
+                        self.or(result, bit)
+                    };
+                }
+                result
+            }
+            Term::Implies(lhs, rhs) => {
+                let (lhs, rhs) = (self.formula(lhs)?, self.formula(rhs)?);
+                self.or(!lhs, rhs)
+            }
+            Term::Ite(cond, then, otherwise) => {
+                let cond = self.formula(cond)?;
+                let (then, otherwise) = (self.formula(then)?, self.formula(otherwise)?);
+                self.mux(cond, then, otherwise)
+            }
+            Term::Cmp(op, lhs, rhs) => match lhs.sort() {
+                Sort::Bool if matches!(op, Cmp::Eq | Cmp::Ne) => {
+                    let (lhs, rhs) = (self.formula(lhs)?, self.formula(rhs)?);
+                    let differ = self.xor(lhs, rhs);
+                    if *op == Cmp::Eq { !differ } else { differ }
+                }
+                Sort::Bool => return Err(format!("`{term}` orders booleans")),
+                Sort::Bits { signed, .. } => {
+                    let (mut xs, mut ys) = (self.word(lhs)?, self.word(rhs)?);
+                    // Flipping the sign bits orders two's complement as
+                    // unsigned.
+                    if signed {
+                        let top = xs.len() - 1;
+                        xs[top] = !xs[top];
+                        ys[top] = !ys[top];
+                    }
+                    match op {
+                        Cmp::Eq => self.equal(&xs, &ys),
+                        Cmp::Ne => !self.equal(&xs, &ys),
+                        Cmp::Lt => self.less(&xs, &ys, false),
+                        Cmp::Le => self.less(&xs, &ys, true),
+                        Cmp::Gt => self.less(&ys, &xs, false),
+                        Cmp::Ge => self.less(&ys, &xs, true),
+                    }
+                }
+                Sort::Int | Sort::Real if involves_bits(term) => {
+                    return Err("numbers computed from bit-vectors are not supported".to_owned());
+                }
+                Sort::Int | Sort::Real => self.atom(*op, lhs, rhs),
+            },
+            term => return Err(format!("`{term}` is not a formula")),
+        };
+        self.formulas.insert(term.clone(), bit);
+        Ok(bit)
+    }
+
+    /// The literal of an arithmetic comparison. Only `==`, `<=` and `<`
+    /// have literals of their own; the others are their negations.
+    fn atom(&mut self, op: Cmp, lhs: &Term, rhs: &Term) -> Bit {
+        let (op, negated) = match op {
+            Cmp::Ne => (Cmp::Eq, true),
+            Cmp::Gt => (Cmp::Le, true),
+            Cmp::Ge => (Cmp::Lt, true),
+            op => (op, false),
+        };
+        let atom = Term::Cmp(op, Box::new(lhs.clone()), Box::new(rhs.clone()));
+        let lit = match self.atoms.iter().find(|(other, _)| *other == atom) {
+            Some(&(_, lit)) => lit,
+            None => {
+                let lit = self.lit();
+                self.atoms.push((atom, lit));
+                lit
+            }
+        };
+        if negated { Bit::Lit(!lit) } else { Bit::Lit(lit) }
+    }
+}
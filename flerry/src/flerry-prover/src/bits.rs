@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Fixed-width machine integers, the values of bit-vector terms.
+//!
+//! A value is a pattern of up to 128 bits, read as an unsigned number or in
+//! two's complement. Arithmetic wraps around as the machine's does. Division
+//! follows SMT-LIB, so that it is defined everywhere: dividing by zero gives
+//! all ones, and a signed quotient is the quotient of the magnitudes with
+//! its sign put back.
+
+use crate::term::Sort;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops;
+
+/// The widest bit-vector there is.
+pub const MAX_WIDTH: u32 = 128;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Bits {
+    width: u32,
+    signed: bool,
+    /// The bits above the width are zero.
+    pattern: u128,
+}
+
+impl Bits {
+    /// `value` wrapped around to `width` bits.
+    pub fn new(width: u32, signed: bool, value: i128) -> Bits {
+        Bits::from_pattern(width, signed, value as u128)
+    }
+
+    /// The value with the low `width` bits of `pattern`.
+    pub fn from_pattern(width: u32, signed: bool, pattern: u128) -> Bits {
+        assert!(
+            (1..=MAX_WIDTH).contains(&width),
+            "a bit-vector is 1 to {MAX_WIDTH} bits wide"
+        );
+        Bits {
+            width,
+            signed,
+            pattern: pattern & mask(width),
+        }
+    }
+
+    pub fn zero(width: u32, signed: bool) -> Bits {
+        Bits::from_pattern(width, signed, 0)
+    }
+
+    /// The least value of the width: zero, or the most negative one.
+    pub fn min(width: u32, signed: bool) -> Bits {
+        let pattern = if signed { 1 << (width - 1) } else { 0 };
+        Bits::from_pattern(width, signed, pattern)
+    }
+
+    pub fn max(width: u32, signed: bool) -> Bits {
+        Bits::from_pattern(width, signed, !Bits::min(width, signed).pattern)
+    }
+
+    pub fn width(self) -> u32 {
+        self.width
+    }
+
+    pub fn is_signed(self) -> bool {
+        self.signed
+    }
+
+    pub fn sort(self) -> Sort {
+        Sort::Bits {
+            width: self.width,
+            signed: self.signed,
+        }
+    }
+
+    pub fn pattern(self) -> u128 {
+        self.pattern
+    }
+
+    /// Bit `index`, counting from the least significant.
+    pub fn bit(self, index: u32) -> bool {
+        index < self.width && self.pattern >> index & 1 == 1
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.pattern == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.signed && self.bit(self.width - 1)
+    }
+
+    /// The pattern read in two's complement, whatever the signedness.
+    fn twos_complement(self) -> i128 {
+        if self.bit(self.width - 1) {
+            (self.pattern | !mask(self.width)) as i128
+        } else {
+            self.pattern as i128
+        }
+    }
+
+    /// The value as an integer, if it fits in an `i128`.
+    pub fn value(self) -> Option<i128> {
+        if self.signed {
+            Some(self.twos_complement())
+        } else {
+            i128::try_from(self.pattern).ok()
+        }
+    }
+
+    /// The value with the same number, wrapped around to `width` bits of
+    /// the other signedness if it does not fit.
+    pub fn cast(self, width: u32, signed: bool) -> Bits {
+        let extended = if self.signed {
+            self.twos_complement() as u128
+        } else {
+            self.pattern
+        };
+        Bits::from_pattern(width, signed, extended)
+    }
+
+    fn with(self, pattern: u128) -> Bits {
+        Bits::from_pattern(self.width, self.signed, pattern)
+    }
+
+    /// The pattern as a literal: in binary up to 16 bits, in hexadecimal
+    /// beyond, with every digit written.
+    pub fn pattern_literal(self) -> String {
+        if self.width <= 16 {
+            format!("0b{:0width$b}", self.pattern, width = self.width as usize)
+        } else {
+            format!("0x{:0width$x}", self.pattern, width = self.width.div_ceil(4) as usize)
+        }
+    }
+}
+
+fn mask(width: u32) -> u128 {
+    if width >= MAX_WIDTH {
+        u128::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+impl ops::Add for Bits {
+    type Output = Bits;
+
+    fn add(self, rhs: Bits) -> Bits {
+        self.with(self.pattern.wrapping_add(rhs.pattern))
+    }
+}
+
+impl ops::Sub for Bits {
+    type Output = Bits;
+
+    fn sub(self, rhs: Bits) -> Bits {
+        self.with(self.pattern.wrapping_sub(rhs.pattern))
+    }
+}
+
+impl ops::Mul for Bits {
+    type Output = Bits;
+
+    fn mul(self, rhs: Bits) -> Bits {
+        self.with(self.pattern.wrapping_mul(rhs.pattern))
+    }
+}
+
+impl ops::Div for Bits {
+    type Output = Bits;
+
+    fn div(self, rhs: Bits) -> Bits {
+        let unsigned = |lhs: u128, rhs: u128| lhs.checked_div(rhs).unwrap_or(u128::MAX);
+        if !self.signed {
+            return self.with(unsigned(self.pattern, rhs.pattern));
+        }
+        let magnitude = |bits: Bits| if bits.is_negative() { -bits } else { bits };
+        let quotient = self.with(unsigned(magnitude(self).pattern, magnitude(rhs).pattern));
+        if self.is_negative() != rhs.is_negative() {
+            -quotient
+        } else {
+            quotient
+        }
+    }
+}
+
+impl ops::Neg for Bits {
+    type Output = Bits;
+
+    fn neg(self) -> Bits {
+        self.with(self.pattern.wrapping_neg())
+    }
+}
+
+impl ops::Not for Bits {
+    type Output = Bits;
+
+    fn not(self) -> Bits {
+        self.with(!self.pattern)
+    }
+}
+
+impl ops::BitAnd for Bits {
+    type Output = Bits;
+
+    fn bitand(self, rhs: Bits) -> Bits {
+        self.with(self.pattern & rhs.pattern)
+    }
+}
+
+impl ops::BitOr for Bits {
+    type Output = Bits;
+
+    fn bitor(self, rhs: Bits) -> Bits {
+        self.with(self.pattern | rhs.pattern)
+    }
+}
+
+impl ops::BitXor for Bits {
+    type Output = Bits;
+
+    fn bitxor(self, rhs: Bits) -> Bits {
+        self.with(self.pattern ^ rhs.pattern)
+    }
+}
+
+impl ops::Shl for Bits {
+    type Output = Bits;
+
+    /// Shifted by `amount`, read unsigned; by the width or more it is zero.
+    fn shl(self, amount: Bits) -> Bits {
+        match u32::try_from(amount.pattern) {
+            Ok(amount) if amount < self.width => self.with(self.pattern << amount),
+            _ => self.with(0),
+        }
+    }
+}
+
+impl ops::Shr for Bits {
+    type Output = Bits;
+
+    /// Shifted by `amount`, read unsigned, bringing in copies of the sign
+    /// bit if the value is signed and zeros if not.
+    fn shr(self, amount: Bits) -> Bits {
+        let fill = if self.is_negative() { u128::MAX } else { 0 };
+        match u32::try_from(amount.pattern) {
+            Ok(amount) if amount < self.width && self.signed => self.with((self.twos_complement() >> amount) as u128),
+            Ok(amount) if amount < self.width => self.with(self.pattern >> amount),
+            _ => self.with(fill),
+        }
+    }
+}
+
+impl Ord for Bits {
+    /// By value, then by width and signedness so that only equal values
+    /// are equal.
+    fn cmp(&self, other: &Bits) -> Ordering {
+        let by_value = if self.signed && other.signed {
+            self.twos_complement().cmp(&other.twos_complement())
+        } else {
+            // An unsigned value is never negative.
+            match (self.is_negative(), other.is_negative()) {
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                _ => self.pattern.cmp(&other.pattern),
+            }
+        };
+        by_value
+            .then(self.width.cmp(&other.width))
+            .then(self.signed.cmp(&other.signed))
+    }
+}
+
+impl PartialOrd for Bits {
+    fn partial_cmp(&self, other: &Bits) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.signed {
+            write!(f, "{}", self.twos_complement())
+        } else {
+            write!(f, "{}", self.pattern)
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! The DIMACS CNF format SAT solvers read and write: a header
+//! `p cnf <variables> <clauses>`, then clauses as numbered literals, each
+//! ending in `0`. Lines starting with `c` are comments, and a line `%`
+//! ends the clauses as in the SATLIB benchmarks.
+
+use crate::sat::Lit;
+use std::fmt;
+
+/// A formula in conjunctive normal form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cnf {
+    pub vars: usize,
+    pub clauses: Vec<Vec<Lit>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DimacsError {
+    /// Counting from one.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DimacsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Cnf, DimacsError> {
+    let mut header: Option<(usize, usize)> = None;
+    let mut cnf = Cnf::default();
+    let mut clause = Vec::new();
+    let mut last = 0;
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| DimacsError {
+            line: index + 1,
+            message,
+        };
+        last = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+        if line.starts_with('%') {
+            break;
+        }
+        if let Some(rest) = line.strip_prefix('p') {
+            if header.is_some() {
+                return Err(error("a second header".to_owned()));
+            }
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            let counts = match fields[..] {
+                ["cnf", vars, clauses] => vars.parse().ok().zip(clauses.parse().ok()),
+                _ => None,
+            };
+            let Some((vars, clauses)) = counts else {
+                return Err(error(format!("expected `p cnf <variables> <clauses>`, found `{line}`")));
+            };
+            header = Some((vars, clauses));
+            cnf.vars = vars;
+            continue;
+        }
+        let Some((vars, _)) = header else {
+            return Err(error("a clause before the `p cnf` header".to_owned()));
+        };
+        for word in line.split_whitespace() {
+            let Ok(n) = word.parse::<i64>() else {
+                return Err(error(format!("expected a literal, found `{word}`")));
+            };
+            if n == 0 {
+                cnf.clauses.push(std::mem::take(&mut clause));
+                continue;
+            }
+            match Lit::from_dimacs(n) {
+                Some(lit) if lit.var() < vars => clause.push(lit),
+                _ => return Err(error(format!("the literal `{n}` is not one of the {vars} variables"))),
+            }
+        }
+    }
+    let Some((_, clauses)) = header else {
+        return Err(DimacsError {
+            line: last.max(1),
+            message: "no `p cnf` header".to_owned(),
+        });
+    };
+    // The last clause may leave out its `0`.
+    if !clause.is_empty() {
+        cnf.clauses.push(clause);
+    }
+    if cnf.clauses.len() != clauses {
+        return Err(DimacsError {
+            line: last.max(1),
+            message: format!(
+                "the header gives {clauses} clauses, but there are {}",
+                cnf.clauses.len()
+            ),
+        });
+    }
+    Ok(cnf)
+}
+
+/// A model as the `v` lines of a solver's answer, ten literals to a line,
+/// ending in `0`.
+pub fn model(values: &[bool]) -> String {
+    let lits: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(var, &value)| Lit::new(var, value).to_string())
+        .chain(["0".to_owned()])
+        .collect();
+    lits.chunks(10)
+        .map(|chunk| format!("v {}\n", chunk.join(" ")))
+        .collect()
+}
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Congruence closure: the equalities between terms that follow from given
+//! ones when functions return equal results for equal arguments.
+//!
+//! Terms are nodes in classes of equal terms, kept in a union-find. Each
+//! class knows the applications it is an argument of, and a table finds the
+//! application with the same function and argument classes, its signature;
+//! merging two classes recomputes the signatures of the applications that
+//! use the smaller one, and a collision is a new equality to merge.
+//!
+//! Every merge is also an edge of a proof forest, labelled with the given
+//! equality or the congruence behind it, so [`Congruence::explain`] can say
+//! which given equalities make two terms equal.
+
+use crate::term::Term;
+use std::collections::HashMap;
+
+/// Why two nodes were merged.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Reason {
+    /// The given equality with this label.
+    Given(usize),
+    /// The two applications have equal arguments.
+    Congruent(usize, usize),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Congruence {
+    terms: Vec<Term>,
+    ids: HashMap<Term, usize>,
+    /// The argument nodes of each application node.
+    args: Vec<Vec<usize>>,
+    parent: Vec<usize>,
+    /// The nodes of each class, kept at its representative.
+    members: Vec<Vec<usize>>,
+    /// The applications with an argument in each class, kept at its
+    /// representative.
+    uses: Vec<Vec<usize>>,
+    signatures: HashMap<(String, Vec<usize>), usize>,
+    /// The edge from each node towards the root of its proof tree.
+    proof: Vec<Option<(usize, Reason)>>,
+}
+
+impl Congruence {
+    pub fn new() -> Congruence {
+        Congruence::default()
+    }
+
+    /// The node for `term`, and for the arguments of the applications in
+    /// it.
+    pub fn add(&mut self, term: &Term) -> usize {
+        if let Some(&id) = self.ids.get(term) {
+            return id;
+        }
+        let args: Vec<usize> = match term {
+            Term::App(_, args, _) => args.iter().map(|arg| self.add(arg)).collect(),
+            _ => Vec::new(),
+        };
+        let id = self.terms.len();
+        self.terms.push(term.clone());
+        self.ids.insert(term.clone(), id);
+        self.parent.push(id);
+        self.members.push(vec![id]);
+        self.uses.push(Vec::new());
+        self.proof.push(None);
+        self.args.push(args.clone());
+        if let Term::App(name, ..) = term {
+            for &arg in &args {
+                let root = self.find(arg);
+                self.uses[root].push(id);
+            }
+            let signature = (name.clone(), args.iter().map(|&arg| self.find(arg)).collect());
+            match self.signatures.get(&signature) {
+                Some(&other) => self.union(id, other, Reason::Congruent(id, other)),
+                None => {
+                    self.signatures.insert(signature, id);
+                }
+            }
+        }
+        id
+    }
+
+    pub fn term(&self, id: usize) -> &Term {
+        &self.terms[id]
+    }
+
+    /// The number of nodes.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The representative of the class of `id`.
+    pub fn find(&self, mut id: usize) -> usize {
+        while self.parent[id] != id {
+            id = self.parent[id];
+        }
+        id
+    }
+
+    pub fn equal(&self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Merges the classes of `a` and `b`, as the given equality `label`,
+    /// and every pair of applications that then have equal arguments.
+    pub fn merge(&mut self, a: usize, b: usize, label: usize) {
+        self.union(a, b, Reason::Given(label));
+    }
+
+    /// The labels of the given equalities that make `a` and `b` equal, or
+    /// `None` if they are not.
+    pub fn explain(&self, a: usize, b: usize) -> Option<Vec<usize>> {
+        if !self.equal(a, b) {
+            return None;
+        }
+        let mut labels = Vec::new();
+        self.collect(a, b, &mut labels);
+        labels.sort_unstable();
+        labels.dedup();
+        Some(labels)
+    }
+
+    fn union(&mut self, a: usize, b: usize, reason: Reason) {
+        let mut pending = vec![(a, b, reason)];
+        while let Some((a, b, reason)) = pending.pop() {
+            let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+            if root_a == root_b {
+                continue;
+            }
+            // The edge goes from `a`, made the root of its tree, to `b`.
+            self.reroot(a);
+            self.proof[a] = Some((b, reason));
+            // The smaller class joins the larger.
+            if self.members[root_a].len() > self.members[root_b].len() {
+                (root_a, root_b) = (root_b, root_a);
+            }
+            self.parent[root_a] = root_b;
+            let members = std::mem::take(&mut self.members[root_a]);
+            self.members[root_b].extend(members);
+            let uses = std::mem::take(&mut self.uses[root_a]);
+            for &app in &uses {
+                let Term::App(name, ..) = &self.terms[app] else {
+                    unreachable!("only applications have uses")
+                };
+                let signature = (name.clone(), self.args[app].iter().map(|&arg| self.find(arg)).collect());
+                match self.signatures.get(&signature) {
+                    Some(&other) if !self.equal(app, other) => {
+                        pending.push((app, other, Reason::Congruent(app, other)));
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.signatures.insert(signature, app);
+                    }
+                }
+            }
+            self.uses[root_b].extend(uses);
+        }
+    }
+
+    /// Reverses the edges from `id` to the root of its proof tree, making
+    /// it the root.
+    fn reroot(&mut self, id: usize) {
+        let mut previous: Option<(usize, Reason)> = None;
+        let mut node = id;
+        loop {
+            let next = self.proof[node];
+            self.proof[node] = previous;
+            match next {
+                Some((parent, reason)) => {
+                    previous = Some((node, reason));
+                    node = parent;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// The nodes from `id` to the root of its proof tree.
+    fn path(&self, mut id: usize) -> Vec<usize> {
+        let mut path = vec![id];
+        while let Some((parent, _)) = self.proof[id] {
+            path.push(parent);
+            id = parent;
+        }
+        path
+    }
+
+    fn collect(&self, a: usize, b: usize, labels: &mut Vec<usize>) {
+        if a == b {
+            return;
+        }
+        let (from_a, from_b) = (self.path(a), self.path(b));
+        let common = *from_a
+            .iter()
+            .find(|node| from_b.contains(node))
+            .expect("equal nodes share a proof tree");
+        for path in [from_a, from_b] {
+            for &node in path.iter().take_while(|&&node| node != common) {
+                let (_, reason) = self.proof[node].expect("a node below the common one has an edge");
+                match reason {
+                    Reason::Given(label) => labels.push(label),
+                    Reason::Congruent(x, y) => {
+                        for (&arg_x, &arg_y) in self.args[x].iter().zip(&self.args[y]) {
+                            self.collect(arg_x, arg_y, labels);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,712 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! The solver the compiler discharges verification conditions with.
+//!
+//! Formulas are quantifier-free, over integers, rationals, fixed-width
+//! bit-vectors and booleans with linear arithmetic and uninterpreted
+//! functions. A formula is proved by
+//! showing its negation has no model; when it has one, the model is the
+//! counterexample shown to the user.
+//!
+//! Nothing outside the crate is called: a CDCL SAT solver decides the
+//! boolean structure of a formula, asking a simplex with branch and bound
+//! whether the comparisons each of its models makes true can hold, and
+//! functions are handled by congruence closure. Bit-vectors are
+//! bit-blasted to the same SAT solver, and `flerry sat` runs it on its own.
+//! A formula can also be written in SMT-LIB, for a solver installed on the
+//! machine to cross-check.
+
+pub mod arith;
+pub mod bitblast;
+pub mod bits;
+pub mod dimacs;
+pub mod euf;
+pub mod linear;
+pub mod rational;
+pub mod sat;
+pub mod simplex;
+pub mod smtlib;
+pub mod solver;
+pub mod term;
+
+pub use bits::Bits;
+pub use rational::Rational;
+pub use solver::{Limits, Proof, Satisfiability, Verdict, check, prove};
+pub use term::{BitOp, Cmp, Model, Sort, Term, Value};
+
+#[cfg(test)]
+mod tests {
+    use crate::linear::{Constraint, Linear};
+    use crate::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn x() -> Term {
+        Term::int_var("x")
+    }
+
+    fn y() -> Term {
+        Term::int_var("y")
+    }
+
+    fn lt(lhs: Term, rhs: Term) -> Term {
+        Term::cmp(Cmp::Lt, lhs, rhs)
+    }
+
+    fn gt(lhs: Term, rhs: Term) -> Term {
+        Term::cmp(Cmp::Gt, lhs, rhs)
+    }
+
+    fn ge(lhs: Term, rhs: Term) -> Term {
+        Term::cmp(Cmp::Ge, lhs, rhs)
+    }
+
+    #[test]
+    fn test_display() {
+        let term = Term::implies(
+            Term::and([gt(x(), Term::int(0)), Term::bool_var("b")]),
+            ge(x() - (y() - Term::int(1)), Term::int(2) * -y()),
+        );
+        assert_eq!(term.to_string(), "x > 0 && b => x - (y - 1) >= 2 * -y");
+        let term = !Term::or([Term::bool_var("a"), Term::bool_var("b")]);
+        assert_eq!(term.to_string(), "!(a || b)");
+        assert_eq!(Term::int(2) + Term::int(3), Term::int(5));
+        assert_eq!(Term::and([Term::Bool(true), x().clone()]), x());
+    }
+
+    #[test]
+    fn test_normalize() {
+        // `2x - 3 <= 0` is `x <= 1` over the integers.
+        let mut expr = Linear::var("x").scale(2).unwrap();
+        expr.constant = -3;
+        let normal = Constraint::le(expr.clone()).normalize().unwrap();
+        assert_eq!(normal.to_string(), "x - 1 <= 0");
+        assert_eq!(Constraint::eq(expr).normalize(), None);
+    }
+
+    #[test]
+    fn test_prove() {
+        let limits = Limits::default();
+        // x > 0 && y > x => y > 1
+        let valid = Term::implies(Term::and([gt(x(), Term::int(0)), gt(y(), x())]), gt(y(), Term::int(1)));
+        assert_eq!(prove(&valid, &limits), Verdict::Proved);
+
+        // x >= 0 => x - 1 >= 0 fails at x = 0.
+        let invalid = Term::implies(ge(x(), Term::int(0)), ge(x() - Term::int(1), Term::int(0)));
+        let Verdict::Refuted(model) = prove(&invalid, &limits) else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(model.to_string(), "x = 0");
+
+        // Between 2x and 2x + 2 exclusive there is only an odd number.
+        let odd = Term::implies(
+            Term::and([lt(Term::int(2) * x(), y()), lt(y(), Term::int(2) * x() + Term::int(2))]),
+            Term::eq(y(), Term::int(2) * x() + Term::int(1)),
+        );
+        assert_eq!(prove(&odd, &limits), Verdict::Proved);
+
+        // Cases: if b then x else -x is never negative when x >= 0.
+        let cases = Term::implies(
+            ge(x(), Term::int(0)),
+            ge(Term::ite(Term::bool_var("b"), x(), -x()), Term::int(0)),
+        );
+        let Verdict::Refuted(model) = prove(&cases, &limits) else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(model.get("b"), Some(Value::Bool(false)));
+        assert!(matches!(model.get("x"), Some(Value::Int(x)) if x > 0));
+
+        // x != y && x <= y => x < y
+        let split = Term::implies(
+            Term::and([Term::cmp(Cmp::Ne, x(), y()), Term::cmp(Cmp::Le, x(), y())]),
+            lt(x(), y()),
+        );
+        assert_eq!(prove(&split, &limits), Verdict::Proved);
+    }
+
+    #[test]
+    fn test_nonlinear() {
+        let limits = Limits::default();
+        // Products stand for fresh variables: `x * y == y * x` is beyond
+        // the solver, but a model it finds is checked before it is shown.
+        let commutes = Term::eq(x() * y(), y() * x());
+        assert!(matches!(prove(&commutes, &limits), Verdict::Unknown(_)));
+        let same = Term::implies(gt(x() * y(), Term::int(0)), gt(x() * y(), Term::int(-1)));
+        assert_eq!(prove(&same, &limits), Verdict::Proved);
+    }
+
+    #[test]
+    fn test_eval() {
+        let mut model = Model::new();
+        model.insert("x", Value::Int(7));
+        model.insert("y", Value::Int(2));
+        let term = Term::eq(x() / y(), Term::int(3));
+        assert_eq!(term.eval(&model), Some(Value::Bool(true)));
+        model.insert("y", Value::Int(0));
+        assert_eq!(term.eval(&model), None);
+        let values = BTreeMap::from([("x".to_owned(), 7)]);
+        assert_eq!(Linear::var("x").scale(3).unwrap().eval(&values), Some(21));
+    }
+
+    fn f(arg: Term) -> Term {
+        Term::App("f".to_owned(), vec![arg], Sort::Int)
+    }
+
+    #[test]
+    fn test_rational() {
+        assert_eq!(Rational::new(2, -4).to_string(), "-1/2");
+        assert_eq!(Rational::new(1, 3) + Rational::new(1, 6), Rational::new(1, 2));
+        assert!(Rational::new(1, 3) < Rational::new(1, 2));
+        assert!(Rational::new(-7, 2) < Rational::int(-3));
+        assert_eq!(Rational::new(-7, 2).floor(), Rational::int(-4));
+        // Overflow is passed on rather than wrapping.
+        let overflow = Rational::int(i128::MAX) + Rational::ONE;
+        assert!(overflow.is_overflow());
+        assert!((overflow * Rational::ZERO).is_overflow());
+    }
+
+    #[test]
+    fn test_reals() {
+        let limits = Limits::default();
+        let (a, b) = (Term::real_var("a"), Term::real_var("b"));
+        let half = |term: Term| term / Term::real(Rational::int(2));
+        // Strictly between two rationals there is another.
+        let between = Term::implies(lt(a.clone(), b.clone()), lt(a.clone(), half(a.clone() + b.clone())));
+        assert_eq!(prove(&between, &limits), Verdict::Proved);
+
+        // 0 < a < 1 has a rational solution but no integer one.
+        let unit = |var: Term, zero: Term, one: Term| Term::and([gt(var.clone(), zero), lt(var, one)]);
+        let real = unit(a.clone(), Term::real(Rational::ZERO), Term::real(Rational::ONE));
+        let Satisfiability::Sat(model) = check(&real, &limits) else {
+            panic!("expected a model");
+        };
+        let Some(Value::Real(value)) = model.get("a") else {
+            panic!("expected a rational value");
+        };
+        assert!(value.is_positive() && value < Rational::ONE);
+        let integer = unit(x(), Term::int(0), Term::int(1));
+        assert!(matches!(check(&integer, &limits), Satisfiability::Unsat(_)));
+    }
+
+    #[test]
+    fn test_certificates() {
+        let limits = Limits::default();
+        // 2x == 2y + 1 has no integer solution; 1 <= 2x - 2y <= 1 needs a
+        // cut, and 2x <= 1 && 2x >= 1 && y == x a branch.
+        let odd = Term::eq(Term::int(2) * x(), Term::int(2) * y() + Term::int(1));
+        let Satisfiability::Unsat(proof) = check(&odd, &limits) else {
+            panic!("expected no model");
+        };
+        assert!(proof.check());
+        let branch = Term::and([
+            Term::cmp(Cmp::Le, Term::int(2) * x(), Term::int(1)),
+            ge(Term::int(2) * x() + y(), Term::int(1)),
+            Term::eq(y(), Term::int(0)),
+        ]);
+        let Satisfiability::Unsat(proof) = check(&branch, &limits) else {
+            panic!("expected no model");
+        };
+        assert!(proof.check());
+
+        // A certificate is checked against the constraints, not trusted.
+        let x_le = |constant| {
+            let mut expr = Linear::var("x");
+            expr.constant = constant;
+            Constraint::le(expr)
+        };
+        let mut ge_one = x_le(-1).expr.scale(-1).unwrap();
+        ge_one.constant = 1;
+        let constraints = [x_le(0), Constraint::le(ge_one)];
+        let integers = BTreeSet::from(["x".to_owned()]);
+        let sum = BTreeMap::from([(0, Rational::ONE), (1, Rational::ONE)]);
+        assert!(arith::Certificate::Farkas(sum.clone()).check(&constraints, &integers));
+        let wrong = BTreeMap::from([(0, Rational::ONE), (1, Rational::int(2))]);
+        assert!(!arith::Certificate::Farkas(wrong).check(&constraints, &integers));
+        let negative = BTreeMap::from([(0, -Rational::ONE), (1, -Rational::ONE)]);
+        assert!(!arith::Certificate::Farkas(negative).check(&constraints, &integers));
+        assert!(!arith::Certificate::Indivisible(0).check(&constraints, &integers));
+    }
+
+    #[test]
+    fn test_congruence() {
+        let limits = Limits::default();
+        let equal = Term::implies(Term::eq(x(), y()), Term::eq(f(x()), f(y())));
+        assert_eq!(prove(&equal, &limits), Verdict::Proved);
+
+        // f(f(f(x))) == x && f(f(f(f(f(x))))) == x => f(x) == x
+        let iterate = |n| (0..n).fold(x(), |term, _| f(term));
+        let cycle = Term::implies(
+            Term::and([Term::eq(iterate(3), x()), Term::eq(iterate(5), x())]),
+            Term::eq(f(x()), x()),
+        );
+        assert_eq!(prove(&cycle, &limits), Verdict::Proved);
+
+        // Different arguments can give different results, and the model
+        // shows the applications as written.
+        let Verdict::Refuted(model) = prove(&Term::eq(f(x()), f(y())), &limits) else {
+            panic!("expected a counterexample");
+        };
+        assert_ne!(model.get("x"), model.get("y"));
+        assert_ne!(model.get("f(x)"), model.get("f(y)"));
+
+        let mut closure = euf::Congruence::new();
+        let (a, b) = (closure.add(&f(f(x()))), closure.add(&x()));
+        let fx = closure.add(&f(x()));
+        closure.merge(fx, b, 0);
+        assert!(closure.equal(a, b));
+        assert_eq!(closure.explain(a, b), Some(vec![0]));
+    }
+
+    /// The corpus, each file with the answer it should get in a comment:
+    /// `c expect: sat`.
+    fn corpus() -> Vec<(String, String)> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/cnf");
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        files
+            .into_iter()
+            .map(|path| (path.display().to_string(), std::fs::read_to_string(&path).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_sat_corpus() {
+        let files = corpus();
+        assert!(files.len() >= 10);
+        for (path, text) in files {
+            let expect = text
+                .lines()
+                .find_map(|line| line.strip_prefix("c expect: "))
+                .unwrap_or_else(|| panic!("{path} has no expected answer"));
+            let cnf = dimacs::parse(&text).unwrap_or_else(|e| panic!("{path}: {e}"));
+            let mut solver = sat::Solver::new();
+            for clause in &cnf.clauses {
+                solver.add_clause(clause);
+            }
+            match solver.solve(&[], None) {
+                sat::Outcome::Sat(model) => {
+                    assert_eq!(expect, "sat", "{path}");
+                    let holds = |lit: &sat::Lit| model[lit.var()] == lit.is_positive();
+                    assert!(cnf.clauses.iter().all(|clause| clause.iter().any(holds)), "{path}");
+                }
+                sat::Outcome::Unsat(core) => {
+                    assert_eq!(expect, "unsat", "{path}");
+                    assert!(core.is_empty());
+                }
+                sat::Outcome::Unknown(reason) => panic!("{path}: {reason}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sat_incremental() {
+        let lit = |n| sat::Lit::from_dimacs(n).unwrap();
+        let mut solver = sat::Solver::new();
+        // 1 => 2, 2 => 3, and 3 excludes 4.
+        solver.add_clause(&[lit(-1), lit(2)]);
+        solver.add_clause(&[lit(-2), lit(3)]);
+        solver.add_clause(&[lit(-3), lit(-4)]);
+        let sat::Outcome::Sat(model) = solver.solve(&[lit(1)], None) else {
+            panic!("expected a model");
+        };
+        assert!(model[0] && model[1] && model[2] && !model[3]);
+
+        // Assuming 1 and 4 fails on both, not on the unrelated 5.
+        let sat::Outcome::Unsat(mut core) = solver.solve(&[lit(5), lit(1), lit(4)], None) else {
+            panic!("expected no model");
+        };
+        core.sort();
+        assert_eq!(core, [lit(1), lit(4)]);
+
+        // Clauses added later count, and the solver stays usable.
+        assert!(solver.add_clause(&[lit(-5), lit(-2)]));
+        assert!(matches!(solver.solve(&[lit(5), lit(1)], None), sat::Outcome::Unsat(_)));
+        assert!(matches!(solver.solve(&[lit(5)], None), sat::Outcome::Sat(_)));
+        assert!(!solver.add_clause(&[]));
+        assert_eq!(solver.solve(&[], None), sat::Outcome::Unsat(Vec::new()));
+    }
+
+    #[test]
+    fn test_dimacs() {
+        let cnf = dimacs::parse("c a comment\np cnf 3 2\n1 -3 0\n2 3\n-1 0\n").unwrap();
+        assert_eq!(cnf.vars, 3);
+        assert_eq!(cnf.clauses.len(), 2);
+        assert_eq!(
+            cnf.clauses[1].iter().map(|lit| lit.to_dimacs()).collect::<Vec<_>>(),
+            [2, 3, -1]
+        );
+        let error = dimacs::parse("p cnf 2 1\n1 3 0\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: the literal `3` is not one of the 2 variables"
+        );
+        assert_eq!(dimacs::parse("1 0").unwrap_err().line, 1);
+        assert_eq!(dimacs::model(&[true, false]), "v 1 -2 0\n");
+    }
+
+    #[test]
+    fn test_bits() {
+        // Against the machine's own arithmetic, at every pair of bytes.
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let (x, y) = (Bits::new(8, false, a.into()), Bits::new(8, false, b.into()));
+                assert_eq!((x + y).pattern(), a.wrapping_add(b).into());
+                assert_eq!((x * y).pattern(), a.wrapping_mul(b).into());
+                assert_eq!((x / y).pattern(), a.checked_div(b).unwrap_or(u8::MAX).into());
+                assert_eq!((x >> y).pattern(), a.checked_shr(b.into()).unwrap_or(0).into());
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+                let (i, j) = (a as i8, b as i8);
+                let (x, y) = (Bits::new(8, true, i.into()), Bits::new(8, true, j.into()));
+                assert_eq!((x - y).value(), Some(i.wrapping_sub(j).into()));
+                if j != 0 {
+                    assert_eq!((x / y).value(), Some(i.wrapping_div(j).into()));
+                }
+                assert_eq!((x >> y).value(), Some(i.checked_shr(b.into()).unwrap_or(i >> 7).into()));
+                assert_eq!(x.cmp(&y), i.cmp(&j));
+            }
+        }
+        // Dividing by zero is defined, as in SMT-LIB.
+        let zero = Bits::zero(8, true);
+        assert_eq!((Bits::new(8, true, 5) / zero).value(), Some(-1));
+        assert_eq!((Bits::new(8, true, -5) / zero).value(), Some(1));
+
+        let byte = Bits::new(8, true, -56);
+        assert_eq!(byte.cast(16, true).value(), Some(-56));
+        assert_eq!(byte.cast(16, false).value(), Some(65480));
+        assert_eq!(byte.cast(4, false).value(), Some(8));
+        assert_eq!(Bits::max(64, false).cast(128, true).value(), Some(u64::MAX.into()));
+        assert_eq!(Value::Bits(byte).to_string(), "-56 (0b11001000)");
+        assert_eq!(Value::Bits(Bits::max(32, false)).to_string(), "4294967295 (0xffffffff)");
+        assert_eq!(
+            Sort::Bits {
+                width: 64,
+                signed: true
+            }
+            .to_string(),
+            "i64"
+        );
+    }
+
+    /// A random bit-vector term of sort `sort` over `vars`, from a linear
+    /// congruential generator.
+    fn random_term(seed: &mut u64, depth: u32, vars: &[Term]) -> Term {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let pick = (*seed >> 33) as usize;
+        let sort = vars[0].sort();
+        if depth == 0 || pick.is_multiple_of(7) {
+            return match pick % 3 {
+                0 => Term::bits((pick / 3 % 16) as i128, sort),
+                _ => vars[pick / 3 % vars.len()].clone(),
+            };
+        }
+        let mut sub = || random_term(seed, depth - 1, vars);
+        let (lhs, rhs) = (sub(), sub());
+        match pick / 7 % 13 {
+            0 => lhs + rhs,
+            1 => lhs - rhs,
+            2 => lhs * rhs,
+            3 => lhs / rhs,
+            4 => -lhs,
+            5 => Term::complement(lhs),
+            6 => Term::bitwise(BitOp::And, lhs, rhs),
+            7 => Term::bitwise(BitOp::Or, lhs, rhs),
+            8 => Term::bitwise(BitOp::Xor, lhs, rhs),
+            9 => Term::bitwise(BitOp::Shl, lhs, rhs),
+            10 => Term::bitwise(BitOp::Shr, lhs, rhs),
+            11 => {
+                let Sort::Bits { width, signed } = sort else {
+                    unreachable!()
+                };
+                let wide = Term::cast(
+                    lhs,
+                    Sort::Bits {
+                        width: width + 3,
+                        signed: !signed,
+                    },
+                );
+                Term::cast(
+                    wide * Term::cast(
+                        rhs,
+                        Sort::Bits {
+                            width: width + 3,
+                            signed: !signed,
+                        },
+                    ),
+                    sort,
+                )
+            }
+            _ => Term::ite(Term::cmp(Cmp::Lt, lhs.clone(), rhs.clone()), lhs, rhs),
+        }
+    }
+
+    #[test]
+    fn test_bitblast() {
+        let limits = Limits::default();
+        let mut seed = 7;
+        for signed in [false, true] {
+            let sort = Sort::Bits { width: 4, signed };
+            let vars = [Term::var("a", sort), Term::var("b", sort)];
+            for round in 0..150 {
+                let op = [Cmp::Eq, Cmp::Ne, Cmp::Lt, Cmp::Le, Cmp::Gt, Cmp::Ge][round % 6];
+                let formula = Term::cmp(op, random_term(&mut seed, 3, &vars), random_term(&mut seed, 3, &vars));
+                // Every assignment, evaluated.
+                let satisfiable = (0..16).any(|a| {
+                    (0..16).any(|b| {
+                        let mut model = Model::new();
+                        model.insert("a", Value::Bits(Bits::new(4, signed, a)));
+                        model.insert("b", Value::Bits(Bits::new(4, signed, b)));
+                        formula.eval(&model) == Some(Value::Bool(true))
+                    })
+                });
+                match check(&formula, &limits) {
+                    Satisfiability::Sat(model) => {
+                        assert!(satisfiable, "{formula}");
+                        assert_eq!(formula.eval(&model), Some(Value::Bool(true)), "{formula}");
+                    }
+                    Satisfiability::Unsat(_) => assert!(!satisfiable, "{formula}"),
+                    Satisfiability::Unknown(reason) => panic!("{formula}: {reason}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let limits = Limits::default();
+        let u64 = Sort::Bits {
+            width: 64,
+            signed: false,
+        };
+        let u128 = Sort::Bits {
+            width: 128,
+            signed: false,
+        };
+        let (x, y) = (Term::var("x", u64), Term::var("y", u64));
+        // The product, computed without wrapping, fits in 64 bits.
+        let wide = |term: &Term| Term::cast(term.clone(), u128);
+        let fits = Term::cmp(Cmp::Le, wide(&x) * wide(&y), Term::bits(u64::MAX.into(), u128));
+        let small = |term: &Term| Term::cmp(Cmp::Le, term.clone(), Term::bits(1000, u64));
+        let bounded = Term::implies(Term::and([small(&x), small(&y)]), fits.clone());
+        assert_eq!(prove(&bounded, &limits), Verdict::Proved);
+        let below = |term: &Term| Term::cmp(Cmp::Lt, term.clone(), Term::bits(1 << 32, u64));
+        let halves = Term::implies(Term::and([below(&x), below(&y)]), fits.clone());
+        assert_eq!(prove(&halves, &limits), Verdict::Proved);
+
+        // Without the bounds, the counterexample is a product that wraps.
+        let Verdict::Refuted(model) = prove(&fits, &limits) else {
+            panic!("expected a counterexample");
+        };
+        let value = |name| match model.get(name) {
+            Some(Value::Bits(value)) => value.pattern(),
+            other => panic!("expected bits, found {other:?}"),
+        };
+        assert!(value("x").checked_mul(value("y")).unwrap() > u64::MAX.into());
+
+        // Masks and shifts at machine width, mixed with integer facts.
+        let u8 = Sort::Bits {
+            width: 8,
+            signed: false,
+        };
+        let b = Term::var("b", u8);
+        let low = Term::bitwise(BitOp::And, b.clone(), Term::bits(0x0f, u8));
+        let high = Term::bitwise(BitOp::Shr, b.clone(), Term::bits(4, u8));
+        let joined = Term::bitwise(BitOp::Or, Term::bitwise(BitOp::Shl, high, Term::bits(4, u8)), low);
+        assert_eq!(prove(&Term::eq(joined, b.clone()), &limits), Verdict::Proved);
+        let mixed = Term::implies(
+            Term::and([
+                Term::cmp(Cmp::Gt, x.clone(), Term::bits(3, u64)),
+                gt(self::x(), Term::int(3)),
+            ]),
+            Term::and([Term::cmp(Cmp::Ne, x, Term::bits(0, u64)), gt(self::x(), Term::int(0))]),
+        );
+        assert_eq!(prove(&mixed, &limits), Verdict::Proved);
+    }
+
+    #[test]
+    fn test_smtlib() {
+        let u8 = Sort::Bits {
+            width: 8,
+            signed: false,
+        };
+        let b = Term::var("b", u8);
+        let formula = Term::implies(
+            gt(x(), Term::int(0)),
+            Term::and([
+                ge(x() / Term::int(2), Term::int(0)),
+                Term::cmp(Cmp::Le, b.clone() + Term::bits(1, u8), b),
+            ]),
+        );
+        let script = smtlib::Script::validity(&formula, "f@1:2-1:9", &["`f` ensures it".to_owned()]);
+        assert_eq!(
+            script.text(),
+            "; `f` ensures it\n\
+             (set-info :smt-lib-version 2.6)\n\
+             (set-option :produce-models true)\n\
+             (set-logic ALL)\n\
+             (declare-const b (_ BitVec 8))\n\
+             (declare-const x Int)\n\
+             (assert (! (not (=> (> x 0) (and (>= (ite (>= x 0) (div x 2) (- (div (- x) 2))) 0) \
+             (bvule (bvadd b #x01) b)))) :named |f@1:2-1:9|))\n\
+             (check-sat)\n\
+             (get-info :reason-unknown)\n\
+             (get-model)\n\
+             (exit)\n"
+        );
+
+        // Answers as z3 and cvc5 give them.
+        assert_eq!(
+            script.answer("unsat\n(error \"model is not available\")\n"),
+            Verdict::Proved
+        );
+        let Verdict::Refuted(model) = script.answer(
+            "sat\n(:reason-unknown \"\")\n(\n  (define-fun x () Int\n    (- 3))\n  \
+             (define-fun b () (_ BitVec 8)\n    #xff)\n)\n",
+        ) else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(model.get("x"), Some(Value::Int(-3)));
+        assert_eq!(
+            model.get("b").map(|value| value.to_string()).as_deref(),
+            Some("255 (0b11111111)")
+        );
+        let Verdict::Refuted(model) =
+            script.answer("sat\n(model\n(define-fun |x| () Int 7)\n(define-fun b () (_ BitVec 8) (_ bv3 8)))")
+        else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(model.get("x"), Some(Value::Int(7)));
+        assert_eq!(
+            model.get("b").map(|value| value.to_string()).as_deref(),
+            Some("3 (0b00000011)")
+        );
+        assert_eq!(
+            script.answer("unknown\n(:reason-unknown \"(incomplete quantifiers)\")\n(error \"no model\")"),
+            Verdict::Unknown("(incomplete quantifiers)".to_owned())
+        );
+        assert_eq!(
+            script.answer("unknown\n(:reason-unknown incomplete)"),
+            Verdict::Unknown("incomplete".to_owned())
+        );
+        assert_eq!(
+            script.answer("(error \"line 4 column 2: unknown constant b\")"),
+            Verdict::Unknown("the solver reported an error: line 4 column 2: unknown constant b".to_owned())
+        );
+        assert_eq!(
+            script.answer("\nSegmentation fault"),
+            Verdict::Unknown("the solver answered `Segmentation fault`".to_owned())
+        );
+
+        // Any process that reads a script and answers will do.
+        let limits = Limits::default();
+        assert_eq!(
+            smtlib::run("sh -c 'cat > /dev/null; echo unsat'", &script, &limits),
+            Verdict::Proved
+        );
+        let Verdict::Refuted(model) =
+            smtlib::run("sh -c 'echo sat; echo \"((define-fun x () Int 1))\"'", &script, &limits)
+        else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(model.get("x"), Some(Value::Int(1)));
+        let quick = Limits {
+            timeout: std::time::Duration::from_millis(50),
+        };
+        assert_eq!(
+            smtlib::run("sleep 5", &script, &quick),
+            Verdict::Unknown("timeout".to_owned())
+        );
+        let Verdict::Unknown(reason) = smtlib::run("flerry-no-such-solver -in", &script, &limits) else {
+            panic!("expected the solver not to run");
+        };
+        assert!(
+            reason.starts_with("could not run `flerry-no-such-solver`: "),
+            "{reason}"
+        );
+        assert_eq!(
+            smtlib::run("z3 '-in", &script, &limits),
+            Verdict::Unknown("the solver command `z3 '-in` has an unclosed quote".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_theory_lemmas() {
+        let limits = Limits::default();
+        // Each `x_i` is on one side of zero, none is below it, and no two in
+        // a row sum to more than one. The comparisons each model of the
+        // disjunctions makes true are refuted by the arithmetic, and the
+        // clause learned has only the comparisons the refutation uses.
+        let vars: Vec<Term> = (0..30).map(|i| Term::int_var(format!("x{i}"))).collect();
+        let sides = vars
+            .iter()
+            .map(|x| Term::or([lt(x.clone(), Term::int(0)), gt(x.clone(), Term::int(0))]));
+        let signs = vars.iter().map(|x| ge(x.clone(), Term::int(0)));
+        let pairs = vars
+            .windows(2)
+            .map(|pair| Term::cmp(Cmp::Le, pair[0].clone() + pair[1].clone(), Term::int(1)));
+        let formula = Term::and(sides.chain(signs).chain(pairs));
+        let Satisfiability::Unsat(Proof::Boolean(lemmas)) = check(&formula, &limits) else {
+            panic!("expected the lemmas of the SAT solver");
+        };
+        assert!(!lemmas.is_empty());
+        for lemma in &lemmas {
+            let Proof::Arithmetic { constraints, .. } = lemma else {
+                panic!("expected an arithmetic lemma");
+            };
+            assert!(constraints.len() <= 3, "{constraints:?}");
+        }
+
+        // Dropping the bounds on the pairs leaves a model.
+        let sides = vars
+            .iter()
+            .map(|x| Term::or([lt(x.clone(), Term::int(0)), gt(x.clone(), Term::int(0))]));
+        let signs = vars.iter().map(|x| ge(x.clone(), Term::int(0)));
+        let Satisfiability::Sat(model) = check(&Term::and(sides.chain(signs)), &limits) else {
+            panic!("expected a model");
+        };
+        assert!((0..30).all(|i| matches!(model.get(&format!("x{i}")), Some(Value::Int(x)) if x > 0)));
+    }
+
+    #[test]
+    fn test_division_by_constants() {
+        let limits = Limits::default();
+        let int = Term::int;
+        // The midpoint of `lo < hi` is in `lo..hi`.
+        let (lo, hi) = (x(), y());
+        let mid = lo.clone() + (hi.clone() - lo.clone()) / int(2);
+        let midpoint = Term::implies(
+            lt(lo.clone(), hi.clone()),
+            Term::and([Term::cmp(Cmp::Le, lo, mid.clone()), lt(mid, hi)]),
+        );
+        assert_eq!(prove(&midpoint, &limits), Verdict::Proved);
+        let halved = Term::implies(gt(x(), int(0)), lt(x() / int(2), x()));
+        assert_eq!(prove(&halved, &limits), Verdict::Proved);
+
+        // Division rounds towards zero, and the remainder has the sign of the dividend.
+        let rem = |lhs: Term, rhs: Term| lhs.clone() - lhs / rhs.clone() * rhs;
+        for (value, divisor, quotient, remainder) in [(-7, 2, -3, -1), (7, -2, -3, 1), (-7, -2, 3, -1), (-1, 2, 0, -1)]
+        {
+            let divides = Term::implies(
+                Term::eq(x(), int(value)),
+                Term::and([
+                    Term::eq(x() / int(divisor), int(quotient)),
+                    Term::eq(rem(x(), int(divisor)), int(remainder)),
+                ]),
+            );
+            assert_eq!(prove(&divides, &limits), Verdict::Proved, "{value} / {divisor}");
+        }
+        let negative = Term::implies(
+            lt(x(), int(0)),
+            Term::and([ge(x() / int(3), x()), lt(rem(x(), int(3)), int(1))]),
+        );
+        assert_eq!(prove(&negative, &limits), Verdict::Proved);
+
+        // What does not follow has a counterexample, checked against the division itself.
+        let Verdict::Refuted(model) = prove(&Term::eq(x() / int(2) * int(2), x()), &limits) else {
+            panic!("expected a counterexample");
+        };
+        assert!(matches!(model.get("x"), Some(Value::Int(x)) if x % 2 != 0), "{model:?}");
+    }
+}
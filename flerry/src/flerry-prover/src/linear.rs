@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Linear expressions with integer coefficients, `2x - y + 3`, and the
+//! constraints the arithmetic procedures work on.
+//!
+//! Coefficients are `i128`, so products of two `i64` constants fit; an
+//! operation that overflows even that gives `None`, which the solver turns
+//! into an unknown result rather than a wrong one.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Linear {
+    /// The coefficient of each variable; never zero.
+    pub coeffs: BTreeMap<String, i128>,
+    pub constant: i128,
+}
+
+impl Linear {
+    pub fn constant(value: i128) -> Linear {
+        Linear {
+            coeffs: BTreeMap::new(),
+            constant: value,
+        }
+    }
+
+    pub fn var(name: impl Into<String>) -> Linear {
+        Linear {
+            coeffs: BTreeMap::from([(name.into(), 1)]),
+            constant: 0,
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    pub fn coeff(&self, name: &str) -> i128 {
+        self.coeffs.get(name).copied().unwrap_or(0)
+    }
+
+    /// `self + factor * other`.
+    pub fn add_scaled(&self, other: &Linear, factor: i128) -> Option<Linear> {
+        let mut sum = self.clone();
+        for (name, coeff) in &other.coeffs {
+            let entry = sum.coeffs.entry(name.clone()).or_insert(0);
+            *entry = entry.checked_add(coeff.checked_mul(factor)?)?;
+            if *entry == 0 {
+                sum.coeffs.remove(name);
+            }
+        }
+        sum.constant = sum.constant.checked_add(other.constant.checked_mul(factor)?)?;
+        Some(sum)
+    }
+
+    pub fn scale(&self, factor: i128) -> Option<Linear> {
+        Linear::default().add_scaled(self, factor)
+    }
+
+    /// Replaces `name` by `value`.
+    pub fn substitute(&self, name: &str, value: &Linear) -> Option<Linear> {
+        let coeff = self.coeff(name);
+        if coeff == 0 {
+            return Some(self.clone());
+        }
+        let mut rest = self.clone();
+        rest.coeffs.remove(name);
+        rest.add_scaled(value, coeff)
+    }
+
+    /// The value with the variables in `values`, taking others to be zero.
+    pub fn eval(&self, values: &BTreeMap<String, i128>) -> Option<i128> {
+        let mut sum = self.constant;
+        for (name, coeff) in &self.coeffs {
+            let value = values.get(name).copied().unwrap_or(0);
+            sum = sum.checked_add(coeff.checked_mul(value)?)?;
+        }
+        Some(sum)
+    }
+
+    /// The greatest common divisor of the coefficients, zero if there are
+    /// none.
+    pub fn content(&self) -> i128 {
+        self.coeffs.values().fold(0, |g, c| gcd(g, *c))
+    }
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (name, coeff) in &self.coeffs {
+            let (sign, magnitude) = if *coeff < 0 { ("-", -coeff) } else { ("+", *coeff) };
+            match (first, sign) {
+                (true, "-") => f.write_str("-")?,
+                (true, _) => {}
+                (false, sign) => write!(f, " {sign} ")?,
+            }
+            if magnitude != 1 {
+                write!(f, "{magnitude}*")?;
+            }
+            f.write_str(name)?;
+            first = false;
+        }
+        if first {
+            write!(f, "{}", self.constant)
+        } else if self.constant < 0 {
+            write!(f, " - {}", -self.constant)
+        } else if self.constant > 0 {
+            write!(f, " + {}", self.constant)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Relation {
+    /// `expr <= 0`
+    Le,
+    /// `expr < 0`, only between rationals: over the integers it is
+    /// `expr + 1 <= 0`.
+    Lt,
+    /// `expr == 0`
+    Eq,
+}
+
+/// `expr <= 0`, `expr < 0` or `expr == 0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Constraint {
+    pub expr: Linear,
+    pub relation: Relation,
+}
+
+impl Constraint {
+    pub fn le(expr: Linear) -> Constraint {
+        Constraint {
+            expr,
+            relation: Relation::Le,
+        }
+    }
+
+    pub fn lt(expr: Linear) -> Constraint {
+        Constraint {
+            expr,
+            relation: Relation::Lt,
+        }
+    }
+
+    pub fn eq(expr: Linear) -> Constraint {
+        Constraint {
+            expr,
+            relation: Relation::Eq,
+        }
+    }
+
+    pub fn holds(&self, values: &BTreeMap<String, i128>) -> Option<bool> {
+        let value = self.expr.eval(values)?;
+        Some(match self.relation {
+            Relation::Le => value <= 0,
+            Relation::Lt => value < 0,
+            Relation::Eq => value == 0,
+        })
+    }
+
+    /// Divides through by the common factor of the coefficients, for a
+    /// constraint over integer variables. `2x - 3 <= 0` is `x - 1 <= 0`,
+    /// and `x < 0` is `x + 1 <= 0`; an equality whose constant the factor
+    /// does not divide has no solution, and gives `None`.
+    pub fn normalize(&self) -> Option<Constraint> {
+        if self.relation == Relation::Lt {
+            let expr = self.expr.add_scaled(&Linear::constant(1), 1)?;
+            return Constraint::le(expr).normalize();
+        }
+        let g = self.expr.content();
+        if g <= 1 {
+            return Some(self.clone());
+        }
+        let coeffs = self.expr.coeffs.iter().map(|(name, c)| (name.clone(), c / g)).collect();
+        let constant = match self.relation {
+            Relation::Le | Relation::Lt => ceil_div(self.expr.constant, g),
+            Relation::Eq if self.expr.constant % g != 0 => return None,
+            Relation::Eq => self.expr.constant / g,
+        };
+        Some(Constraint {
+            expr: Linear { coeffs, constant },
+            relation: self.relation,
+        })
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self.relation {
+            Relation::Le => "<=",
+            Relation::Lt => "<",
+            Relation::Eq => "==",
+        };
+        write!(f, "{} {symbol} 0", self.expr)
+    }
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `a / b` rounded down, for `b > 0`.
+pub fn floor_div(a: i128, b: i128) -> i128 {
+    a.div_euclid(b)
+}
+
+/// `a / b` rounded up, for `b > 0`.
+pub fn ceil_div(a: i128, b: i128) -> i128 {
+    -(-a).div_euclid(b)
+}
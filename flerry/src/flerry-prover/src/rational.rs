@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Exact rational numbers, for the simplex and its certificates.
+//!
+//! Numerators and denominators are `i128`, kept in lowest terms. An
+//! operation whose result does not fit gives the overflowed value, which
+//! every later operation passes on like a floating-point NaN; the simplex
+//! checks for it and answers unknown rather than wrong.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    /// Positive, or zero for the overflowed value.
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+    pub const ONE: Rational = Rational { num: 1, den: 1 };
+    /// The result of an operation that overflowed.
+    pub const OVERFLOW: Rational = Rational { num: 0, den: 0 };
+
+    pub fn new(num: i128, den: i128) -> Rational {
+        if den == 0 {
+            return Rational::OVERFLOW;
+        }
+        let g = gcd(num, den);
+        let (num, den) = (num / g, den / g);
+        if den < 0 {
+            match (num.checked_neg(), den.checked_neg()) {
+                (Some(num), Some(den)) => Rational { num, den },
+                _ => Rational::OVERFLOW,
+            }
+        } else {
+            Rational { num, den }
+        }
+    }
+
+    pub fn int(value: i128) -> Rational {
+        Rational { num: value, den: 1 }
+    }
+
+    pub fn num(self) -> i128 {
+        self.num
+    }
+
+    pub fn den(self) -> i128 {
+        self.den
+    }
+
+    pub fn is_overflow(self) -> bool {
+        self.den == 0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.num == 0 && self.den != 0
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.den == 1
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.num > 0 && self.den != 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.num < 0 && self.den != 0
+    }
+
+    /// The greatest integer not above this number.
+    pub fn floor(self) -> Rational {
+        if self.is_overflow() {
+            return self;
+        }
+        Rational::int(self.num.div_euclid(self.den))
+    }
+
+    pub fn abs(self) -> Rational {
+        if self.num < 0 { -self } else { self }
+    }
+
+    fn checked(num: Option<i128>, den: Option<i128>) -> Rational {
+        match (num, den) {
+            (Some(num), Some(den)) => Rational::new(num, den),
+            _ => Rational::OVERFLOW,
+        }
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Rational {
+        Rational::int(value.into())
+    }
+}
+
+impl ops::Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        match self.num.checked_neg() {
+            Some(num) => Rational { num, den: self.den },
+            None => Rational::OVERFLOW,
+        }
+    }
+}
+
+impl ops::Add for Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: Rational) -> Rational {
+        if self.is_overflow() || rhs.is_overflow() {
+            return Rational::OVERFLOW;
+        }
+        // Over the least common multiple of the denominators, so sums of
+        // numbers with the same denominator stay small.
+        let g = gcd(self.den, rhs.den);
+        let (left, right) = (rhs.den / g, self.den / g);
+        let num = self
+            .num
+            .checked_mul(left)
+            .zip(rhs.num.checked_mul(right))
+            .and_then(|(a, b)| a.checked_add(b));
+        Rational::checked(num, self.den.checked_mul(left))
+    }
+}
+
+impl ops::Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: Rational) -> Rational {
+        self + -rhs
+    }
+}
+
+impl ops::Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: Rational) -> Rational {
+        if self.is_overflow() || rhs.is_overflow() {
+            return Rational::OVERFLOW;
+        }
+        // Cancel across first, so products of numbers in lowest terms
+        // overflow only when the result does not fit.
+        let (a, b) = (gcd(self.num, rhs.den), gcd(rhs.num, self.den));
+        let num = (self.num / a).checked_mul(rhs.num / b);
+        let den = (self.den / b).checked_mul(rhs.den / a);
+        Rational::checked(num, den)
+    }
+}
+
+impl ops::Div for Rational {
+    type Output = Rational;
+
+    fn div(self, rhs: Rational) -> Rational {
+        if rhs.num == 0 || rhs.is_overflow() {
+            return Rational::OVERFLOW;
+        }
+        self * Rational::new(rhs.den, rhs.num)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    /// Compares by continued fractions, which cannot overflow. The
+    /// overflowed value sorts first.
+    fn cmp(&self, other: &Rational) -> Ordering {
+        match (self.is_overflow(), other.is_overflow()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => compare(self.num, self.den, other.num, other.den),
+        }
+    }
+}
+
+/// Compares `a / b` with `c / d`, for positive `b` and `d`.
+fn compare(a: i128, b: i128, c: i128, d: i128) -> Ordering {
+    let (q1, r1) = (a.div_euclid(b), a.rem_euclid(b));
+    let (q2, r2) = (c.div_euclid(d), c.rem_euclid(d));
+    match q1.cmp(&q2) {
+        Ordering::Equal => match (r1 == 0, r2 == 0) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // `r1 / b` against `r2 / d` is `d / r2` against `b / r1`.
+            (false, false) => compare(d, r2, b, r1),
+        },
+        order => order,
+    }
+}
+
+impl fmt::Display for Rational {
+    /// `3`, `-1/2`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.den {
+            0 => f.write_str("overflow"),
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{den}", self.num),
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // Only `gcd(i128::MIN, 0)` does not fit, and dividing by one is safe.
+    i128::try_from(a).unwrap_or(1).max(1)
+}
@@ -0,0 +1,626 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! A CDCL SAT solver, for the boolean structure of formulas.
+//!
+//! Each clause watches two of its literals and is only looked at when one
+//! of them becomes false, to find another to watch or, failing that, to
+//! propagate the other. A conflict is analysed back to its first unique
+//! implication point; the clause learned from it, with the literals its
+//! other literals imply dropped, sends the search back to the second
+//! highest level in it. Variables are decided by VSIDS: those in conflicts
+//! gain activity, the increment grows so older activity fades, and each
+//! takes the value it last had. Restarts follow the Luby sequence, and the
+//! less active half of the learned clauses is dropped as they pile up.
+//!
+//! The solver is incremental, as a theory solver driving it needs: clauses
+//! can be added between calls to [`Solver::solve`], and each call can
+//! assume literals, failing with those of them that cannot hold together.
+
+use std::fmt;
+use std::ops;
+use std::time::Instant;
+
+/// Conflicts before the first restart; later ones wait a multiple of it.
+const RESTART_INTERVAL: usize = 100;
+
+const VAR_DECAY: f64 = 0.95;
+const CLAUSE_DECAY: f64 = 0.999;
+
+/// A variable or its negation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub fn new(var: usize, positive: bool) -> Lit {
+        Lit((var as u32) << 1 | u32::from(!positive))
+    }
+
+    pub fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 & 1 == 0
+    }
+
+    /// The literal DIMACS writes as `n`: variable `|n|` counting from one,
+    /// negated if `n` is negative.
+    pub fn from_dimacs(n: i64) -> Option<Lit> {
+        let var = usize::try_from(n.unsigned_abs()).ok()?.checked_sub(1)?;
+        (var < 1 << 31).then(|| Lit::new(var, n > 0))
+    }
+
+    pub fn to_dimacs(self) -> i64 {
+        let var = self.var() as i64 + 1;
+        if self.is_positive() { var } else { -var }
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl ops::Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+impl fmt::Display for Lit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_dimacs())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The value of each variable.
+    Sat(Vec<bool>),
+    /// Assumptions that cannot all hold; none if the clauses cannot hold
+    /// whatever is assumed.
+    Unsat(Vec<Lit>),
+    /// The solver ran out of time.
+    Unknown(String),
+}
+
+/// What the solver has done so far, over every call.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub decisions: usize,
+    pub propagations: usize,
+    pub conflicts: usize,
+    pub restarts: usize,
+    pub learned: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Clause {
+    /// The first two are watched. The first of a clause that implied a
+    /// literal is that literal.
+    lits: Vec<Lit>,
+    learned: bool,
+    activity: f64,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Solver {
+    clauses: Vec<Clause>,
+    /// The clauses watching each literal, by literal.
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    /// The clause that implied each assigned variable, none for decisions.
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    /// Where each decision level after the first starts on the trail.
+    starts: Vec<usize>,
+    /// The first literal on the trail not yet propagated.
+    head: usize,
+    activity: Vec<f64>,
+    var_increment: f64,
+    clause_increment: f64,
+    order: Heap,
+    phases: Vec<bool>,
+    seen: Vec<bool>,
+    /// Set once the clauses are known to be unsatisfiable.
+    unsat: bool,
+    /// The learned clauses kept, and how many there may be before half go.
+    learned: usize,
+    max_learned: usize,
+    pub stats: Stats,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            var_increment: 1.0,
+            clause_increment: 1.0,
+            ..Solver::default()
+        }
+    }
+
+    /// A new variable.
+    pub fn var(&mut self) -> usize {
+        let var = self.values.len();
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.phases.push(false);
+        self.seen.push(false);
+        self.watches.extend([Vec::new(), Vec::new()]);
+        self.order.insert(var, &self.activity);
+        var
+    }
+
+    pub fn vars(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Adds a clause, with variables for any of its literals that have
+    /// none. False if the clauses are now known to be unsatisfiable.
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if self.unsat {
+            return false;
+        }
+        self.backtrack(0);
+        let mut lits = lits.to_vec();
+        if let Some(max) = lits.iter().map(|lit| lit.var()).max() {
+            while self.vars() <= max {
+                self.var();
+            }
+        }
+        lits.sort_unstable();
+        lits.dedup();
+        // A literal and its negation are next to each other when sorted.
+        if lits.windows(2).any(|pair| pair[0] == !pair[1]) || lits.iter().any(|&lit| self.value(lit) == Some(true)) {
+            return true;
+        }
+        lits.retain(|&lit| self.value(lit).is_none());
+        match lits.len() {
+            0 => self.unsat = true,
+            1 => {
+                self.assign(lits[0], None);
+                self.unsat = self.propagate().is_some();
+            }
+            _ => {
+                self.attach(lits, false);
+            }
+        }
+        !self.unsat
+    }
+
+    /// Whether the clauses can all hold with the assumptions, which are
+    /// decided first in the order given. `deadline` bounds the search.
+    pub fn solve(&mut self, assumptions: &[Lit], deadline: Option<Instant>) -> Outcome {
+        if self.unsat {
+            return Outcome::Unsat(Vec::new());
+        }
+        for &lit in assumptions {
+            while self.vars() <= lit.var() {
+                self.var();
+            }
+        }
+        self.max_learned = self.max_learned.max(self.clauses.len() / 3).max(1000);
+        for restarts in 0.. {
+            let budget = RESTART_INTERVAL * luby(restarts);
+            if let Some(outcome) = self.search(budget, assumptions, deadline) {
+                self.backtrack(0);
+                return outcome;
+            }
+            self.stats.restarts += 1;
+        }
+        unreachable!("the restarts go on until the search ends")
+    }
+
+    /// Searches until `budget` conflicts have passed, `None` if they do
+    /// without an answer.
+    fn search(&mut self, budget: usize, assumptions: &[Lit], deadline: Option<Instant>) -> Option<Outcome> {
+        let mut conflicts = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.stats.conflicts += 1;
+                conflicts += 1;
+                if self.level() == 0 {
+                    self.unsat = true;
+                    return Some(Outcome::Unsat(Vec::new()));
+                }
+                let (learned, level) = self.analyze(conflict);
+                self.backtrack(level);
+                let asserting = learned[0];
+                if learned.len() == 1 {
+                    self.assign(asserting, None);
+                } else {
+                    let id = self.attach(learned, true);
+                    self.bump_clause(id);
+                    self.assign(asserting, Some(id));
+                }
+                self.stats.learned += 1;
+                self.var_increment /= VAR_DECAY;
+                self.clause_increment /= CLAUSE_DECAY;
+                if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                    return Some(Outcome::Unknown("timeout".to_owned()));
+                }
+                continue;
+            }
+            if conflicts >= budget {
+                self.backtrack(0);
+                return None;
+            }
+            if self.learned >= self.max_learned + self.trail.len() {
+                self.reduce();
+            }
+            let mut next = None;
+            while let Some(&assumption) = assumptions.get(self.level()) {
+                match self.value(assumption) {
+                    // Already true: an empty level keeps the levels in
+                    // step with the assumptions.
+                    Some(true) => self.starts.push(self.trail.len()),
+                    Some(false) => return Some(Outcome::Unsat(self.failed(assumption))),
+                    None => {
+                        next = Some(assumption);
+                        break;
+                    }
+                }
+            }
+            let lit = match next {
+                Some(lit) => lit,
+                None => match self.decide() {
+                    Some(lit) => lit,
+                    None => {
+                        let model = self.values.iter().map(|value| value.unwrap_or(false)).collect();
+                        return Some(Outcome::Sat(model));
+                    }
+                },
+            };
+            self.stats.decisions += 1;
+            self.starts.push(self.trail.len());
+            self.assign(lit, None);
+        }
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|value| value == lit.is_positive())
+    }
+
+    fn level(&self) -> usize {
+        self.starts.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(lit.is_positive());
+        self.levels[var] = self.level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>, learned: bool) -> usize {
+        let id = self.clauses.len();
+        self.learned += usize::from(learned);
+        self.watches[lits[0].index()].push(id);
+        self.watches[lits[1].index()].push(id);
+        self.clauses.push(Clause {
+            lits,
+            learned,
+            activity: 0.0,
+            deleted: false,
+        });
+        id
+    }
+
+    /// Assigns what the clauses imply, giving the clause that became false
+    /// if one did.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let false_lit = !self.trail[self.head];
+            self.head += 1;
+            self.stats.propagations += 1;
+            let mut watchers = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut i = 0;
+            let mut conflict = None;
+            while i < watchers.len() {
+                let id = watchers[i];
+                let lits = &mut self.clauses[id].lits;
+                if lits[0] == false_lit {
+                    lits.swap(0, 1);
+                }
+                let first = lits[0];
+                let value = |lit: Lit| self.values[lit.var()].map(|value| value == lit.is_positive());
+                if value(first) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                if let Some(k) = (2..lits.len()).find(|&k| value(lits[k]) != Some(false)) {
+                    lits.swap(1, k);
+                    let watched = lits[1];
+                    self.watches[watched.index()].push(id);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+                if value(first) == Some(false) {
+                    conflict = Some(id);
+                    break;
+                }
+                self.assign(first, Some(id));
+                i += 1;
+            }
+            self.watches[false_lit.index()] = watchers;
+            if conflict.is_some() {
+                self.head = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// The clause learned from a conflict, asserting its first literal, and
+    /// the level to go back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learned = vec![Lit(0)];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let asserting = loop {
+            self.bump_clause(clause);
+            // The first literal of a reason is the one it implied.
+            let skip = usize::from(clause != conflict);
+            for k in skip..self.clauses[clause].lits.len() {
+                let lit = self.clauses[clause].lits[k];
+                let var = lit.var();
+                if self.seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                self.seen[var] = true;
+                self.bump_var(var);
+                if self.levels[var] == self.level() {
+                    pending += 1;
+                } else {
+                    learned.push(lit);
+                }
+            }
+            // The latest literal of this level in the conflict.
+            let lit = loop {
+                index -= 1;
+                if self.seen[self.trail[index].var()] {
+                    break self.trail[index];
+                }
+            };
+            self.seen[lit.var()] = false;
+            pending -= 1;
+            if pending == 0 {
+                break lit;
+            }
+            clause = self.reasons[lit.var()].expect("a literal implied at this level has a reason");
+        };
+        learned[0] = !asserting;
+
+        // Drop literals implied by others in the clause.
+        let analyzed = learned.clone();
+        learned.retain(|&lit| {
+            lit == !asserting
+                || self.reasons[lit.var()].is_none_or(|reason| {
+                    self.clauses[reason].lits[1..]
+                        .iter()
+                        .any(|other| !self.seen[other.var()] && self.levels[other.var()] > 0)
+                })
+        });
+        for lit in analyzed {
+            self.seen[lit.var()] = false;
+        }
+
+        if learned.len() == 1 {
+            return (learned, 0);
+        }
+        let (second, _) = learned
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by_key(|(_, lit)| self.levels[lit.var()])
+            .expect("the clause has a second literal");
+        learned.swap(1, second);
+        let level = self.levels[learned[1].var()];
+        (learned, level)
+    }
+
+    /// The assumptions that made the assumption `lit` false, and `lit`.
+    fn failed(&mut self, lit: Lit) -> Vec<Lit> {
+        let mut core = vec![lit];
+        if self.levels[lit.var()] == 0 {
+            return core;
+        }
+        self.seen[lit.var()] = true;
+        let start = self.starts.first().copied().unwrap_or(self.trail.len());
+        for index in (start..self.trail.len()).rev() {
+            let var = self.trail[index].var();
+            if !self.seen[var] {
+                continue;
+            }
+            match self.reasons[var] {
+                // Every decision so far is an assumption.
+                None => core.push(self.trail[index]),
+                Some(reason) => {
+                    for k in 1..self.clauses[reason].lits.len() {
+                        let other = self.clauses[reason].lits[k].var();
+                        if self.levels[other] > 0 {
+                            self.seen[other] = true;
+                        }
+                    }
+                }
+            }
+            self.seen[var] = false;
+        }
+        core
+    }
+
+    /// The most active unassigned variable, with its last value.
+    fn decide(&mut self) -> Option<Lit> {
+        while let Some(var) = self.order.pop(&self.activity) {
+            if self.values[var].is_none() {
+                return Some(Lit::new(var, self.phases[var]));
+            }
+        }
+        None
+    }
+
+    /// Undoes the assignments above `level`.
+    fn backtrack(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+        let start = self.starts[level];
+        for index in (start..self.trail.len()).rev() {
+            let lit = self.trail[index];
+            let var = lit.var();
+            self.values[var] = None;
+            self.reasons[var] = None;
+            self.phases[var] = lit.is_positive();
+            self.order.insert(var, &self.activity);
+        }
+        self.trail.truncate(start);
+        self.starts.truncate(level);
+        self.head = self.trail.len();
+    }
+
+    fn bump_var(&mut self, var: usize) {
+        self.activity[var] += self.var_increment;
+        if self.activity[var] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.var_increment *= 1e-100;
+        }
+        self.order.increase(var, &self.activity);
+    }
+
+    fn bump_clause(&mut self, id: usize) {
+        if !self.clauses[id].learned {
+            return;
+        }
+        self.clauses[id].activity += self.clause_increment;
+        if self.clauses[id].activity > 1e20 {
+            for clause in &mut self.clauses {
+                clause.activity *= 1e-20;
+            }
+            self.clause_increment *= 1e-20;
+        }
+    }
+
+    /// Drops the less active half of the learned clauses, keeping those
+    /// that imply a current assignment and binary ones.
+    fn reduce(&mut self) {
+        let locked = |solver: &Solver, id: usize| {
+            let first = solver.clauses[id].lits[0];
+            solver.reasons[first.var()] == Some(id) && solver.value(first) == Some(true)
+        };
+        let mut candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|&id| {
+                let clause = &self.clauses[id];
+                clause.learned && !clause.deleted && clause.lits.len() > 2 && !locked(self, id)
+            })
+            .collect();
+        candidates.sort_by(|&a, &b| self.clauses[a].activity.total_cmp(&self.clauses[b].activity));
+        for &id in &candidates[..candidates.len() / 2] {
+            self.learned -= 1;
+            self.clauses[id].deleted = true;
+            self.clauses[id].lits = Vec::new();
+        }
+        for watchers in &mut self.watches {
+            watchers.retain(|&id| !self.clauses[id].deleted);
+        }
+        self.max_learned += self.max_learned / 10;
+    }
+}
+
+/// The Luby sequence, 1 1 2 1 1 2 4 1 1 2 ..., from zero.
+fn luby(mut index: usize) -> usize {
+    let (mut size, mut power) = (1, 0);
+    while size < index + 1 {
+        power += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != index {
+        size = (size - 1) / 2;
+        power -= 1;
+        index %= size;
+    }
+    1 << power
+}
+
+/// The unassigned variables, most active first.
+#[derive(Debug, Clone, Default)]
+struct Heap {
+    vars: Vec<usize>,
+    /// Where each variable is in `vars`, if it is.
+    positions: Vec<Option<usize>>,
+}
+
+impl Heap {
+    fn insert(&mut self, var: usize, activity: &[f64]) {
+        if self.positions.len() <= var {
+            self.positions.resize(var + 1, None);
+        }
+        if self.positions[var].is_some() {
+            return;
+        }
+        self.positions[var] = Some(self.vars.len());
+        self.vars.push(var);
+        self.up(self.vars.len() - 1, activity);
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let top = *self.vars.first()?;
+        let last = self.vars.pop().expect("the heap is not empty");
+        self.positions[top] = None;
+        if !self.vars.is_empty() {
+            self.vars[0] = last;
+            self.positions[last] = Some(0);
+            self.down(0, activity);
+        }
+        Some(top)
+    }
+
+    /// Moves `var` up after its activity grew.
+    fn increase(&mut self, var: usize, activity: &[f64]) {
+        if let Some(position) = self.positions.get(var).copied().flatten() {
+            self.up(position, activity);
+        }
+    }
+
+    fn up(&mut self, mut position: usize, activity: &[f64]) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if activity[self.vars[parent]] >= activity[self.vars[position]] {
+                break;
+            }
+            self.swap(parent, position);
+            position = parent;
+        }
+    }
+
+    fn down(&mut self, mut position: usize, activity: &[f64]) {
+        loop {
+            let mut largest = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.vars.len() && activity[self.vars[child]] > activity[self.vars[largest]] {
+                    largest = child;
+                }
+            }
+            if largest == position {
+                break;
+            }
+            self.swap(largest, position);
+            position = largest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.vars.swap(a, b);
+        self.positions[self.vars[a]] = Some(a);
+        self.positions[self.vars[b]] = Some(b);
+    }
+}
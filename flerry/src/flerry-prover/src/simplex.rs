@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! The general simplex method, deciding whether bounds on linear forms over
+//! the rationals can all hold.
+//!
+//! Each form is a variable of its own, basic, defined by a row of the
+//! tableau in terms of the nonbasic variables. Only the bounds change as
+//! constraints are asserted; `check` then pivots, choosing variables by
+//! Bland's rule so it cannot cycle, until every basic variable is within its
+//! bounds or a row shows one cannot be. Strict bounds are bounds with an
+//! infinitesimal added: values are pairs `a + bδ`, compared in order.
+//!
+//! Every bound carries the constraint it came from and how many times that
+//! constraint it is, so a conflicting row reads off directly as a sum of
+//! constraints that is false: the Farkas certificate the caller checks.
+
+use crate::rational::Rational;
+use std::collections::BTreeMap;
+use std::ops;
+
+/// `real + delta * δ`, for an infinitesimal positive `δ`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Delta {
+    pub real: Rational,
+    pub delta: Rational,
+}
+
+impl Delta {
+    pub const ZERO: Delta = Delta {
+        real: Rational::ZERO,
+        delta: Rational::ZERO,
+    };
+
+    pub fn new(real: Rational, delta: Rational) -> Delta {
+        Delta { real, delta }
+    }
+
+    pub fn is_overflow(self) -> bool {
+        self.real.is_overflow() || self.delta.is_overflow()
+    }
+
+    /// The value for a particular `δ`.
+    pub fn at(self, delta: Rational) -> Rational {
+        self.real + self.delta * delta
+    }
+}
+
+impl ops::Add for Delta {
+    type Output = Delta;
+
+    fn add(self, rhs: Delta) -> Delta {
+        Delta::new(self.real + rhs.real, self.delta + rhs.delta)
+    }
+}
+
+impl ops::Sub for Delta {
+    type Output = Delta;
+
+    fn sub(self, rhs: Delta) -> Delta {
+        Delta::new(self.real - rhs.real, self.delta - rhs.delta)
+    }
+}
+
+impl ops::Mul<Rational> for Delta {
+    type Output = Delta;
+
+    fn mul(self, rhs: Rational) -> Delta {
+        Delta::new(self.real * rhs, self.delta * rhs)
+    }
+}
+
+/// A bound on a variable, `var <= value` or `var >= value`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bound {
+    pub value: Delta,
+    /// The constraint the bound comes from.
+    pub reason: usize,
+    /// `var - value` for an upper bound, or `value - var` for a lower one,
+    /// is this many times the constraint's expression.
+    pub scale: Rational,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Sat,
+    /// Multiples of constraints whose sum is a false constant constraint,
+    /// by constraint.
+    Conflict(BTreeMap<usize, Rational>),
+    /// A coefficient or value did not fit.
+    Overflow,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Simplex {
+    /// The row of each basic variable: its coefficient for each nonbasic
+    /// variable it is the sum of.
+    rows: Vec<Option<BTreeMap<usize, Rational>>>,
+    lower: Vec<Option<Bound>>,
+    upper: Vec<Option<Bound>>,
+    values: Vec<Delta>,
+}
+
+impl Simplex {
+    pub fn new() -> Simplex {
+        Simplex::default()
+    }
+
+    /// A new unbounded variable, nonbasic at zero.
+    pub fn var(&mut self) -> usize {
+        self.rows.push(None);
+        self.lower.push(None);
+        self.upper.push(None);
+        self.values.push(Delta::ZERO);
+        self.rows.len() - 1
+    }
+
+    /// A new variable equal to the sum of `form`'s variables by their
+    /// coefficients, basic.
+    pub fn define(&mut self, form: &BTreeMap<usize, Rational>) -> usize {
+        let mut row: BTreeMap<usize, Rational> = BTreeMap::new();
+        for (&var, &coeff) in form {
+            match &self.rows[var] {
+                Some(definition) => {
+                    for (&inner, &factor) in definition {
+                        add_to(&mut row, inner, coeff * factor);
+                    }
+                }
+                None => add_to(&mut row, var, coeff),
+            }
+        }
+        let value = self.evaluate(&row);
+        let var = self.var();
+        self.rows[var] = Some(row);
+        self.values[var] = value;
+        var
+    }
+
+    pub fn value(&self, var: usize) -> Delta {
+        self.values[var]
+    }
+
+    /// Asserts `var <= bound.value`, giving the conflict at once if it is
+    /// below the lower bound.
+    pub fn upper(&mut self, var: usize, bound: Bound) -> Result<(), BTreeMap<usize, Rational>> {
+        if self.upper[var].is_some_and(|upper| upper.value <= bound.value) {
+            return Ok(());
+        }
+        if let Some(lower) = self.lower[var]
+            && bound.value < lower.value
+        {
+            return Err(explanation([bound, lower]));
+        }
+        self.upper[var] = Some(bound);
+        if self.rows[var].is_none() && bound.value < self.values[var] {
+            self.update(var, bound.value);
+        }
+        Ok(())
+    }
+
+    /// Asserts `var >= bound.value`, giving the conflict at once if it is
+    /// above the upper bound.
+    pub fn lower(&mut self, var: usize, bound: Bound) -> Result<(), BTreeMap<usize, Rational>> {
+        if self.lower[var].is_some_and(|lower| lower.value >= bound.value) {
+            return Ok(());
+        }
+        if let Some(upper) = self.upper[var]
+            && bound.value > upper.value
+        {
+            return Err(explanation([bound, upper]));
+        }
+        self.lower[var] = Some(bound);
+        if self.rows[var].is_none() && bound.value > self.values[var] {
+            self.update(var, bound.value);
+        }
+        Ok(())
+    }
+
+    /// Pivots until every variable is within its bounds, or a row shows
+    /// they cannot all be.
+    pub fn check(&mut self) -> Check {
+        if self.is_overflow() {
+            return Check::Overflow;
+        }
+        loop {
+            let violated = (0..self.rows.len()).find_map(|var| {
+                self.rows[var].as_ref()?;
+                let value = self.values[var];
+                if let Some(lower) = self.lower[var]
+                    && value < lower.value
+                {
+                    return Some((var, lower, true));
+                }
+                match self.upper[var] {
+                    Some(upper) if value > upper.value => Some((var, upper, false)),
+                    _ => None,
+                }
+            });
+            let Some((basic, bound, increase)) = violated else {
+                return Check::Sat;
+            };
+            let row = self.rows[basic].as_ref().expect("the violated variable is basic");
+            // The nonbasic variable to move: it must be able to go the way
+            // that moves the basic one towards its bound.
+            let entering = row.iter().find(|&(&var, coeff)| {
+                let up = coeff.is_positive() == increase;
+                if up {
+                    self.upper[var].is_none_or(|upper| self.values[var] < upper.value)
+                } else {
+                    self.lower[var].is_none_or(|lower| self.values[var] > lower.value)
+                }
+            });
+            let Some((&entering, _)) = entering else {
+                // Every variable in the row is at the bound that keeps the
+                // basic one from its own.
+                let mut bounds = vec![bound];
+                for (&var, coeff) in row {
+                    let up = coeff.is_positive() == increase;
+                    let other = if up { self.upper[var] } else { self.lower[var] };
+                    let other = other.expect("a variable at its bound has one");
+                    bounds.push(Bound {
+                        scale: other.scale * coeff.abs(),
+                        ..other
+                    });
+                }
+                return Check::Conflict(explanation(bounds));
+            };
+            self.pivot_and_update(basic, entering, bound.value);
+            if self.is_overflow() {
+                return Check::Overflow;
+            }
+        }
+    }
+
+    /// Concrete values for the variables: `δ` made small enough that every
+    /// bound still holds.
+    pub fn model(&self) -> Vec<Rational> {
+        let mut delta = Rational::ONE;
+        for (var, &value) in self.values.iter().enumerate() {
+            // `lower <= value` must hold for the chosen `δ`, and likewise
+            // for the upper bound.
+            let gaps = [
+                self.lower[var].map(|lower| value - lower.value),
+                self.upper[var].map(|upper| upper.value - value),
+            ];
+            for gap in gaps.into_iter().flatten() {
+                if gap.real.is_positive() && gap.delta.is_negative() {
+                    delta = delta.min(gap.real / -gap.delta);
+                }
+            }
+        }
+        self.values.iter().map(|value| value.at(delta)).collect()
+    }
+
+    /// Moves the nonbasic `var` to `value`, and the basic variables with it.
+    fn update(&mut self, var: usize, value: Delta) {
+        let change = value - self.values[var];
+        for basic in 0..self.rows.len() {
+            if let Some(coeff) = self.rows[basic].as_ref().and_then(|row| row.get(&var)) {
+                self.values[basic] = self.values[basic] + change * *coeff;
+            }
+        }
+        self.values[var] = value;
+    }
+
+    /// Sets the basic `basic` to `value` by moving the nonbasic `entering`,
+    /// then swaps the two.
+    fn pivot_and_update(&mut self, basic: usize, entering: usize, value: Delta) {
+        let coeff = self.rows[basic].as_ref().expect("a basic variable has a row")[&entering];
+        let theta = (value - self.values[basic]) * (Rational::ONE / coeff);
+        self.update(entering, self.values[entering] + theta);
+        self.values[basic] = value;
+        self.pivot(basic, entering);
+    }
+
+    /// Makes `entering` basic in place of `basic`.
+    fn pivot(&mut self, basic: usize, entering: usize) {
+        let mut row = self.rows[basic].take().expect("a basic variable has a row");
+        let coeff = row.remove(&entering).expect("the entering variable is in the row");
+        // basic = coeff * entering + rest, so
+        // entering = basic / coeff - rest / coeff.
+        let inverse = Rational::ONE / coeff;
+        let mut definition: BTreeMap<usize, Rational> = row.into_iter().map(|(var, c)| (var, -c * inverse)).collect();
+        definition.insert(basic, inverse);
+        for other in self.rows.iter_mut().flatten() {
+            let Some(factor) = other.remove(&entering) else {
+                continue;
+            };
+            for (&var, &c) in &definition {
+                add_to(other, var, factor * c);
+            }
+        }
+        self.rows[entering] = Some(definition);
+    }
+
+    fn evaluate(&self, row: &BTreeMap<usize, Rational>) -> Delta {
+        row.iter()
+            .fold(Delta::ZERO, |sum, (&var, &coeff)| sum + self.values[var] * coeff)
+    }
+
+    fn is_overflow(&self) -> bool {
+        self.values.iter().any(|value| value.is_overflow())
+            || self
+                .rows
+                .iter()
+                .flatten()
+                .any(|row| row.values().any(|coeff| coeff.is_overflow()))
+    }
+}
+
+fn add_to(row: &mut BTreeMap<usize, Rational>, var: usize, coeff: Rational) {
+    let sum = row.get(&var).copied().unwrap_or(Rational::ZERO) + coeff;
+    if sum.is_zero() {
+        row.remove(&var);
+    } else {
+        row.insert(var, sum);
+    }
+}
+
+/// The multiples of constraints that the bounds, each times its scale,
+/// add up to.
+fn explanation(bounds: impl IntoIterator<Item = Bound>) -> BTreeMap<usize, Rational> {
+    let mut multiples = BTreeMap::new();
+    for bound in bounds {
+        let entry = multiples.entry(bound.reason).or_insert(Rational::ZERO);
+        *entry = *entry + bound.scale;
+    }
+    multiples
+}
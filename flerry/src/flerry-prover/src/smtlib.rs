@@ -0,0 +1,511 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! SMT-LIB 2.6, the language SMT solvers read: formulas written as scripts
+//! another solver can decide, and its answers read back.
+//!
+//! A script declares the variables and functions of the formula, asserts
+//! its negation under a name, and asks for a model, so `unsat` proves the
+//! formula and the model of a `sat` answer is a counterexample. Bit-vectors
+//! do not say whether they are signed; the operations and comparisons on
+//! them do, and the model is read back with the signedness of each
+//! variable's sort. Integer division rounds towards zero here but not in
+//! SMT-LIB, where the remainder is never negative, so it is written out as
+//! the division of the magnitude of the dividend.
+
+use crate::bits::Bits;
+use crate::rational::Rational;
+use crate::solver::{Limits, Verdict};
+use crate::term::{BitOp, Cmp, Model, Sort, Term, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::{Read, Write as _};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// A formula as a script for an SMT solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    text: String,
+    /// The variable each declared symbol stands for, and its sort.
+    symbols: BTreeMap<String, (String, Sort)>,
+}
+
+impl Script {
+    /// A script asking for values that make `formula` false, asserting its
+    /// negation under `name`. Each of `comments` is a line at the top.
+    pub fn validity(formula: &Term, name: &str, comments: &[String]) -> Script {
+        let mut text = String::new();
+        for comment in comments {
+            for line in comment.lines() {
+                writeln!(text, "; {line}").expect("writing to a string cannot fail");
+            }
+        }
+        text.push_str("(set-info :smt-lib-version 2.6)\n(set-option :produce-models true)\n(set-logic ALL)\n");
+        let mut symbols = BTreeMap::new();
+        for (var, sort) in formula.vars() {
+            let symbol = symbol(&var);
+            writeln!(text, "(declare-const {symbol} {})", sort_name(sort)).expect("writing to a string cannot fail");
+            symbols.insert(unquote(&symbol), (var, sort));
+        }
+        let mut functions = BTreeMap::new();
+        functions_in(formula, &mut functions);
+        for (function, (args, sort)) in functions {
+            let args: Vec<String> = args.into_iter().map(sort_name).collect();
+            writeln!(
+                text,
+                "(declare-fun {} ({}) {})",
+                symbol(&function),
+                args.join(" "),
+                sort_name(sort)
+            )
+            .expect("writing to a string cannot fail");
+        }
+        writeln!(text, "(assert (! (not {}) :named {}))", term(formula), symbol(name))
+            .expect("writing to a string cannot fail");
+        text.push_str("(check-sat)\n(get-info :reason-unknown)\n(get-model)\n(exit)\n");
+        Script { text, symbols }
+    }
+
+    /// The script, as a solver reads it.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// What a solver's answer to the script says of the formula. Errors
+    /// after the first answer, such as there being no model of `unsat`,
+    /// are ignored.
+    pub fn answer(&self, output: &str) -> Verdict {
+        let sexps = match parse(output) {
+            Ok(sexps) => sexps,
+            Err(message) => return Verdict::Unknown(format!("the solver's answer could not be read: {message}")),
+        };
+        match sexps.first() {
+            Some(Sexp::Atom(answer)) if answer == "unsat" => Verdict::Proved,
+            Some(Sexp::Atom(answer)) if answer == "sat" => Verdict::Refuted(self.model(&sexps[1..])),
+            Some(Sexp::Atom(answer)) if answer == "unknown" => {
+                let reason = sexps[1..].iter().find_map(|sexp| match sexp {
+                    Sexp::List(items) => match items.as_slice() {
+                        [Sexp::Atom(key), Sexp::Atom(reason) | Sexp::Str(reason)]
+                            if key == ":reason-unknown" && !reason.is_empty() =>
+                        {
+                            Some(reason.clone())
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                });
+                Verdict::Unknown(reason.unwrap_or_else(|| "the solver answered unknown".to_owned()))
+            }
+            Some(Sexp::List(items)) if matches!(items.first(), Some(Sexp::Atom(head)) if head == "error") => {
+                let message = match items.get(1) {
+                    Some(Sexp::Str(message)) => message.clone(),
+                    _ => String::new(),
+                };
+                Verdict::Unknown(format!("the solver reported an error: {message}"))
+            }
+            _ => {
+                let first = output.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+                Verdict::Unknown(format!("the solver answered `{}`", first.trim()))
+            }
+        }
+    }
+
+    /// The values of the declared variables in the `define-fun`s of a
+    /// `get-model` answer, which may be headed by `model`.
+    fn model(&self, sexps: &[Sexp]) -> Model {
+        let mut model = Model::new();
+        for sexp in sexps {
+            let Sexp::List(items) = sexp else {
+                continue;
+            };
+            for item in items {
+                let Sexp::List(definition) = item else {
+                    continue;
+                };
+                let [Sexp::Atom(keyword), Sexp::Atom(symbol), Sexp::List(params), _, value] = definition.as_slice()
+                else {
+                    continue;
+                };
+                if keyword != "define-fun" || !params.is_empty() {
+                    continue;
+                }
+                if let Some((var, sort)) = self.symbols.get(symbol)
+                    && let Some(value) = value_of(value, *sort)
+                {
+                    model.insert(var.clone(), value);
+                }
+            }
+        }
+        model
+    }
+}
+
+/// Runs the solver `command` on `script`, giving it until the timeout of
+/// `limits` to answer. The command is split into words at whitespace
+/// outside quotes, and run without a shell.
+pub fn run(command: &str, script: &Script, limits: &Limits) -> Verdict {
+    let deadline = Instant::now() + limits.timeout;
+    let words = match split(command) {
+        Some(words) if !words.is_empty() => words,
+        Some(_) => return Verdict::Unknown("no solver command was given".to_owned()),
+        None => return Verdict::Unknown(format!("the solver command `{command}` has an unclosed quote")),
+    };
+    let child = Command::new(&words[0])
+        .args(&words[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return Verdict::Unknown(format!("could not run `{}`: {e}", words[0])),
+    };
+    // The solver is fed and read on their own threads, so that neither
+    // pipe filling up can stall it.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let text = script.text.clone();
+    let writer = std::thread::spawn(move || stdin.write_all(text.as_bytes()));
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(5)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Verdict::Unknown("timeout".to_owned());
+            }
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Verdict::Unknown(format!("could not wait for `{}`: {e}", words[0]));
+            }
+        }
+    }
+    // A solver that stops reading once it has its answer closes the pipe
+    // early, which is no error.
+    let _ = writer.join();
+    match reader.join() {
+        Ok(Ok(output)) => script.answer(&output),
+        Ok(Err(e)) => Verdict::Unknown(format!("could not read the answer of `{}`: {e}", words[0])),
+        Err(_) => Verdict::Unknown(format!("could not read the answer of `{}`", words[0])),
+    }
+}
+
+/// `command` split into words at whitespace, with quotes grouping words
+/// and removed. `None` if a quote is not closed.
+fn split(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_default().push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_default().push(c),
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    words.extend(word);
+    Some(words)
+}
+
+fn sort_name(sort: Sort) -> String {
+    match sort {
+        Sort::Int => "Int".to_owned(),
+        Sort::Real => "Real".to_owned(),
+        Sort::Bool => "Bool".to_owned(),
+        Sort::Bits { width, .. } => format!("(_ BitVec {width})"),
+    }
+}
+
+/// `name` as a symbol: as it is if it is a simple symbol, and between bars
+/// if not. A bar or backslash cannot be in a quoted symbol and becomes `_`.
+fn symbol(name: &str) -> String {
+    let simple = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if simple {
+        name.to_owned()
+    } else {
+        format!("|{}|", name.replace(['|', '\\'], "_"))
+    }
+}
+
+fn unquote(symbol: &str) -> String {
+    symbol.trim_matches('|').to_owned()
+}
+
+/// The uninterpreted functions `term` applies, with the sorts of their
+/// arguments and results.
+fn functions_in(term: &Term, out: &mut BTreeMap<String, (Vec<Sort>, Sort)>) {
+    if let Term::App(function, args, sort) = term {
+        out.entry(function.clone())
+            .or_insert_with(|| (args.iter().map(Term::sort).collect(), *sort));
+    }
+    for child in term.children() {
+        functions_in(child, out);
+    }
+}
+
+fn term(term: &Term) -> String {
+    let mut out = String::new();
+    write_term(&mut out, term);
+    out
+}
+
+fn write_term(out: &mut String, term: &Term) {
+    let bits = |term: &Term| match term.sort() {
+        Sort::Bits { signed, .. } => Some(signed),
+        _ => None,
+    };
+    let apply = |out: &mut String, op: &str, args: &[&Term]| {
+        out.push('(');
+        out.push_str(op);
+        for arg in args {
+            out.push(' ');
+            write_term(out, arg);
+        }
+        out.push(')');
+    };
+    match term {
+        Term::Int(value) => out.push_str(&integer(i128::from(*value))),
+        Term::Real(value) => out.push_str(&real(*value)),
+        Term::Bits(value) if value.width() % 4 == 0 => {
+            write!(
+                out,
+                "#x{:0width$x}",
+                value.pattern(),
+                width = (value.width() / 4) as usize
+            )
+            .expect("writing to a string cannot fail");
+        }
+        Term::Bits(value) => {
+            write!(out, "#b{:0width$b}", value.pattern(), width = value.width() as usize)
+                .expect("writing to a string cannot fail");
+        }
+        Term::Bool(value) => write!(out, "{value}").expect("writing to a string cannot fail"),
+        Term::Var(name, _) => out.push_str(&symbol(name)),
+        Term::App(function, args, _) if args.is_empty() => out.push_str(&symbol(function)),
+        Term::App(function, args, _) => apply(out, &symbol(function), &args.iter().collect::<Vec<_>>()),
+        Term::Neg(operand) => apply(out, if bits(operand).is_some() { "bvneg" } else { "-" }, &[operand]),
+        Term::Add(lhs, rhs) => apply(out, if bits(lhs).is_some() { "bvadd" } else { "+" }, &[lhs, rhs]),
+        Term::Sub(lhs, rhs) => apply(out, if bits(lhs).is_some() { "bvsub" } else { "-" }, &[lhs, rhs]),
+        Term::Mul(lhs, rhs) => apply(out, if bits(lhs).is_some() { "bvmul" } else { "*" }, &[lhs, rhs]),
+        Term::Div(lhs, rhs) => match (bits(lhs), lhs.sort()) {
+            (Some(true), _) => apply(out, "bvsdiv", &[lhs, rhs]),
+            (Some(false), _) => apply(out, "bvudiv", &[lhs, rhs]),
+            (None, Sort::Int) => {
+                // `div` rounds so that the remainder is not negative, which
+                // is towards zero for a dividend that is not negative.
+                let (lhs, rhs) = (self::term(lhs), self::term(rhs));
+                write!(out, "(ite (>= {lhs} 0) (div {lhs} {rhs}) (- (div (- {lhs}) {rhs})))")
+                    .expect("writing to a string cannot fail");
+            }
+            (None, _) => apply(out, "/", &[lhs, rhs]),
+        },
+        Term::Bitwise(op, lhs, rhs) => {
+            let op = match op {
+                BitOp::And => "bvand",
+                BitOp::Or => "bvor",
+                BitOp::Xor => "bvxor",
+                BitOp::Shl => "bvshl",
+                BitOp::Shr if bits(lhs) == Some(true) => "bvashr",
+                BitOp::Shr => "bvlshr",
+            };
+            apply(out, op, &[lhs, rhs]);
+        }
+        Term::Complement(operand) => apply(out, "bvnot", &[operand]),
+        Term::Cast(operand, sort) => {
+            let (Sort::Bits { width: from, signed }, Sort::Bits { width: to, .. }) = (operand.sort(), *sort) else {
+                unreachable!("only bit-vectors are cast")
+            };
+            let op = if to > from {
+                let extend = if signed { "sign_extend" } else { "zero_extend" };
+                format!("(_ {extend} {})", to - from)
+            } else if to < from {
+                format!("(_ extract {} 0)", to - 1)
+            } else {
+                return write_term(out, operand);
+            };
+            apply(out, &op, &[operand]);
+        }
+        Term::Cmp(cmp, lhs, rhs) => {
+            let op = match (cmp, bits(lhs)) {
+                (Cmp::Eq, _) => "=",
+                (Cmp::Ne, _) => "distinct",
+                (Cmp::Lt, None) => "<",
+                (Cmp::Le, None) => "<=",
+                (Cmp::Gt, None) => ">",
+                (Cmp::Ge, None) => ">=",
+                (Cmp::Lt, Some(true)) => "bvslt",
+                (Cmp::Le, Some(true)) => "bvsle",
+                (Cmp::Gt, Some(true)) => "bvsgt",
+                (Cmp::Ge, Some(true)) => "bvsge",
+                (Cmp::Lt, Some(false)) => "bvult",
+                (Cmp::Le, Some(false)) => "bvule",
+                (Cmp::Gt, Some(false)) => "bvugt",
+                (Cmp::Ge, Some(false)) => "bvuge",
+            };
+            apply(out, op, &[lhs, rhs]);
+        }
+        Term::Not(operand) => apply(out, "not", &[operand]),
+        Term::And(terms) if terms.is_empty() => out.push_str("true"),
+        Term::Or(terms) if terms.is_empty() => out.push_str("false"),
+        Term::And(terms) => apply(out, "and", &terms.iter().collect::<Vec<_>>()),
+        Term::Or(terms) => apply(out, "or", &terms.iter().collect::<Vec<_>>()),
+        Term::Implies(lhs, rhs) => apply(out, "=>", &[lhs, rhs]),
+        Term::Ite(cond, then, otherwise) => apply(out, "ite", &[cond, then, otherwise]),
+    }
+}
+
+/// An integer as a term: a numeral, negated if it is below zero.
+fn integer(value: i128) -> String {
+    if value < 0 {
+        format!("(- {})", value.unsigned_abs())
+    } else {
+        value.to_string()
+    }
+}
+
+fn real(value: Rational) -> String {
+    let fraction = format!("(/ {}.0 {}.0)", value.num().unsigned_abs(), value.den());
+    if value.is_negative() {
+        format!("(- {fraction})")
+    } else {
+        fraction
+    }
+}
+
+/// A value in a model, read as a value of `sort`.
+fn value_of(sexp: &Sexp, sort: Sort) -> Option<Value> {
+    match (sort, sexp) {
+        (Sort::Bool, Sexp::Atom(atom)) => match atom.as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        (Sort::Int, _) => {
+            let value = number(sexp)?;
+            value
+                .is_integer()
+                .then(|| i64::try_from(value.num()).ok().map(Value::Int))?
+        }
+        (Sort::Real, _) => number(sexp).map(Value::Real),
+        (Sort::Bits { width, signed }, Sexp::Atom(atom)) => {
+            let (digits, radix) = if let Some(digits) = atom.strip_prefix("#b") {
+                (digits, 2)
+            } else {
+                (atom.strip_prefix("#x")?, 16)
+            };
+            let pattern = u128::from_str_radix(digits, radix).ok()?;
+            Some(Value::Bits(Bits::from_pattern(width, signed, pattern)))
+        }
+        // `(_ bv200 8)`
+        (Sort::Bits { width, signed }, Sexp::List(items)) => match items.as_slice() {
+            [Sexp::Atom(underscore), Sexp::Atom(value), Sexp::Atom(_)] if underscore == "_" => {
+                let pattern = value.strip_prefix("bv")?.parse().ok()?;
+                Some(Value::Bits(Bits::from_pattern(width, signed, pattern)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A number written as a numeral, a decimal, `(- x)` or `(/ x y)`.
+fn number(sexp: &Sexp) -> Option<Rational> {
+    match sexp {
+        Sexp::Atom(atom) => {
+            let (whole, fraction) = atom.split_once('.').unwrap_or((atom, ""));
+            if whole.is_empty() || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let den = 10i128.checked_pow(fraction.len() as u32)?;
+            let num = format!("{whole}{fraction}").parse().ok()?;
+            Some(Rational::new(num, den))
+        }
+        Sexp::List(items) => match items.as_slice() {
+            [Sexp::Atom(op), operand] if op == "-" => number(operand).map(|value| -value),
+            [Sexp::Atom(op), num, den] if op == "/" => {
+                let (num, den) = (number(num)?, number(den)?);
+                (!den.is_zero()).then(|| num / den)
+            }
+            _ => None,
+        },
+        Sexp::Str(_) => None,
+    }
+}
+
+/// An S-expression of a solver's answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sexp {
+    /// A symbol, without the bars of a quoted one, a keyword or a literal.
+    Atom(String),
+    Str(String),
+    List(Vec<Sexp>),
+}
+
+/// The S-expressions in `text`, one after another.
+fn parse(text: &str) -> Result<Vec<Sexp>, String> {
+    let mut stack: Vec<Vec<Sexp>> = vec![Vec::new()];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => stack.push(Vec::new()),
+            ')' => {
+                let list = stack.pop().filter(|_| !stack.is_empty()).ok_or("an unmatched `)`")?;
+                stack
+                    .last_mut()
+                    .expect("the outermost list stays")
+                    .push(Sexp::List(list));
+            }
+            ';' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next().ok_or("an unclosed string")? {
+                        // `""` is a quote inside a string.
+                        '"' if chars.next_if_eq(&'"').is_some() => string.push('"'),
+                        '"' => break,
+                        c => string.push(c),
+                    }
+                }
+                stack.last_mut().expect("there is a list").push(Sexp::Str(string));
+            }
+            '|' => {
+                let mut symbol = String::new();
+                loop {
+                    match chars.next().ok_or("an unclosed `|`")? {
+                        '|' => break,
+                        c => symbol.push(c),
+                    }
+                }
+                stack.last_mut().expect("there is a list").push(Sexp::Atom(symbol));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut atom = c.to_string();
+                while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && !"()\";|".contains(c)) {
+                    atom.push(c);
+                }
+                stack.last_mut().expect("there is a list").push(Sexp::Atom(atom));
+            }
+        }
+    }
+    match stack.pop() {
+        Some(sexps) if stack.is_empty() => Ok(sexps),
+        _ => Err("an unclosed `(`".to_owned()),
+    }
+}
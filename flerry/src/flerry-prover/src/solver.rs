@@ -0,0 +1,611 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Satisfiability of quantifier-free formulas over integers, rationals,
+//! booleans and uninterpreted functions.
+//!
+//! The formula is put in negation normal form, with `if` lifted out of
+//! arithmetic and `!=` split into `<` or `>`. Its boolean structure goes to
+//! the CDCL solver in [`sat`], with a variable for each comparison, and the
+//! comparisons each model of it makes true are checked together by the
+//! arithmetic solver. If they cannot all hold, the clause ruling out the
+//! ones the certificate uses is learned and the search goes on, so the SAT
+//! solver never tries them together again. The formula is monotone in its
+//! comparisons, so a comparison the model makes false can be left out of
+//! the check: the arithmetic making it true only makes the formula truer.
+//!
+//! An integer division by a constant, rounded towards zero, is a fresh
+//! integer with clauses saying the remainder has the sign of the dividend
+//! and is smaller than the divisor, so it is as exact as the rest.
+//!
+//! Products of variables, other integer divisions and applications of
+//! uninterpreted functions stand for fresh variables. For applications that
+//! loses that equal arguments give equal results, so a model is checked by
+//! congruence closure, and where it gives two applications equal arguments
+//! but different results the search goes on with the lemma saying they are
+//! equal. The rest can only make more formulas satisfiable, so
+//! "unsatisfiable" stays exact; a model is checked against the formula
+//! itself before it is reported, and one that does not hold is an unknown
+//! result.
+//!
+//! An unsatisfiable answer comes with a [`Proof`], and is only given when
+//! the proof checks.
+//!
+//! A formula with bit-vectors in it is bit-blasted instead, by
+//! [`bitblast::check`].
+
+use crate::arith::{self, Certificate, Outcome};
+use crate::bitblast;
+use crate::bits::Bits;
+use crate::euf::Congruence;
+use crate::linear::{Constraint, Linear};
+use crate::rational::Rational;
+use crate::sat::{self, Lit};
+use crate::term::{Cmp, Model, Sort, Term, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+/// How long the solver may search before answering unknown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Limits {
+    pub timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Satisfiability {
+    Sat(Model),
+    Unsat(Proof),
+    /// The solver could not decide, for the reason given.
+    Unknown(String),
+}
+
+/// Why a formula has no model: the lemmas the SAT solver learned, each
+/// literals that cannot all hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Proof {
+    /// Arithmetic literals with no common solution.
+    Arithmetic {
+        constraints: Vec<Constraint>,
+        integers: BTreeSet<String>,
+        certificate: Certificate,
+    },
+    /// The SAT solver found no model of the boolean structure of the
+    /// formula, taking the arithmetic comparisons in each lemma not to hold
+    /// together. Only the lemmas are checked.
+    Boolean(Vec<Proof>),
+    /// The SAT solver found no model of the bits of the formula, taking
+    /// the arithmetic comparisons in each lemma not to hold together. Only
+    /// the lemmas are checked.
+    BitBlasted(Vec<Proof>),
+}
+
+impl Proof {
+    /// Whether the certificate of every arithmetic contradiction in the
+    /// proof checks.
+    pub fn check(&self) -> bool {
+        match self {
+            Proof::Arithmetic {
+                constraints,
+                integers,
+                certificate,
+            } => certificate.check(constraints, integers),
+            Proof::Boolean(lemmas) | Proof::BitBlasted(lemmas) => lemmas.iter().all(Proof::check),
+        }
+    }
+}
+
+/// Whether a formula holds for every value of its variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Proved,
+    /// Values of the variables that make the formula false.
+    Refuted(Model),
+    Unknown(String),
+}
+
+/// Whether some values of its variables make `formula` true.
+pub fn check(formula: &Term, limits: &Limits) -> Satisfiability {
+    if bitblast::involves_bits(formula) {
+        return bitblast::check(formula, limits);
+    }
+    let integers = formula
+        .vars()
+        .into_iter()
+        .filter(|(_, sort)| *sort == Sort::Int)
+        .map(|(name, _)| name)
+        .collect();
+    let mut solver = Solver {
+        deadline: Instant::now() + limits.timeout,
+        formula,
+        abstractions: HashMap::new(),
+        integers,
+        sat: sat::Solver::new(),
+        lits: HashMap::new(),
+        atoms: Vec::new(),
+        bools: Vec::new(),
+    };
+    let normal = solver.nnf(formula, true);
+    match solver.search(&normal) {
+        Search::Sat(model) => Satisfiability::Sat(model),
+        Search::Unsat(proof) if proof.check() => Satisfiability::Unsat(proof),
+        Search::Unsat(_) => Satisfiability::Unknown("the proof of unsatisfiability does not check".to_owned()),
+        Search::Unknown(reason) => Satisfiability::Unknown(reason),
+    }
+}
+
+/// Whether `formula` is valid: its negation has no model.
+pub fn prove(formula: &Term, limits: &Limits) -> Verdict {
+    match check(&!formula.clone(), limits) {
+        Satisfiability::Sat(model) => Verdict::Refuted(model),
+        Satisfiability::Unsat(_) => Verdict::Proved,
+        Satisfiability::Unknown(reason) => Verdict::Unknown(reason),
+    }
+}
+
+enum Search {
+    Sat(Model),
+    Unsat(Proof),
+    Unknown(String),
+}
+
+enum Theory {
+    Sat(Model),
+    /// The indices of comparisons that cannot all hold, and why.
+    Unsat(Vec<usize>, Proof),
+    Unknown(String),
+}
+
+struct Solver<'f> {
+    deadline: Instant,
+    formula: &'f Term,
+    /// The variable standing for each term the arithmetic cannot reason
+    /// about.
+    abstractions: HashMap<Term, String>,
+    /// The variables, abstractions among them, with integer values.
+    integers: BTreeSet<String>,
+    sat: sat::Solver,
+    /// The literal standing for each part of the formula encoded.
+    lits: HashMap<Term, Lit>,
+    /// The comparisons, as constraints, with their literals.
+    atoms: Vec<(Constraint, Lit)>,
+    /// The boolean variables, with their literals.
+    bools: Vec<(String, Lit)>,
+}
+
+impl Solver<'_> {
+    /// `term`, or its negation when not `positive`, built from `&&`, `||`,
+    /// boolean variables and their negations, and comparisons `==`, `<`,
+    /// `<=`, `>`, `>=` of numeric terms without `if`.
+    fn nnf(&mut self, term: &Term, positive: bool) -> Term {
+        match term {
+            Term::Bool(value) => Term::Bool(*value == positive),
+            Term::Var(..) => literal(term.clone(), positive),
+            Term::App(..) => {
+                let var = Term::bool_var(self.abstract_term(term));
+                literal(var, positive)
+            }
+            Term::Not(inner) => self.nnf(inner, !positive),
+            Term::And(terms) | Term::Or(terms) => {
+                let parts: Vec<Term> = terms.iter().map(|term| self.nnf(term, positive)).collect();
+                if matches!(term, Term::And(_)) == positive {
+                    Term::and(parts)
+                } else {
+                    Term::or(parts)
+                }
+            }
+            Term::Implies(lhs, rhs) => {
+                let disjunction = Term::Or(vec![!(**lhs).clone(), (**rhs).clone()]);
+                self.nnf(&disjunction, positive)
+            }
+            Term::Ite(cond, then, otherwise) => {
+                let cases = Term::Or(vec![
+                    Term::And(vec![(**cond).clone(), (**then).clone()]),
+                    Term::And(vec![!(**cond).clone(), (**otherwise).clone()]),
+                ]);
+                self.nnf(&cases, positive)
+            }
+            Term::Cmp(op, lhs, rhs) => {
+                if let Some(ite) = find_ite(lhs).or_else(|| find_ite(rhs)) {
+                    let Term::Ite(cond, then, otherwise) = ite else {
+                        unreachable!("`find_ite` finds an `if`")
+                    };
+                    let case = |value: &Term| {
+                        Term::Cmp(
+                            *op,
+                            Box::new(replace(lhs, ite, value)),
+                            Box::new(replace(rhs, ite, value)),
+                        )
+                    };
+                    let cases = Term::Ite(cond.clone(), Box::new(case(then)), Box::new(case(otherwise)));
+                    return self.nnf(&cases, positive);
+                }
+                if lhs.sort() == Sort::Bool {
+                    // `a == b` is `a && b || !a && !b`.
+                    let same = Term::Or(vec![
+                        Term::And(vec![(**lhs).clone(), (**rhs).clone()]),
+                        Term::And(vec![!(**lhs).clone(), !(**rhs).clone()]),
+                    ]);
+                    return self.nnf(&same, (*op == Cmp::Eq) == positive);
+                }
+                let op = if positive { *op } else { op.negate() };
+                if op == Cmp::Ne {
+                    return Term::Or(vec![
+                        Term::Cmp(Cmp::Lt, lhs.clone(), rhs.clone()),
+                        Term::Cmp(Cmp::Gt, lhs.clone(), rhs.clone()),
+                    ]);
+                }
+                Term::Cmp(op, lhs.clone(), rhs.clone())
+            }
+            Term::Int(_)
+            | Term::Real(_)
+            | Term::Bits(_)
+            | Term::Neg(_)
+            | Term::Add(..)
+            | Term::Sub(..)
+            | Term::Mul(..)
+            | Term::Div(..)
+            | Term::Bitwise(..)
+            | Term::Complement(_)
+            | Term::Cast(..) => unreachable!("a numeric term is not a formula"),
+        }
+    }
+
+    /// The variable standing for `term`, the same one each time.
+    fn abstract_term(&mut self, term: &Term) -> String {
+        if let Some(name) = self.abstractions.get(term) {
+            return name.clone();
+        }
+        let name = format!("%{}", self.abstractions.len());
+        if term.sort() == Sort::Int {
+            self.integers.insert(name.clone());
+        }
+        self.abstractions.insert(term.clone(), name.clone());
+        name
+    }
+
+    /// `term` as a linear expression over a positive denominator, which
+    /// rationals need.
+    fn linear(&mut self, term: &Term) -> Option<(Linear, i128)> {
+        Some(match term {
+            Term::Int(value) => (Linear::constant(*value as i128), 1),
+            Term::Real(value) => (Linear::constant(value.num()), value.den()),
+            Term::Var(name, _) => (Linear::var(name.clone()), 1),
+            Term::Neg(term) => {
+                let (expr, den) = self.linear(term)?;
+                (expr.scale(-1)?, den)
+            }
+            Term::Add(lhs, rhs) => sum(self.linear(lhs)?, self.linear(rhs)?, 1)?,
+            Term::Sub(lhs, rhs) => sum(self.linear(lhs)?, self.linear(rhs)?, -1)?,
+            Term::Mul(lhs, rhs) => {
+                let ((lhs_linear, lhs_den), (rhs_linear, rhs_den)) = (self.linear(lhs)?, self.linear(rhs)?);
+                let den = lhs_den.checked_mul(rhs_den)?;
+                if lhs_linear.is_constant() {
+                    (rhs_linear.scale(lhs_linear.constant)?, den)
+                } else if rhs_linear.is_constant() {
+                    (lhs_linear.scale(rhs_linear.constant)?, den)
+                } else {
+                    (Linear::var(self.abstract_term(term)), 1)
+                }
+            }
+            // Division by a constant is exact between rationals.
+            Term::Div(lhs, rhs) if lhs.sort() == Sort::Real => {
+                let ((lhs_linear, lhs_den), (rhs_linear, rhs_den)) = (self.linear(lhs)?, self.linear(rhs)?);
+                if rhs_linear.is_constant() && rhs_linear.constant != 0 {
+                    let sign = rhs_linear.constant.signum();
+                    let den = lhs_den.checked_mul(rhs_linear.constant.checked_mul(sign)?)?;
+                    (lhs_linear.scale(rhs_den.checked_mul(sign)?)?, den)
+                } else {
+                    (Linear::var(self.abstract_term(term)), 1)
+                }
+            }
+            // The applications in the operands take part in congruence.
+            Term::Div(lhs, rhs) => {
+                let ((lhs_linear, lhs_den), (rhs_linear, rhs_den)) = (self.linear(lhs)?, self.linear(rhs)?);
+                if rhs_linear.is_constant() && rhs_linear.constant != 0 && lhs_den == 1 && rhs_den == 1 {
+                    (self.quotient(term, lhs_linear, rhs_linear.constant)?, 1)
+                } else {
+                    (Linear::var(self.abstract_term(term)), 1)
+                }
+            }
+            _ => (Linear::var(self.abstract_term(term)), 1),
+        })
+    }
+
+    /// The variable standing for `term`, `dividend / divisor` rounded
+    /// towards zero, with clauses saying so added the first time: the
+    /// remainder `dividend - divisor * q` has the sign of the dividend and
+    /// is nearer zero than the divisor.
+    fn quotient(&mut self, term: &Term, dividend: Linear, divisor: i128) -> Option<Linear> {
+        if let Some(name) = self.abstractions.get(term) {
+            return Some(Linear::var(name.clone()));
+        }
+        let name = self.abstract_term(term);
+        let remainder = dividend.add_scaled(&Linear::var(name.clone()), -divisor)?;
+        let bound = Linear::constant(divisor.checked_abs()? - 1);
+        let nonnegative = self.atom(Constraint::le(dividend.scale(-1)?));
+        let negative = self.atom(Constraint::le(dividend.add_scaled(&Linear::constant(1), 1)?));
+        let at_least_zero = self.atom(Constraint::le(remainder.scale(-1)?));
+        let below_divisor = self.atom(Constraint::le(remainder.add_scaled(&bound, -1)?));
+        let at_most_zero = self.atom(Constraint::le(remainder.clone()));
+        let above_divisor = self.atom(Constraint::le(remainder.scale(-1)?.add_scaled(&bound, -1)?));
+        self.sat.add_clause(&[nonnegative, negative]);
+        self.sat.add_clause(&[!nonnegative, at_least_zero]);
+        self.sat.add_clause(&[!nonnegative, below_divisor]);
+        self.sat.add_clause(&[!negative, at_most_zero]);
+        self.sat.add_clause(&[!negative, above_divisor]);
+        Some(Linear::var(name))
+    }
+
+    /// A literal standing for `constraint`, which the arithmetic checks
+    /// whenever it is true.
+    fn atom(&mut self, constraint: Constraint) -> Lit {
+        let lit = Lit::new(self.sat.var(), true);
+        self.atoms.push((constraint, lit));
+        lit
+    }
+
+    /// The constraint a comparison literal stands for, `None` on overflow.
+    fn constraint(&mut self, op: Cmp, lhs: &Term, rhs: &Term) -> Option<Constraint> {
+        // `lhs - rhs` is the difference over a positive denominator, which
+        // does not change its sign.
+        let (difference, _) = sum(self.linear(lhs)?, self.linear(rhs)?, -1)?;
+        let strict = |expr: Linear| match lhs.sort() {
+            Sort::Real => Some(Constraint::lt(expr)),
+            _ => Some(Constraint::le(expr.add_scaled(&Linear::constant(1), 1)?)),
+        };
+        match op {
+            Cmp::Eq => Some(Constraint::eq(difference)),
+            Cmp::Le => Some(Constraint::le(difference)),
+            Cmp::Lt => strict(difference),
+            Cmp::Ge => Some(Constraint::le(difference.scale(-1)?)),
+            Cmp::Gt => strict(difference.scale(-1)?),
+            Cmp::Ne => unreachable!("`!=` is split in negation normal form"),
+        }
+    }
+
+    /// `term` with the applications the search has a variable for replaced
+    /// by it.
+    fn abstracted(&self, term: &Term) -> Term {
+        match self.abstractions.get(term) {
+            Some(name) if matches!(term, Term::App(..)) => Term::var(name.clone(), term.sort()),
+            _ => term.map(&mut |child| self.abstracted(child)),
+        }
+    }
+
+    /// An instance of congruence that `model` breaks: applications whose
+    /// arguments the model makes equal but not their results. The lemma
+    /// says that if those equalities hold, the results are equal.
+    fn congruence(&self, model: &Model) -> Option<Term> {
+        let mut closure = Congruence::new();
+        for term in self.abstractions.keys() {
+            if matches!(term, Term::App(..)) {
+                closure.add(term);
+            }
+        }
+        let values: Vec<Option<Value>> = (0..closure.len())
+            .map(|id| self.abstracted(closure.term(id)).eval(model))
+            .collect();
+        // Merge the terms with equal values, then look for a class with
+        // different ones.
+        let mut given = Vec::new();
+        let mut first: HashMap<Value, usize> = HashMap::new();
+        for (id, value) in values.iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            match first.get(value) {
+                Some(&other) => {
+                    closure.merge(other, id, given.len());
+                    given.push((other, id));
+                }
+                None => {
+                    first.insert(*value, id);
+                }
+            }
+        }
+        let mut classes: HashMap<usize, (usize, Value)> = HashMap::new();
+        for (id, value) in values.iter().enumerate() {
+            let Some(value) = *value else {
+                continue;
+            };
+            let (other, other_value) = *classes.entry(closure.find(id)).or_insert((id, value));
+            if other_value == value {
+                continue;
+            }
+            let labels = closure.explain(other, id).expect("the two are in one class");
+            let term = |id: usize| closure.term(id).clone();
+            let premises = labels.into_iter().map(|label| {
+                let (a, b) = given[label];
+                !Term::eq(term(a), term(b))
+            });
+            return Some(Term::or(premises.chain([Term::eq(term(other), term(id))])));
+        }
+        None
+    }
+
+    /// A literal that implies `term`, which is in negation normal form,
+    /// with clauses saying so added to the SAT solver. `None` on overflow.
+    ///
+    /// A conjunction implies each of its parts and a disjunction one of
+    /// them; nothing is said the other way, since negation normal form
+    /// never needs it.
+    fn encode(&mut self, term: &Term) -> Option<Lit> {
+        if let Some(&lit) = self.lits.get(term) {
+            return Some(lit);
+        }
+        let lit = match term {
+            Term::Not(inner) => return Some(!self.encode(inner)?),
+            Term::And(terms) | Term::Or(terms) => {
+                let parts = terms
+                    .iter()
+                    .map(|term| self.encode(term))
+                    .collect::<Option<Vec<Lit>>>()?;
+                let gate = Lit::new(self.sat.var(), true);
+                if matches!(term, Term::And(_)) {
+                    for part in parts {
+                        self.sat.add_clause(&[!gate, part]);
+                    }
+                } else {
+                    self.sat.add_clause(&[[!gate].as_slice(), &parts].concat());
+                }
+                gate
+            }
+            Term::Bool(value) => {
+                let lit = Lit::new(self.sat.var(), true);
+                self.sat.add_clause(&[if *value { lit } else { !lit }]);
+                lit
+            }
+            Term::Var(name, _) => {
+                let lit = Lit::new(self.sat.var(), true);
+                self.bools.push((name.clone(), lit));
+                lit
+            }
+            Term::Cmp(op, lhs, rhs) => {
+                let constraint = self.constraint(*op, lhs, rhs)?;
+                self.atom(constraint)
+            }
+            term => unreachable!("`{term}` is not in negation normal form"),
+        };
+        self.lits.insert(term.clone(), lit);
+        Some(lit)
+    }
+
+    /// Looks for a model of `formula`, which is in negation normal form.
+    fn search(&mut self, formula: &Term) -> Search {
+        let Some(root) = self.encode(formula) else {
+            return Search::Unknown("arithmetic overflow".to_owned());
+        };
+        self.sat.add_clause(&[root]);
+        let mut lemmas = Vec::new();
+        loop {
+            let values = match self.sat.solve(&[], Some(self.deadline)) {
+                sat::Outcome::Sat(values) => values,
+                sat::Outcome::Unsat(_) => return Search::Unsat(Proof::Boolean(lemmas)),
+                sat::Outcome::Unknown(reason) => return Search::Unknown(reason),
+            };
+            let holds = |lit: Lit| values[lit.var()] == lit.is_positive();
+            let (constraints, lits): (Vec<Constraint>, Vec<Lit>) =
+                self.atoms.iter().filter(|(_, lit)| holds(*lit)).cloned().unzip();
+            let mut model = match self.theory(&constraints) {
+                Theory::Sat(model) => model,
+                Theory::Unsat(core, proof) => {
+                    let clause: Vec<Lit> = core.into_iter().map(|index| !lits[index]).collect();
+                    self.sat.add_clause(&clause);
+                    lemmas.push(proof);
+                    continue;
+                }
+                Theory::Unknown(reason) => return Search::Unknown(reason),
+            };
+            for (name, lit) in &self.bools {
+                model.insert(name.clone(), Value::Bool(holds(*lit)));
+            }
+            // Variables the search never constrained can be anything.
+            let vars = self.formula.vars().into_iter();
+            let abstractions = self.abstractions.iter().map(|(term, name)| (name.clone(), term.sort()));
+            for (name, sort) in vars.chain(abstractions) {
+                if model.get(&name).is_none() {
+                    let value = match sort {
+                        Sort::Int => Value::Int(0),
+                        Sort::Real => Value::Real(Rational::ZERO),
+                        Sort::Bits { width, signed } => Value::Bits(Bits::zero(width, signed)),
+                        Sort::Bool => Value::Bool(false),
+                    };
+                    model.insert(name, value);
+                }
+            }
+            if let Some(lemma) = self.congruence(&model) {
+                if Instant::now() > self.deadline {
+                    return Search::Unknown("timeout".to_owned());
+                }
+                let lemma = self.nnf(&lemma, true);
+                let Some(lit) = self.encode(&lemma) else {
+                    return Search::Unknown("arithmetic overflow".to_owned());
+                };
+                self.sat.add_clause(&[lit]);
+                continue;
+            }
+            if self.abstracted(self.formula).eval(&model) != Some(Value::Bool(true)) {
+                return Search::Unknown("the arithmetic is not linear".to_owned());
+            }
+            // Applications are shown as they are written.
+            for (term, name) in &self.abstractions {
+                let value = model.remove(name);
+                if let (Term::App(..), Some(value)) = (term, value) {
+                    model.insert(term.to_string(), value);
+                }
+            }
+            return Search::Sat(model);
+        }
+    }
+
+    /// Whether the constraints have a solution, integer where the
+    /// variables are. If not, the ones the certificate uses.
+    fn theory(&self, constraints: &[Constraint]) -> Theory {
+        match arith::solve(constraints, &self.integers, self.deadline) {
+            Outcome::Sat(values) => {
+                let mut model = Model::new();
+                for (name, value) in values {
+                    let value = if self.integers.contains(&name) {
+                        let Ok(value) = i64::try_from(value.num()) else {
+                            return Theory::Unknown("arithmetic overflow".to_owned());
+                        };
+                        Value::Int(value)
+                    } else {
+                        Value::Real(value)
+                    };
+                    model.insert(name, value);
+                }
+                Theory::Sat(model)
+            }
+            Outcome::Unsat(certificate) => {
+                let (core, certificate) = certificate.narrow(constraints.len());
+                let proof = Proof::Arithmetic {
+                    constraints: core.iter().map(|&index| constraints[index].clone()).collect(),
+                    integers: self.integers.clone(),
+                    certificate,
+                };
+                Theory::Unsat(core, proof)
+            }
+            Outcome::Unknown(reason) => Theory::Unknown(reason),
+        }
+    }
+}
+
+/// `lhs + factor * rhs`, over the product of their denominators.
+fn sum((lhs, lhs_den): (Linear, i128), (rhs, rhs_den): (Linear, i128), factor: i128) -> Option<(Linear, i128)> {
+    if lhs_den == rhs_den {
+        return Some((lhs.add_scaled(&rhs, factor)?, lhs_den));
+    }
+    let expr = lhs.scale(rhs_den)?.add_scaled(&rhs, factor.checked_mul(lhs_den)?)?;
+    Some((expr, lhs_den.checked_mul(rhs_den)?))
+}
+
+fn literal(var: Term, positive: bool) -> Term {
+    if positive { var } else { Term::Not(Box::new(var)) }
+}
+
+/// The first `if` in an integer position of `term`.
+fn find_ite(term: &Term) -> Option<&Term> {
+    match term {
+        Term::Ite(..) => Some(term),
+        // The arguments of an application are abstracted with it.
+        Term::App(..) => None,
+        term => term.children().into_iter().find_map(find_ite),
+    }
+}
+
+/// `term` with every occurrence of `target` replaced by `value`.
+fn replace(term: &Term, target: &Term, value: &Term) -> Term {
+    if term == target {
+        return value.clone();
+    }
+    term.map(&mut |child| replace(child, target, value))
+}
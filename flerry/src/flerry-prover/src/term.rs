@@ -0,0 +1,715 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+//! Formulas over integers, rationals, bit-vectors and booleans, the
+//! language verification conditions are written in.
+//!
+//! The constructors, and the arithmetic operators on terms, fold constants and drop `true` and `false` where they
+//! decide nothing, so conditions built up piece by piece stay readable.
+//! Terms print in Flerry's own syntax: `x + 1 > 0 && b`.
+
+use crate::bits::Bits;
+use crate::rational::Rational;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Sort {
+    Int,
+    Real,
+    Bool,
+    /// Fixed-width integers, whose arithmetic wraps around: `u8`, `i64`.
+    Bits {
+        width: u32,
+        signed: bool,
+    },
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sort::Int => f.write_str("Int"),
+            Sort::Real => f.write_str("Real"),
+            Sort::Bool => f.write_str("Bool"),
+            Sort::Bits { width, signed: false } => write!(f, "u{width}"),
+            Sort::Bits { width, signed: true } => write!(f, "i{width}"),
+        }
+    }
+}
+
+/// An operation on the bits of fixed-width integers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    /// A shift by the right operand, read unsigned.
+    Shl,
+    /// A shift bringing in copies of the sign bit for a signed operand,
+    /// zeros for an unsigned one.
+    Shr,
+}
+
+impl BitOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BitOp::And => "&",
+            BitOp::Or => "|",
+            BitOp::Xor => "^",
+            BitOp::Shl => "<<",
+            BitOp::Shr => ">>",
+        }
+    }
+
+    pub fn apply(self, lhs: Bits, rhs: Bits) -> Bits {
+        match self {
+            BitOp::And => lhs & rhs,
+            BitOp::Or => lhs | rhs,
+            BitOp::Xor => lhs ^ rhs,
+            BitOp::Shl => lhs << rhs,
+            BitOp::Shr => lhs >> rhs,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Cmp::Eq => "==",
+            Cmp::Ne => "!=",
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Gt => ">",
+            Cmp::Ge => ">=",
+        }
+    }
+
+    /// The comparison that holds exactly when this one does not.
+    pub fn negate(self) -> Cmp {
+        match self {
+            Cmp::Eq => Cmp::Ne,
+            Cmp::Ne => Cmp::Eq,
+            Cmp::Lt => Cmp::Ge,
+            Cmp::Le => Cmp::Gt,
+            Cmp::Gt => Cmp::Le,
+            Cmp::Ge => Cmp::Lt,
+        }
+    }
+
+    pub fn holds<T: Ord>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            Cmp::Eq => lhs == rhs,
+            Cmp::Ne => lhs != rhs,
+            Cmp::Lt => lhs < rhs,
+            Cmp::Le => lhs <= rhs,
+            Cmp::Gt => lhs > rhs,
+            Cmp::Ge => lhs >= rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Term {
+    Int(i64),
+    Real(Rational),
+    Bits(Bits),
+    Bool(bool),
+    Var(String, Sort),
+    /// A function the solver knows nothing about but that it returns equal
+    /// results for equal arguments: `len(xs)`.
+    App(String, Vec<Term>, Sort),
+    Neg(Box<Term>),
+    Add(Box<Term>, Box<Term>),
+    Sub(Box<Term>, Box<Term>),
+    Mul(Box<Term>, Box<Term>),
+    /// Division rounding towards zero between integers, exact between
+    /// rationals. Between bit-vectors it is as [`Bits`] has it.
+    Div(Box<Term>, Box<Term>),
+    Bitwise(BitOp, Box<Term>, Box<Term>),
+    /// Every bit flipped: `~x`.
+    Complement(Box<Term>),
+    /// A bit-vector as another width or signedness: the same number if it
+    /// fits, wrapped around if not.
+    Cast(Box<Term>, Sort),
+    Cmp(Cmp, Box<Term>, Box<Term>),
+    Not(Box<Term>),
+    And(Vec<Term>),
+    Or(Vec<Term>),
+    Implies(Box<Term>, Box<Term>),
+    Ite(Box<Term>, Box<Term>, Box<Term>),
+}
+
+/// A value of a variable in a model.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Int(i64),
+    Real(Rational),
+    Bits(Bits),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Real(value) => write!(f, "{value}"),
+            // The pattern too, which is what a machine integer is.
+            Value::Bits(value) => write!(f, "{value} ({})", value.pattern_literal()),
+            Value::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Values for the variables of a formula, under which it is true.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Model {
+    values: BTreeMap<String, Value>,
+}
+
+impl Model {
+    pub fn new() -> Model {
+        Model::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.values.get(name).copied()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: Value) {
+        self.values.insert(name.into(), value);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.values.remove(name)
+    }
+
+    /// The variables and their values, by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Value)> {
+        self.values.iter().map(|(name, value)| (name.as_str(), *value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl fmt::Display for Model {
+    /// `x = 1, b = false`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{name} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Term {
+    pub fn int(value: i64) -> Term {
+        Term::Int(value)
+    }
+
+    pub fn real(value: Rational) -> Term {
+        Term::Real(value)
+    }
+
+    pub fn var(name: impl Into<String>, sort: Sort) -> Term {
+        Term::Var(name.into(), sort)
+    }
+
+    pub fn int_var(name: impl Into<String>) -> Term {
+        Term::var(name, Sort::Int)
+    }
+
+    pub fn real_var(name: impl Into<String>) -> Term {
+        Term::var(name, Sort::Real)
+    }
+
+    pub fn bool_var(name: impl Into<String>) -> Term {
+        Term::var(name, Sort::Bool)
+    }
+
+    /// `value` wrapped around to `sort`, a bit-vector sort.
+    pub fn bits(value: i128, sort: Sort) -> Term {
+        let Sort::Bits { width, signed } = sort else {
+            panic!("`{sort}` is not a bit-vector sort")
+        };
+        Term::Bits(Bits::new(width, signed, value))
+    }
+
+    pub fn bitwise(op: BitOp, lhs: Term, rhs: Term) -> Term {
+        match (lhs, rhs) {
+            (Term::Bits(a), Term::Bits(b)) => Term::Bits(op.apply(a, b)),
+            (term, Term::Bits(zero)) if zero.is_zero() && op != BitOp::And => term,
+            (lhs, rhs) => Term::Bitwise(op, Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    pub fn complement(term: Term) -> Term {
+        match term {
+            Term::Bits(value) => Term::Bits(!value),
+            Term::Complement(inner) => *inner,
+            term => Term::Complement(Box::new(term)),
+        }
+    }
+
+    /// `term`, a bit-vector, as one of `sort`.
+    pub fn cast(term: Term, sort: Sort) -> Term {
+        let Sort::Bits { width, signed } = sort else {
+            panic!("`{sort}` is not a bit-vector sort")
+        };
+        match term {
+            Term::Bits(value) => Term::Bits(value.cast(width, signed)),
+            term if term.sort() == sort => term,
+            term => Term::Cast(Box::new(term), sort),
+        }
+    }
+
+    pub fn cmp(op: Cmp, lhs: Term, rhs: Term) -> Term {
+        match (&lhs, &rhs) {
+            (Term::Int(a), Term::Int(b)) => Term::Bool(op.holds(a, b)),
+            (Term::Real(a), Term::Real(b)) => Term::Bool(op.holds(a, b)),
+            (Term::Bits(a), Term::Bits(b)) => Term::Bool(op.holds(a, b)),
+            (Term::Bool(a), Term::Bool(b)) if matches!(op, Cmp::Eq | Cmp::Ne) => Term::Bool(op.holds(a, b)),
+            _ if lhs == rhs && matches!(op, Cmp::Eq | Cmp::Le | Cmp::Ge) => Term::Bool(true),
+            _ => Term::Cmp(op, Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    pub fn eq(lhs: Term, rhs: Term) -> Term {
+        Term::cmp(Cmp::Eq, lhs, rhs)
+    }
+
+    pub fn and(terms: impl IntoIterator<Item = Term>) -> Term {
+        let mut conjuncts = Vec::new();
+        for term in terms {
+            match term {
+                Term::Bool(true) => {}
+                Term::Bool(false) => return Term::Bool(false),
+                Term::And(inner) => conjuncts.extend(inner),
+                term => conjuncts.push(term),
+            }
+        }
+        match conjuncts.len() {
+            0 => Term::Bool(true),
+            1 => conjuncts.remove(0),
+            _ => Term::And(conjuncts),
+        }
+    }
+
+    pub fn or(terms: impl IntoIterator<Item = Term>) -> Term {
+        let mut disjuncts = Vec::new();
+        for term in terms {
+            match term {
+                Term::Bool(false) => {}
+                Term::Bool(true) => return Term::Bool(true),
+                Term::Or(inner) => disjuncts.extend(inner),
+                term => disjuncts.push(term),
+            }
+        }
+        match disjuncts.len() {
+            0 => Term::Bool(false),
+            1 => disjuncts.remove(0),
+            _ => Term::Or(disjuncts),
+        }
+    }
+
+    pub fn implies(lhs: Term, rhs: Term) -> Term {
+        match (lhs, rhs) {
+            (Term::Bool(true), term) => term,
+            (Term::Bool(false), _) | (_, Term::Bool(true)) => Term::Bool(true),
+            (term, Term::Bool(false)) => !term,
+            (lhs, rhs) => Term::Implies(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    pub fn ite(cond: Term, then: Term, otherwise: Term) -> Term {
+        match cond {
+            Term::Bool(true) => then,
+            Term::Bool(false) => otherwise,
+            _ if then == otherwise => then,
+            cond => Term::Ite(Box::new(cond), Box::new(then), Box::new(otherwise)),
+        }
+    }
+
+    pub fn sort(&self) -> Sort {
+        match self {
+            Term::Int(_) => Sort::Int,
+            Term::Real(_) => Sort::Real,
+            Term::Bits(value) => value.sort(),
+            // Both operands have the same sort.
+            Term::Neg(term)
+            | Term::Add(term, _)
+            | Term::Sub(term, _)
+            | Term::Mul(term, _)
+            | Term::Div(term, _)
+            | Term::Bitwise(_, term, _)
+            | Term::Complement(term) => term.sort(),
+            Term::Var(_, sort) | Term::App(_, _, sort) | Term::Cast(_, sort) => *sort,
+            Term::Ite(_, then, _) => then.sort(),
+            Term::Bool(_) | Term::Cmp(..) | Term::Not(_) | Term::And(_) | Term::Or(_) | Term::Implies(..) => Sort::Bool,
+        }
+    }
+
+    /// The terms directly inside this one.
+    pub fn children(&self) -> Vec<&Term> {
+        match self {
+            Term::Int(_) | Term::Real(_) | Term::Bits(_) | Term::Bool(_) | Term::Var(..) => Vec::new(),
+            Term::App(_, args, _) | Term::And(args) | Term::Or(args) => args.iter().collect(),
+            Term::Neg(term) | Term::Not(term) | Term::Complement(term) | Term::Cast(term, _) => vec![term],
+            Term::Add(lhs, rhs)
+            | Term::Sub(lhs, rhs)
+            | Term::Mul(lhs, rhs)
+            | Term::Div(lhs, rhs)
+            | Term::Bitwise(_, lhs, rhs)
+            | Term::Cmp(_, lhs, rhs)
+            | Term::Implies(lhs, rhs) => vec![lhs, rhs],
+            Term::Ite(cond, then, otherwise) => vec![cond, then, otherwise],
+        }
+    }
+
+    /// The term with each child replaced by `f` of it, rebuilt with the
+    /// simplifying constructors.
+    pub fn map(&self, f: &mut dyn FnMut(&Term) -> Term) -> Term {
+        match self {
+            Term::Int(_) | Term::Real(_) | Term::Bits(_) | Term::Bool(_) | Term::Var(..) => self.clone(),
+            Term::App(name, args, sort) => Term::App(name.clone(), args.iter().map(&mut *f).collect(), *sort),
+            Term::Neg(term) => -f(term),
+            Term::Add(lhs, rhs) => f(lhs) + f(rhs),
+            Term::Sub(lhs, rhs) => f(lhs) - f(rhs),
+            Term::Mul(lhs, rhs) => f(lhs) * f(rhs),
+            Term::Div(lhs, rhs) => f(lhs) / f(rhs),
+            Term::Bitwise(op, lhs, rhs) => Term::bitwise(*op, f(lhs), f(rhs)),
+            Term::Complement(term) => Term::complement(f(term)),
+            Term::Cast(term, sort) => Term::cast(f(term), *sort),
+            Term::Cmp(op, lhs, rhs) => Term::cmp(*op, f(lhs), f(rhs)),
+            Term::Not(term) => !f(term),
+            Term::And(terms) => Term::and(terms.iter().map(&mut *f).collect::<Vec<_>>()),
+            Term::Or(terms) => Term::or(terms.iter().map(&mut *f).collect::<Vec<_>>()),
+            Term::Implies(lhs, rhs) => Term::implies(f(lhs), f(rhs)),
+            Term::Ite(cond, then, otherwise) => Term::ite(f(cond), f(then), f(otherwise)),
+        }
+    }
+
+    /// Replaces the variables named in `substitution`.
+    pub fn substitute(&self, substitution: &HashMap<String, Term>) -> Term {
+        match self {
+            Term::Var(name, _) => substitution.get(name).cloned().unwrap_or_else(|| self.clone()),
+            term => term.map(&mut |child| child.substitute(substitution)),
+        }
+    }
+
+    /// The free variables and their sorts.
+    pub fn vars(&self) -> BTreeMap<String, Sort> {
+        let mut vars = BTreeMap::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    fn collect_vars(&self, vars: &mut BTreeMap<String, Sort>) {
+        match self {
+            Term::Var(name, sort) => {
+                vars.insert(name.clone(), *sort);
+            }
+            term => term.children().into_iter().for_each(|child| child.collect_vars(vars)),
+        }
+    }
+
+    /// The value of the term under `model`, if every variable in it has one
+    /// and no arithmetic overflows or divides by zero. Applications of
+    /// uninterpreted functions have no value.
+    pub fn eval(&self, model: &Model) -> Option<Value> {
+        let bool = |term: &Term| match term.eval(model)? {
+            Value::Bool(value) => Some(value),
+            Value::Int(_) | Value::Real(_) | Value::Bits(_) => None,
+        };
+        let bits = |term: &Term| match term.eval(model)? {
+            Value::Bits(value) => Some(value),
+            Value::Int(_) | Value::Real(_) | Value::Bool(_) => None,
+        };
+        let arith = |lhs: &Term,
+                     rhs: &Term,
+                     int: fn(i64, i64) -> Option<i64>,
+                     real: fn(Rational, Rational) -> Rational,
+                     bits: fn(Bits, Bits) -> Bits| {
+            match (lhs.eval(model)?, rhs.eval(model)?) {
+                (Value::Int(a), Value::Int(b)) => int(a, b).map(Value::Int),
+                (Value::Real(a), Value::Real(b)) => Some(real(a, b)).filter(|r| !r.is_overflow()).map(Value::Real),
+                (Value::Bits(a), Value::Bits(b)) => Some(Value::Bits(bits(a, b))),
+                _ => None,
+            }
+        };
+        Some(match self {
+            Term::Int(value) => Value::Int(*value),
+            Term::Real(value) => Value::Real(*value),
+            Term::Bits(value) => Value::Bits(*value),
+            Term::Bool(value) => Value::Bool(*value),
+            Term::Var(name, _) => model.get(name)?,
+            Term::App(..) => return None,
+            Term::Neg(term) => match term.eval(model)? {
+                Value::Int(value) => Value::Int(value.checked_neg()?),
+                Value::Real(value) => Value::Real(Some(-value).filter(|r| !r.is_overflow())?),
+                Value::Bits(value) => Value::Bits(-value),
+                Value::Bool(_) => return None,
+            },
+            Term::Add(lhs, rhs) => arith(lhs, rhs, i64::checked_add, |a, b| a + b, |a, b| a + b)?,
+            Term::Sub(lhs, rhs) => arith(lhs, rhs, i64::checked_sub, |a, b| a - b, |a, b| a - b)?,
+            Term::Mul(lhs, rhs) => arith(lhs, rhs, i64::checked_mul, |a, b| a * b, |a, b| a * b)?,
+            Term::Div(lhs, rhs) => arith(lhs, rhs, i64::checked_div, |a, b| a / b, |a, b| a / b)?,
+            Term::Bitwise(op, lhs, rhs) => Value::Bits(op.apply(bits(lhs)?, bits(rhs)?)),
+            Term::Complement(term) => Value::Bits(!bits(term)?),
+            Term::Cast(term, sort) => {
+                let Sort::Bits { width, signed } = sort else {
+                    return None;
+                };
+                Value::Bits(bits(term)?.cast(*width, *signed))
+            }
+            Term::Cmp(op, lhs, rhs) => match (lhs.eval(model)?, rhs.eval(model)?) {
+                (Value::Int(a), Value::Int(b)) => Value::Bool(op.holds(a, b)),
+                (Value::Real(a), Value::Real(b)) => Value::Bool(op.holds(a, b)),
+                (Value::Bits(a), Value::Bits(b)) => Value::Bool(op.holds(a, b)),
+                (Value::Bool(a), Value::Bool(b)) => Value::Bool(op.holds(a, b)),
+                _ => return None,
+            },
+            Term::Not(term) => Value::Bool(!bool(term)?),
+            Term::And(terms) => {
+                let mut all = true;
+                for term in terms {
+                    all &= bool(term)?;
+                }
+                Value::Bool(all)
+            }
+            Term::Or(terms) => {
+                let mut any = false;
+                for term in terms {
+                    any |= bool(term)?;
+                }
+                Value::Bool(any)
+            }
+            Term::Implies(lhs, rhs) => Value::Bool(!bool(lhs)? || bool(rhs)?),
+            Term::Ite(cond, then, otherwise) => {
+                if bool(cond)? {
+                    then.eval(model)?
+                } else {
+                    otherwise.eval(model)?
+                }
+            }
+        })
+    }
+
+    /// How tightly the term binds when printed, on the scale of Flerry's
+    /// operator precedence. The bitwise operators, which Flerry does not
+    /// have, bind as in Rust: between comparisons and arithmetic.
+    fn precedence(&self) -> u8 {
+        match self {
+            Term::Implies(..) | Term::Ite(..) => 0,
+            Term::Or(_) => 1,
+            Term::And(_) => 2,
+            Term::Cmp(..) => 3,
+            Term::Bitwise(BitOp::Or, ..) => 4,
+            Term::Bitwise(BitOp::Xor, ..) => 5,
+            Term::Bitwise(BitOp::And, ..) => 6,
+            Term::Bitwise(BitOp::Shl | BitOp::Shr, ..) => 7,
+            Term::Add(..) | Term::Sub(..) => 8,
+            Term::Mul(..) | Term::Div(..) => 9,
+            Term::Cast(..) => 10,
+            Term::Neg(_) | Term::Not(_) | Term::Complement(_) => 11,
+            Term::Int(value) if *value < 0 => 11,
+            Term::Bits(value) if value.is_negative() => 11,
+            // A fraction prints as a division.
+            Term::Real(value) if !value.is_integer() => 9,
+            Term::Real(value) if value.is_negative() => 11,
+            Term::Int(_) | Term::Real(_) | Term::Bits(_) | Term::Bool(_) | Term::Var(..) | Term::App(..) => 12,
+        }
+    }
+
+    /// Writes the term, parenthesized if it binds looser than `min`.
+    fn write(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        let parens = self.precedence() < min;
+        if parens {
+            f.write_str("(")?;
+        }
+        let prec = self.precedence();
+        match self {
+            Term::Int(value) => write!(f, "{value}")?,
+            Term::Real(value) => write!(f, "{value}")?,
+            Term::Bits(value) => write!(f, "{value}")?,
+            Term::Bool(value) => write!(f, "{value}")?,
+            Term::Var(name, _) => f.write_str(name)?,
+            Term::App(name, args, _) => {
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    arg.write(f, 0)?;
+                }
+                f.write_str(")")?;
+            }
+            Term::Neg(term) => {
+                f.write_str("-")?;
+                term.write(f, prec)?;
+            }
+            Term::Not(term) => {
+                f.write_str("!")?;
+                term.write(f, prec)?;
+            }
+            Term::Complement(term) => {
+                f.write_str("~")?;
+                term.write(f, prec)?;
+            }
+            Term::Cast(term, sort) => {
+                term.write(f, prec)?;
+                write!(f, " as {sort}")?;
+            }
+            Term::Add(lhs, rhs) => binary(f, lhs, "+", rhs, prec)?,
+            Term::Sub(lhs, rhs) => binary(f, lhs, "-", rhs, prec)?,
+            Term::Mul(lhs, rhs) => binary(f, lhs, "*", rhs, prec)?,
+            Term::Div(lhs, rhs) => binary(f, lhs, "/", rhs, prec)?,
+            Term::Bitwise(op, lhs, rhs) => binary(f, lhs, op.symbol(), rhs, prec)?,
+            // Comparisons do not chain, so both sides bind tighter.
+            Term::Cmp(op, lhs, rhs) => {
+                lhs.write(f, prec + 1)?;
+                write!(f, " {} ", op.symbol())?;
+                rhs.write(f, prec + 1)?;
+            }
+            Term::And(terms) | Term::Or(terms) => {
+                let symbol = if matches!(self, Term::And(_)) { " && " } else { " || " };
+                for (i, term) in terms.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(symbol)?;
+                    }
+                    term.write(f, prec + 1)?;
+                }
+            }
+            // `=>` groups to the right.
+            Term::Implies(lhs, rhs) => {
+                lhs.write(f, prec + 1)?;
+                f.write_str(" => ")?;
+                rhs.write(f, prec)?;
+            }
+            Term::Ite(cond, then, otherwise) => {
+                f.write_str("if ")?;
+                cond.write(f, 1)?;
+                f.write_str(" then ")?;
+                then.write(f, 1)?;
+                f.write_str(" else ")?;
+                otherwise.write(f, 1)?;
+            }
+        }
+        if parens {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+impl ops::Neg for Term {
+    type Output = Term;
+
+    fn neg(self) -> Term {
+        match self {
+            Term::Int(value) if value != i64::MIN => Term::Int(-value),
+            Term::Real(value) if !(-value).is_overflow() => Term::Real(-value),
+            Term::Bits(value) => Term::Bits(-value),
+            Term::Neg(inner) => *inner,
+            term => Term::Neg(Box::new(term)),
+        }
+    }
+}
+
+impl ops::Add for Term {
+    type Output = Term;
+
+    fn add(self, rhs: Term) -> Term {
+        match (self, rhs) {
+            (Term::Int(a), Term::Int(b)) if a.checked_add(b).is_some() => Term::Int(a + b),
+            (Term::Real(a), Term::Real(b)) if !(a + b).is_overflow() => Term::Real(a + b),
+            (Term::Bits(a), Term::Bits(b)) => Term::Bits(a + b),
+            (Term::Int(0), term) | (term, Term::Int(0)) => term,
+            (Term::Bits(zero), term) | (term, Term::Bits(zero)) if zero.is_zero() => term,
+            (lhs, rhs) => Term::Add(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+}
+
+impl ops::Sub for Term {
+    type Output = Term;
+
+    fn sub(self, rhs: Term) -> Term {
+        match (self, rhs) {
+            (Term::Int(a), Term::Int(b)) if a.checked_sub(b).is_some() => Term::Int(a - b),
+            (Term::Real(a), Term::Real(b)) if !(a - b).is_overflow() => Term::Real(a - b),
+            (Term::Bits(a), Term::Bits(b)) => Term::Bits(a - b),
+            (term, Term::Int(0)) => term,
+            (term, Term::Bits(zero)) if zero.is_zero() => term,
+            (lhs, rhs) => Term::Sub(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+}
+
+impl ops::Mul for Term {
+    type Output = Term;
+
+    fn mul(self, rhs: Term) -> Term {
+        match (self, rhs) {
+            (Term::Int(a), Term::Int(b)) if a.checked_mul(b).is_some() => Term::Int(a * b),
+            (Term::Real(a), Term::Real(b)) if !(a * b).is_overflow() => Term::Real(a * b),
+            (Term::Bits(a), Term::Bits(b)) => Term::Bits(a * b),
+            (Term::Int(1), term) | (term, Term::Int(1)) => term,
+            (lhs, rhs) => Term::Mul(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+}
+
+impl ops::Div for Term {
+    type Output = Term;
+
+    fn div(self, rhs: Term) -> Term {
+        match (self, rhs) {
+            (Term::Int(a), Term::Int(b)) if a.checked_div(b).is_some() => Term::Int(a / b),
+            (Term::Real(a), Term::Real(b)) if !(a / b).is_overflow() => Term::Real(a / b),
+            (Term::Bits(a), Term::Bits(b)) => Term::Bits(a / b),
+            (term, Term::Int(1)) => term,
+            (lhs, rhs) => Term::Div(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+}
+
+impl ops::Not for Term {
+    type Output = Term;
+
+    fn not(self) -> Term {
+        match self {
+            Term::Bool(value) => Term::Bool(!value),
+            Term::Not(inner) => *inner,
+            term => Term::Not(Box::new(term)),
+        }
+    }
+}
+
+/// A left-associative operator: the right operand binds tighter.
+fn binary(f: &mut fmt::Formatter<'_>, lhs: &Term, symbol: &str, rhs: &Term, prec: u8) -> fmt::Result {
+    lhs.write(f, prec)?;
+    write!(f, " {symbol} ")?;
+    rhs.write(f, prec + 1)
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
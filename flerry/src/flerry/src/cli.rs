@@ -1,54 +1,302 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
 
-use crate::utils::cli_utils::{Cli, read_file, run};
-use clap::Parser;
+use crate::utils::cli_utils::{Command, lint_options, parse_args, read_file, report, run, run_with_goals};
+use crate::utils::diff::unified_diff;
+use flerry_compiler::compiler::Options;
+use flerry_compiler::contracts::ContractMode;
+use flerry_compiler::dump::{Format, dump};
+use flerry_compiler::verify::Status;
+use flerry_core::span::line_col;
+use flerry_prover::Limits;
+use flerry_prover::dimacs;
+use flerry_prover::sat::{self, Lit};
 use colored::*;
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 pub fn cli() {
-    match Cli::try_parse() {
-        Ok(cli) => {
-            let path = Path::new(&cli.path);
-
-            match read_file(path) {
-                Ok(content) => {
-                    println!(
-                        "{}{}{}{}",
-                        "[SUCCESS]".green(),
-                        " Debug:".magenta(),
-                        " File read correctly, will attempt to compile now\n",
-                        content
-                    );
-
-                    // Compile
-                    run();
-                }
-                Err(e) => {
-                    eprintln!(
-                        "{}{}{}{}{}",
-                        "[FAILURE]".red(),
-                        " Debug:".magenta(),
-                        " File was not processed correctly or does not exist.\n",
-                        "\nError: ".red(),
-                        e,
-                    );
-                    std::process::exit(64);
+    match parse_args(std::env::args_os()) {
+        Ok((cli, matches)) => {
+            let options = Options {
+                lints: lint_options(&matches),
+                contracts: ContractMode::from_name(&cli.contracts).unwrap_or_default(),
+                release: cli.release,
+                ..Options::default()
+            };
+            match cli.command {
+                Some(Command::Fix { path, dry_run }) => fix(&path, dry_run, &options),
+                Some(Command::Parse { path, dump_ast, lower, format }) => {
+                    parse(&path, dump_ast, lower, &format, &options)
                 }
+                Some(Command::Verify {
+                    path,
+                    timeout,
+                    solver_cmd,
+                    emit,
+                }) => verify(&path, timeout, solver_cmd, emit.is_some(), &options),
+                Some(Command::Sat { path, assume, timeout }) => sat(&path, &assume, timeout),
+                None => match cli.path {
+                    Some(path) if cli.holes == "json" => goals(&path, &options),
+                    Some(path) => compile(&path, &options),
+                    None => missing_input(),
+                },
             }
         }
 
         Err(e) => {
             if e.kind() == clap::error::ErrorKind::MissingRequiredArgument {
-                eprintln!(
-                    "{}{}",
-                    "Error: ".red(),
-                    "No input files.\n\nPlease specify the path to the file you want to compile."
-                );
-                std::process::exit(1);
+                missing_input();
             } else {
                 e.exit();
             }
         }
     }
 }
+
+fn missing_input() -> ! {
+    eprintln!(
+        "{}No input files.\n\nPlease specify the path to the file you want to compile.",
+        "Error: ".red()
+    );
+    std::process::exit(1);
+}
+
+fn read_failure(e: std::io::Error) -> ! {
+    eprintln!(
+        "{}{} File was not processed correctly or does not exist.\n{}{}",
+        "[FAILURE]".red(),
+        " Debug:".magenta(),
+        "\nError: ".red(),
+        e,
+    );
+    std::process::exit(64);
+}
+
+fn compile(path: &str, options: &Options) {
+    match read_file(Path::new(path)) {
+        Ok(content) => {
+            println!(
+                "{}{} File read correctly, will attempt to compile now\n{}",
+                "[SUCCESS]".green(),
+                " Debug:".magenta(),
+                content
+            );
+
+            // Compile
+            if !run(&content, path, options) {
+                std::process::exit(65);
+            }
+        }
+        Err(e) => read_failure(e),
+    }
+}
+
+/// Checks the file and prints the goals of its holes as JSON, with nothing
+/// else on stdout for an editor to read past.
+fn goals(path: &str, options: &Options) {
+    match read_file(Path::new(path)) {
+        Ok(content) => {
+            if !run_with_goals(&content, path, options) {
+                std::process::exit(65);
+            }
+        }
+        Err(e) => read_failure(e),
+    }
+}
+
+fn fix(path: &str, dry_run: bool, options: &Options) {
+    // Read the file untouched: fixes are written back over it byte for byte.
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => read_failure(e),
+    };
+
+    let diagnostics = flerry_compiler::compiler::compile_with(&content, options);
+    let (fixed, applied) = flerry_compiler::fix::apply_fixes(&content, &diagnostics);
+
+    if dry_run {
+        print!("{}", unified_diff(&content, &fixed, path));
+        return;
+    }
+
+    if applied > 0
+        && let Err(e) = std::fs::write(path, &fixed)
+    {
+        read_failure(e);
+    }
+    println!("{} Applied {applied} fix(es) to {path}", "[SUCCESS]".green());
+
+    // Whatever could not be fixed automatically is still worth reporting.
+    let remaining = flerry_compiler::compiler::compile_with(&fixed, options);
+    if report(&remaining, &fixed, path) {
+        std::process::exit(65);
+    }
+}
+
+fn parse(path: &str, dump_ast: bool, lower: bool, format: &str, options: &Options) {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => read_failure(e),
+    };
+
+    // Lowering compiles the file: the checks it leaves depend on what was proved.
+    let (module, diagnostics) = if lower {
+        flerry_compiler::compiler::lower(&content, options)
+    } else {
+        flerry_compiler::compiler::parse_source(&content)
+    };
+    if dump_ast {
+        // clap only lets the known formats through.
+        let format = Format::from_name(format).unwrap_or(Format::Tree);
+        print!("{}", dump(&module, format));
+    } else {
+        print!("{}", flerry_compiler::pretty::module(&module));
+    }
+
+    if report(&diagnostics, &content, path) {
+        std::process::exit(65);
+    }
+}
+
+/// Writes the verification conditions of the file as SMT-LIB scripts next
+/// to it, `f.fl` to `f.<function>.<n>.smt2` with each function's checks
+/// counted from one. Errors in the file are left to compiling it.
+fn emit_smt2(path: &str, options: &Options) {
+    let content = match read_file(Path::new(path)) {
+        Ok(content) => content,
+        Err(e) => read_failure(e),
+    };
+    let (_, scripts) = flerry_compiler::compiler::smt2(&content, options);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (function, script) in scripts {
+        let count = counts.entry(function.clone()).or_default();
+        *count += 1;
+        let file = Path::new(path).with_extension(format!("{function}.{count}.smt2"));
+        if let Err(e) = std::fs::write(&file, script.text()) {
+            read_failure(e);
+        }
+        println!("{} Wrote {}", "[SUCCESS]".green(), file.display());
+    }
+}
+
+/// Proves what the file's refinement types and contracts claim, printing
+/// what became of each obligation and reporting those not proved. With
+/// `emit` the obligations are first written out as SMT-LIB scripts.
+fn verify(path: &str, timeout: u64, solver: Option<String>, emit: bool, options: &Options) {
+    let content = match read_file(Path::new(path)) {
+        Ok(content) => content,
+        Err(e) => read_failure(e),
+    };
+    let options = Options {
+        limits: Limits {
+            timeout: Duration::from_millis(timeout),
+        },
+        // Verifying proves the obligations whatever happens to them after.
+        contracts: if options.contracts.proves() {
+            options.contracts
+        } else {
+            ContractMode::Static
+        },
+        solver,
+        ..options.clone()
+    };
+    if emit {
+        emit_smt2(path, &options);
+    }
+
+    let (mut diagnostics, reports) = flerry_compiler::compiler::verify(&content, &options);
+    let (mut proved, mut failed, mut unknown) = (0, 0, 0);
+    for obligation in &reports {
+        let (line, column) = line_col(&content, obligation.span.start);
+        let (outcome, details) = match &obligation.status {
+            Status::Proved => {
+                proved += 1;
+                ("proved".green(), String::new())
+            }
+            Status::Failed(counterexample) => {
+                failed += 1;
+                let details = counterexample
+                    .as_ref()
+                    .map_or(String::new(), |values| format!(" (counterexample: {values})"));
+                ("failed".red(), details)
+            }
+            Status::Unknown(reason) => {
+                unknown += 1;
+                ("unknown".yellow(), format!(" ({reason})"))
+            }
+        };
+        println!("{path}:{line}:{column}: {outcome}: {}{details}", obligation.description);
+    }
+    println!("{} obligation(s): {proved} proved, {failed} failed, {unknown} unknown", reports.len());
+
+    diagnostics.extend(reports.into_iter().filter_map(|obligation| obligation.diagnostic));
+    if report(&diagnostics, &content, path) {
+        std::process::exit(65);
+    }
+}
+
+/// Solves a CNF file, printing the answer in the format of the SAT
+/// competitions and exiting with their codes: 10 for satisfiable, 20 for
+/// unsatisfiable.
+fn sat(path: &str, assume: &[i64], timeout: Option<u64>) {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => read_failure(e),
+    };
+    let cnf = match dimacs::parse(&content) {
+        Ok(cnf) => cnf,
+        Err(e) => {
+            eprintln!("{}: {path}: {e}", "error".red().bold());
+            std::process::exit(65);
+        }
+    };
+    let mut assumptions = Vec::new();
+    for &n in assume {
+        match Lit::from_dimacs(n) {
+            Some(lit) if lit.var() < cnf.vars => assumptions.push(lit),
+            _ => {
+                eprintln!("{}: `{n}` is not a literal of the {} variables", "error".red().bold(), cnf.vars);
+                std::process::exit(64);
+            }
+        }
+    }
+
+    let start = Instant::now();
+    let mut solver = sat::Solver::new();
+    for clause in &cnf.clauses {
+        solver.add_clause(clause);
+    }
+    let outcome = solver.solve(&assumptions, timeout.map(|ms| start + Duration::from_millis(ms)));
+    let stats = solver.stats;
+    println!("c {} variables, {} clauses", cnf.vars, cnf.clauses.len());
+    println!(
+        "c {} decisions, {} propagations, {} conflicts, {} restarts in {:?}",
+        stats.decisions,
+        stats.propagations,
+        stats.conflicts,
+        stats.restarts,
+        start.elapsed()
+    );
+    match outcome {
+        sat::Outcome::Sat(mut model) => {
+            println!("s SATISFIABLE");
+            model.resize(cnf.vars, false);
+            print!("{}", dimacs::model(&model));
+            std::process::exit(10);
+        }
+        sat::Outcome::Unsat(core) => {
+            if !core.is_empty() {
+                let lits: Vec<String> = core.iter().map(Lit::to_string).collect();
+                println!("c failed assumptions: {}", lits.join(" "));
+            }
+            println!("s UNSATISFIABLE");
+            std::process::exit(20);
+        }
+        sat::Outcome::Unknown(reason) => {
+            println!("c {reason}");
+            println!("s UNKNOWN");
+        }
+    }
+}
@@ -7,3 +7,122 @@ mod utils;
 fn main() {
     cli::cli();
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::cli_utils::{Cli, Command, parse_args};
+    use crate::utils::diagnostics::render;
+    use crate::utils::diff::unified_diff;
+    use clap::Parser;
+    use flerry_core::{Diagnostic, Span};
+
+    #[test]
+    fn test_diff_identical() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "x.fl"), "");
+    }
+
+    #[test]
+    fn test_diff_insert() {
+        let diff = unified_diff("a\nb\nc\n", "a\nb\nnew\nc\n", "/x.fl");
+        assert_eq!(diff, "--- a/x.fl\n+++ b/x.fl\n@@ -1,3 +1,4 @@\n a\n b\n+new\n c\n");
+    }
+
+    #[test]
+    fn test_diff_delete() {
+        let diff = unified_diff("a\nb\nc\n", "a\nc\n", "x.fl");
+        assert_eq!(diff, "--- a/x.fl\n+++ b/x.fl\n@@ -1,3 +1,2 @@\n a\n-b\n c\n");
+    }
+
+    #[test]
+    fn test_diff_replace() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        let diff = unified_diff(old, new, "x.fl");
+        assert_eq!(
+            diff,
+            "--- a/x.fl\n+++ b/x.fl\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_separate_hunks() {
+        let old: String = (1..=20).map(|n| format!("{n}\n")).collect();
+        let new = old.replacen("2\n", "two\n", 1).replacen("19\n", "nineteen\n", 1);
+        let diff = unified_diff(&old, &new, "x.fl");
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n"));
+        assert!(diff.contains("-19\n+nineteen\n 20\n"));
+    }
+
+    #[test]
+    fn test_render_groups_labels_by_line() {
+        colored::control::set_override(false);
+        let source = "x = 1\ny = x + true\n";
+        let diagnostic = Diagnostic::error("mismatched types", Span::new(14, 18))
+            .with_label(Span::new(14, 18), "expected `Int`, found `Bool`")
+            .with_label(Span::new(10, 11), "this is an `Int`");
+        let rendered = render(&diagnostic, source, "x.fl");
+        assert_eq!(rendered.matches("y = x + true").count(), 1);
+        assert!(
+            rendered.contains(
+                "2 | y = x + true\n  |     - this is an `Int`\n  |         ^^^^ expected `Int`, found `Bool`\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_emit_is_verify_only() {
+        let cli = Cli::try_parse_from(["flerry", "verify", "--emit", "smt2", "x.fl"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Verify { emit: Some(_), .. })));
+        assert!(Cli::try_parse_from(["flerry", "parse", "--emit", "smt2", "x.fl"]).is_err());
+        assert!(Cli::try_parse_from(["flerry", "--emit", "smt2", "x.fl"]).is_err());
+    }
+
+    #[test]
+    fn test_global_flags_before_subcommands() {
+        let flags: [&[&str]; 7] = [
+            &["-W", "shadowing"],
+            &["-A", "unused_variables"],
+            &["-D", "warnings"],
+            &["-F", "unused_imports"],
+            &["--cap-lints", "warn"],
+            &["--contracts", "runtime"],
+            &["--release"],
+        ];
+        let subcommands: [&[&str]; 4] = [
+            &["fix", "--dry-run", "a.fl"],
+            &["parse", "--lower", "a.fl"],
+            &["verify", "a.fl"],
+            &["sat", "a.cnf"],
+        ];
+        for flag in flags {
+            for subcommand in subcommands {
+                let args = [&["flerry"], flag, subcommand].concat();
+                let parsed = parse_args(&args);
+                assert!(parsed.is_ok_and(|(cli, _)| cli.command.is_some()), "{args:?}");
+            }
+        }
+        let (cli, matches) = parse_args(["flerry", "-D", "warnings", "verify", "b.fl"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Verify { path, .. }) if path == "b.fl"));
+        assert_eq!(matches.get_many::<String>("deny").unwrap().collect::<Vec<_>>(), ["warnings"]);
+    }
+
+    #[test]
+    fn test_compile_arguments_reject_subcommands() {
+        assert!(parse_args(["flerry", "a.fl"]).is_ok_and(|(cli, _)| cli.path.as_deref() == Some("a.fl")));
+        assert!(parse_args(["flerry", "a.fl", "verify", "b.fl"]).is_err());
+        assert!(parse_args(["flerry", "--holes", "json", "verify", "b.fl"]).is_err());
+    }
+
+    #[test]
+    fn test_contract_flags_before_subcommands() {
+        let (cli, _) = parse_args(["flerry", "--contracts=runtime", "--release", "parse", "--lower", "k.fl"]).unwrap();
+        assert_eq!(cli.contracts, "runtime");
+        assert!(cli.release);
+        assert!(matches!(cli.command, Some(Command::Parse { lower: true, path, .. }) if path == "k.fl"));
+
+        let (cli, _) = parse_args(["flerry", "parse", "--lower", "--contracts=both", "k.fl"]).unwrap();
+        assert_eq!(cli.contracts, "both");
+        assert!(parse_args(["flerry", "--contracts=sometimes", "parse", "k.fl"]).is_err());
+    }
+}
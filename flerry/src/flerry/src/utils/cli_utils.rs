@@ -1,8 +1,16 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
 
+use crate::utils::diagnostics::render;
 use clap::builder::styling::{AnsiColor, Style};
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use colored::*;
+use flerry_compiler::compiler::Options;
+use flerry_compiler::lint::{LintLevel, LintOptions, WARNINGS, find_lint, similar_lint};
+use flerry_core::{Diagnostic, Level};
+use std::ffi::OsString;
 use std::path::Path;
 
 // Configures the style for the CLI.
@@ -15,9 +23,169 @@ const STYLES: clap::builder::styling::Styles = clap::builder::styling::Styles::s
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, styles = STYLES)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// The path to the file to run
     #[arg(value_name = "path")]
-    pub path: String,
+    pub path: Option<String>,
+
+    /// Report a lint as a warning
+    #[arg(short = 'W', value_name = "lint", global = true)]
+    pub warn: Vec<String>,
+
+    /// Silence a lint
+    #[arg(short = 'A', value_name = "lint", global = true)]
+    pub allow: Vec<String>,
+
+    /// Report a lint as an error; `-D warnings` does so for every warning
+    #[arg(short = 'D', value_name = "lint", global = true)]
+    pub deny: Vec<String>,
+
+    /// Report a lint as an error and forbid attributes from lowering it
+    #[arg(short = 'F', value_name = "lint", global = true)]
+    pub forbid: Vec<String>,
+
+    /// The highest level any lint is reported at
+    #[arg(long, value_name = "level", global = true, value_parser = ["allow", "warn", "deny", "forbid"])]
+    pub cap_lints: Option<String>,
+
+    /// How to report the goals of holes: as errors, or as JSON on stdout for editors
+    #[arg(long, value_name = "format", default_value = "text", value_parser = ["text", "json"])]
+    pub holes: String,
+
+    /// What becomes of contracts and refinement types: not checked, checked when the program runs, proved, or
+    /// proved with what is not checked when it runs
+    #[arg(
+        long,
+        value_name = "mode",
+        default_value = "static",
+        global = true,
+        value_parser = ["off", "runtime", "static", "both"]
+    )]
+    pub contracts: String,
+
+    /// Build for release, leaving out the runtime checks of contracts that were proved
+    #[arg(long, global = true)]
+    pub release: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Apply the compiler's machine-applicable suggestions to a file
+    Fix {
+        /// The path to the file to fix
+        #[arg(value_name = "path")]
+        path: String,
+
+        /// Print the changes as a unified diff instead of writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Parse a file and print it back as canonical source, or dump its syntax tree
+    Parse {
+        /// The path to the file to parse
+        #[arg(value_name = "path")]
+        path: String,
+
+        /// Print the abstract syntax tree instead of the source
+        #[arg(long)]
+        dump_ast: bool,
+
+        /// Print the program as lowered for a backend: contracts become the checks `--contracts` asks for, and every
+        /// lambda is lifted
+        #[arg(long)]
+        lower: bool,
+
+        /// How to print the syntax tree
+        #[arg(long, value_name = "format", default_value = "tree", value_parser = ["tree", "sexp", "json"])]
+        format: String,
+    },
+    /// Prove the refinement types and contracts of a file, reporting each obligation
+    Verify {
+        /// The path to the file to verify
+        #[arg(value_name = "path")]
+        path: String,
+
+        /// How long the solver may spend on each obligation, in milliseconds
+        #[arg(long, value_name = "ms", default_value_t = 2000)]
+        timeout: u64,
+
+        /// Prove the obligations with an SMT-LIB solver instead, run by this command and given each script on stdin
+        #[arg(long, value_name = "command")]
+        solver_cmd: Option<String>,
+
+        /// Also write the verification conditions as SMT-LIB 2.6 scripts, one per check, next to the file
+        #[arg(long, value_name = "format", value_parser = ["smt2"])]
+        emit: Option<String>,
+    },
+    /// Run the prover's SAT solver on a DIMACS CNF file, answering as SAT solvers do
+    Sat {
+        /// The path to the CNF file
+        #[arg(value_name = "path")]
+        path: String,
+
+        /// Solve with a literal assumed, given as in the file; can be repeated
+        #[arg(long, value_name = "lit", allow_hyphen_values = true)]
+        assume: Vec<i64>,
+
+        /// How long the solver may search, in milliseconds
+        #[arg(long, value_name = "ms")]
+        timeout: Option<u64>,
+    },
+}
+
+/// Parses the command line. The global flags may come before or after the
+/// subcommand, but the path and `--holes` are for compiling a file, so a
+/// subcommand after them is an error rather than a reason to ignore them.
+pub fn parse_args<I, T>(args: I) -> Result<(Cli, ArgMatches), clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut command = Cli::command();
+    let matches = command.try_get_matches_from_mut(args)?;
+    if matches.subcommand().is_some() {
+        for (id, arg) in [("path", "[path]"), ("holes", "--holes <format>")] {
+            if matches.value_source(id) == Some(ValueSource::CommandLine) {
+                let message = format!("the argument '{arg}' cannot be used with a subcommand");
+                return Err(command.error(ErrorKind::ArgumentConflict, message));
+            }
+        }
+    }
+    Ok((Cli::from_arg_matches(&matches)?, matches))
+}
+
+/// Collects the lint flags in the order they were given on the command line,
+/// warning about any that do not name a lint.
+pub fn lint_options(matches: &ArgMatches) -> LintOptions {
+    let mut flags = Vec::new();
+    for (id, level) in [
+        ("allow", LintLevel::Allow),
+        ("warn", LintLevel::Warn),
+        ("deny", LintLevel::Deny),
+        ("forbid", LintLevel::Forbid),
+    ] {
+        let (Some(indices), Some(names)) = (matches.indices_of(id), matches.get_many::<String>(id)) else {
+            continue;
+        };
+        flags.extend(indices.zip(names).map(|(index, name)| (index, level, name.clone())));
+    }
+    flags.sort_by_key(|(index, _, _)| *index);
+
+    for (_, level, name) in &flags {
+        if name != WARNINGS && find_lint(name).is_none() {
+            let hint = similar_lint(name).map_or(String::new(), |lint| format!(" (did you mean `{lint}`?)"));
+            eprintln!("{}: unknown lint `{name}` given to `{}`{hint}\n", "warning".yellow().bold(), level.flag());
+        }
+    }
+
+    LintOptions {
+        flags: flags.into_iter().map(|(_, level, name)| (level, name)).collect(),
+        cap: matches
+            .get_one::<String>("cap_lints")
+            .and_then(|level| LintLevel::from_name(level)),
+    }
 }
 
 pub fn read_file(path: &Path) -> std::io::Result<String> {
@@ -25,6 +193,49 @@ pub fn read_file(path: &Path) -> std::io::Result<String> {
     Ok(content.trim_end().to_string())
 }
 
-pub fn run() {
-    flerry_compiler::compiler::compile();
+/// Prints every diagnostic to stderr and returns whether any was an error.
+pub fn report(diagnostics: &[Diagnostic], source: &str, path: &str) -> bool {
+    let mut sorted: Vec<&Diagnostic> = diagnostics.iter().collect();
+    sorted.sort_by_key(|d| d.span.start);
+    for diagnostic in sorted {
+        eprintln!("{}", render(diagnostic, source, path));
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.iter().filter(|d| d.level == Level::Warning).count();
+    let plural = |n: usize, what: &str| format!("{n} {what}{}", if n == 1 { "" } else { "s" });
+    match (errors, warnings) {
+        (0, 0) => {}
+        (0, warnings) => eprintln!(
+            "{}: {} emitted\n",
+            "warning".yellow().bold(),
+            plural(warnings, "warning")
+        ),
+        (errors, 0) => eprintln!(
+            "{}: aborting due to {}\n",
+            "error".red().bold(),
+            plural(errors, "previous error")
+        ),
+        (errors, warnings) => eprintln!(
+            "{}: aborting due to {}; {} emitted\n",
+            "error".red().bold(),
+            plural(errors, "previous error"),
+            plural(warnings, "warning")
+        ),
+    }
+
+    errors > 0
+}
+
+pub fn run(source: &str, path: &str, options: &Options) -> bool {
+    let diagnostics = flerry_compiler::compiler::compile_with(source, options);
+    !report(&diagnostics, source, path)
+}
+
+/// Like `run`, but prints the goals of the holes as JSON on stdout instead
+/// of reporting them. A file with holes has not compiled either way.
+pub fn run_with_goals(source: &str, path: &str, options: &Options) -> bool {
+    let (diagnostics, goals) = flerry_compiler::compiler::compile_goals(source, options);
+    print!("{}", flerry_compiler::holes::json(&goals, source));
+    !report(&diagnostics, source, path) && goals.is_empty()
 }
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+use colored::*;
+use flerry_core::span::{line_bounds, line_col};
+use flerry_core::{Diagnostic, Level, Span};
+
+/// Renders a diagnostic with the offending source lines underlined.
+pub fn render(diagnostic: &Diagnostic, source: &str, path: &str) -> String {
+    let header = match diagnostic.level {
+        Level::Error => "error".red().bold(),
+        Level::Warning => "warning".yellow().bold(),
+        Level::Note => "note".cyan().bold(),
+    };
+    let (line, col) = line_col(source, diagnostic.span.start);
+    let mut out = format!("{}{} {}\n", header, ":".bold(), diagnostic.message.bold());
+
+    // The primary span comes first, then any secondary labels in source order.
+    let mut marks: Vec<(Span, &str, bool)> = vec![(diagnostic.span, "", true)];
+    for label in &diagnostic.labels {
+        if label.span == diagnostic.span {
+            marks[0].1 = &label.message;
+        } else {
+            marks.push((label.span, &label.message, false));
+        }
+    }
+    marks.sort_by_key(|(span, _, _)| span.start);
+
+    let gutter = marks
+        .iter()
+        .map(|(span, _, _)| line_col(source, span.start).0.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let pad = " ".repeat(gutter);
+
+    out.push_str(&format!("{pad}{} {path}:{line}:{col}\n", "-->".blue().bold()));
+    out.push_str(&format!("{pad} {}\n", "|".blue().bold()));
+
+    // Labels on the same line share one copy of it, each with its own marker row.
+    let mut k = 0;
+    while k < marks.len() {
+        let (line, _) = line_col(source, marks[k].0.start);
+        let (start, end) = line_bounds(source, marks[k].0.start);
+        out.push_str(&format!(
+            "{:>gutter$} {} {}\n",
+            line.to_string().blue().bold(),
+            "|".blue().bold(),
+            &source[start..end]
+        ));
+        while k < marks.len() && line_bounds(source, marks[k].0.start).0 == start {
+            let (span, message, primary) = marks[k];
+            let col = line_col(source, span.start).1;
+            let width = span.end.min(end).saturating_sub(span.start).max(1);
+            let underline = if primary { "^" } else { "-" }.repeat(width);
+            let underline = if primary {
+                match diagnostic.level {
+                    Level::Error => underline.red().bold(),
+                    Level::Warning => underline.yellow().bold(),
+                    Level::Note => underline.cyan().bold(),
+                }
+            } else {
+                underline.blue().bold()
+            };
+            let row = format!(
+                "{pad} {} {}{} {}",
+                "|".blue().bold(),
+                " ".repeat(col - 1),
+                underline,
+                message
+            );
+            out.push_str(row.trim_end());
+            out.push('\n');
+            k += 1;
+        }
+    }
+
+    for note in &diagnostic.notes {
+        out.push_str(&format!("{pad} {} {}: {note}\n", "=".blue().bold(), "note".bold()));
+    }
+    for help in &diagnostic.help {
+        out.push_str(&format!("{pad} {} {}: {help}\n", "=".blue().bold(), "help".bold()));
+    }
+    for suggestion in &diagnostic.suggestions {
+        out.push_str(&format!(
+            "{pad} {} {}: {}\n",
+            "=".blue().bold(),
+            "help".bold(),
+            suggestion.message
+        ));
+    }
+
+    out
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
+
+/// Lines of context shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Produces a unified diff between `old` and `new`, or an empty string when
+/// the two are identical.
+pub fn unified_diff(old: &str, new: &str, path: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table, filled from the back.
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Walk the table into a script of (op, old line, new line).
+    let mut script = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            script.push((Op::Keep, i, j));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            script.push((Op::Delete, i, j));
+            i += 1;
+        } else {
+            script.push((Op::Insert, i, j));
+            j += 1;
+        }
+    }
+
+    if script.iter().all(|(op, _, _)| *op == Op::Keep) {
+        return String::new();
+    }
+
+    let name = path.trim_start_matches('/');
+    let mut out = format!("--- a/{name}\n+++ b/{name}\n");
+    let mut k = 0;
+    while k < script.len() {
+        if script[k].0 == Op::Keep {
+            k += 1;
+            continue;
+        }
+
+        // Grow the hunk until the next change is more than two contexts away.
+        let start = k.saturating_sub(CONTEXT);
+        let mut end = k;
+        let mut last_change = k;
+        while end < script.len() {
+            if script[end].0 != Op::Keep {
+                last_change = end;
+            } else if end - last_change > 2 * CONTEXT {
+                break;
+            }
+            end += 1;
+        }
+        let end = (last_change + CONTEXT + 1).min(script.len());
+
+        let hunk = &script[start..end];
+        let old_len = hunk.iter().filter(|(op, _, _)| *op != Op::Insert).count();
+        let new_len = hunk.iter().filter(|(op, _, _)| *op != Op::Delete).count();
+        let (_, old_start, new_start) = hunk[0];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_len > 0),
+            old_len,
+            new_start + usize::from(new_len > 0),
+            new_len
+        ));
+        for &(op, i, j) in hunk {
+            match op {
+                Op::Keep => out.push_str(&format!(" {}\n", a[i])),
+                Op::Delete => out.push_str(&format!("-{}\n", a[i])),
+                Op::Insert => out.push_str(&format!("+{}\n", b[j])),
+            }
+        }
+        k = end;
+    }
+
+    out
+}
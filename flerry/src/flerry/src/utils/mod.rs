@@ -2,3 +2,5 @@
 // Copyright (C) 2025 Affan Ahmad <st_iaffan@Outlook.com>
 
 pub mod cli_utils;
+pub mod diagnostics;
+pub mod diff;